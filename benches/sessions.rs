@@ -0,0 +1,200 @@
+// Benchmarks for the detector session tracker.
+//
+// Run with `cargo bench`. The 10M session cases need several GB of memory and
+// take a while to populate, pass a filter (e.g. `cargo bench -- 100000`) to
+// skip them when iterating.
+
+#[macro_use]
+extern crate criterion;
+extern crate rust_dark_decoy;
+
+use std::collections::HashSet;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use criterion::{black_box, BatchSize, BenchmarkId, Criterion};
+
+use rust_dark_decoy::flow_tracker::FlowNoSrcPort;
+use rust_dark_decoy::hashing::{HashAlgorithm, MapHasher};
+use rust_dark_decoy::sessions::{SessionConfig, SessionDetails, SessionEntry, SessionTracker};
+use rust_dark_decoy::timekeeping::{Clock, MonotonicNs};
+
+const S2NS: u64 = 1000*1000*1000;
+const SIZES: [u32; 3] = [100_000, 1_000_000, 10_000_000];
+
+// Deterministic v4 session for index i. Clients and phantoms are spread over
+// distinct /8s so every index maps to a unique key.
+fn v4_session(i: u32, timeout: u64) -> SessionDetails {
+    let client = Ipv4Addr::from(0x0a00_0000 + i);
+    let phantom = Ipv4Addr::from(0xc000_0000 + i);
+    SessionDetails::new(&client.to_string(), &phantom.to_string(), 443, Duration::from_nanos(timeout)).unwrap()
+}
+
+fn v4_flow(i: u32) -> FlowNoSrcPort {
+    FlowNoSrcPort {
+        src_ip: Ipv4Addr::from(0x0a00_0000 + i).into(),
+        dst_ip: Ipv4Addr::from(0xc000_0000 + i).into(),
+        dst_port: 443,
+    }
+}
+
+fn populated_tracker(n: u32, timeout: u64) -> SessionTracker {
+    let mut st = SessionTracker::new();
+    for i in 0..n {
+        st.add_session(v4_session(i, timeout));
+    }
+    st
+}
+
+fn bench_lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("is_tracked_session");
+    group.sample_size(20);
+    for n in SIZES.iter() {
+        let st = populated_tracker(*n, 3600*S2NS);
+        let hit = v4_flow(n / 2);
+        let miss = v4_flow(n + 1);
+        group.bench_with_input(BenchmarkId::new("hit", n), &hit, |b, f| {
+            b.iter(|| st.is_tracked_session(black_box(f)))
+        });
+        group.bench_with_input(BenchmarkId::new("miss", n), &miss, |b, f| {
+            b.iter(|| st.is_tracked_session(black_box(f)))
+        });
+    }
+
+    // Same lookups served from the lock-free snapshot.
+    let n = SIZES[1];
+    let mut st = populated_tracker(n, 3600*S2NS);
+    st.enable_snapshot(Duration::from_millis(10));
+    let hit = v4_flow(n / 2);
+    group.bench_with_input(BenchmarkId::new("snapshot_hit", n), &hit, |b, f| {
+        b.iter(|| st.is_tracked_session(black_box(f)))
+    });
+    group.finish();
+}
+
+// Lookups while another thread takes the write lock to insert new sessions the
+// way the redis ingest thread does.
+fn bench_concurrent(c: &mut Criterion) {
+    let mut group = c.benchmark_group("concurrent_lookup_insert");
+    let n = SIZES[1];
+
+    for snapshot in [false, true].iter() {
+        let mut st = populated_tracker(n, 3600*S2NS);
+        if *snapshot {
+            st.enable_snapshot(Duration::from_millis(10));
+        }
+
+        let done = Arc::new(AtomicBool::new(false));
+        let writer = {
+            let sessions = Arc::clone(&st.tracked_sessions);
+            let done = Arc::clone(&done);
+            thread::spawn(move || {
+                let mut i = n;
+                while !done.load(Ordering::Relaxed) {
+                    let sd = v4_session(i, 3600*S2NS);
+                    let mut map = sessions.write().unwrap();
+                    map.insert(sd.get_key(), SessionEntry { session_id: i as u64, ..SessionEntry::new(sd, MonotonicNs(u64::max_value())) });
+                    drop(map);
+                    i += 1;
+                }
+            })
+        };
+
+        let hit = v4_flow(n / 2);
+        let name = if *snapshot { "snapshot" } else { "rwlock" };
+        group.bench_with_input(BenchmarkId::new(name, n), &hit, |b, f| {
+            b.iter(|| st.is_tracked_session(black_box(f)))
+        });
+
+        done.store(true, Ordering::Relaxed);
+        writer.join().unwrap();
+    }
+    group.finish();
+}
+
+fn bench_key_construction(c: &mut Criterion) {
+    let mut group = c.benchmark_group("get_key");
+    let v4 = SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_secs(1)).unwrap();
+    let v6 = SessionDetails::new("2601::123:abcd", "2001::1234", 443, Duration::from_secs(1)).unwrap();
+    group.bench_function("v4", |b| b.iter(|| black_box(&v4).get_key()));
+    group.bench_function("v6", |b| b.iter(|| black_box(&v6).get_key()));
+    group.finish();
+}
+
+// Half of the sessions have already expired so each run removes n/2 entries
+// and scans the rest.
+fn bench_drop_stale(c: &mut Criterion) {
+    let mut group = c.benchmark_group("drop_stale_sessions");
+    group.sample_size(10);
+    for n in SIZES[..2].iter() {
+        group.bench_with_input(BenchmarkId::from_parameter(n), n, |b, n| {
+            b.iter_batched(|| {
+                let mut st = SessionTracker::new();
+                for i in 0..*n {
+                    let timeout = if i % 2 == 0 { 0 } else { 3600*S2NS };
+                    st.add_session(v4_session(i, timeout));
+                }
+                st
+            },
+            |mut st| st.drop_stale_sessions(),
+            BatchSize::LargeInput)
+        });
+    }
+    group.finish();
+}
+
+fn hash_algorithms() -> Vec<HashAlgorithm> {
+    ["sip", "fx", "ahash"].iter().filter_map(|a| a.parse().ok()).collect()
+}
+
+// Lookups in a tracker whose map uses each hash algorithm, and the cost of
+// hashing a key on its own. Also prints how many of the keys share a bucket
+// with an earlier key in a table of 2^20 buckets (the low 20 bits of the
+// hash), which should be about the same for every algorithm on these keys.
+fn bench_hash_algorithms(c: &mut Criterion) {
+    let mut group = c.benchmark_group("map_hash");
+    let n = SIZES[1];
+    let keys: Vec<String> = (0..n).map(|i| v4_session(i, S2NS).get_key()).collect();
+    for algorithm in hash_algorithms() {
+        let hasher = MapHasher::new(algorithm);
+        let mut buckets = HashSet::new();
+        for key in keys.iter() {
+            let mut h = hasher.build_hasher();
+            key.hash(&mut h);
+            buckets.insert(h.finish() & ((1 << 20) - 1));
+        }
+        println!("map_hash/{}: {} of {} keys collide in 2^20 buckets", algorithm, keys.len() - buckets.len(), keys.len());
+
+        let name = algorithm.to_string();
+        group.bench_with_input(BenchmarkId::new("hash_key", &name), &keys[0], |b, k| {
+            b.iter(|| {
+                let mut h = hasher.build_hasher();
+                black_box(k).hash(&mut h);
+                h.finish()
+            })
+        });
+
+        let config = SessionConfig { hash: algorithm, ..SessionConfig::default() };
+        let mut st = SessionTracker::with_config(config, Clock::Monotonic);
+        for i in 0..n {
+            st.add_session(v4_session(i, 3600*S2NS));
+        }
+        let hit = v4_flow(n / 2);
+        let miss = v4_flow(n + 1);
+        group.bench_with_input(BenchmarkId::new(format!("{}_hit", name), n), &hit, |b, f| {
+            b.iter(|| st.is_tracked_session(black_box(f)))
+        });
+        group.bench_with_input(BenchmarkId::new(format!("{}_miss", name), n), &miss, |b, f| {
+            b.iter(|| st.is_tracked_session(black_box(f)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_lookup, bench_concurrent, bench_key_construction, bench_drop_stale,
+                 bench_hash_algorithms);
+criterion_main!(benches);
@@ -0,0 +1,225 @@
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+
+#include <rte_eal.h>
+#include <rte_ethdev.h>
+#include <rte_lcore.h>
+#include <rte_mbuf.h>
+#include <rte_memzone.h>
+
+#include "capture_dpdk.h"
+#include "rust_foreign_interface.h"
+
+// Set by the primary process once the port is started.
+#define READY_MEMZONE "conjure_port_ready"
+// How long secondary processes wait for the primary to start the port.
+#define READY_WAIT_US (60LL*1000LL*1000LL)
+#define READY_POLL_US (100LL*1000LL)
+#define MAX_RX_BURST 256
+// Toeplitz key sizes: what most PMDs take, and the largest any does (i40e).
+#define RSS_KEY_DEFAULT 40
+#define RSS_KEY_MAX 52
+
+static struct DpdkSettings g_settings;
+static uint16_t g_queue = 0;
+static int g_primary = 0;
+static struct rte_mbuf* g_burst[MAX_RX_BURST];
+// This queue's counts since the port was started. The port wide ones come
+// from rte_eth_stats_get.
+static uint64_t g_rx_packets = 0;
+static uint64_t g_rx_bytes = 0;
+static uint64_t g_rx_oversize = 0;
+
+static void configure_port(uint16_t port, uint16_t num_queues)
+{
+    struct rte_eth_dev_info info;
+    int rc = rte_eth_dev_info_get(port, &info);
+    if(rc != 0)
+    {
+        fprintf(stderr, "DPDK: no port %u: %s\n", port, rte_strerror(-rc));
+        exit(-1);
+    }
+    if(num_queues > info.max_rx_queues)
+    {
+        fprintf(stderr, "DPDK: port %u has %u receive queues, %u cores need "
+                        "one each\n", port, info.max_rx_queues, num_queues);
+        exit(-1);
+    }
+
+    // One pool for every queue, created by the primary and used by the PMD
+    // on behalf of all processes.
+    unsigned int pool_size = g_settings.mbufs;
+    struct rte_mempool* pool = rte_pktmbuf_pool_create(
+        "conjure_rx", pool_size, 256, 0,
+        g_settings.mbuf_size + RTE_PKTMBUF_HEADROOM, rte_eth_dev_socket_id(port));
+    if(pool == NULL)
+    {
+        fprintf(stderr, "DPDK: can't create a pool of %u mbufs: %s\n",
+                pool_size, rte_strerror(rte_errno));
+        exit(-1);
+    }
+
+    // The PMD's default Toeplitz key hashes the two directions of a flow
+    // differently. This one (0x6d5a repeated) hashes a pair of addresses the
+    // same either way round, so both directions land on the same core.
+    static uint8_t symmetric_key[RSS_KEY_MAX];
+    uint8_t key_len = info.hash_key_size ? info.hash_key_size : RSS_KEY_DEFAULT;
+    if(key_len > RSS_KEY_MAX)
+    {
+        fprintf(stderr, "DPDK: port %u takes a %u byte RSS key, at most %u "
+                        "supported\n", port, key_len, RSS_KEY_MAX);
+        exit(-1);
+    }
+    for(int i = 0; i < key_len; i += 2)
+    {
+        symmetric_key[i] = 0x6d;
+        symmetric_key[i + 1] = 0x5a;
+    }
+
+    struct rte_eth_conf conf;
+    memset(&conf, 0, sizeof(conf));
+    conf.rxmode.mq_mode = RTE_ETH_MQ_RX_RSS;
+    // Hash on addresses only, as with the PF_RING cluster's 2-tuple
+    // balancing, under the symmetric key.
+    conf.rx_adv_conf.rss_conf.rss_hf = RTE_ETH_RSS_IP & info.flow_type_rss_offloads;
+    conf.rx_adv_conf.rss_conf.rss_key = symmetric_key;
+    conf.rx_adv_conf.rss_conf.rss_key_len = key_len;
+    rc = rte_eth_dev_configure(port, num_queues, 0, &conf);
+    if(rc != 0)
+    {
+        fprintf(stderr, "DPDK: can't configure port %u: %s\n", port, rte_strerror(-rc));
+        exit(-1);
+    }
+
+    uint16_t rx_desc = g_settings.rx_desc;
+    uint16_t tx_desc = 0;
+    rte_eth_dev_adjust_nb_rx_tx_desc(port, &rx_desc, &tx_desc);
+    for(uint16_t q = 0; q < num_queues; q++)
+    {
+        rc = rte_eth_rx_queue_setup(port, q, rx_desc,
+                                    rte_eth_dev_socket_id(port), NULL, pool);
+        if(rc != 0)
+        {
+            fprintf(stderr, "DPDK: can't set up queue %u of port %u: %s\n",
+                    q, port, rte_strerror(-rc));
+            exit(-1);
+        }
+    }
+
+    rc = rte_eth_dev_start(port);
+    if(rc != 0)
+    {
+        fprintf(stderr, "DPDK: can't start port %u: %s\n", port, rte_strerror(-rc));
+        exit(-1);
+    }
+    rte_eth_promiscuous_enable(port);
+
+    if(rte_memzone_reserve(READY_MEMZONE, sizeof(uint16_t), SOCKET_ID_ANY, 0) == NULL)
+    {
+        fprintf(stderr, "DPDK: can't signal the port is ready: %s\n",
+                rte_strerror(rte_errno));
+        exit(-1);
+    }
+    printf("DPDK: port %u started with %u queues, %u descriptors each\n",
+           port, num_queues, rx_desc);
+}
+
+static void wait_for_port(uint16_t port)
+{
+    int64_t waited = 0;
+    while(rte_memzone_lookup(READY_MEMZONE) == NULL)
+    {
+        if(waited >= READY_WAIT_US)
+        {
+            fprintf(stderr, "DPDK: port %u not started by the primary process\n", port);
+            exit(-1);
+        }
+        usleep(READY_POLL_US);
+        waited += READY_POLL_US;
+    }
+}
+
+void conjure_dpdk_start(int proc_ind, int cpu, uint16_t num_queues)
+{
+    g_settings = rust_dpdk_settings(proc_ind, cpu);
+    g_queue = proc_ind;
+    if(g_settings.rx_burst > MAX_RX_BURST)
+        g_settings.rx_burst = MAX_RX_BURST;
+
+    if(rte_eal_init(g_settings.argc, g_settings.argv) < 0)
+    {
+        fprintf(stderr, "DPDK: EAL init failed in child %d: %s\n",
+                proc_ind, rte_strerror(rte_errno));
+        exit(-1);
+    }
+    if(g_queue >= num_queues)
+    {
+        fprintf(stderr, "DPDK: child %d has no queue, the port has %u\n",
+                proc_ind, num_queues);
+        exit(-1);
+    }
+
+    g_primary = rte_eal_process_type() == RTE_PROC_PRIMARY;
+    if(g_primary)
+        configure_port(g_settings.port, num_queues);
+    else
+        wait_for_port(g_settings.port);
+    printf("DPDK: child %d (%s) polling port %u queue %u\n", proc_ind,
+           g_primary ? "primary" : "secondary", g_settings.port, g_queue);
+}
+
+int conjure_dpdk_rx_burst(void* rust_ptr)
+{
+    uint16_t n = rte_eth_rx_burst(g_settings.port, g_queue, g_burst,
+                                  g_settings.rx_burst);
+    for(uint16_t i = 0; i < n; i++)
+    {
+        struct rte_mbuf* m = g_burst[i];
+        // Frames spread over several mbufs would have to be copied to be
+        // parsed; the pool's mbufs are sized so this only happens for frames
+        // larger than DETECTOR_DPDK_MBUF_SIZE, which are dropped.
+        if(m->nb_segs == 1)
+        {
+            // Zero-copy: the parser reads the frame where the NIC wrote it.
+            rust_process_packet(rust_ptr, rte_pktmbuf_mtod(m, uint8_t*),
+                                rte_pktmbuf_data_len(m));
+            g_rx_bytes += rte_pktmbuf_pkt_len(m);
+        }
+        else
+            g_rx_oversize++;
+        rte_pktmbuf_free(m);
+    }
+    g_rx_packets += n;
+    return n;
+}
+
+uint64_t conjure_dpdk_report(void* rust_ptr)
+{
+    // The port's counts are reported by every process, its drops only by
+    // the primary so they are not counted once per core.
+    struct rte_eth_stats stats;
+    memset(&stats, 0, sizeof(stats));
+    rte_eth_stats_get(g_settings.port, &stats);
+
+    struct DpdkRxCounters totals;
+    totals.packets = g_rx_packets;
+    totals.bytes = g_rx_bytes;
+    totals.oversize = g_rx_oversize;
+    totals.errors = stats.ierrors;
+    totals.missed = stats.imissed;
+    totals.no_mbuf = stats.rx_nombuf;
+    rust_dpdk_rx_stats(rust_ptr, g_settings.port, g_queue, totals);
+    return g_primary ? stats.imissed + stats.rx_nombuf : 0;
+}
+
+void conjure_dpdk_stop(void)
+{
+    if(g_primary)
+    {
+        rte_eth_dev_stop(g_settings.port);
+        rte_eth_dev_close(g_settings.port);
+    }
+    rte_eal_cleanup();
+}
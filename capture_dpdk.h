@@ -0,0 +1,27 @@
+#ifndef _INCLGUARD_CONJURE_CAPTURE_DPDK_H_
+#define _INCLGUARD_CONJURE_CAPTURE_DPDK_H_
+
+#include <stdint.h>
+
+// DPDK capture backend, built with `make CAPTURE=dpdk` (which defines
+// CONJURE_USE_DPDK and builds the Rust side with the dpdk feature). See
+// src/dpdk.rs for how the processes share the port.
+
+// Starts the EAL for the process of core `proc_ind`, pinned to `cpu`, and
+// waits until its receive queue is ready. The first process to start
+// configures the port with `num_queues` queues. Exits on failure.
+void conjure_dpdk_start(int proc_ind, int cpu, uint16_t num_queues);
+
+// Receives one burst on this process's queue and hands each frame to
+// rust_process_packet. Returns the number of frames received.
+int conjure_dpdk_rx_burst(void* rust_ptr);
+
+// Reports this queue's receive counts to Rust (rust_dpdk_rx_stats) and
+// returns the frames the port dropped so far (0 but in the primary), for the
+// "drop" report line.
+uint64_t conjure_dpdk_report(void* rust_ptr);
+
+// Stops the port (primary process only) and releases the EAL.
+void conjure_dpdk_stop(void);
+
+#endif //_INCLGUARD_CONJURE_CAPTURE_DPDK_H_
@@ -1,5 +1,6 @@
 #define _GNU_SOURCE
 #include <signal.h>
+#include <sys/mman.h>
 #include <sys/sysinfo.h>
 #include <sys/wait.h>
 #include <sched.h>
@@ -18,7 +19,11 @@
 // `./scripts/tapdance-build.sh` ultimately calls `make zc_tapdance`, while
 // `./scripts/tapdance-build.sh --nozerocopy` calls `make tapdance`.
 // #define TAPDANCE_USE_PF_RING_ZERO_COPY
-#ifdef TAPDANCE_USE_PF_RING_ZERO_COPY
+// `make CAPTURE=dpdk` instead defines CONJURE_USE_DPDK, capturing with DPDK
+// (capture_dpdk.c) rather than PF_RING.
+#if defined(CONJURE_USE_DPDK)
+#include "capture_dpdk.h"
+#elif defined(TAPDANCE_USE_PF_RING_ZERO_COPY)
 #include "pfring_zc.h"
 #define pfring_maybezc_stat pfring_zc_stat
 #define pfring_maybezc_stats pfring_zc_stats
@@ -52,25 +57,72 @@ size_t write_reporter(uint8_t* buf, size_t len);
 #define DESIRED_PAUSE_DUR_NS 10000
 
 #define NO_ZC_BUFFER_LEN 9000
+// Buffers of the cluster each child creates when it opens a device queue
+// itself (-i in zero-copy mode): enough for the queue's ring plus a burst.
+#define ZC_DEVICE_BUFFERS (32768 + PF_BURST_SIZE)
 #define MAX_NUM_FORKED_PROCS 256
 pid_t g_forked_pids[MAX_NUM_FORKED_PROCS];
-#ifdef TAPDANCE_USE_PF_RING_ZERO_COPY
+#if defined(CONJURE_USE_DPDK)
+// The DPDK port and queue are kept by capture_dpdk.c.
+#elif defined(TAPDANCE_USE_PF_RING_ZERO_COPY)
 pfring_zc_queue* g_ring = 0;
 pfring_zc_buffer_pool* g_pool = 0;
 pfring_zc_pkt_buff* g_buf[PF_BURST_SIZE];
+// Only set when reading a device queue directly rather than through
+// zbalance_ipc, see startup_pfring_maybezc.
+pfring_zc_cluster* g_cluster = 0;
 #else
 pfring* g_ring = 0;
+#endif
+#ifndef CONJURE_USE_DPDK
 const char* g_iface_name = 0;
+// Each child's receive and drop totals, in memory shared by all of them, so
+// that child 0 can report the sum over every queue. Indexed by
+// proc_ind - g_pfring_offset.
+struct queue_stats
+{
+    uint64_t recv;
+    uint64_t drop;
+};
+struct queue_stats* g_queue_stats = 0;
+int g_pfring_offset = 0;
 #endif
 int g_num_worker_procs = 0;
 void* g_rust_cli_conf_proto_ptr = 0;
 void* g_rust_failed_map = 0;
 int g_update_cli_conf_when_convenient = 0;
 int g_update_overloaded_decoys_when_convenient = 0;
+int g_dump_sessions_when_convenient = 0;
 
 #define TIMESPEC_DIFF(a, b) ((a.tv_sec - b.tv_sec)*1000000000LL + \
                              ((int64_t)a.tv_nsec - (int64_t)b.tv_nsec))
 
+#ifndef CONJURE_USE_DPDK
+// Reports the receive and drop totals of all the children's queues, as last
+// stored by each of them, so that the detector's total capture rate can be
+// read from one line however many queues it's spread over. Child 0 does this
+// once per report period.
+void report_queue_stats()
+{
+    uint64_t recv = 0;
+    uint64_t drop = 0;
+    uint64_t busiest = 0;
+    for(int i=0; i<g_num_worker_procs; i++)
+    {
+        uint64_t q_recv = __atomic_load_n(&g_queue_stats[i].recv, __ATOMIC_RELAXED);
+        recv += q_recv;
+        drop += __atomic_load_n(&g_queue_stats[i].drop, __ATOMIC_RELAXED);
+        if(q_recv > busiest)
+            busiest = q_recv;
+    }
+    // Enough for "queues x recv x drop x busiest x\n" for x=2**64
+    char buf[120];
+    snprintf(buf, sizeof(buf), "queues %d recv %lu drop %lu busiest %lu\n",
+             g_num_worker_procs, recv, drop, busiest);
+    write_reporter((uint8_t*)buf, strlen(buf));
+}
+#endif
+
 void the_program(uint8_t core_id, unsigned int log_interval,
                  uint8_t* station_key, char* workers_socket_addr)
 {
@@ -82,7 +134,9 @@ void the_program(uint8_t core_id, unsigned int log_interval,
 
     //rust_update_cli_conf(g_rust_cli_conf_proto_ptr);
     printf(">>>> starting core %d\n", core_id);
-#ifdef TAPDANCE_USE_PF_RING_ZERO_COPY
+#if defined(CONJURE_USE_DPDK)
+    printf("DPDK TapDance child proc started on core %d!\n", core_id);
+#elif defined(TAPDANCE_USE_PF_RING_ZERO_COPY)
     printf("Zero-copy TapDance child proc started on core %d!\n", core_id);
 #else
     printf("NON-zero-copy TapDance child proc started on core %d!\n", core_id);
@@ -113,16 +167,28 @@ void the_program(uint8_t core_id, unsigned int log_interval,
     int64_t ns_since_status_report;
     // log_interval is milliseconds
     int64_t log_interval_ns = log_interval * 1000LL * 1000LL;
+#ifdef CONJURE_USE_DPDK
+    // The port was just started, or is counting since the primary started it
+    unsigned long drops_prev = 0;
+    unsigned long drops_cur = 0;
+#else
     pfring_maybezc_stat stats;
     pfring_maybezc_stats(g_ring, &stats);
     unsigned long drops_prev = stats.drop;
     unsigned long drops_cur = stats.drop;
+#endif
 
     while(1)
     {
         while(recvd_pkts < PKT_BURST_SIZE)
         {
-#ifdef TAPDANCE_USE_PF_RING_ZERO_COPY
+#if defined(CONJURE_USE_DPDK)
+            int cur_recvd_pkts;
+            if((cur_recvd_pkts = conjure_dpdk_rx_burst(rust_ptr)) > 0)
+                recvd_pkts += cur_recvd_pkts;
+            else
+                break;
+#elif defined(TAPDANCE_USE_PF_RING_ZERO_COPY)
             int cur_recvd_pkts;
             if((cur_recvd_pkts =
                 pfring_zc_recv_pkt_burst(g_ring, g_buf, PF_BURST_SIZE, 0)) > 0)
@@ -174,13 +240,25 @@ void the_program(uint8_t core_id, unsigned int log_interval,
                 g_update_overloaded_decoys_when_convenient = 0;
                 //rust_update_overloaded_decoys(rust_ptr);
             }
+            if(unlikely(g_dump_sessions_when_convenient))
+            {
+                g_dump_sessions_when_convenient = 0;
+                rust_dump_sessions(rust_ptr);
+            }
         }
         if(unlikely(ns_since_status_report > log_interval_ns))
         {
             prev_status_report = cur_time_ns;
             rust_periodic_report(rust_ptr);
+#ifdef CONJURE_USE_DPDK
+            drops_cur = conjure_dpdk_report(rust_ptr);
+#else
             pfring_maybezc_stats(g_ring, &stats);
             drops_cur = stats.drop;
+            struct queue_stats* own = &g_queue_stats[core_id - g_pfring_offset];
+            __atomic_store_n(&own->recv, stats.recv, __ATOMIC_RELAXED);
+            __atomic_store_n(&own->drop, stats.drop, __ATOMIC_RELAXED);
+#endif
 
             // Always report to gobbler (prometheus philosophy)
             char buf[50]; // Enough for "drop x x\n" for x=2**64
@@ -189,6 +267,10 @@ void the_program(uint8_t core_id, unsigned int log_interval,
             write_reporter((uint8_t*)buf, strlen(buf));
 
             drops_prev = drops_cur;
+#ifndef CONJURE_USE_DPDK
+            if(core_id == g_pfring_offset)
+                report_queue_stats();
+#endif
         }
     }
 }
@@ -207,18 +289,35 @@ static void notify_overloaded_decoys_file_update(int sig, siginfo_t* si,
 {
     g_update_overloaded_decoys_when_convenient = 1;
 }
+static void notify_dump_sessions(int sig, siginfo_t* si, void* junk)
+{
+    g_dump_sessions_when_convenient = 1;
+}
 
 void sigproc_child(int sig)
 {
     static char called = 0;
     if(called) return; else called = 1;
 
-#ifdef TAPDANCE_USE_PF_RING_ZERO_COPY
+#if defined(CONJURE_USE_DPDK)
+    conjure_dpdk_stop();
+    fprintf(stderr, "DPDK Tapdance child process shut down\n");
+#elif defined(TAPDANCE_USE_PF_RING_ZERO_COPY)
     pfring_zc_queue_breakloop(g_ring);
-    for (int i=0; i<PF_BURST_SIZE; i++)
-        pfring_zc_release_packet_handle_to_pool(g_pool, g_buf[i]);
-    pfring_zc_ipc_detach_queue(g_ring);
-    pfring_zc_ipc_detach_buffer_pool(g_pool);
+    if(g_cluster)
+    {
+        for (int i=0; i<PF_BURST_SIZE; i++)
+            pfring_zc_release_packet_handle(g_cluster, g_buf[i]);
+        pfring_zc_close_device(g_ring);
+        pfring_zc_destroy_cluster(g_cluster);
+    }
+    else
+    {
+        for (int i=0; i<PF_BURST_SIZE; i++)
+            pfring_zc_release_packet_handle_to_pool(g_pool, g_buf[i]);
+        pfring_zc_ipc_detach_queue(g_ring);
+        pfring_zc_ipc_detach_buffer_pool(g_pool);
+    }
     fprintf(stderr, "PF_RING zero-copy Tapdance child process shut down\n");
 #else
     pfring_breakloop(g_ring);
@@ -256,9 +355,65 @@ void set_affinity(int id)
         fprintf(stderr, "Error binding to core %ld: errno=%i\n", core_id, s);
 }
 
-#ifdef TAPDANCE_USE_PF_RING_ZERO_COPY
-void startup_pfring_maybezc(unsigned int cluster_id, int proc_ind)
+#if defined(CONJURE_USE_DPDK)
+// Each child polls the port's queue numbered like it, see capture_dpdk.c.
+#elif defined(TAPDANCE_USE_PF_RING_ZERO_COPY)
+// With -i, each child opens receive queue proc_ind of the device itself
+// ("zc:<iface>@<proc_ind>"), in a cluster of its own (cluster_id + proc_ind)
+// with buffers on the NUMA node of the core it is pinned to. The NIC's RSS
+// spreads flows across the queues, so no zbalance_ipc is needed; the device
+// must have been loaded with at least as many RSS queues as there are
+// children (plus the -z offset).
+void startup_pfring_zc_device(unsigned int cluster_id, int proc_ind,
+                              int core_affinity)
 {
+    char device_id[200];
+    if(strchr(g_iface_name, ','))
+    {
+        fprintf(stderr, "Error: only one device (-i %s) can be read without "
+                        "zbalance_ipc\n", g_iface_name);
+        exit(-1);
+    }
+    if(!strncmp(g_iface_name, "zc:", 3))
+        snprintf(device_id, sizeof(device_id), "%s@%d", g_iface_name, proc_ind);
+    else
+        snprintf(device_id, sizeof(device_id), "zc:%s@%d", g_iface_name, proc_ind);
+
+    if(!(g_cluster = pfring_zc_create_cluster(
+            cluster_id + proc_ind, NO_ZC_BUFFER_LEN, 0, ZC_DEVICE_BUFFERS,
+            pfring_zc_numa_get_cpu_node(core_affinity), NULL, 0)))
+    {
+        fprintf(stderr, "pfring_zc_create_cluster error [%s] for %s (%d)\n",
+                strerror(errno), device_id, cluster_id + proc_ind);
+        exit(-1);
+    }
+    if(!(g_ring = pfring_zc_open_device(g_cluster, device_id, rx_only, 0)))
+    {
+        fprintf(stderr, "pfring_zc_open_device error [%s] opening %s\n",
+                strerror(errno), device_id);
+        exit(-1);
+    }
+    for (int i=0; i<PF_BURST_SIZE; i++)
+    {
+        if(!(g_buf[i] = pfring_zc_get_packet_handle(g_cluster)))
+        {
+            fprintf(stderr, "pfring_zc_get_packet_handle error [%s] "
+                            "opening %s\n", strerror(errno), device_id);
+            exit(-1);
+        }
+    }
+    printf("Child %d reading %s directly\n", proc_ind, device_id);
+}
+
+void startup_pfring_maybezc(unsigned int cluster_id, int proc_ind,
+                            int core_affinity)
+{
+    if(g_iface_name)
+    {
+        startup_pfring_zc_device(cluster_id, proc_ind, core_affinity);
+        return;
+    }
+
     char cluster_iface_id[200];
     int channel = proc_ind;
     sprintf(cluster_iface_id, "zc:%d@%d", cluster_id, channel);
@@ -290,7 +445,8 @@ void startup_pfring_maybezc(unsigned int cluster_id, int proc_ind)
     }
 }
 #else
-void startup_pfring_maybezc(unsigned int cluster_id, int proc_ind)
+void startup_pfring_maybezc(unsigned int cluster_id, int proc_ind,
+                            int core_affinity)
 {
     char cluster_iface_id[200];
     memset(cluster_iface_id, 0, 200);
@@ -321,7 +477,7 @@ void startup_pfring_maybezc(unsigned int cluster_id, int proc_ind)
         exit(-1);
     }
 }
-#endif // TAPDANCE_USE_PF_RING_ZERO_COPY
+#endif // CONJURE_USE_DPDK, TAPDANCE_USE_PF_RING_ZERO_COPY
 
 pid_t start_tapdance_process(int core_affinity, unsigned int cluster_id,
                              int proc_ind, unsigned int log_interval,
@@ -331,12 +487,23 @@ pid_t start_tapdance_process(int core_affinity, unsigned int cluster_id,
     if(the_pid == 0)
     {
         printf(">>> Child proc %d created\n", core_affinity);
-        startup_pfring_maybezc(cluster_id, proc_ind);
+#ifdef CONJURE_USE_DPDK
+        conjure_dpdk_start(proc_ind, core_affinity, g_num_worker_procs);
+#else
+        startup_pfring_maybezc(cluster_id, proc_ind, core_affinity);
+#endif
 
         set_affinity(core_affinity);
         signal(SIGINT, sigproc_child);
         signal(SIGTERM, sigproc_child);
         signal(SIGPIPE, ignore_sigpipe);
+
+        // Only the children track sessions, so only they dump them on SIGHUP.
+        struct sigaction sa_dump;
+        sa_dump.sa_flags = SA_SIGINFO; // use sa_sigaction, not sa_handler
+        sigemptyset(&sa_dump.sa_mask);
+        sa_dump.sa_sigaction = notify_dump_sessions;
+        sigaction(SIGHUP, &sa_dump, NULL);
         the_program(proc_ind, log_interval, station_key, workers_socket_addr);
     }
     printf("Core %d: PID %d, lcore %d\n", proc_ind, the_pid, core_affinity);
@@ -372,6 +539,14 @@ struct cmd_options
     int             skip_core;    // -1 if not skipping any core, otherwise the core to skip
     char*           zmq_address;  // address of output ZMQ socket to bind
     char*           zmq_worker_address;  // address of ZMQ socket to bind for communication between threads
+    int             healthcheck;  // run the health checks and exit (-H)
+
+    // With -C, the cores to pin the children to, one each, in order. This
+    // replaces -n, -o and -s, so that the children can be placed on exactly
+    // the cores sharing a NUMA node with the NIC, or around cores reserved
+    // for other work. 0 cores if not given.
+    int             core_list[MAX_NUM_FORKED_PROCS];
+    int             core_list_len;
 };
 
 static uint8_t station_key[TD_KEYLEN_BYTES] = {
@@ -383,6 +558,46 @@ static uint8_t station_key[TD_KEYLEN_BYTES] = {
 
 static uint8_t public_key[TD_KEYLEN_BYTES] = { 0 };
 
+// Parses a -C argument, core IDs separated by commas (e.g. "2,3,6,7"), into
+// options->core_list. Exits if one isn't a core ID or is given twice.
+void parse_core_list(const char* arg, struct cmd_options* options)
+{
+    char* list = strdup(arg);
+    char* saveptr = 0;
+    options->core_list_len = 0;
+    for (char* tok = strtok_r(list, ",", &saveptr); tok;
+         tok = strtok_r(0, ",", &saveptr))
+    {
+        char* end = 0;
+        long core = strtol(tok, &end, 10);
+        if (end == tok || *end != '\0' || core < 0 ||
+            core >= MAX_NUM_FORKED_PROCS)
+        {
+            fprintf(stderr, "Error: bad core ID \"%s\" in -C %s. This program\n"
+                            "can only use 0 through %d inclusive.\n",
+                    tok, arg, MAX_NUM_FORKED_PROCS-1);
+            exit(-1);
+        }
+        for (int i=0; i<options->core_list_len; i++)
+        {
+            if (options->core_list[i] == core)
+            {
+                fprintf(stderr, "Error: core %ld given twice in -C %s\n",
+                        core, arg);
+                exit(-1);
+            }
+        }
+        if (options->core_list_len >= MAX_NUM_FORKED_PROCS)
+        {
+            fprintf(stderr, "Error: more than %d cores in -C\n",
+                    MAX_NUM_FORKED_PROCS);
+            exit(-1);
+        }
+        options->core_list[options->core_list_len++] = (int)core;
+    }
+    free(list);
+}
+
 void parse_cmd_args(int argc, char* argv[], struct cmd_options* options)
 {
     // Defaults, development
@@ -394,6 +609,8 @@ void parse_cmd_args(int argc, char* argv[], struct cmd_options* options)
     int skip_core = -1; // If >0, skip this core when incrementing
     options->zmq_address = "ipc://@detector";
     options->zmq_worker_address = "ipc://@detector-workers";
+    options->healthcheck = 0;
+    options->core_list_len = 0;
 
     char* keyfile_name = 0;
 
@@ -401,15 +618,20 @@ void parse_cmd_args(int argc, char* argv[], struct cmd_options* options)
     options->public_key = public_key;
 
     char c;
-    while ((c = getopt(argc,argv,"i:n:c:o:l:K:s:a:w:z:")) != -1)
+    while ((c = getopt(argc,argv,"i:n:c:o:l:K:s:a:w:z:C:H")) != -1)
     {
         switch (c)
         {
             case 'i':
-#ifdef TAPDANCE_USE_PF_RING_ZERO_COPY
-                fprintf(stderr, "Warning: -i unused in zero copy mode\n");
+#if defined(CONJURE_USE_DPDK)
+                fprintf(stderr, "Warning: -i unused with DPDK, the port is "
+                                "given by DETECTOR_DPDK_EAL_ARGS/_PORT\n");
 #else
-                g_iface_name = optarg;
+                // In zero copy mode, the device whose queues the children
+                // read directly instead of attaching to zbalance_ipc. Empty
+                // (as from an unset variable in the unit file) means none.
+                if(optarg[0])
+                    g_iface_name = optarg;
 #endif
                 break;
             case 'n':
@@ -439,7 +661,19 @@ void parse_cmd_args(int argc, char* argv[], struct cmd_options* options)
                 strcpy(options->zmq_worker_address, optarg);
                 break;
             case 'z':
+#ifdef CONJURE_USE_DPDK
+                // Child i always polls queue i of the port
+                fprintf(stderr, "Warning: -z unused with DPDK\n");
+#else
                 options->pfring_offset = atoi(optarg);
+#endif
+                break;
+            case 'C':
+                if(optarg[0])
+                    parse_core_list(optarg, options);
+                break;
+            case 'H':
+                options->healthcheck = 1;
                 break;
             default:
                 fprintf(stderr, "Unknown option %c\n", c);
@@ -475,9 +709,33 @@ void parse_cmd_args(int argc, char* argv[], struct cmd_options* options)
         printf("Using default key\n");
     }
 
+    int cores_online = get_nprocs_conf();
+    if (options->core_list_len > 0)
+    {
+        for (int i=0; i<options->core_list_len; i++)
+        {
+            if (options->core_list[i] >= cores_online)
+            {
+                fprintf(stderr,
+                    "Error: core %d in -C is beyond the range of core IDs\n"
+                    "currently available on this machine. Cores 0 to %d\n"
+                    "inclusive are available.\n",
+                    options->core_list[i], cores_online - 1);
+                exit(-1);
+            }
+        }
+        if (cpu_procs_i32 != 1 && cpu_procs_i32 != options->core_list_len)
+            fprintf(stderr, "Warning: -n %d ignored, starting one process on "
+                            "each of the %d cores given with -C\n",
+                    cpu_procs_i32, options->core_list_len);
+        cpu_procs_i32 = options->core_list_len;
+    }
+
     int last_core_id_requested = (options->core_affinity_offset +
                                  cpu_procs_i32) - 1;
     if (skip_core > 0) last_core_id_requested++;
+    // The cores given with -C were checked above
+    if (options->core_list_len > 0) last_core_id_requested = 0;
     if (last_core_id_requested >= MAX_NUM_FORKED_PROCS)
     {
         fprintf(stderr,
@@ -492,7 +750,6 @@ void parse_cmd_args(int argc, char* argv[], struct cmd_options* options)
         }
         exit(-1);
     }
-    int cores_online = get_nprocs_conf();
     if(last_core_id_requested >= cores_online)
     {
         fprintf(stderr,
@@ -506,7 +763,7 @@ void parse_cmd_args(int argc, char* argv[], struct cmd_options* options)
         }
         exit(-1);
     }
-#ifndef TAPDANCE_USE_PF_RING_ZERO_COPY
+#if !defined(TAPDANCE_USE_PF_RING_ZERO_COPY) && !defined(CONJURE_USE_DPDK)
     if(g_iface_name == 0)
     {
         fprintf(stderr, "Error: you are running in non-zero-copy mode and did\n"
@@ -574,6 +831,33 @@ int main(int argc, char* argv[])
 
     g_num_worker_procs = options.cpu_procs;
     int pfring_offset = options.pfring_offset;
+#ifndef CONJURE_USE_DPDK
+    g_pfring_offset = pfring_offset;
+    // Shared with (inherited by) every child, see report_queue_stats.
+    g_queue_stats = mmap(0, g_num_worker_procs * sizeof(struct queue_stats),
+                         PROT_READ | PROT_WRITE, MAP_SHARED | MAP_ANONYMOUS,
+                         -1, 0);
+    if (g_queue_stats == MAP_FAILED)
+    {
+        perror("mmap queue stats");
+        exit(-1);
+    }
+#endif
+
+    // Check every core we would start, and exit nonzero if any is unhealthy.
+    if (options.healthcheck)
+    {
+        const char* iface = 0;
+#if !defined(TAPDANCE_USE_PF_RING_ZERO_COPY) && !defined(CONJURE_USE_DPDK)
+        iface = g_iface_name;
+#endif
+        int unhealthy = 0;
+        int i;
+        for (i=0; i<g_num_worker_procs; i++)
+            unhealthy |= rust_healthcheck(i+pfring_offset, iface);
+        fflush(stdout);
+        exit(unhealthy ? 1 : 0);
+    }
 
     // To keep it simple, we will let the parent and children all have this same
     // handler. All the handler does is set a global flag to 1, so no big deal.
@@ -599,7 +883,9 @@ int main(int argc, char* argv[])
     for (i=0; i<g_num_worker_procs; i++)
     {
         printf("Starting process %d...\n", i);
-        if (core_num == options.skip_core) core_num++;
+        if (options.core_list_len > 0)
+            core_num = options.core_list[i];
+        else if (core_num == options.skip_core) core_num++;
         g_forked_pids[i] =
             start_tapdance_process(core_num,
                                    options.cluster_id, i+pfring_offset, options.log_interval,
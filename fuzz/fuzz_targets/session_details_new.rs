@@ -0,0 +1,30 @@
+// Calls SessionDetails::new with arbitrary client and phantom address strings.
+// The input is split on the first NUL byte into the client and phantom
+// strings, the leading 12 bytes supply the phantom port and timeout (in
+// nanoseconds, as registrations carry it).
+//
+//   cargo +nightly fuzz run session_details_new
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use std::convert::TryInto;
+use std::time::Duration;
+use rust_dark_decoy::sessions::SessionDetails;
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 12 {
+        return;
+    }
+    let phantom_port = u32::from_le_bytes(data[0..4].try_into().unwrap());
+    let timeout = u64::from_le_bytes(data[4..12].try_into().unwrap());
+
+    let addrs = String::from_utf8_lossy(&data[12..]);
+    let mut parts = addrs.splitn(2, '\0');
+    let client_ip = parts.next().unwrap_or("");
+    let phantom_ip = parts.next().unwrap_or("");
+
+    if let Ok(sd) = SessionDetails::new(client_ip, phantom_ip, phantom_port, Duration::from_nanos(timeout)) {
+        let _ = sd.get_key();
+        let _ = format!("{}", sd);
+    }
+});
@@ -0,0 +1,31 @@
+// Feeds arbitrary bytes through the same steps the redis ingest thread takes
+// for a dark_decoy_map message: protobuf parsing, conversion to
+// SessionDetails, and insertion into a SessionTracker.
+//
+//   cargo +nightly fuzz run station_to_detector
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use protobuf::Message;
+use rust_dark_decoy::sessions::{sessions_from, SessionResult, SessionTracker};
+use rust_dark_decoy::signalling::StationToDetector;
+
+fuzz_target!(|data: &[u8]| {
+    let s2d: StationToDetector = match Message::parse_from_bytes(data) {
+        Ok(s2d) => s2d,
+        Err(_) => return,
+    };
+
+    if let Ok(sd) = SessionResult::from(&s2d) {
+        let _ = sd.get_key();
+        let _ = format!("{}", sd);
+    }
+
+    if let Ok(sds) = sessions_from(&s2d) {
+        let mut st = SessionTracker::new();
+        for sd in sds {
+            st.add_session(sd);
+        }
+        let _ = st.dump();
+    }
+});
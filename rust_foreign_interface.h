@@ -16,6 +16,34 @@ uint8_t rust_event_loop_tick(void *rust_global);
 // uint8_t rust_update_overloaded_decoys(void* rust_global);
 uint8_t rust_periodic_report(void *rust_global);
 uint8_t rust_periodic_cleanup(void *rust_global);
+uint8_t rust_dump_sessions(void *rust_global);
+uint8_t rust_healthcheck(int32_t cur_lcore_id, const char *iface);
+
+#ifdef CONJURE_USE_DPDK
+// See src/dpdk.rs.
+struct DpdkSettings {
+	int argc;
+	char **argv;
+	uint16_t port;
+	uint16_t rx_desc;
+	uint16_t rx_burst;
+	uint16_t mbuf_size;
+	uint32_t mbufs;
+};
+
+struct DpdkRxCounters {
+	uint64_t packets;
+	uint64_t bytes;
+	uint64_t oversize;
+	uint64_t errors;
+	uint64_t missed;
+	uint64_t no_mbuf;
+};
+
+struct DpdkSettings rust_dpdk_settings(int32_t cur_lcore_id, int32_t cpu);
+void rust_dpdk_rx_stats(void *rust_global, uint16_t port, uint16_t queue,
+	struct DpdkRxCounters totals);
+#endif
 
 int send_packet_to_proxy(uint8_t id, uint8_t *pkt, size_t len);
 
@@ -0,0 +1,459 @@
+//
+// Admin Socket
+//
+// With DETECTOR_ADMIN_SOCKET set, each detector core listens for operator
+// commands on a unix socket (admin_socket_path). A client connects, writes a
+// single command line and reads the response until the connection is closed,
+// e.g.
+//
+//   echo "drain on" | socat - UNIX-CONNECT:/run/dark-decoy-admin-0.sock
+//
+// Commands run on the admin thread against the core's session tracker, so
+// they only hold up packet processing for as long as they hold the session
+// lock. Every core has its own socket and state; commands that should apply
+// to the whole detector (e.g. drain) need to be sent to every core.
+//
+// The commands can remove sessions and hand them (client addresses included)
+// to another host, and there is no authentication beyond the socket's
+// permissions: it is only accessible to the detector's user (0600). Clients
+// are served one at a time, and one that doesn't send its command within
+// READ_TIMEOUT is dropped so it can't hold up the others.
+//
+//   sessions          number of tracked sessions
+//   session <client> <phantom> [port]
+//                     the tracked session(s) a registration maps to, one line
+//                     each as in the session dump, or nothing
+//   client <client>   the tracked sessions registered by <client>, one line
+//                     each as in the session dump (which includes the time
+//                     left until they expire), or nothing
+//   phantom <phantom> [port]
+//                     the same for the sessions registered for <phantom>, only
+//                     those on [port] if given
+//   remove <client> <phantom> [port]
+//                     removes one registration of the session(s), see
+//                     SessionTracker::remove_registration
+//   flush <subnet> [block <seconds>]
+//                     removes every session whose phantom is in <subnet>
+//                     (e.g. 10.10.0.0/16) and, with block, refuses new
+//                     registrations for those phantoms for <seconds>; answers
+//                     the number removed. See phantom_block.rs.
+//   blocks            the phantom subnets blocked, one line each with the
+//                     time left
+//   unblock <subnet>  lifts the block on <subnet>
+//   drain [on|off]    show or set drain mode: registrations are ignored,
+//                     sessions already tracked are served (and extended by
+//                     activity) until they expire
+//   handoff <url>     enter drain mode and hand every session off to the
+//                     detector whose redis is at <url>
+//   journal [client]  the registrations recently received from the station,
+//                     oldest first, with whether each was accepted or why it
+//                     was rejected; only those from [client] if given, which
+//                     is matched as sent or as a ClientId. Clients are listed
+//                     by ClientId. See journal.rs.
+//   healthcheck       run the health checks (see healthcheck.rs), one line
+//                     each, followed by an error if any failed. The capture
+//                     interface isn't known to the core and isn't checked.
+//
+// An empty <client> ("") is only valid for v6 phantoms, as in registrations,
+// and a missing port means the default phantom ports.
+//
+// Errors are reported as a single line starting with "error:".
+//
+// Each command is logged, with the client address it names (if any) shown as
+// its ClientId.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::IpAddr;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::time::Duration;
+
+use ipnetwork::IpNetwork;
+
+use client_id::ClientId;
+use error::{DetectorError, error_chain};
+use healthcheck::{self, HealthcheckConfig};
+use sessions::{SessionDetails, SessionTracker};
+use util::bind_private;
+
+// Time a client has to send its command line.
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub fn admin_socket_path(lcore: i32) -> String {
+    format!("/run/dark-decoy-admin-{}.sock", lcore)
+}
+
+/// Listens on `path`, replacing the socket left behind by a previous run.
+/// Only the detector's user can connect, see util::bind_private.
+pub fn bind(path: &str) -> io::Result<UnixListener> {
+    bind_private(Path::new(path), |p| UnixListener::bind(p))
+}
+
+// Serves one client at a time for the lifetime of the process.
+pub fn serve(listener: UnixListener, mut tracker: SessionTracker, health: Option<HealthcheckConfig>) {
+    for stream in listener.incoming() {
+        match stream {
+            Ok(s) => {
+                if let Err(e) = handle_client(s, &mut tracker, health.as_ref()) {
+                    debug!("admin client error: {}", e);
+                }
+            },
+            Err(e) => warn!("admin socket accept failed: {}", e),
+        }
+    }
+}
+
+fn handle_client(stream: UnixStream, tracker: &mut SessionTracker, health: Option<&HealthcheckConfig>) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    let mut out = &stream;
+    for l in handle_command(tracker, health, line.trim()) {
+        writeln!(out, "{}", l)?;
+    }
+    Ok(())
+}
+
+/// Runs a single command line and returns the response lines. `health` is
+/// needed for the healthcheck command.
+pub fn handle_command(tracker: &mut SessionTracker, health: Option<&HealthcheckConfig>, line: &str) -> Vec<String> {
+    let args: Vec<&str> = line.split_whitespace().collect();
+    info!("admin command: {}", logged_command(&args));
+    match args.as_slice() {
+        ["sessions"] => vec![format!("{}", tracker.len())],
+        ["drain"] => vec![drain_status(tracker)],
+        ["drain", "on"] => {
+            tracker.set_draining(true);
+            vec![drain_status(tracker)]
+        },
+        ["drain", "off"] => {
+            tracker.set_draining(false);
+            vec![drain_status(tracker)]
+        },
+        ["session", client, phantom] | ["session", client, phantom, _] =>
+            match registration(client, phantom, args.get(3)) {
+                Ok(sd) => tracker.describe_registration(&sd),
+                Err(e) => vec![format!("error: {}", e)],
+            },
+        ["client", client] => match client.parse() {
+            Ok(ip) => tracker.describe_client(&ip),
+            Err(_) => vec![format!("error: bad address {:?}", client)],
+        },
+        ["phantom", phantom] | ["phantom", phantom, _] => {
+            let port = match args.get(2).map(|p| p.parse::<u16>()) {
+                Some(Ok(p)) => Some(p),
+                Some(Err(_)) => return vec![format!("error: bad port {:?}", args[2])],
+                None => None,
+            };
+            match phantom.parse() {
+                Ok(ip) => tracker.describe_phantom(&ip, port),
+                Err(_) => vec![format!("error: bad address {:?}", phantom)],
+            }
+        },
+        ["remove", client, phantom] | ["remove", client, phantom, _] =>
+            match registration(client, phantom, args.get(3)) {
+                Ok(sd) => match tracker.remove_registration(&sd) {
+                    Some(left) => vec![format!("removed, {} registrations left", left)],
+                    None => vec!["error: not tracked".to_string()],
+                },
+                Err(e) => vec![format!("error: {}", e)],
+            },
+        ["flush", subnet] | ["flush", subnet, "block", _] => {
+            let subnet: IpNetwork = match subnet.parse() {
+                Ok(s) => s,
+                Err(_) => return vec![format!("error: bad subnet {:?}", subnet)],
+            };
+            let block = match args.get(3).map(|s| s.parse::<u64>()) {
+                Some(Ok(s)) => Some(Duration::from_secs(s)),
+                Some(Err(_)) => return vec![format!("error: bad block time {:?}", args[3])],
+                None => None,
+            };
+            let flushed = tracker.flush_phantom_subnet(subnet, block);
+            match block {
+                Some(b) => vec![format!("flushed {} sessions, {} blocked for {}s", flushed, subnet, b.as_secs())],
+                None => vec![format!("flushed {} sessions", flushed)],
+            }
+        },
+        ["blocks"] => tracker.describe_phantom_blocks(),
+        ["unblock", subnet] => match subnet.parse::<IpNetwork>() {
+            Ok(s) if tracker.unblock_phantom_subnet(&s) => vec![format!("unblocked {}", s)],
+            Ok(s) => vec![format!("error: {} not blocked", s)],
+            Err(_) => vec![format!("error: bad subnet {:?}", subnet)],
+        },
+        ["handoff", url] => match tracker.handoff(url) {
+            Ok(n) => vec![format!("handed off {} sessions to {}", n, url)],
+            Err(e) => vec![format!("error: handoff to {} failed: {}", url, error_chain(&e))],
+        },
+        ["journal"] | ["journal", _] => match tracker.journal() {
+            Some(journal) => journal.entries(args.get(1).map(|c| c.trim_matches('"')))
+                .iter().map(|e| e.to_string()).collect(),
+            None => vec!["error: ingest journal not configured".to_string()],
+        },
+        ["healthcheck"] => match health {
+            Some(config) => run_healthcheck(tracker, config),
+            None => vec!["error: health checks not configured".to_string()],
+        },
+        _ => vec![format!("error: unknown command {:?}", line)],
+    }
+}
+
+// The command `args` as logged, client addresses shown as their ClientId.
+fn logged_command(args: &[&str]) -> String {
+    let names_client = |i: usize| i == 1 && match args[0] {
+        "session" | "client" | "remove" | "journal" => true,
+        _ => false,
+    };
+    let logged: Vec<String> = args.iter().enumerate().map(|(i, arg)| {
+        if !names_client(i) {
+            return arg.to_string()
+        }
+        match arg.trim_matches('"').parse::<IpAddr>() {
+            Ok(ip) => ClientId::new(ip).to_string(),
+            // a ClientId, or "" for none, is logged as given
+            Err(_) if arg.starts_with("c-") || arg.trim_matches('"').is_empty() => arg.to_string(),
+            // anything else may be a mistyped address
+            Err(_) => "<client>".to_string(),
+        }
+    }).collect();
+    logged.join(" ")
+}
+
+fn run_healthcheck(tracker: &SessionTracker, config: &HealthcheckConfig) -> Vec<String> {
+    let checks = healthcheck::run(config, tracker.config());
+    let mut lines: Vec<String> = checks.iter().map(|c| c.to_string()).collect();
+    let failed = checks.iter().filter(|c| c.result.is_err()).count();
+    if failed > 0 {
+        lines.push(format!("error: {} health checks failed", failed));
+    }
+    lines
+}
+
+// Session for a registration from `client` to `phantom` on `port` (default
+// ports if None). `client` may be given as "" for v6 phantoms.
+fn registration(client: &str, phantom: &str, port: Option<&&str>) -> Result<SessionDetails, String> {
+    let port = match port {
+        Some(p) => p.parse::<u16>().map_err(|_| format!("bad port {:?}", p))? as u32,
+        None => 0,
+    };
+    let client = client.trim_matches('"');
+    SessionDetails::new(client, phantom, port, Duration::from_secs(0))
+        .map_err(|e| error_chain(&DetectorError::from(e)))
+}
+
+fn drain_status(tracker: &SessionTracker) -> String {
+    match tracker.is_draining() {
+        true => "draining".to_string(),
+        false => "accepting".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use admin::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::PathBuf;
+    use client_id;
+    use healthcheck::HealthcheckConfig;
+    use journal::JournalEntry;
+    use sessions::{SessionConfig, SessionDetails, SessionTracker};
+    use registration::Registration;
+    use signalling::StationToDetector;
+    use timekeeping::{Clock, WallClockNs};
+
+    const S: u64 = 1000*1000*1000;
+
+    #[test]
+    fn test_admin_commands() {
+        let mut st = SessionTracker::new();
+        st.add_session(SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_secs(1)).unwrap());
+
+        assert_eq!(handle_command(&mut st, None, "sessions"), vec!["1"]);
+        assert_eq!(handle_command(&mut st, None, "drain"), vec!["accepting"]);
+        assert_eq!(handle_command(&mut st, None, "drain on"), vec!["draining"]);
+        assert!(st.is_draining());
+        assert_eq!(handle_command(&mut st, None, " drain   off "), vec!["accepting"]);
+        assert!(!st.is_draining());
+
+        assert!(handle_command(&mut st, None, "drain maybe")[0].starts_with("error:"));
+        assert!(handle_command(&mut st, None, "")[0].starts_with("error:"));
+        assert!(handle_command(&mut st, None, "handoff not-a-url")[0].starts_with("error:"));
+
+        // client addresses aren't logged
+        let id = client_id::ClientId::new("192.168.0.1".parse().unwrap());
+        assert_eq!(logged_command(&["remove", "192.168.0.1", "10.10.0.1"]), format!("remove {} 10.10.0.1", id));
+        assert_eq!(logged_command(&["journal", "192.168.0.l"]), "journal <client>");
+        assert_eq!(logged_command(&["session", "\"\"", "2001::1"]), "session \"\" 2001::1");
+        assert_eq!(logged_command(&["phantom", "10.10.0.1"]), "phantom 10.10.0.1");
+    }
+
+    #[test]
+    fn test_admin_healthcheck() {
+        let mut st = SessionTracker::new();
+        assert_eq!(handle_command(&mut st, None, "healthcheck"), vec!["error: health checks not configured"]);
+
+        // nothing of the host is looked at
+        let health = HealthcheckConfig {
+            redis_url: "redis://127.0.0.1:1/".to_string(),
+            interface: None,
+            tun: "tun0".to_string(),
+            config_errors: Vec::new(),
+            net_dir: PathBuf::from("/nonexistent/net"),
+            tun_device: PathBuf::from("/nonexistent/tun"),
+        };
+        let lines = handle_command(&mut st, Some(&health), "healthcheck");
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0], "ok config: valid");
+        assert!(lines[1].starts_with("FAIL redis: "));
+        assert_eq!(lines[3], "FAIL tun: no interface tun0");
+        assert_eq!(lines[5], "error: 2 health checks failed");
+        // the synthetic registration isn't added to the core's tracker
+        assert_eq!(st.len(), 0);
+    }
+
+    #[test]
+    fn test_admin_session_lookup() {
+        let mut st = SessionTracker::new();
+        let sd = SessionDetails::new("192.168.0.1", "10.10.0.1", 0, Duration::from_secs(1)).unwrap();
+        st.add_session(sd);
+        st.add_session(sd);
+        st.add_session(SessionDetails::new("", "2001::1234", 8443, Duration::from_secs(1)).unwrap());
+
+        let found = handle_command(&mut st, None, "session 192.168.0.1 10.10.0.1");
+        assert_eq!(found.len(), 1);
+        assert!(found[0].ends_with("(2 registrations)"), "{}", found[0]);
+        assert_eq!(handle_command(&mut st, None, "session 192.168.0.1 10.10.0.1 443"), found);
+        assert!(handle_command(&mut st, None, "session 192.168.0.1 10.10.0.1 8443").is_empty());
+        assert_eq!(handle_command(&mut st, None, "session \"\" 2001::1234 8443").len(), 1);
+        assert!(handle_command(&mut st, None, "session 192.168.0.1 10.10.0.1 https")[0].starts_with("error: bad port"));
+        assert_eq!(handle_command(&mut st, None, "session 2001::1 10.10.0.1"),
+            vec!["error: invalid registration: Client/Phantom v4/v6 mismatch"]);
+
+        assert_eq!(handle_command(&mut st, None, "client 192.168.0.1"), found);
+        assert_eq!(handle_command(&mut st, None, "phantom 10.10.0.1"), found);
+        assert_eq!(handle_command(&mut st, None, "phantom 2001::1234").len(), 1);
+        assert_eq!(handle_command(&mut st, None, "phantom 2001::1234 8443").len(), 1);
+        assert!(handle_command(&mut st, None, "phantom 2001::1234 443").is_empty());
+        assert!(handle_command(&mut st, None, "phantom 2001::1234 https")[0].starts_with("error: bad port"));
+        assert!(handle_command(&mut st, None, "client 192.168.0.2").is_empty());
+        assert_eq!(handle_command(&mut st, None, "client nobody"), vec!["error: bad address \"nobody\""]);
+
+        assert_eq!(handle_command(&mut st, None, "remove 192.168.0.1 10.10.0.1"), vec!["removed, 1 registrations left"]);
+        assert_eq!(handle_command(&mut st, None, "remove 192.168.0.1 10.10.0.1"), vec!["removed, 0 registrations left"]);
+        assert_eq!(handle_command(&mut st, None, "remove 192.168.0.1 10.10.0.1"), vec!["error: not tracked"]);
+        assert!(handle_command(&mut st, None, "client 192.168.0.1").is_empty());
+        assert_eq!(st.len(), 1);
+    }
+
+    #[test]
+    fn test_admin_journal() {
+        let mut st = SessionTracker::new();
+        assert_eq!(handle_command(&mut st, None, "journal"), vec!["error: ingest journal not configured"]);
+
+        let config = SessionConfig { journal_len: Some(8), ..SessionConfig::default() };
+        let mut st = SessionTracker::with_config(config, Clock::Monotonic);
+        let registration = |client: &str, phantom: &str| Registration {
+            client_ip: client.to_string(),
+            phantom_ip: phantom.to_string(),
+            ..Registration::default()
+        };
+        {
+            let journal = st.journal().unwrap();
+            journal.push(JournalEntry::new(&registration("192.168.0.1", "10.10.0.1"), WallClockNs(1_000_000_000), None));
+            journal.push(JournalEntry::new(&registration("", "2001::1"), WallClockNs(2_000_000_000), None));
+            journal.push(JournalEntry::new(&registration("192.168.0.1", "nope"), WallClockNs(3_000_000_000),
+                Some("invalid registration: Invalid phantom address".to_string())));
+        }
+        assert_eq!(handle_command(&mut st, None, "journal").len(), 3);
+        let id = client_id::describe("192.168.0.1");
+        assert_eq!(handle_command(&mut st, None, "journal 192.168.0.1"), vec![
+            format!("1.000 {} -> 10.10.0.1:0 (Unspecified) accepted", id),
+            format!("3.000 {} -> nope:0 (Unspecified) rejected: invalid registration: Invalid phantom address", id),
+        ]);
+        assert_eq!(handle_command(&mut st, None, &format!("journal {}", id)).len(), 2);
+        assert_eq!(handle_command(&mut st, None, "journal \"\"").len(), 1);
+        assert!(handle_command(&mut st, None, "journal 192.168.0.2").is_empty());
+    }
+
+    #[test]
+    fn test_admin_flush() {
+        use std::sync::mpsc;
+        use ingest::ChannelFeed;
+        use protobuf::Message;
+
+        let clock = Clock::simulated(0);
+        let mut st = SessionTracker::with_clock(clock.clone());
+        for phantom in ["10.10.0.1", "10.10.7.1", "10.11.0.1"].iter() {
+            st.add_session(SessionDetails::new("192.168.0.2", phantom, 443, Duration::from_secs(100)).unwrap());
+        }
+        st.add_session(SessionDetails::new("", "2001::1", 443, Duration::from_secs(100)).unwrap());
+        st.add_session(SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_secs(100)).unwrap());
+
+        assert_eq!(handle_command(&mut st, None, "flush 10.10.0.0/24"), vec!["flushed 2 sessions"]);
+        assert_eq!(st.len(), 3);
+        assert!(handle_command(&mut st, None, "blocks").is_empty());
+        assert_eq!(handle_command(&mut st, None, "flush 10.10.0.0/16 block 60"),
+            vec!["flushed 1 sessions, 10.10.0.0/16 blocked for 60s"]);
+        assert_eq!(st.len(), 2);
+        clock.advance(10*S);
+        assert_eq!(handle_command(&mut st, None, "blocks"), vec!["10.10.0.0/16 blocked for 50s"]);
+
+        // registrations for the subnet are refused while it is blocked
+        let ingest = |st: &SessionTracker| {
+            let mut s2d = StationToDetector::new();
+            s2d.set_client_ip("192.168.0.1".to_string());
+            s2d.set_phantom_ip("10.10.3.1".to_string());
+            s2d.set_timeout_ns(100*S);
+            let (tx, rx) = mpsc::channel();
+            let thread = st.spawn_ingest_thread(ChannelFeed::new(rx));
+            tx.send(s2d.write_to_bytes().unwrap()).unwrap();
+            drop(tx);
+            thread.join().unwrap();
+        };
+        ingest(&st);
+        assert_eq!(st.len(), 2);
+        assert_eq!(st.take_phantom_blocks_refused(), 1);
+        assert_eq!(handle_command(&mut st, None, "unblock 10.10.0.0/16"), vec!["unblocked 10.10.0.0/16"]);
+        assert_eq!(handle_command(&mut st, None, "unblock 10.10.0.0/16"), vec!["error: 10.10.0.0/16 not blocked"]);
+        ingest(&st);
+        assert_eq!(st.len(), 3);
+
+        // blocks run out
+        handle_command(&mut st, None, "flush 10.10.0.0/16 block 1");
+        clock.advance(S);
+        assert!(handle_command(&mut st, None, "blocks").is_empty());
+        ingest(&st);
+        assert_eq!(st.len(), 3);
+
+        assert_eq!(handle_command(&mut st, None, "flush 10.10.0.0"), vec!["flushed 0 sessions"]);
+        assert_eq!(handle_command(&mut st, None, "flush nope"), vec!["error: bad subnet \"nope\""]);
+        assert_eq!(handle_command(&mut st, None, "flush 10.10.0.0/16 block soon"), vec!["error: bad block time \"soon\""]);
+        assert_eq!(handle_command(&mut st, None, "flush 2001::/16"), vec!["flushed 1 sessions"]);
+    }
+
+    #[test]
+    fn test_admin_socket() {
+        use std::io::{Read, Write};
+        use std::os::unix::net::UnixStream;
+
+        let path = "/tmp/dark-decoy-admin-test.sock";
+        let st = SessionTracker::new();
+        st.spawn_admin_thread(bind(path).unwrap(), None);
+        assert_eq!(fs::metadata(path).unwrap().permissions().mode() & 0o777, 0o600);
+
+        // a client that never sends its command is dropped after the read
+        // timeout, and the next one served
+        let idle = UnixStream::connect(path).unwrap();
+        let mut client = UnixStream::connect(path).unwrap();
+        client.write_all(b"drain on\n").unwrap();
+        let mut resp = String::new();
+        client.read_to_string(&mut resp).unwrap();
+        assert_eq!(resp, "draining\n");
+        assert!(st.is_draining());
+        drop(idle);
+
+        // binding again replaces the existing socket
+        drop(bind(path).unwrap());
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,57 @@
+//
+// Session Affinity
+//
+// The application runs several worker processes and wants every packet and
+// event of a session handled by the same one. Each session gets an affinity
+// hash, computed from its session map key when it is registered, that the
+// detector hands over with the session (the SessionMatched and SessionExpired
+// events carry it) so the application can pick a worker without keeping any
+// state of its own.
+//
+// The hash is 32 bit FNV-1a of the key's bytes, chosen because it is trivial
+// to compute anywhere (Go's hash/fnv New32a gives the same values), and a
+// session's worker is the hash modulo the number of workers (worker()). The
+// key depends on the detector's session settings (see SessionConfig::key), so
+// only sessions registered under the same settings hash alike.
+
+// 32 bit FNV parameters.
+const FNV_OFFSET_BASIS: u32 = 0x811c_9dc5;
+const FNV_PRIME: u32 = 0x0100_0193;
+
+/// Affinity hash of the session with map key `key`.
+pub fn affinity_hash(key: &str) -> u32 {
+    let mut hash = FNV_OFFSET_BASIS;
+    for b in key.bytes() {
+        hash ^= u32::from(b);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+/// The worker, out of `workers`, sessions with affinity hash `hash` go to.
+pub fn worker(hash: u32, workers: u32) -> u32 {
+    match workers {
+        0 => 0,
+        n => hash % n,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use affinity::*;
+
+    #[test]
+    fn test_affinity_hash() {
+        // FNV-1a reference values
+        assert_eq!(affinity_hash(""), 0x811c_9dc5);
+        assert_eq!(affinity_hash("a"), 0xe40c_292c);
+        assert_eq!(affinity_hash("foobar"), 0xbf9c_f968);
+
+        let key = "192.168.0.1-10.10.0.1-443";
+        assert_eq!(affinity_hash(key), affinity_hash(&key.to_string()));
+        assert_ne!(affinity_hash(key), affinity_hash("192.168.0.1-10.10.0.1-80"));
+        assert!(worker(affinity_hash(key), 4) < 4);
+        assert_eq!(worker(7, 4), 3);
+        assert_eq!(worker(7, 0), 0);
+    }
+}
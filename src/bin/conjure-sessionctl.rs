@@ -0,0 +1,290 @@
+//
+// conjure-sessionctl
+//
+// Publishes hand-made registrations to a detector and checks what it made of
+// them, for testing without a station.
+//
+//   conjure-sessionctl add --phantom <ip> [--client <ip>] [fields...]
+//   conjure-sessionctl update ...    (same options as add)
+//   conjure-sessionctl query --phantom <ip> [--client <ip>] [--port <port>]
+//   conjure-sessionctl delete --phantom <ip> [--client <ip>] [--port <port>]
+//
+// add publishes a StationToDetector on the detector's registration channel,
+// the way the application does. Every StationToDetector field can be set:
+//
+//   --client <ip>            client_ip (may be omitted for v6 phantoms)
+//   --phantom <ip>           phantom_ip
+//   --phantom-v6 <ip>        phantom_ip_v6, for a dual-stack registration
+//   --port <port>            phantom_port (default ports if omitted)
+//   --timeout-ms <ms>        timeout_ns (default 5 minutes)
+//   --source <name>          registration_source, e.g. API
+//   --max-conns <n>          max_connections
+//   --expires-in-ms <ms>     expires_at_unix_ns, this long from now
+//   --stamp                  station_time_unix_ns, set to now
+//   --redis <url>            redis server (default the local one)
+//   --channel <name>         channel (default the registration channel)
+//   --control <addr:port>    send to the detector's control listener instead
+//                            of redis (see control.rs), to each of --cores
+//                            ports for a unicast address
+//   --control-key <file>     hex key shared with the control listener
+//
+// Registrations only reach the detector over redis or its control listener,
+// so there is no ZMQ option. Publishing a registration again with add extends
+// the session's lifetime if the new one is longer and counts the extra
+// registration. update publishes it with update_expiry set instead: the
+// detector resets the expiry of the session, if tracked, to the new lifetime
+// from now, and creates none. StationToDetector has no delete, so query and delete go to
+// the admin socket of each detector core (--cores <n>, default 1) instead, see
+// admin.rs. --admin-socket <path> queries the socket at <path> instead.
+
+extern crate hex;
+extern crate protobuf;
+extern crate redis;
+extern crate rust_dark_decoy;
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, UdpSocket};
+use std::os::unix::net::UnixStream;
+use std::process;
+
+use protobuf::{Message, ProtobufEnum};
+
+use rust_dark_decoy::admin::admin_socket_path;
+use rust_dark_decoy::control::seal_message;
+use rust_dark_decoy::error::error_chain;
+use rust_dark_decoy::ingest::{REDIS_URL, REDIS_CHANNEL, get_redis_conn};
+use rust_dark_decoy::signalling::{RegistrationSource, StationToDetector};
+use rust_dark_decoy::timekeeping::WallClockNs;
+
+const DEFAULT_TIMEOUT_MS: u64 = 5*60*1000;
+
+// Options taking a value, and flags.
+const VALUE_OPTIONS: [&'static str; 14] = ["client", "phantom", "phantom-v6", "port", "timeout-ms",
+    "source", "max-conns", "expires-in-ms", "redis", "channel", "cores", "admin-socket",
+    "control", "control-key"];
+const FLAGS: [&'static str; 1] = ["stamp"];
+
+const USAGE: &'static str = "usage: conjure-sessionctl add|update|query|delete --phantom <ip> [--client <ip>] [options]";
+
+struct Options
+{
+    values: HashMap<String, String>,
+    flags: Vec<String>,
+}
+
+impl Options
+{
+    fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(|v| v.as_str())
+    }
+
+    fn number<T: std::str::FromStr>(&self, name: &str) -> Result<Option<T>, String> {
+        match self.get(name) {
+            Some(v) => v.parse().map(Some).map_err(|_| format!("bad --{} {:?}", name, v)),
+            None => Ok(None),
+        }
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|f| f == name)
+    }
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut opts = Options { values: HashMap::new(), flags: Vec::new() };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let name = match arg.starts_with("--") {
+            true => &arg[2..],
+            false => return Err(format!("unexpected argument {:?}", arg)),
+        };
+        if FLAGS.contains(&name) {
+            opts.flags.push(name.to_string());
+        } else if VALUE_OPTIONS.contains(&name) {
+            let value = args.next().ok_or(format!("--{} needs a value", name))?;
+            opts.values.insert(name.to_string(), value.to_string());
+        } else {
+            return Err(format!("unknown option --{}", name))
+        }
+    }
+    if opts.get("phantom").is_none() {
+        return Err("--phantom is required".to_string())
+    }
+    Ok(opts)
+}
+
+fn registration(opts: &Options, now: WallClockNs) -> Result<StationToDetector, String> {
+    let mut s2d = StationToDetector::new();
+    s2d.set_phantom_ip(opts.get("phantom").unwrap_or("").to_string());
+    s2d.set_client_ip(opts.get("client").unwrap_or("").to_string());
+    if let Some(v6) = opts.get("phantom-v6") {
+        s2d.set_phantom_ip_v6(v6.to_string());
+    }
+    if let Some(port) = opts.number::<u16>("port")? {
+        s2d.set_phantom_port(port as u32);
+    }
+    let timeout_ms = opts.number::<u64>("timeout-ms")?.unwrap_or(DEFAULT_TIMEOUT_MS);
+    s2d.set_timeout_ns(timeout_ms.saturating_mul(1000*1000));
+    if let Some(name) = opts.get("source") {
+        let source = RegistrationSource::values().iter()
+            .find(|s| format!("{:?}", s).eq_ignore_ascii_case(name))
+            .ok_or(format!("unknown --source {:?}", name))?;
+        s2d.set_registration_source(*source);
+    }
+    if let Some(n) = opts.number("max-conns")? {
+        s2d.set_max_connections(n);
+    }
+    if let Some(ms) = opts.number::<u64>("expires-in-ms")? {
+        s2d.set_expires_at_unix_ns(now.saturating_add(ms.saturating_mul(1000*1000)).0);
+    }
+    if opts.flag("stamp") {
+        s2d.set_station_time_unix_ns(now.0);
+    }
+    Ok(s2d)
+}
+
+// Returns the number of subscribers that received the registration.
+fn publish(opts: &Options, s2d: &StationToDetector) -> Result<i64, String> {
+    let url = opts.get("redis").unwrap_or(REDIS_URL);
+    let channel = opts.get("channel").unwrap_or(REDIS_CHANNEL);
+    let payload = s2d.write_to_bytes().map_err(|e| format!("can't serialize registration: {}", e))?;
+    let con = get_redis_conn(url).map_err(|e| error_chain(&e))?;
+    redis::cmd("PUBLISH").arg(channel).arg(payload).query(&con)
+        .map_err(|e| format!("redis publish to {} failed: {}", channel, e))
+}
+
+// Sends the registration to the control listener at `addr`, once per core
+// for a unicast address. Returns the number of datagrams sent.
+fn send_control(opts: &Options, addr: &str, s2d: &StationToDetector) -> Result<i64, String> {
+    let addr: SocketAddr = addr.parse().map_err(|_| format!("bad --control address {}", addr))?;
+    let key_path = opts.get("control-key").ok_or("--control needs --control-key")?;
+    let key = fs::read_to_string(key_path).map_err(|e| format!("{}: {}", key_path, e))
+        .and_then(|s| hex::decode(s.trim()).map_err(|e| format!("{}: {}", key_path, e)))?;
+    let payload = s2d.write_to_bytes().map_err(|e| format!("can't serialize registration: {}", e))?;
+    let msg = seal_message(&key, &payload);
+
+    let cores = if addr.ip().is_multicast() { 1 } else { opts.number::<u16>("cores")?.unwrap_or(1) };
+    let local = if addr.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+    let socket = UdpSocket::bind(local).map_err(|e| e.to_string())?;
+    for core in 0..cores {
+        let mut to = addr;
+        to.set_port(addr.port() + core);
+        socket.send_to(&msg, to).map_err(|e| format!("send to {} failed: {}", to, e))?;
+    }
+    Ok(cores as i64)
+}
+
+// Admin command line for a query or delete.
+fn admin_command(command: &str, opts: &Options) -> String {
+    let client = match opts.get("client") {
+        Some(c) => c,
+        None => "\"\"",
+    };
+    let mut line = format!("{} {} {}", command, client, opts.get("phantom").unwrap_or(""));
+    if let Some(port) = opts.get("port") {
+        line.push_str(&format!(" {}", port));
+    }
+    line
+}
+
+fn admin(path: &str, line: &str) -> io::Result<Vec<String>> {
+    let mut stream = UnixStream::connect(path)?;
+    writeln!(stream, "{}", line)?;
+    BufReader::new(stream).lines().collect()
+}
+
+fn run(command: &str, opts: &Options) -> Result<(), String> {
+    match command {
+        "add" | "update" => {
+            let mut s2d = registration(opts, WallClockNs::now())?;
+            s2d.set_update_expiry(command == "update");
+            if let Some(addr) = opts.get("control") {
+                println!("sent {} control messages", send_control(opts, addr, &s2d)?);
+                return Ok(())
+            }
+            let receivers = publish(opts, &s2d)?;
+            println!("published to {} subscribers", receivers);
+            if receivers == 0 {
+                return Err("no detector is subscribed".to_string())
+            }
+        },
+        "query" | "delete" => {
+            let line = admin_command(match command { "query" => "session", _ => "remove" }, opts);
+            let paths: Vec<String> = match opts.get("admin-socket") {
+                Some(p) => vec![p.to_string()],
+                None => (0..opts.number::<i32>("cores")?.unwrap_or(1)).map(admin_socket_path).collect(),
+            };
+            for path in paths.iter() {
+                let lines = admin(path, &line).map_err(|e| format!("{}: {}", path, e))?;
+                if lines.is_empty() {
+                    println!("{}: not tracked", path);
+                }
+                for l in lines {
+                    println!("{}: {}", path, l);
+                }
+            }
+        },
+        _ => return Err(USAGE.to_string()),
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    }
+    let opts = match parse_options(&args[1..]) {
+        Ok(o) => o,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            process::exit(2);
+        },
+    };
+    if let Err(e) = run(&args[0], &opts) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opts(args: &[&str]) -> Result<Options, String> {
+        parse_options(&args.iter().map(|a| a.to_string()).collect::<Vec<String>>())
+    }
+
+    #[test]
+    fn test_sessionctl_registration() {
+        let o = opts(&["--client", "192.168.0.1", "--phantom", "10.10.0.1", "--phantom-v6", "2001::1",
+                       "--port", "8443", "--source", "api", "--max-conns", "2",
+                       "--expires-in-ms", "1000", "--stamp"]).unwrap();
+        let s2d = registration(&o, WallClockNs(5000)).unwrap();
+        assert_eq!(s2d.get_client_ip(), "192.168.0.1");
+        assert_eq!(s2d.get_phantom_ip(), "10.10.0.1");
+        assert_eq!(s2d.get_phantom_ip_v6(), "2001::1");
+        assert_eq!(s2d.get_phantom_port(), 8443);
+        assert_eq!(s2d.get_timeout_ns(), DEFAULT_TIMEOUT_MS * 1000*1000);
+        assert_eq!(s2d.get_registration_source(), RegistrationSource::API);
+        assert_eq!(s2d.get_max_connections(), 2);
+        assert_eq!(s2d.get_expires_at_unix_ns(), 5000 + 1000*1000*1000);
+        assert_eq!(s2d.get_station_time_unix_ns(), 5000);
+        assert_eq!(admin_command("session", &o), "session 192.168.0.1 10.10.0.1 8443");
+
+        let o = opts(&["--phantom", "2001::1"]).unwrap();
+        let s2d = registration(&o, WallClockNs(5000)).unwrap();
+        assert!(!s2d.has_phantom_port() && !s2d.has_station_time_unix_ns());
+        assert_eq!(admin_command("remove", &o), "remove \"\" 2001::1");
+
+        assert!(opts(&["--client", "192.168.0.1"]).is_err());
+        assert!(opts(&["--phantom"]).is_err());
+        assert!(opts(&["--phantom", "10.10.0.1", "--colour", "red"]).is_err());
+        assert!(registration(&opts(&["--phantom", "10.10.0.1", "--port", "70000"]).unwrap(), WallClockNs(0)).is_err());
+        assert!(registration(&opts(&["--phantom", "10.10.0.1", "--source", "mail"]).unwrap(), WallClockNs(0)).is_err());
+    }
+}
@@ -0,0 +1,159 @@
+//
+// Adaptive Cleanup Interval
+//
+// A fixed maintenance interval either wastes passes over a small, quiet
+// session map or lets expired sessions pile up in a large, busy one. With
+// MaintenanceConfig.adaptive set, the maintenance thread instead picks the
+// time to its next pass after each one, from the size of the map and the
+// rate sessions have been expiring at:
+//
+// - The rate is the sessions dropped per second of tracker clock, smoothed
+//   over passes (RATE_SMOOTHING) so one burst doesn't swing the interval.
+// - Expired sessions are allowed to make up at most max_stale_percent of the
+//   map (and at least one session) between passes, so the interval is that
+//   allowance over the rate: big or quiet maps are visited rarely, small
+//   maps with churn often.
+// - A pass that hit max_drops_per_tick left expired sessions behind, so the
+//   next one comes after the shortest interval.
+// - The interval is kept within [MaintenanceConfig.interval, adaptive.max];
+//   with nothing expiring it is the longest.
+//
+// The interval chosen is published as a gauge (CleanupGauge) for the periodic
+// report, with the rate and passes it was chosen over.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+// Weight of the newest pass in the smoothed expiration rate.
+const RATE_SMOOTHING: f64 = 0.25;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AdaptiveConfig
+{
+    // Longest interval between passes.
+    pub max: Duration,
+    // Share of the map, in percent, expired sessions may make up between
+    // passes.
+    pub max_stale_percent: f64,
+}
+
+impl Default for AdaptiveConfig
+{
+    fn default() -> AdaptiveConfig {
+        AdaptiveConfig { max: Duration::from_secs(10), max_stale_percent: 1.0 }
+    }
+}
+
+// Interval last chosen, shared with the periodic report.
+pub struct CleanupGauge
+{
+    interval_ns: AtomicU64,
+    // Smoothed expiration rate, in sessions per 1000 s.
+    rate_milli: AtomicU64,
+    passes: AtomicU64,
+}
+
+impl CleanupGauge
+{
+    pub fn new(interval: Duration) -> CleanupGauge {
+        CleanupGauge {
+            interval_ns: AtomicU64::new(interval.as_nanos() as u64),
+            rate_milli: AtomicU64::new(0),
+            passes: AtomicU64::new(0),
+        }
+    }
+
+    /// Interval the next pass comes after.
+    pub fn interval(&self) -> Duration {
+        Duration::from_nanos(self.interval_ns.load(Ordering::Relaxed))
+    }
+
+    /// Current interval and rate, and passes since the last call, formatted
+    /// for the periodic report.
+    pub fn take_report(&self) -> String {
+        format!("session cleanup interval {}ms expiring {:.1}/s passes {}",
+            self.interval().as_millis(),
+            self.rate_milli.load(Ordering::Relaxed) as f64 / 1000.0,
+            self.passes.swap(0, Ordering::Relaxed))
+    }
+}
+
+// Chooses the maintenance thread's intervals.
+pub struct AdaptiveInterval
+{
+    config: AdaptiveConfig,
+    min: Duration,
+    // Smoothed sessions dropped per second, None before the first pass.
+    rate: Option<f64>,
+    gauge: Arc<CleanupGauge>,
+}
+
+impl AdaptiveInterval
+{
+    /// Intervals between `min` and `config.max`, starting at `min`.
+    pub fn new(min: Duration, config: &AdaptiveConfig, gauge: Arc<CleanupGauge>) -> AdaptiveInterval {
+        gauge.interval_ns.store(min.as_nanos() as u64, Ordering::Relaxed);
+        AdaptiveInterval { config: config.clone(), min: min, rate: None, gauge: gauge }
+    }
+
+    /// Interval to the next pass, after one that dropped `dropped` sessions
+    /// (and hit the drop cap if `capped`), `elapsed` after the previous one,
+    /// leaving `sessions` in the map.
+    pub fn next(&mut self, sessions: usize, dropped: usize, capped: bool, elapsed: Duration) -> Duration {
+        let secs = elapsed.as_secs_f64();
+        if secs > 0.0 {
+            let observed = dropped as f64 / secs;
+            self.rate = Some(match self.rate {
+                Some(rate) => rate + RATE_SMOOTHING * (observed - rate),
+                None => observed,
+            });
+        }
+        let max = self.config.max.max(self.min);
+        let rate = self.rate.unwrap_or(0.0);
+        let interval = if capped {
+            self.min
+        } else if rate <= 0.0 {
+            max
+        } else {
+            let allowed = (sessions as f64 * self.config.max_stale_percent / 100.0).max(1.0);
+            Duration::from_secs_f64((allowed / rate).min(max.as_secs_f64())).max(self.min)
+        };
+        self.gauge.interval_ns.store(interval.as_nanos() as u64, Ordering::Relaxed);
+        self.gauge.rate_milli.store((rate * 1000.0) as u64, Ordering::Relaxed);
+        self.gauge.passes.fetch_add(1, Ordering::Relaxed);
+        interval
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cleanup_interval::*;
+
+    #[test]
+    fn test_adaptive_interval() {
+        let config = AdaptiveConfig { max: Duration::from_secs(10), max_stale_percent: 1.0 };
+        let gauge = Arc::new(CleanupGauge::new(Duration::from_secs(1)));
+        let mut adaptive = AdaptiveInterval::new(Duration::from_millis(100), &config, Arc::clone(&gauge));
+        assert_eq!(gauge.interval(), Duration::from_millis(100));
+
+        // nothing expiring: the longest interval
+        assert_eq!(adaptive.next(1000, 0, false, Duration::from_secs(1)), Duration::from_secs(10));
+
+        // 100k sessions, 1000 expiring a second: 1000 allowed stale, 1s
+        let mut a = AdaptiveInterval::new(Duration::from_millis(100), &config, Arc::clone(&gauge));
+        assert_eq!(a.next(100000, 1000, false, Duration::from_secs(1)), Duration::from_secs(1));
+        // a smaller map with the same churn is visited more often
+        assert_eq!(a.next(20000, 1000, false, Duration::from_secs(1)), Duration::from_millis(200));
+        // and never more often than the shortest interval
+        assert_eq!(a.next(1000, 1000, false, Duration::from_secs(1)), Duration::from_millis(100));
+        // a burst moves the rate by a quarter of the difference only
+        assert_eq!(a.next(100000, 5000, false, Duration::from_secs(1)), Duration::from_millis(500));
+        // a capped pass left sessions behind
+        assert_eq!(a.next(100000, 10, true, Duration::from_secs(1)), Duration::from_millis(100));
+
+        assert_eq!(gauge.interval(), Duration::from_millis(100));
+        assert!(gauge.take_report().starts_with("session cleanup interval 100ms expiring "));
+        assert!(gauge.take_report().ends_with("passes 0"));
+    }
+}
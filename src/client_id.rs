@@ -0,0 +1,196 @@
+//
+// Client Identifiers
+//
+// Raw client addresses must never be written to disk. Everything the detector
+// logs, exports or reports identifies a client by its ClientId instead: a
+// keyed hash of the address, printed as
+//
+//   c-<16 hex digits>
+//
+// The hash is the first 8 bytes of HMAC-SHA256(day key, address octets),
+// where the day key is HMAC-SHA256(key, "client-id" || UTC day number) so ids
+// change every day at midnight UTC without a restart: the same client has the
+// same id in every output of a day, and ids of different days can't be
+// linked without the key. The key comes from DETECTOR_CLIENT_ID_KEY_FILE,
+// shared by every core; without one each core draws a random key at startup
+// and ids are only comparable within one core's logs.
+//
+// The address itself is kept in the ClientId for the in-memory matching code
+// (ClientId::addr) and never formatted.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::{Once, RwLock};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use hex;
+use rand::{self, Rng};
+
+use control::hmac_sha256;
+use timekeeping::WallClockNs;
+
+const DAY_NS: u64 = 24*60*60*1000*1000*1000;
+
+// Key of the process, None until set or first used.
+static KEY_INIT: Once = Once::new();
+static mut KEY: Option<RwLock<Option<Vec<u8>>>> = None;
+// Bumped by set_key so threads drop their cached day keys.
+static KEY_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+fn process_key() -> &'static RwLock<Option<Vec<u8>>> {
+    unsafe {
+        KEY_INIT.call_once(|| { KEY = Some(RwLock::new(None)); });
+        KEY.as_ref().expect("client id key lock not initialized")
+    }
+}
+
+thread_local! {
+    // (key generation, day, day key) last used on this thread.
+    static DAY_KEY: RefCell<Option<(usize, u64, [u8; 32])>> = RefCell::new(None);
+}
+
+/// Uses `key` for the ids formatted from now on, in every thread.
+pub fn set_key(key: &[u8]) {
+    *process_key().write().unwrap_or_else(|e| e.into_inner()) = Some(key.to_vec());
+    KEY_GENERATION.fetch_add(1, Ordering::AcqRel);
+}
+
+/// Key of the ids of UTC day `day` (days since the unix epoch) under `key`.
+pub fn day_key(key: &[u8], day: u64) -> [u8; 32] {
+    let mut msg = b"client-id".to_vec();
+    msg.extend_from_slice(&day.to_be_bytes());
+    hmac_sha256(key, &msg)
+}
+
+/// Id of `addr` under `day_key`.
+pub fn hash(day_key: &[u8], addr: &IpAddr) -> u64 {
+    let octets = match *addr {
+        IpAddr::V4(ip) => ip.octets().to_vec(),
+        IpAddr::V6(ip) => ip.octets().to_vec(),
+    };
+    let mac = hmac_sha256(day_key, &octets);
+    u64::from_be_bytes(*array_ref![mac, 0, 8])
+}
+
+// The process's key, drawing a random one if none was set.
+fn current_key() -> Vec<u8> {
+    if let Some(ref key) = *process_key().read().unwrap_or_else(|e| e.into_inner()) {
+        return key.clone()
+    }
+    let mut key = process_key().write().unwrap_or_else(|e| e.into_inner());
+    key.get_or_insert_with(|| rand::thread_rng().gen::<[u8; 32]>().to_vec()).clone()
+}
+
+// Day key of `day` under the process's key, cached per thread.
+fn current_day_key(day: u64) -> [u8; 32] {
+    let generation = KEY_GENERATION.load(Ordering::Acquire);
+    DAY_KEY.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        match *cache {
+            Some((g, d, k)) if g == generation && d == day => k,
+            _ => {
+                let k = day_key(&current_key(), day);
+                *cache = Some((generation, day, k));
+                k
+            },
+        }
+    })
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ClientId
+{
+    addr: IpAddr,
+}
+
+impl ClientId
+{
+    pub fn new(addr: IpAddr) -> ClientId {
+        ClientId { addr: addr }
+    }
+
+    /// The client's address, for matching only. Format the ClientId instead
+    /// of this.
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    /// Today's id.
+    pub fn hash(&self) -> u64 {
+        self.hash_at(WallClockNs::now())
+    }
+
+    /// The id on the UTC day of `now`.
+    pub fn hash_at(&self, now: WallClockNs) -> u64 {
+        hash(&current_day_key(now.0 / DAY_NS), &self.addr)
+    }
+}
+
+impl fmt::Display for ClientId
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "c-{:016x}", self.hash())
+    }
+}
+
+// Debug is formatted like Display so {:?} doesn't leak the address either.
+impl fmt::Debug for ClientId
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// A client address as sent in a registration, for output: its ClientId if it
+/// parses, "" if empty (v6 registrations without a client).
+pub fn describe(client: &str) -> String {
+    match client.parse() {
+        Ok(addr) => ClientId::new(addr).to_string(),
+        Err(_) if client.is_empty() => "\"\"".to_string(),
+        Err(_) => "unparseable".to_string(),
+    }
+}
+
+/// The key in a DETECTOR_CLIENT_ID_KEY_FILE (hex).
+pub fn parse_key(contents: &str) -> Option<Vec<u8>> {
+    match hex::decode(contents.trim()) {
+        Ok(ref key) if !key.is_empty() => Some(key.clone()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use client_id::*;
+
+    #[test]
+    fn test_client_id_hash() {
+        let a: IpAddr = "192.168.0.1".parse().unwrap();
+        let b: IpAddr = "2601::abcd:ef00".parse().unwrap();
+        let today = day_key(b"key", 19000);
+        assert_eq!(hash(&today, &a), hash(&day_key(b"key", 19000), &a));
+        assert!(hash(&today, &a) != hash(&today, &b));
+        // other days and other keys give other ids
+        assert!(hash(&today, &a) != hash(&day_key(b"key", 19001), &a));
+        assert!(hash(&today, &a) != hash(&day_key(b"other key", 19000), &a));
+
+        // the formatted id never includes the address
+        let id = ClientId::new(a);
+        let s = id.to_string();
+        assert_eq!(s.len(), 18);
+        assert!(s.starts_with("c-"));
+        assert_eq!(format!("{:?}", id), s);
+        assert_eq!(id.addr(), a);
+        assert_eq!(id.hash_at(WallClockNs(5)), id.hash_at(WallClockNs(DAY_NS - 1)));
+        assert!(id.hash_at(WallClockNs(5)) != id.hash_at(WallClockNs(DAY_NS)));
+
+        assert_eq!(describe("192.168.0.1"), s);
+        assert_eq!(describe(""), "\"\"");
+        assert_eq!(describe("192.168.0.1 "), "unparseable");
+
+        assert_eq!(parse_key(" 00ff\n"), Some(vec![0, 255]));
+        assert_eq!(parse_key(""), None);
+        assert_eq!(parse_key("xyz"), None);
+    }
+}
@@ -0,0 +1,433 @@
+//
+// Station Configuration Push
+//
+// A few settings are controlled centrally by the station rather than per
+// detector: the lifetime of registrations that don't carry one, the
+// transports registrations may be for, and client and phantom blocklists. The
+// station sends them as a ConfigPush inside a StationToDetector on the
+// registration channel; a message carrying a push carries no registration.
+// With DETECTOR_ACCEPT_CONFIG_PUSH=true each core's ingest threads:
+//
+//  - validate the push as a whole: its epoch must be newer than the one
+//    applied and every blocklist entry must parse. An invalid push is
+//    rejected and the configuration in effect is kept;
+//  - apply a valid push by swapping in the new configuration at once, so each
+//    registration is checked against either the old or the new one, never a
+//    mix of both;
+//  - acknowledge the push with a ConfigAck DetectorToStation event carrying
+//    its epoch, and the reason if it was rejected.
+//
+// The station also pushes the phantom subnets in use, as a PhantomSubnetSet,
+// whenever its phantom pools change. A set is validated (newer generation,
+// every subnet parses, not empty), swapped in whole and acknowledged with a
+// PhantomSubnetsAck carrying its generation the same way. Once a set is in
+// effect:
+//
+//  - registrations for phantoms outside it are refused;
+//  - each core's packet thread picks it up within a cleanup pass and uses it
+//    for the per subnet stats and never matched summaries (phantom_subnets.rs)
+//    and, on the core that owns it, the XDP pre-filter (xdp.rs), instead of
+//    the subnets loaded from PHANTOM_SUBNET_LOCATION at startup.
+//
+// Once per report period each core also sends a Heartbeat event with the
+// epoch and phantom subnet generation in effect (0 before the first push), so
+// the station can tell which detectors missed a push.
+//
+// A pushed configuration only applies to registrations received after it;
+// sessions already tracked are not checked again. Without
+// DETECTOR_ACCEPT_CONFIG_PUSH pushes are ignored.
+
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use arc_swap::ArcSwap;
+use ipnetwork::IpNetwork;
+use thiserror::Error;
+
+use feedback::{EventPublisher, DETECTOR_EVENT_CHANNEL};
+use ingest::REDIS_URL;
+use sessions::SessionDetails;
+use signalling::{ConfigPush, DetectorEvent, DetectorToStation, PhantomSubnetSet, TransportType};
+
+// Configuration from the last push applied.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PushedConfig
+{
+    pub epoch: u64,
+    // Lifetime (ns) of registrations that don't carry one, 0 for none.
+    pub default_timeout_ns: u64,
+    // Empty accepts every transport.
+    pub allowed_transports: Vec<TransportType>,
+    pub client_blocklist: Vec<IpNetwork>,
+    pub phantom_blocklist: Vec<IpNetwork>,
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ConfigPushError {
+    #[error("epoch {0} is not newer than {1}")]
+    Stale(u64, u64),
+    #[error("invalid blocklist entry {0:?}")]
+    InvalidNetwork(String),
+    #[error("generation {0} is not newer than {1}")]
+    StaleGeneration(u64, u64),
+    #[error("invalid phantom subnet {0:?}")]
+    InvalidSubnet(String),
+    #[error("no phantom subnets")]
+    NoSubnets,
+}
+
+impl PushedConfig
+{
+    /// Validates `push`, returning the configuration it describes.
+    pub fn from_push(push: &ConfigPush) -> Result<PushedConfig, ConfigPushError> {
+        Ok(PushedConfig {
+            epoch: push.get_epoch(),
+            default_timeout_ns: push.get_default_timeout_ns(),
+            allowed_transports: push.get_allowed_transports().to_vec(),
+            client_blocklist: parse_networks(push.get_client_blocklist())?,
+            phantom_blocklist: parse_networks(push.get_phantom_blocklist())?,
+        })
+    }
+
+    /// Why the registration session `sd` is refused, None if it is accepted.
+    pub fn refuses(&self, sd: &SessionDetails) -> Option<&'static str> {
+        if !self.allowed_transports.is_empty() && !self.allowed_transports.contains(&sd.transport) {
+            return Some("transport not allowed")
+        }
+        if self.client_blocklist.iter().any(|net| net.contains(sd.client_ip)) {
+            return Some("client blocklisted")
+        }
+        if self.phantom_blocklist.iter().any(|net| net.contains(sd.phantom_ip)) {
+            return Some("phantom blocklisted")
+        }
+        None
+    }
+}
+
+// Phantom subnets from the last set applied.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PushedSubnets
+{
+    // 0 before the first set.
+    pub generation: u64,
+    pub subnets: Vec<IpNetwork>,
+}
+
+impl PushedSubnets
+{
+    /// Validates `set`, returning the subnets it holds.
+    pub fn from_push(set: &PhantomSubnetSet) -> Result<PushedSubnets, ConfigPushError> {
+        let subnets = set.get_subnets().iter()
+            .map(|n| n.parse().map_err(|_| ConfigPushError::InvalidSubnet(n.clone())))
+            .collect::<Result<Vec<IpNetwork>, ConfigPushError>>()?;
+        if subnets.is_empty() {
+            return Err(ConfigPushError::NoSubnets)
+        }
+        Ok(PushedSubnets { generation: set.get_generation(), subnets: subnets })
+    }
+
+    /// Whether `phantom` may be registered: any address before a set is
+    /// pushed, only those in its subnets after.
+    pub fn allows(&self, phantom: &IpAddr) -> bool {
+        self.generation == 0 || self.subnets.iter().any(|net| net.contains(*phantom))
+    }
+}
+
+fn parse_networks(nets: &[String]) -> Result<Vec<IpNetwork>, ConfigPushError> {
+    nets.iter()
+        .map(|n| n.parse().map_err(|_| ConfigPushError::InvalidNetwork(n.clone())))
+        .collect()
+}
+
+pub struct StationConfig
+{
+    current: ArcSwap<PushedConfig>,
+    subnets: ArcSwap<PushedSubnets>,
+    // Held while a push is validated against the current epoch (or
+    // generation) and applied.
+    apply_lock: Mutex<()>,
+    // None discards acknowledgments.
+    publisher: Option<Mutex<EventPublisher>>,
+
+    // Counted since the last take_report.
+    rejected_pushes: AtomicU64,
+    refused: AtomicU64,
+    rejected_subnets: AtomicU64,
+    refused_phantoms: AtomicU64,
+}
+
+impl StationConfig
+{
+    /// Configuration acknowledging pushes on the detector event channel.
+    pub fn spawn() -> StationConfig {
+        StationConfig::new(Some(EventPublisher::spawn(REDIS_URL, DETECTOR_EVENT_CHANNEL)))
+    }
+
+    pub fn new(publisher: Option<EventPublisher>) -> StationConfig {
+        StationConfig {
+            current: ArcSwap::from_pointee(PushedConfig::default()),
+            subnets: ArcSwap::from_pointee(PushedSubnets::default()),
+            apply_lock: Mutex::new(()),
+            publisher: publisher.map(Mutex::new),
+            rejected_pushes: AtomicU64::new(0),
+            refused: AtomicU64::new(0),
+            rejected_subnets: AtomicU64::new(0),
+            refused_phantoms: AtomicU64::new(0),
+        }
+    }
+
+    /// The configuration in effect.
+    pub fn current(&self) -> Arc<PushedConfig> {
+        self.current.load_full()
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.current.load().epoch
+    }
+
+    /// Validates and applies `push`, and acknowledges it to the station.
+    pub fn apply(&self, push: &ConfigPush) -> Result<(), ConfigPushError> {
+        let result = {
+            let _guard = self.apply_lock.lock().unwrap_or_else(|e| e.into_inner());
+            let applied = self.epoch();
+            PushedConfig::from_push(push).and_then(|config| {
+                if config.epoch <= applied {
+                    return Err(ConfigPushError::Stale(config.epoch, applied))
+                }
+                self.current.store(Arc::new(config));
+                Ok(())
+            })
+        };
+        if result.is_err() {
+            self.rejected_pushes.fetch_add(1, Ordering::Relaxed);
+        }
+        self.publish(&ack_event(push.get_epoch(), &result));
+        result
+    }
+
+    /// The phantom subnets in effect.
+    pub fn phantom_subnets(&self) -> Arc<PushedSubnets> {
+        self.subnets.load_full()
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.subnets.load().generation
+    }
+
+    /// Validates and applies the phantom subnets `set`, and acknowledges it
+    /// to the station.
+    pub fn apply_subnets(&self, set: &PhantomSubnetSet) -> Result<(), ConfigPushError> {
+        let result = {
+            let _guard = self.apply_lock.lock().unwrap_or_else(|e| e.into_inner());
+            let applied = self.generation();
+            PushedSubnets::from_push(set).and_then(|subnets| {
+                if subnets.generation <= applied {
+                    return Err(ConfigPushError::StaleGeneration(subnets.generation, applied))
+                }
+                self.subnets.store(Arc::new(subnets));
+                Ok(())
+            })
+        };
+        if result.is_err() {
+            self.rejected_subnets.fetch_add(1, Ordering::Relaxed);
+        }
+        self.publish(&subnets_ack_event(set.get_generation(), &result));
+        result
+    }
+
+    fn publish(&self, event: &DetectorToStation) {
+        if let Some(ref publisher) = self.publisher {
+            publisher.lock().unwrap_or_else(|e| e.into_inner()).publish(event);
+        }
+    }
+
+    /// Counts a registration refused by the configuration in effect.
+    pub fn count_refused(&self) {
+        self.refused.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a registration refused for a phantom outside the phantom
+    /// subnets in effect.
+    pub fn count_refused_phantom(&self) {
+        self.refused_phantoms.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Heartbeat event carrying the epoch in effect.
+    pub fn heartbeat(&self) -> DetectorToStation {
+        let mut event = DetectorToStation::new();
+        event.set_event(DetectorEvent::Heartbeat);
+        event.set_config_epoch(self.epoch());
+        event.set_phantom_subnets_generation(self.generation());
+        event
+    }
+
+    /// Epoch in effect, and pushes rejected and registrations refused since
+    /// the last call, formatted for the periodic report.
+    pub fn take_report(&self) -> String {
+        format!("station config epoch {} rejected pushes {} refused registrations {}",
+            self.epoch(),
+            self.rejected_pushes.swap(0, Ordering::Relaxed),
+            self.refused.swap(0, Ordering::Relaxed))
+    }
+
+    /// Phantom subnet generation in effect, and sets rejected and
+    /// registrations refused for their phantom since the last call.
+    pub fn take_subnets_report(&self) -> String {
+        let subnets = self.phantom_subnets();
+        format!("phantom subnets generation {} subnets {} rejected pushes {} refused registrations {}",
+            subnets.generation,
+            subnets.subnets.len(),
+            self.rejected_subnets.swap(0, Ordering::Relaxed),
+            self.refused_phantoms.swap(0, Ordering::Relaxed))
+    }
+}
+
+fn ack_event(epoch: u64, result: &Result<(), ConfigPushError>) -> DetectorToStation {
+    let mut event = DetectorToStation::new();
+    event.set_event(DetectorEvent::ConfigAck);
+    event.set_config_epoch(epoch);
+    if let Err(ref e) = *result {
+        event.set_config_error(e.to_string());
+    }
+    event
+}
+
+fn subnets_ack_event(generation: u64, result: &Result<(), ConfigPushError>) -> DetectorToStation {
+    let mut event = DetectorToStation::new();
+    event.set_event(DetectorEvent::PhantomSubnetsAck);
+    event.set_phantom_subnets_generation(generation);
+    if let Err(ref e) = *result {
+        event.set_config_error(e.to_string());
+    }
+    event
+}
+
+#[cfg(test)]
+mod tests {
+    use config_push::*;
+    use protobuf::Message;
+    use std::sync::mpsc::sync_channel;
+    use std::time::Duration;
+
+    fn push(epoch: u64, clients: &[&str]) -> ConfigPush {
+        let mut push = ConfigPush::new();
+        push.set_epoch(epoch);
+        push.set_default_timeout_ns(30*1000*1000*1000);
+        push.set_allowed_transports(vec![TransportType::Min]);
+        for c in clients {
+            push.mut_client_blocklist().push(c.to_string());
+        }
+        push.mut_phantom_blocklist().push("10.99.0.0/16".to_string());
+        push
+    }
+
+    #[test]
+    fn test_pushed_config() {
+        assert_eq!(PushedConfig::from_push(&push(1, &["192.0.2.0/24", "nope"])),
+            Err(ConfigPushError::InvalidNetwork("nope".to_string())));
+        let config = PushedConfig::from_push(&push(1, &["192.0.2.0/24", "2001:db8::1"])).unwrap();
+        assert_eq!(config.client_blocklist.len(), 2);
+
+        let session = |client: &str, phantom: &str, transport: TransportType| {
+            let mut sd = SessionDetails::new(client, phantom, 443, Duration::from_secs(0)).unwrap();
+            sd.transport = transport;
+            sd
+        };
+        assert_eq!(config.refuses(&session("192.168.0.1", "10.10.0.1", TransportType::Min)), None);
+        assert_eq!(config.refuses(&session("192.168.0.1", "10.10.0.1", TransportType::Obfs4)),
+            Some("transport not allowed"));
+        assert_eq!(config.refuses(&session("192.0.2.7", "10.10.0.1", TransportType::Min)),
+            Some("client blocklisted"));
+        assert_eq!(config.refuses(&session("2001:db8::1", "2001::1234", TransportType::Min)),
+            Some("client blocklisted"));
+        assert_eq!(config.refuses(&session("192.168.0.1", "10.99.1.1", TransportType::Min)),
+            Some("phantom blocklisted"));
+
+        // nothing pushed accepts everything
+        assert_eq!(PushedConfig::default().refuses(&session("192.0.2.7", "10.99.1.1", TransportType::Obfs4)), None);
+    }
+
+    #[test]
+    fn test_station_config_apply() {
+        let (tx, rx) = sync_channel(8);
+        let sc = StationConfig::new(Some(EventPublisher::from_sender(tx)));
+        assert_eq!(sc.epoch(), 0);
+        assert_eq!(sc.heartbeat().get_config_epoch(), 0);
+
+        assert_eq!(sc.apply(&push(2, &["192.0.2.0/24"])), Ok(()));
+        assert_eq!(sc.epoch(), 2);
+        // invalid and stale pushes leave the configuration in effect
+        assert!(sc.apply(&push(3, &["nope"])).is_err());
+        assert_eq!(sc.apply(&push(2, &[])), Err(ConfigPushError::Stale(2, 2)));
+        assert_eq!(sc.current().client_blocklist.len(), 1);
+        assert_eq!(sc.current().default_timeout_ns, 30*1000*1000*1000);
+
+        let acks: Vec<(DetectorEvent, u64, String)> = (0..3).map(|_| {
+            let ack: DetectorToStation = Message::parse_from_bytes(&rx.recv().unwrap()).unwrap();
+            (ack.get_event(), ack.get_config_epoch(), ack.get_config_error().to_string())
+        }).collect();
+        assert_eq!(acks, vec![
+            (DetectorEvent::ConfigAck, 2, "".to_string()),
+            (DetectorEvent::ConfigAck, 3, "invalid blocklist entry \"nope\"".to_string()),
+            (DetectorEvent::ConfigAck, 2, "epoch 2 is not newer than 2".to_string()),
+        ]);
+
+        let heartbeat = sc.heartbeat();
+        assert_eq!(heartbeat.get_event(), DetectorEvent::Heartbeat);
+        assert_eq!(heartbeat.get_config_epoch(), 2);
+
+        sc.count_refused();
+        assert_eq!(sc.take_report(), "station config epoch 2 rejected pushes 2 refused registrations 1");
+        assert_eq!(sc.take_report(), "station config epoch 2 rejected pushes 0 refused registrations 0");
+    }
+
+    fn subnet_set(generation: u64, subnets: &[&str]) -> PhantomSubnetSet {
+        let mut set = PhantomSubnetSet::new();
+        set.set_generation(generation);
+        for s in subnets {
+            set.mut_subnets().push(s.to_string());
+        }
+        set
+    }
+
+    #[test]
+    fn test_station_config_phantom_subnets() {
+        let (tx, rx) = sync_channel(8);
+        let sc = StationConfig::new(Some(EventPublisher::from_sender(tx)));
+        // before any set every phantom is allowed
+        assert_eq!(sc.generation(), 0);
+        assert!(sc.phantom_subnets().allows(&"192.0.2.1".parse().unwrap()));
+
+        assert_eq!(sc.apply_subnets(&subnet_set(5, &["10.10.0.0/16", "2001:db8::/32"])), Ok(()));
+        let subnets = sc.phantom_subnets();
+        assert_eq!(subnets.generation, 5);
+        assert!(subnets.allows(&"10.10.3.4".parse().unwrap()));
+        assert!(subnets.allows(&"2001:db8::9".parse().unwrap()));
+        assert!(!subnets.allows(&"192.0.2.1".parse().unwrap()));
+
+        // invalid, empty and stale sets leave the one in effect
+        assert_eq!(sc.apply_subnets(&subnet_set(6, &["10.11.0.0/16", "nope"])),
+            Err(ConfigPushError::InvalidSubnet("nope".to_string())));
+        assert_eq!(sc.apply_subnets(&subnet_set(6, &[])), Err(ConfigPushError::NoSubnets));
+        assert_eq!(sc.apply_subnets(&subnet_set(4, &["10.11.0.0/16"])), Err(ConfigPushError::StaleGeneration(4, 5)));
+        assert_eq!(sc.phantom_subnets().subnets.len(), 2);
+        // the station config epoch is separate
+        assert_eq!(sc.epoch(), 0);
+
+        let acks: Vec<(DetectorEvent, u64, String)> = (0..4).map(|_| {
+            let ack: DetectorToStation = Message::parse_from_bytes(&rx.recv().unwrap()).unwrap();
+            (ack.get_event(), ack.get_phantom_subnets_generation(), ack.get_config_error().to_string())
+        }).collect();
+        assert_eq!(acks, vec![
+            (DetectorEvent::PhantomSubnetsAck, 5, "".to_string()),
+            (DetectorEvent::PhantomSubnetsAck, 6, "invalid phantom subnet \"nope\"".to_string()),
+            (DetectorEvent::PhantomSubnetsAck, 6, "no phantom subnets".to_string()),
+            (DetectorEvent::PhantomSubnetsAck, 4, "generation 4 is not newer than 5".to_string()),
+        ]);
+        assert_eq!(sc.heartbeat().get_phantom_subnets_generation(), 5);
+
+        sc.count_refused_phantom();
+        assert_eq!(sc.take_subnets_report(), "phantom subnets generation 5 subnets 2 rejected pushes 3 refused registrations 1");
+    }
+}
@@ -0,0 +1,526 @@
+//
+// Conntrack Teardown
+//
+// Expiring a session only stops the detector from forwarding new connections
+// for it. Connections that were already forwarded keep going: their kernel
+// conntrack (NAT) entries stay established and the station keeps receiving
+// their packets. With conntrack teardown enabled (SessionConfig.
+// conntrack_teardown), sessions that expire while they still have active
+// connections are handed to a background thread that deletes the matching
+// conntrack entries over ctnetlink, cutting those connections promptly.
+// Deployments that prefer to let connections drain leave it disabled.
+//
+// - An entry matches an expired session if its original direction is TCP to
+//   the session's phantom address and port and, for v4 sessions, from the
+//   session's client. v6 sessions match any client, like their session keys.
+// - Teardown stays off the packet path. Each cleanup pass queues its expired
+//   sessions as one batch; if the thread falls behind (TEARDOWN_QUEUE_LEN
+//   batches) further batches are dropped and counted.
+// - A batch costs one dump of the conntrack table per address family, plus a
+//   delete per matching entry, however many sessions it holds.
+// - Talking to ctnetlink needs CAP_NET_ADMIN.
+
+use std::io;
+use std::mem;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::thread;
+
+use libc;
+
+use error::{DetectorError, DetectorResult, error_chain};
+use sessions::SessionDetails;
+
+// Batches of expired sessions buffered for the teardown thread.
+const TEARDOWN_QUEUE_LEN: usize = 64;
+
+const RECV_BUF_LEN: usize = 64 * 1024;
+
+// linux/netlink.h
+const NLMSG_HDRLEN: usize = 16;
+const NLMSG_ERROR: u16 = 2;
+const NLMSG_DONE: u16 = 3;
+const NLM_F_REQUEST: u16 = 0x1;
+const NLM_F_ACK: u16 = 0x4;
+const NLM_F_DUMP: u16 = 0x300;
+const NLA_HDRLEN: usize = 4;
+const NLA_F_NESTED: u16 = 0x8000;
+const NLA_TYPE_MASK: u16 = 0x3fff;
+
+// linux/netfilter/nfnetlink.h and nfnetlink_conntrack.h
+const NFGENMSG_LEN: usize = 4;
+const NFNL_SUBSYS_CTNETLINK: u16 = 1;
+const IPCTNL_MSG_CT_GET: u16 = 1;
+const IPCTNL_MSG_CT_DELETE: u16 = 2;
+const CTA_TUPLE_ORIG: u16 = 1;
+const CTA_TUPLE_IP: u16 = 1;
+const CTA_TUPLE_PROTO: u16 = 2;
+const CTA_IP_V4_SRC: u16 = 1;
+const CTA_IP_V4_DST: u16 = 2;
+const CTA_IP_V6_SRC: u16 = 3;
+const CTA_IP_V6_DST: u16 = 4;
+const CTA_PROTO_NUM: u16 = 1;
+const CTA_PROTO_DST_PORT: u16 = 3;
+
+const IPPROTO_TCP: u8 = 6;
+
+pub trait ConntrackFlusher
+{
+    /// Deletes the conntrack entries of the connections forwarded for
+    /// `sessions` and returns how many were deleted.
+    fn flush(&mut self, sessions: &[SessionDetails]) -> DetectorResult<usize>;
+}
+
+// Flushes entries from the kernel's conntrack table over ctnetlink.
+pub struct NetlinkConntrack;
+
+impl ConntrackFlusher for NetlinkConntrack
+{
+    fn flush(&mut self, sessions: &[SessionDetails]) -> DetectorResult<usize> {
+        let mut sock = NetlinkSocket::open()?;
+        let mut deleted = 0;
+        for &(family, v4) in [(libc::AF_INET, true), (libc::AF_INET6, false)].iter() {
+            if !sessions.iter().any(|s| s.phantom_ip.is_ipv4() == v4) {
+                continue
+            }
+            for (tuple, orig) in sock.dump(family as u8)? {
+                if sessions.iter().any(|s| tuple.matches(s)) && sock.delete(family as u8, &orig)? {
+                    deleted += 1;
+                }
+            }
+        }
+        Ok(deleted)
+    }
+}
+
+// Original direction of a conntrack entry.
+#[derive(Debug, PartialEq)]
+struct Tuple
+{
+    src: IpAddr,
+    dst: IpAddr,
+    proto: u8,
+    dst_port: u16,
+}
+
+impl Tuple
+{
+    fn matches(&self, session: &SessionDetails) -> bool {
+        self.proto == IPPROTO_TCP
+            && self.dst == session.phantom_ip
+            && self.dst_port as u32 == session.phantom_port
+            && (session.phantom_ip.is_ipv6() || self.src == session.client_ip)
+    }
+}
+
+fn conntrack_error(context: &'static str, source: io::Error) -> DetectorError {
+    DetectorError::Conntrack { context: context, source: source }
+}
+
+struct NetlinkSocket
+{
+    fd: libc::c_int,
+    seq: u32,
+}
+
+impl NetlinkSocket
+{
+    fn open() -> DetectorResult<NetlinkSocket> {
+        let fd = unsafe {
+            libc::socket(libc::AF_NETLINK, libc::SOCK_RAW | libc::SOCK_CLOEXEC, libc::NETLINK_NETFILTER)
+        };
+        if fd < 0 {
+            return Err(conntrack_error("socket", io::Error::last_os_error()))
+        }
+        // From here on the fd is closed on drop.
+        let sock = NetlinkSocket { fd: fd, seq: 0 };
+
+        let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        let res = unsafe {
+            libc::bind(fd, &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+                       mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t)
+        };
+        if res < 0 {
+            return Err(conntrack_error("bind", io::Error::last_os_error()))
+        }
+        Ok(sock)
+    }
+
+    fn send(&mut self, msg_type: u16, flags: u16, family: u8, attrs: &[u8]) -> DetectorResult<()> {
+        self.seq = self.seq.wrapping_add(1);
+        let msg = request(msg_type, flags, family, self.seq, attrs);
+        let sent = unsafe {
+            libc::send(self.fd, msg.as_ptr() as *const libc::c_void, msg.len(), 0)
+        };
+        if sent < 0 {
+            return Err(conntrack_error("send", io::Error::last_os_error()))
+        }
+        Ok(())
+    }
+
+    fn recv(&self, buf: &mut [u8]) -> DetectorResult<usize> {
+        let n = unsafe {
+            libc::recv(self.fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len(), 0)
+        };
+        if n < 0 {
+            return Err(conntrack_error("recv", io::Error::last_os_error()))
+        }
+        Ok(n as usize)
+    }
+
+    // Every entry of the family's table, with its raw CTA_TUPLE_ORIG payload.
+    fn dump(&mut self, family: u8) -> DetectorResult<Vec<(Tuple, Vec<u8>)>> {
+        self.send(IPCTNL_MSG_CT_GET, NLM_F_REQUEST | NLM_F_DUMP, family, &[])?;
+        let mut entries = Vec::new();
+        let mut buf = vec![0u8; RECV_BUF_LEN];
+        loop {
+            let n = self.recv(&mut buf)?;
+            if parse_dump(&buf[..n], &mut entries)? {
+                return Ok(entries)
+            }
+        }
+    }
+
+    // Deletes the entry with original tuple `orig`. False if it was already
+    // gone.
+    fn delete(&mut self, family: u8, orig: &[u8]) -> DetectorResult<bool> {
+        let mut attrs = Vec::new();
+        put_attr(&mut attrs, CTA_TUPLE_ORIG | NLA_F_NESTED, orig);
+        self.send(IPCTNL_MSG_CT_DELETE, NLM_F_REQUEST | NLM_F_ACK, family, &attrs)?;
+
+        let mut buf = vec![0u8; RECV_BUF_LEN];
+        let n = self.recv(&mut buf)?;
+        for (msg_type, payload) in messages(&buf[..n]) {
+            if msg_type == NLMSG_ERROR {
+                return match ack_errno(payload) {
+                    0 => Ok(true),
+                    e if e == libc::ENOENT => Ok(false),
+                    e => Err(conntrack_error("delete", io::Error::from_raw_os_error(e))),
+                }
+            }
+        }
+        Err(conntrack_error("delete", io::Error::new(io::ErrorKind::InvalidData, "no ack")))
+    }
+}
+
+impl Drop for NetlinkSocket
+{
+    fn drop(&mut self) {
+        unsafe { libc::close(self.fd); }
+    }
+}
+
+fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+fn get_u16(b: &[u8]) -> u16 {
+    u16::from_ne_bytes([b[0], b[1]])
+}
+
+fn put_attr(buf: &mut Vec<u8>, attr_type: u16, payload: &[u8]) {
+    buf.extend_from_slice(&((NLA_HDRLEN + payload.len()) as u16).to_ne_bytes());
+    buf.extend_from_slice(&attr_type.to_ne_bytes());
+    buf.extend_from_slice(payload);
+    let padded = align(buf.len());
+    buf.resize(padded, 0);
+}
+
+// ctnetlink message: netlink header, nfgenmsg, attributes.
+fn request(msg_type: u16, flags: u16, family: u8, seq: u32, attrs: &[u8]) -> Vec<u8> {
+    let len = NLMSG_HDRLEN + NFGENMSG_LEN + attrs.len();
+    let mut msg = Vec::with_capacity(len);
+    msg.extend_from_slice(&(len as u32).to_ne_bytes());
+    msg.extend_from_slice(&((NFNL_SUBSYS_CTNETLINK << 8) | msg_type).to_ne_bytes());
+    msg.extend_from_slice(&flags.to_ne_bytes());
+    msg.extend_from_slice(&seq.to_ne_bytes());
+    msg.extend_from_slice(&0u32.to_ne_bytes());
+    // nfgenmsg: family, NFNETLINK_V0, res_id
+    msg.extend_from_slice(&[family, 0, 0, 0]);
+    msg.extend_from_slice(attrs);
+    msg
+}
+
+// (type, payload) of each netlink message in `buf`.
+fn messages(buf: &[u8]) -> Vec<(u16, &[u8])> {
+    let mut out = Vec::new();
+    let mut off = 0;
+    while off + NLMSG_HDRLEN <= buf.len() {
+        let len = u32::from_ne_bytes([buf[off], buf[off+1], buf[off+2], buf[off+3]]) as usize;
+        if len < NLMSG_HDRLEN || off + len > buf.len() {
+            break
+        }
+        out.push((get_u16(&buf[off+4..]), &buf[off+NLMSG_HDRLEN..off+len]));
+        off += align(len);
+    }
+    out
+}
+
+// (type, payload) of each attribute in `buf`.
+fn attributes(buf: &[u8]) -> Vec<(u16, &[u8])> {
+    let mut out = Vec::new();
+    let mut off = 0;
+    while off + NLA_HDRLEN <= buf.len() {
+        let len = get_u16(&buf[off..]) as usize;
+        if len < NLA_HDRLEN || off + len > buf.len() {
+            break
+        }
+        out.push((get_u16(&buf[off+2..]) & NLA_TYPE_MASK, &buf[off+NLA_HDRLEN..off+len]));
+        off += align(len);
+    }
+    out
+}
+
+// The (negated) errno of an NLMSG_ERROR payload.
+fn ack_errno(payload: &[u8]) -> i32 {
+    if payload.len() < 4 {
+        return 0
+    }
+    -i32::from_ne_bytes([payload[0], payload[1], payload[2], payload[3]])
+}
+
+fn parse_tuple(orig: &[u8]) -> Option<Tuple> {
+    let (mut src, mut dst, mut proto, mut dst_port) = (None, None, None, None);
+    for (attr_type, payload) in attributes(orig) {
+        match attr_type {
+            CTA_TUPLE_IP => for (ip_type, ip) in attributes(payload) {
+                let addr = match ip.len() {
+                    4 => IpAddr::V4(Ipv4Addr::new(ip[0], ip[1], ip[2], ip[3])),
+                    16 => {
+                        let mut octets = [0u8; 16];
+                        octets.copy_from_slice(ip);
+                        IpAddr::V6(Ipv6Addr::from(octets))
+                    },
+                    _ => continue,
+                };
+                match ip_type {
+                    CTA_IP_V4_SRC | CTA_IP_V6_SRC => src = Some(addr),
+                    CTA_IP_V4_DST | CTA_IP_V6_DST => dst = Some(addr),
+                    _ => {},
+                }
+            },
+            CTA_TUPLE_PROTO => for (proto_type, p) in attributes(payload) {
+                match proto_type {
+                    CTA_PROTO_NUM if !p.is_empty() => proto = Some(p[0]),
+                    // network byte order
+                    CTA_PROTO_DST_PORT if p.len() >= 2 => dst_port = Some(u16::from_be_bytes([p[0], p[1]])),
+                    _ => {},
+                }
+            },
+            _ => {},
+        }
+    }
+    Some(Tuple { src: src?, dst: dst?, proto: proto?, dst_port: dst_port.unwrap_or(0) })
+}
+
+// Adds the entries in one read of a dump to `entries`. True once the dump is
+// complete.
+fn parse_dump(buf: &[u8], entries: &mut Vec<(Tuple, Vec<u8>)>) -> DetectorResult<bool> {
+    for (msg_type, payload) in messages(buf) {
+        match msg_type {
+            NLMSG_DONE => return Ok(true),
+            NLMSG_ERROR => match ack_errno(payload) {
+                0 => {},
+                e => return Err(conntrack_error("dump", io::Error::from_raw_os_error(e))),
+            },
+            _ if payload.len() >= NFGENMSG_LEN => {
+                for (attr_type, orig) in attributes(&payload[NFGENMSG_LEN..]) {
+                    if attr_type != CTA_TUPLE_ORIG {
+                        continue
+                    }
+                    if let Some(tuple) = parse_tuple(orig) {
+                        entries.push((tuple, orig.to_vec()));
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+    Ok(false)
+}
+
+struct TeardownStats
+{
+    sessions: AtomicU64,
+    flushed: AtomicU64,
+    failed: AtomicU64,
+    dropped: AtomicU64,
+}
+
+// Queue of expired sessions for the teardown thread, and its counters.
+pub struct Teardown
+{
+    tx: SyncSender<Vec<SessionDetails>>,
+    stats: Arc<TeardownStats>,
+}
+
+impl Teardown
+{
+    /// Starts the thread flushing the entries of expired sessions with
+    /// `flusher`.
+    pub fn spawn<F: ConntrackFlusher + Send + 'static>(flusher: F) -> Teardown {
+        let (tx, rx) = sync_channel(TEARDOWN_QUEUE_LEN);
+        let teardown = Teardown::from_sender(tx);
+        let stats = Arc::clone(&teardown.stats);
+        thread::spawn(move || { run_teardown(flusher, rx, &stats) });
+        teardown
+    }
+
+    /// Teardown that hands batches to `tx` instead of a flushing thread.
+    pub fn from_sender(tx: SyncSender<Vec<SessionDetails>>) -> Teardown {
+        Teardown {
+            tx: tx,
+            stats: Arc::new(TeardownStats {
+                sessions: AtomicU64::new(0),
+                flushed: AtomicU64::new(0),
+                failed: AtomicU64::new(0),
+                dropped: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Queues sessions that expired with active connections.
+    pub fn expired(&self, sessions: Vec<SessionDetails>) {
+        if sessions.is_empty() {
+            return
+        }
+        if self.tx.try_send(sessions).is_err() {
+            self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Counters since the last call, formatted for the periodic report.
+    pub fn take_report(&self) -> String {
+        format!("conntrack teardown sessions {} flushed {} failed {} dropped {}",
+            self.stats.sessions.swap(0, Ordering::Relaxed),
+            self.stats.flushed.swap(0, Ordering::Relaxed),
+            self.stats.failed.swap(0, Ordering::Relaxed),
+            self.stats.dropped.swap(0, Ordering::Relaxed))
+    }
+}
+
+// Runs until the Teardown is dropped.
+fn run_teardown<F: ConntrackFlusher>(mut flusher: F, rx: Receiver<Vec<SessionDetails>>, stats: &TeardownStats) {
+    for sessions in rx.iter() {
+        stats.sessions.fetch_add(sessions.len() as u64, Ordering::Relaxed);
+        match flusher.flush(&sessions) {
+            Ok(n) => {
+                stats.flushed.fetch_add(n as u64, Ordering::Relaxed);
+            },
+            Err(e) => {
+                warn!("{}", error_chain(&e));
+                stats.failed.fetch_add(1, Ordering::Relaxed);
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use conntrack::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    // Original tuple attribute payload for a TCP connection.
+    fn orig(src: &[u8], dst: &[u8], dst_port: u16) -> Vec<u8> {
+        let (src_type, dst_type) = match src.len() {
+            4 => (CTA_IP_V4_SRC, CTA_IP_V4_DST),
+            _ => (CTA_IP_V6_SRC, CTA_IP_V6_DST),
+        };
+        let mut ip = Vec::new();
+        put_attr(&mut ip, src_type, src);
+        put_attr(&mut ip, dst_type, dst);
+        let mut proto = Vec::new();
+        put_attr(&mut proto, CTA_PROTO_NUM, &[IPPROTO_TCP]);
+        put_attr(&mut proto, 2, &[0x9c, 0x40]);
+        put_attr(&mut proto, CTA_PROTO_DST_PORT, &dst_port.to_be_bytes());
+        let mut tuple = Vec::new();
+        put_attr(&mut tuple, CTA_TUPLE_IP | NLA_F_NESTED, &ip);
+        put_attr(&mut tuple, CTA_TUPLE_PROTO | NLA_F_NESTED, &proto);
+        tuple
+    }
+
+    #[test]
+    fn test_conntrack_dump_parsing() {
+        let v4 = orig(&[192, 168, 0, 1], &[10, 10, 0, 1], 443);
+        let mut attrs = Vec::new();
+        put_attr(&mut attrs, CTA_TUPLE_ORIG | NLA_F_NESTED, &v4);
+        put_attr(&mut attrs, 2 | NLA_F_NESTED, &orig(&[10, 10, 0, 1], &[192, 168, 0, 1], 40000));
+        let mut buf = request(0, 0, libc::AF_INET as u8, 1, &attrs);
+        buf.extend(request(0, 0, libc::AF_INET as u8, 1, &[]));
+
+        let mut entries = Vec::new();
+        assert!(!parse_dump(&buf, &mut entries).unwrap());
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].1, v4);
+        let tuple = &entries[0].0;
+        assert_eq!(*tuple, Tuple { src: "192.168.0.1".parse().unwrap(), dst: "10.10.0.1".parse().unwrap(),
+                                   proto: IPPROTO_TCP, dst_port: 443 });
+
+        assert!(tuple.matches(&SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_secs(0)).unwrap()));
+        assert!(!tuple.matches(&SessionDetails::new("192.168.0.2", "10.10.0.1", 443, Duration::from_secs(0)).unwrap()));
+        assert!(!tuple.matches(&SessionDetails::new("192.168.0.1", "10.10.0.1", 8443, Duration::from_secs(0)).unwrap()));
+
+        // v6 sessions match any client
+        let v6 = parse_tuple(&orig(&[0x20, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2],
+                                   &[0x20, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x12, 0x34], 443)).unwrap();
+        assert!(v6.matches(&SessionDetails::new("", "2001::1234", 443, Duration::from_secs(0)).unwrap()));
+
+        // done, and errors
+        let mut done = request(0, 0, 0, 1, &[]);
+        done[4..6].copy_from_slice(&NLMSG_DONE.to_ne_bytes());
+        assert!(parse_dump(&done, &mut entries).unwrap());
+        let mut err = request(0, 0, 0, 1, &[]);
+        err[4..6].copy_from_slice(&NLMSG_ERROR.to_ne_bytes());
+        err.truncate(NLMSG_HDRLEN);
+        err.extend_from_slice(&(-1i32).to_ne_bytes());
+        let len = err.len() as u32;
+        err[0..4].copy_from_slice(&len.to_ne_bytes());
+        match parse_dump(&err, &mut entries) {
+            Err(DetectorError::Conntrack { context, ref source }) => {
+                assert_eq!(context, "dump");
+                assert_eq!(source.raw_os_error(), Some(1));
+            },
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    struct Recorder
+    {
+        calls: Vec<usize>,
+    }
+
+    impl<'a> ConntrackFlusher for &'a mut Recorder
+    {
+        fn flush(&mut self, sessions: &[SessionDetails]) -> DetectorResult<usize> {
+            self.calls.push(sessions.len());
+            match sessions.len() {
+                1 => Err(conntrack_error("send", io::Error::from_raw_os_error(1))),
+                n => Ok(n * 2),
+            }
+        }
+    }
+
+    #[test]
+    fn test_teardown_queue() {
+        let (tx, rx) = mpsc::sync_channel(2);
+        let teardown = Teardown::from_sender(tx);
+        let sd = SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_secs(0)).unwrap();
+        teardown.expired(vec![]);
+        teardown.expired(vec![sd, sd]);
+        teardown.expired(vec![sd]);
+        teardown.expired(vec![sd, sd, sd]);
+
+        let mut recorder = Recorder { calls: Vec::new() };
+        let stats = Arc::clone(&teardown.stats);
+        drop(teardown.tx);
+        run_teardown(&mut recorder, rx, &stats);
+        assert_eq!(recorder.calls, vec![2, 1]);
+
+        let teardown = Teardown { tx: mpsc::sync_channel(1).0, stats: stats };
+        assert_eq!(teardown.take_report(), "conntrack teardown sessions 3 flushed 4 failed 1 dropped 1");
+    }
+}
@@ -0,0 +1,271 @@
+//
+// Control Listener
+//
+// Deployments that can't run redis on the detector host can have the station
+// send registrations straight to the detector over a private interface. With
+// a control address configured (DETECTOR_CONTROL_ADDR) each core listens for
+// UDP datagrams carrying one StationToDetector message each, and feeds them to
+// the same ingest path as the redis channel.
+//
+// Datagram layout:
+//
+//   length (u16, big endian) | StationToDetector (length bytes) | HMAC (32 bytes)
+//
+// where HMAC is HMAC-SHA256 over the length and message under the key shared
+// with the station (DETECTOR_CONTROL_KEY_FILE). Datagrams that are truncated,
+// carry trailing data or fail authentication are logged and dropped.
+//
+// Anyone on the path can capture a datagram and send it again, so messages
+// received here always go through a replay guard (see replay.rs), whether or
+// not one is configured for redis: each must carry a message_id and a
+// station_time_unix_ns within the replay window (DETECTOR_REPLAY_WINDOW_MS,
+// default 5 minutes), and an id is accepted once.
+//
+// Every core needs every registration. The listening socket is opened with
+// SO_REUSEPORT so all the cores of a detector can bind the same multicast
+// group address, and each receives a copy of every datagram. With a unicast
+// address the kernel would instead spread datagrams across the cores, so each
+// core binds its own port (the configured port plus the core number) and the
+// station sends each message to every core.
+
+use std::io;
+use std::mem;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::os::unix::io::FromRawFd;
+
+use hmac::{Hmac, Mac};
+use libc;
+use sha2::Sha256;
+
+use error::{DetectorError, DetectorResult, error_chain};
+use ingest::RegistrationFeed;
+
+const LENGTH_LEN: usize = 2;
+const MAC_LEN: usize = 32;
+
+// Largest UDP payload.
+const MAX_DATAGRAM: usize = 65507;
+
+#[derive(Clone)]
+pub struct ControlConfig
+{
+    // Address (unicast or multicast group) and port to listen on.
+    pub addr: SocketAddr,
+    // HMAC key shared with the station.
+    pub key: Vec<u8>,
+    // Interface address to join a v4 multicast group on.
+    pub multicast_interface: Ipv4Addr,
+}
+
+// Registrations received on the control address.
+pub struct ControlFeed
+{
+    socket: UdpSocket,
+    key: Vec<u8>,
+}
+
+impl ControlFeed
+{
+    /// Binds `config.addr` with SO_REUSEPORT, joining it if it's a multicast
+    /// group.
+    pub fn bind(config: &ControlConfig) -> DetectorResult<ControlFeed> {
+        let socket = bind_reuseport(&config.addr)
+            .map_err(|e| DetectorError::Control { context: "bind", source: e })?;
+        let joined = match config.addr.ip() {
+            IpAddr::V4(group) if group.is_multicast() =>
+                socket.join_multicast_v4(&group, &config.multicast_interface),
+            IpAddr::V6(group) if group.is_multicast() =>
+                socket.join_multicast_v6(&group, 0),
+            _ => Ok(()),
+        };
+        joined.map_err(|e| DetectorError::Control { context: "multicast join", source: e })?;
+        Ok(ControlFeed::from_socket(socket, &config.key))
+    }
+
+    pub fn from_socket(socket: UdpSocket, key: &[u8]) -> ControlFeed {
+        ControlFeed { socket: socket, key: key.to_vec() }
+    }
+}
+
+impl RegistrationFeed for ControlFeed
+{
+    // Runs for the lifetime of the process, unless the socket has a read
+    // timeout: the feed closes the first time one expires.
+    fn for_each_payload(&mut self, handle: &mut dyn FnMut(&[u8])) -> DetectorResult<()> {
+        let mut buf = vec![0; MAX_DATAGRAM];
+        loop {
+            let (n, from) = match self.socket.recv_from(&mut buf) {
+                Ok(r) => r,
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock ||
+                              e.kind() == io::ErrorKind::TimedOut => return Ok(()),
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(DetectorError::Control { context: "receive", source: e }),
+            };
+            match open_message(&self.key, &buf[..n]) {
+                Ok(payload) => handle(payload),
+                Err(e) => debug!("control message from {}: {}", from, error_chain(&e)),
+            }
+        }
+    }
+}
+
+/// Frames and authenticates `payload` for the control listener. `payload`
+/// must fit in a datagram.
+pub fn seal_message(key: &[u8], payload: &[u8]) -> Vec<u8> {
+    let mut msg = Vec::with_capacity(LENGTH_LEN + payload.len() + MAC_LEN);
+    msg.push((payload.len() >> 8) as u8);
+    msg.push(payload.len() as u8);
+    msg.extend_from_slice(payload);
+    let mac = hmac_sha256(key, &msg);
+    msg.extend_from_slice(&mac);
+    msg
+}
+
+/// The authenticated payload of a datagram from the station.
+pub fn open_message<'a>(key: &[u8], msg: &'a [u8]) -> DetectorResult<&'a [u8]> {
+    if msg.len() < LENGTH_LEN + MAC_LEN {
+        return Err(DetectorError::ControlMessage("truncated"))
+    }
+    let len = ((msg[0] as usize) << 8) | msg[1] as usize;
+    if msg.len() != LENGTH_LEN + len + MAC_LEN {
+        return Err(DetectorError::ControlMessage("length mismatch"))
+    }
+    let (framed, mac) = msg.split_at(LENGTH_LEN + len);
+    let mut expected = new_hmac(key);
+    expected.input(framed);
+    // compares in constant time
+    if expected.verify(mac).is_err() {
+        return Err(DetectorError::ControlMessage("bad HMAC"))
+    }
+    Ok(&framed[LENGTH_LEN..])
+}
+
+// HMAC-SHA256 (RFC 2104).
+pub fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; MAC_LEN] {
+    let mut hmac = new_hmac(key);
+    hmac.input(data);
+    let mut mac = [0u8; MAC_LEN];
+    mac.copy_from_slice(&hmac.result().code());
+    mac
+}
+
+fn new_hmac(key: &[u8]) -> Hmac<Sha256> {
+    // HMAC takes keys of any length
+    Hmac::new_varkey(key).expect("HMAC key")
+}
+
+// A UDP socket bound to `addr` with SO_REUSEPORT set (std can't set socket
+// options before binding).
+fn bind_reuseport(addr: &SocketAddr) -> io::Result<UdpSocket> {
+    let family = match addr {
+        SocketAddr::V4(_) => libc::AF_INET,
+        SocketAddr::V6(_) => libc::AF_INET6,
+    };
+    let fd = unsafe { libc::socket(family, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error())
+    }
+    // owns fd from here on, closing it on error
+    let socket = unsafe { UdpSocket::from_raw_fd(fd) };
+
+    let one: libc::c_int = 1;
+    let res = unsafe {
+        libc::setsockopt(fd, libc::SOL_SOCKET, libc::SO_REUSEPORT,
+                         &one as *const libc::c_int as *const libc::c_void,
+                         mem::size_of::<libc::c_int>() as libc::socklen_t)
+    };
+    if res < 0 {
+        return Err(io::Error::last_os_error())
+    }
+
+    let res = match addr {
+        SocketAddr::V4(a) => {
+            let mut sa: libc::sockaddr_in = unsafe { mem::zeroed() };
+            sa.sin_family = libc::AF_INET as libc::sa_family_t;
+            sa.sin_port = a.port().to_be();
+            sa.sin_addr.s_addr = u32::from(*a.ip()).to_be();
+            unsafe {
+                libc::bind(fd, &sa as *const libc::sockaddr_in as *const libc::sockaddr,
+                           mem::size_of::<libc::sockaddr_in>() as libc::socklen_t)
+            }
+        },
+        SocketAddr::V6(a) => {
+            let mut sa: libc::sockaddr_in6 = unsafe { mem::zeroed() };
+            sa.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+            sa.sin6_port = a.port().to_be();
+            sa.sin6_addr.s6_addr = a.ip().octets();
+            unsafe {
+                libc::bind(fd, &sa as *const libc::sockaddr_in6 as *const libc::sockaddr,
+                           mem::size_of::<libc::sockaddr_in6>() as libc::socklen_t)
+            }
+        },
+    };
+    if res < 0 {
+        return Err(io::Error::last_os_error())
+    }
+    Ok(socket)
+}
+
+#[cfg(test)]
+mod tests {
+    use control::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_control_hmac() {
+        // RFC 4231 test case 2
+        let mac = hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+        assert_eq!(::hex::encode(&mac[..]),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+        // keys longer than a block are hashed first (test case 6)
+        let mac = hmac_sha256(&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First");
+        assert_eq!(::hex::encode(&mac[..]),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54");
+    }
+
+    #[test]
+    fn test_control_messages() {
+        let key = b"station key";
+        let msg = seal_message(key, b"registration");
+        assert_eq!(msg.len(), 2 + 12 + 32);
+        assert_eq!(open_message(key, &msg).unwrap(), b"registration");
+
+        match open_message(b"other key", &msg) {
+            Err(DetectorError::ControlMessage("bad HMAC")) => {},
+            r => panic!("unexpected {:?}", r),
+        }
+        let mut tampered = msg.clone();
+        tampered[5] ^= 1;
+        assert!(open_message(key, &tampered).is_err());
+        match open_message(key, &msg[..msg.len() - 1]) {
+            Err(DetectorError::ControlMessage("length mismatch")) => {},
+            r => panic!("unexpected {:?}", r),
+        }
+        assert!(open_message(key, &msg[..10]).is_err());
+        assert_eq!(open_message(key, &seal_message(key, b"")).unwrap(), b"");
+    }
+
+    #[test]
+    fn test_control_feed() {
+        let config = ControlConfig {
+            addr: "127.0.0.1:0".parse().unwrap(),
+            key: b"station key".to_vec(),
+            multicast_interface: Ipv4Addr::new(0, 0, 0, 0),
+        };
+        let mut feed = ControlFeed::bind(&config).unwrap();
+        let addr = feed.socket.local_addr().unwrap();
+        // another core can bind the same address
+        drop(bind_reuseport(&addr).unwrap());
+        feed.socket.set_read_timeout(Some(Duration::from_millis(200))).unwrap();
+
+        let station = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        station.send_to(&seal_message(&config.key, b"first"), addr).unwrap();
+        station.send_to(&seal_message(b"forged", b"forged"), addr).unwrap();
+        station.send_to(&seal_message(&config.key, b"second"), addr).unwrap();
+
+        let mut seen = Vec::new();
+        feed.for_each_payload(&mut |p| seen.push(p.to_vec())).unwrap();
+        assert_eq!(seen, vec![b"first".to_vec(), b"second".to_vec()]);
+    }
+}
@@ -0,0 +1,224 @@
+//
+// Dark Scan Telemetry
+//
+// Connections to phantom space that don't belong to a registered session are
+// either scanners sweeping the address space or a censor probing phantoms it
+// has seen used. With dark scan telemetry enabled (DETECTOR_DARK_SCAN_SUBNETS)
+// the detector samples the SYNs of unmatched flows to the configured subnets
+// and reports, per period, which source networks hit which ports and how
+// hard.
+//
+// Only aggregates leave the detector:
+//  - sources are truncated to a network (DarkScanConfig.v4_prefix/v6_prefix)
+//    before they are counted, and individual addresses are only kept as keyed
+//    hashes, to count distinct sources, until the end of the period;
+//  - (source network, port) buckets seen from fewer than min_sources distinct
+//    sources in a period are not reported individually, only in the totals;
+//  - at most MAX_BUCKETS buckets are kept per period (further ones are counted
+//    as overflow) and the REPORT_TOP busiest are reported.
+//
+// Rates are estimated from the sampled SYNs, scaled by the sampling rate. v6
+// traffic only reaches this point on port 443 and the phantom ports.
+
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::net::IpAddr;
+
+use ipnetwork::IpNetwork;
+use rand;
+
+use timekeeping::MonotonicNs;
+
+// Buckets kept per period.
+const MAX_BUCKETS: usize = 4096;
+
+// Buckets reported per period, busiest first.
+const REPORT_TOP: usize = 20;
+
+#[derive(Clone)]
+pub struct DarkScanConfig
+{
+    // Phantom space to watch.
+    pub subnets: Vec<IpNetwork>,
+    // Count one in `sample` unmatched SYNs. 1 counts every SYN.
+    pub sample: u32,
+    // Prefix lengths source addresses are truncated to.
+    pub v4_prefix: u8,
+    pub v6_prefix: u8,
+    // Distinct sources a bucket needs in a period to be reported.
+    pub min_sources: usize,
+}
+
+impl Default for DarkScanConfig
+{
+    fn default() -> DarkScanConfig {
+        DarkScanConfig {
+            subnets: Vec::new(),
+            sample: 1,
+            v4_prefix: 24,
+            v6_prefix: 48,
+            min_sources: 5,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Bucket
+{
+    syns: u64,
+    // Keyed hashes of the source addresses.
+    sources: HashSet<u64>,
+}
+
+pub struct DarkScan
+{
+    config: DarkScanConfig,
+
+    // Per process key for the source address hashes.
+    hasher: RandomState,
+
+    // (source network, prefix length, destination port)
+    buckets: HashMap<(IpAddr, u8, u16), Bucket>,
+
+    // Unmatched SYNs to the watched subnets, and how many were sampled.
+    seen: u64,
+    sampled: u64,
+    // Sampled SYNs not counted in a bucket because MAX_BUCKETS was reached.
+    overflow: u64,
+
+    period_start: MonotonicNs,
+}
+
+impl DarkScan
+{
+    pub fn new(config: DarkScanConfig, now: MonotonicNs) -> DarkScan {
+        DarkScan {
+            config: config,
+            hasher: RandomState::new(),
+            buckets: HashMap::new(),
+            seen: 0,
+            sampled: 0,
+            overflow: 0,
+            period_start: now,
+        }
+    }
+
+    /// Counts a SYN from `src` to `dst`:`port` that didn't match a session.
+    /// Ignored unless `dst` is in one of the watched subnets.
+    pub fn observe(&mut self, src: &IpAddr, dst: &IpAddr, port: u16) {
+        if !self.config.subnets.iter().any(|net| net.contains(*dst)) {
+            return
+        }
+        self.seen += 1;
+        if self.config.sample > 1 && rand::random::<u32>() % self.config.sample != 0 {
+            return
+        }
+        self.sampled += 1;
+
+        let prefix = match src {
+            IpAddr::V4(_) => self.config.v4_prefix,
+            IpAddr::V6(_) => self.config.v6_prefix,
+        };
+        let network = match IpNetwork::new(*src, prefix) {
+            Ok(net) => net.network(),
+            Err(_) => *src,
+        };
+        let key = (network, prefix, port);
+        if !self.buckets.contains_key(&key) && self.buckets.len() >= MAX_BUCKETS {
+            self.overflow += 1;
+            return
+        }
+
+        let mut hasher = self.hasher.build_hasher();
+        src.hash(&mut hasher);
+        let source = hasher.finish();
+
+        let bucket = self.buckets.entry(key).or_insert_with(Bucket::default);
+        bucket.syns += 1;
+        bucket.sources.insert(source);
+    }
+
+    /// Report lines for the period ending at `now`, resetting the counters:
+    /// a summary followed by the busiest buckets with enough sources.
+    pub fn take_report(&mut self, now: MonotonicNs) -> Vec<String> {
+        let secs = (now.saturating_since(self.period_start) as f64 / 1e9).max(1e-9);
+        let scale = self.config.sample.max(1) as f64;
+
+        let mut shown: Vec<(&(IpAddr, u8, u16), &Bucket)> = self.buckets.iter()
+            .filter(|&(_, b)| b.sources.len() >= self.config.min_sources)
+            .collect();
+        let hidden = self.buckets.len() - shown.len();
+        shown.sort_by(|a, b| b.1.syns.cmp(&a.1.syns).then(a.0.cmp(b.0)));
+
+        let mut lines = vec![format!("dark scan syns {} sampled {} buckets {} hidden {} overflow {}",
+            self.seen, self.sampled, self.buckets.len(), hidden, self.overflow)];
+        for &(&(net, prefix, port), bucket) in shown.iter().take(REPORT_TOP) {
+            lines.push(format!("dark scan {}/{} port {} syns {} sources {} rate {:.1}/s",
+                net, prefix, port, bucket.syns, bucket.sources.len(),
+                bucket.syns as f64 * scale / secs));
+        }
+
+        self.buckets.clear();
+        self.seen = 0;
+        self.sampled = 0;
+        self.overflow = 0;
+        self.period_start = now;
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dark_scan::*;
+    use timekeeping::MonotonicNs;
+
+    #[test]
+    fn test_dark_scan_aggregation() {
+        let config = DarkScanConfig {
+            subnets: vec!["192.122.190.0/24".parse().unwrap()],
+            min_sources: 3,
+            ..DarkScanConfig::default()
+        };
+        let mut scan = DarkScan::new(config, MonotonicNs(0));
+        let phantom: IpAddr = "192.122.190.7".parse().unwrap();
+
+        // four sources in one /24 scanning 443, twice each
+        for host in 1..5 {
+            let src: IpAddr = format!("10.1.2.{}", host).parse().unwrap();
+            scan.observe(&src, &phantom, 443);
+            scan.observe(&src, &phantom, 443);
+        }
+        // too few sources to be shown
+        scan.observe(&"10.9.9.9".parse().unwrap(), &phantom, 22);
+        // outside the watched subnets
+        scan.observe(&"10.1.2.1".parse().unwrap(), &"8.8.8.8".parse().unwrap(), 443);
+
+        assert_eq!(scan.take_report(MonotonicNs(2*1000*1000*1000)), vec![
+            "dark scan syns 9 sampled 9 buckets 2 hidden 1 overflow 0",
+            "dark scan 10.1.2.0/24 port 443 syns 8 sources 4 rate 4.0/s",
+        ]);
+        assert_eq!(scan.take_report(MonotonicNs(3*1000*1000*1000)),
+            vec!["dark scan syns 0 sampled 0 buckets 0 hidden 0 overflow 0"]);
+    }
+
+    #[test]
+    fn test_dark_scan_limits() {
+        let config = DarkScanConfig {
+            subnets: vec!["2001:48a8:687f:1::/64".parse().unwrap()],
+            v6_prefix: 32,
+            min_sources: 1,
+            ..DarkScanConfig::default()
+        };
+        let mut scan = DarkScan::new(config, MonotonicNs(0));
+        let phantom: IpAddr = "2001:48a8:687f:1::5".parse().unwrap();
+        for port in 0..(MAX_BUCKETS + 10) {
+            scan.observe(&"2a00:1:2:3::1".parse().unwrap(), &phantom, port as u16);
+        }
+        let report = scan.take_report(MonotonicNs(1000*1000*1000));
+        assert_eq!(report[0], format!("dark scan syns {} sampled {} buckets {} hidden 0 overflow 10",
+            MAX_BUCKETS + 10, MAX_BUCKETS + 10, MAX_BUCKETS));
+        assert_eq!(report.len(), 1 + REPORT_TOP);
+        assert_eq!(report[1], "dark scan 2a00:1::/32 port 0 syns 1 sources 1 rate 1.0/s");
+    }
+}
@@ -0,0 +1,383 @@
+//
+// Encapsulated Capture
+//
+// Some taps deliver the mirrored traffic encapsulated: ERSPAN or plain GRE
+// from a remote switch port, MPLS labelled from a provider core. With the
+// layers enabled in DecapConfig (DETECTOR_DECAP) the parser unwraps them so
+// the inner IP packet is what reaches flow and session matching:
+//
+//  - gre: an IP packet carrying GRE (protocol 47) whose payload is IPv4, IPv6
+//    or an Ethernet frame (transparent Ethernet bridging, 0x6558);
+//  - erspan: an IP packet carrying GRE whose payload is ERSPAN. Type I (0x88be
+//    without a sequence number) has no header of its own, type II (0x88be)
+//    an 8 byte header and type III (0x22eb) a 12 byte header, followed by an
+//    8 byte platform specific subheader if its O flag is set. All of them
+//    then carry the mirrored Ethernet frame;
+//  - mpls: an MPLS label stack (0x8847, 0x8848), on Ethernet or in GRE, up to
+//    the bottom of stack label. The payload after it is taken to be IPv4 or
+//    IPv6 by its version nibble; anything else (pseudowires) is dropped.
+//
+// Inner Ethernet frames have their VLAN tags skipped (see vlan.rs), without
+// being counted per VLAN. Layers nest up to MAX_LAYERS deep. Packets that
+// aren't encapsulated in an enabled layer are passed on as they are, so a tap
+// can mix encapsulated and plain traffic, but GRE that phantom clients send
+// would be unwrapped too. Packets an enabled layer can't be parsed from
+// (truncated, fragmented outer packets) are dropped and counted.
+//
+// This replaces the fixed PARSE_GRE_OFFSET skip for taps whose headers vary.
+
+use std::str::FromStr;
+
+use pnet::packet::ethernet::{EtherType, EtherTypes};
+use pnet::packet::ip::{IpNextHeaderProtocol, IpNextHeaderProtocols};
+
+use util::walk_v6_chain;
+use vlan::strip_tags;
+
+// Most encapsulation layers unwrapped from one packet.
+const MAX_LAYERS: usize = 4;
+
+const ETHERTYPE_TEB: EtherType = EtherType(0x6558);
+const ETHERTYPE_ERSPAN_2: EtherType = EtherType(0x88be);
+const ETHERTYPE_ERSPAN_3: EtherType = EtherType(0x22eb);
+const ETHERTYPE_MPLS: EtherType = EtherType(0x8847);
+const ETHERTYPE_MPLS_MULTICAST: EtherType = EtherType(0x8848);
+
+// GRE flags (first byte): checksum, key and sequence number present.
+const GRE_C: u8 = 0x80;
+const GRE_K: u8 = 0x20;
+const GRE_S: u8 = 0x10;
+
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct DecapConfig
+{
+    pub gre: bool,
+    pub erspan: bool,
+    pub mpls: bool,
+}
+
+impl DecapConfig
+{
+    pub fn enabled(&self) -> bool {
+        self.gre || self.erspan || self.mpls
+    }
+}
+
+// Comma separated layers, e.g. "erspan,mpls".
+impl FromStr for DecapConfig
+{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<DecapConfig, String> {
+        let mut config = DecapConfig::default();
+        for layer in s.split(',').map(|l| l.trim()).filter(|l| !l.is_empty()) {
+            match layer {
+                "gre" => config.gre = true,
+                "erspan" => config.erspan = true,
+                "mpls" => config.mpls = true,
+                _ => return Err(format!("unknown encapsulation {}", layer)),
+            }
+        }
+        Ok(config)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Layer
+{
+    Gre,
+    Erspan,
+    Mpls,
+}
+
+// What the next layer of a packet is.
+#[derive(Debug, PartialEq)]
+enum Next<'p>
+{
+    // Not encapsulated in an enabled layer.
+    Done,
+    Unwrapped(Layer, EtherType, &'p [u8]),
+    Failed(Layer),
+}
+
+pub struct Decap
+{
+    config: DecapConfig,
+
+    gre: u64,
+    erspan: u64,
+    mpls: u64,
+    failed: u64,
+}
+
+impl Decap
+{
+    pub fn new(config: DecapConfig) -> Decap {
+        Decap {
+            config: config,
+            gre: 0,
+            erspan: 0,
+            mpls: 0,
+            failed: 0,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.enabled()
+    }
+
+    /// The innermost ethertype and payload of an Ethernet payload of type
+    /// `ethertype`, unwrapping the enabled layers. None, counted, if one of
+    /// them can't be parsed.
+    pub fn unwrap<'p>(&mut self, ethertype: EtherType, payload: &'p [u8]) -> Option<(EtherType, &'p [u8])> {
+        let mut ethertype = ethertype;
+        let mut payload = payload;
+        for _ in 0..MAX_LAYERS {
+            let layer = match next_layer(&self.config, ethertype, payload) {
+                Next::Done => return Some((ethertype, payload)),
+                Next::Unwrapped(layer, t, p) => {
+                    ethertype = t;
+                    payload = p;
+                    layer
+                },
+                Next::Failed(_) => {
+                    self.failed += 1;
+                    return None
+                },
+            };
+            match layer {
+                Layer::Gre => self.gre += 1,
+                Layer::Erspan => self.erspan += 1,
+                Layer::Mpls => self.mpls += 1,
+            }
+        }
+        self.failed += 1;
+        None
+    }
+
+    /// Layers unwrapped since the last call, and packets dropped.
+    pub fn take_report(&mut self) -> String {
+        let report = format!("decap gre {} erspan {} mpls {} failed {}",
+            self.gre, self.erspan, self.mpls, self.failed);
+        self.gre = 0;
+        self.erspan = 0;
+        self.mpls = 0;
+        self.failed = 0;
+        report
+    }
+}
+
+fn next_layer<'p>(config: &DecapConfig, ethertype: EtherType, payload: &'p [u8]) -> Next<'p> {
+    match ethertype {
+        ETHERTYPE_MPLS | ETHERTYPE_MPLS_MULTICAST if config.mpls => match mpls_payload(payload) {
+            Some((t, p)) => Next::Unwrapped(Layer::Mpls, t, p),
+            None => Next::Failed(Layer::Mpls),
+        },
+        EtherTypes::Ipv4 | EtherTypes::Ipv6 if config.gre || config.erspan => {
+            let gre = match gre_packet(ethertype, payload) {
+                Ok(Some(gre)) => gre,
+                Ok(None) => return Next::Done,
+                Err(()) => return Next::Failed(Layer::Gre),
+            };
+            let (protocol, flags, body) = match gre_payload(gre) {
+                Some(p) => p,
+                None => return Next::Failed(Layer::Gre),
+            };
+            match protocol {
+                EtherTypes::Ipv4 | EtherTypes::Ipv6 | ETHERTYPE_MPLS | ETHERTYPE_MPLS_MULTICAST if config.gre =>
+                    Next::Unwrapped(Layer::Gre, protocol, body),
+                ETHERTYPE_TEB if config.gre => match ethernet_payload(body) {
+                    Some((t, p)) => Next::Unwrapped(Layer::Gre, t, p),
+                    None => Next::Failed(Layer::Gre),
+                },
+                ETHERTYPE_ERSPAN_2 | ETHERTYPE_ERSPAN_3 if config.erspan => {
+                    match erspan_payload(protocol, flags, body).and_then(ethernet_payload) {
+                        Some((t, p)) => Next::Unwrapped(Layer::Erspan, t, p),
+                        None => Next::Failed(Layer::Erspan),
+                    }
+                },
+                _ => Next::Done,
+            }
+        },
+        _ => Next::Done,
+    }
+}
+
+// The GRE header and payload in an IP packet, None if it isn't GRE. Fragments
+// of GRE packets can't be unwrapped.
+fn gre_packet(ethertype: EtherType, ip: &[u8]) -> Result<Option<&[u8]>, ()> {
+    if ethertype == EtherTypes::Ipv4 {
+        if ip.len() < 20 || ip[0] >> 4 != 4 {
+            return Err(())
+        }
+        if IpNextHeaderProtocol(ip[9]) != IpNextHeaderProtocols::Gre {
+            return Ok(None)
+        }
+        let header_len = (ip[0] & 0x0f) as usize * 4;
+        let total_len = u16::from_be_bytes([ip[2], ip[3]]) as usize;
+        // more fragments, or a fragment offset
+        if ip[6] & 0x20 != 0 || u16::from_be_bytes([ip[6], ip[7]]) & 0x1fff != 0 {
+            return Err(())
+        }
+        if header_len < 20 || total_len < header_len || total_len > ip.len() {
+            return Err(())
+        }
+        return Ok(Some(&ip[header_len..total_len]))
+    }
+    if ip.len() < 40 || ip[0] >> 4 != 6 {
+        return Err(())
+    }
+    let end = 40 + u16::from_be_bytes([ip[4], ip[5]]) as usize;
+    if end > ip.len() {
+        return Err(())
+    }
+    match walk_v6_chain(IpNextHeaderProtocol(ip[6]), &ip[40..end]) {
+        Ok((IpNextHeaderProtocols::Gre, offset)) => Ok(Some(&ip[40 + offset..end])),
+        Ok(_) => Ok(None),
+        Err(_) => Err(()),
+    }
+}
+
+// The protocol type, flags and payload of a GRE packet.
+fn gre_payload(gre: &[u8]) -> Option<(EtherType, u8, &[u8])> {
+    if gre.len() < 4 {
+        return None
+    }
+    let flags = gre[0];
+    let protocol = EtherType(u16::from_be_bytes([gre[2], gre[3]]));
+    let mut len = 4;
+    for &flag in [GRE_C, GRE_K, GRE_S].iter() {
+        if flags & flag != 0 {
+            len += 4;
+        }
+    }
+    if gre.len() < len {
+        return None
+    }
+    Some((protocol, flags, &gre[len..]))
+}
+
+// The mirrored frame in an ERSPAN payload of GRE `protocol` with GRE `flags`.
+fn erspan_payload(protocol: EtherType, flags: u8, body: &[u8]) -> Option<&[u8]> {
+    let len = match protocol {
+        // type I has no sequence number and no header
+        ETHERTYPE_ERSPAN_2 if flags & GRE_S == 0 => 0,
+        ETHERTYPE_ERSPAN_2 => 8,
+        _ => match body.get(11) {
+            Some(b) if b & 0x01 != 0 => 20,
+            Some(_) => 12,
+            None => return None,
+        },
+    };
+    match body.len() >= len {
+        true => Some(&body[len..]),
+        false => None,
+    }
+}
+
+// The ethertype and payload of an Ethernet frame, past its VLAN tags.
+fn ethernet_payload(frame: &[u8]) -> Option<(EtherType, &[u8])> {
+    if frame.len() < 14 {
+        return None
+    }
+    let ethertype = EtherType(u16::from_be_bytes([frame[12], frame[13]]));
+    strip_tags(ethertype, &frame[14..]).map(|(t, _, p)| (t, p))
+}
+
+// The IP packet below an MPLS label stack.
+fn mpls_payload(labels: &[u8]) -> Option<(EtherType, &[u8])> {
+    let mut offset = 0;
+    loop {
+        if labels.len() < offset + 4 {
+            return None
+        }
+        let bottom = labels[offset + 2] & 0x01 != 0;
+        offset += 4;
+        if bottom {
+            break
+        }
+    }
+    let payload = &labels[offset..];
+    match payload.first().map(|b| b >> 4) {
+        Some(4) => Some((EtherTypes::Ipv4, payload)),
+        Some(6) => Some((EtherTypes::Ipv6, payload)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use decap::*;
+
+    // An IPv4 header for `payload` of `protocol`.
+    fn ipv4(protocol: u8, payload: &[u8]) -> Vec<u8> {
+        let len = (20 + payload.len()) as u16;
+        let mut p = vec![0x45, 0, (len >> 8) as u8, len as u8, 0, 0, 0x40, 0, 64, protocol, 0, 0,
+                         192, 0, 2, 1, 192, 0, 2, 2];
+        p.extend_from_slice(payload);
+        p
+    }
+
+    fn gre(flags: u8, protocol: u16, extra: usize, payload: &[u8]) -> Vec<u8> {
+        let mut p = vec![flags, 0, (protocol >> 8) as u8, protocol as u8];
+        p.extend(vec![0; extra]);
+        p.extend_from_slice(payload);
+        p
+    }
+
+    fn ethernet(ethertype: u16, payload: &[u8]) -> Vec<u8> {
+        let mut p = vec![0; 12];
+        p.extend_from_slice(&[(ethertype >> 8) as u8, ethertype as u8]);
+        p.extend_from_slice(payload);
+        p
+    }
+
+    #[test]
+    fn test_decap_layers() {
+        let inner = ipv4(6, &[0; 20]);
+        let all: DecapConfig = "gre, erspan,mpls".parse().unwrap();
+        assert_eq!(all, DecapConfig { gre: true, erspan: true, mpls: true });
+        assert!("gre,vxlan".parse::<DecapConfig>().is_err());
+        let mut decap = Decap::new(all.clone());
+
+        // plain traffic is passed as is
+        assert_eq!(decap.unwrap(EtherTypes::Ipv4, &inner), Some((EtherTypes::Ipv4, &inner[..])));
+
+        // GRE with a key, carrying IP
+        let outer = ipv4(47, &gre(GRE_K, 0x0800, 4, &inner));
+        assert_eq!(decap.unwrap(EtherTypes::Ipv4, &outer), Some((EtherTypes::Ipv4, &inner[..])));
+
+        // ERSPAN II and III (with a platform subheader) of a VLAN tagged frame
+        let frame = ethernet(0x8100, &[0, 5, 0x08, 0x00].iter().chain(inner.iter()).cloned().collect::<Vec<u8>>());
+        let outer = ipv4(47, &gre(GRE_S, 0x88be, 4, &[vec![0; 8], frame.clone()].concat()));
+        assert_eq!(decap.unwrap(EtherTypes::Ipv4, &outer), Some((EtherTypes::Ipv4, &inner[..])));
+        let mut header = vec![0; 20];
+        header[11] = 0x01;
+        let outer = ipv4(47, &gre(GRE_S, 0x22eb, 4, &[header, frame.clone()].concat()));
+        assert_eq!(decap.unwrap(EtherTypes::Ipv4, &outer), Some((EtherTypes::Ipv4, &inner[..])));
+        // type I
+        let outer = ipv4(47, &gre(0, 0x88be, 0, &frame));
+        assert_eq!(decap.unwrap(EtherTypes::Ipv4, &outer), Some((EtherTypes::Ipv4, &inner[..])));
+
+        // two MPLS labels, the second the bottom of the stack
+        let labels = [vec![0, 0x10, 0x00, 64, 0, 0x20, 0x01, 64], inner.clone()].concat();
+        assert_eq!(decap.unwrap(ETHERTYPE_MPLS, &labels), Some((EtherTypes::Ipv4, &inner[..])));
+        // and in GRE
+        let outer = ipv4(47, &gre(0, 0x8847, 0, &labels));
+        assert_eq!(decap.unwrap(EtherTypes::Ipv4, &outer), Some((EtherTypes::Ipv4, &inner[..])));
+
+        // truncated
+        assert_eq!(decap.unwrap(EtherTypes::Ipv4, &ipv4(47, &[0, 0])), None);
+        assert_eq!(decap.unwrap(ETHERTYPE_MPLS, &labels[..4]), None);
+        assert_eq!(decap.take_report(), "decap gre 2 erspan 3 mpls 2 failed 2");
+
+        // layers that aren't enabled are left alone
+        let mut decap = Decap::new("mpls".parse().unwrap());
+        let outer = ipv4(47, &gre(0, 0x88be, 0, &frame));
+        assert_eq!(decap.unwrap(EtherTypes::Ipv4, &outer), Some((EtherTypes::Ipv4, &outer[..])));
+        let mut decap = Decap::new("gre".parse().unwrap());
+        assert_eq!(decap.unwrap(EtherTypes::Ipv4, &outer), Some((EtherTypes::Ipv4, &outer[..])));
+        assert_eq!(decap.unwrap(ETHERTYPE_MPLS, &labels), Some((ETHERTYPE_MPLS, &labels[..])));
+        assert_eq!(decap.take_report(), "decap gre 0 erspan 0 mpls 0 failed 0");
+    }
+}
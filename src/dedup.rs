@@ -0,0 +1,228 @@
+//
+// Duplicate Packet Filter
+//
+// Some taps mirror a packet onto two capture queues, so two cores each see a
+// copy: it is counted twice in the stats, extends its session twice and, for
+// a phantom session, is forwarded twice. With DedupConfig set, the cores of a
+// pair (DETECTOR_DEDUP_PAIRS, e.g. the two queues a tap duplicates across)
+// share a DedupCache and drop a frame the other core (or they themselves)
+// saw within DedupConfig.window, before it is counted or parsed.
+//
+// - Frames are identified by a keyed hash of their length and first
+//   HASHED_BYTES bytes (past any GRE header), which cover the headers (IP ID,
+//   TCP sequence numbers, checksums) and the start of the payload. The key is
+//   drawn per cache, so frames can't be crafted to collide with others.
+// - The cache is a fixed array of slots, each an atomic word holding a tag
+//   (the hash above its low TIME_BITS) and the time the frame was seen, in
+//   microseconds modulo 2^TIME_BITS. Both cores swap their frames into it
+//   without locks. A slot taken over by another frame forgets the first, so
+//   a full cache misses duplicates rather than dropping distinct frames.
+// - A frame identical to one seen within the window is dropped even if the
+//   tap didn't duplicate it, so the window should stay well below the
+//   shortest retransmission timeout; the default is 1ms.
+//
+// Cores not in a pair don't filter. Reported per period: frames checked and
+// duplicates dropped.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex, Once, Weak};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use timekeeping::MonotonicNs;
+
+// Bytes of each frame hashed.
+const HASHED_BYTES: usize = 128;
+// Low bits of a slot hold the time, the rest the tag.
+const TIME_BITS: u32 = 20;
+const TIME_MASK: u64 = (1 << TIME_BITS) - 1;
+// Longest window the slot times can measure.
+pub const MAX_WINDOW_US: u64 = 1000 * 1000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DedupConfig
+{
+    // Time a frame is remembered for.
+    pub window: Duration,
+    // Slots in each pair's cache.
+    pub slots: usize,
+    // Pairs of cores (lcores) that share a cache.
+    pub pairs: Vec<(i32, i32)>,
+}
+
+impl Default for DedupConfig
+{
+    fn default() -> DedupConfig {
+        DedupConfig { window: Duration::from_millis(1), slots: 1 << 16, pairs: Vec::new() }
+    }
+}
+
+impl DedupConfig
+{
+    /// The core `lcore` shares its cache with, if it is in a pair.
+    pub fn partner(&self, lcore: i32) -> Option<i32> {
+        self.pairs.iter().find_map(|&(a, b)| {
+            if lcore == a {
+                Some(b)
+            } else if lcore == b {
+                Some(a)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+// Recently seen frames of one pair of cores.
+pub struct DedupCache
+{
+    slots: Vec<AtomicU64>,
+    window_us: u64,
+    key: RandomState,
+}
+
+impl DedupCache
+{
+    pub fn new(config: &DedupConfig) -> DedupCache {
+        let window_us = (config.window.as_micros() as u64).min(MAX_WINDOW_US);
+        DedupCache {
+            slots: (0..config.slots.max(1)).map(|_| AtomicU64::new(0)).collect(),
+            window_us: window_us,
+            key: RandomState::new(),
+        }
+    }
+
+    /// Whether `frame` was seen within the window before `now`, remembering
+    /// it as seen at `now` either way.
+    pub fn seen(&self, frame: &[u8], now: MonotonicNs) -> bool {
+        let mut hasher = self.key.build_hasher();
+        hasher.write_usize(frame.len());
+        hasher.write(&frame[..frame.len().min(HASHED_BYTES)]);
+        let hash = hasher.finish();
+
+        let slot = &self.slots[(hash % self.slots.len() as u64) as usize];
+        let tag = hash >> TIME_BITS;
+        let time = (now.0 / 1000) & TIME_MASK;
+        let old = slot.swap(tag << TIME_BITS | time, Ordering::Relaxed);
+        // an empty slot holds tag 0 at time 0, as good as any other frame
+        old >> TIME_BITS == tag && time.wrapping_sub(old & TIME_MASK) & TIME_MASK < self.window_us
+    }
+}
+
+// Caches of the pairs with a core running, found by the second core of each.
+type PairCaches = Vec<((i32, i32), Weak<DedupCache>)>;
+static CACHES_INIT: Once = Once::new();
+static mut CACHES: Option<Mutex<PairCaches>> = None;
+
+fn pair_caches() -> &'static Mutex<PairCaches> {
+    unsafe {
+        CACHES_INIT.call_once(|| { CACHES = Some(Mutex::new(Vec::new())); });
+        CACHES.as_ref().expect("dedup caches not set")
+    }
+}
+
+// One core's view of its pair's cache.
+pub struct Dedup
+{
+    cache: Arc<DedupCache>,
+    // Since the last take_report.
+    checked: u64,
+    duplicates: u64,
+}
+
+impl Dedup
+{
+    /// The filter of core `lcore`, sharing a cache with its partner, or None
+    /// if it isn't in a pair.
+    pub fn for_core(config: &DedupConfig, lcore: i32) -> Option<Dedup> {
+        let partner = config.partner(lcore)?;
+        let pair = (lcore.min(partner), lcore.max(partner));
+        let mut caches = pair_caches().lock().unwrap_or_else(|e| e.into_inner());
+        caches.retain(|c| c.1.strong_count() > 0);
+        let cache = match caches.iter().find(|c| c.0 == pair).and_then(|c| c.1.upgrade()) {
+            Some(cache) => cache,
+            None => {
+                let cache = Arc::new(DedupCache::new(config));
+                caches.push((pair, Arc::downgrade(&cache)));
+                cache
+            },
+        };
+        Some(Dedup::with_cache(cache))
+    }
+
+    pub fn with_cache(cache: Arc<DedupCache>) -> Dedup {
+        Dedup { cache: cache, checked: 0, duplicates: 0 }
+    }
+
+    /// Whether `frame`, received at `now`, duplicates one seen within the
+    /// window and should be dropped.
+    pub fn duplicate(&mut self, frame: &[u8], now: MonotonicNs) -> bool {
+        self.checked += 1;
+        let seen = self.cache.seen(frame, now);
+        if seen {
+            self.duplicates += 1;
+        }
+        seen
+    }
+
+    /// Frames checked and duplicates dropped since the last call, formatted
+    /// for the periodic report.
+    pub fn take_report(&mut self) -> String {
+        let report = format!("dedup checked {} duplicates {}", self.checked, self.duplicates);
+        self.checked = 0;
+        self.duplicates = 0;
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dedup::*;
+
+    const US: u64 = 1000;
+
+    #[test]
+    fn test_dedup_pair() {
+        let config = DedupConfig { pairs: vec![(2, 3), (4, 5)], ..DedupConfig::default() };
+        assert_eq!((config.partner(2), config.partner(5), config.partner(6)), (Some(3), Some(4), None));
+        assert!(Dedup::for_core(&config, 6).is_none());
+
+        let mut a = Dedup::for_core(&config, 2).unwrap();
+        let mut b = Dedup::for_core(&config, 3).unwrap();
+        let mut other = Dedup::for_core(&config, 4).unwrap();
+        let frame = [7u8; 200];
+        assert!(!a.duplicate(&frame, MonotonicNs(1000 * US)));
+        // the partner drops its copy, another pair doesn't see it
+        assert!(b.duplicate(&frame, MonotonicNs(1200 * US)));
+        assert!(!other.duplicate(&frame, MonotonicNs(1200 * US)));
+        assert_eq!(a.take_report(), "dedup checked 1 duplicates 0");
+        assert_eq!(b.take_report(), "dedup checked 1 duplicates 1");
+    }
+
+    #[test]
+    fn test_dedup_cache() {
+        let config = DedupConfig { window: Duration::from_micros(500), slots: 1024, pairs: Vec::new() };
+        let cache = DedupCache::new(&config);
+        let frame: Vec<u8> = (0..200).map(|i| i as u8).collect();
+        assert!(!cache.seen(&frame, MonotonicNs(10 * US)));
+        assert!(cache.seen(&frame, MonotonicNs(509 * US)));
+        // remembered from the last sighting, forgotten after the window
+        assert!(!cache.seen(&frame, MonotonicNs(1009 * US)));
+
+        // frames differing in their headers or length are distinct, past
+        // HASHED_BYTES only the length counts
+        let mut other = frame.clone();
+        other[20] ^= 1;
+        assert!(!cache.seen(&other, MonotonicNs(1010 * US)));
+        assert!(!cache.seen(&frame[..199], MonotonicNs(1010 * US)));
+        let mut tail = frame.clone();
+        tail[150] ^= 1;
+        assert!(cache.seen(&tail, MonotonicNs(1011 * US)));
+
+        // an empty cache holds no frames, whatever the time
+        let cache = DedupCache::new(&config);
+        let distinct = (0..512u32).filter(|i| !cache.seen(&i.to_be_bytes(), MonotonicNs(0))).count();
+        assert_eq!(distinct, 512);
+    }
+}
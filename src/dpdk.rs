@@ -0,0 +1,206 @@
+//
+// DPDK Capture
+//
+// Detectors built with the dpdk feature (make CAPTURE=dpdk) receive packets
+// from a DPDK poll-mode driver instead of PF_RING. The capture loop itself is
+// C (capture_dpdk.c, called from detect.c); this file holds the parts of it
+// that are configuration and reporting:
+//
+// - Each core process starts its own EAL as a DPDK multi-process instance
+//   (--proc-type=auto, sharing --file-prefix). The arguments are taken from
+//   DETECTOR_DPDK_EAL_ARGS as given, e.g. the PCI address of the port
+//   (-a 0000:3b:00.0) and hugepage settings, followed by the ones the detector
+//   needs: the core the process is pinned to and the process type, unless
+//   already given. See DpdkConfig::eal_args.
+// - The first process to start becomes the primary. It configures the port
+//   (DETECTOR_DPDK_PORT) with one RSS receive queue per core and starts it;
+//   the others wait for it and each polls the queue numbered like its core.
+//   RSS hashes the addresses under a symmetric key, so both directions of a
+//   flow reach the same core's flow tracker.
+//   The primary must outlive the secondaries, as it does when the detector is
+//   started and stopped as a whole.
+// - Packets are handed to the parser in place: rust_process_packet gets the
+//   address of the data in the mbuf, which is only freed once it returns.
+//   Frames too large for one mbuf (DpdkConfig.mbuf_size) would need a copy
+//   and are dropped and counted instead.
+// - Once per report period each process reports the packets and bytes it
+//   received on its queue, frames dropped for not fitting an mbuf, and the
+//   port's receive errors, frames missed for lack of descriptors and mbuf
+//   allocation failures (see DpdkRxStats).
+
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int};
+
+// Frames received in one burst.
+pub const RX_BURST: u16 = 32;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DpdkConfig
+{
+    // EAL arguments from the configuration, passed through as is.
+    pub eal_args: Vec<String>,
+    // Shared by every process of the detector, and only them.
+    pub file_prefix: String,
+    pub port: u16,
+    // Receive descriptors per queue.
+    pub rx_desc: u16,
+    // Mbufs in the pool, for all queues.
+    pub mbufs: u32,
+    // Data room of each mbuf, the largest frame received.
+    pub mbuf_size: u16,
+}
+
+impl Default for DpdkConfig
+{
+    fn default() -> DpdkConfig {
+        DpdkConfig {
+            eal_args: Vec::new(),
+            file_prefix: "conjure".to_string(),
+            port: 0,
+            rx_desc: 1024,
+            mbufs: 65535,
+            mbuf_size: 9216,
+        }
+    }
+}
+
+impl DpdkConfig
+{
+    /// Full EAL command line (program name first) of the process pinned to
+    /// `cpu`.
+    pub fn eal_args(&self, lcore: i32, cpu: i32) -> Vec<String> {
+        let mut args = vec![format!("conjure-{}", lcore)];
+        args.extend(self.eal_args.iter().cloned());
+        let given = |opt: &str| self.eal_args.iter().any(|a| a == opt || a.starts_with(&format!("{}=", opt)));
+        if !given("-l") && !given("--lcores") && !given("-c") {
+            args.push("-l".to_string());
+            args.push(cpu.to_string());
+        }
+        if !given("--proc-type") {
+            args.push("--proc-type=auto".to_string());
+        }
+        if !given("--file-prefix") {
+            args.push(format!("--file-prefix={}", self.file_prefix));
+        }
+        args
+    }
+}
+
+// Settings for capture_dpdk.c. argv and the strings it points to are never
+// freed, the EAL keeps pointers into them.
+#[repr(C)]
+pub struct DpdkSettings
+{
+    pub argc: c_int,
+    pub argv: *mut *mut c_char,
+    pub port: u16,
+    pub rx_desc: u16,
+    pub rx_burst: u16,
+    pub mbuf_size: u16,
+    pub mbufs: u32,
+}
+
+/// Settings of the process for core `lcore`, pinned to cpu `cpu`.
+pub fn settings(config: &DpdkConfig, lcore: i32, cpu: i32) -> DpdkSettings {
+    let mut argv: Vec<*mut c_char> = config.eal_args(lcore, cpu).into_iter()
+        .map(|a| CString::new(a).unwrap_or_default().into_raw())
+        .collect();
+    let argc = argv.len() as c_int;
+    // NULL terminated, like main's
+    argv.push(::std::ptr::null_mut());
+    let argv = Box::leak(argv.into_boxed_slice()).as_mut_ptr();
+    DpdkSettings {
+        argc: argc,
+        argv: argv,
+        port: config.port,
+        rx_desc: config.rx_desc,
+        rx_burst: RX_BURST,
+        mbuf_size: config.mbuf_size,
+        mbufs: config.mbufs,
+    }
+}
+
+// Running totals since the port was started, as read by capture_dpdk.c.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DpdkRxCounters
+{
+    // This process's queue.
+    pub packets: u64,
+    pub bytes: u64,
+    pub oversize: u64,
+    // The whole port.
+    pub errors: u64,
+    pub missed: u64,
+    pub no_mbuf: u64,
+}
+
+// Receive counts of one queue, reported per period.
+#[derive(Default)]
+pub struct DpdkRxStats
+{
+    last: DpdkRxCounters,
+}
+
+impl DpdkRxStats
+{
+    /// Report line for the counts of `queue` on `port` since the last call,
+    /// given the running totals.
+    pub fn report(&mut self, port: u16, queue: u16, totals: DpdkRxCounters) -> String {
+        let last = self.last;
+        self.last = totals;
+        format!("dpdk port {} queue {} rx packets {} bytes {} oversize {} port errors {} missed {} no mbuf {}",
+            port, queue,
+            totals.packets.wrapping_sub(last.packets),
+            totals.bytes.wrapping_sub(last.bytes),
+            totals.oversize.wrapping_sub(last.oversize),
+            totals.errors.wrapping_sub(last.errors),
+            totals.missed.wrapping_sub(last.missed),
+            totals.no_mbuf.wrapping_sub(last.no_mbuf))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dpdk::*;
+    use std::ffi::CStr;
+
+    #[test]
+    fn test_dpdk_eal_args() {
+        let config = DpdkConfig {
+            eal_args: vec!["-a".to_string(), "0000:3b:00.0".to_string(), "--socket-mem=2048".to_string()],
+            ..DpdkConfig::default()
+        };
+        assert_eq!(config.eal_args(2, 6), vec!["conjure-2", "-a", "0000:3b:00.0", "--socket-mem=2048",
+            "-l", "6", "--proc-type=auto", "--file-prefix=conjure"]);
+
+        // what the configuration gives isn't overridden
+        let config = DpdkConfig {
+            eal_args: vec!["--lcores=4".to_string(), "--proc-type".to_string(), "secondary".to_string()],
+            file_prefix: "conjure-test".to_string(),
+            ..DpdkConfig::default()
+        };
+        assert_eq!(config.eal_args(0, 4), vec!["conjure-0", "--lcores=4", "--proc-type", "secondary",
+            "--file-prefix=conjure-test"]);
+
+        let s = settings(&config, 0, 4);
+        assert_eq!(s.argc, 5);
+        let argv = unsafe { ::std::slice::from_raw_parts(s.argv, 6) };
+        assert_eq!(unsafe { CStr::from_ptr(argv[4]) }.to_str().unwrap(), "--file-prefix=conjure-test");
+        assert!(argv[5].is_null());
+        assert_eq!((s.port, s.rx_burst), (0, RX_BURST));
+    }
+
+    #[test]
+    fn test_dpdk_rx_stats() {
+        let mut stats = DpdkRxStats::default();
+        let mut totals = DpdkRxCounters { packets: 10, bytes: 6000, oversize: 0, errors: 1, missed: 5, no_mbuf: 0 };
+        assert_eq!(stats.report(0, 3, totals),
+            "dpdk port 0 queue 3 rx packets 10 bytes 6000 oversize 0 port errors 1 missed 5 no mbuf 0");
+        totals.packets += 4;
+        totals.bytes += 400;
+        totals.oversize += 1;
+        assert_eq!(stats.report(0, 3, totals),
+            "dpdk port 0 queue 3 rx packets 4 bytes 400 oversize 1 port errors 0 missed 0 no mbuf 0");
+    }
+}
@@ -0,0 +1,178 @@
+//
+// Encapsulation Header
+//
+// Without help the application has to work out which registration each
+// forwarded packet belongs to from its addresses, as the detector did. Built
+// with the encap-header feature, the detector instead hands every forwarded
+// packet over with a small header naming its session. Such frames can't go up
+// the kernel's IP stack: they are written into the tun device as EtherType
+// 0x88b5 (IEEE 802 local experimental), which the stack ignores, and the
+// application reads them from a packet socket bound to the device with that
+// protocol. This module's encoder and decoder are public for it to reuse.
+//
+// Header, version 1, 16 bytes, integers big endian, followed by the IP packet:
+//
+//   0       version (1)
+//   1       header length, 16 for version 1; decoders skip anything past the
+//           fields they know
+//   2       transport (TransportType value)
+//   3       flags: CONFIRMED, a transport detector confirmed the session;
+//           DECOY, a decoy mode session; NO_SESSION, the packet (a later
+//           fragment) couldn't be tied to a session and the other fields are
+//           zero
+//   4..8    session affinity hash (see affinity.rs)
+//   8..16   session id, as in the detector's logs and events
+//
+// Later versions only add fields at the end and raise the header length, so
+// a version 1 decoder reads any of them.
+
+use std::fmt;
+
+use protobuf::ProtobufEnum;
+
+use sessions::SessionEntry;
+use signalling::SessionMode;
+
+pub const VERSION: u8 = 1;
+pub const HEADER_LEN: usize = 16;
+/// EtherType of encapsulated frames on the tun device.
+pub const ETHERTYPE: u16 = 0x88b5;
+
+pub const FLAG_CONFIRMED: u8 = 0x01;
+pub const FLAG_DECOY: u8 = 0x02;
+pub const FLAG_NO_SESSION: u8 = 0x04;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct EncapHeader
+{
+    pub transport: u8,
+    pub flags: u8,
+    pub affinity: u32,
+    pub session_id: u64,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum EncapError
+{
+    Truncated,
+    // Version 0, or a header length too short for version 1's fields.
+    BadHeader,
+}
+
+impl fmt::Display for EncapError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EncapError::Truncated => write!(f, "truncated encapsulation header"),
+            EncapError::BadHeader => write!(f, "bad encapsulation header"),
+        }
+    }
+}
+
+impl EncapHeader
+{
+    /// Header of a packet of the session `entry`.
+    pub fn for_session(entry: &SessionEntry) -> EncapHeader {
+        let mut flags = 0;
+        if entry.confirmed {
+            flags |= FLAG_CONFIRMED;
+        }
+        if entry.details.mode == SessionMode::Decoy {
+            flags |= FLAG_DECOY;
+        }
+        EncapHeader {
+            transport: entry.details.transport.value() as u8,
+            flags: flags,
+            affinity: entry.affinity,
+            session_id: entry.session_id,
+        }
+    }
+
+    /// Header of a packet that couldn't be tied to a session.
+    pub fn no_session() -> EncapHeader {
+        EncapHeader { flags: FLAG_NO_SESSION, ..EncapHeader::default() }
+    }
+
+    /// Appends the header to `buf`.
+    pub fn encode(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&[VERSION, HEADER_LEN as u8, self.transport, self.flags]);
+        buf.extend_from_slice(&self.affinity.to_be_bytes());
+        buf.extend_from_slice(&self.session_id.to_be_bytes());
+    }
+
+    /// The header at the start of `frame`, and the packet following it.
+    pub fn decode(frame: &[u8]) -> Result<(EncapHeader, &[u8]), EncapError> {
+        if frame.len() < 2 {
+            return Err(EncapError::Truncated)
+        }
+        let len = frame[1] as usize;
+        if frame[0] == 0 || len < HEADER_LEN {
+            return Err(EncapError::BadHeader)
+        }
+        if frame.len() < len {
+            return Err(EncapError::Truncated)
+        }
+        let mut affinity = [0; 4];
+        affinity.copy_from_slice(&frame[4..8]);
+        let mut session_id = [0; 8];
+        session_id.copy_from_slice(&frame[8..16]);
+        let header = EncapHeader {
+            transport: frame[2],
+            flags: frame[3],
+            affinity: u32::from_be_bytes(affinity),
+            session_id: u64::from_be_bytes(session_id),
+        };
+        Ok((header, &frame[len..]))
+    }
+
+    /// The tun frame `frame` (packet information then an IP packet) as an
+    /// encapsulated frame with this header.
+    pub fn wrap(&self, frame: &[u8]) -> Vec<u8> {
+        let mut wrapped = Vec::with_capacity(frame.len() + HEADER_LEN);
+        // same packet information flags, new protocol
+        wrapped.extend_from_slice(frame.get(..2).unwrap_or(&[0, 0]));
+        wrapped.extend_from_slice(&ETHERTYPE.to_be_bytes());
+        self.encode(&mut wrapped);
+        wrapped.extend_from_slice(frame.get(4..).unwrap_or(&[]));
+        wrapped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use encap::*;
+
+    #[test]
+    fn test_encap_header() {
+        let header = EncapHeader {
+            transport: 2,
+            flags: FLAG_CONFIRMED,
+            affinity: 0xdead_beef,
+            session_id: 0x0102_0304_0506_0708,
+        };
+        let mut buf = Vec::new();
+        header.encode(&mut buf);
+        assert_eq!(buf, vec![1, 16, 2, 1, 0xde, 0xad, 0xbe, 0xef, 1, 2, 3, 4, 5, 6, 7, 8]);
+        buf.extend_from_slice(&[0x45, 0]);
+        assert_eq!(EncapHeader::decode(&buf), Ok((header, &[0x45, 0][..])));
+
+        // longer headers of later versions are skipped
+        let mut v2 = buf[..16].to_vec();
+        v2[0] = 2;
+        v2[1] = 20;
+        v2.extend_from_slice(&[9, 9, 9, 9, 0x60]);
+        assert_eq!(EncapHeader::decode(&v2), Ok((header, &[0x60][..])));
+
+        assert_eq!(EncapHeader::decode(&buf[..10]), Err(EncapError::Truncated));
+        assert_eq!(EncapHeader::decode(&[1]), Err(EncapError::Truncated));
+        assert_eq!(EncapHeader::decode(&[0, 16]), Err(EncapError::BadHeader));
+        assert_eq!(EncapHeader::decode(&[1, 8, 0, 0, 0, 0, 0, 0]), Err(EncapError::BadHeader));
+
+        // the tun frame's packet information gives way to the encapsulation
+        let wrapped = EncapHeader::no_session().wrap(&[0x00, 0x01, 0x08, 0x00, 0x45, 0]);
+        assert_eq!(&wrapped[..4], &[0x00, 0x01, 0x88, 0xb5]);
+        let (decoded, pkt) = EncapHeader::decode(&wrapped[4..]).unwrap();
+        assert_eq!(decoded.flags, FLAG_NO_SESSION);
+        assert_eq!(pkt, &[0x45, 0]);
+    }
+}
@@ -0,0 +1,135 @@
+//
+// Detector Errors
+//
+// Failures that cross module boundaries (reading registrations from redis,
+// parsing protobufs, opening the tun device, forwarding packets, passing
+// registrations on over ZMQ, tearing down conntrack entries, answering
+// unmatched connections) are reported as a DetectorError. Each variant keeps
+// the underlying error as its source, so callers and tests can match on the
+// kind of failure while logs still show the whole chain (error_chain).
+//
+// Errors that only concern one message (a registration with a bad address, a
+// packet that can't be forwarded) are logged and the message is skipped; the
+// packet path never stops because of them.
+
+use std::error::Error as StdError;
+use std::io;
+
+use protobuf::ProtobufError;
+use redis::RedisError;
+use thiserror::Error;
+use zmq;
+
+use sessions::SessionError;
+
+#[derive(Debug, Error)]
+pub enum DetectorError {
+    #[error("invalid registration")]
+    Session(#[from] SessionError),
+
+    #[error("can't parse {message}")]
+    Parse { message: &'static str, #[source] source: ProtobufError },
+
+    #[error("can't serialize {message}")]
+    Serialize { message: &'static str, #[source] source: ProtobufError },
+
+    #[error("redis {context} failed")]
+    Redis { context: String, #[source] source: RedisError },
+
+    // The tun crate's errors don't implement std::error::Error, so only their
+    // description is kept.
+    #[error("can't open {device}: {reason}")]
+    Capture { device: String, reason: String },
+
+    #[error("can't forward packet into tun: {0}")]
+    Forward(String),
+
+    #[error("can't send registration over ZMQ")]
+    Zmq(#[from] zmq::Error),
+
+    #[error("conntrack {context} failed")]
+    Conntrack { context: &'static str, #[source] source: io::Error },
+
+    #[error("control listener {context} failed")]
+    Control { context: &'static str, #[source] source: io::Error },
+
+    #[error("invalid control message: {0}")]
+    ControlMessage(&'static str),
+
+    // Boxed so the variant exists without the kafka feature.
+    #[error("kafka {context} failed")]
+    Kafka { context: String, #[source] source: Box<dyn StdError + Send + Sync> },
+
+    // Boxed so the variant exists without the json-registrations feature.
+    #[error("JSON registration {context} failed")]
+    Json { context: String, #[source] source: Box<dyn StdError + Send + Sync> },
+
+    #[error("unmatched {context} failed")]
+    Unmatched { context: &'static str, #[source] source: io::Error },
+
+    #[error("xdp filter {context} failed")]
+    Xdp { context: &'static str, #[source] source: io::Error },
+
+    #[error("fwmark {context} failed")]
+    Fwmark { context: &'static str, #[source] source: io::Error },
+
+    #[error("probe {context} failed")]
+    Probe { context: &'static str, #[source] source: io::Error },
+
+    #[error("proxy ring {context} failed")]
+    ProxyRing { context: &'static str, #[source] source: io::Error },
+
+    #[error("session export {context} {path} failed")]
+    SessionExport { context: &'static str, path: String, #[source] source: io::Error },
+}
+
+pub type DetectorResult<T> = Result<T, DetectorError>;
+
+impl DetectorError
+{
+    pub fn redis(context: &str, source: RedisError) -> DetectorError {
+        DetectorError::Redis { context: context.to_string(), source: source }
+    }
+}
+
+/// `err` followed by each of its sources, e.g. "redis subscribe to
+/// dark_decoy_map failed: connection refused".
+pub fn error_chain(err: &dyn StdError) -> String {
+    let mut out = err.to_string();
+    let mut source = err.source();
+    while let Some(e) = source {
+        out.push_str(": ");
+        out.push_str(&e.to_string());
+        source = e.source();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use error::*;
+    use protobuf::Message;
+    use signalling::StationToDetector;
+
+    #[test]
+    fn test_error_chain() {
+        let err = DetectorError::from(SessionError::MixedV4V6Error);
+        match err {
+            DetectorError::Session(SessionError::MixedV4V6Error) => {},
+            ref e => panic!("unexpected {:?}", e),
+        }
+        assert_eq!(error_chain(&err), "invalid registration: Client/Phantom v4/v6 mismatch");
+
+        let source = match StationToDetector::parse_from_bytes(&[0xff, 0xff, 0xff]) {
+            Err(e) => e,
+            Ok(_) => panic!("parsed garbage"),
+        };
+        let err = DetectorError::Parse { message: "StationToDetector", source: source };
+        assert!(err.source().is_some());
+        assert!(error_chain(&err).starts_with("can't parse StationToDetector: "));
+
+        let err = DetectorError::Forward("down".to_string());
+        assert!(err.source().is_none());
+        assert_eq!(error_chain(&err), "can't forward packet into tun: down");
+    }
+}
@@ -0,0 +1,280 @@
+//
+// Detector Feedback
+//
+// Events the detector reports back to the station application are sent as
+// DetectorToStation protobufs published on a redis channel. Publishing happens
+// on a background thread so the packet processing path only ever does a
+// non-blocking push onto a bounded queue; if the queue is full (or redis is
+// unavailable) events are dropped and counted rather than delaying packets.
+//
+// The same publisher carries session replication messages to peer detectors
+// (see replication.rs).
+//
+// Besides per-connection SessionMatched events, once per report period the
+// detector sends a NeverMatchedSummary of the sessions that expired without a
+// client ever connecting (see never_matched_event, its counts bucketed and
+// noised as configured, see stats_privacy.rs), and a SessionExpired event
+// with the traffic totals of each one that expired after a client connected
+// (see session_expired_event). The sessions of a dual-stack registration are
+// reported as one, under their group_id. Alerts on the tracked session count are sent
+// as they happen, see session_alerts.rs.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::thread;
+
+use ipnetwork::IpNetwork;
+use protobuf::{Message, ProtobufEnum};
+use redis;
+
+use sessions::{NeverMatchedKey, SessionEntry};
+use signalling::{DetectorEvent, DetectorToStation, NeverMatchedCount};
+use stats_privacy::StatsPrivacy;
+
+pub const DETECTOR_EVENT_CHANNEL: &'static str = "detector_to_station";
+
+// Phantom subnet of the never matched counts withheld by StatsPrivacy.
+pub const OTHER_BUCKET: &'static str = "other";
+
+// Events buffered for the publishing thread before new ones are dropped.
+const EVENT_QUEUE_LEN: usize = 4096;
+
+pub struct EventPublisher
+{
+    tx: SyncSender<Vec<u8>>,
+
+    // Events that could not be queued since the last call to take_dropped.
+    dropped: u64,
+}
+
+impl EventPublisher
+{
+    /// Starts the publishing thread for `channel` on the redis server at `url`.
+    pub fn spawn(url: &str, channel: &str) -> EventPublisher {
+        let (tx, rx) = sync_channel(EVENT_QUEUE_LEN);
+        let url = url.to_string();
+        let channel = channel.to_string();
+        thread::spawn(move || { publish_events(&url, &channel, rx) });
+        EventPublisher::from_sender(tx)
+    }
+
+    /// Publisher that hands serialized events to `tx` instead of redis.
+    pub fn from_sender(tx: SyncSender<Vec<u8>>) -> EventPublisher {
+        EventPublisher {
+            tx: tx,
+            dropped: 0,
+        }
+    }
+
+    pub fn publish<M: Message>(&mut self, event: &M) {
+        let payload = match event.write_to_bytes() {
+            Ok(b) => b,
+            Err(e) => {
+                warn!("Failed to serialize {}: {}", event.descriptor().name(), e);
+                self.dropped += 1;
+                return
+            },
+        };
+        match self.tx.try_send(payload) {
+            Ok(_) => {},
+            Err(TrySendError::Full(_)) | Err(TrySendError::Disconnected(_)) => {
+                self.dropped += 1;
+            },
+        }
+    }
+
+    /// Number of events dropped since the last call.
+    pub fn take_dropped(&mut self) -> u64 {
+        let dropped = self.dropped;
+        self.dropped = 0;
+        dropped
+    }
+}
+
+/// Summary of the sessions in `expired` (see ExpiryStats::take_never_matched)
+/// with their phantoms grouped into the subnets given by `subnet_of`, and the
+/// counts passed through `privacy`. Buckets it withholds are merged into one
+/// of its own, with the phantom subnet "other" and no transport or
+/// registration source. None if there are none.
+pub fn never_matched_event<F>(expired: HashMap<NeverMatchedKey, u64>, subnet_of: F,
+                              privacy: &mut StatsPrivacy) -> Option<DetectorToStation>
+    where F: Fn(&IpAddr) -> Option<IpNetwork>
+{
+    if expired.is_empty() {
+        return None
+    }
+    let mut counts: HashMap<_, u64> = HashMap::new();
+    for ((transport, source, phantom), n) in expired.into_iter() {
+        let subnet = subnet_of(&phantom).map(|net| net.to_string()).unwrap_or_default();
+        *counts.entry((transport, source, subnet)).or_insert(0) += n;
+    }
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|a, b| (a.0).2.cmp(&(b.0).2)
+        .then((a.0).1.value().cmp(&(b.0).1.value()))
+        .then((a.0).0.value().cmp(&(b.0).0.value())));
+    // None is the bucket of the withheld ones
+    let counts = privacy.bucket(counts.into_iter().map(|(k, n)| (Some(k), n)).collect(), None);
+    if counts.is_empty() {
+        return None
+    }
+
+    let mut event = DetectorToStation::new();
+    event.set_event(DetectorEvent::NeverMatchedSummary);
+    for (key, n) in counts.into_iter() {
+        let mut count = NeverMatchedCount::new();
+        match key {
+            Some((transport, source, subnet)) => {
+                count.set_transport(transport);
+                count.set_registration_source(source);
+                count.set_phantom_subnet(subnet);
+            },
+            None => count.set_phantom_subnet(OTHER_BUCKET.to_string()),
+        }
+        count.set_sessions(n);
+        event.mut_never_matched().push(count);
+    }
+    Some(event)
+}
+
+/// SessionExpired event for `entry`, a session dropped after being matched.
+pub fn session_expired_event(entry: &SessionEntry) -> DetectorToStation {
+    let mut event = DetectorToStation::new();
+    event.set_event(DetectorEvent::SessionExpired);
+    event.set_session_id(entry.session_id);
+    event.set_phantom_ip(entry.details.phantom_ip.to_string());
+    event.set_phantom_port(entry.details.phantom_port);
+    event.set_client_ip(entry.details.client_ip.to_string());
+    event.set_registration_source(entry.details.registration_source);
+    event.set_affinity(entry.affinity);
+    event.set_connections(entry.connections);
+    event.set_packets(entry.packets);
+    event.set_bytes(entry.bytes);
+    if let Some(group) = entry.details.group {
+        event.set_group_id(group);
+    }
+    event
+}
+
+// Runs until the publisher is dropped, or gives up if redis is unreachable.
+fn publish_events(url: &str, channel: &str, rx: Receiver<Vec<u8>>) {
+    let con = match redis::Client::open(url).and_then(|c| c.get_connection()) {
+        Ok(con) => con,
+        Err(e) => {
+            error!("detector events disabled, can't connect to redis: {}", e);
+            return
+        },
+    };
+
+    for payload in rx.iter() {
+        let res: redis::RedisResult<i64> = redis::cmd("PUBLISH").arg(channel).arg(payload).query(&con);
+        if let Err(e) = res {
+            debug!("Error publishing detector event: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use feedback::*;
+    use signalling::{DetectorEvent, DetectorToStation, RegistrationSource, TransportType};
+    use sessions::{SessionDetails, SessionEntry};
+    use stats_privacy::StatsPrivacyConfig;
+    use timekeeping::MonotonicNs;
+    use protobuf::Message;
+    use std::time::Duration;
+
+    #[test]
+    fn test_event_publisher_queue() {
+        let (tx, rx) = sync_channel(1);
+        let mut publisher = EventPublisher::from_sender(tx);
+
+        let mut event = DetectorToStation::new();
+        event.set_event(DetectorEvent::SessionMatched);
+        event.set_session_id(0x1234);
+
+        publisher.publish(&event);
+        // queue is full, second event is dropped instead of blocking
+        publisher.publish(&event);
+        assert_eq!(publisher.take_dropped(), 1);
+        assert_eq!(publisher.take_dropped(), 0);
+
+        let received: DetectorToStation = Message::parse_from_bytes(&rx.recv().unwrap()).unwrap();
+        assert_eq!(received.get_event(), DetectorEvent::SessionMatched);
+        assert_eq!(received.get_session_id(), 0x1234);
+
+        drop(rx);
+        publisher.publish(&event);
+        assert_eq!(publisher.take_dropped(), 1);
+    }
+
+    #[test]
+    fn test_never_matched_event() {
+        let subnet: IpNetwork = "10.10.0.0/16".parse().unwrap();
+        let subnet_of = |ip: &IpAddr| if subnet.contains(*ip) { Some(subnet) } else { None };
+        let mut privacy = StatsPrivacy::new(StatsPrivacyConfig::default());
+        assert!(never_matched_event(HashMap::new(), subnet_of, &mut privacy).is_none());
+
+        let mut expired = HashMap::new();
+        let api = RegistrationSource::API;
+        expired.insert((TransportType::Min, api, "10.10.0.1".parse().unwrap()), 2);
+        expired.insert((TransportType::Min, api, "10.10.5.5".parse().unwrap()), 3);
+        expired.insert((TransportType::Obfs4, api, "10.10.0.1".parse().unwrap()), 1);
+        expired.insert((TransportType::Min, api, "192.0.2.1".parse().unwrap()), 4);
+
+        let event = never_matched_event(expired.clone(), subnet_of, &mut privacy).unwrap();
+        assert_eq!(event.get_event(), DetectorEvent::NeverMatchedSummary);
+        let counts: Vec<(TransportType, String, u64)> = event.get_never_matched().iter()
+            .map(|c| (c.get_transport(), c.get_phantom_subnet().to_string(), c.get_sessions()))
+            .collect();
+        assert_eq!(counts, vec![
+            (TransportType::Min, "".to_string(), 4),
+            (TransportType::Min, "10.10.0.0/16".to_string(), 5),
+            (TransportType::Obfs4, "10.10.0.0/16".to_string(), 1),
+        ]);
+
+        // small buckets are merged, or left out when the merged one is small
+        let config = StatsPrivacyConfig { min_bucket: 5, epsilon: None };
+        let mut privacy = StatsPrivacy::new(config);
+        let event = never_matched_event(expired.clone(), subnet_of, &mut privacy).unwrap();
+        let counts: Vec<(TransportType, String, u64)> = event.get_never_matched().iter()
+            .map(|c| (c.get_transport(), c.get_phantom_subnet().to_string(), c.get_sessions()))
+            .collect();
+        assert_eq!(counts, vec![
+            (TransportType::Min, "10.10.0.0/16".to_string(), 5),
+            (TransportType::Null, OTHER_BUCKET.to_string(), 5),
+        ]);
+        // which isn't taken for sessions of the Null transport
+        let other = &event.get_never_matched()[1];
+        assert!(!other.has_transport() && !other.has_registration_source());
+        assert!(event.get_never_matched()[0].has_transport());
+        let config = StatsPrivacyConfig { min_bucket: 11, epsilon: None };
+        let mut privacy = StatsPrivacy::new(config);
+        assert!(never_matched_event(expired, subnet_of, &mut privacy).is_none());
+    }
+
+    #[test]
+    fn test_session_expired_event() {
+        let details = SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_nanos(1000)).unwrap();
+        let entry = SessionEntry {
+            session_id: 0x1234,
+            connections: 3,
+            packets: 40,
+            bytes: 30000,
+            affinity: 0xa5,
+            ..SessionEntry::new(details, MonotonicNs(1000))
+        };
+        let event = session_expired_event(&entry);
+        assert_eq!(event.get_event(), DetectorEvent::SessionExpired);
+        assert_eq!(event.get_session_id(), 0x1234);
+        assert_eq!(event.get_phantom_ip(), "10.10.0.1");
+        assert_eq!(event.get_phantom_port(), 443);
+        assert_eq!((event.get_connections(), event.get_packets(), event.get_bytes()), (3, 40, 30000));
+        assert_eq!(event.get_affinity(), 0xa5);
+        assert!(!event.has_group_id());
+
+        let mut grouped = entry;
+        grouped.details.group = Some(0x77);
+        assert_eq!(session_expired_event(&grouped).get_group_id(), 0x77);
+    }
+}
@@ -1,14 +1,25 @@
-use std::collections::{HashSet, VecDeque};
-use time::precise_time_ns;
+use std::cmp;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::mem;
 
 use std::net::{IpAddr, SocketAddr};
-use pnet::packet::tcp::TcpPacket;
+use pnet::packet::Packet;
+use pnet::packet::tcp::{TcpFlags, TcpPacket};
+use rand::{self, Rng, XorShiftRng};
 use pnet::packet::udp::UdpPacket;
 
 use util::IpPacket;
 use std::fmt;
 
-use sessions::SessionTracker;
+use sessions::{SessionConfig, SessionEntry, SessionTracker};
+use timekeeping::{Clock, MonotonicNs};
+use hashing::MapHasher;
+use client_id::ClientId;
+use transport_detector::{Verdict, FIRST_FLIGHT_SEGMENTS};
+use tls_fingerprint::TlsFingerprint;
+use quic::DcidHash;
+use syn_filter::SynFilter;
+use negative_cache::NegativeCache;
 
 // All members are stored in host-order, even src_ip and dst_ip.
 #[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
@@ -22,17 +33,17 @@ pub struct Flow
 
 // flow log client should only ever be set at initialization so this should
 // never result in a race condition. Also all threads read the same
-// environment variable so they will all set it the same.  
+// environment variable so they will all set it the same. Clients are logged
+// by their ClientId, never by address.
 pub static mut FLOW_CLIENT_LOG: bool = false;
 impl fmt::Display for Flow {
 
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let socket_src = SocketAddr::new(self.src_ip, self.src_port);
         let socket_dst = SocketAddr::new(self.dst_ip, self.dst_port);
 
         unsafe {
             match FLOW_CLIENT_LOG {
-                true => write!(f, "{} -> {}",socket_src, socket_dst),
+                true => write!(f, "{}:{} -> {}", ClientId::new(self.src_ip), self.src_port, socket_dst),
                 false => write!(f, "_ -> {}", socket_dst),
             }
         }
@@ -115,12 +126,11 @@ pub struct FlowNoSrcPort
 
 impl fmt::Display for FlowNoSrcPort {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let socket_src = SocketAddr::new(self.src_ip, 0);
         let socket_dst = SocketAddr::new(self.dst_ip, self.dst_port);
 
         unsafe {
             match FLOW_CLIENT_LOG {
-                true => write!(f, "{} -> {}",socket_src, socket_dst),
+                true => write!(f, "{} -> {}", ClientId::new(self.src_ip), socket_dst),
                 false => write!(f, "_ -> {}", socket_dst),
             }
         }
@@ -166,8 +176,7 @@ impl FlowNoSrcPort {
 
 pub struct SchedEvent
 {
-    // Nanoseconds since an unspecified epoch (precise_time_ns()).
-    drop_time: u64,
+    drop_time: MonotonicNs,
     flow: Flow,
 }
 
@@ -176,7 +185,7 @@ pub struct FlowTracker
     // Keys present in this map are potentially tagged flows.
     // Key not present in map => sure flow isn't of interest. Ignore all non-SYN packets.
     // Key present, value InTLSHandshake => don't yet know if it's of interest yet
-    tracked_flows: HashSet<Flow>,
+    tracked_flows: HashSet<Flow, MapHasher>,
 
     // stale_drops_tracked is used to periodically drop idle flows that are being tracked.
     stale_drops_tracked: VecDeque<SchedEvent>,
@@ -185,12 +194,181 @@ pub struct FlowTracker
     // Map values are timeouts, which are used to drop stale dark decoys
     pub phantom_flows: SessionTracker,
     // pub phantom_flows: Arc<RwLock<HashMap<IpAddr, u64>>>,
+
+    // Connections to phantoms that are counted against their session's
+    // connection limit, with their state and the time a packet was last
+    // forwarded for each.
+    phantom_conns: HashMap<Flow, PhantomConn, MapHasher>,
+    // Phantom connections opened in their SYN, in the order they were opened,
+    // for the flow limit to evict. Some have left their SYN or closed since,
+    // they are skipped when evicting and pruned with idle connections.
+    syn_conns: VecDeque<Flow>,
+    // Phantom connections refused at their session's connection limit (or
+    // the flow limit), with when a packet was last seen for each, so their
+    // later packets are refused too. Dropped after the SYN timeout idle.
+    refused_conns: HashMap<Flow, MonotonicNs, MapHasher>,
+    // Connections seen past their SYN in asymmetric mode with one packet
+    // carrying payload so far, and when, opened by their next (see
+    // open_phantom_midstream). Dropped after the SYN timeout idle.
+    midstream_pending: HashMap<Flow, MonotonicNs, MapHasher>,
+    // The payload so far of phantom connections the transport detectors need
+    // more of, with the sequence number the next TCP segment should have.
+    held_payloads: HashMap<Flow, (Vec<u8>, Option<u32>), MapHasher>,
+
+    timeouts: FlowTimeouts,
+    gc: GcStats,
+
+    // Bound on the flows above, if any, and what it evicted and refused.
+    limit: Option<FlowLimit>,
+    evictions: EvictionStats,
+    rng: XorShiftRng,
+
+    // Where candidates' SYNs are noted instead, if enabled (see syn_filter.rs).
+    syn_filter: Option<SynFilter>,
+
+    // Flows recently found in no session, if enabled (see negative_cache.rs).
+    negative_cache: Option<NegativeCache>,
+
+    // Furthest the client's sequence numbers may stray from a phantom
+    // connection's window, None if they aren't checked, and the packets
+    // outside it since the last report.
+    seq_window: Option<u32>,
+    seq_rejects: u64,
+
+    // Only the client to phantom direction is captured (see set_asymmetric),
+    // and the phantom connections opened past their SYN since the last
+    // report.
+    asymmetric: bool,
+    midstream_opens: u64,
+
+    // Shared with phantom_flows so both age out against the same time.
+    clock: Clock,
 }
 
-// Amount of time that we timeout all flows
-const TIMEOUT_TRACKED_NS: u64 = 30 * 1000 * 1000 * 1000;
-//const FIN_TIMEOUT_NS: u64 = 2*1000*1000*1000;
+/// How long flows may go without a packet before they are dropped, by state.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FlowTimeouts
+{
+    // Flows still in their handshake: registration candidates waiting for
+    // their first app data packet (counted from the SYN), and phantom
+    // connections that have only sent a SYN.
+    pub syn_ns: u64,
+    // Phantom connections past the SYN.
+    pub established_ns: u64,
+    // Phantom connections whose client sent a FIN. They keep counting
+    // towards their session's connection limit until then.
+    pub fin_ns: u64,
+    // UDP flows to phantoms.
+    pub udp_ns: u64,
+}
 
+impl Default for FlowTimeouts
+{
+    fn default() -> FlowTimeouts {
+        FlowTimeouts {
+            syn_ns: 30 * 1000 * 1000 * 1000,
+            established_ns: 300 * 1000 * 1000 * 1000,
+            fin_ns: 2 * 1000 * 1000 * 1000,
+            udp_ns: 60 * 1000 * 1000 * 1000,
+        }
+    }
+}
+
+impl FlowTimeouts
+{
+    /// The defaults in asymmetric mode (see FlowTracker::set_asymmetric).
+    /// The phantom's FIN, and whether it accepted a RST, go unseen, so a
+    /// connection the client closed waits longer for its last ACKs of the
+    /// phantom's data before it stops counting.
+    pub fn asymmetric() -> FlowTimeouts {
+        FlowTimeouts {
+            fin_ns: 10 * 1000 * 1000 * 1000,
+            ..FlowTimeouts::default()
+        }
+    }
+}
+
+/// Bound on the flows tracked, registration candidates and phantom
+/// connections together, so a SYN flood can't grow the tables without limit.
+///
+/// A new flow that would exceed max_flows evicts a candidate, the oldest
+/// (Fifo, by its SYN) or one picked at random (Random). Flows of tracked
+/// sessions are kept over candidates: a phantom connection still in its SYN
+/// is only evicted, oldest first, for another session's flow, once no
+/// candidate is left. Established phantom connections are never evicted; with
+/// nothing to evict the new flow is refused, a phantom connection as if its
+/// session were at its connection limit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FlowLimit
+{
+    pub max_flows: usize,
+    pub eviction: Eviction,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Eviction
+{
+    Fifo,
+    Random,
+}
+
+// Candidates picked at random before Random eviction falls back to the
+// oldest, as the picks can land on flows already dropped.
+const RANDOM_EVICTION_TRIES: usize = 4;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum ConnState
+{
+    Syn,
+    Established,
+    FinWait,
+    Udp,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct PhantomConn
+{
+    state: ConnState,
+    last_seen: MonotonicNs,
+    // Forwarded so far, added to the session when the connection closes.
+    packets: u64,
+    bytes: u64,
+    // Its first payload was shown to the transport detectors, and one of
+    // them rejected it.
+    inspected: bool,
+    rejected: bool,
+    // Segments its payload was held over for the transport detectors.
+    held_segments: u8,
+    // Sequence number following the client's furthest segment so far, None
+    // until its first TCP packet is checked (see check_phantom_seq).
+    next_seq: Option<u32>,
+    // ISN of the phantom's SYN-ACK, None until it is seen, and whether the
+    // client has acknowledged it.
+    server_isn: Option<u32>,
+    handshake_acked: bool,
+    // A UDP flow the phantom answered, or that opened with a QUIC Initial,
+    // so the client is talking to the phantom rather than probing it.
+    confirmed: bool,
+}
+
+// Flows dropped for being idle since the last report, by state.
+#[derive(Default)]
+struct GcStats
+{
+    syn: u64,
+    established: u64,
+    fin: u64,
+    udp: u64,
+}
+
+// Flows evicted and refused by the flow limit since the last report.
+#[derive(Default)]
+struct EvictionStats
+{
+    candidate: u64,
+    session_syn: u64,
+    refused: u64,
+}
 
 
 
@@ -198,25 +376,182 @@ impl FlowTracker
 {
     pub fn new() -> FlowTracker
     {
+        FlowTracker::with_clock(Clock::Monotonic)
+    }
 
-        let ret = FlowTracker
-            {
-                tracked_flows: HashSet::new(),
-                phantom_flows: SessionTracker::new(),
-                stale_drops_tracked: VecDeque::with_capacity(16384),
-            };
+    pub fn with_clock(clock: Clock) -> FlowTracker
+    {
+        FlowTracker::with_config(SessionConfig::default(), clock)
+    }
 
+    pub fn with_config(session_config: SessionConfig, clock: Clock) -> FlowTracker
+    {
+        let mut ret = FlowTracker::without_threads(session_config, clock);
+
+        // if configured, replicate sessions with peer detectors (before the
+        // ingest thread starts so it publishes what it accepts)
+        ret.phantom_flows.spawn_replication();
+        // and to apply the configuration the station pushes
+        ret.phantom_flows.spawn_station_config();
+        // and to import sessions handed off by another detector
+        ret.phantom_flows.spawn_handoff_import();
         // launch thread to ingest from redis
         ret.phantom_flows.spawn_update_thread();
+        // and, if configured, from the station's control messages
+        ret.phantom_flows.spawn_control_listener();
+        // if configured, one to tear down the connections of expired sessions
+        ret.phantom_flows.spawn_conntrack_teardown();
+        // and one to drop stale sessions
+        ret.phantom_flows.spawn_maintenance_thread();
+        // if configured, one to export session records
+        ret.phantom_flows.spawn_session_export();
         ret
     }
-    pub fn begin_tracking_flow(&mut self, flow: &Flow)
+
+    /// A FlowTracker that starts none of the session tracker's threads: no
+    /// registrations are ingested but those added directly, and stale sessions
+    /// are only dropped by drop_all_stale_flows (see simulation.rs).
+    pub fn without_threads(session_config: SessionConfig, clock: Clock) -> FlowTracker
+    {
+        let hash = session_config.hash;
+        FlowTracker
+            {
+                tracked_flows: HashSet::with_hasher(MapHasher::new(hash)),
+                phantom_flows: SessionTracker::with_config(session_config, clock.clone()),
+                stale_drops_tracked: VecDeque::with_capacity(16384),
+                phantom_conns: HashMap::with_hasher(MapHasher::new(hash)),
+                syn_conns: VecDeque::new(),
+                refused_conns: HashMap::with_hasher(MapHasher::new(hash)),
+                midstream_pending: HashMap::with_hasher(MapHasher::new(hash)),
+                held_payloads: HashMap::with_hasher(MapHasher::new(hash)),
+                timeouts: FlowTimeouts::default(),
+                gc: GcStats::default(),
+                limit: None,
+                evictions: EvictionStats::default(),
+                rng: rand::weak_rng(),
+                syn_filter: None,
+                negative_cache: None,
+                seq_window: None,
+                seq_rejects: 0,
+                asymmetric: false,
+                midstream_opens: 0,
+                clock: clock,
+            }
+    }
+
+    pub fn set_timeouts(&mut self, timeouts: FlowTimeouts)
     {
+        self.timeouts = timeouts;
+    }
+
+    pub fn timeouts(&self) -> &FlowTimeouts
+    {
+        &self.timeouts
+    }
+
+    /// Checks the client's sequence numbers on phantom connections against
+    /// a window of `window` bytes either side of what it sent so far, see
+    /// check_phantom_seq. None doesn't check them.
+    pub fn set_seq_window(&mut self, window: Option<u32>)
+    {
+        self.seq_window = window;
+    }
+
+    pub fn seq_window(&self) -> Option<u32>
+    {
+        self.seq_window
+    }
+
+    /// Asymmetric mode, for captures that only see packets from clients to
+    /// phantoms. The handshake and the phantom's side of a connection can't
+    /// be confirmed there, so:
+    ///
+    /// - a phantom connection whose SYN wasn't captured is opened by the
+    ///   client's second packet with payload (see open_phantom_midstream);
+    /// - the client's sequence numbers aren't checked, whatever the window
+    ///   set (see check_phantom_seq);
+    /// - a client RST doesn't close the connection at once, it waits out the
+    ///   FIN timeout like a FIN (see reset_phantom_connection).
+    ///
+    /// Timeouts are left as set, FlowTimeouts::asymmetric has the defaults
+    /// for this mode.
+    pub fn set_asymmetric(&mut self, asymmetric: bool)
+    {
+        self.asymmetric = asymmetric;
+    }
+
+    pub fn is_asymmetric(&self) -> bool
+    {
+        self.asymmetric
+    }
+
+    pub fn set_limit(&mut self, limit: Option<FlowLimit>)
+    {
+        self.limit = limit;
+    }
+
+    pub fn limit(&self) -> Option<&FlowLimit>
+    {
+        self.limit.as_ref()
+    }
+
+    /// Notes registration candidates' SYNs in a SynFilter of `bits` bits per
+    /// generation, remembering them for the SYN timeout, rather than tracking
+    /// them right away (see track_progress).
+    pub fn enable_syn_filter(&mut self, bits: usize)
+    {
+        self.syn_filter = Some(SynFilter::new(bits, self.timeouts.syn_ns, self.clock.now()));
+    }
+
+    /// Remembers up to `slots` flows found in no phantom session, skipping
+    /// their lookups until a session is added (see is_phantom_flow).
+    pub fn enable_negative_cache(&mut self, slots: usize)
+    {
+        self.negative_cache = Some(NegativeCache::new(slots));
+    }
+
+    /// Called for the SYN of a registration candidate, with its sequence
+    /// number. Its flow is tracked, or with a SYN filter only noted in it.
+    pub fn begin_tracking_flow(&mut self, flow: &Flow, syn_seq: u32)
+    {
+        if let Some(ref mut filter) = self.syn_filter {
+            if !self.tracked_flows.contains(flow) {
+                filter.insert(flow, syn_seq.wrapping_add(1), self.clock.now());
+                return
+            }
+        }
+        self.track_candidate(flow)
+    }
+
+    /// Called for the later packets of a possible registration candidate,
+    /// with their sequence number: whether its flow is tracked, tracking it
+    /// now if its SYN was noted in the SYN filter and `seq` follows it (the
+    /// handshake's ACK or the first data, not packets after the flow stopped
+    /// being tracked).
+    pub fn track_progress(&mut self, flow: &Flow, seq: u32) -> bool
+    {
+        if self.tracked_flows.contains(flow) {
+            return true
+        }
+        let now = self.clock.now();
+        if !self.syn_filter.as_mut().map_or(false, |f| f.promote(flow, seq, now)) {
+            return false
+        }
+        self.track_candidate(flow);
+        self.tracked_flows.contains(flow)
+    }
+
+    fn track_candidate(&mut self, flow: &Flow)
+    {
+        if !self.tracked_flows.contains(flow) && !self.make_room(false) {
+            self.evictions.refused += 1;
+            return
+        }
         // Always push back, even if the entry was already there. Doesn't hurt
         // to do a second check on overdueness, and this is simplest.
         self.stale_drops_tracked.push_back(
             SchedEvent {
-                drop_time: precise_time_ns() + TIMEOUT_TRACKED_NS,
+                drop_time: self.clock.now().saturating_add(self.timeouts.syn_ns),
                 flow: *flow,
             });
         // Begin tracking as a potential TD flow (if not already in the set).
@@ -229,6 +564,32 @@ impl FlowTracker
         self.phantom_flows.is_tracked_session(flow)
     }
 
+    /// Whether `flow` matches a phantom session, its source port included
+    /// (see SessionTracker::is_tracked_flow). With the negative cache, flows
+    /// that matched none since the last session was added aren't looked up
+    /// again.
+    pub fn is_phantom_flow(&mut self, flow: &Flow) -> bool
+    {
+        let cache = match self.negative_cache {
+            Some(ref mut cache) => cache,
+            None => return self.phantom_flows.is_tracked_flow(flow),
+        };
+        let generation = self.phantom_flows.insert_generation();
+        if cache.untracked(flow, generation) {
+            return false
+        }
+        let tracked = self.phantom_flows.is_tracked_flow(flow);
+        if !tracked {
+            cache.insert(flow, generation);
+        }
+        tracked
+    }
+
+    pub fn get_phantom_session(&self, flow: &FlowNoSrcPort) -> Option<SessionEntry>
+    {
+        self.phantom_flows.get_session(flow)
+    }
+
     pub fn is_tracked_flow(&self, flow: &Flow) -> bool
     {
         self.tracked_flows.contains(&flow)
@@ -237,9 +598,387 @@ impl FlowTracker
     /// used to update (increase) the time that we  consider a session 
     /// valid for tracking purposes. Called when packets from a session are
     /// seen so that forwarding continues past the original registration timeout. 
-    pub fn update_phantom_flow(&mut self, flow: &FlowNoSrcPort)
+    /// Only packets of a phantom connection past its SYN, or of a confirmed
+    /// UDP flow (see confirm_phantom_udp_flow), extend the session, unless
+    /// the session config extends on any packet, so unsolicited scans of the
+    /// phantom don't keep it alive.
+    pub fn update_phantom_flow(&mut self, flow: &Flow)
+    {
+        if !self.phantom_flows.config().extend_on_any_packet {
+            match self.phantom_conns.get(flow) {
+                Some(conn) if conn.state == ConnState::Udp && !conn.confirmed => return,
+                Some(conn) if conn.state != ConnState::Syn => {},
+                _ => return,
+            }
+        }
+        self.phantom_flows.update_session(&FlowNoSrcPort::from_flow(flow))
+    }
+
+    /// Called for the client's SYN of a connection to a registered phantom.
+    /// Returns false if the session is at its connection limit (or the flow
+    /// limit is reached, see FlowLimit), in which case the connection should
+    /// not be forwarded. Retransmitted SYNs for a
+    /// connection that is already open are not counted again.
+    pub fn open_phantom_connection(&mut self, flow: &Flow) -> bool
+    {
+        self.open_phantom_flow(flow, ConnState::Syn)
+    }
+
+    /// Called for every UDP packet to a registered phantom. Returns false if
+    /// the flow is new and the session is at its connection limit (or the
+    /// flow limit is reached), in which case the packet should not be
+    /// forwarded.
+    pub fn open_phantom_udp_flow(&mut self, flow: &Flow) -> bool
+    {
+        self.open_phantom_flow(flow, ConnState::Udp)
+    }
+
+    /// Called in asymmetric mode for a packet other than the SYN (or a RST)
+    /// of a phantom connection that isn't open, its SYN having gone
+    /// uncaptured, carrying `payload_len` bytes. The connection opens past
+    /// its SYN with its second packet carrying payload, returning false as
+    /// open_phantom_connection does. Until then it doesn't count against the
+    /// session's connections, so a scanner's lone or empty packets can't use
+    /// them up. Always true, opening nothing, outside asymmetric mode.
+    pub fn open_phantom_midstream(&mut self, flow: &Flow, payload_len: usize) -> bool
+    {
+        if !self.asymmetric || payload_len == 0 || self.phantom_conns.contains_key(flow) {
+            return true
+        }
+        let now = self.clock.now();
+        if self.midstream_pending.insert(*flow, now).is_none() {
+            return true
+        }
+        self.midstream_pending.remove(flow);
+        if !self.open_phantom_flow(flow, ConnState::Established) {
+            return false
+        }
+        self.midstream_opens += 1;
+        true
+    }
+
+    fn open_phantom_flow(&mut self, flow: &Flow, state: ConnState) -> bool
+    {
+        let now = self.clock.now();
+        if let Some(conn) = self.phantom_conns.get_mut(flow) {
+            conn.last_seen = now;
+            return true
+        }
+        if !self.make_room(true) {
+            self.evictions.refused += 1;
+            self.refused_conns.insert(*flow, now);
+            return false
+        }
+        if !self.phantom_flows.open_connection(&FlowNoSrcPort::from_flow(flow)) {
+            self.refused_conns.insert(*flow, now);
+            return false
+        }
+        self.refused_conns.remove(flow);
+        self.phantom_conns.insert(*flow, PhantomConn {
+            state: state,
+            last_seen: now,
+            packets: 0,
+            bytes: 0,
+            inspected: false,
+            rejected: false,
+            held_segments: 0,
+            next_seq: None,
+            server_isn: None,
+            handshake_acked: false,
+            confirmed: false,
+        });
+        if state == ConnState::Syn {
+            self.syn_conns.push_back(*flow);
+        }
+        true
+    }
+
+    /// The phantom session `flow` belongs to, if this is its first match
+    /// (see SessionTracker::latch_match).
+    pub fn latch_phantom_match(&self, flow: &FlowNoSrcPort) -> Option<SessionEntry>
+    {
+        self.phantom_flows.latch_match(flow)
+    }
+
+    pub fn is_phantom_connection(&self, flow: &Flow) -> bool
+    {
+        self.phantom_conns.contains_key(flow)
+    }
+
+    /// Whether `flow` was refused a phantom connection (see
+    /// open_phantom_connection) and hasn't been opened since, noting a packet
+    /// seen for it if so.
+    pub fn is_refused_phantom_connection(&mut self, flow: &Flow) -> bool
+    {
+        match self.refused_conns.get_mut(flow) {
+            Some(last_seen) => {
+                *last_seen = self.clock.now();
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Called for every other packet forwarded for a phantom connection.
+    pub fn touch_phantom_connection(&mut self, flow: &Flow)
+    {
+        if let Some(conn) = self.phantom_conns.get_mut(flow) {
+            if conn.state == ConnState::Syn {
+                conn.state = ConnState::Established;
+            }
+            conn.last_seen = self.clock.now();
+        }
+    }
+
+    /// Called for every packet forwarded for a phantom connection, with its
+    /// length (IP).
+    pub fn count_phantom_packet(&mut self, flow: &Flow, bytes: usize)
+    {
+        if let Some(conn) = self.phantom_conns.get_mut(flow) {
+            conn.packets += 1;
+            conn.bytes += bytes as u64;
+        }
+    }
+
+    /// Whether the UDP flow `flow` has been confirmed, see
+    /// confirm_phantom_udp_flow.
+    pub fn is_confirmed_phantom_udp_flow(&self, flow: &Flow) -> bool
+    {
+        match self.phantom_conns.get(flow) {
+            Some(conn) => conn.confirmed,
+            None => false,
+        }
+    }
+
+    /// Called for a UDP flow to a phantom whose packet carried a QUIC
+    /// Initial, or with the client's flow for a UDP packet the phantom sent
+    /// back (see note_phantom_udp_reply). A scan's probes get neither, and
+    /// only once confirmed do the flow's packets extend its session.
+    pub fn confirm_phantom_udp_flow(&mut self, flow: &Flow)
+    {
+        if let Some(conn) = self.phantom_conns.get_mut(flow) {
+            if conn.state == ConnState::Udp {
+                conn.confirmed = true;
+            }
+        }
+    }
+
+    /// Called for a UDP packet that isn't to a registered phantom, on
+    /// `reply`. If it is the reverse of a phantom's UDP flow, the phantom
+    /// answered its client and the flow is confirmed. Returns whether it
+    /// was.
+    pub fn note_phantom_udp_reply(&mut self, reply: &Flow) -> bool
+    {
+        if self.phantom_conns.is_empty() {
+            return false
+        }
+        let client = Flow::from_parts(reply.dst_ip, reply.src_ip, reply.dst_port, reply.src_port);
+        match self.phantom_conns.get(&client) {
+            Some(conn) if conn.state == ConnState::Udp => {},
+            _ => return false,
+        }
+        self.confirm_phantom_udp_flow(&client);
+        true
+    }
+
+    /// Called for a SYN-ACK from a phantom (the station answering for it)
+    /// on `reply`, the reverse of the client's flow, noting its ISN for
+    /// check_phantom_seq. Returns whether it answers a phantom connection's
+    /// SYN. Nothing is noted unless sequence numbers are checked.
+    pub fn note_phantom_syn_ack(&mut self, reply: &Flow, isn: u32) -> bool
+    {
+        if self.seq_window.is_none() || self.asymmetric {
+            return false
+        }
+        let client = Flow::from_parts(reply.dst_ip, reply.src_ip, reply.dst_port, reply.src_port);
+        match self.phantom_conns.get_mut(&client) {
+            Some(conn) if conn.state == ConnState::Syn => {
+                conn.server_isn = Some(isn);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// Whether a TCP packet from the client of a phantom connection is within
+    /// the connection's sequence window: from `window` bytes behind the end
+    /// of the client's furthest segment (retransmissions) to `window` bytes
+    /// past it. The window starts at the client's SYN, restarting with
+    /// another SYN only while the connection is still in its SYN, and
+    /// follows the packets within it. Until one of them acknowledges the
+    /// phantom's SYN-ACK (see note_phantom_syn_ack), up to `window` bytes
+    /// past its ISN, the client's packets carrying an ACK are outside it too.
+    /// Packets outside it are likely spoofed, by someone who didn't see the
+    /// handshake, and shouldn't count as the connection's activity. True if
+    /// sequence numbers aren't checked (in asymmetric mode they never are,
+    /// the SYN may be missed) or the flow isn't a phantom connection.
+    pub fn check_phantom_seq(&mut self, flow: &Flow, tcp_pkt: &TcpPacket) -> bool
+    {
+        let window = match self.seq_window {
+            Some(w) if !self.asymmetric => cmp::min(w, i32::MAX as u32) as i32,
+            _ => return true,
+        };
+        let conn = match self.phantom_conns.get_mut(flow) {
+            Some(c) => c,
+            None => return true,
+        };
+        let flags = tcp_pkt.get_flags();
+        let seq = tcp_pkt.get_sequence();
+        // SYN and FIN take a sequence number each
+        let len = tcp_pkt.payload().len() as u32
+            + (flags & TcpFlags::SYN != 0) as u32
+            + (flags & TcpFlags::FIN != 0) as u32;
+        let end = seq.wrapping_add(len);
+        // a connection starts (or, until past its SYN, restarts) with the
+        // client's SYN; later SYNs are checked like any other packet
+        let restart = flags & TcpFlags::SYN != 0 && flags & TcpFlags::ACK == 0
+            && conn.state == ConnState::Syn;
+        let next = match conn.next_seq {
+            Some(_) if restart => end,
+            Some(next) => {
+                let offset = end.wrapping_sub(next) as i32;
+                if offset < -window || offset > window {
+                    self.seq_rejects += 1;
+                    return false
+                }
+                if offset > 0 { end } else { next }
+            },
+            None => end,
+        };
+        if flags & TcpFlags::ACK != 0 && !conn.handshake_acked {
+            let acked = conn.server_isn.map_or(false, |isn| {
+                let offset = tcp_pkt.get_acknowledgement().wrapping_sub(isn.wrapping_add(1)) as i32;
+                offset >= 0 && offset <= window
+            });
+            if !acked {
+                self.seq_rejects += 1;
+                return false
+            }
+            conn.handshake_acked = true;
+        }
+        conn.next_seq = Some(next);
+        true
+    }
+
+    /// True the first time it is called for a phantom connection, for a
+    /// packet with payload, when that payload should go to the transport
+    /// detectors.
+    pub fn first_phantom_payload(&mut self, flow: &Flow) -> bool
+    {
+        match self.phantom_conns.get_mut(flow) {
+            Some(ref mut conn) if !conn.inspected => {
+                conn.inspected = true;
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// The payload to show the transport detectors for a phantom connection
+    /// sending `payload` (at `seq`, for TCP): the payload itself if it is the
+    /// `first`, or if they need more (see hold_phantom_payload), the payload
+    /// held so far followed by it. None otherwise, or for a TCP segment that
+    /// doesn't follow the held payload (a retransmission, or out of order).
+    pub fn phantom_payload_to_inspect(&mut self, flow: &Flow, first: bool, seq: Option<u32>, payload: &[u8]) -> Option<Vec<u8>>
+    {
+        if first {
+            return Some(payload.to_vec())
+        }
+        let (mut held, next_seq) = self.held_payloads.remove(flow)?;
+        if seq.is_some() && seq != next_seq {
+            self.held_payloads.insert(*flow, (held, next_seq));
+            return None
+        }
+        held.extend_from_slice(payload);
+        Some(held)
+    }
+
+    /// Holds `payload`, all a phantom connection sent so far, as the
+    /// transport detectors need more of it; its next TCP segment should be at
+    /// `next_seq`. False, holding nothing, once the connection is past its
+    /// first flight (FIRST_FLIGHT_SEGMENTS), when it should be rejected.
+    pub fn hold_phantom_payload(&mut self, flow: &Flow, payload: Vec<u8>, next_seq: Option<u32>) -> bool
+    {
+        let conn = match self.phantom_conns.get_mut(flow) {
+            Some(conn) => conn,
+            None => return false,
+        };
+        conn.held_segments = conn.held_segments.saturating_add(1);
+        if conn.held_segments >= FIRST_FLIGHT_SEGMENTS {
+            self.held_payloads.remove(flow);
+            return false
+        }
+        self.held_payloads.insert(*flow, (payload, next_seq));
+        true
+    }
+
+    /// Stops forwarding a phantom connection a transport detector rejected.
+    /// It still counts against its session until it is closed.
+    pub fn reject_phantom_connection(&mut self, flow: &Flow)
+    {
+        if let Some(conn) = self.phantom_conns.get_mut(flow) {
+            conn.rejected = true;
+        }
+    }
+
+    pub fn is_rejected_phantom_connection(&self, flow: &Flow) -> bool
     {
-        self.phantom_flows.update_session(flow)
+        self.phantom_conns.get(flow).map_or(false, |conn| conn.rejected)
+    }
+
+    /// Records a transport detector's verdict on a phantom connection in its
+    /// session.
+    pub fn apply_phantom_verdict(&mut self, flow: &Flow, verdict: Verdict)
+    {
+        if verdict == Verdict::Reject {
+            self.reject_phantom_connection(flow);
+        }
+        self.phantom_flows.apply_verdict(&FlowNoSrcPort::from_flow(flow), verdict)
+    }
+
+    /// Records the fingerprint of a phantom connection's ClientHello in its
+    /// session.
+    pub fn set_phantom_fingerprint(&mut self, flow: &Flow, fingerprint: TlsFingerprint)
+    {
+        self.phantom_flows.set_tls_fingerprint(&FlowNoSrcPort::from_flow(flow), fingerprint)
+    }
+
+    /// Records the destination connection ID hash of a phantom connection's
+    /// QUIC Initial in its session.
+    pub fn set_phantom_dcid_hash(&mut self, flow: &Flow, hash: DcidHash)
+    {
+        self.phantom_flows.set_quic_dcid_hash(&FlowNoSrcPort::from_flow(flow), hash)
+    }
+
+    /// Called when the client sends FIN. The connection is closed once it has
+    /// been idle for the FIN timeout.
+    pub fn fin_phantom_connection(&mut self, flow: &Flow)
+    {
+        if let Some(conn) = self.phantom_conns.get_mut(flow) {
+            conn.state = ConnState::FinWait;
+            conn.last_seen = self.clock.now();
+        }
+    }
+
+    /// Called when the client sends RST. The connection is closed, or in
+    /// asymmetric mode, where the RST may be spoofed and whether the phantom
+    /// accepted it goes unseen, treated as if the client sent FIN.
+    pub fn reset_phantom_connection(&mut self, flow: &Flow)
+    {
+        if self.asymmetric {
+            self.fin_phantom_connection(flow)
+        } else {
+            self.close_phantom_connection(flow)
+        }
+    }
+
+    /// Closes a phantom connection, adding what it forwarded to its session.
+    pub fn close_phantom_connection(&mut self, flow: &Flow)
+    {
+        self.held_payloads.remove(flow);
+        if let Some(conn) = self.phantom_conns.remove(flow) {
+            self.phantom_flows.close_connection_with(&FlowNoSrcPort::from_flow(flow), conn.packets, conn.bytes);
+        }
     }
 
     pub fn stop_tracking_flow(&mut self, flow: &Flow)
@@ -247,9 +986,70 @@ impl FlowTracker
         self.tracked_flows.remove(flow);
     }
 
+    // Makes room for a new flow, of a tracked session if `session`, when the
+    // flows are at their limit. False if nothing could be evicted for it.
+    fn make_room(&mut self, session: bool) -> bool
+    {
+        let max_flows = match self.limit {
+            Some(ref l) => l.max_flows,
+            None => return true,
+        };
+        if self.tracked_flows.len() + self.phantom_conns.len() < max_flows {
+            return true
+        }
+        if self.evict_candidate() {
+            self.evictions.candidate += 1;
+            return true
+        }
+        if session && self.evict_syn_connection() {
+            self.evictions.session_syn += 1;
+            return true
+        }
+        false
+    }
+
+    // Stops tracking a registration candidate, see FlowLimit.
+    fn evict_candidate(&mut self) -> bool
+    {
+        if self.tracked_flows.is_empty() {
+            return false
+        }
+        if self.limit.as_ref().map(|l| l.eviction) == Some(Eviction::Random) {
+            for _ in 0..RANDOM_EVICTION_TRIES {
+                let i = self.rng.gen_range(0, self.stale_drops_tracked.len());
+                if self.tracked_flows.remove(&self.stale_drops_tracked[i].flow) {
+                    return true
+                }
+            }
+        }
+        // entries are in SYN order, some for flows already dropped
+        while let Some(event) = self.stale_drops_tracked.pop_front() {
+            if self.tracked_flows.remove(&event.flow) {
+                return true
+            }
+        }
+        false
+    }
+
+    // Closes the phantom connection still in its SYN that was opened first.
+    fn evict_syn_connection(&mut self) -> bool
+    {
+        // entries are in SYN order, some for flows past their SYN or closed
+        while let Some(flow) = self.syn_conns.pop_front() {
+            match self.phantom_conns.get(&flow) {
+                Some(conn) if conn.state == ConnState::Syn => {
+                    self.close_phantom_connection(&flow);
+                    return true
+                },
+                _ => {},
+            }
+        }
+        false
+    }
+
     // drop_stale_tracked_flows returns the number of tracked flows that it drops.
     fn drop_stale_tracked_flows(&mut self) -> usize {
-        let right_now = precise_time_ns();
+        let right_now = self.clock.now();
         let num_tracked_flows_before = self.tracked_flows.len();
         loop {
             let flow = match self.stale_drops_tracked.front() {
@@ -271,7 +1071,9 @@ impl FlowTracker
                     // entries in stale_drops_tracked are supposed to be sorted by time, so
                     // once we see a flow that doesn't need to be removed, then
                     // there is no need to check further
-                    return num_tracked_flows_before - self.tracked_flows.len();
+                    let dropped = num_tracked_flows_before - self.tracked_flows.len();
+                    self.gc.syn += dropped as u64;
+                    return dropped;
                 }
             }
         }
@@ -280,14 +1082,51 @@ impl FlowTracker
     // drop_stale_phantom_flows returns the number of registered dark decoy
     // flows that it drops. 
     fn drop_stale_phantom_flows(&mut self) -> usize {
+        if self.phantom_flows.is_maintained() {
+            return 0
+        }
         self.phantom_flows.drop_stale_sessions()
     }
 
+    // drop_idle_phantom_conns returns the number of phantom connections that
+    // had been idle longer than their state's timeout and were closed.
+    fn drop_idle_phantom_conns(&mut self) -> usize {
+        let now = self.clock.now();
+        let timeouts = &self.timeouts;
+        let idle: Vec<(Flow, ConnState)> = self.phantom_conns.iter()
+            .filter(|&(_, conn)| {
+                let timeout = match conn.state {
+                    ConnState::Syn => timeouts.syn_ns,
+                    ConnState::Established => timeouts.established_ns,
+                    ConnState::FinWait => timeouts.fin_ns,
+                    ConnState::Udp => timeouts.udp_ns,
+                };
+                conn.last_seen.saturating_add(timeout) <= now
+            })
+            .map(|(flow, conn)| (*flow, conn.state))
+            .collect();
+        for &(ref flow, state) in idle.iter() {
+            self.close_phantom_connection(flow);
+            match state {
+                ConnState::Syn => self.gc.syn += 1,
+                ConnState::Established => self.gc.established += 1,
+                ConnState::FinWait => self.gc.fin += 1,
+                ConnState::Udp => self.gc.udp += 1,
+            }
+        }
+        let syn_ns = self.timeouts.syn_ns;
+        self.refused_conns.retain(|_, last_seen| last_seen.saturating_add(syn_ns) > now);
+        self.midstream_pending.retain(|_, seen| seen.saturating_add(syn_ns) > now);
+        let conns = &self.phantom_conns;
+        self.syn_conns.retain(|flow| conns.get(flow).map_or(false, |conn| conn.state == ConnState::Syn));
+        idle.len()
+    }
+
     // This function returns the number of flows that it drops.
     #[allow(non_snake_case)]
     pub fn drop_all_stale_flows(&mut self) -> usize
     {
-        self.drop_stale_tracked_flows() + self.drop_stale_phantom_flows()
+        self.drop_stale_tracked_flows() + self.drop_idle_phantom_conns() + self.drop_stale_phantom_flows()
     }
 
     pub fn count_tracked_flows(&self) -> usize
@@ -298,14 +1137,69 @@ impl FlowTracker
     {
         self.phantom_flows.len()
     }
+    pub fn count_phantom_connections(&self) -> usize
+    {
+        self.phantom_conns.len()
+    }
+
+    /// Flows dropped for being idle since the last report, by state.
+    pub fn take_gc_report(&mut self) -> String
+    {
+        let gc = mem::replace(&mut self.gc, GcStats::default());
+        format!("flow gc dropped syn {} established {} fin {} udp {}",
+            gc.syn, gc.established, gc.fin, gc.udp)
+    }
+
+    /// Packets of phantom connections outside their sequence window since
+    /// the last call.
+    pub fn take_seq_rejects(&mut self) -> u64
+    {
+        mem::replace(&mut self.seq_rejects, 0)
+    }
+
+    /// Phantom connections opened past their SYN in asymmetric mode since the
+    /// last call.
+    pub fn take_midstream_opens(&mut self) -> u64
+    {
+        mem::replace(&mut self.midstream_opens, 0)
+    }
+
+    /// Negative cache counts since the last report, None if it isn't enabled.
+    pub fn take_negative_cache_report(&mut self) -> Option<String>
+    {
+        self.negative_cache.as_mut().map(|c| c.take_report())
+    }
+
+    /// SYN filter counts since the last report, None if it isn't enabled.
+    pub fn take_syn_filter_report(&mut self) -> Option<String>
+    {
+        self.syn_filter.as_mut().map(|f| f.take_report())
+    }
+
+    /// Flows evicted (registration candidates, and phantom connections in
+    /// their SYN) and refused for the flow limit since the last report.
+    pub fn take_eviction_report(&mut self) -> String
+    {
+        let evictions = mem::replace(&mut self.evictions, EvictionStats::default());
+        format!("flow limit evicted candidate {} session syn {} refused {}",
+            evictions.candidate, evictions.session_syn, evictions.refused)
+    }
 }
 
 
 #[cfg(test)]
 mod tests {
-    use flow_tracker::{FlowNoSrcPort, Flow};
+    use flow_tracker::{Eviction, FlowNoSrcPort, Flow, FlowLimit, FlowTimeouts, FlowTracker};
+    use pnet::packet::tcp::{TcpFlags, TcpPacket};
+    use client_id::ClientId;
+    use sessions::{SessionConfig, SessionDetails};
+    use timekeeping::{Clock, MonotonicNs};
+    use std::time::Duration;
+    use transport_detector::{Verdict, FIRST_FLIGHT_SEGMENTS};
     use std::fmt::Write;
 
+    const S2NS: u64 = 1000*1000*1000;
+
     #[test]
     fn test_flow_display_format() {
         Flow::set_log_client(false);
@@ -371,7 +1265,8 @@ mod tests {
         let mut output = String::new();
         write!(&mut output, "{}", flow6)
             .expect("Error occurred while trying to write in String");
-        assert_eq!(output, "[2601::abcd:ef00]:5672 -> [26ff::1]:443");
+        assert_eq!(output, format!("{}:5672 -> [26ff::1]:443", ClientId::new(flow6.src_ip)));
+        assert!(!output.contains("2601"));
 
 
         let flow4 = Flow {
@@ -384,7 +1279,7 @@ mod tests {
         let mut output = String::new();
         write!(&mut output, "{}", flow4)
             .expect("Error occurred while trying to write in String");
-        assert_eq!(output, "10.22.0.1:5672 -> 128.138.97.6:443");
+        assert_eq!(output, format!("{}:5672 -> 128.138.97.6:443", ClientId::new(flow4.src_ip)));
 
 
         let flow_n6 = FlowNoSrcPort {
@@ -396,7 +1291,7 @@ mod tests {
         let mut output = String::new();
         write!(&mut output, "{}", flow_n6)
             .expect("Error occurred while trying to write in String");
-        assert_eq!(output, "[2601::abcd:ef00]:0 -> [26ff::1]:443");
+        assert_eq!(output, format!("{} -> [26ff::1]:443", ClientId::new(flow_n6.src_ip)));
 
 
         let flow_n4 = FlowNoSrcPort {
@@ -408,7 +1303,7 @@ mod tests {
         let mut output = String::new();
         write!(&mut output, "{}", flow_n4)
             .expect("Error occurred while trying to write in String");
-        assert_eq!(output, "10.22.0.1:0 -> 128.138.97.6:443");
+        assert_eq!(output, format!("{} -> 128.138.97.6:443", ClientId::new(flow_n4.src_ip)));
     }
 
     #[test]
@@ -437,4 +1332,412 @@ mod tests {
         assert_eq!(vec![0x26, 0x01, 0,0,0,0,0,0,0,0,0,0,0xab, 0xcd, 0xef, 0x00], src);
         assert_eq!(vec![0x26, 0xff, 0,0,0,0,0,0,0,0,0,0,   0,    0,    0,    1], dst);
     }
+
+    #[test]
+    fn test_flow_idle_timeouts() {
+        let clock = Clock::simulated(0);
+        let mut ft = FlowTracker::without_threads(SessionConfig::default(), clock.clone());
+        ft.set_timeouts(FlowTimeouts { syn_ns: 10*S2NS, established_ns: 100*S2NS, fin_ns: 2*S2NS, udp_ns: 30*S2NS });
+        let flow = |port| Flow::from_parts("10.22.0.1".parse().unwrap(), "128.138.97.6".parse().unwrap(), port, 443);
+
+        ft.begin_tracking_flow(&flow(1), 0);
+        ft.open_phantom_connection(&flow(2));
+        ft.open_phantom_connection(&flow(3));
+        ft.touch_phantom_connection(&flow(3));
+        ft.open_phantom_connection(&flow(4));
+        ft.touch_phantom_connection(&flow(4));
+        ft.fin_phantom_connection(&flow(4));
+        assert!(ft.open_phantom_udp_flow(&flow(5)));
+        assert!(ft.is_phantom_connection(&flow(5)));
+        assert_eq!(ft.count_phantom_connections(), 4);
+
+        // each connection's first payload is inspected once; rejected ones
+        // are kept until they close
+        assert!(ft.first_phantom_payload(&flow(3)));
+        assert!(!ft.first_phantom_payload(&flow(3)));
+        assert!(!ft.first_phantom_payload(&flow(1)));
+        ft.apply_phantom_verdict(&flow(3), Verdict::Reject);
+        assert!(ft.is_rejected_phantom_connection(&flow(3)));
+        assert!(!ft.is_rejected_phantom_connection(&flow(4)));
+        assert!(ft.is_phantom_connection(&flow(3)));
+
+        // FIN-wait goes first, then the SYN-only flows
+        clock.advance(2*S2NS);
+        assert_eq!(ft.drop_all_stale_flows(), 1);
+        assert!(!ft.is_phantom_connection(&flow(4)));
+        clock.advance(8*S2NS);
+        assert_eq!(ft.drop_all_stale_flows(), 2);
+        assert!(!ft.is_tracked_flow(&flow(1)));
+        assert!(!ft.is_phantom_connection(&flow(2)));
+
+        // UDP flows are kept alive by their packets
+        clock.advance(10*S2NS);
+        assert!(ft.open_phantom_udp_flow(&flow(5)));
+        clock.advance(29*S2NS);
+        assert_eq!(ft.drop_all_stale_flows(), 0);
+        clock.advance(1*S2NS);
+        assert_eq!(ft.drop_all_stale_flows(), 1);
+        assert_eq!(ft.count_phantom_connections(), 1);
+
+        clock.advance(60*S2NS);
+        assert_eq!(ft.drop_all_stale_flows(), 1);
+        assert_eq!(ft.count_phantom_connections(), 0);
+        assert_eq!(ft.take_gc_report(), "flow gc dropped syn 2 established 1 fin 1 udp 1");
+        assert_eq!(ft.take_gc_report(), "flow gc dropped syn 0 established 0 fin 0 udp 0");
+    }
+
+    #[test]
+    fn test_flow_limit() {
+        let clock = Clock::simulated(0);
+        let mut ft = FlowTracker::without_threads(SessionConfig::default(), clock.clone());
+        ft.set_limit(Some(FlowLimit { max_flows: 3, eviction: Eviction::Fifo }));
+        let flow = |port| Flow::from_parts("10.22.0.1".parse().unwrap(), "128.138.97.6".parse().unwrap(), port, 443);
+
+        // candidates make room for each other, oldest first
+        ft.begin_tracking_flow(&flow(1), 0);
+        ft.begin_tracking_flow(&flow(2), 0);
+        assert!(ft.open_phantom_connection(&flow(10)));
+        ft.begin_tracking_flow(&flow(2), 0);
+        ft.begin_tracking_flow(&flow(3), 0);
+        assert!(!ft.is_tracked_flow(&flow(1)));
+        assert!(ft.is_tracked_flow(&flow(2)) && ft.is_tracked_flow(&flow(3)));
+
+        // session flows evict candidates, then each other's SYNs, never
+        // established connections
+        assert!(ft.open_phantom_connection(&flow(11)));
+        ft.touch_phantom_connection(&flow(11));
+        clock.advance(S2NS);
+        assert!(ft.open_phantom_connection(&flow(12)));
+        assert_eq!((ft.count_tracked_flows(), ft.count_phantom_connections()), (0, 3));
+        ft.begin_tracking_flow(&flow(4), 0);
+        assert!(!ft.is_tracked_flow(&flow(4)));
+        assert!(ft.open_phantom_connection(&flow(13)));
+        assert!(!ft.is_phantom_connection(&flow(10)));
+        assert!(ft.is_phantom_connection(&flow(11)) && ft.is_phantom_connection(&flow(12)));
+        ft.touch_phantom_connection(&flow(12));
+        ft.touch_phantom_connection(&flow(13));
+        assert!(!ft.open_phantom_connection(&flow(14)));
+        // the refused connection stays refused, an evicted one doesn't
+        assert!(ft.is_refused_phantom_connection(&flow(14)));
+        assert!(!ft.is_refused_phantom_connection(&flow(10)));
+        // an open connection is never refused its packets
+        assert!(ft.open_phantom_connection(&flow(13)));
+        assert_eq!(ft.take_eviction_report(), "flow limit evicted candidate 3 session syn 1 refused 2");
+        assert_eq!(ft.take_eviction_report(), "flow limit evicted candidate 0 session syn 0 refused 0");
+        clock.advance(FlowTimeouts::default().syn_ns);
+        ft.drop_all_stale_flows();
+        assert!(!ft.is_refused_phantom_connection(&flow(14)));
+
+        // random eviction stays within the limit too
+        let mut ft = FlowTracker::without_threads(SessionConfig::default(), clock.clone());
+        ft.set_limit(Some(FlowLimit { max_flows: 50, eviction: Eviction::Random }));
+        for port in 0..500 {
+            ft.begin_tracking_flow(&flow(port), 0);
+            assert!(ft.count_tracked_flows() <= 50);
+        }
+        assert!(ft.is_tracked_flow(&flow(499)));
+        assert_eq!(ft.take_eviction_report(), "flow limit evicted candidate 450 session syn 0 refused 0");
+    }
+
+    #[test]
+    fn test_flow_syn_filter() {
+        let clock = Clock::simulated(0);
+        let mut ft = FlowTracker::without_threads(SessionConfig::default(), clock.clone());
+        ft.set_timeouts(FlowTimeouts { syn_ns: 10*S2NS, ..FlowTimeouts::default() });
+        ft.enable_syn_filter(1 << 12);
+        let flow = |port| Flow::from_parts("10.22.0.1".parse().unwrap(), "128.138.97.6".parse().unwrap(), port, 443);
+
+        // a bare SYN gets no entry, its handshake's next packet does
+        ft.begin_tracking_flow(&flow(1), 1000);
+        ft.begin_tracking_flow(&flow(2), 1000);
+        assert_eq!(ft.count_tracked_flows(), 0);
+        clock.advance(S2NS);
+        assert!(ft.track_progress(&flow(1), 1001));
+        assert!(ft.is_tracked_flow(&flow(1)));
+        assert!(ft.track_progress(&flow(1), 1001));
+        assert!(!ft.track_progress(&flow(3), 1001));
+        assert_eq!(ft.count_tracked_flows(), 1);
+        assert_eq!(ft.take_syn_filter_report().unwrap(), "syn filter noted 2 promoted 1 fill 0%");
+
+        // and the entry times out from then
+        clock.advance(10*S2NS);
+        assert_eq!(ft.drop_all_stale_flows(), 1);
+        // SYNs are forgotten after the SYN timeout or two
+        clock.advance(10*S2NS);
+        assert!(!ft.track_progress(&flow(2), 1001));
+
+        // a flow's app data is checked once: after its first app packet it
+        // isn't promoted again
+        ft.begin_tracking_flow(&flow(4), 5000);
+        assert!(ft.track_progress(&flow(4), 5001));
+        assert!(ft.track_progress(&flow(4), 5001 + 517));
+        ft.stop_tracking_flow(&flow(4));
+        assert!(!ft.track_progress(&flow(4), 5001 + 517 + 100));
+        assert!(!ft.track_progress(&flow(4), 5001 + 517 + 200));
+
+        // without the filter every SYN is tracked, and nothing is promoted
+        let mut ft = FlowTracker::without_threads(SessionConfig::default(), clock.clone());
+        ft.begin_tracking_flow(&flow(1), 1000);
+        assert!(ft.is_tracked_flow(&flow(1)));
+        assert!(!ft.track_progress(&flow(2), 1001));
+        assert!(ft.take_syn_filter_report().is_none());
+    }
+
+    #[test]
+    fn test_flow_extension_needs_connection() {
+        let expiry = |ft: &FlowTracker, flow: &Flow| ft.get_phantom_session(&FlowNoSrcPort::from_flow(flow)).unwrap().expire_time;
+        let scan = Flow::from_parts("10.22.0.1".parse().unwrap(), "128.138.97.6".parse().unwrap(), 1, 443);
+        let client = Flow::from_parts("10.22.0.1".parse().unwrap(), "128.138.97.6".parse().unwrap(), 2, 443);
+
+        let clock = Clock::simulated(0);
+        let mut ft = FlowTracker::without_threads(SessionConfig::default(), clock.clone());
+        ft.phantom_flows.add_session(SessionDetails::new("10.22.0.1", "128.138.97.6", 443, Duration::from_secs(30)).unwrap());
+        clock.advance(S2NS);
+
+        // stray packets and a lone SYN don't extend
+        ft.update_phantom_flow(&scan);
+        ft.open_phantom_connection(&scan);
+        ft.update_phantom_flow(&scan);
+        assert_eq!(expiry(&ft, &scan), MonotonicNs(30*S2NS));
+
+        // the rest of a connection does
+        ft.open_phantom_connection(&client);
+        ft.touch_phantom_connection(&client);
+        ft.update_phantom_flow(&client);
+        assert!(expiry(&ft, &client) > MonotonicNs(30*S2NS));
+
+        // a UDP flow only once the phantom answers it, or it carries a QUIC
+        // Initial
+        let udp = Flow::from_parts("10.22.0.1".parse().unwrap(), "128.138.97.6".parse().unwrap(), 3, 443);
+        let reply = Flow::from_parts(udp.dst_ip, udp.src_ip, udp.dst_port, udp.src_port);
+        let before = expiry(&ft, &udp);
+        clock.advance(S2NS);
+        assert!(!ft.note_phantom_udp_reply(&reply));
+        ft.open_phantom_udp_flow(&udp);
+        ft.update_phantom_flow(&udp);
+        assert_eq!(expiry(&ft, &udp), before);
+        assert!(!ft.is_confirmed_phantom_udp_flow(&udp));
+        assert!(ft.note_phantom_udp_reply(&reply));
+        assert!(ft.is_confirmed_phantom_udp_flow(&udp));
+        ft.update_phantom_flow(&udp);
+        assert!(expiry(&ft, &udp) > before);
+
+        let quic = Flow::from_parts("10.22.0.1".parse().unwrap(), "128.138.97.6".parse().unwrap(), 4, 443);
+        ft.open_phantom_udp_flow(&quic);
+        ft.confirm_phantom_udp_flow(&quic);
+        assert!(ft.is_confirmed_phantom_udp_flow(&quic));
+        // TCP connections aren't confirmed this way
+        ft.confirm_phantom_udp_flow(&client);
+        assert!(!ft.is_confirmed_phantom_udp_flow(&client));
+
+        // as does anything with the escape hatch
+        let config = SessionConfig { extend_on_any_packet: true, ..SessionConfig::default() };
+        let mut ft = FlowTracker::without_threads(config, clock.clone());
+        ft.phantom_flows.add_session(SessionDetails::new("10.22.0.1", "128.138.97.6", 443, Duration::from_secs(30)).unwrap());
+        ft.update_phantom_flow(&scan);
+        assert!(expiry(&ft, &scan) > MonotonicNs(31*S2NS));
+    }
+
+    #[test]
+    fn test_flow_held_payload() {
+        let client = Flow::from_parts("10.22.0.1".parse().unwrap(), "128.138.97.6".parse().unwrap(), 2, 443);
+        let mut ft = FlowTracker::without_threads(SessionConfig::default(), Clock::simulated(0));
+        ft.open_phantom_connection(&client);
+
+        // a short first segment is held, and shown again with the next one
+        assert!(ft.first_phantom_payload(&client));
+        assert_eq!(ft.phantom_payload_to_inspect(&client, true, Some(100), b"G").unwrap(), b"G");
+        assert!(ft.hold_phantom_payload(&client, b"G".to_vec(), Some(101)));
+        // retransmissions aren't added
+        assert!(ft.phantom_payload_to_inspect(&client, false, Some(100), b"G").is_none());
+        assert_eq!(ft.phantom_payload_to_inspect(&client, false, Some(101), b"ET").unwrap(), b"GET");
+        // nothing more once the detectors have their verdict
+        assert!(ft.phantom_payload_to_inspect(&client, false, Some(103), b" /").is_none());
+
+        // past the first flight the connection can't be held any longer
+        let other = Flow::from_parts("10.22.0.1".parse().unwrap(), "128.138.97.6".parse().unwrap(), 3, 443);
+        ft.open_phantom_connection(&other);
+        for seq in 1..FIRST_FLIGHT_SEGMENTS as u32 {
+            assert!(ft.hold_phantom_payload(&other, vec![0; seq as usize], Some(seq)));
+        }
+        assert!(!ft.hold_phantom_payload(&other, vec![0; 8], Some(8)));
+        assert!(ft.phantom_payload_to_inspect(&other, false, Some(8), b"x").is_none());
+    }
+
+    // A client TCP segment of `len` payload bytes at `seq`.
+    fn segment(seq: u32, flags: u16, len: usize) -> Vec<u8> {
+        acking(seq, 0, flags, len)
+    }
+
+    // As segment, acknowledging `ack`.
+    fn acking(seq: u32, ack: u32, flags: u16, len: usize) -> Vec<u8> {
+        let mut buf = vec![0u8; 20 + len];
+        buf[4..8].copy_from_slice(&seq.to_be_bytes());
+        buf[8..12].copy_from_slice(&ack.to_be_bytes());
+        buf[12] = 5 << 4 | (flags >> 8) as u8;
+        buf[13] = flags as u8;
+        buf
+    }
+
+    #[test]
+    fn test_flow_seq_window() {
+        let client = Flow::from_parts("10.22.0.1".parse().unwrap(), "128.138.97.6".parse().unwrap(), 2, 443);
+        let mut ft = FlowTracker::without_threads(SessionConfig::default(), Clock::simulated(0));
+        ft.phantom_flows.add_session(SessionDetails::new("10.22.0.1", "128.138.97.6", 443, Duration::from_secs(30)).unwrap());
+        ft.open_phantom_connection(&client);
+        let mut check = |ft: &mut FlowTracker, seq: u32, flags: u16, len: usize| {
+            let buf = segment(seq, flags, len);
+            ft.check_phantom_seq(&client, &TcpPacket::new(&buf).unwrap())
+        };
+
+        // not checked unless enabled
+        assert!(check(&mut ft, 1000, TcpFlags::SYN, 0));
+        assert!(check(&mut ft, 5000000, TcpFlags::ACK, 0));
+
+        ft.set_seq_window(Some(65536));
+        // the window starts at the SYN, the ISN takes one number
+        let isn = 0xffff_ff00;
+        assert!(check(&mut ft, isn, TcpFlags::SYN, 0));
+        // the client's ACK has to acknowledge the phantom's SYN-ACK
+        let ack = |ft: &mut FlowTracker, ack: u32| {
+            let buf = acking(isn + 1, ack, TcpFlags::ACK, 0);
+            ft.check_phantom_seq(&client, &TcpPacket::new(&buf).unwrap())
+        };
+        let reply = Flow::from_parts(client.dst_ip, client.src_ip, 443, 2);
+        assert!(!ack(&mut ft, 7001));
+        assert!(ft.note_phantom_syn_ack(&reply, 7000));
+        assert!(!ft.note_phantom_syn_ack(&client, 7000));
+        assert!(!ack(&mut ft, 7000));
+        assert!(!ack(&mut ft, 7001 + 70000));
+        assert!(ack(&mut ft, 7001));
+        assert_eq!(ft.take_seq_rejects(), 3);
+        // and moves with the data, across the wrap
+        assert!(check(&mut ft, isn + 1, TcpFlags::ACK | TcpFlags::PSH, 1000));
+        assert!(check(&mut ft, 60000, TcpFlags::ACK, 1000));
+        // retransmissions and segments just ahead are within it
+        assert!(check(&mut ft, isn + 1, TcpFlags::ACK | TcpFlags::PSH, 1000));
+        assert!(check(&mut ft, 70000, TcpFlags::ACK, 1000));
+        // blind guesses aren't
+        assert!(!check(&mut ft, 0x8000_0000, TcpFlags::RST, 0));
+        assert!(!check(&mut ft, isn - 70000, TcpFlags::ACK | TcpFlags::FIN, 0));
+        assert!(!check(&mut ft, 200000, TcpFlags::ACK, 0));
+        assert_eq!(ft.take_seq_rejects(), 3);
+        assert_eq!(ft.take_seq_rejects(), 0);
+
+        // a new SYN restarts the window
+        assert!(check(&mut ft, 0x8000_0000, TcpFlags::SYN, 0));
+        assert!(check(&mut ft, 0x8000_0001, TcpFlags::ACK | TcpFlags::FIN, 0));
+        assert!(!check(&mut ft, 70000, TcpFlags::ACK, 0));
+
+        // past the SYN, a spoofed SYN doesn't move the window, nor a spoofed
+        // SYN-ACK
+        ft.touch_phantom_connection(&client);
+        assert!(!ft.note_phantom_syn_ack(&reply, 9000));
+        assert!(!check(&mut ft, 200000, TcpFlags::SYN, 0));
+        assert!(!check(&mut ft, 200001, TcpFlags::ACK, 100));
+        assert!(check(&mut ft, 0x8000_0001, TcpFlags::SYN, 0));
+        assert!(check(&mut ft, 0x8000_0002, TcpFlags::ACK, 100));
+        assert_eq!(ft.take_seq_rejects(), 3);
+
+        // other flows aren't checked
+        let other = Flow::from_parts("10.22.0.1".parse().unwrap(), "128.138.97.6".parse().unwrap(), 3, 443);
+        let buf = segment(1, TcpFlags::ACK, 0);
+        assert!(ft.check_phantom_seq(&other, &TcpPacket::new(&buf).unwrap()));
+    }
+
+    #[test]
+    fn test_flow_asymmetric() {
+        let client = Flow::from_parts("10.22.0.1".parse().unwrap(), "128.138.97.6".parse().unwrap(), 2, 443);
+        let clock = Clock::simulated(0);
+        let mut ft = FlowTracker::without_threads(SessionConfig::default(), clock.clone());
+        ft.phantom_flows.add_session(SessionDetails::new("10.22.0.1", "128.138.97.6", 443, Duration::from_secs(30)).unwrap());
+        ft.set_seq_window(Some(65536));
+
+        // symmetric: nothing opens past the SYN, a RST closes
+        assert!(ft.open_phantom_midstream(&client, 100));
+        assert!(ft.open_phantom_midstream(&client, 100));
+        assert!(!ft.is_phantom_connection(&client));
+        ft.open_phantom_connection(&client);
+        ft.reset_phantom_connection(&client);
+        assert!(!ft.is_phantom_connection(&client));
+
+        ft.set_asymmetric(true);
+        ft.set_timeouts(FlowTimeouts::asymmetric());
+        // a connection whose SYN was missed opens established with its
+        // second packet carrying payload, extending its session
+        assert!(ft.open_phantom_midstream(&client, 0));
+        assert!(ft.open_phantom_midstream(&client, 100));
+        assert!(!ft.is_phantom_connection(&client));
+        assert!(ft.open_phantom_midstream(&client, 100));
+        assert!(ft.open_phantom_midstream(&client, 100));
+        assert!(ft.is_phantom_connection(&client));
+        assert_eq!(ft.take_midstream_opens(), 1);
+        clock.advance(S2NS);
+        ft.update_phantom_flow(&client);
+        assert!(ft.get_phantom_session(&FlowNoSrcPort::from_flow(&client)).unwrap().expire_time > MonotonicNs(30*S2NS));
+
+        // sequence numbers aren't checked
+        for &seq in [1u32, 0x8000_0000, 12345].iter() {
+            let buf = segment(seq, TcpFlags::ACK, 100);
+            assert!(ft.check_phantom_seq(&client, &TcpPacket::new(&buf).unwrap()));
+        }
+        assert_eq!(ft.take_seq_rejects(), 0);
+
+        // a RST only closes it after the FIN timeout
+        ft.reset_phantom_connection(&client);
+        assert!(ft.is_phantom_connection(&client));
+        clock.advance(5*S2NS);
+        ft.drop_all_stale_flows();
+        assert!(ft.is_phantom_connection(&client));
+        clock.advance(5*S2NS);
+        ft.drop_all_stale_flows();
+        assert!(!ft.is_phantom_connection(&client));
+        assert_eq!(ft.take_gc_report(), "flow gc dropped syn 0 established 0 fin 1 udp 0");
+
+        // lone packets don't count against the session's connections, and
+        // are forgotten after the SYN timeout
+        let mut ft = FlowTracker::without_threads(SessionConfig::default(), clock.clone());
+        ft.set_asymmetric(true);
+        ft.set_timeouts(FlowTimeouts::asymmetric());
+        let limited = SessionDetails::builder()
+            .client(client.src_ip)
+            .phantom(client.dst_ip)
+            .port(443)
+            .timeout(Duration::from_secs(60))
+            .max_connections(1)
+            .build()
+            .unwrap();
+        ft.phantom_flows.add_session(limited);
+        let scans: Vec<Flow> = (10..20).map(|port| Flow::from_parts(client.src_ip, client.dst_ip, port, 443)).collect();
+        for scan in scans.iter() {
+            assert!(ft.open_phantom_midstream(scan, 100));
+        }
+        assert!(ft.open_phantom_midstream(&client, 100));
+        assert!(ft.open_phantom_midstream(&client, 100));
+        assert!(ft.is_phantom_connection(&client));
+        clock.advance(FlowTimeouts::asymmetric().syn_ns + 1);
+        ft.drop_all_stale_flows();
+        assert!(ft.midstream_pending.is_empty());
+    }
+
+    #[test]
+    fn test_flow_negative_cache() {
+        let client = Flow::from_parts("10.22.0.1".parse().unwrap(), "128.138.97.6".parse().unwrap(), 2, 443);
+        let other = Flow::from_parts("10.22.0.2".parse().unwrap(), "128.138.97.6".parse().unwrap(), 2, 443);
+        let mut ft = FlowTracker::without_threads(SessionConfig::default(), Clock::simulated(0));
+        ft.enable_negative_cache(16);
+
+        assert!(!ft.is_phantom_flow(&client));
+        assert!(!ft.is_phantom_flow(&client));
+        assert!(!ft.is_phantom_flow(&client));
+        assert_eq!(ft.take_negative_cache_report().unwrap(), "negative cache hits 2 lookups 1");
+
+        // a session added for the flow invalidates the miss
+        ft.phantom_flows.add_session(SessionDetails::new("10.22.0.1", "128.138.97.6", 443, Duration::from_secs(30)).unwrap());
+        assert!(ft.is_phantom_flow(&client));
+        assert!(ft.is_phantom_flow(&client));
+        assert!(!ft.is_phantom_flow(&other));
+        assert!(!ft.is_phantom_flow(&other));
+        assert_eq!(ft.take_negative_cache_report().unwrap(), "negative cache hits 1 lookups 3");
+    }
 }
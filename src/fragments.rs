@@ -0,0 +1,205 @@
+//
+// Fragment Association
+//
+// Only the first fragment of a fragmented packet carries the transport header,
+// so the fragments after it can't be matched to a session by port. With a
+// fragment cache (DETECTOR_FRAGMENT_CACHE) the detector remembers the
+// (source, destination, IP ID) of first fragments it forwarded for a matched
+// session, with the session, and forwards the later fragments with the same
+// key for that session too: into the same tun device, with the same mark and
+// header. Later fragments that don't belong to a forwarded packet are dropped,
+// as before.
+//
+// First fragments are expected before the rest; a later fragment that arrives
+// ahead of its first fragment is dropped. Entries are kept for timeout_ns
+// after the first fragment (fragments of one packet arrive together) and at
+// most `capacity` at a time, the oldest evicted first. The cache is per core
+// and only used by the packet thread.
+
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+
+use flow_tracker::FlowNoSrcPort;
+use timekeeping::MonotonicNs;
+use util::Fragment;
+
+#[derive(Clone)]
+pub struct FragmentCacheConfig
+{
+    // Fragmented packets remembered at a time.
+    pub capacity: usize,
+    // How long after its first fragment a packet's later fragments are
+    // forwarded.
+    pub timeout_ns: u64,
+}
+
+impl Default for FragmentCacheConfig
+{
+    fn default() -> FragmentCacheConfig {
+        FragmentCacheConfig { capacity: 4096, timeout_ns: 2*1000*1000*1000 }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FragmentKey
+{
+    pub src: IpAddr,
+    pub dst: IpAddr,
+    pub id: u32,
+}
+
+impl FragmentKey
+{
+    pub fn new(src: IpAddr, dst: IpAddr, frag: &Fragment) -> FragmentKey {
+        FragmentKey { src: src, dst: dst, id: frag.id }
+    }
+}
+
+pub struct FragmentCache
+{
+    config: FragmentCacheConfig,
+    // Expiry time of each remembered packet, and the session it was
+    // forwarded for.
+    entries: HashMap<FragmentKey, (MonotonicNs, FlowNoSrcPort)>,
+    // Keys in the order they were remembered, with the expiry they were
+    // remembered with, for eviction.
+    order: VecDeque<(FragmentKey, MonotonicNs)>,
+
+    remembered: u64,
+    forwarded: u64,
+    unmatched: u64,
+    evicted: u64,
+}
+
+impl FragmentCache
+{
+    pub fn new(config: FragmentCacheConfig) -> FragmentCache {
+        FragmentCache {
+            config: config,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            remembered: 0,
+            forwarded: 0,
+            unmatched: 0,
+            evicted: 0,
+        }
+    }
+
+    /// Remembers a first fragment that was forwarded for the session
+    /// `session`.
+    pub fn remember(&mut self, key: FragmentKey, session: FlowNoSrcPort, now: MonotonicNs) {
+        self.expire(now);
+        if self.config.capacity == 0 {
+            return;
+        }
+        while self.entries.len() >= self.config.capacity {
+            match self.order.pop_front() {
+                Some((k, expire)) => {
+                    if self.entries.get(&k).map(|e| e.0) == Some(expire) {
+                        self.entries.remove(&k);
+                        self.evicted += 1;
+                    }
+                },
+                None => break,
+            }
+        }
+        let expire = now.saturating_add(self.config.timeout_ns);
+        self.entries.insert(key, (expire, session));
+        self.order.push_back((key, expire));
+        self.remembered += 1;
+    }
+
+    /// The session a later fragment's first fragment was forwarded for, if
+    /// it was, counting it either way.
+    pub fn lookup(&mut self, key: &FragmentKey, now: MonotonicNs) -> Option<FlowNoSrcPort> {
+        let session = match self.entries.get(key) {
+            Some(&(expire, session)) if expire > now => Some(session),
+            _ => None,
+        };
+        if session.is_some() {
+            self.forwarded += 1;
+        } else {
+            self.unmatched += 1;
+        }
+        session
+    }
+
+    fn expire(&mut self, now: MonotonicNs) {
+        while let Some(&(k, expire)) = self.order.front() {
+            if expire > now {
+                break;
+            }
+            self.order.pop_front();
+            if self.entries.get(&k).map(|e| e.0) == Some(expire) {
+                self.entries.remove(&k);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Counters since the last call, formatted for the periodic report.
+    pub fn take_report(&mut self, now: MonotonicNs) -> String {
+        self.expire(now);
+        let report = format!("fragments remembered {} forwarded {} unmatched {} evicted {} cached {}",
+            self.remembered, self.forwarded, self.unmatched, self.evicted, self.entries.len());
+        self.remembered = 0;
+        self.forwarded = 0;
+        self.unmatched = 0;
+        self.evicted = 0;
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fragments::*;
+    use timekeeping::MonotonicNs;
+
+    fn session(port: u16) -> FlowNoSrcPort {
+        FlowNoSrcPort {
+            src_ip: "192.168.0.1".parse().unwrap(),
+            dst_ip: "10.10.0.1".parse().unwrap(),
+            dst_port: port,
+        }
+    }
+
+    fn key(id: u32) -> FragmentKey {
+        FragmentKey {
+            src: "192.168.0.1".parse().unwrap(),
+            dst: "10.10.0.1".parse().unwrap(),
+            id: id,
+        }
+    }
+
+    #[test]
+    fn test_fragment_cache() {
+        let mut cache = FragmentCache::new(FragmentCacheConfig { capacity: 2, timeout_ns: 100 });
+        cache.remember(key(1), session(443), MonotonicNs(0));
+        assert_eq!(cache.lookup(&key(1), MonotonicNs(50)), Some(session(443)));
+        assert_eq!(cache.lookup(&key(1), MonotonicNs(99)), Some(session(443)));
+        assert_eq!(cache.lookup(&key(2), MonotonicNs(50)), None);
+        // same id from another source
+        let mut other = key(1);
+        other.src = "192.168.0.2".parse().unwrap();
+        assert_eq!(cache.lookup(&other, MonotonicNs(50)), None);
+        // timed out
+        assert_eq!(cache.lookup(&key(1), MonotonicNs(100)), None);
+
+        // the oldest is evicted when full
+        cache.remember(key(2), session(443), MonotonicNs(200));
+        cache.remember(key(3), session(443), MonotonicNs(210));
+        cache.remember(key(4), session(8443), MonotonicNs(220));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.lookup(&key(2), MonotonicNs(230)), None);
+        assert_eq!(cache.lookup(&key(3), MonotonicNs(230)), Some(session(443)));
+        assert_eq!(cache.lookup(&key(4), MonotonicNs(230)), Some(session(8443)));
+
+        assert_eq!(cache.take_report(MonotonicNs(230)),
+                   "fragments remembered 4 forwarded 4 unmatched 4 evicted 1 cached 2");
+        assert_eq!(cache.take_report(MonotonicNs(400)),
+                   "fragments remembered 0 forwarded 0 unmatched 0 evicted 0 cached 0");
+    }
+}
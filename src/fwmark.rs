@@ -0,0 +1,160 @@
+//
+// Firewall Marks
+//
+// Firewall policy on the station needs to tell the traffic the detector
+// forwards apart from everything else. Packets written into a tun device
+// can't carry a mark themselves, so the detector marks them by the device
+// they come in on: each core installs an nftables table, conjure_det<core>,
+// whose prerouting chain (at mangle priority, ahead of routing and NAT) sets
+// the mark of each of its devices that has one. The mark of tun<core> is
+// DETECTOR_FWMARK, and routed devices take the mark of their routes (see
+// tun_routes.rs), so traffic can be marked by IP version, transport or
+// phantom subnet too. Later rules can then match on "meta mark".
+//
+// The table is replaced whole when the core starts, so restarts don't stack
+// rules. Installing it needs the nft binary and CAP_NET_ADMIN; if it fails
+// marking is disabled. Packets and bytes (IP) forwarded are counted per mark.
+
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+use error::{DetectorError, DetectorResult};
+
+pub struct FwMarks
+{
+    table: String,
+    // Name and mark of tun<core>, and of each routed device by index.
+    default: (String, Option<u32>),
+    routed: Vec<(String, Option<u32>)>,
+    // Packets and bytes forwarded per mark since the last report, in the
+    // order the marks first appear.
+    counts: Vec<(u32, u64, u64)>,
+}
+
+/// A mark from the environment, in hex with a leading 0x or decimal.
+pub fn parse_mark(s: &str) -> Option<u32> {
+    let s = s.trim();
+    if s.starts_with("0x") || s.starts_with("0X") {
+        u32::from_str_radix(&s[2..], 16).ok()
+    } else {
+        s.parse().ok()
+    }
+}
+
+impl FwMarks
+{
+    /// Marks of core `lcore`'s devices: `default` for tun<core> and `routed`
+    /// for the routed devices by index. None if no device has a mark.
+    pub fn new(lcore: i32, default: Option<u32>, routed: Vec<(String, Option<u32>)>) -> Option<FwMarks> {
+        if default.is_none() && routed.iter().all(|d| d.1.is_none()) {
+            return None
+        }
+        let mut counts: Vec<(u32, u64, u64)> = Vec::new();
+        for mark in default.iter().chain(routed.iter().filter_map(|d| d.1.as_ref())) {
+            if !counts.iter().any(|c| c.0 == *mark) {
+                counts.push((*mark, 0, 0));
+            }
+        }
+        Some(FwMarks {
+            table: format!("conjure_det{}", lcore),
+            default: (format!("tun{}", lcore), default),
+            routed: routed,
+            counts: counts,
+        })
+    }
+
+    /// The nftables script replacing the core's table.
+    pub fn ruleset(&self) -> String {
+        let mut script = format!("table inet {0} {{}}\ndelete table inet {0}\ntable inet {0} {{\n", self.table);
+        script.push_str("\tchain prerouting {\n");
+        script.push_str("\t\ttype filter hook prerouting priority mangle; policy accept;\n");
+        for &(ref name, mark) in Some(&self.default).into_iter().chain(self.routed.iter()) {
+            if let Some(mark) = mark {
+                script.push_str(&format!("\t\tiifname \"{}\" meta mark set 0x{:08x}\n", name, mark));
+            }
+        }
+        script.push_str("\t}\n}\n");
+        script
+    }
+
+    /// Replaces the core's table with ruleset().
+    pub fn install(&self) -> DetectorResult<()> {
+        let fwmark_error = |e| DetectorError::Fwmark { context: "install", source: e };
+        let mut nft = Command::new("nft").args(["-f", "-"])
+            .stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::piped())
+            .spawn().map_err(fwmark_error)?;
+        if let Some(ref mut stdin) = nft.stdin {
+            stdin.write_all(self.ruleset().as_bytes()).map_err(fwmark_error)?;
+        }
+        let out = nft.wait_with_output().map_err(fwmark_error)?;
+        if !out.status.success() {
+            let msg = String::from_utf8_lossy(&out.stderr).trim().to_string();
+            return Err(fwmark_error(io::Error::new(io::ErrorKind::Other, msg)))
+        }
+        Ok(())
+    }
+
+    /// Counts a packet of `bytes` forwarded into `device` (by index, None
+    /// for tun<core>).
+    pub fn count(&mut self, device: Option<usize>, bytes: usize) {
+        let mark = match device {
+            Some(i) => self.routed.get(i).and_then(|d| d.1),
+            None => self.default.1,
+        };
+        if let Some(mark) = mark {
+            if let Some(c) = self.counts.iter_mut().find(|c| c.0 == mark) {
+                c.1 += 1;
+                c.2 += bytes as u64;
+            }
+        }
+    }
+
+    /// Packets and bytes forwarded per mark since the last call.
+    pub fn take_report(&mut self) -> String {
+        let marks: Vec<String> = self.counts.iter_mut().map(|c| {
+            let s = format!("0x{:x} pkts {} bytes {}", c.0, c.1, c.2);
+            c.1 = 0;
+            c.2 = 0;
+            s
+        }).collect();
+        format!("fwmarks {}", marks.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fwmark::*;
+
+    #[test]
+    fn test_fwmarks() {
+        assert_eq!(parse_mark("0x10"), Some(16));
+        assert_eq!(parse_mark(" 32 "), Some(32));
+        assert_eq!(parse_mark("0xzz"), None);
+
+        assert!(FwMarks::new(2, None, vec![("tunv6-2".to_string(), None)]).is_none());
+
+        let routed = vec![
+            ("obfs4-2".to_string(), Some(0x20)),
+            ("tunv6-2".to_string(), None),
+            ("min-2".to_string(), Some(0x10)),
+        ];
+        let mut marks = FwMarks::new(2, Some(0x10), routed).unwrap();
+        assert_eq!(marks.ruleset(), "table inet conjure_det2 {}\n\
+            delete table inet conjure_det2\n\
+            table inet conjure_det2 {\n\
+            \tchain prerouting {\n\
+            \t\ttype filter hook prerouting priority mangle; policy accept;\n\
+            \t\tiifname \"tun2\" meta mark set 0x00000010\n\
+            \t\tiifname \"obfs4-2\" meta mark set 0x00000020\n\
+            \t\tiifname \"min-2\" meta mark set 0x00000010\n\
+            \t}\n}\n");
+
+        marks.count(None, 100);
+        marks.count(Some(2), 50);
+        marks.count(Some(0), 60);
+        // unmarked
+        marks.count(Some(1), 70);
+        assert_eq!(marks.take_report(), "fwmarks 0x10 pkts 2 bytes 150 0x20 pkts 1 bytes 60");
+        assert_eq!(marks.take_report(), "fwmarks 0x10 pkts 0 bytes 0 0x20 pkts 0 bytes 0");
+    }
+}
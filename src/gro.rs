@@ -0,0 +1,328 @@
+//
+// GRO Superpackets
+//
+// With generic (or large) receive offload on the capture interface, runs of
+// segments of a TCP flow are coalesced into one "superpacket" of up to 64KB
+// before the detector sees them. Counted as one packet a superpacket skews the
+// per-packet stats, and forwarded as is it is larger than the tun device's MTU.
+//
+// TCP packets larger than the tun MTU (GroConfig.mtu, DETECTOR_TUN_MTU) are
+// counted as the segments they were on the wire, and split back into segments
+// that fit the MTU when forwarded. Each segment gets a copy of the IP and TCP
+// headers with the lengths, sequence number, IPv4 ID and checksums fixed up;
+// FIN and PSH are only kept on the last segment and CWR only on the first.
+//
+// v6 packets with extension headers, and anything that isn't TCP, are not
+// split. They are forwarded whole and counted as unsplittable.
+
+use pnet::packet::ip::IpNextHeaderProtocols;
+use pnet::packet::ipv4::Ipv4Packet;
+use pnet::packet::ipv6::Ipv6Packet;
+use pnet::packet::tcp::TcpFlags;
+
+use util::{IpPacket, checksum, deser_be_u32_slice};
+
+#[derive(Clone)]
+pub struct GroConfig
+{
+    // Largest IP packet written to the tun device.
+    pub mtu: usize,
+}
+
+impl Default for GroConfig
+{
+    fn default() -> GroConfig {
+        GroConfig { mtu: 1500 }
+    }
+}
+
+pub struct Gro
+{
+    config: GroConfig,
+
+    superpackets: u64,
+    segments: u64,
+    split: u64,
+    unsplittable: u64,
+}
+
+impl Gro
+{
+    pub fn new(config: GroConfig) -> Gro {
+        Gro {
+            config: config,
+            superpackets: 0,
+            segments: 0,
+            split: 0,
+            unsplittable: 0,
+        }
+    }
+
+    /// The number of segments `ip_pkt` was on the wire: more than one for a
+    /// TCP superpacket larger than the MTU.
+    pub fn segments(&mut self, ip_pkt: &IpPacket) -> usize {
+        if ip_pkt.packet().len() <= self.config.mtu {
+            return 1;
+        }
+        let n = match tcp_layout(ip_pkt) {
+            Some((ip_len, tcp_len, payload_len)) => match self.mss(ip_len, tcp_len) {
+                Some(mss) => ((payload_len + mss - 1) / mss).max(1),
+                None => 1,
+            },
+            None => 1,
+        };
+        self.superpackets += 1;
+        self.segments += n as u64;
+        n
+    }
+
+    /// Splits a packet larger than the MTU into segments that fit, returned
+    /// as tun frames. None if the packet fits or can't be split.
+    pub fn split(&mut self, ip_pkt: &IpPacket) -> Option<Vec<Vec<u8>>> {
+        if ip_pkt.packet().len() <= self.config.mtu {
+            return None;
+        }
+        let layout = tcp_layout(ip_pkt)
+            .and_then(|(ip_len, tcp_len, payload_len)| {
+                self.mss(ip_len, tcp_len).map(|mss| (ip_len, tcp_len, payload_len, mss))
+            });
+        let (ip_len, tcp_len, payload_len, mss) = match layout {
+            Some(l) => l,
+            None => {
+                self.unsplittable += 1;
+                return None;
+            },
+        };
+
+        let pkt = ip_pkt.packet();
+        let headers = &pkt[..ip_len + tcp_len];
+        let payload = &pkt[ip_len + tcp_len..ip_len + tcp_len + payload_len];
+        let seq = deser_be_u32_slice(&headers[ip_len+4..ip_len+8]);
+        let flags = headers[ip_len + 13];
+
+        let mut frames = Vec::with_capacity((payload_len + mss - 1) / mss);
+        for (i, chunk) in payload.chunks(mss).enumerate() {
+            let mut seg = Vec::with_capacity(headers.len() + chunk.len());
+            seg.extend_from_slice(headers);
+            seg.extend_from_slice(chunk);
+
+            let offset = i * mss;
+            let mut seg_flags = flags;
+            if i > 0 {
+                seg_flags &= !(TcpFlags::CWR as u8);
+            }
+            if offset + chunk.len() < payload_len {
+                seg_flags &= !((TcpFlags::FIN | TcpFlags::PSH) as u8);
+            }
+            seg[ip_len+4..ip_len+8].copy_from_slice(&be32(seq.wrapping_add(offset as u32)));
+            seg[ip_len + 13] = seg_flags;
+            seg[ip_len+16..ip_len+18].copy_from_slice(&[0, 0]);
+
+            let seg_tcp_len = seg.len() - ip_len;
+            let frame = match ip_pkt {
+                IpPacket::V4(v4) => {
+                    let len = seg.len() as u16;
+                    seg[2..4].copy_from_slice(&be16(len));
+                    seg[4..6].copy_from_slice(&be16(v4.get_identification().wrapping_add(i as u16)));
+                    seg[10..12].copy_from_slice(&[0, 0]);
+                    let sum = checksum(&[&seg[..ip_len]]);
+                    seg[10..12].copy_from_slice(&be16(sum));
+
+                    let mut pseudo = Vec::with_capacity(12);
+                    pseudo.extend_from_slice(&seg[12..20]);
+                    pseudo.extend_from_slice(&[0, 6]);
+                    pseudo.extend_from_slice(&be16(seg_tcp_len as u16));
+                    let sum = checksum(&[&pseudo, &seg[ip_len..]]);
+                    seg[ip_len+16..ip_len+18].copy_from_slice(&be16(sum));
+                    Ipv4Packet::new(&seg).map(|p| IpPacket::V4(p).tun_frame())
+                },
+                IpPacket::V6(_) => {
+                    seg[4..6].copy_from_slice(&be16(seg_tcp_len as u16));
+
+                    let mut pseudo = Vec::with_capacity(40);
+                    pseudo.extend_from_slice(&seg[8..40]);
+                    pseudo.extend_from_slice(&be32(seg_tcp_len as u32));
+                    pseudo.extend_from_slice(&[0, 0, 0, 6]);
+                    let sum = checksum(&[&pseudo, &seg[ip_len..]]);
+                    seg[ip_len+16..ip_len+18].copy_from_slice(&be16(sum));
+                    Ipv6Packet::new(&seg).map(|p| IpPacket::V6(p).tun_frame())
+                },
+            };
+            match frame {
+                Some(f) => frames.push(f),
+                None => {
+                    self.unsplittable += 1;
+                    return None;
+                },
+            }
+        }
+        self.split += 1;
+        Some(frames)
+    }
+
+    // The most TCP payload per segment, if the headers leave room for any.
+    fn mss(&self, ip_len: usize, tcp_len: usize) -> Option<usize> {
+        match self.config.mtu.checked_sub(ip_len + tcp_len) {
+            Some(0) | None => None,
+            Some(mss) => Some(mss),
+        }
+    }
+
+    /// Counters since the last call, formatted for the periodic report.
+    pub fn take_report(&mut self) -> String {
+        let report = format!("gro superpackets {} segments {} split {} unsplittable {}",
+            self.superpackets, self.segments, self.split, self.unsplittable);
+        self.superpackets = 0;
+        self.segments = 0;
+        self.split = 0;
+        self.unsplittable = 0;
+        report
+    }
+}
+
+// The IP header, TCP header and TCP payload lengths of a TCP packet that can
+// be split.
+fn tcp_layout(ip_pkt: &IpPacket) -> Option<(usize, usize, usize)> {
+    let pkt = ip_pkt.packet();
+    let (ip_len, total) = match ip_pkt {
+        IpPacket::V4(v4) => {
+            if v4.get_next_level_protocol() != IpNextHeaderProtocols::Tcp {
+                return None;
+            }
+            // superpackets over 64KB have a total length of 0
+            let total = match v4.get_total_length() as usize {
+                0 => pkt.len(),
+                n => n,
+            };
+            (v4.get_header_length() as usize * 4, total)
+        },
+        IpPacket::V6(v6) => {
+            if v6.get_next_header() != IpNextHeaderProtocols::Tcp {
+                return None;
+            }
+            (40, 40 + v6.get_payload_length() as usize)
+        },
+    };
+    if ip_len < 20 || total > pkt.len() || total < ip_len + 20 {
+        return None;
+    }
+    let tcp_len = (pkt[ip_len + 12] >> 4) as usize * 4;
+    if tcp_len < 20 || total < ip_len + tcp_len {
+        return None;
+    }
+    Some((ip_len, tcp_len, total - ip_len - tcp_len))
+}
+
+fn be16(n: u16) -> [u8; 2] {
+    [(n >> 8) as u8, n as u8]
+}
+
+fn be32(n: u32) -> [u8; 4] {
+    [(n >> 24) as u8, (n >> 16) as u8, (n >> 8) as u8, n as u8]
+}
+
+#[cfg(test)]
+mod tests {
+    use gro::*;
+
+    // A TCP packet from 10.0.0.1:40000 (or 2001:db8::1) to the phantom
+    // carrying `payload_len` bytes, with the IPv4 ID 0xfffe.
+    fn superpacket(v6: bool, flags: u8, payload_len: usize) -> Vec<u8> {
+        let mut tcp = Vec::new();
+        tcp.extend_from_slice(&be16(40000));
+        tcp.extend_from_slice(&be16(443));
+        tcp.extend_from_slice(&be32(0xffff_ff00));
+        tcp.extend_from_slice(&be32(1));
+        tcp.push(5 << 4);
+        tcp.push(flags);
+        tcp.extend_from_slice(&[0xff, 0xff, 0, 0, 0, 0]);
+        tcp.extend((0..payload_len).map(|i| i as u8));
+
+        let mut pkt = Vec::new();
+        if v6 {
+            pkt.extend_from_slice(&[0x60, 0, 0, 0]);
+            pkt.extend_from_slice(&be16(tcp.len() as u16));
+            pkt.extend_from_slice(&[6, 64]);
+            pkt.extend_from_slice(&"2001:db8::1".parse::<::std::net::Ipv6Addr>().unwrap().octets());
+            pkt.extend_from_slice(&"2001:db8::2".parse::<::std::net::Ipv6Addr>().unwrap().octets());
+        } else {
+            pkt.extend_from_slice(&[0x45, 0]);
+            pkt.extend_from_slice(&be16(20 + tcp.len() as u16));
+            pkt.extend_from_slice(&[0xff, 0xfe, 0x40, 0, 64, 6, 0, 0]);
+            pkt.extend_from_slice(&[10, 0, 0, 1, 192, 0, 2, 1]);
+        }
+        pkt.extend_from_slice(&tcp);
+        pkt
+    }
+
+    #[test]
+    fn test_gro_split_v4() {
+        let mut gro = Gro::new(GroConfig { mtu: 1500 });
+        let flags = TcpFlags::CWR | TcpFlags::PSH | TcpFlags::FIN | TcpFlags::ACK;
+        let raw = superpacket(false, flags as u8, 3000);
+        let ip = IpPacket::V4(Ipv4Packet::new(&raw).unwrap());
+
+        assert_eq!(gro.segments(&ip), 3);
+        let frames = gro.split(&ip).unwrap();
+        assert_eq!(frames.len(), 3);
+        let mut payload = Vec::new();
+        for (i, frame) in frames.iter().enumerate() {
+            // tun header, then the segment
+            assert_eq!(&frame[..4], &[0x00, 0x01, 0x08, 0x00]);
+            let seg = &frame[4..];
+            assert!(seg.len() <= 1500);
+            assert_eq!(deser_be_u32_slice(&seg[0..4]) & 0xffff, seg.len() as u32);
+            assert_eq!(&seg[4..6], &be16(0xfffe_u16.wrapping_add(i as u16)));
+            assert_eq!(deser_be_u32_slice(&seg[24..28]), 0xffff_ff00_u32.wrapping_add(i as u32 * 1460));
+            let seg_flags = seg[33] as u16;
+            assert_eq!(seg_flags & TcpFlags::CWR != 0, i == 0);
+            assert_eq!(seg_flags & (TcpFlags::FIN | TcpFlags::PSH) != 0, i == 2);
+            assert!(seg_flags & TcpFlags::ACK != 0);
+
+            // checksums verify
+            assert_eq!(checksum(&[&seg[..20]]), 0);
+            let mut pseudo = seg[12..20].to_vec();
+            pseudo.extend_from_slice(&[0, 6]);
+            pseudo.extend_from_slice(&be16(seg.len() as u16 - 20));
+            assert_eq!(checksum(&[&pseudo, &seg[20..]]), 0);
+            payload.extend_from_slice(&seg[40..]);
+        }
+        assert_eq!(payload, raw[40..].to_vec());
+
+        // packets that fit aren't split
+        let small = superpacket(false, TcpFlags::ACK as u8, 100);
+        let ip = IpPacket::V4(Ipv4Packet::new(&small).unwrap());
+        assert_eq!(gro.segments(&ip), 1);
+        assert!(gro.split(&ip).is_none());
+
+        assert_eq!(gro.take_report(), "gro superpackets 1 segments 3 split 1 unsplittable 0");
+    }
+
+    #[test]
+    fn test_gro_split_v6() {
+        let mut gro = Gro::new(GroConfig { mtu: 1280 });
+        let raw = superpacket(true, TcpFlags::ACK as u8, 2000);
+        let ip = IpPacket::V6(Ipv6Packet::new(&raw).unwrap());
+        let frames = gro.split(&ip).unwrap();
+        assert_eq!(frames.len(), 2);
+        for frame in frames.iter() {
+            assert_eq!(&frame[..4], &[0x00, 0x01, 0x86, 0xdd]);
+            let seg = &frame[4..];
+            let tcp_len = seg.len() - 40;
+            assert_eq!(&seg[4..6], &be16(tcp_len as u16));
+            let mut pseudo = seg[8..40].to_vec();
+            pseudo.extend_from_slice(&be32(tcp_len as u32));
+            pseudo.extend_from_slice(&[0, 0, 0, 6]);
+            assert_eq!(checksum(&[&pseudo, &seg[40..]]), 0);
+        }
+        assert_eq!(frames[0].len(), 4 + 1280);
+
+        // not TCP: forwarded whole
+        let mut udp = raw.clone();
+        udp[6] = 17;
+        let ip = IpPacket::V6(Ipv6Packet::new(&udp).unwrap());
+        assert_eq!(gro.segments(&ip), 1);
+        assert!(gro.split(&ip).is_none());
+        assert_eq!(gro.take_report(), "gro superpackets 1 segments 1 split 1 unsplittable 1");
+    }
+}
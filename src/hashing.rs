@@ -0,0 +1,267 @@
+//
+// Map Hashing
+//
+// The session map is keyed by formatted address strings, and the flow maps by
+// address tuples, all looked up for every packet. The default SipHash is
+// built to resist hash flooding but is slow for keys like these, so the
+// algorithm used by the session and flow maps can be chosen
+// (SessionConfig.hash, DETECTOR_MAP_HASH):
+//
+//  - sip: SipHash 1-3 with random keys (std's default);
+//  - fx: the multiply-rotate hash rustc uses, much faster on short keys but
+//    unkeyed, so an attacker who controls the keys can force collisions;
+//  - ahash: AES based keyed hash, fast where the CPU has AES instructions.
+//    Needs the ahash-hasher feature.
+//
+// Clients pick their own source addresses and ports, which end up in the
+// flow keys, so fx trades flooding resistance for speed. benches/sessions.rs
+// compares the algorithms' throughput and collisions on session keys.
+
+use std::collections::hash_map::{DefaultHasher, RandomState};
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::str::FromStr;
+
+#[cfg(feature = "ahash-hasher")]
+use ahash;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HashAlgorithm
+{
+    Sip,
+    Fx,
+    #[cfg(feature = "ahash-hasher")]
+    AHash,
+}
+
+impl Default for HashAlgorithm
+{
+    fn default() -> HashAlgorithm {
+        HashAlgorithm::Sip
+    }
+}
+
+impl FromStr for HashAlgorithm
+{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<HashAlgorithm, String> {
+        match s {
+            "sip" => Ok(HashAlgorithm::Sip),
+            "fx" => Ok(HashAlgorithm::Fx),
+            #[cfg(feature = "ahash-hasher")]
+            "ahash" => Ok(HashAlgorithm::AHash),
+            #[cfg(not(feature = "ahash-hasher"))]
+            "ahash" => Err("ahash needs the ahash-hasher feature".to_string()),
+            _ => Err(format!("unknown hash algorithm {:?}", s)),
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HashAlgorithm::Sip => write!(f, "sip"),
+            HashAlgorithm::Fx => write!(f, "fx"),
+            #[cfg(feature = "ahash-hasher")]
+            HashAlgorithm::AHash => write!(f, "ahash"),
+        }
+    }
+}
+
+// Builds the hashers of a map with the chosen algorithm (and, for keyed
+// algorithms, the map's random keys).
+#[derive(Clone)]
+pub enum MapHasher
+{
+    Sip(RandomState),
+    Fx,
+    #[cfg(feature = "ahash-hasher")]
+    AHash(ahash::RandomState),
+}
+
+impl MapHasher
+{
+    pub fn new(algorithm: HashAlgorithm) -> MapHasher {
+        match algorithm {
+            HashAlgorithm::Sip => MapHasher::Sip(RandomState::new()),
+            HashAlgorithm::Fx => MapHasher::Fx,
+            #[cfg(feature = "ahash-hasher")]
+            HashAlgorithm::AHash => MapHasher::AHash(ahash::RandomState::new()),
+        }
+    }
+}
+
+impl Default for MapHasher
+{
+    fn default() -> MapHasher {
+        MapHasher::new(HashAlgorithm::default())
+    }
+}
+
+impl BuildHasher for MapHasher
+{
+    type Hasher = MapHasherState;
+
+    #[inline]
+    fn build_hasher(&self) -> MapHasherState {
+        match self {
+            MapHasher::Sip(s) => MapHasherState::Sip(s.build_hasher()),
+            MapHasher::Fx => MapHasherState::Fx(FxHasher::default()),
+            #[cfg(feature = "ahash-hasher")]
+            MapHasher::AHash(s) => MapHasherState::AHash(s.build_hasher()),
+        }
+    }
+}
+
+pub enum MapHasherState
+{
+    Sip(DefaultHasher),
+    Fx(FxHasher),
+    #[cfg(feature = "ahash-hasher")]
+    AHash(ahash::AHasher),
+}
+
+impl Hasher for MapHasherState
+{
+    #[inline]
+    fn finish(&self) -> u64 {
+        match self {
+            MapHasherState::Sip(h) => h.finish(),
+            MapHasherState::Fx(h) => h.finish(),
+            #[cfg(feature = "ahash-hasher")]
+            MapHasherState::AHash(h) => h.finish(),
+        }
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        match self {
+            MapHasherState::Sip(h) => h.write(bytes),
+            MapHasherState::Fx(h) => h.write(bytes),
+            #[cfg(feature = "ahash-hasher")]
+            MapHasherState::AHash(h) => h.write(bytes),
+        }
+    }
+}
+
+const FX_SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+// rustc's FxHasher: each word is mixed in with a rotate, xor and multiply.
+#[derive(Default)]
+pub struct FxHasher
+{
+    hash: u64,
+}
+
+impl FxHasher
+{
+    #[inline]
+    fn add(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(FX_SEED);
+    }
+}
+
+impl Hasher for FxHasher
+{
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for c in chunks.by_ref() {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(c);
+            self.add(u64::from_le_bytes(word));
+        }
+        let rest = chunks.remainder();
+        if !rest.is_empty() {
+            let mut word = [0u8; 8];
+            word[..rest.len()].copy_from_slice(rest);
+            // keep "ab" and "ab\0" apart
+            self.add(u64::from_le_bytes(word) ^ ((rest.len() as u64) << 59));
+        }
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.add(i as u64);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.add(i as u64);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.add(i as u64);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.add(i);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.add(i as u64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hashing::*;
+    use std::collections::HashSet;
+    use std::hash::Hash;
+
+    fn hash_with<T: Hash>(builder: &MapHasher, value: &T) -> u64 {
+        let mut h = builder.build_hasher();
+        value.hash(&mut h);
+        h.finish()
+    }
+
+    #[test]
+    fn test_hash_algorithms() {
+        assert_eq!("sip".parse::<HashAlgorithm>(), Ok(HashAlgorithm::Sip));
+        assert_eq!("fx".parse::<HashAlgorithm>(), Ok(HashAlgorithm::Fx));
+        assert!("md5".parse::<HashAlgorithm>().is_err());
+        assert_eq!(HashAlgorithm::Fx.to_string(), "fx");
+        assert_eq!(HashAlgorithm::default(), HashAlgorithm::Sip);
+        #[cfg(not(feature = "ahash-hasher"))]
+        assert!("ahash".parse::<HashAlgorithm>().is_err());
+
+        // fx is unkeyed, the same everywhere
+        let fx = MapHasher::new(HashAlgorithm::Fx);
+        let key = "192.168.0.1-10.10.0.1-443".to_string();
+        assert_eq!(hash_with(&fx, &key), hash_with(&MapHasher::new(HashAlgorithm::Fx), &key));
+        // sip is keyed per map, but consistent within one
+        let sip = MapHasher::new(HashAlgorithm::Sip);
+        assert_eq!(hash_with(&sip, &key), hash_with(&sip, &key));
+
+        // trailing zero bytes change the hash
+        let mut a = FxHasher::default();
+        a.write(b"ab");
+        let mut b = FxHasher::default();
+        b.write(b"ab\0");
+        assert!(a.finish() != b.finish());
+
+        // every algorithm works as a map hasher
+        let mut algorithms = vec![HashAlgorithm::Sip, HashAlgorithm::Fx];
+        #[cfg(feature = "ahash-hasher")]
+        algorithms.push(HashAlgorithm::AHash);
+        for algorithm in algorithms {
+            let mut set = HashSet::with_hasher(MapHasher::new(algorithm));
+            for i in 0..1000 {
+                set.insert(format!("10.0.{}.{}-192.0.0.1-443", i / 256, i % 256));
+            }
+            assert_eq!(set.len(), 1000);
+            assert!(set.contains("10.0.3.231-192.0.0.1-443"));
+            assert!(!set.contains("10.0.3.232-192.0.0.1-443"));
+        }
+    }
+}
@@ -0,0 +1,254 @@
+//
+// Health Check
+//
+// A quick answer to "is this detector healthy". `conjure -H` runs the checks
+// for every core it would start, printing the results and exiting nonzero if
+// any failed, and the admin command "healthcheck" runs them from inside a
+// running core. Each check gives one line, "ok <check>: <detail>" or
+// "FAIL <check>: <reason>":
+//
+//   config     the DETECTOR_* settings and the station config parse
+//   redis      the registration redis answers PING
+//   interface  the capture interface exists and is up (not checked when
+//              capturing from a PF_RING ZC cluster)
+//   tun        tun<core> exists (see on-reboot.sh) and /dev/net/tun can be
+//              opened for writing
+//   ingest     a synthetic registration fed to an ingest thread, with the
+//              core's session config, ends up tracked
+//
+// The ingest check uses a tracker of its own, so it never adds a session to
+// a running core.
+
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use protobuf::Message;
+use redis;
+
+use error::error_chain;
+use flow_tracker::FlowNoSrcPort;
+use ingest::{ChannelFeed, get_redis_conn};
+use sessions::{SessionConfig, SessionTracker, DEFAULT_PHANTOM_PORT};
+use signalling::StationToDetector;
+use timekeeping::{Clock, WallClockNs};
+
+// The synthetic registration, from TEST-NET addresses.
+const LOOPBACK_CLIENT: &'static str = "192.0.2.1";
+const LOOPBACK_PHANTOM: &'static str = "198.51.100.1";
+
+// How long the ingest check waits for the registration to be tracked (it may
+// go through an insertion queue).
+const LOOPBACK_WAIT: Duration = Duration::from_secs(1);
+
+const IFF_UP: u32 = 0x1;
+
+#[derive(Clone)]
+pub struct HealthcheckConfig
+{
+    pub redis_url: String,
+    // Capture interface. None when capturing from a PF_RING ZC cluster.
+    pub interface: Option<String>,
+    pub tun: String,
+    // Problems found reading the configuration.
+    pub config_errors: Vec<String>,
+    // Where the interfaces and the tun device are looked for, the host's by
+    // default.
+    pub net_dir: PathBuf,
+    pub tun_device: PathBuf,
+}
+
+impl Default for HealthcheckConfig
+{
+    fn default() -> HealthcheckConfig {
+        HealthcheckConfig {
+            redis_url: String::new(),
+            interface: None,
+            tun: String::new(),
+            config_errors: Vec::new(),
+            net_dir: PathBuf::from("/sys/class/net"),
+            tun_device: PathBuf::from("/dev/net/tun"),
+        }
+    }
+}
+
+pub struct Check
+{
+    pub name: &'static str,
+    pub result: Result<String, String>,
+}
+
+impl fmt::Display for Check
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.result {
+            Ok(ref detail) => write!(f, "ok {}: {}", self.name, detail),
+            Err(ref reason) => write!(f, "FAIL {}: {}", self.name, reason),
+        }
+    }
+}
+
+/// Runs every check, `session_config` being the core's.
+pub fn run(config: &HealthcheckConfig, session_config: &SessionConfig) -> Vec<Check> {
+    vec![
+        Check { name: "config", result: check_config(&config.config_errors) },
+        Check { name: "redis", result: check_redis(&config.redis_url) },
+        Check { name: "interface", result: check_interface(config, config.interface.as_deref()) },
+        Check { name: "tun", result: check_tun(config, &config.tun) },
+        Check { name: "ingest", result: check_ingest(session_config) },
+    ]
+}
+
+pub fn healthy(checks: &[Check]) -> bool {
+    checks.iter().all(|c| c.result.is_ok())
+}
+
+fn check_config(errors: &[String]) -> Result<String, String> {
+    if errors.is_empty() {
+        Ok("valid".to_string())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+fn check_redis(url: &str) -> Result<String, String> {
+    let con = get_redis_conn(url).map_err(|e| error_chain(&e))?;
+    let pong: String = redis::cmd("PING").query(&con)
+        .map_err(|e| format!("PING to {} failed: {}", url, e))?;
+    Ok(format!("{} answered {}", url, pong))
+}
+
+// Interface flags from sysfs, or why they can't be read.
+fn interface_flags(config: &HealthcheckConfig, device: &str) -> Result<u32, String> {
+    let path = config.net_dir.join(device).join("flags");
+    let flags = fs::read_to_string(&path)
+        .map_err(|_| format!("no interface {}", device))?;
+    u32::from_str_radix(flags.trim().trim_start_matches("0x"), 16)
+        .map_err(|_| format!("can't parse {} {:?}", path.display(), flags.trim()))
+}
+
+fn check_interface(config: &HealthcheckConfig, device: Option<&str>) -> Result<String, String> {
+    let device = match device {
+        Some(d) => d,
+        None => return Ok("zero copy cluster, not checked".to_string()),
+    };
+    if interface_flags(config, device)? & IFF_UP == 0 {
+        return Err(format!("{} is down", device))
+    }
+    Ok(format!("{} is up", device))
+}
+
+fn check_tun(config: &HealthcheckConfig, device: &str) -> Result<String, String> {
+    interface_flags(config, device)?;
+    let tun = config.tun_device.display();
+    OpenOptions::new().read(true).write(true).open(&config.tun_device)
+        .map_err(|e| format!("can't open {}: {}", tun, e))?;
+    Ok(format!("{} exists and {} is writable", device, tun))
+}
+
+fn check_ingest(session_config: &SessionConfig) -> Result<String, String> {
+    let tracker = SessionTracker::with_config(session_config.clone(), Clock::Monotonic);
+    let (tx, rx) = mpsc::channel();
+    let ingest = tracker.spawn_ingest_thread(ChannelFeed::new(rx));
+
+    let mut s2d = StationToDetector::new();
+    s2d.set_client_ip(LOOPBACK_CLIENT.to_string());
+    s2d.set_phantom_ip(LOOPBACK_PHANTOM.to_string());
+    s2d.set_timeout_ns(10*1000*1000*1000);
+    s2d.set_station_time_unix_ns(WallClockNs::now().0);
+    s2d.set_message_id(b"healthcheck".to_vec());
+    let payload = s2d.write_to_bytes().map_err(|e| e.to_string())?;
+    tx.send(payload).map_err(|e| e.to_string())?;
+    drop(tx);
+    ingest.join().map_err(|_| "ingest thread panicked".to_string())?;
+
+    let port = match session_config.phantom_ports.first() {
+        Some(p) => *p,
+        None => DEFAULT_PHANTOM_PORT,
+    };
+    let flow = FlowNoSrcPort {
+        src_ip: LOOPBACK_CLIENT.parse().unwrap(),
+        dst_ip: LOOPBACK_PHANTOM.parse().unwrap(),
+        dst_port: port,
+    };
+    let mut waited = Duration::from_millis(0);
+    while !tracker.is_tracked_session(&flow) {
+        if waited >= LOOPBACK_WAIT {
+            return Err("synthetic registration was not tracked".to_string())
+        }
+        thread::sleep(Duration::from_millis(10));
+        waited += Duration::from_millis(10);
+    }
+    Ok("synthetic registration tracked".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use healthcheck::*;
+    use std::path::Path;
+
+    // A host of our own in `dir`, where eth0 is up, eth1 down and tun0 exists.
+    fn fake_host(dir: &Path) -> HealthcheckConfig {
+        let _ = fs::remove_dir_all(dir);
+        for (device, flags) in [("eth0", "0x1003"), ("eth1", "0x1002"), ("tun0", "0x1091")].iter() {
+            fs::create_dir_all(dir.join("net").join(device)).unwrap();
+            fs::write(dir.join("net").join(device).join("flags"), format!("{}\n", flags)).unwrap();
+        }
+        fs::write(dir.join("tun"), b"").unwrap();
+        HealthcheckConfig {
+            redis_url: "redis://127.0.0.1:1/".to_string(),
+            interface: Some("eth0".to_string()),
+            tun: "tun0".to_string(),
+            config_errors: Vec::new(),
+            net_dir: dir.join("net"),
+            tun_device: dir.join("tun"),
+        }
+    }
+
+    #[test]
+    fn test_healthcheck_checks() {
+        let dir = ::std::env::temp_dir().join(format!("healthcheck-checks-test-{}", ::std::process::id()));
+        let config = fake_host(&dir);
+        assert_eq!(check_config(&[]), Ok("valid".to_string()));
+        assert_eq!(check_config(&["a".to_string(), "b".to_string()]), Err("a; b".to_string()));
+
+        assert_eq!(check_interface(&config, Some("eth0")), Ok("eth0 is up".to_string()));
+        assert_eq!(check_interface(&config, Some("eth1")), Err("eth1 is down".to_string()));
+        assert!(check_interface(&config, None).is_ok());
+        assert_eq!(check_interface(&config, Some("no-such-if0")), Err("no interface no-such-if0".to_string()));
+        assert!(check_tun(&config, "tun0").is_ok());
+        assert_eq!(check_tun(&config, "no-such-tun0"), Err("no interface no-such-tun0".to_string()));
+        let no_tun = HealthcheckConfig { tun_device: dir.join("no-such-tun"), ..config.clone() };
+        assert!(check_tun(&no_tun, "tun0").unwrap_err().starts_with("can't open "));
+
+        assert!(check_redis("redis://127.0.0.1:1/").is_err());
+
+        assert_eq!(check_ingest(&SessionConfig::default()), Ok("synthetic registration tracked".to_string()));
+        let other_port = SessionConfig { phantom_ports: vec![8443], ..SessionConfig::default() };
+        assert!(check_ingest(&other_port).is_ok());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_healthcheck_report() {
+        let dir = ::std::env::temp_dir().join(format!("healthcheck-report-test-{}", ::std::process::id()));
+        let config = HealthcheckConfig {
+            tun: "no-such-tun0".to_string(),
+            config_errors: vec!["can't parse DETECTOR_TUN_MTU".to_string()],
+            ..fake_host(&dir)
+        };
+        let checks = run(&config, &SessionConfig::default());
+        assert!(!healthy(&checks));
+        let lines: Vec<String> = checks.iter().map(|c| c.to_string()).collect();
+        assert_eq!(lines[0], "FAIL config: can't parse DETECTOR_TUN_MTU");
+        assert!(lines[1].starts_with("FAIL redis: "));
+        assert_eq!(lines[2], "ok interface: eth0 is up");
+        assert_eq!(lines[3], "FAIL tun: no interface no-such-tun0");
+        assert_eq!(lines[4], "ok ingest: synthetic registration tracked");
+        assert!(healthy(&checks[2..3]));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,301 @@
+//
+// Registration Feeds
+//
+// The session tracker ingest thread consumes serialized StationToDetector
+// messages (or, for the feeds of json_registration.rs, JSON registrations)
+// from a RegistrationFeed. In production this is the redis pubsub
+// channel the application publishes new registrations to; tests use the
+// channel backed feed so the ingest logic can be exercised without a redis
+// server. At startup the registrations the station keeps under a redis key
+// can also be read once (RedisSnapshot), to prime the tracker with the
+// sessions registered before the detector subscribed.
+//
+// (The trait is not called RegistrationSource to avoid confusion with the
+// protobuf enum of that name, which describes the registrar that produced a
+// registration rather than how it reached the detector.)
+//
+// Registrations stamped with the station's clock at sending are also used to
+// measure how long messages take to reach the detector (IngestLatency). The
+// measurement includes any skew between the two hosts' clocks; messages that
+// appear to arrive before they were sent are counted separately.
+//
+// A feed that can't be read at all (e.g. redis is unreachable, or its
+// connection drops) returns a DetectorError and the thread reading it exits;
+// the error is logged by the caller, which for the redis ingest thread is its
+// supervisor (see supervisor.rs), reconnecting with a new feed.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::Receiver;
+
+use protobuf::Message;
+use redis;
+
+use error::{DetectorError, DetectorResult};
+use metrics::{Histogram, ns_label};
+use probes::{Heartbeat, HEARTBEAT_INTERVAL};
+use timekeeping::{self, WallClockNs};
+
+pub const REDIS_URL: &'static str = "redis://127.0.0.1/";
+pub const REDIS_CHANNEL: &'static str = "dark_decoy_map";
+
+// Upper bounds of the ingest latency histogram buckets, in ns.
+const LATENCY_BUCKETS_NS: [u64; 5] = [
+    1000*1000, 10*1000*1000, 100*1000*1000, 1000*1000*1000, 10*1000*1000*1000];
+
+// How the payloads of a feed are serialized.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PayloadFormat
+{
+    // StationToDetector protobufs.
+    Protobuf,
+    // JSON registrations, see json_registration.rs.
+    #[cfg(feature = "json-registrations")]
+    Json,
+}
+
+pub trait RegistrationFeed
+{
+    /// Calls `handle` with the raw payload of each message received, in order.
+    /// Only returns once the feed is closed, or with an error if the feed
+    /// can't be read; errors reading an individual message are logged and
+    /// skipped.
+    fn for_each_payload(&mut self, handle: &mut dyn FnMut(&[u8])) -> DetectorResult<()>;
+
+    /// How the payloads are serialized.
+    fn format(&self) -> PayloadFormat {
+        PayloadFormat::Protobuf
+    }
+}
+
+// Subscribes to a redis pubsub channel.
+pub struct RedisFeed
+{
+    url: String,
+    channel: String,
+    // Beaten on every message, and every HEARTBEAT_INTERVAL while idle.
+    heartbeat: Option<Heartbeat>,
+}
+
+impl RedisFeed
+{
+    pub fn new(url: &str, channel: &str) -> RedisFeed {
+        RedisFeed {
+            url: url.to_string(),
+            channel: channel.to_string(),
+            heartbeat: None,
+        }
+    }
+
+    /// Beats `heartbeat` while reading the channel (see probes.rs).
+    pub fn with_heartbeat(mut self, heartbeat: Heartbeat) -> RedisFeed {
+        self.heartbeat = Some(heartbeat);
+        self
+    }
+}
+
+impl RegistrationFeed for RedisFeed
+{
+    // Only returns if we can't subscribe or the connection fails, otherwise
+    // it runs for the lifetime of the process.
+    fn for_each_payload(&mut self, handle: &mut dyn FnMut(&[u8])) -> DetectorResult<()> {
+        let mut con = get_redis_conn(&self.url)?;
+        if self.heartbeat.is_some() {
+            con.set_read_timeout(Some(HEARTBEAT_INTERVAL))
+                .map_err(|e| DetectorError::redis("read timeout", e))?;
+        }
+        let mut pubsub = con.as_pubsub();
+        pubsub.subscribe(&self.channel)
+            .map_err(|e| DetectorError::redis(&format!("subscribe to {}", self.channel), e))?;
+
+        loop {
+            // beaten once the read returns, so a read hung past the timeout
+            // stops the heartbeat
+            let msg = pubsub.get_message();
+            if let Some(ref heartbeat) = self.heartbeat {
+                heartbeat.beat(timekeeping::now());
+            }
+            let msg = match msg {
+                Ok(m) => m,
+                Err(ref e) if e.is_timeout() => continue,
+                // the connection is gone, reading again would only spin
+                Err(e) if e.is_io_error() || e.is_connection_dropped() => {
+                    return Err(DetectorError::redis(&format!("read from {}", self.channel), e))
+                },
+                Err(e) => {
+                    debug!("Error reading message from redis: {}", e);
+                    continue
+                }
+            };
+            let payload : Vec<u8> = match msg.get_payload(){
+                Ok(m) => m,
+                Err(e) => {
+                    debug!("Error reading payload: {}", e);
+                    continue
+                }
+            };
+            handle(&payload);
+        }
+    }
+}
+
+// Reads the registrations stored under a redis key, the values of a hash or
+// the members of a set, then closes. A missing key holds none.
+pub struct RedisSnapshot
+{
+    url: String,
+    key: String,
+}
+
+impl RedisSnapshot
+{
+    pub fn new(url: &str, key: &str) -> RedisSnapshot {
+        RedisSnapshot {
+            url: url.to_string(),
+            key: key.to_string(),
+        }
+    }
+}
+
+impl RegistrationFeed for RedisSnapshot
+{
+    fn for_each_payload(&mut self, handle: &mut dyn FnMut(&[u8])) -> DetectorResult<()> {
+        let con = get_redis_conn(&self.url)?;
+        let key_type: String = redis::cmd("TYPE").arg(&self.key).query(&con)
+            .map_err(|e| DetectorError::redis(&format!("type of {}", self.key), e))?;
+        let command = match key_type.as_str() {
+            "hash" => "HVALS",
+            "set" => "SMEMBERS",
+            "none" => return Ok(()),
+            _ => return Err(DetectorError::redis(&format!("{} is a {}, not a hash or set", self.key, key_type),
+                redis::RedisError::from((redis::ErrorKind::TypeError, "unexpected key type")))),
+        };
+        let payloads: Vec<Vec<u8>> = redis::cmd(command).arg(&self.key).query(&con)
+            .map_err(|e| DetectorError::redis(&format!("read {}", self.key), e))?;
+        for payload in payloads.iter() {
+            handle(payload);
+        }
+        Ok(())
+    }
+}
+
+pub fn get_redis_conn(url: &str) -> DetectorResult<redis::Connection>
+{
+    redis::Client::open(url)
+        .and_then(|client| client.get_connection())
+        .map_err(|e| DetectorError::redis(&format!("connection to {}", url), e))
+}
+
+/// Parses a protobuf payload received from a feed. `message` names the
+/// message type for the error.
+pub fn parse_message<M: Message>(payload: &[u8], message: &'static str) -> DetectorResult<M> {
+    M::parse_from_bytes(payload)
+        .map_err(|e| DetectorError::Parse { message: message, source: e })
+}
+
+// In-memory feed. Closes once every Sender for the channel has been dropped.
+pub struct ChannelFeed
+{
+    rx: Receiver<Vec<u8>>,
+}
+
+impl ChannelFeed
+{
+    pub fn new(rx: Receiver<Vec<u8>>) -> ChannelFeed {
+        ChannelFeed { rx: rx }
+    }
+}
+
+impl RegistrationFeed for ChannelFeed
+{
+    fn for_each_payload(&mut self, handle: &mut dyn FnMut(&[u8])) -> DetectorResult<()> {
+        for payload in self.rx.iter() {
+            handle(&payload);
+        }
+        Ok(())
+    }
+}
+
+// Delay between the station timestamp of a registration and its receipt.
+pub struct IngestLatency
+{
+    delays: Histogram,
+
+    // Messages stamped later than the local time they were received at.
+    ahead: AtomicU64,
+}
+
+impl IngestLatency
+{
+    pub fn new() -> IngestLatency {
+        IngestLatency {
+            delays: Histogram::new(&LATENCY_BUCKETS_NS),
+            ahead: AtomicU64::new(0),
+        }
+    }
+
+    /// Records a message sent at `sent` by the station's clock and received
+    /// at `received` by ours. Returns the delay, or None if the station's
+    /// clock was ahead.
+    pub fn record(&self, sent: WallClockNs, received: WallClockNs) -> Option<u64> {
+        if sent > received {
+            self.ahead.fetch_add(1, Ordering::Relaxed);
+            return None
+        }
+        let delay = received.saturating_since(sent);
+        self.delays.record(delay);
+        Some(delay)
+    }
+
+    /// Histogram and ahead count since the last call, formatted for the
+    /// periodic report.
+    pub fn take_report(&self) -> String {
+        format!("{} ahead {}", self.delays.take_formatted(ns_label),
+            self.ahead.swap(0, Ordering::Relaxed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ingest::*;
+    use signalling::StationToDetector;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_channel_feed_order_and_close() {
+        let (tx, rx) = mpsc::channel();
+        let mut feed = ChannelFeed::new(rx);
+
+        tx.send(vec![1]).unwrap();
+        tx.send(vec![]).unwrap();
+        tx.send(vec![2, 3]).unwrap();
+        drop(tx);
+
+        let mut seen: Vec<Vec<u8>> = Vec::new();
+        feed.for_each_payload(&mut |p| seen.push(p.to_vec())).unwrap();
+        assert_eq!(seen, vec![vec![1], vec![], vec![2, 3]]);
+    }
+
+    #[test]
+    fn test_redis_feed_unreachable() {
+        let mut feed = RedisFeed::new("redis://127.0.0.1:1/", REDIS_CHANNEL);
+        match feed.for_each_payload(&mut |_| panic!("no payloads expected")) {
+            Err(DetectorError::Redis { ref context, .. }) =>
+                assert_eq!(context, "connection to redis://127.0.0.1:1/"),
+            other => panic!("unexpected {:?}", other.err()),
+        }
+        match parse_message::<StationToDetector>(&[0xff, 0xff, 0xff], "StationToDetector") {
+            Err(DetectorError::Parse { message, .. }) => assert_eq!(message, "StationToDetector"),
+            other => panic!("unexpected {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn test_ingest_latency() {
+        let l = IngestLatency::new();
+        assert_eq!(l.record(WallClockNs(1000), WallClockNs(1000 + 5*1000*1000)), Some(5*1000*1000));
+        assert_eq!(l.record(WallClockNs(1000), WallClockNs(1000 + 20*1000*1000*1000)), Some(20*1000*1000*1000));
+        assert_eq!(l.record(WallClockNs(2000), WallClockNs(1000)), None);
+        assert_eq!(l.take_report(), "<1ms 0 <10ms 1 <100ms 0 <1s 0 <10s 0 >=10s 1 ahead 1");
+        assert_eq!(l.take_report(), "<1ms 0 <10ms 0 <100ms 0 <1s 0 <10s 0 >=10s 0 ahead 0");
+    }
+}
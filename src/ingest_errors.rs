@@ -0,0 +1,284 @@
+//
+// Ingest Error Storms
+//
+// A publisher spamming garbage on the registration channel used to get a
+// debug line per message and have every one of them parsed. The ingest path
+// now reports its failures to an IngestErrors, which:
+//
+// - counts them by kind: payloads that don't parse (Parse) and registrations
+//   that parse but describe no valid session, e.g. bad addresses or
+//   lifetimes (Invalid);
+// - logs at most IngestErrorConfig.logs_per_sec of each kind per second, and
+//   how many it suppressed once the second is over;
+// - with a breaker rate set, trips once more messages than that fail, of
+//   either kind, within a second. For the cooldown that follows the ingest
+//   thread slow-polls its feed (sleeping slow_poll before handling each
+//   message), so a storm neither burns CPU nor floods the logs. Messages are
+//   still all handled, valid registrations received meanwhile are only late.
+//
+// Each feed has a breaker (and log lines) of its own (IngestErrors::for_feed),
+// so a storm on one feed doesn't slow the others. Their counts are reported
+// together.
+//
+// Reported per period: failures by kind, suppressed log lines, messages
+// slowed and breaker trips.
+
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use timekeeping::{MonotonicNs, duration_ns};
+
+const WINDOW_NS: u64 = 1000*1000*1000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IngestError
+{
+    Parse,
+    Invalid,
+}
+
+impl IngestError
+{
+    fn name(self) -> &'static str {
+        match self {
+            IngestError::Parse => "parse",
+            IngestError::Invalid => "invalid",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct IngestErrorConfig
+{
+    // Lines logged per second for each kind of failure.
+    pub logs_per_sec: u32,
+    // Failures per second that trip the breaker, None never trips.
+    pub breaker_rate: Option<u32>,
+    // Time the breaker stays open once tripped.
+    pub cooldown: Duration,
+    // Sleep before each message handled while the breaker is open.
+    pub slow_poll: Duration,
+}
+
+impl Default for IngestErrorConfig
+{
+    fn default() -> IngestErrorConfig {
+        IngestErrorConfig {
+            logs_per_sec: 10,
+            breaker_rate: None,
+            cooldown: Duration::from_secs(10),
+            slow_poll: Duration::from_millis(10),
+        }
+    }
+}
+
+// Failures since start, in the current second.
+#[derive(Default)]
+struct Window
+{
+    start: MonotonicNs,
+    count: u64,
+}
+
+impl Window
+{
+    // Starts a new window if the current one is over, returning whether it
+    // was.
+    fn roll(&mut self, now: MonotonicNs) -> bool {
+        if now.saturating_since(self.start) < WINDOW_NS {
+            return false
+        }
+        self.start = now;
+        self.count = 0;
+        true
+    }
+}
+
+#[derive(Default)]
+struct BreakerState
+{
+    // Log lines of each kind in the current second, and failures of any kind.
+    logged: [Window; 2],
+    failures: Window,
+    suppressed: [u64; 2],
+    open_until: Option<MonotonicNs>,
+}
+
+// Shared by the IngestErrors of every feed.
+struct IngestErrorCounts
+{
+    failed: [AtomicU64; 2],
+    suppressed: AtomicU64,
+    slowed: AtomicU64,
+    trips: AtomicU64,
+    // Breakers open now.
+    open: AtomicU64,
+}
+
+pub struct IngestErrors
+{
+    config: IngestErrorConfig,
+    state: Mutex<BreakerState>,
+    counts: Arc<IngestErrorCounts>,
+}
+
+impl IngestErrors
+{
+    pub fn new(config: IngestErrorConfig) -> IngestErrors {
+        IngestErrors {
+            config: config,
+            state: Mutex::new(BreakerState::default()),
+            counts: Arc::new(IngestErrorCounts {
+                failed: [AtomicU64::new(0), AtomicU64::new(0)],
+                suppressed: AtomicU64::new(0),
+                slowed: AtomicU64::new(0),
+                trips: AtomicU64::new(0),
+                open: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Errors of another feed, with a breaker and log lines of its own,
+    /// counted and reported together with these.
+    pub fn for_feed(&self) -> IngestErrors {
+        IngestErrors {
+            config: self.config.clone(),
+            state: Mutex::new(BreakerState::default()),
+            counts: Arc::clone(&self.counts),
+        }
+    }
+
+    pub fn config(&self) -> &IngestErrorConfig {
+        &self.config
+    }
+
+    /// Counts a failure of `kind` at `now`, logging `reason` unless this
+    /// second's lines of that kind are used up, and trips the breaker if
+    /// failures are over its rate.
+    pub fn failed(&self, kind: IngestError, now: MonotonicNs, reason: &str) {
+        let i = kind as usize;
+        self.counts.failed[i].fetch_add(1, Ordering::Relaxed);
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.logged[i].roll(now) && state.suppressed[i] > 0 {
+            debug!("suppressed {} more ingest {} errors", state.suppressed[i], kind.name());
+            state.suppressed[i] = 0;
+        }
+        if state.logged[i].count < self.config.logs_per_sec as u64 {
+            state.logged[i].count += 1;
+            debug!("{}", reason);
+        } else {
+            state.suppressed[i] += 1;
+            self.counts.suppressed.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let rate = match self.config.breaker_rate {
+            Some(r) => r as u64,
+            None => return,
+        };
+        state.failures.roll(now);
+        state.failures.count += 1;
+        if state.failures.count > rate && state.open_until.is_none() {
+            state.open_until = Some(now.saturating_add(duration_ns(self.config.cooldown)));
+            self.counts.trips.fetch_add(1, Ordering::Relaxed);
+            self.counts.open.fetch_add(1, Ordering::Relaxed);
+            warn!("over {} malformed registrations in a second, slowing ingest for {}ms",
+                rate, self.config.cooldown.as_millis());
+        }
+    }
+
+    /// Whether the feed is to be slowed down before a message received at
+    /// `now` is handled, the breaker being open. Closes the breaker once its
+    /// cooldown is over.
+    pub fn slowing(&self, now: MonotonicNs) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        match state.open_until {
+            Some(until) if now < until => {
+                self.counts.slowed.fetch_add(1, Ordering::Relaxed);
+                true
+            },
+            Some(_) => {
+                info!("resuming ingest after slowing it for malformed registrations");
+                self.counts.open.fetch_sub(1, Ordering::Relaxed);
+                state.open_until = None;
+                state.failures = Window { start: now, count: 0 };
+                false
+            },
+            None => false,
+        }
+    }
+
+    /// Counters since the last call, formatted for the periodic report.
+    pub fn take_report(&self) -> String {
+        let counts = &self.counts;
+        format!("ingest errors parse {} invalid {} suppressed logs {} slowed {} breaker trips {}{}",
+            counts.failed[IngestError::Parse as usize].swap(0, Ordering::Relaxed),
+            counts.failed[IngestError::Invalid as usize].swap(0, Ordering::Relaxed),
+            counts.suppressed.swap(0, Ordering::Relaxed),
+            counts.slowed.swap(0, Ordering::Relaxed),
+            counts.trips.swap(0, Ordering::Relaxed),
+            if counts.open.load(Ordering::Relaxed) > 0 { " (open)" } else { "" })
+    }
+}
+
+impl Drop for IngestErrors
+{
+    fn drop(&mut self) {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.open_until.is_some() {
+            self.counts.open.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ingest_errors::*;
+
+    const MS: u64 = 1000*1000;
+
+    #[test]
+    fn test_ingest_errors() {
+        let config = IngestErrorConfig {
+            logs_per_sec: 2,
+            breaker_rate: Some(6),
+            cooldown: Duration::from_secs(3),
+            ..IngestErrorConfig::default()
+        };
+        let errors = IngestErrors::new(config);
+        for i in 0..5 {
+            errors.failed(IngestError::Parse, MonotonicNs(i * MS), "garbage");
+        }
+        errors.failed(IngestError::Invalid, MonotonicNs(5 * MS), "bad address");
+        assert!(!errors.slowing(MonotonicNs(6 * MS)));
+        assert_eq!(errors.take_report(), "ingest errors parse 5 invalid 1 suppressed logs 3 slowed 0 breaker trips 0");
+
+        // a second later the lines are back, the failure count restarts
+        for i in 0..6 {
+            errors.failed(IngestError::Parse, MonotonicNs(1000 * MS + i), "garbage");
+        }
+        assert!(!errors.slowing(MonotonicNs(1001 * MS)));
+        // the seventh in that second trips the breaker
+        errors.failed(IngestError::Invalid, MonotonicNs(1002 * MS), "bad address");
+        assert!(errors.slowing(MonotonicNs(1003 * MS)));
+        // only this feed's
+        let other = errors.for_feed();
+        assert!(!other.slowing(MonotonicNs(1003 * MS)));
+        assert!(errors.slowing(MonotonicNs(4001 * MS)));
+        assert_eq!(errors.take_report(),
+            "ingest errors parse 6 invalid 1 suppressed logs 4 slowed 2 breaker trips 1 (open)");
+
+        // closes after the cooldown, with a fresh count
+        assert!(!errors.slowing(MonotonicNs(4002 * MS)));
+        errors.failed(IngestError::Parse, MonotonicNs(4003 * MS), "garbage");
+        assert!(!errors.slowing(MonotonicNs(4004 * MS)));
+        assert_eq!(errors.take_report(), "ingest errors parse 1 invalid 0 suppressed logs 0 slowed 0 breaker trips 0");
+
+        // without a breaker rate it never trips
+        let errors = IngestErrors::new(IngestErrorConfig::default());
+        for i in 0..1000 {
+            errors.failed(IngestError::Parse, MonotonicNs(i), "garbage");
+        }
+        assert!(!errors.slowing(MonotonicNs(1000)));
+    }
+}
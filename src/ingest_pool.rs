@@ -0,0 +1,238 @@
+//
+// Ingest Worker Pool
+//
+// One ingest thread parses, checks and inserts every registration, and during
+// a station republish storm it caps how many registrations per second the
+// detector takes in. With an ingest pool configured (SessionConfig.ingest_pool)
+// the ingest thread only parses each message and follows its sequence number,
+// then hands it to one of a few worker threads which check and apply it.
+//
+// Registrations are sent to a worker by a hash of their session key, as the
+// SessionConfig builds it (mode, tenant, addresses and port), so the
+// registrations of one session are applied in the order they were received.
+// A registration with more than one session (v4 and v6 phantoms, or no port)
+// goes by its first. Messages that act on more than one
+// session (config and phantom subnet pushes, tenant session deletes) wait for
+// every worker to apply what it was sent before (barrier) and are then applied
+// by the ingest thread itself, so nothing registered before them is applied
+// after them, nor the other way around.
+//
+// Each worker's queue is bounded. When the queue of the worker a registration
+// hashes to is full the ingest thread blocks until it has room (counted as a
+// stall), pushing back on the feed rather than dropping registrations.
+//
+// Reported per period: the registrations applied by each worker, stalls and
+// barriers.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{sync_channel, SyncSender, TrySendError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use registration::{Command, Registration};
+
+// Time the ingest thread sleeps between looks at the workers while waiting
+// for them at a barrier.
+const BARRIER_POLL: Duration = Duration::from_micros(100);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct IngestPoolConfig
+{
+    // Worker threads.
+    pub workers: usize,
+    // Registrations queued for each worker before the ingest thread blocks.
+    pub capacity: usize,
+}
+
+impl Default for IngestPoolConfig
+{
+    fn default() -> IngestPoolConfig {
+        IngestPoolConfig { workers: 4, capacity: 1024 }
+    }
+}
+
+pub struct IngestPoolStats
+{
+    applied: Vec<AtomicU64>,
+    stalls: AtomicU64,
+    barriers: AtomicU64,
+}
+
+impl IngestPoolStats
+{
+    pub fn new(config: &IngestPoolConfig) -> IngestPoolStats {
+        IngestPoolStats {
+            applied: (0..config.workers.max(1)).map(|_| AtomicU64::new(0)).collect(),
+            stalls: AtomicU64::new(0),
+            barriers: AtomicU64::new(0),
+        }
+    }
+
+    /// Counters since the last call, formatted for the periodic report.
+    pub fn take_report(&self) -> String {
+        let applied: Vec<String> = self.applied.iter()
+            .map(|a| a.swap(0, Ordering::Relaxed).to_string())
+            .collect();
+        format!("ingest workers applied {} stalls {} barriers {}",
+            applied.join(" "),
+            self.stalls.swap(0, Ordering::Relaxed),
+            self.barriers.swap(0, Ordering::Relaxed))
+    }
+}
+
+struct Worker
+{
+    tx: SyncSender<Registration>,
+    // Registrations sent to the worker and not yet applied.
+    pending: Arc<AtomicUsize>,
+    thread: JoinHandle<()>,
+}
+
+pub struct IngestPool
+{
+    workers: Vec<Worker>,
+    stats: Arc<IngestPoolStats>,
+}
+
+impl IngestPool
+{
+    /// Starts the workers, each applying the registrations it is sent with
+    /// the function `make_apply` returns for it. They exit once the pool is
+    /// dropped.
+    pub fn spawn<M, F>(config: &IngestPoolConfig, stats: Arc<IngestPoolStats>, mut make_apply: M) -> IngestPool
+        where M: FnMut(usize) -> F, F: FnMut(&Registration) + Send + 'static
+    {
+        let workers = (0..stats.applied.len()).map(|i| {
+            let (tx, rx) = sync_channel::<Registration>(config.capacity.max(1));
+            let pending = Arc::new(AtomicUsize::new(0));
+            let thread_pending = Arc::clone(&pending);
+            let thread_stats = Arc::clone(&stats);
+            let mut apply = make_apply(i);
+            let thread = thread::spawn(move || {
+                while let Ok(reg) = rx.recv() {
+                    apply(&reg);
+                    thread_stats.applied[i].fetch_add(1, Ordering::Relaxed);
+                    thread_pending.fetch_sub(1, Ordering::Release);
+                }
+            });
+            Worker { tx: tx, pending: pending, thread: thread }
+        }).collect();
+        IngestPool { workers: workers, stats: stats }
+    }
+
+    /// Sends `reg` to the worker `shard` (the hash of its session key) picks,
+    /// blocking while that worker's queue is full. Panics if the worker is
+    /// gone, so the ingest thread is restarted with a new pool.
+    pub fn dispatch(&self, reg: Registration, shard: u64) {
+        let worker = &self.workers[(shard % self.workers.len() as u64) as usize];
+        worker.pending.fetch_add(1, Ordering::Relaxed);
+        let sent = match worker.tx.try_send(reg) {
+            Ok(_) => true,
+            Err(TrySendError::Full(reg)) => {
+                self.stats.stalls.fetch_add(1, Ordering::Relaxed);
+                worker.tx.send(reg).is_ok()
+            },
+            Err(TrySendError::Disconnected(_)) => false,
+        };
+        if !sent {
+            panic!("ingest worker exited");
+        }
+    }
+
+    /// Waits until every worker has applied everything sent to it so far.
+    pub fn barrier(&self) {
+        self.stats.barriers.fetch_add(1, Ordering::Relaxed);
+        for worker in self.workers.iter() {
+            while worker.pending.load(Ordering::Acquire) != 0 {
+                if worker.thread.is_finished() {
+                    panic!("ingest worker exited");
+                }
+                thread::sleep(BARRIER_POLL);
+            }
+        }
+    }
+}
+
+impl Drop for IngestPool
+{
+    fn drop(&mut self) {
+        for worker in self.workers.drain(..) {
+            drop(worker.tx);
+            let _ = worker.thread.join();
+        }
+    }
+}
+
+/// Whether `reg` acts on more than one session, and has to be applied with
+/// the workers stopped at a barrier.
+pub fn is_station_wide(reg: &Registration) -> bool {
+    match reg.command {
+        Command::Register | Command::Update => false,
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ingest_pool::*;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+    use std::sync::Mutex;
+
+    fn shard(phantom: &str) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        phantom.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn registration(client: &str, phantom: &str, timeout: u64) -> Registration {
+        Registration {
+            client_ip: client.to_string(),
+            phantom_ip: phantom.to_string(),
+            timeout: Some(Duration::from_nanos(timeout)),
+            ..Registration::default()
+        }
+    }
+
+    #[test]
+    fn test_ingest_pool_ordering() {
+        let config = IngestPoolConfig { workers: 3, capacity: 2 };
+        let stats = Arc::new(IngestPoolStats::new(&config));
+        let applied = Arc::new(Mutex::new(Vec::new()));
+        let pool = IngestPool::spawn(&config, Arc::clone(&stats), |i| {
+            let applied = Arc::clone(&applied);
+            move |reg: &Registration| {
+                applied.lock().unwrap().push((i, reg.phantom_ip.clone(), reg.timeout.unwrap().as_nanos() as u64));
+            }
+        });
+
+        let phantoms = ["10.10.0.1", "10.10.0.2", "10.10.0.3", "10.10.0.4", "10.10.0.5"];
+        for t in 1..20 {
+            for p in phantoms.iter() {
+                pool.dispatch(registration("192.168.0.1", p, t), shard(p));
+            }
+        }
+        pool.barrier();
+        let applied = applied.lock().unwrap().clone();
+        assert_eq!(applied.len(), 19 * phantoms.len());
+
+        // every session's registrations were applied by one worker, in order
+        for p in phantoms.iter() {
+            let of: Vec<_> = applied.iter().filter(|a| a.1 == *p).collect();
+            assert!(of.iter().all(|a| a.0 == of[0].0));
+            assert_eq!(of.iter().map(|a| a.2).collect::<Vec<_>>(), (1..20).collect::<Vec<u64>>());
+        }
+
+        let report = stats.take_report();
+        assert!(report.starts_with("ingest workers applied "));
+        assert!(report.ends_with("barriers 1"));
+        assert_eq!(stats.take_report(), "ingest workers applied 0 0 0 stalls 0 barriers 0");
+
+        let delete = Registration { command: Command::DeleteTenantSessions, ..Registration::default() };
+        assert!(is_station_wide(&delete));
+        assert!(!is_station_wide(&registration("192.168.0.1", "10.10.0.1", 1)));
+        let update = Registration { command: Command::Update, ..registration("192.168.0.1", "10.10.0.1", 2) };
+        assert!(!is_station_wide(&update));
+    }
+}
@@ -0,0 +1,210 @@
+//
+// Registration Insertion Queue
+//
+// Under heavy packet load the packet threads hold the session map's read lock
+// almost continuously, and an ingest thread waiting for the write lock for
+// every registration falls behind. With an insertion queue configured
+// (SessionConfig.insert_queue) the ingest threads only parse and check
+// registrations and queue their sessions; a dedicated applier thread takes
+// everything pending (up to max_batch registrations) and inserts it under a
+// single write lock, so a burst of registrations costs one brief lock window
+// instead of one each.
+//
+// The queue is bounded. When it is full the ingest thread blocks until the
+// applier catches up (counted as a stall), pushing back on the feed rather
+// than dropping registrations.
+//
+// Reported per period: the current and largest queue depth, the batches and
+// registrations applied, stalls, and the time from queueing to insertion.
+
+use std::mem;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::thread;
+use std::time::Instant;
+
+use metrics::{Histogram, ns_label};
+use sessions::SessionDetails;
+
+const APPLY_LATENCY_BUCKETS_NS: [u64; 5] = [
+    100*1000, 1000*1000, 10*1000*1000, 100*1000*1000, 1000*1000*1000];
+
+#[derive(Clone)]
+pub struct InsertQueueConfig
+{
+    // Registrations queued before ingest threads block.
+    pub capacity: usize,
+    // Most registrations applied under one write lock.
+    pub max_batch: usize,
+}
+
+impl Default for InsertQueueConfig
+{
+    fn default() -> InsertQueueConfig {
+        InsertQueueConfig { capacity: 4096, max_batch: 256 }
+    }
+}
+
+// Sessions of one registration, and when they were queued.
+struct Pending
+{
+    sessions: Vec<SessionDetails>,
+    queued_at: Instant,
+}
+
+struct InsertQueueStats
+{
+    depth: AtomicU64,
+    max_depth: AtomicU64,
+    batches: AtomicU64,
+    applied: AtomicU64,
+    stalls: AtomicU64,
+    dropped: AtomicU64,
+    latency: Histogram,
+}
+
+pub struct InsertQueue
+{
+    tx: SyncSender<Pending>,
+    stats: Arc<InsertQueueStats>,
+}
+
+impl InsertQueue
+{
+    /// Starts the applier thread, which passes each batch of queued
+    /// registrations (the sessions of each) to `apply`. It exits once the
+    /// queue is dropped.
+    pub fn spawn<F>(config: &InsertQueueConfig, apply: F) -> InsertQueue
+        where F: FnMut(&[Vec<SessionDetails>]) + Send + 'static
+    {
+        let (tx, rx) = sync_channel(config.capacity.max(1));
+        let stats = Arc::new(InsertQueueStats {
+            depth: AtomicU64::new(0),
+            max_depth: AtomicU64::new(0),
+            batches: AtomicU64::new(0),
+            applied: AtomicU64::new(0),
+            stalls: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            latency: Histogram::new(&APPLY_LATENCY_BUCKETS_NS),
+        });
+        let max_batch = config.max_batch.max(1);
+        let thread_stats = Arc::clone(&stats);
+        thread::spawn(move || { run_applier(rx, max_batch, &thread_stats, apply) });
+        InsertQueue { tx: tx, stats: stats }
+    }
+
+    /// Queues the sessions of one registration, blocking while the queue is
+    /// full.
+    pub fn push(&self, sessions: Vec<SessionDetails>) {
+        let pending = Pending { sessions: sessions, queued_at: Instant::now() };
+        let depth = self.stats.depth.fetch_add(1, Ordering::Relaxed) + 1;
+        self.stats.max_depth.fetch_max(depth, Ordering::Relaxed);
+        let sent = match self.tx.try_send(pending) {
+            Ok(_) => true,
+            Err(TrySendError::Full(p)) => {
+                self.stats.stalls.fetch_add(1, Ordering::Relaxed);
+                self.tx.send(p).is_ok()
+            },
+            Err(TrySendError::Disconnected(_)) => false,
+        };
+        if !sent {
+            self.stats.depth.fetch_sub(1, Ordering::Relaxed);
+            self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Registrations queued and not yet applied.
+    pub fn depth(&self) -> u64 {
+        self.stats.depth.load(Ordering::Relaxed)
+    }
+
+    /// Counters since the last call, formatted for the periodic report.
+    pub fn take_report(&self) -> String {
+        let depth = self.depth();
+        format!("insert queue depth {} max {} batches {} registrations {} stalls {} dropped {} latency {}",
+            depth,
+            self.stats.max_depth.swap(depth, Ordering::Relaxed),
+            self.stats.batches.swap(0, Ordering::Relaxed),
+            self.stats.applied.swap(0, Ordering::Relaxed),
+            self.stats.stalls.swap(0, Ordering::Relaxed),
+            self.stats.dropped.swap(0, Ordering::Relaxed),
+            self.stats.latency.take_formatted(ns_label))
+    }
+}
+
+// Runs until the InsertQueue is dropped.
+fn run_applier<F>(rx: Receiver<Pending>, max_batch: usize, stats: &InsertQueueStats, mut apply: F)
+    where F: FnMut(&[Vec<SessionDetails>])
+{
+    let mut batch = Vec::with_capacity(max_batch);
+    let mut registrations = Vec::with_capacity(max_batch);
+    while let Ok(first) = rx.recv() {
+        batch.push(first);
+        while batch.len() < max_batch {
+            match rx.try_recv() {
+                Ok(p) => batch.push(p),
+                Err(_) => break,
+            }
+        }
+
+        // kept apart, so each registration is admitted on its own
+        registrations.clear();
+        for p in batch.iter_mut() {
+            registrations.push(mem::replace(&mut p.sessions, Vec::new()));
+        }
+        apply(&registrations);
+
+        for p in batch.drain(..) {
+            let waited = p.queued_at.elapsed();
+            stats.latency.record(waited.as_secs() * 1000*1000*1000 + waited.subsec_nanos() as u64);
+            stats.applied.fetch_add(1, Ordering::Relaxed);
+            stats.depth.fetch_sub(1, Ordering::Relaxed);
+        }
+        stats.batches.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use insert_queue::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_insert_queue_batches() {
+        let (applied_tx, applied_rx) = mpsc::channel();
+        let (gate_tx, gate_rx) = mpsc::channel::<()>();
+        let config = InsertQueueConfig { capacity: 8, max_batch: 3 };
+        let queue = InsertQueue::spawn(&config, move |registrations| {
+            // hold the applier until the test lets it go
+            gate_rx.recv().unwrap();
+            let sessions: Vec<usize> = registrations.iter().map(|r| r.len()).collect();
+            applied_tx.send(sessions).unwrap();
+        });
+
+        // the applier takes the first registration on its own and is held
+        let sd = SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_nanos(1000)).unwrap();
+        queue.push(vec![sd]);
+        thread::sleep(Duration::from_millis(50));
+
+        // the rest queue up behind it and are applied together, at most
+        // max_batch at a time
+        for _ in 0..4 {
+            queue.push(vec![sd, sd]);
+        }
+        assert_eq!(queue.depth(), 5);
+        let mut batches = Vec::new();
+        for _ in 0..3 {
+            gate_tx.send(()).unwrap();
+            batches.push(applied_rx.recv_timeout(Duration::from_secs(5)).unwrap());
+        }
+        assert_eq!(batches, vec![vec![1], vec![2, 2, 2], vec![2]]);
+        thread::sleep(Duration::from_millis(50));
+
+        let report = queue.take_report();
+        assert!(report.starts_with("insert queue depth 0 max 5 batches 3 registrations 5 stalls 0 dropped 0 latency "),
+            "{}", report);
+        assert!(queue.take_report().starts_with("insert queue depth 0 max 0 batches 0 registrations 0 "));
+    }
+}
@@ -0,0 +1,157 @@
+//
+// Ingest Journal
+//
+// "The client says it registered but the session never showed up" is
+// impossible to answer from counters alone. With a journal length configured
+// (DETECTOR_INGEST_JOURNAL_LEN) the ingest threads record the last N messages
+// they received from the station, in order, each with:
+//
+//  - when it was received (our wall clock);
+//  - the client, phantom(s) and port it was for, and its registration source,
+//    as sent, even if they don't parse. The client is only kept in memory and
+//    is listed by its ClientId (see client_id.rs);
+//  - whether it was accepted (tracked or extended) or why it was rejected:
+//    unparseable, replayed, refused by the pushed configuration, invalid
+//    addresses or lifetime, or ignored while draining.
+//
+// The admin socket lists the journal, optionally only the entries for one
+// client (by address or ClientId), so an operator can tell whether a registration ever arrived and
+// what happened to it. Once the journal is full the oldest entry is dropped.
+// Configuration pushes carry no registration and are not recorded.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Mutex;
+
+use client_id;
+use registration::Registration;
+use signalling::RegistrationSource;
+use timekeeping::WallClockNs;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct JournalEntry
+{
+    pub received: WallClockNs,
+    pub client: String,
+    pub phantom: String,
+    // Empty unless the registration is dual-stack.
+    pub phantom_v6: String,
+    pub phantom_port: u32,
+    pub source: RegistrationSource,
+    // None if the registration was accepted.
+    pub rejected: Option<String>,
+}
+
+impl JournalEntry
+{
+    /// Entry for `reg`, received at `received`.
+    pub fn new(reg: &Registration, received: WallClockNs, rejected: Option<String>) -> JournalEntry {
+        JournalEntry {
+            received: received,
+            client: reg.client_ip.clone(),
+            phantom: reg.phantom_ip.clone(),
+            phantom_v6: reg.phantom_ip_v6.clone().unwrap_or_default(),
+            phantom_port: reg.phantom_port,
+            source: reg.source,
+            rejected: rejected,
+        }
+    }
+}
+
+impl fmt::Display for JournalEntry
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ms = self.received.0 / (1000*1000);
+        write!(f, "{}.{:03} {} -> {}", ms / 1000, ms % 1000, client_id::describe(&self.client), self.phantom)?;
+        if !self.phantom_v6.is_empty() {
+            write!(f, ",{}", self.phantom_v6)?;
+        }
+        write!(f, ":{} ({:?}) ", self.phantom_port, self.source)?;
+        match self.rejected {
+            Some(ref reason) => write!(f, "rejected: {}", reason),
+            None => write!(f, "accepted"),
+        }
+    }
+}
+
+pub struct IngestJournal
+{
+    capacity: usize,
+    entries: Mutex<VecDeque<JournalEntry>>,
+}
+
+impl IngestJournal
+{
+    pub fn new(capacity: usize) -> IngestJournal {
+        IngestJournal {
+            capacity: capacity,
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// Records that `reg` was accepted (`rejected` None) or why it wasn't.
+    pub fn record(&self, reg: &Registration, rejected: Option<String>) {
+        self.push(JournalEntry::new(reg, WallClockNs::now(), rejected));
+    }
+
+    pub fn push(&self, entry: JournalEntry) {
+        if self.capacity == 0 {
+            return
+        }
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// The entries for `client`, an address as sent or a ClientId (all of
+    /// them if None), oldest first.
+    pub fn entries(&self, client: Option<&str>) -> Vec<JournalEntry> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.iter()
+            .filter(|e| client.map_or(true, |c| e.client == c || client_id::describe(&e.client) == c))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use journal::*;
+
+    fn reg(client: &str) -> Registration {
+        Registration {
+            client_ip: client.to_string(),
+            phantom_ip: "10.10.0.1".to_string(),
+            phantom_port: 443,
+            source: RegistrationSource::API,
+            ..Registration::default()
+        }
+    }
+
+    #[test]
+    fn test_ingest_journal() {
+        let journal = IngestJournal::new(3);
+        journal.push(JournalEntry::new(&reg("192.168.0.1"), WallClockNs(1_500_000_000), None));
+        journal.push(JournalEntry::new(&reg("192.168.0.2"), WallClockNs(2_000_000_000), Some("draining".to_string())));
+        journal.push(JournalEntry::new(&reg("192.168.0.1"), WallClockNs(2_500_000_000), None));
+        journal.push(JournalEntry::new(&reg("192.168.0.3"), WallClockNs(3_000_000_000), None));
+
+        // the oldest entry was dropped
+        let lines: Vec<String> = journal.entries(None).iter().map(|e| e.to_string()).collect();
+        assert_eq!(lines, vec![
+            format!("2.000 {} -> 10.10.0.1:443 (API) rejected: draining", client_id::describe("192.168.0.2")),
+            format!("2.500 {} -> 10.10.0.1:443 (API) accepted", client_id::describe("192.168.0.1")),
+            format!("3.000 {} -> 10.10.0.1:443 (API) accepted", client_id::describe("192.168.0.3")),
+        ]);
+        assert!(!lines.iter().any(|l| l.contains("192.168")));
+        assert_eq!(journal.entries(Some("192.168.0.1")).len(), 1);
+        assert_eq!(journal.entries(Some(&client_id::describe("192.168.0.1"))).len(), 1);
+        assert!(journal.entries(Some("192.168.0.9")).is_empty());
+
+        let dual = Registration { phantom_ip_v6: Some("2001::1".to_string()), ..reg("192.168.0.4") };
+        assert_eq!(JournalEntry::new(&dual, WallClockNs(0), None).to_string(),
+            format!("0.000 {} -> 10.10.0.1,2001::1:443 (API) accepted", client_id::describe("192.168.0.4")));
+    }
+}
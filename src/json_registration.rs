@@ -0,0 +1,302 @@
+//
+// JSON Registrations
+//
+// Lab setups and simple integrations can register phantoms without compiling
+// the protobufs. Detectors built with the json-registrations feature also
+// take registrations as JSON objects, on a redis channel of their own
+// (DETECTOR_JSON_CHANNEL) and on a unix datagram socket (DETECTOR_JSON_SOCKET,
+// one object per datagram). Each object is converted into a Registration and
+// then handled exactly like a message from the station.
+//
+// Schema (only client_ip and phantom_ip are required to register):
+//
+//   {
+//     "command": "register",            register (default) or update
+//                                       (see StationToDetector.update_expiry)
+//     "client_ip": "192.0.2.1",
+//     "phantom_ip": "10.10.0.1",
+//     "phantom_ip_v6": "2001:db8::1",   dual-stack registrations
+//     "phantom_port": 443,              default: the configured ports
+//     "timeout_s": 120,                 lifetime from receipt
+//     "expires_at_unix_s": 1700000000,  absolute expiration
+//     "extension_s": 30,                default: the configured extension
+//     "transport": "min",               TransportType, any case
+//     "mode": "phantom",                SessionMode, any case
+//     "source": "api",                  RegistrationSource, any case
+//     "max_connections": 4,             default: no limit
+//     "client_ports": [40000, 40999],   first and last source port the
+//                                       client connects from, default: any
+//     "tenant": 0,
+//     "message_id": "0123abcd"          hex, for the replay guard
+//   }
+//
+// Unknown fields are rejected, so a misspelled field doesn't silently fall
+// back to its default. Config and phantom subnet pushes, and sequence
+// numbers, are only carried by the protobufs. Neither feed authenticates its
+// senders, so deleting a tenant's sessions (delete_tenant_sessions) is only
+// accepted from the station, on redis or the HMAC-authenticated control
+// address.
+//
+// Every core needs every registration: the redis channel reaches all of them,
+// while core N binds its own socket, the configured path followed by -N. The
+// socket is only readable and writable by the detector's user (mode 0600).
+
+use std::error::Error as StdError;
+use std::fmt::Debug;
+use std::io;
+use std::os::unix::net::UnixDatagram;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use hex;
+use protobuf::ProtobufEnum;
+use serde_derive::Deserialize;
+use serde_json;
+
+use error::{DetectorError, DetectorResult};
+use ingest::{PayloadFormat, RegistrationFeed};
+use registration::{Command, Registration};
+use tenants::TenantId;
+use timekeeping::WallClockNs;
+use util::bind_private;
+
+// Largest datagram read from the socket.
+const MAX_DATAGRAM: usize = 65536;
+
+#[derive(Clone, Default)]
+pub struct JsonConfig
+{
+    // Redis channel to subscribe to, if any.
+    pub channel: Option<String>,
+    // Unix datagram socket to bind, if any.
+    pub socket: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JsonRegistration
+{
+    pub command: Option<String>,
+    #[serde(default)]
+    pub client_ip: String,
+    #[serde(default)]
+    pub phantom_ip: String,
+    pub phantom_ip_v6: Option<String>,
+    pub phantom_port: Option<u16>,
+    pub timeout_s: Option<u64>,
+    pub expires_at_unix_s: Option<u64>,
+    pub extension_s: Option<u64>,
+    pub transport: Option<String>,
+    pub mode: Option<String>,
+    pub source: Option<String>,
+    pub max_connections: Option<u32>,
+    pub client_ports: Option<(u32, u32)>,
+    pub tenant: Option<TenantId>,
+    pub message_id: Option<String>,
+}
+
+impl JsonRegistration
+{
+    /// The Registration the object describes, or why it describes none.
+    pub fn registration(&self) -> Result<Registration, String> {
+        let command = match self.command.as_ref().map(|c| c.as_str()) {
+            None | Some("register") => Command::Register,
+            Some("update") => Command::Update,
+            Some("delete_tenant_sessions") =>
+                return Err("delete_tenant_sessions is only accepted from the station".to_string()),
+            Some(c) => return Err(format!("unknown command {}", c)),
+        };
+        if self.client_ip.is_empty() || self.phantom_ip.is_empty() {
+            return Err("client_ip and phantom_ip are required".to_string())
+        }
+        let mut reg = Registration {
+            command: command,
+            client_ip: self.client_ip.clone(),
+            phantom_ip: self.phantom_ip.clone(),
+            phantom_ip_v6: self.phantom_ip_v6.clone(),
+            phantom_port: self.phantom_port.unwrap_or(0) as u32,
+            timeout: self.timeout_s.map(Duration::from_secs),
+            expires_at: self.expires_at_unix_s.map(|s| WallClockNs(s.saturating_mul(1000*1000*1000))),
+            max_connections: self.max_connections.unwrap_or(0),
+            tenant: self.tenant.unwrap_or(0),
+            extension: self.extension_s.map(Duration::from_secs),
+            client_ports: self.client_ports,
+            ..Registration::default()
+        };
+        if let Some(ref name) = self.transport {
+            reg.transport = enum_by_name("transport", name)?;
+        }
+        if let Some(ref name) = self.mode {
+            reg.mode = enum_by_name("mode", name)?;
+        }
+        if let Some(ref name) = self.source {
+            reg.source = enum_by_name("source", name)?;
+        }
+        if let Some(ref id) = self.message_id {
+            reg.message_id = hex::decode(id).map_err(|_| format!("message_id {} is not hex", id))?;
+        }
+        Ok(reg)
+    }
+}
+
+// The value of a protobuf enum named `name`, in any case.
+fn enum_by_name<E: ProtobufEnum + Debug>(field: &str, name: &str) -> Result<E, String> {
+    E::values().iter()
+        .find(|v| format!("{:?}", v).eq_ignore_ascii_case(name))
+        .cloned()
+        .ok_or_else(|| format!("unknown {} {}", field, name))
+}
+
+/// Parses a JSON registration received from a feed.
+pub fn parse_json_registration(payload: &[u8]) -> DetectorResult<Registration> {
+    let json: JsonRegistration = serde_json::from_slice(payload)
+        .map_err(|e| json_error("parse", Box::new(e)))?;
+    json.registration().map_err(|e| json_error("check", e.into()))
+}
+
+fn json_error(context: &str, source: Box<dyn StdError + Send + Sync>) -> DetectorError {
+    DetectorError::Json { context: context.to_string(), source: source }
+}
+
+// Any feed, its payloads read as JSON registrations (e.g. a RedisFeed of
+// DETECTOR_JSON_CHANNEL).
+pub struct JsonFeed<F>
+{
+    inner: F,
+}
+
+impl<F: RegistrationFeed> JsonFeed<F>
+{
+    pub fn new(inner: F) -> JsonFeed<F> {
+        JsonFeed { inner: inner }
+    }
+}
+
+impl<F: RegistrationFeed> RegistrationFeed for JsonFeed<F>
+{
+    fn for_each_payload(&mut self, handle: &mut dyn FnMut(&[u8])) -> DetectorResult<()> {
+        self.inner.for_each_payload(handle)
+    }
+
+    fn format(&self) -> PayloadFormat {
+        PayloadFormat::Json
+    }
+}
+
+// JSON registrations received on a unix datagram socket.
+pub struct JsonSocketFeed
+{
+    socket: UnixDatagram,
+}
+
+impl JsonSocketFeed
+{
+    /// Binds `path` with mode 0600, replacing a socket left there by an
+    /// earlier run.
+    pub fn bind(path: &Path) -> DetectorResult<JsonSocketFeed> {
+        let socket = bind_private(path, |p| UnixDatagram::bind(p))
+            .map_err(|e| json_error(&format!("socket bind {}", path.display()), Box::new(e)))?;
+        Ok(JsonSocketFeed { socket: socket })
+    }
+}
+
+impl RegistrationFeed for JsonSocketFeed
+{
+    // Runs for the lifetime of the process, unless the socket has a read
+    // timeout: the feed closes the first time one expires.
+    fn for_each_payload(&mut self, handle: &mut dyn FnMut(&[u8])) -> DetectorResult<()> {
+        let mut buf = vec![0; MAX_DATAGRAM];
+        loop {
+            let n = match self.socket.recv(&mut buf) {
+                Ok(n) => n,
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock ||
+                              e.kind() == io::ErrorKind::TimedOut => return Ok(()),
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(json_error("socket receive", Box::new(e))),
+            };
+            handle(&buf[..n]);
+        }
+    }
+
+    fn format(&self) -> PayloadFormat {
+        PayloadFormat::Json
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use json_registration::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use signalling::{RegistrationSource, SessionMode, TransportType};
+
+    #[test]
+    fn test_json_registration() {
+        let json = JsonRegistration {
+            client_ip: "192.168.0.1".to_string(),
+            phantom_ip: "10.10.0.1".to_string(),
+            phantom_port: Some(8443),
+            timeout_s: Some(30),
+            extension_s: Some(90),
+            transport: Some("MIN".to_string()),
+            mode: Some("decoy".to_string()),
+            source: Some("api".to_string()),
+            tenant: Some(3),
+            message_id: Some("0a0b".to_string()),
+            ..JsonRegistration::default()
+        };
+        let reg = json.registration().unwrap();
+        assert_eq!(reg.command, Command::Register);
+        assert_eq!(reg.phantom_port, 8443);
+        assert_eq!(reg.timeout, Some(Duration::from_secs(30)));
+        assert_eq!(reg.extension, Some(Duration::from_secs(90)));
+        assert_eq!(reg.transport, TransportType::Min);
+        assert_eq!(reg.mode, SessionMode::Decoy);
+        assert_eq!(reg.source, RegistrationSource::API);
+        assert_eq!(reg.tenant, 3);
+        assert_eq!(reg.message_id, vec![0x0a, 0x0b]);
+        assert_eq!(reg.sequence, None);
+        assert_eq!(reg.sessions(WallClockNs(0)).unwrap().len(), 1);
+
+        let bad = JsonRegistration { transport: Some("carrier pigeon".to_string()), ..json };
+        assert_eq!(bad.registration().err(), Some("unknown transport carrier pigeon".to_string()));
+        let bad = JsonRegistration { message_id: Some("xyz".to_string()), ..bad };
+        assert!(bad.registration().is_err());
+        assert!(JsonRegistration::default().registration().is_err());
+
+        // destructive commands need the station's authenticated feeds
+        let delete = JsonRegistration {
+            command: Some("delete_tenant_sessions".to_string()),
+            tenant: Some(7),
+            ..JsonRegistration::default()
+        };
+        assert_eq!(delete.registration().err(),
+            Some("delete_tenant_sessions is only accepted from the station".to_string()));
+
+        match parse_json_registration(b"{\"client_ip\": ") {
+            Err(DetectorError::Json { ref context, .. }) => assert_eq!(context, "parse"),
+            r => panic!("unexpected {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_json_socket_feed() {
+        let path = ::std::env::temp_dir().join(format!("conjure-json-test-{}", ::std::process::id()));
+        let mut feed = JsonSocketFeed::bind(&path).unwrap();
+        // a stale socket is replaced
+        drop(feed);
+        feed = JsonSocketFeed::bind(&path).unwrap();
+        assert_eq!(feed.format(), PayloadFormat::Json);
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        feed.socket.set_read_timeout(Some(Duration::from_millis(200))).unwrap();
+
+        let client = UnixDatagram::unbound().unwrap();
+        client.send_to(b"{\"client_ip\": \"192.168.0.1\"}", &path).unwrap();
+        client.send_to(b"{}", &path).unwrap();
+
+        let mut seen = Vec::new();
+        feed.for_each_payload(&mut |p| seen.push(p.to_vec())).unwrap();
+        assert_eq!(seen, vec![b"{\"client_ip\": \"192.168.0.1\"}".to_vec(), b"{}".to_vec()]);
+        fs::remove_file(&path).unwrap();
+    }
+}
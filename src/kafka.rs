@@ -0,0 +1,214 @@
+//
+// Kafka Registration Feed
+//
+// Where the registration pipeline already lands in Kafka, detectors built
+// with the kafka feature can consume registrations from a topic directly
+// (DETECTOR_KAFKA_BROKERS, DETECTOR_KAFKA_TOPIC) instead of through redis.
+// Each record is one serialized StationToDetector, handled exactly like a
+// message from the redis channel.
+//
+// - Every core needs every registration, so each core consumes the whole
+//   topic in its own consumer group (the configured group id plus the core
+//   number). Offsets are committed for the group, so a restarted detector
+//   resumes where it stopped rather than replaying the topic; a new group
+//   starts from the end of the topic, as registrations older than a session
+//   timeout are of no use.
+// - An offset is only stored once its record has been handled, so a crash
+//   replays at most the records since the last commit. Replayed
+//   registrations only extend sessions that are already tracked.
+// - TLS and SASL are configured with the librdkafka settings of the same
+//   names (KafkaConfig.security), see kafka_config_from_env.
+// - Lag (records on the brokers not yet consumed) is refreshed every
+//   LAG_INTERVAL and reported per period by KafkaLag.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{BaseConsumer, Consumer};
+use rdkafka::error::KafkaError;
+use rdkafka::message::Message;
+use rdkafka::topic_partition_list::Offset;
+
+use error::{DetectorError, DetectorResult, error_chain};
+use ingest::RegistrationFeed;
+
+// Time between lag measurements, and the longest a poll blocks.
+const LAG_INTERVAL: Duration = Duration::from_secs(10);
+const POLL_TIMEOUT: Duration = Duration::from_millis(500);
+const WATERMARK_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Default)]
+pub struct KafkaConfig
+{
+    pub brokers: String,
+    pub topic: String,
+    pub group_id: String,
+    // Further librdkafka settings, e.g. security.protocol, ssl.ca.location,
+    // sasl.mechanisms, sasl.username and sasl.password.
+    pub security: Vec<(String, String)>,
+}
+
+impl KafkaConfig
+{
+    fn client_config(&self) -> ClientConfig {
+        let mut config = ClientConfig::new();
+        config.set("bootstrap.servers", self.brokers.as_str())
+            .set("group.id", self.group_id.as_str())
+            .set("enable.auto.commit", "true")
+            .set("enable.auto.offset.store", "false")
+            .set("auto.offset.reset", "latest");
+        for (key, value) in self.security.iter() {
+            config.set(key.as_str(), value.as_str());
+        }
+        config
+    }
+}
+
+fn kafka_error(context: &str, e: KafkaError) -> DetectorError {
+    DetectorError::Kafka { context: context.to_string(), source: Box::new(e) }
+}
+
+// Consumer lag, shared with the periodic report.
+pub struct KafkaLag
+{
+    // Records behind the high watermark, per partition, at the last
+    // measurement.
+    partitions: Mutex<HashMap<i32, i64>>,
+    consumed: AtomicU64,
+    errors: AtomicU64,
+}
+
+impl KafkaLag
+{
+    pub fn new() -> KafkaLag {
+        KafkaLag {
+            partitions: Mutex::new(HashMap::new()),
+            consumed: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+        }
+    }
+
+    fn set(&self, lags: HashMap<i32, i64>) {
+        *self.partitions.lock().unwrap_or_else(|e| e.into_inner()) = lags;
+    }
+
+    /// Report line: total and largest partition lag at the last measurement,
+    /// and records consumed and failed since the last report.
+    pub fn take_report(&self) -> String {
+        let partitions = self.partitions.lock().unwrap_or_else(|e| e.into_inner());
+        format!("kafka lag {} max {} partitions {} consumed {} errors {}",
+            partitions.values().sum::<i64>(),
+            partitions.values().cloned().max().unwrap_or(0),
+            partitions.len(),
+            self.consumed.swap(0, Ordering::Relaxed),
+            self.errors.swap(0, Ordering::Relaxed))
+    }
+}
+
+// Consumes the registration topic.
+pub struct KafkaFeed
+{
+    config: KafkaConfig,
+    lag: Arc<KafkaLag>,
+}
+
+impl KafkaFeed
+{
+    pub fn new(config: KafkaConfig) -> KafkaFeed {
+        KafkaFeed { config: config, lag: Arc::new(KafkaLag::new()) }
+    }
+
+    pub fn lag(&self) -> Arc<KafkaLag> {
+        Arc::clone(&self.lag)
+    }
+
+    fn measure_lag(&self, consumer: &BaseConsumer) -> DetectorResult<()> {
+        let positions = consumer.position().map_err(|e| kafka_error("position", e))?;
+        let mut lags = HashMap::new();
+        for elem in positions.elements() {
+            let (_, high) = consumer.fetch_watermarks(elem.topic(), elem.partition(), WATERMARK_TIMEOUT)
+                .map_err(|e| kafka_error("watermark fetch", e))?;
+            let lag = match elem.offset() {
+                Offset::Offset(position) => (high - position).max(0),
+                // nothing consumed yet, and we start at the end
+                _ => 0,
+            };
+            lags.insert(elem.partition(), lag);
+        }
+        self.lag.set(lags);
+        Ok(())
+    }
+}
+
+impl RegistrationFeed for KafkaFeed
+{
+    // Only returns if the consumer can't be created or subscribed, otherwise
+    // it runs for the lifetime of the process.
+    fn for_each_payload(&mut self, handle: &mut dyn FnMut(&[u8])) -> DetectorResult<()> {
+        let consumer: BaseConsumer = self.config.client_config().create()
+            .map_err(|e| kafka_error("consumer creation", e))?;
+        consumer.subscribe(&[self.config.topic.as_str()])
+            .map_err(|e| kafka_error(&format!("subscribe to {}", self.config.topic), e))?;
+
+        let mut last_lag = Instant::now();
+        loop {
+            match consumer.poll(POLL_TIMEOUT) {
+                Some(Ok(msg)) => {
+                    self.lag.consumed.fetch_add(1, Ordering::Relaxed);
+                    if let Some(payload) = msg.payload() {
+                        handle(payload);
+                    }
+                    if let Err(e) = consumer.store_offset(&msg) {
+                        debug!("can't store kafka offset: {}", e);
+                    }
+                },
+                Some(Err(e)) => {
+                    self.lag.errors.fetch_add(1, Ordering::Relaxed);
+                    debug!("Error reading message from kafka: {}", e);
+                },
+                None => {},
+            }
+            if last_lag.elapsed() >= LAG_INTERVAL {
+                last_lag = Instant::now();
+                if let Err(e) = self.measure_lag(&consumer) {
+                    debug!("{}", error_chain(&e));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kafka::*;
+
+    #[test]
+    fn test_kafka_client_config() {
+        let config = KafkaConfig {
+            brokers: "k1:9093,k2:9093".to_string(),
+            topic: "registrations".to_string(),
+            group_id: "detector-1-0".to_string(),
+            security: vec![("security.protocol".to_string(), "sasl_ssl".to_string()),
+                           ("sasl.username".to_string(), "detector".to_string())],
+        };
+        let client = config.client_config();
+        assert_eq!(client.get("bootstrap.servers"), Some("k1:9093,k2:9093"));
+        assert_eq!(client.get("group.id"), Some("detector-1-0"));
+        assert_eq!(client.get("enable.auto.offset.store"), Some("false"));
+        assert_eq!(client.get("security.protocol"), Some("sasl_ssl"));
+        assert_eq!(client.get("sasl.username"), Some("detector"));
+    }
+
+    #[test]
+    fn test_kafka_lag_report() {
+        let lag = KafkaLag::new();
+        assert_eq!(lag.take_report(), "kafka lag 0 max 0 partitions 0 consumed 0 errors 0");
+        lag.set([(0, 5), (1, 0), (2, 12)].iter().cloned().collect());
+        lag.consumed.fetch_add(3, Ordering::Relaxed);
+        assert_eq!(lag.take_report(), "kafka lag 17 max 12 partitions 3 consumed 3 errors 0");
+        assert_eq!(lag.take_report(), "kafka lag 17 max 12 partitions 3 consumed 0 errors 0");
+    }
+}
@@ -0,0 +1,301 @@
+//
+// Hot Path Latency Tracing
+//
+// Where the time goes between a frame reaching the detector and the
+// forwarding decision. Built with the latency-tracing feature, and with
+// DETECTOR_LATENCY_SAMPLE set, one in every `sample` packets is traced: each
+// stage it passes through is timed and recorded in that stage's histogram,
+// and the periodic report gives percentiles per stage. Without the feature
+// the spans compile to nothing.
+//
+// Stages:
+//  - capture: parsing the frame handed over by the capture loop down to its
+//    IP header (the capture itself happens before the detector sees it);
+//  - flow lookup: finding or starting the tracked flow of a 443 connection;
+//  - session lookup: matching the packet against the registered sessions;
+//  - forward: writing the packet to the tun interface;
+//  - total: everything rust_process_packet does with the packet.
+//
+// Histograms have log-linear buckets (HDR style, SUB_BUCKET_BITS significant
+// bits), so percentiles are within 1/2^SUB_BUCKET_BITS of the true value
+// whatever its magnitude, at a fixed cost per record.
+
+use metrics::ns_label;
+#[cfg(feature = "latency-tracing")]
+use timekeeping::{self, MonotonicNs};
+
+// Significant bits kept per value.
+const SUB_BUCKET_BITS: u32 = 4;
+const SUB_BUCKETS: u64 = 1 << SUB_BUCKET_BITS;
+// Values at or above 2^MAX_EXPONENT ns (~9 minutes) are counted as the
+// largest bucket.
+const MAX_EXPONENT: u32 = 39;
+const BUCKETS: usize = (SUB_BUCKETS as usize) * (MAX_EXPONENT - SUB_BUCKET_BITS + 2) as usize;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Stage
+{
+    Capture,
+    FlowLookup,
+    SessionLookup,
+    Forward,
+    Total,
+}
+
+const STAGES: [Stage; 5] = [Stage::Capture, Stage::FlowLookup, Stage::SessionLookup,
+                            Stage::Forward, Stage::Total];
+
+impl Stage
+{
+    fn name(&self) -> &'static str {
+        match self {
+            Stage::Capture => "capture",
+            Stage::FlowLookup => "flow_lookup",
+            Stage::SessionLookup => "session_lookup",
+            Stage::Forward => "forward",
+            Stage::Total => "total",
+        }
+    }
+}
+
+// Log-linear histogram of ns durations.
+pub struct LatencyHistogram
+{
+    counts: Vec<u64>,
+    total: u64,
+    max: u64,
+}
+
+#[cfg_attr(not(feature = "latency-tracing"), allow(dead_code))]
+impl LatencyHistogram
+{
+    pub fn new() -> LatencyHistogram {
+        LatencyHistogram { counts: vec![0; BUCKETS], total: 0, max: 0 }
+    }
+
+    fn bucket(value: u64) -> usize {
+        if value < SUB_BUCKETS {
+            return value as usize
+        }
+        let exponent = 63 - value.leading_zeros();
+        if exponent > MAX_EXPONENT {
+            return BUCKETS - 1
+        }
+        let shift = exponent - SUB_BUCKET_BITS;
+        let sub = ((value >> shift) & (SUB_BUCKETS - 1)) as usize;
+        SUB_BUCKETS as usize * (shift + 1) as usize + sub
+    }
+
+    // Largest value counted in bucket `index`.
+    fn bucket_max(index: usize) -> u64 {
+        let sub_buckets = SUB_BUCKETS as usize;
+        if index < sub_buckets {
+            return index as u64
+        }
+        let shift = (index / sub_buckets - 1) as u32;
+        let sub = (index % sub_buckets) as u64;
+        ((SUB_BUCKETS + sub + 1) << shift) - 1
+    }
+
+    pub fn record(&mut self, value: u64) {
+        self.counts[LatencyHistogram::bucket(value)] += 1;
+        self.total += 1;
+        if value > self.max {
+            self.max = value;
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.total
+    }
+
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    /// Value at or below which `percentile` percent of the recorded values
+    /// fall, to the histogram's precision. 0 if nothing was recorded.
+    pub fn percentile(&self, percentile: f64) -> u64 {
+        if self.total == 0 {
+            return 0
+        }
+        let rank = ((percentile / 100.0) * self.total as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (index, count) in self.counts.iter().enumerate() {
+            seen += *count;
+            if seen >= rank {
+                return LatencyHistogram::bucket_max(index).min(self.max)
+            }
+        }
+        self.max
+    }
+
+    pub fn reset(&mut self) {
+        for c in self.counts.iter_mut() {
+            *c = 0;
+        }
+        self.total = 0;
+        self.max = 0;
+    }
+}
+
+// Start of a timed stage, if the packet is traced.
+#[cfg(feature = "latency-tracing")]
+#[derive(Clone, Copy)]
+pub struct Span(Option<MonotonicNs>);
+
+#[cfg(not(feature = "latency-tracing"))]
+#[derive(Clone, Copy)]
+pub struct Span;
+
+#[cfg_attr(not(feature = "latency-tracing"), allow(dead_code))]
+pub struct LatencyTrace
+{
+    // Trace one in `sample` packets, 0 never.
+    sample: u32,
+    // Packets until the next traced one.
+    countdown: u32,
+    // Whether the current packet is traced.
+    tracing: bool,
+    histograms: Vec<LatencyHistogram>,
+}
+
+impl LatencyTrace
+{
+    pub fn new(sample: u32) -> LatencyTrace {
+        LatencyTrace {
+            sample: sample,
+            countdown: 0,
+            tracing: false,
+            histograms: STAGES.iter().map(|_| LatencyHistogram::new()).collect(),
+        }
+    }
+
+    /// Whether tracing was compiled in.
+    pub fn available() -> bool {
+        cfg!(feature = "latency-tracing")
+    }
+
+    /// Called as each packet arrives, decides whether it is traced.
+    #[cfg(feature = "latency-tracing")]
+    #[inline]
+    pub fn begin_packet(&mut self) {
+        self.tracing = false;
+        if self.sample == 0 {
+            return
+        }
+        if self.countdown == 0 {
+            self.countdown = self.sample;
+            self.tracing = true;
+        }
+        self.countdown -= 1;
+    }
+
+    #[cfg(not(feature = "latency-tracing"))]
+    #[inline(always)]
+    pub fn begin_packet(&mut self) {}
+
+    #[cfg(feature = "latency-tracing")]
+    #[inline]
+    pub fn start(&self) -> Span {
+        if self.tracing { Span(Some(timekeeping::now())) } else { Span(None) }
+    }
+
+    #[cfg(not(feature = "latency-tracing"))]
+    #[inline(always)]
+    pub fn start(&self) -> Span {
+        Span
+    }
+
+    /// Records the time since `span` started against `stage`.
+    #[cfg(feature = "latency-tracing")]
+    #[inline]
+    pub fn finish(&mut self, stage: Stage, span: Span) {
+        if let Span(Some(start)) = span {
+            self.histograms[stage as usize].record(timekeeping::now().saturating_since(start));
+        }
+    }
+
+    #[cfg(not(feature = "latency-tracing"))]
+    #[inline(always)]
+    pub fn finish(&mut self, _stage: Stage, _span: Span) {}
+
+    /// One line per stage traced this period, resetting the histograms.
+    pub fn take_report(&mut self) -> Vec<String> {
+        if self.sample == 0 {
+            return Vec::new()
+        }
+        let mut lines = Vec::new();
+        for (stage, h) in STAGES.iter().zip(self.histograms.iter_mut()) {
+            lines.push(format!("latency {} sampled {} p50 {} p90 {} p99 {} p999 {} max {}",
+                stage.name(), h.count(), ns_label(h.percentile(50.0)),
+                ns_label(h.percentile(90.0)), ns_label(h.percentile(99.0)),
+                ns_label(h.percentile(99.9)), ns_label(h.max())));
+            h.reset();
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use latency::*;
+
+    #[test]
+    fn test_latency_histogram() {
+        let mut h = LatencyHistogram::new();
+        assert_eq!(h.percentile(50.0), 0);
+
+        // buckets cover every value, in order, within 1/16 of it
+        let mut last = 0;
+        for v in (0..20).chain((4..MAX_EXPONENT + 1).map(|e| (1u64 << e) + 12345 % (1u64 << e))) {
+            let b = LatencyHistogram::bucket(v);
+            assert!(b >= last);
+            last = b;
+            let max = LatencyHistogram::bucket_max(b);
+            assert!(max >= v && max - v <= v / SUB_BUCKETS, "{} in bucket up to {}", v, max);
+        }
+        assert_eq!(LatencyHistogram::bucket(u64::max_value()), BUCKETS - 1);
+
+        for v in 1..1001 {
+            h.record(v * 1000);
+        }
+        assert_eq!(h.count(), 1000);
+        let p50 = h.percentile(50.0);
+        assert!(p50 >= 500*1000 && p50 < 500*1000 + 500*1000/16, "p50 {}", p50);
+        let p99 = h.percentile(99.0);
+        assert!(p99 >= 990*1000 && p99 <= 1000*1000, "p99 {}", p99);
+        assert_eq!(h.percentile(100.0), 1000*1000);
+        assert_eq!(h.max(), 1000*1000);
+        h.reset();
+        assert_eq!(h.count(), 0);
+    }
+
+    #[test]
+    fn test_latency_trace_disabled() {
+        let mut trace = LatencyTrace::new(0);
+        for _ in 0..10 {
+            trace.begin_packet();
+            let span = trace.start();
+            trace.finish(Stage::Total, span);
+        }
+        assert!(trace.take_report().is_empty());
+    }
+
+    #[cfg(feature = "latency-tracing")]
+    #[test]
+    fn test_latency_trace_sampling() {
+        let mut trace = LatencyTrace::new(4);
+        for _ in 0..10 {
+            trace.begin_packet();
+            let span = trace.start();
+            trace.finish(Stage::SessionLookup, span);
+        }
+        let report = trace.take_report();
+        assert_eq!(report.len(), STAGES.len());
+        assert!(report[0].starts_with("latency capture sampled 0 "));
+        // packets 1, 5 and 9
+        assert!(report[2].starts_with("latency session_lookup sampled 3 "));
+        assert!(trace.take_report()[2].starts_with("latency session_lookup sampled 0 "));
+    }
+}
@@ -19,9 +19,20 @@ extern crate redis;
 extern crate toml;
 extern crate serde;
 extern crate serde_derive;
+extern crate arc_swap;
+extern crate ipnetwork;
+extern crate thiserror;
+extern crate sha2;
+extern crate hmac;
+#[cfg(feature = "kafka")]
+extern crate rdkafka;
+#[cfg(feature = "ahash-hasher")]
+extern crate ahash;
+#[cfg(feature = "json-registrations")]
+extern crate serde_json;
 
-use std::mem::transmute;
-use time::precise_time_ns;
+use std::mem::{self, transmute};
+use std::cell::RefCell;
 
 use radix::PrefixTree;
 use std::io::BufReader;
@@ -29,12 +40,19 @@ use std::io::BufRead;
 use std::fs::File;
 use std::env;
 use std::fs;
+use std::cmp;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::{Duration, Instant};
 use serde_derive::Deserialize;
 
 use std::ffi::CStr;
 use std::os::raw::c_char;
 
 use tuntap::{IFF_TUN,TunTap};
+use tun_queue::{DEFAULT_QUEUE_FRAMES, TunQueue};
+use ipnetwork::IpNetwork;
+
+use error::{DetectorError, DetectorResult, error_chain};
 
 // Must go before all other modules so that the report! macro will be visible.
 #[macro_use]
@@ -47,9 +65,110 @@ pub mod process_packet;
 pub mod util;
 pub mod signalling;
 pub mod sessions;
+pub mod session_map;
+pub mod hashing;
+pub mod insert_queue;
+pub mod ingest;
+pub mod ingest_pool;
+pub mod timekeeping;
+pub mod feedback;
+pub mod metrics;
+pub mod replication;
+pub mod admin;
+pub mod phantom_subnets;
+pub mod error;
+pub mod conntrack;
+pub mod dark_scan;
+pub mod unmatched;
+pub mod fragments;
+pub mod gro;
+pub mod vlan;
+pub mod decap;
+pub mod transport_detector;
+pub mod tls_fingerprint;
+pub mod quic;
+pub mod tun_routes;
+pub mod tun_queue;
+pub mod affinity;
+pub mod fwmark;
+pub mod encap;
+pub mod latency;
+pub mod control;
+pub mod registration;
+pub mod replay;
+pub mod healthcheck;
+pub mod probes;
+pub mod supervisor;
+pub mod session_alerts;
+pub mod sequence;
+pub mod tenants;
+pub mod phantom_block;
+pub mod config_push;
+pub mod journal;
+pub mod qa_sampler;
+pub mod xdp;
+pub mod session_export;
+pub mod client_id;
+pub mod cleanup_interval;
+pub mod ingest_errors;
+pub mod stats_privacy;
+pub mod dedup;
+pub mod syn_filter;
+pub mod proxy_ring;
+pub mod negative_cache;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+#[cfg(feature = "json-registrations")]
+pub mod json_registration;
+#[cfg(feature = "simulation")]
+pub mod simulation;
+#[cfg(feature = "dpdk")]
+pub mod dpdk;
 
 
-use flow_tracker::{Flow,FlowTracker};
+use flow_tracker::{Eviction,Flow,FlowLimit,FlowTimeouts,FlowTracker};
+use session_map::SweepBudget;
+use sessions::{DecoyConfig, SessionConfig, TimeoutBounds};
+use replication::ReplicationConfig;
+use phantom_subnets::PhantomSubnets;
+use dark_scan::{DarkScan, DarkScanConfig};
+use unmatched::{Unmatched, UnmatchedAction, UnmatchedConfig};
+use tun_routes::{TunRoute, TunRouter};
+use fwmark::FwMarks;
+use probes::{Heartbeat, ProbeConfig, Watchdog};
+use session_alerts::{SessionAlertConfig, SessionAlerts};
+use fragments::{FragmentCache, FragmentCacheConfig};
+use gro::{Gro, GroConfig};
+use vlan::{Vlans, VlanConfig, MAX_TAGS};
+use decap::{Decap, DecapConfig};
+use qa_sampler::{QaSampler, QaSamplerConfig};
+use transport_detector::{PrefixDetector, TransportDetectors};
+use latency::LatencyTrace;
+use stats_privacy::{StatsPrivacy, StatsPrivacyConfig};
+use dedup::{Dedup, DedupConfig};
+use proxy_ring::{ProxyRing, ProxyRingConfig, DEFAULT_RING_BYTES, MIN_RING_BYTES};
+use control::ControlConfig;
+use replay::ReplayConfig;
+use healthcheck::HealthcheckConfig;
+use insert_queue::InsertQueueConfig;
+use ingest_pool::IngestPoolConfig;
+use cleanup_interval::AdaptiveConfig;
+use session_export::SessionExportConfig;
+use xdp::{XdpConfig, XdpFilter, XdpMode};
+#[cfg(feature = "kafka")]
+use kafka::{KafkaConfig, KafkaFeed, KafkaLag};
+#[cfg(feature = "kafka")]
+use std::sync::Arc;
+#[cfg(feature = "json-registrations")]
+use json_registration::{JsonConfig, JsonFeed, JsonSocketFeed};
+#[cfg(feature = "json-registrations")]
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::convert::TryFrom;
+use timekeeping::{Clock, MonotonicNs};
+use feedback::{EventPublisher, DETECTOR_EVENT_CHANNEL};
+use signalling::TransportType;
+use protobuf::ProtobufEnum;
 
 
 // Global program state for one instance of a TapDance station process.
@@ -65,7 +184,8 @@ pub struct PerCoreGlobal
     // Just some scratch space for mio.
     //events_buf: Events,
 
-    pub tun: TunTap,
+    // tun<core>, written non-blocking (see tun_queue.rs).
+    pub tun: TunQueue,
 
     pub stats: PerCoreStats,
 
@@ -73,18 +193,105 @@ pub struct PerCoreGlobal
     pub ip_tree:   PrefixTree,
     // ZMQ socket for sending information to the dark decoy application
     zmq_sock:      zmq::Socket,
+    // DetectorToStation events for the application (published over redis)
+    events:        EventPublisher,
 
     // Filter list of addresses to ignore traffic from. This primarily functions to prevent liveness
     // testing from other stations in a conjure cluster from clogging up the logs with connection
     // notifications. 
-    filter_list: Vec<String>,
+    filter_list: Vec<IpAddr>,
 
     // If we're reading from a GRE tap, we can provide an optional offset that we read
     // into the packet (skipping the GRE header).
     gre_offset: usize,
+
+    // Shadow (dry-run) mode: ingest registrations, match and count traffic as
+    // normal, but never write packets to the tun interface or publish
+    // registrations to the application. The decisions that would have been made
+    // are logged instead so they can be compared against a live detector.
+    shadow_mode: bool,
+
+    // Per phantom subnet counters, if enabled.
+    pub phantom_subnets: Option<PhantomSubnets>,
+    // Generation of the phantom subnets pushed by the station that the
+    // counters and the XDP filter use, 0 while they use the file's.
+    phantom_subnets_generation: u64,
+
+    // Unmatched traffic to phantom space, if enabled.
+    pub dark_scan: Option<DarkScan>,
+
+    // What unmatched connections to phantom space get, unless dropped.
+    pub unmatched: Option<Unmatched>,
+
+    // Routes matched traffic into other tun devices than tun<core>, see
+    // tun_routes.rs.
+    pub tun_routes: Option<TunRouter>,
+    // Takes the place of tun<core> once the proxy accepted it, see
+    // proxy_ring.rs.
+    pub proxy_ring: Option<ProxyRing>,
+    // Marks the forwarded traffic by the device it goes into, see fwmark.rs.
+    pub fwmarks: Option<FwMarks>,
+
+    // First fragments forwarded for matched sessions, so the rest of their
+    // packets are forwarded too.
+    pub fragments: Option<FragmentCache>,
+
+    // Accounting for and splitting of GRO superpackets.
+    pub gro: Gro,
+
+    // VLAN tag skipping, and packet counts per tag stack.
+    pub vlans: Vlans,
+
+    // Unwrapping of GRE, ERSPAN and MPLS encapsulated capture.
+    pub decap: Decap,
+
+    // First payload inspection of phantom connections, none unless enabled.
+    pub transport_detectors: TransportDetectors,
+
+    // Forward UDP to registered phantoms (DETECTOR_PHANTOM_UDP), rather than
+    // only TCP.
+    phantom_udp: bool,
+    // Fingerprint the TLS ClientHello of phantom connections.
+    tls_fingerprints: bool,
+    // Whether the first datagram of UDP phantom connections is parsed as a
+    // QUIC Initial (DETECTOR_QUIC_INITIALS).
+    quic_initials: bool,
+
+    // Quality sampling of matched sessions, if enabled.
+    pub qa: Option<QaSampler>,
+
+    // Per stage timing of sampled packets (latency-tracing feature).
+    pub latency: LatencyTrace,
+
+    // The XDP pre-filter on the tap interface, if enabled and this core
+    // attached it.
+    pub xdp: Option<XdpFilter>,
+
+    // Beaten by the capture loop for the liveness probe, if probes are
+    // enabled (see probes.rs).
+    pub heartbeat: Option<Heartbeat>,
+
+    // Alerts on the tracked session count, if enabled.
+    pub session_alerts: Option<SessionAlerts>,
+
+    // Bucketing and noise for the counts published to the station.
+    pub stats_privacy: StatsPrivacy,
+
+    // Drops frames duplicated across this core and its pair, if enabled.
+    pub dedup: Option<Dedup>,
+
+    // Lag of the Kafka registration feed, if enabled.
+    #[cfg(feature = "kafka")]
+    pub kafka_lag: Option<Arc<KafkaLag>>,
+
+    // Receive counts of the core's DPDK queue (dpdk feature).
+    #[cfg(feature = "dpdk")]
+    pub dpdk_rx: dpdk::DpdkRxStats,
 }
 
-// Tracking of some pretty straightforward quantities
+// Tracking of some pretty straightforward quantities. Only the core's packet
+// thread writes these, so they are plain integers; counters the core's other
+// threads also bump are kept in a metrics::Counters set (see metrics.rs).
 pub struct PerCoreStats
 {
     pub elligator_this_period: u64,
@@ -103,12 +310,34 @@ pub struct PerCoreStats
     tot_usr_us: i64,
     tot_sys_us: i64,
     // For computing measurement duration (because period won't be exactly 1
-    // sec). Value is nanoseconds since an unspecified epoch. (It's a time,
-    // not a duration).
-    last_measure_time: u64,
+    // sec). Value is timekeeping::now(). (It's a time, not a duration).
+    last_measure_time: MonotonicNs,
 
     pub not_in_tree_this_period: u64,
     pub in_tree_this_period: u64,
+
+    // Phantom connections not forwarded because their session was at its
+    // connection limit.
+    pub conn_limit_rejects_this_period: u64,
+
+    // Sessions whose first connection was seen (see
+    // SessionTracker::latch_match).
+    pub sessions_matched_this_period: u64,
+
+    // Actions skipped because the detector is running in shadow mode.
+    pub shadow_forwards_this_period: u64,
+    pub shadow_registrations_this_period: u64,
+
+    // v6 packets dropped because their extension header chain couldn't be
+    // walked, and non-initial v6 fragments (only forwarded with a fragment
+    // cache).
+    pub v6_unparseable_this_period: u64,
+    pub v6_later_fragments_this_period: u64,
+
+    // First datagrams of UDP phantom connections that were QUIC Initials, and
+    // that weren't.
+    pub quic_initials_this_period: u64,
+    pub quic_other_this_period: u64,
 }
 
 // Currently used to parse the Toml config. If this needs to play a larger role 
@@ -120,14 +349,19 @@ struct StationConfig {
 
 const IP_LIST_PATH: &'static str = "/var/lib/dark-decoy.prefixes";
 const STATION_CONF_PATH: &'static str = "CJ_STATION_CONFIG";
+const PHANTOM_SUBNET_PATH: &'static str = "PHANTOM_SUBNET_LOCATION";
 
 impl PerCoreGlobal
 {
     fn new(priv_key: [u8; 32], the_lcore: i32, workers_socket_addr: &str) -> PerCoreGlobal
     {
 
-        let tun = TunTap::new(IFF_TUN, &format!("tun{}", the_lcore)).unwrap();
-        tun.set_up().unwrap();
+        // DETECTOR_TUN_QUEUE_FRAMES set in conjure.conf (default 1024)
+        let tun_queue_frames = tun_queue_frames_from_env();
+        let tun_name = format!("tun{}", the_lcore);
+        let tun = open_tun(&tun_name)
+            .and_then(|tun| TunQueue::open(tun, &tun_name, tun_queue_frames))
+            .unwrap_or_else(|e| panic!("{}", error_chain(&e)));
 
         // Setup ZMQ
         let zmq_ctx = zmq::Context::new();
@@ -140,6 +374,7 @@ impl PerCoreGlobal
             .expect("Something went wrong reading the station config file");
         let value: StationConfig = toml::from_str(&contents)
             .expect("Failed to parse toml station config");
+        let filter_list = station_filter_list(&value.detector_filter_list);
 
         // Also all threads read the same environment variable so they will all
         // set it the same, race condition for setting client ip logging doesn't
@@ -150,6 +385,12 @@ impl PerCoreGlobal
             "false" => Flow::set_log_client(false),
             &_ => Flow::set_log_client(false), // default disable 
         };
+        // Clients are logged and exported by ClientId, a keyed hash of their
+        // address. DETECTOR_CLIENT_ID_KEY_FILE set in conjure.conf (default a
+        // random key per core)
+        if let Some(key) = client_id_key_from_env() {
+            client_id::set_key(&key);
+        }
 
         let gre_offset = match env::var("PARSE_GRE_OFFSET") {
             Ok(val) => val.parse::<usize>().unwrap(),
@@ -159,17 +400,343 @@ impl PerCoreGlobal
 
         debug!("gre_offset: {}", gre_offset);
 
+        // DETECTOR_SHADOW_MODE set in conjure.conf (default disabled)
+        let shadow_mode = match env::var("DETECTOR_SHADOW_MODE") {
+            Ok(val) => val == "true",
+            Err(_) => false,
+        };
+        if shadow_mode {
+            info!("detector running in shadow mode, packets will not be forwarded");
+        }
+
+        // DETECTOR_PHANTOM_UDP set in conjure.conf (default disabled)
+        let phantom_udp = match env::var("DETECTOR_PHANTOM_UDP") {
+            Ok(val) => val == "true",
+            Err(_) => false,
+        };
+
+        // DETECTOR_TLS_FINGERPRINTS set in conjure.conf (default disabled)
+        let tls_fingerprints = match env::var("DETECTOR_TLS_FINGERPRINTS") {
+            Ok(val) => val == "true",
+            Err(_) => false,
+        };
+
+        // DETECTOR_QUIC_INITIALS set in conjure.conf (default disabled)
+        let quic_initials = match env::var("DETECTOR_QUIC_INITIALS") {
+            Ok(val) => val == "true",
+            Err(_) => false,
+        };
+
+        let mut flow_tracker = FlowTracker::with_config(session_config_from_env(the_lcore), Clock::Monotonic);
+        // DETECTOR_FLOW_ASYMMETRIC set in conjure.conf (default disabled)
+        let asymmetric = flow_asymmetric_from_env();
+        if asymmetric {
+            info!("only client to phantom packets captured, sequence checks and RST teardown disabled");
+        }
+        flow_tracker.set_asymmetric(asymmetric);
+        flow_tracker.set_timeouts(flow_timeouts_from_env(asymmetric));
+        flow_tracker.set_limit(flow_limit_from_env());
+        // DETECTOR_SEQ_WINDOW set in conjure.conf (default disabled)
+        flow_tracker.set_seq_window(seq_window_from_env());
+        // DETECTOR_SYN_FILTER_BITS set in conjure.conf (default disabled)
+        if let Some(bits) = syn_filter_bits_from_env() {
+            flow_tracker.enable_syn_filter(bits);
+        }
+        // DETECTOR_NEGATIVE_CACHE_SLOTS set in conjure.conf (default disabled)
+        if let Some(slots) = negative_cache_slots_from_env() {
+            flow_tracker.enable_negative_cache(slots);
+        }
+
+        // DETECTOR_SNAPSHOT_INTERVAL_MS set in conjure.conf (default disabled)
+        if let Some(ms) = env_number("DETECTOR_SNAPSHOT_INTERVAL_MS") {
+            debug!("session snapshot lookups enabled, interval {}ms", ms);
+            flow_tracker.phantom_flows.enable_snapshot(Duration::from_millis(ms));
+        }
+
+        // DETECTOR_PHANTOM_SUBNET_STATS set in conjure.conf (default disabled)
+        let phantom_subnets = match env::var("DETECTOR_PHANTOM_SUBNET_STATS") {
+            Ok(ref val) if val == "true" => {
+                match PhantomSubnets::load(&env::var(PHANTOM_SUBNET_PATH).unwrap_or_default()) {
+                    Ok(s) => Some(s),
+                    Err(e) => {
+                        error!("phantom subnet stats disabled: {}", e);
+                        None
+                    },
+                }
+            },
+            _ => None,
+        };
+
+        // DETECTOR_UNMATCHED_ACTION set in conjure.conf (default drop)
+        let unmatched = match unmatched_config_from_env() {
+            Some(config) => match Unmatched::open(config, the_lcore) {
+                Ok(u) => Some(u),
+                Err(e) => {
+                    error!("unmatched action disabled: {}", error_chain(&e));
+                    None
+                },
+            },
+            None => None,
+        };
+
+        // DETECTOR_TUN_ROUTES set in conjure.conf (default everything into
+        // tun<core>)
+        let tun_routes = match tun_routes_from_env() {
+            Some(routes) => match TunRouter::open(routes, the_lcore, tun_queue_frames) {
+                Ok(r) => Some(r),
+                Err(e) => {
+                    error!("tun routes disabled: {}", error_chain(&e));
+                    None
+                },
+            },
+            None => None,
+        };
+
+        // DETECTOR_PROXY_RING_SOCKET set in conjure.conf (default everything
+        // into tun<core>)
+        let proxy_ring = match proxy_ring_config_from_env() {
+            Some(_) if shadow_mode => None,
+            Some(config) => match ProxyRing::negotiate(&config, the_lcore) {
+                Ok(ring) => {
+                    info!("forwarding to the proxy over a shared memory ring instead of {}", tun_name);
+                    Some(ring)
+                },
+                Err(e) => {
+                    error!("proxy ring disabled, forwarding into {}: {}", tun_name, error_chain(&e));
+                    None
+                },
+            },
+            None => None,
+        };
+
+        // DETECTOR_FWMARK set in conjure.conf, and the marks of the tun routes
+        // (default disabled)
+        let routed = match tun_routes {
+            Some(ref router) => router.device_marks(),
+            None => Vec::new(),
+        };
+        let fwmarks = match FwMarks::new(the_lcore, fwmark_from_env(), routed) {
+            Some(marks) => match marks.install() {
+                Ok(()) => Some(marks),
+                Err(e) => {
+                    error!("fwmarks disabled: {}", error_chain(&e));
+                    None
+                },
+            },
+            None => None,
+        };
+
+        // DETECTOR_LATENCY_SAMPLE set in conjure.conf (default disabled)
+        let latency_sample = match env_number::<u32>("DETECTOR_LATENCY_SAMPLE") {
+            Some(_) if !LatencyTrace::available() => {
+                error!("DETECTOR_LATENCY_SAMPLE set but latency tracing wasn't built in (latency-tracing feature)");
+                0
+            },
+            Some(n) => n,
+            None => 0,
+        };
+
+        // DETECTOR_KAFKA_BROKERS set in conjure.conf (default disabled)
+        #[cfg(feature = "kafka")]
+        let kafka_lag = kafka_config_from_env(the_lcore).map(|config| {
+            let feed = KafkaFeed::new(config);
+            let lag = feed.lag();
+            flow_tracker.phantom_flows.spawn_ingest_thread(feed);
+            lag
+        });
+
+        // DETECTOR_JSON_CHANNEL, DETECTOR_JSON_SOCKET set in conjure.conf
+        // (default disabled)
+        #[cfg(feature = "json-registrations")]
+        {
+            let json = json_config_from_env(the_lcore);
+            if let Some(channel) = json.channel {
+                let feed = JsonFeed::new(ingest::RedisFeed::new(ingest::REDIS_URL, &channel));
+                flow_tracker.phantom_flows.spawn_ingest_thread(feed);
+            }
+            if let Some(path) = json.socket {
+                match JsonSocketFeed::bind(&path) {
+                    Ok(feed) => { flow_tracker.phantom_flows.spawn_ingest_thread(feed); },
+                    Err(e) => error!("JSON registration socket disabled: {}", error_chain(&e)),
+                }
+            }
+        }
+
+        let dark_scan = dark_scan_config_from_env().map(|c| DarkScan::new(c, timekeeping::now()));
+        let fragments = fragment_cache_config_from_env().map(FragmentCache::new);
+        let gro = Gro::new(gro_config_from_env());
+        let vlans = Vlans::new(vlan_config_from_env());
+        let decap = Decap::new(decap_config_from_env());
+        let transport_detectors = transport_detectors_from_env();
+        let qa = qa_sampler_config_from_env().map(QaSampler::spawn);
+        let xdp = match xdp_config_from_env() {
+            Some(config) => match XdpFilter::attach(config) {
+                Ok(filter) => {
+                    info!("xdp filter attached to {}", filter.config().interface);
+                    Some(filter)
+                },
+                Err(ref e) if xdp::already_attached(e) => {
+                    debug!("xdp filter attached by another core");
+                    None
+                },
+                Err(e) => {
+                    error!("xdp filter disabled: {}", error_chain(&e));
+                    None
+                },
+            },
+            None => None,
+        };
+
+        // DETECTOR_ADMIN_SOCKET set in conjure.conf (default disabled)
+        if admin_socket_from_env() {
+            // the admin healthcheck reports the configuration problems found so far
+            let health = HealthcheckConfig {
+                redis_url: ingest::REDIS_URL.to_string(),
+                interface: None,
+                tun: format!("tun{}", the_lcore),
+                config_errors: take_config_errors(),
+                ..HealthcheckConfig::default()
+            };
+            match admin::bind(&admin::admin_socket_path(the_lcore)) {
+                Ok(listener) => { flow_tracker.phantom_flows.spawn_admin_thread(listener, Some(health)); },
+                Err(e) => error!("admin socket disabled: {}", e),
+            }
+        }
+
+        // DETECTOR_PROBE_ADDR set in conjure.conf (default disabled)
+        let heartbeat = match probe_config_from_env(the_lcore) {
+            Some(config) => match probes::bind(&config.addr) {
+                Ok(listener) => {
+                    let heartbeat = Heartbeat::new();
+                    let mut watchdog = Watchdog::new(config.stall_ns, timekeeping::now());
+                    watchdog.watch("capture", heartbeat.clone());
+                    flow_tracker.phantom_flows.spawn_probe_thread(listener, watchdog);
+                    Some(heartbeat)
+                },
+                Err(e) => {
+                    error!("probes disabled: {}", error_chain(&e));
+                    None
+                },
+            },
+            None => None,
+        };
+
         PerCoreGlobal {
             priv_key: priv_key,
             lcore: the_lcore,
             // sessions: HashMap::new(),
-            flow_tracker: FlowTracker::new(),
+            flow_tracker: flow_tracker,
             tun: tun,
             stats: PerCoreStats::new(),
             ip_tree: PrefixTree::new(),
             zmq_sock: zmq_sock,
-            filter_list: value.detector_filter_list,
+            events: EventPublisher::spawn(ingest::REDIS_URL, DETECTOR_EVENT_CHANNEL),
+            filter_list: filter_list,
             gre_offset: gre_offset,
+            shadow_mode: shadow_mode,
+            phantom_subnets: phantom_subnets,
+            phantom_subnets_generation: 0,
+            dark_scan: dark_scan,
+            unmatched: unmatched,
+            tun_routes: tun_routes,
+            proxy_ring: proxy_ring,
+            fwmarks: fwmarks,
+            fragments: fragments,
+            gro: gro,
+            vlans: vlans,
+            decap: decap,
+            transport_detectors: transport_detectors,
+            phantom_udp: phantom_udp,
+            tls_fingerprints: tls_fingerprints,
+            quic_initials: quic_initials,
+            qa: qa,
+            latency: LatencyTrace::new(latency_sample),
+            xdp: xdp,
+            heartbeat: heartbeat,
+            session_alerts: SessionAlerts::new(session_alert_config_from_env(), the_lcore as u32),
+            stats_privacy: StatsPrivacy::new(stats_privacy_config_from_env()),
+            dedup: dedup_config_from_env().and_then(|c| Dedup::for_core(&c, the_lcore)),
+            #[cfg(feature = "kafka")]
+            kafka_lag: kafka_lag,
+            #[cfg(feature = "dpdk")]
+            dpdk_rx: dpdk::DpdkRxStats::default(),
+        }
+    }
+
+    // A core forwarding into tun<the_lcore> with the given session settings,
+    // and every other setting at its default. Nothing is read from the
+    // environment or the station config, there are no ZMQ workers or admin
+    // socket, and events are discarded. For the end-to-end tests in
+    // tests/netns.rs.
+    #[cfg(feature = "netns-tests")]
+    pub fn for_netns_test(the_lcore: i32, session_config: SessionConfig) -> DetectorResult<PerCoreGlobal>
+    {
+        let tun_name = format!("tun{}", the_lcore);
+        let tun = TunQueue::open(open_tun(&tun_name)?, &tun_name, DEFAULT_QUEUE_FRAMES)?;
+        let zmq_ctx = zmq::Context::new();
+        let zmq_sock = zmq_ctx.socket(zmq::PUB).unwrap();
+        let (events_tx, _) = std::sync::mpsc::sync_channel(1);
+
+        Ok(PerCoreGlobal {
+            priv_key: [0; 32],
+            lcore: the_lcore,
+            flow_tracker: FlowTracker::with_config(session_config, Clock::Monotonic),
+            tun: tun,
+            stats: PerCoreStats::new(),
+            ip_tree: PrefixTree::new(),
+            zmq_sock: zmq_sock,
+            events: EventPublisher::from_sender(events_tx),
+            filter_list: Vec::new(),
+            gre_offset: 0,
+            shadow_mode: false,
+            phantom_subnets: None,
+            phantom_subnets_generation: 0,
+            dark_scan: None,
+            unmatched: None,
+            tun_routes: None,
+            proxy_ring: None,
+            fwmarks: None,
+            fragments: None,
+            gro: Gro::new(GroConfig::default()),
+            vlans: Vlans::new(VlanConfig::default()),
+            decap: Decap::new(DecapConfig::default()),
+            transport_detectors: TransportDetectors::new(),
+            phantom_udp: false,
+            tls_fingerprints: false,
+            quic_initials: false,
+            qa: None,
+            latency: LatencyTrace::new(0),
+            xdp: None,
+            heartbeat: None,
+            session_alerts: None,
+            stats_privacy: StatsPrivacy::new(StatsPrivacyConfig::default()),
+            dedup: None,
+            #[cfg(feature = "kafka")]
+            kafka_lag: None,
+            #[cfg(feature = "dpdk")]
+            dpdk_rx: dpdk::DpdkRxStats::default(),
+        })
+    }
+
+    // Switches the subnet counters and the XDP filter to the phantom subnets
+    // the station pushed, if there is a newer set.
+    fn follow_phantom_subnets(&mut self)
+    {
+        let pushed = match self.flow_tracker.phantom_flows.station_config() {
+            Some(sc) if sc.generation() != self.phantom_subnets_generation => sc.phantom_subnets(),
+            _ => return,
+        };
+        self.phantom_subnets_generation = pushed.generation;
+        if let Some(ref mut subnets) = self.phantom_subnets {
+            subnets.replace(&pushed.subnets);
+        }
+        if let Some(ref mut xdp) = self.xdp {
+            match xdp.set_pushed_subnets(&pushed.subnets) {
+                Ok((added, removed)) => info!("xdp filter phantom subnets generation {}, {} added {} removed",
+                    pushed.generation, added, removed),
+                Err(e) => error!("xdp filter phantom subnets generation {} not applied: {}",
+                    pushed.generation, error_chain(&e)),
+            }
         }
     }
 
@@ -196,6 +763,1002 @@ impl PerCoreGlobal
 
 }
 
+// The tun interface `device` (phantom traffic goes into tun<core>), brought up.
+fn open_tun(device: &str) -> DetectorResult<TunTap> {
+    let capture = |e: String| DetectorError::Capture { device: device.to_string(), reason: e };
+    let tun = TunTap::new(IFF_TUN, device).map_err(|e| capture(e.to_string()))?;
+    tun.set_up().map_err(|e| capture(e.to_string()))?;
+    Ok(tun)
+}
+
+thread_local! {
+    // Configuration problems logged by config_error! on this thread.
+    static CONFIG_ERRORS: RefCell<Vec<String>> = RefCell::new(Vec::new());
+}
+
+// Logs a configuration problem and keeps it for the health check.
+macro_rules! config_error {
+    ($($arg:tt)*) => {{
+        let msg = format!($($arg)*);
+        error!("Error, {}", msg);
+        CONFIG_ERRORS.with(|e| e.borrow_mut().push(msg));
+    }}
+}
+
+// Configuration problems logged on this thread since the last call.
+fn take_config_errors() -> Vec<String> {
+    CONFIG_ERRORS.with(|e| mem::replace(&mut *e.borrow_mut(), Vec::new()))
+}
+
+// Problems with the configuration of core `lcore`, found by reading the
+// environment and the station config again.
+fn config_errors(lcore: i32) -> Vec<String> {
+    take_config_errors();
+    session_config_from_env(lcore);
+    dark_scan_config_from_env();
+    fragment_cache_config_from_env();
+    gro_config_from_env();
+    vlan_config_from_env();
+    decap_config_from_env();
+    flow_timeouts_from_env(flow_asymmetric_from_env());
+    flow_limit_from_env();
+    syn_filter_bits_from_env();
+    negative_cache_slots_from_env();
+    seq_window_from_env();
+    unmatched_config_from_env();
+    tun_routes_from_env();
+    proxy_ring_config_from_env();
+    tun_queue_frames_from_env();
+    fwmark_from_env();
+    probe_config_from_env(lcore);
+    session_alert_config_from_env();
+    stats_privacy_config_from_env();
+    dedup_config_from_env();
+    transport_detectors_from_env();
+    qa_sampler_config_from_env();
+    xdp_config_from_env();
+    client_id_key_from_env();
+    #[cfg(feature = "kafka")]
+    kafka_config_from_env(lcore);
+    #[cfg(feature = "json-registrations")]
+    json_config_from_env(lcore);
+    let mut errors = take_config_errors();
+
+    match env::var(STATION_CONF_PATH) {
+        Ok(path) => match fs::read_to_string(&path) {
+            Ok(contents) => match toml::from_str::<StationConfig>(&contents) {
+                Ok(value) => {
+                    station_filter_list(&value.detector_filter_list);
+                    errors.extend(take_config_errors());
+                },
+                Err(e) => errors.push(format!("can't parse station config {}: {}", path, e)),
+            },
+            Err(e) => errors.push(format!("can't read station config {}: {}", path, e)),
+        },
+        Err(_) => errors.push(format!("{} not set", STATION_CONF_PATH)),
+    }
+    errors
+}
+
+// The addresses in the station config's detector_filter_list, logging the ones
+// that can't be parsed.
+fn station_filter_list(list: &[String]) -> Vec<IpAddr> {
+    let mut addrs = Vec::new();
+    for addr in list.iter() {
+        match addr.trim().parse() {
+            Ok(ip) => addrs.push(ip),
+            Err(_) => config_error!("can't parse detector_filter_list entry {}", addr),
+        }
+    }
+    addrs
+}
+
+// Numeric setting from the environment. None if unset or 0 (disabled), logs
+// an error and returns None if it can't be parsed.
+fn env_number<T: FromStr + PartialEq + Default>(name: &str) -> Option<T> {
+    match env::var(name) {
+        Ok(val) => match val.parse::<T>() {
+            Ok(ref n) if *n == T::default() => None,
+            Ok(n) => Some(n),
+            Err(_) => {
+                config_error!("can't parse {}", name);
+                None
+            },
+        },
+        Err(_) => None,
+    }
+}
+
+// Comma separated subnets from the environment, logging the ones that can't be
+// parsed.
+fn env_subnets(name: &str) -> Vec<IpNetwork> {
+    let mut subnets = Vec::new();
+    for s in env::var(name).unwrap_or_default().split(',')
+        .map(|s| s.trim()).filter(|s| !s.is_empty())
+    {
+        match s.parse() {
+            Ok(net) => subnets.push(net),
+            Err(_) => config_error!("can't parse {} subnet {}", name, s),
+        }
+    }
+    subnets
+}
+
+// Dark scan telemetry options, None (disabled) unless
+// DETECTOR_DARK_SCAN_SUBNETS lists at least one subnet.
+fn dark_scan_config_from_env() -> Option<DarkScanConfig> {
+    let mut config = DarkScanConfig::default();
+    config.subnets = env_subnets("DETECTOR_DARK_SCAN_SUBNETS");
+    if config.subnets.is_empty() {
+        return None
+    }
+    if let Some(n) = env_number("DETECTOR_DARK_SCAN_SAMPLE") {
+        config.sample = n;
+    }
+    if let Some(len) = env_number::<u8>("DETECTOR_DARK_SCAN_V4_PREFIX") {
+        config.v4_prefix = cmp::min(len, 32);
+    }
+    if let Some(len) = env_number::<u8>("DETECTOR_DARK_SCAN_V6_PREFIX") {
+        config.v6_prefix = cmp::min(len, 128);
+    }
+    if let Some(n) = env_number("DETECTOR_DARK_SCAN_MIN_SOURCES") {
+        config.min_sources = n;
+    }
+    Some(config)
+}
+
+// Fragment forwarding, None (later fragments dropped) unless
+// DETECTOR_FRAGMENT_CACHE is set.
+fn fragment_cache_config_from_env() -> Option<FragmentCacheConfig> {
+    let mut config = FragmentCacheConfig::default();
+    config.capacity = env_number("DETECTOR_FRAGMENT_CACHE")?;
+    if let Some(ms) = env_number::<u64>("DETECTOR_FRAGMENT_TIMEOUT_MS") {
+        config.timeout_ns = ms * 1000*1000;
+    }
+    Some(config)
+}
+
+// Idle flow timeouts, the defaults (for asymmetric mode if `asymmetric`) for
+// any not set.
+fn flow_timeouts_from_env(asymmetric: bool) -> FlowTimeouts {
+    let timeout = |var: &str, default_ns: u64| match env_number::<u64>(var) {
+        Some(0) => {
+            config_error!("{} must be at least 1", var);
+            default_ns
+        },
+        Some(s) => s.saturating_mul(1000*1000*1000),
+        None => default_ns,
+    };
+    let defaults = if asymmetric { FlowTimeouts::asymmetric() } else { FlowTimeouts::default() };
+    FlowTimeouts {
+        syn_ns: timeout("DETECTOR_FLOW_SYN_TIMEOUT_S", defaults.syn_ns),
+        established_ns: timeout("DETECTOR_FLOW_ESTABLISHED_TIMEOUT_S", defaults.established_ns),
+        fin_ns: timeout("DETECTOR_FLOW_FIN_TIMEOUT_S", defaults.fin_ns),
+        udp_ns: timeout("DETECTOR_FLOW_UDP_TIMEOUT_S", defaults.udp_ns),
+    }
+}
+
+// Whether only client to phantom packets are captured, see
+// FlowTracker::set_asymmetric.
+fn flow_asymmetric_from_env() -> bool {
+    match env::var("DETECTOR_FLOW_ASYMMETRIC") {
+        Ok(val) => val == "true",
+        Err(_) => false,
+    }
+}
+
+// Bound on the tracked flows, None (unbounded) unless DETECTOR_MAX_FLOWS is
+// set. Candidates are evicted oldest first unless DETECTOR_FLOW_EVICTION is
+// random.
+fn flow_limit_from_env() -> Option<FlowLimit> {
+    let max_flows = env_number::<usize>("DETECTOR_MAX_FLOWS")?;
+    let eviction = match env::var("DETECTOR_FLOW_EVICTION").unwrap_or_default().as_ref() {
+        "" | "fifo" => Eviction::Fifo,
+        "random" => Eviction::Random,
+        other => {
+            config_error!("can't parse DETECTOR_FLOW_EVICTION {}", other);
+            Eviction::Fifo
+        },
+    };
+    Some(FlowLimit { max_flows: max_flows, eviction: eviction })
+}
+
+// Bits per generation of the SYN filter, None (candidates tracked from their
+// SYN) unless DETECTOR_SYN_FILTER_BITS is set.
+fn syn_filter_bits_from_env() -> Option<usize> {
+    env_number("DETECTOR_SYN_FILTER_BITS")
+}
+
+// Flows each core remembers finding in no session, None (every packet looked
+// up) unless DETECTOR_NEGATIVE_CACHE_SLOTS is set.
+fn negative_cache_slots_from_env() -> Option<usize> {
+    env_number("DETECTOR_NEGATIVE_CACHE_SLOTS")
+}
+
+// Bytes either side of a phantom connection's window the client's sequence
+// numbers may fall, None (not checked) unless DETECTOR_SEQ_WINDOW is set.
+fn seq_window_from_env() -> Option<u32> {
+    let window = env_number::<u32>("DETECTOR_SEQ_WINDOW")?;
+    if window > i32::MAX as u32 {
+        config_error!("DETECTOR_SEQ_WINDOW must be at most {}", i32::MAX);
+        return None;
+    }
+    Some(window)
+}
+
+// VLAN tags expected on every packet, any number up to MAX_TAGS unless
+// DETECTOR_VLAN_TAGS is set.
+fn vlan_config_from_env() -> VlanConfig {
+    let mut config = VlanConfig::default();
+    match env::var("DETECTOR_VLAN_TAGS") {
+        Ok(ref val) if !val.is_empty() && val != "any" => match val.parse::<usize>() {
+            Ok(n) if n <= MAX_TAGS => config.expected_tags = Some(n),
+            _ => config_error!("DETECTOR_VLAN_TAGS must be any or at most {}", MAX_TAGS),
+        },
+        _ => {},
+    }
+    config
+}
+
+// Encapsulation layers unwrapped before IP parsing, none unless DETECTOR_DECAP
+// lists them.
+fn decap_config_from_env() -> DecapConfig {
+    match env::var("DETECTOR_DECAP") {
+        Ok(ref val) => match val.parse::<DecapConfig>() {
+            Ok(config) => config,
+            Err(e) => {
+                config_error!("DETECTOR_DECAP: {}", e);
+                DecapConfig::default()
+            },
+        },
+        Err(_) => DecapConfig::default(),
+    }
+}
+
+// GRO superpacket handling, splitting to a 1500 byte tun MTU unless
+// DETECTOR_TUN_MTU is set.
+fn gro_config_from_env() -> GroConfig {
+    let mut config = GroConfig::default();
+    if let Some(mtu) = env_number("DETECTOR_TUN_MTU") {
+        config.mtu = mtu;
+    }
+    config
+}
+
+// Unmatched connection handling, None (drop, as without it) unless
+// DETECTOR_UNMATCHED_ACTION is other than drop and DETECTOR_UNMATCHED_SUBNETS
+// lists at least one subnet, or DETECTOR_UNMATCHED_RULES gives a subnet an
+// action other than drop.
+fn unmatched_config_from_env() -> Option<UnmatchedConfig> {
+    let mut config = UnmatchedConfig::default();
+    match env::var("DETECTOR_UNMATCHED_ACTION") {
+        Ok(ref val) if !val.is_empty() => match val.parse() {
+            Ok(action) => config.action = action,
+            Err(e) => {
+                config_error!("DETECTOR_UNMATCHED_ACTION: {}", e);
+                return None
+            },
+        },
+        _ => {},
+    }
+    if config.action != UnmatchedAction::Drop {
+        config.subnets = env_subnets("DETECTOR_UNMATCHED_SUBNETS");
+        if config.subnets.is_empty() {
+            config_error!("DETECTOR_UNMATCHED_ACTION needs DETECTOR_UNMATCHED_SUBNETS");
+            return None
+        }
+    }
+    for rule in env::var("DETECTOR_UNMATCHED_RULES").unwrap_or_default().split(',')
+        .map(|r| r.trim()).filter(|r| !r.is_empty())
+    {
+        let mut parts = rule.splitn(2, '=');
+        let net = parts.next().unwrap_or_default().trim().parse();
+        let action = parts.next().map(|a| a.trim().parse::<UnmatchedAction>());
+        match (net, action) {
+            (Ok(net), Some(Ok(action))) => config.rules.push((net, action)),
+            _ => config_error!("can't parse DETECTOR_UNMATCHED_RULES entry {}", rule),
+        }
+    }
+    if config.all_rules().iter().all(|r| r.1 == UnmatchedAction::Drop) {
+        return None
+    }
+    if let Some(ms) = env_number::<u64>("DETECTOR_UNMATCHED_RST_DELAY_MS") {
+        config.reset_delay_ns = ms.saturating_mul(1000*1000);
+    }
+    if let Some(n) = env_number("DETECTOR_UNMATCHED_MAX_DELAYED") {
+        config.max_delayed = n;
+    }
+    if let Ok(prefix) = env::var("DETECTOR_UNMATCHED_TUN") {
+        if !prefix.is_empty() {
+            config.tun_prefix = prefix;
+        }
+    }
+    Some(config)
+}
+
+// Shared memory ring to the proxy listening at DETECTOR_PROXY_RING_SOCKET,
+// of DETECTOR_PROXY_RING_BYTES, None (forwarding into tun) unless the socket
+// is set.
+fn proxy_ring_config_from_env() -> Option<ProxyRingConfig> {
+    let socket = match env::var("DETECTOR_PROXY_RING_SOCKET") {
+        Ok(ref path) if !path.is_empty() => path.clone(),
+        _ => return None,
+    };
+    let bytes = match env_number::<usize>("DETECTOR_PROXY_RING_BYTES") {
+        Some(n) if n < MIN_RING_BYTES => {
+            config_error!("DETECTOR_PROXY_RING_BYTES must be at least {}", MIN_RING_BYTES);
+            DEFAULT_RING_BYTES
+        },
+        Some(n) => n,
+        None => DEFAULT_RING_BYTES,
+    };
+    Some(ProxyRingConfig { socket: socket, bytes: bytes })
+}
+
+// Frames each tun device queues while it won't take them,
+// DETECTOR_TUN_QUEUE_FRAMES or the default.
+fn tun_queue_frames_from_env() -> usize {
+    env_number("DETECTOR_TUN_QUEUE_FRAMES").unwrap_or(DEFAULT_QUEUE_FRAMES)
+}
+
+// Whether each core serves admin commands on its socket (see admin.rs), false
+// unless DETECTOR_ADMIN_SOCKET is set.
+fn admin_socket_from_env() -> bool {
+    match env::var("DETECTOR_ADMIN_SOCKET") {
+        Ok(val) => val == "true",
+        Err(_) => false,
+    }
+}
+
+// Probes of core `lcore`, None (disabled) unless DETECTOR_PROBE_ADDR is set.
+// Each core listens on the port plus its number.
+fn probe_config_from_env(lcore: i32) -> Option<ProbeConfig> {
+    let val = env::var("DETECTOR_PROBE_ADDR").unwrap_or_default();
+    if val.is_empty() {
+        return None
+    }
+    let mut addr: SocketAddr = match val.parse() {
+        Ok(a) => a,
+        Err(_) => {
+            config_error!("can't parse DETECTOR_PROBE_ADDR {}", val);
+            return None
+        },
+    };
+    let port = match u16::try_from(lcore).ok().and_then(|l| addr.port().checked_add(l)) {
+        Some(p) => p,
+        None => {
+            config_error!("DETECTOR_PROBE_ADDR port {} plus core {} is out of range", addr.port(), lcore);
+            return None
+        },
+    };
+    addr.set_port(port);
+    let stall_ms = env_number::<u64>("DETECTOR_PROBE_STALL_MS").unwrap_or(probes::DEFAULT_STALL_MS);
+    Some(ProbeConfig { addr: addr, stall_ns: stall_ms.saturating_mul(1000*1000) })
+}
+
+// Session count alerts, each disabled (0) unless set.
+fn session_alert_config_from_env() -> SessionAlertConfig {
+    let config = SessionAlertConfig {
+        high: env_number("DETECTOR_SESSION_ALERT_HIGH"),
+        low: env_number("DETECTOR_SESSION_ALERT_LOW"),
+        max_change_pct: env_number("DETECTOR_SESSION_ALERT_CHANGE_PCT"),
+    };
+    if let (Some(high), Some(low)) = (config.high, config.low) {
+        if low >= high {
+            config_error!("DETECTOR_SESSION_ALERT_LOW must be below DETECTOR_SESSION_ALERT_HIGH");
+        }
+    }
+    config
+}
+
+// Published stats bucketing and noise, both disabled (0) unless set.
+fn stats_privacy_config_from_env() -> StatsPrivacyConfig {
+    let epsilon = env_number::<f64>("DETECTOR_STATS_EPSILON");
+    if epsilon.map_or(false, |e| e < 0.0 || !e.is_finite()) {
+        config_error!("DETECTOR_STATS_EPSILON must be positive");
+        return StatsPrivacyConfig::default()
+    }
+    StatsPrivacyConfig {
+        min_bucket: env_number("DETECTOR_STATS_MIN_BUCKET").unwrap_or(0),
+        epsilon: epsilon,
+    }
+}
+
+// Duplicate filter, None (disabled) unless DETECTOR_DEDUP_WINDOW_US and
+// DETECTOR_DEDUP_PAIRS ("0:1,2:3", cores sharing a cache) are set.
+fn dedup_config_from_env() -> Option<DedupConfig> {
+    let window_us = env_number::<u64>("DETECTOR_DEDUP_WINDOW_US")?;
+    if window_us > dedup::MAX_WINDOW_US {
+        config_error!("DETECTOR_DEDUP_WINDOW_US must be at most {}", dedup::MAX_WINDOW_US);
+        return None
+    }
+    let val = env::var("DETECTOR_DEDUP_PAIRS").unwrap_or_default();
+    let mut pairs = Vec::new();
+    for pair in val.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let mut cores = pair.splitn(2, ':').map(|c| c.trim().parse::<i32>());
+        match (cores.next(), cores.next()) {
+            (Some(Ok(a)), Some(Ok(b))) if a != b => pairs.push((a, b)),
+            _ => {
+                config_error!("can't parse DETECTOR_DEDUP_PAIRS {}", val);
+                return None
+            },
+        }
+    }
+    if pairs.is_empty() {
+        config_error!("DETECTOR_DEDUP_WINDOW_US is set but DETECTOR_DEDUP_PAIRS is empty");
+        return None
+    }
+    let config = DedupConfig {
+        window: Duration::from_micros(window_us),
+        slots: env_number("DETECTOR_DEDUP_SLOTS").unwrap_or(DedupConfig::default().slots),
+        pairs: pairs,
+    };
+    let mut cores: Vec<i32> = config.pairs.iter().flat_map(|&(a, b)| vec![a, b]).collect();
+    cores.sort();
+    if cores.windows(2).any(|w| w[0] == w[1]) {
+        config_error!("DETECTOR_DEDUP_PAIRS lists a core in more than one pair");
+        return None
+    }
+    Some(config)
+}
+
+// Mark of the traffic forwarded into tun<core>, None (unmarked) unless
+// DETECTOR_FWMARK is set.
+fn fwmark_from_env() -> Option<u32> {
+    let val = env::var("DETECTOR_FWMARK").unwrap_or_default();
+    if val.is_empty() {
+        return None
+    }
+    match fwmark::parse_mark(&val) {
+        Some(0) => None,
+        Some(mark) => Some(mark),
+        None => {
+            config_error!("can't parse DETECTOR_FWMARK {}", val);
+            None
+        },
+    }
+}
+
+// Tun routes, None (everything into tun<core>) unless DETECTOR_TUN_ROUTES
+// names a file listing some.
+fn tun_routes_from_env() -> Option<Vec<TunRoute>> {
+    let path = env::var("DETECTOR_TUN_ROUTES").unwrap_or_default();
+    if path.is_empty() {
+        return None
+    }
+    match tun_routes::load_routes(&path) {
+        Ok(ref routes) if routes.is_empty() => None,
+        Ok(routes) => Some(routes),
+        Err(e) => {
+            config_error!("{}", e);
+            None
+        },
+    }
+}
+
+// QA sampling, None (disabled) unless DETECTOR_QA_SAMPLE_RATE is set.
+// DETECTOR_QA_SAMPLE_WEIGHTS lists transport=weight pairs, e.g. "obfs4=4".
+fn qa_sampler_config_from_env() -> Option<QaSamplerConfig> {
+    let mut config = QaSamplerConfig::default();
+    config.rate = env_number("DETECTOR_QA_SAMPLE_RATE")?;
+    if let Ok(val) = env::var("DETECTOR_QA_SAMPLE_WEIGHTS") {
+        for pair in val.split(',').map(|p| p.trim()).filter(|p| !p.is_empty()) {
+            let mut parts = pair.splitn(2, '=');
+            let name = parts.next().unwrap_or_default();
+            let transport = TransportType::values().iter()
+                .find(|t| format!("{:?}", t).eq_ignore_ascii_case(name));
+            match (transport, parts.next().map(|w| w.parse::<f64>())) {
+                (Some(t), Some(Ok(w))) => config.weights.push((*t, w)),
+                _ => config_error!("can't parse DETECTOR_QA_SAMPLE_WEIGHTS entry {}", pair),
+            }
+        }
+    }
+    if let Some(s) = env_number::<u64>("DETECTOR_QA_WINDOW_S") {
+        config.window_ns = s.saturating_mul(1000*1000*1000);
+    }
+    if let Some(n) = env_number("DETECTOR_QA_MAX_SESSIONS") {
+        config.max_sessions = n;
+    }
+    Some(config)
+}
+
+// XDP pre-filter, None (disabled) unless DETECTOR_XDP_INTERFACE is set. The
+// phantom subnets come from the same file as the subnet stats'.
+fn xdp_config_from_env() -> Option<XdpConfig> {
+    let interface = env::var("DETECTOR_XDP_INTERFACE").unwrap_or_default();
+    if interface.is_empty() {
+        return None
+    }
+    let subnets_path = env::var(PHANTOM_SUBNET_PATH).unwrap_or_default();
+    if subnets_path.is_empty() {
+        config_error!("DETECTOR_XDP_INTERFACE set but {} isn't", PHANTOM_SUBNET_PATH);
+        return None
+    }
+    let mut config = XdpConfig::new(&interface, &subnets_path);
+    match env::var("DETECTOR_XDP_MODE").unwrap_or_default().as_ref() {
+        "" | "driver" => {},
+        "generic" => config.mode = XdpMode::Generic,
+        other => config_error!("can't parse DETECTOR_XDP_MODE {}", other),
+    }
+    if let Some(n) = env_number("DETECTOR_XDP_SAMPLE") {
+        config.sample = n;
+    }
+    let mut pass_ports = Vec::new();
+    for p in env::var("DETECTOR_XDP_PASS_PORTS").unwrap_or_default().split(',')
+        .map(|p| p.trim()).filter(|p| !p.is_empty())
+    {
+        match p.parse() {
+            Ok(port) => pass_ports.push(port),
+            Err(_) => config_error!("can't parse DETECTOR_XDP_PASS_PORTS port {}", p),
+        }
+    }
+    if !pass_ports.is_empty() {
+        config.pass_ports = pass_ports;
+    }
+    Some(config)
+}
+
+// Key of the ClientIds, None (a random key) unless
+// DETECTOR_CLIENT_ID_KEY_FILE is set.
+fn client_id_key_from_env() -> Option<Vec<u8>> {
+    let path = env::var("DETECTOR_CLIENT_ID_KEY_FILE").unwrap_or_default();
+    if path.is_empty() {
+        return None
+    }
+    let key = fs::read_to_string(&path).ok().and_then(|s| client_id::parse_key(&s));
+    if key.is_none() {
+        config_error!("DETECTOR_CLIENT_ID_KEY_FILE {} must hold a hex key", path);
+    }
+    key
+}
+
+// Transport detectors shown the first payload of phantom connections: the
+// prefix transport's if DETECTOR_PREFIX_TAGS lists its prefixes (hex).
+fn transport_detectors_from_env() -> TransportDetectors {
+    let mut detectors = TransportDetectors::new();
+    let mut prefixes = Vec::new();
+    for p in env::var("DETECTOR_PREFIX_TAGS").unwrap_or_default()
+        .split(',').map(|p| p.trim()).filter(|p| !p.is_empty())
+    {
+        match hex::decode(p) {
+            Ok(ref prefix) if !prefix.is_empty() => prefixes.push(prefix.clone()),
+            _ => config_error!("can't parse DETECTOR_PREFIX_TAGS prefix {}", p),
+        }
+    }
+    if !prefixes.is_empty() {
+        detectors.add(Box::new(PrefixDetector::new(prefixes)));
+    }
+    detectors
+}
+
+// Control listener for core `lcore` on `addr`, with the key from
+// DETECTOR_CONTROL_KEY_FILE. Unicast addresses get a port per core.
+fn control_config_from_env(addr: &str, lcore: i32) -> Option<ControlConfig> {
+    let mut addr: SocketAddr = match addr.parse() {
+        Ok(a) => a,
+        Err(_) => {
+            config_error!("can't parse DETECTOR_CONTROL_ADDR {}", addr);
+            return None
+        },
+    };
+    if !addr.ip().is_multicast() {
+        let port = match u16::try_from(lcore).ok().and_then(|l| addr.port().checked_add(l)) {
+            Some(p) => p,
+            None => {
+                config_error!("DETECTOR_CONTROL_ADDR port {} plus core {} is out of range", addr.port(), lcore);
+                return None
+            },
+        };
+        addr.set_port(port);
+    }
+
+    let key_path = env::var("DETECTOR_CONTROL_KEY_FILE").unwrap_or_default();
+    let key = match fs::read_to_string(&key_path).map(|s| hex::decode(s.trim())) {
+        Ok(Ok(ref key)) if !key.is_empty() => key.clone(),
+        _ => {
+            config_error!("DETECTOR_CONTROL_KEY_FILE {} must hold a hex key", key_path);
+            return None
+        },
+    };
+
+    let multicast_interface = match env::var("DETECTOR_CONTROL_INTERFACE") {
+        Ok(ref val) if !val.is_empty() => match val.parse() {
+            Ok(ip) => ip,
+            Err(_) => {
+                config_error!("can't parse DETECTOR_CONTROL_INTERFACE {}", val);
+                return None
+            },
+        },
+        _ => Ipv4Addr::new(0, 0, 0, 0),
+    };
+
+    Some(ControlConfig { addr: addr, key: key, multicast_interface: multicast_interface })
+}
+
+// DPDK capture settings, the defaults for those unset.
+#[cfg(feature = "dpdk")]
+fn dpdk_config_from_env() -> dpdk::DpdkConfig {
+    let mut config = dpdk::DpdkConfig::default();
+    if let Ok(args) = env::var("DETECTOR_DPDK_EAL_ARGS") {
+        config.eal_args = args.split_whitespace().map(|a| a.to_string()).collect();
+    }
+    match env::var("DETECTOR_DPDK_FILE_PREFIX") {
+        Ok(ref p) if !p.is_empty() => config.file_prefix = p.clone(),
+        _ => {},
+    }
+    if let Ok(val) = env::var("DETECTOR_DPDK_PORT") {
+        match val.parse() {
+            Ok(port) => config.port = port,
+            Err(_) => config_error!("DETECTOR_DPDK_PORT: bad port {:?}", val),
+        }
+    }
+    if let Some(n) = env_number("DETECTOR_DPDK_RX_DESC") {
+        config.rx_desc = n;
+    }
+    if let Some(n) = env_number("DETECTOR_DPDK_MBUFS") {
+        config.mbufs = n;
+    }
+    if let Some(n) = env_number("DETECTOR_DPDK_MBUF_SIZE") {
+        config.mbuf_size = n;
+    }
+    config
+}
+
+// Kafka registration feed for core `lcore`, None (disabled) unless
+// DETECTOR_KAFKA_BROKERS is set. Each core consumes in its own group.
+#[cfg(feature = "kafka")]
+fn kafka_config_from_env(lcore: i32) -> Option<KafkaConfig> {
+    let brokers = env::var("DETECTOR_KAFKA_BROKERS").unwrap_or_default();
+    if brokers.is_empty() {
+        return None
+    }
+    let mut config = KafkaConfig {
+        brokers: brokers,
+        topic: env::var("DETECTOR_KAFKA_TOPIC").unwrap_or_else(|_| ingest::REDIS_CHANNEL.to_string()),
+        group_id: format!("{}-{}",
+            env::var("DETECTOR_KAFKA_GROUP").unwrap_or_else(|_| "conjure-detector".to_string()), lcore),
+        security: Vec::new(),
+    };
+
+    let settings = [("DETECTOR_KAFKA_SECURITY_PROTOCOL", "security.protocol"),
+                    ("DETECTOR_KAFKA_CA_FILE", "ssl.ca.location"),
+                    ("DETECTOR_KAFKA_CERT_FILE", "ssl.certificate.location"),
+                    ("DETECTOR_KAFKA_KEY_FILE", "ssl.key.location"),
+                    ("DETECTOR_KAFKA_SASL_MECHANISM", "sasl.mechanisms"),
+                    ("DETECTOR_KAFKA_SASL_USERNAME", "sasl.username")];
+    for &(var, setting) in settings.iter() {
+        match env::var(var) {
+            Ok(ref val) if !val.is_empty() => config.security.push((setting.to_string(), val.clone())),
+            _ => {},
+        }
+    }
+    // The password is read from a file so it stays out of the environment.
+    if let Ok(path) = env::var("DETECTOR_KAFKA_SASL_PASSWORD_FILE") {
+        if !path.is_empty() {
+            match fs::read_to_string(&path) {
+                Ok(p) => config.security.push(("sasl.password".to_string(), p.trim().to_string())),
+                Err(e) => {
+                    config_error!("can't read DETECTOR_KAFKA_SASL_PASSWORD_FILE {}: {}", path, e);
+                    return None
+                },
+            }
+        }
+    }
+    Some(config)
+}
+
+// JSON registration sources for core `lcore`, each disabled unless its
+// variable is set. Each core binds its own socket.
+#[cfg(feature = "json-registrations")]
+fn json_config_from_env(lcore: i32) -> JsonConfig {
+    let mut config = JsonConfig::default();
+    match env::var("DETECTOR_JSON_CHANNEL") {
+        Ok(ref channel) if channel == ingest::REDIS_CHANNEL =>
+            config_error!("DETECTOR_JSON_CHANNEL must differ from the protobuf channel {}", channel),
+        Ok(channel) => if !channel.is_empty() {
+            config.channel = Some(channel);
+        },
+        Err(_) => {},
+    }
+    match env::var("DETECTOR_JSON_SOCKET") {
+        Ok(ref path) if !path.is_empty() => config.socket = Some(PathBuf::from(format!("{}-{}", path, lcore))),
+        _ => {},
+    }
+    config
+}
+
+// Session tracking options, from the DETECTOR_* variables set in conjure.conf.
+fn session_config_from_env(lcore: i32) -> SessionConfig {
+    let mut config = SessionConfig::default();
+
+    config.strict_v6_prefix = match env_number::<u8>("DETECTOR_STRICT_V6_PREFIX") {
+        Some(len) if len > 128 => {
+            config_error!("DETECTOR_STRICT_V6_PREFIX must be at most 128");
+            None
+        },
+        len => len,
+    };
+    if let Some(len) = config.strict_v6_prefix {
+        debug!("strict v6 session keys, client prefix /{}", len);
+    }
+
+    // DETECTOR_V4_PHANTOM_ONLY set in conjure.conf (default disabled)
+    config.v4_phantom_only = match env::var("DETECTOR_V4_PHANTOM_ONLY") {
+        Ok(val) => val == "true",
+        Err(_) => false,
+    };
+    if config.v4_phantom_only {
+        debug!("v4 session keys ignore the client address");
+    }
+
+    config.v4_client_prefix = match env_number::<u8>("DETECTOR_V4_CLIENT_PREFIX") {
+        Some(len) if len > 32 => {
+            config_error!("DETECTOR_V4_CLIENT_PREFIX must be at most 32");
+            None
+        },
+        len => len,
+    };
+    if let Some(len) = config.v4_client_prefix {
+        debug!("v4 session keys, client prefix /{}", len);
+    }
+
+    // DETECTOR_MAP_HASH set in conjure.conf (default sip)
+    if let Ok(val) = env::var("DETECTOR_MAP_HASH") {
+        if !val.is_empty() {
+            match val.parse() {
+                Ok(hash) => config.hash = hash,
+                Err(e) => config_error!("DETECTOR_MAP_HASH: {}", e),
+            }
+        }
+    }
+
+    config.max_connections = env_number("DETECTOR_MAX_CONNS_PER_SESSION");
+
+    config.max_lifetime = env_number("DETECTOR_MAX_SESSION_LIFETIME_S").map(Duration::from_secs);
+    let min_timeout = env_number::<u64>("DETECTOR_MIN_REGISTRATION_TIMEOUT_MS");
+    let max_timeout = env_number::<u64>("DETECTOR_MAX_REGISTRATION_TIMEOUT_S");
+    if min_timeout.is_some() || max_timeout.is_some() {
+        config.timeout_bounds = Some(TimeoutBounds {
+            min: Duration::from_millis(min_timeout.unwrap_or(0)),
+            max: max_timeout.map(Duration::from_secs),
+            reject: match env::var("DETECTOR_REGISTRATION_TIMEOUT_POLICY") {
+                Ok(ref val) if val == "reject" => true,
+                Ok(ref val) if val == "clamp" || val.is_empty() => false,
+                Ok(val) => {
+                    config_error!("DETECTOR_REGISTRATION_TIMEOUT_POLICY: expected clamp or reject, got {:?}", val);
+                    false
+                },
+                Err(_) => false,
+            },
+        });
+    }
+    config.max_total_lifetime = env_number("DETECTOR_MAX_SESSION_TOTAL_LIFETIME_S").map(Duration::from_secs);
+    config.max_extensions = env_number("DETECTOR_MAX_SESSION_EXTENSIONS");
+
+    // DETECTOR_SESSION_EXTENSION_S and DETECTOR_TRANSPORT_EXTENSIONS set in
+    // conjure.conf (default 300 s for every transport)
+    config.extension = env_number("DETECTOR_SESSION_EXTENSION_S").map(Duration::from_secs);
+    if let Ok(val) = env::var("DETECTOR_TRANSPORT_EXTENSIONS") {
+        for pair in val.split(',').map(|p| p.trim()).filter(|p| !p.is_empty()) {
+            let mut parts = pair.splitn(2, '=');
+            let name = parts.next().unwrap_or_default();
+            let transport = TransportType::values().iter()
+                .find(|t| format!("{:?}", t).eq_ignore_ascii_case(name));
+            match (transport, parts.next().map(|s| s.parse::<u64>())) {
+                (Some(t), Some(Ok(s))) => config.transport_extensions.push((*t, Duration::from_secs(s))),
+                _ => config_error!("can't parse DETECTOR_TRANSPORT_EXTENSIONS entry {}", pair),
+            }
+        }
+    }
+
+    // DETECTOR_EXTEND_ON_ANY_PACKET set in conjure.conf (default disabled)
+    config.extend_on_any_packet = match env::var("DETECTOR_EXTEND_ON_ANY_PACKET") {
+        Ok(val) => val == "true",
+        Err(_) => false,
+    };
+
+    // DETECTOR_DECOY_SESSIONS set in conjure.conf (default disabled)
+    if let Ok(ref val) = env::var("DETECTOR_DECOY_SESSIONS") {
+        if val == "true" {
+            let mut decoy = DecoyConfig::default();
+            if let Some(s) = env_number("DETECTOR_DECOY_EXTENSION_S") {
+                decoy.extension = Duration::from_secs(s);
+            }
+            decoy.max_lifetime = env_number("DETECTOR_DECOY_MAX_LIFETIME_S").map(Duration::from_secs);
+            config.decoy = Some(decoy);
+        }
+    }
+
+    config.latency_warn = env_number("DETECTOR_INGEST_LATENCY_WARN_MS").map(Duration::from_millis);
+
+    // DETECTOR_PHANTOM_PORTS set in conjure.conf (default 443)
+    if let Ok(val) = env::var("DETECTOR_PHANTOM_PORTS") {
+        for p in val.split(',').map(|p| p.trim()).filter(|p| !p.is_empty()) {
+            match p.parse::<u16>() {
+                Ok(port) if port != 0 => config.phantom_ports.push(port),
+                _ => config_error!("can't parse DETECTOR_PHANTOM_PORTS port {}", p),
+            }
+        }
+    }
+
+    // DETECTOR_ACCEPT_HANDOFF set in conjure.conf (default disabled)
+    config.accept_handoff = match env::var("DETECTOR_ACCEPT_HANDOFF") {
+        Ok(val) => val == "true",
+        Err(_) => false,
+    };
+
+    // DETECTOR_CONNTRACK_TEARDOWN set in conjure.conf (default disabled)
+    config.conntrack_teardown = match env::var("DETECTOR_CONNTRACK_TEARDOWN") {
+        Ok(val) => val == "true",
+        Err(_) => false,
+    };
+
+    // DETECTOR_CONTROL_ADDR set in conjure.conf (default disabled)
+    if let Ok(val) = env::var("DETECTOR_CONTROL_ADDR") {
+        if !val.is_empty() {
+            config.control = control_config_from_env(&val, lcore);
+        }
+    }
+
+    // DETECTOR_REPLAY_WINDOW_MS set in conjure.conf (default disabled)
+    if let Some(ms) = env_number::<u64>("DETECTOR_REPLAY_WINDOW_MS") {
+        let mut replay = ReplayConfig { window_ns: ms.saturating_mul(1000*1000), ..ReplayConfig::default() };
+        if let Some(n) = env_number("DETECTOR_REPLAY_MAX_IDS") {
+            replay.max_ids = n;
+        }
+        replay.require_id = match env::var("DETECTOR_REPLAY_REQUIRE_ID") {
+            Ok(val) => val == "true",
+            Err(_) => false,
+        };
+        config.replay = Some(replay);
+    }
+
+    config.journal_len = env_number("DETECTOR_INGEST_JOURNAL_LEN");
+
+    // DETECTOR_PRIME_KEY set in conjure.conf (default disabled)
+    config.prime_key = match env::var("DETECTOR_PRIME_KEY") {
+        Ok(ref val) if !val.is_empty() => Some(val.clone()),
+        _ => None,
+    };
+
+    // DETECTOR_TENANTS set in conjure.conf (default disabled)
+    if let Ok(val) = env::var("DETECTOR_TENANTS") {
+        if !val.is_empty() {
+            match val.parse() {
+                Ok(tenants) => config.tenants = Some(tenants),
+                Err(e) => config_error!("DETECTOR_TENANTS: {}", e),
+            }
+        }
+    }
+
+    // DETECTOR_ACCEPT_CONFIG_PUSH set in conjure.conf (default disabled)
+    config.accept_config_push = match env::var("DETECTOR_ACCEPT_CONFIG_PUSH") {
+        Ok(val) => val == "true",
+        Err(_) => false,
+    };
+
+    // DETECTOR_INSERT_QUEUE_LEN set in conjure.conf (default disabled)
+    if let Some(capacity) = env_number("DETECTOR_INSERT_QUEUE_LEN") {
+        let mut queue = InsertQueueConfig { capacity: capacity, ..InsertQueueConfig::default() };
+        if let Some(n) = env_number("DETECTOR_INSERT_BATCH") {
+            queue.max_batch = n;
+        }
+        config.insert_queue = Some(queue);
+    }
+
+    // DETECTOR_INGEST_WORKERS set in conjure.conf (default disabled)
+    if let Some(workers) = env_number("DETECTOR_INGEST_WORKERS") {
+        let mut pool = IngestPoolConfig { workers: workers, ..IngestPoolConfig::default() };
+        if let Some(n) = env_number("DETECTOR_INGEST_WORKER_QUEUE_LEN") {
+            pool.capacity = n;
+        }
+        config.ingest_pool = Some(pool);
+    }
+
+    // DETECTOR_INGEST_ERROR_LOGS_PER_S, DETECTOR_INGEST_BREAKER_RATE set in
+    // conjure.conf (default 10 lines, breaker disabled)
+    if let Some(n) = env_number("DETECTOR_INGEST_ERROR_LOGS_PER_S") {
+        config.ingest_errors.logs_per_sec = n;
+    }
+    config.ingest_errors.breaker_rate = env_number("DETECTOR_INGEST_BREAKER_RATE");
+    if let Some(ms) = env_number("DETECTOR_INGEST_BREAKER_COOLDOWN_MS") {
+        config.ingest_errors.cooldown = Duration::from_millis(ms);
+    }
+
+    // Every core receives the same registrations, the first one publishes
+    // them for the peers.
+    if let Some(id) = env_number("DETECTOR_REPLICATION_ID") {
+        let peers: Vec<String> = env::var("DETECTOR_REPLICATION_PEERS").unwrap_or_default()
+            .split(',')
+            .map(|p| p.trim().to_string())
+            .filter(|p| !p.is_empty())
+            .collect();
+        if peers.is_empty() {
+            config_error!("DETECTOR_REPLICATION_ID set without DETECTOR_REPLICATION_PEERS");
+        } else {
+            config.replication = Some(ReplicationConfig {
+                detector_id: id,
+                peers: peers,
+                publish: lcore == 0,
+            });
+        }
+    }
+
+    if let Some(ms) = env_number("DETECTOR_SESSION_MAINTENANCE_MS") {
+        let maintenance = &mut config.maintenance;
+        maintenance.interval = Some(Duration::from_millis(ms));
+        maintenance.jitter = Duration::from_millis(
+            env_number("DETECTOR_SESSION_MAINTENANCE_JITTER_MS").unwrap_or(0));
+        maintenance.max_drops_per_tick = env_number("DETECTOR_SESSION_MAINTENANCE_MAX_DROPS");
+        match env_number::<u64>("DETECTOR_SESSION_MAINTENANCE_MAX_MS") {
+            Some(max) if max <= ms => config_error!("DETECTOR_SESSION_MAINTENANCE_MAX_MS must exceed DETECTOR_SESSION_MAINTENANCE_MS"),
+            Some(max) => {
+                let mut adaptive = AdaptiveConfig { max: Duration::from_millis(max), ..AdaptiveConfig::default() };
+                match env_number::<f64>("DETECTOR_SESSION_MAINTENANCE_STALE_PCT") {
+                    Some(pct) if pct < 0.0 || pct > 100.0 =>
+                        config_error!("DETECTOR_SESSION_MAINTENANCE_STALE_PCT must be between 0 and 100"),
+                    Some(pct) => adaptive.max_stale_percent = pct,
+                    None => {},
+                }
+                maintenance.adaptive = Some(adaptive);
+            },
+            None => {},
+        }
+    }
+
+    let budget_entries = env_number("DETECTOR_SESSION_CLEANUP_BUDGET");
+    let budget_us = env_number("DETECTOR_SESSION_CLEANUP_BUDGET_US");
+    if budget_entries.is_some() || budget_us.is_some() {
+        config.cleanup_budget = Some(SweepBudget {
+            entries: budget_entries.unwrap_or(usize::MAX),
+            time: budget_us.map(Duration::from_micros),
+        });
+    }
+
+    config.export = session_export_config_from_env(lcore);
+
+    // Extensions over the lifetime cap are still capped, but were likely
+    // meant for another deployment
+    for e in config.extension_errors() {
+        config_error!("{}", e);
+    }
+
+    config
+}
+
+// Session export of core `lcore`, None (disabled) unless
+// DETECTOR_SESSION_EXPORT_DIR is set.
+fn session_export_config_from_env(lcore: i32) -> Option<SessionExportConfig> {
+    let dir = env::var("DETECTOR_SESSION_EXPORT_DIR").unwrap_or_default();
+    if dir.is_empty() {
+        return None
+    }
+    let mut config = SessionExportConfig::new(&dir, lcore);
+    if let Some(s) = env_number("DETECTOR_SESSION_EXPORT_INTERVAL_S") {
+        config.interval = Duration::from_secs(s);
+    }
+    if let Some(mb) = env_number::<u64>("DETECTOR_SESSION_EXPORT_ROTATE_MB") {
+        config.rotate_bytes = mb.saturating_mul(1024*1024);
+    }
+    if let Some(n) = env_number("DETECTOR_SESSION_EXPORT_MAX_FILES") {
+        config.max_files = n;
+    }
+    config.max_age = env_number::<u64>("DETECTOR_SESSION_EXPORT_RETENTION_H")
+        .map(|h| Duration::from_secs(h.saturating_mul(3600)));
+    if let Ok(path) = env::var("DETECTOR_SESSION_EXPORT_KEY_FILE") {
+        if !path.is_empty() {
+            match fs::read_to_string(&path).map(|s| hex::decode(s.trim())) {
+                Ok(Ok(ref key)) if !key.is_empty() => config.key = key.clone(),
+                _ => {
+                    config_error!("DETECTOR_SESSION_EXPORT_KEY_FILE {} must hold a hex key", path);
+                    return None
+                },
+            }
+        }
+    }
+    Some(config)
+}
+
 impl PerCoreStats
 {
     fn new() -> PerCoreStats
@@ -214,21 +1777,29 @@ impl PerCoreStats
 
                        tot_usr_us: 0,
                        tot_sys_us: 0,
-                       last_measure_time: precise_time_ns(),
+                       last_measure_time: timekeeping::now(),
 
                         not_in_tree_this_period: 0,
-                        in_tree_this_period: 0 }
+                        in_tree_this_period: 0,
+                        conn_limit_rejects_this_period: 0,
+                        sessions_matched_this_period: 0,
+                        shadow_forwards_this_period: 0,
+                        shadow_registrations_this_period: 0,
+                        v6_unparseable_this_period: 0,
+                        v6_later_fragments_this_period: 0,
+                        quic_initials_this_period: 0,
+                        quic_other_this_period: 0 }
     }
     fn periodic_status_report(&mut self, tracked: usize, dark_decoys: usize)
     {
-        let cur_measure_time = precise_time_ns();
+        let cur_measure_time = timekeeping::now();
         let (user_secs, user_usecs, sys_secs, sys_usecs) =
             c_api::c_get_cpu_time();
         let user_microsecs: i64 = user_usecs + 1000000 * user_secs;
         let sys_microsecs: i64 = sys_usecs + 1000000 * sys_secs;
 
         /*
-        let measured_dur_ns = cur_measure_time - self.last_measure_time;
+        let measured_dur_ns = cur_measure_time.saturating_since(self.last_measure_time);
         let total_cpu_usec = (user_microsecs + sys_microsecs)
                      - (self.tot_usr_us + self.tot_sys_us);
         */
@@ -275,6 +1846,14 @@ impl PerCoreStats
 
         self.not_in_tree_this_period = 0;
         self.in_tree_this_period = 0;
+        self.conn_limit_rejects_this_period = 0;
+        self.sessions_matched_this_period = 0;
+        self.shadow_forwards_this_period = 0;
+        self.shadow_registrations_this_period = 0;
+        self.v6_unparseable_this_period = 0;
+        self.v6_later_fragments_this_period = 0;
+        self.quic_initials_this_period = 0;
+        self.quic_other_this_period = 0;
     }
 }
 
@@ -284,9 +1863,269 @@ pub extern "C" fn rust_periodic_report(ptr: *mut PerCoreGlobal)
 {
     #[allow(unused_mut)]
     let mut global = unsafe { &mut *ptr };
+    let dropped_events = global.events.take_dropped();
+    if dropped_events > 0 {
+        report!("detector events dropped {}", dropped_events);
+    }
+    report!("phantom connections {} rejected over limit {}",
+        global.flow_tracker.count_phantom_connections(),
+        global.stats.conn_limit_rejects_this_period);
+    report!("sessions first matched {}", global.stats.sessions_matched_this_period);
+    report!("{}", global.flow_tracker.take_gc_report());
+    if global.flow_tracker.limit().is_some() {
+        report!("{}", global.flow_tracker.take_eviction_report());
+    }
+    if let Some(report) = global.flow_tracker.take_syn_filter_report() {
+        report!("{}", report);
+    }
+    if let Some(report) = global.flow_tracker.take_negative_cache_report() {
+        report!("{}", report);
+    }
+    if global.flow_tracker.is_asymmetric() {
+        report!("phantom connections opened midstream {}",
+            global.flow_tracker.take_midstream_opens());
+    } else if global.flow_tracker.seq_window().is_some() {
+        report!("phantom packets outside sequence window {}",
+            global.flow_tracker.take_seq_rejects());
+    }
+    if global.flow_tracker.phantom_flows.is_draining() {
+        report!("draining ignored registrations {}",
+            global.flow_tracker.phantom_flows.take_drained());
+    }
+    let per_port: Vec<String> = global.flow_tracker.phantom_flows.sessions_per_port().iter()
+        .map(|&(port, n)| format!("{} {}", port, n))
+        .collect();
+    report!("sessions per port {}", per_port.join(" "));
+    report!("v6 unparseable headers {} later fragments {}",
+        global.stats.v6_unparseable_this_period,
+        global.stats.v6_later_fragments_this_period);
+    if global.quic_initials {
+        report!("quic initials {} other {}",
+            global.stats.quic_initials_this_period,
+            global.stats.quic_other_this_period);
+    }
+    if global.shadow_mode {
+        report!("shadow would forward {} pkts would register {}",
+            global.stats.shadow_forwards_this_period,
+            global.stats.shadow_registrations_this_period);
+    }
+    if global.flow_tracker.phantom_flows.config().timeout_bounds.is_some() {
+        report!("registration timeouts {}",
+            global.flow_tracker.phantom_flows.take_timeout_report());
+    }
+    report!("session expiry changes {}",
+        global.flow_tracker.phantom_flows.take_expiry_change_report());
+    report!("ingest latency {}",
+        global.flow_tracker.phantom_flows.ingest_latency().take_report());
+    report!("{}", global.flow_tracker.phantom_flows.ingest_supervisor().take_report());
+    report!("{}", global.flow_tracker.phantom_flows.ingest_errors().take_report());
+    let port_refusals = global.flow_tracker.phantom_flows.take_client_port_refusals();
+    if port_refusals > 0 {
+        report!("packets outside their session's client ports {}", port_refusals);
+    }
+    let recoveries = global.flow_tracker.phantom_flows.take_lock_recoveries();
+    if recoveries > 0 {
+        report!("session map lock recoveries {}", recoveries);
+    }
+    let blocked = global.flow_tracker.phantom_flows.take_phantom_blocks_refused();
+    if blocked > 0 {
+        report!("registrations refused for blocked phantom subnets {}", blocked);
+    }
+    report!("session expiry {}",
+        global.flow_tracker.phantom_flows.expiry_stats().take_report());
+    let never_matched = global.flow_tracker.phantom_flows.expiry_stats().take_never_matched();
+    let summary = {
+        let subnets = &global.phantom_subnets;
+        feedback::never_matched_event(never_matched, |ip| subnets.as_ref().and_then(|s| s.subnet_of(ip)),
+            &mut global.stats_privacy)
+    };
+    if let Some(event) = summary {
+        global.events.publish(&event);
+    }
+    if global.stats_privacy.config().enabled() {
+        report!("{}", global.stats_privacy.take_report());
+    }
+    if let Some(ref mut dedup) = global.dedup {
+        report!("{}", dedup.take_report());
+    }
+    if let Some(tenants) = global.flow_tracker.phantom_flows.take_tenant_report() {
+        report!("{}", tenants);
+    }
+    let sequences = global.flow_tracker.phantom_flows.sequences().take_counts();
+    if !sequences.is_empty() {
+        report!("{}", sequence::format_counts(&sequences));
+    }
+    if let Some(event) = sequence::gaps_event(&sequences, global.lcore as u32) {
+        global.events.publish(&event);
+    }
+    if let Some(ref mut alerts) = global.session_alerts {
+        for event in alerts.check(global.flow_tracker.phantom_flows.len() as u64) {
+            global.events.publish(&event);
+        }
+    }
+    let (expired, unreported) = global.flow_tracker.phantom_flows.expiry_stats().take_expired_matched();
+    for entry in expired.iter() {
+        global.events.publish(&feedback::session_expired_event(entry));
+    }
+    if unreported > 0 {
+        report!("session expired events dropped {}", unreported);
+    }
     global.stats.periodic_status_report(
         global.flow_tracker.count_tracked_flows(),
         global.flow_tracker.count_phantom_flows());
+
+    let by_source: Vec<String> = global.flow_tracker.phantom_flows.count_by_source().iter()
+        .map(|(src, count)| format!("{:?} {}", src, count))
+        .collect();
+    report!("sessions by source {}", by_source.join(", "));
+    if let Some(ref mut subnets) = global.phantom_subnets {
+        for line in subnets.take_report(&global.flow_tracker.phantom_flows) {
+            report!("{}", line);
+        }
+    }
+    if let Some(r) = global.flow_tracker.phantom_flows.replication() {
+        for line in r.take_report() {
+            report!("{}", line);
+        }
+    }
+    if let Some(t) = global.flow_tracker.phantom_flows.conntrack_teardown() {
+        report!("{}", t.take_report());
+    }
+    if let Some(r) = global.flow_tracker.phantom_flows.replay_guard() {
+        report!("{}", r.take_report());
+    }
+    if let Some(r) = global.flow_tracker.phantom_flows.control_replay_guard() {
+        report!("control {}", r.take_report());
+    }
+    if let Some(q) = global.flow_tracker.phantom_flows.insert_queue() {
+        report!("{}", q.take_report());
+    }
+    if let Some(p) = global.flow_tracker.phantom_flows.ingest_pool() {
+        report!("{}", p.take_report());
+    }
+    if let Some(g) = global.flow_tracker.phantom_flows.cleanup_gauge() {
+        report!("{}", g.take_report());
+    }
+    if let Some(e) = global.flow_tracker.phantom_flows.session_export() {
+        report!("{}", e.take_report());
+    }
+    if let Some(c) = global.flow_tracker.phantom_flows.station_config() {
+        report!("{}", c.take_report());
+        report!("{}", c.take_subnets_report());
+        global.events.publish(&c.heartbeat());
+    }
+    if let Some(ref mut qa) = global.qa {
+        qa.flush(timekeeping::now());
+        report!("{}", qa.take_report());
+    }
+    if let Some(ref mut scan) = global.dark_scan {
+        for line in scan.take_report(timekeeping::now()) {
+            report!("{}", line);
+        }
+    }
+    if let Some(ref mut unmatched) = global.unmatched {
+        report!("{}", unmatched.take_report());
+    }
+    report!("tun {}", global.tun.take_report());
+    if let Some(ref mut router) = global.tun_routes {
+        report!("{}", router.take_report());
+    }
+    if let Some(ref mut ring) = global.proxy_ring {
+        report!("{}", ring.take_report());
+    }
+    if let Some(ref mut marks) = global.fwmarks {
+        report!("{}", marks.take_report());
+    }
+    report!("{}", global.gro.take_report());
+    if !global.transport_detectors.is_empty() {
+        report!("{}", global.transport_detectors.take_report());
+    }
+    report!("{}", global.vlans.take_report());
+    if global.decap.enabled() {
+        report!("{}", global.decap.take_report());
+    }
+    if let Some(ref mut fragments) = global.fragments {
+        report!("{}", fragments.take_report(timekeeping::now()));
+    }
+    for line in global.latency.take_report() {
+        report!("{}", line);
+    }
+    if let Some(ref mut xdp) = global.xdp {
+        report!("{}", xdp.take_report());
+    }
+    #[cfg(feature = "kafka")]
+    {
+        if let Some(ref lag) = global.kafka_lag {
+            report!("{}", lag.take_report());
+        }
+    }
+}
+
+// EAL arguments and port settings of the DPDK capture for core `lcore_id`,
+// pinned to `cpu`. capture_dpdk.c calls this before starting the EAL.
+#[cfg(feature = "dpdk")]
+#[no_mangle]
+pub extern "C" fn rust_dpdk_settings(lcore_id: i32, cpu: i32) -> dpdk::DpdkSettings
+{
+    dpdk::settings(&dpdk_config_from_env(), lcore_id, cpu)
+}
+
+// Reports the receive counts of the core's DPDK queue. detect.c calls this
+// after each rust_periodic_report.
+#[cfg(feature = "dpdk")]
+#[no_mangle]
+pub extern "C" fn rust_dpdk_rx_stats(ptr: *mut PerCoreGlobal, port: u16, queue: u16,
+                                     totals: dpdk::DpdkRxCounters)
+{
+    let global = unsafe { &mut *ptr };
+    report!("{}", global.dpdk_rx.report(port, queue, totals));
+}
+
+// Logs every tracked phantom session along with the registration source that
+// created it and its remaining lifetime, then the sessions per phantom subnet
+// if subnet stats are enabled. detect.c calls this on SIGHUP.
+#[no_mangle]
+pub extern "C" fn rust_dump_sessions(ptr: *mut PerCoreGlobal)
+{
+    #[allow(unused_mut)]
+    let mut global = unsafe { &mut *ptr };
+    let lines = global.flow_tracker.phantom_flows.dump();
+    info!("session dump: {} sessions", lines.len());
+    for line in lines.iter() {
+        info!("session {}", line);
+    }
+    if let Some(ref subnets) = global.phantom_subnets {
+        for line in subnets.dump(&global.flow_tracker.phantom_flows) {
+            info!("phantom subnet {}", line);
+        }
+    }
+}
+
+// Runs the health checks for core `lcore_id` capturing from `iface` (NULL in
+// zero copy mode) and prints the results. Returns 0 if they all passed.
+// detect.c calls this for every core it would start when run with -H.
+#[no_mangle]
+pub extern "C" fn rust_healthcheck(lcore_id: i32, iface: *const c_char) -> u8
+{
+    let interface = match iface.is_null() {
+        true => None,
+        false => Some(unsafe { CStr::from_ptr(iface) }.to_string_lossy().into_owned()),
+    };
+    let config = HealthcheckConfig {
+        redis_url: ingest::REDIS_URL.to_string(),
+        interface: interface,
+        tun: format!("tun{}", lcore_id),
+        config_errors: config_errors(lcore_id),
+        ..HealthcheckConfig::default()
+    };
+    let checks = healthcheck::run(&config, &session_config_from_env(lcore_id));
+    for check in checks.iter() {
+        println!("core {}: {}", lcore_id, check);
+    }
+    match healthcheck::healthy(&checks) {
+        true => 0,
+        false => 1,
+    }
 }
 
 #[repr(C)]
@@ -323,9 +2162,20 @@ pub extern "C" fn rust_detect_init(lcore_id: i32, ckey: *const u8, workers_socke
 
 // Called so we can tick the event loop forward. Must not block.
 #[no_mangle]
-pub extern "C" fn rust_event_loop_tick(_ptr: *mut PerCoreGlobal)
+pub extern "C" fn rust_event_loop_tick(ptr: *mut PerCoreGlobal)
 {
-
+    #[allow(unused_mut)]
+    let mut global = unsafe { &mut *ptr };
+    if let Some(ref heartbeat) = global.heartbeat {
+        heartbeat.beat(timekeeping::now());
+    }
+    // frames the tun devices didn't take while packets were processed
+    if global.tun.depth() > 0 {
+        global.tun.flush();
+    }
+    if let Some(ref mut router) = global.tun_routes {
+        router.flush();
+    }
 }
 
 // Drops TLS flows that took too long to send their first app data packet,
@@ -337,6 +2187,21 @@ pub extern "C" fn rust_periodic_cleanup(ptr: *mut PerCoreGlobal)
     #[allow(unused_mut)]
     let mut global = unsafe { &mut *ptr };
     global.flow_tracker.drop_all_stale_flows();
+    global.follow_phantom_subnets();
+    if let Some(ref mut unmatched) = global.unmatched {
+        unmatched.send_due(timekeeping::now());
+    }
+    if let Some(ref mut xdp) = global.xdp {
+        xdp.refresh(Instant::now());
+    }
+    let proxy_gone = match global.proxy_ring {
+        Some(ref mut ring) => !ring.connected(),
+        None => false,
+    };
+    if proxy_gone {
+        warn!("proxy closed or broke its ring, forwarding into {}", global.tun.name());
+        global.proxy_ring = None;
+    }
 
     /*
     // Any session that hangs around for 30 seconds with a None cli stream
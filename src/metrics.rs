@@ -0,0 +1,218 @@
+//
+// Metrics
+//
+// Counters that are written from one thread (e.g. the ingest thread) and read
+// by the periodic report on the packet processing thread. Everything here is
+// lock free and safe to share behind an Arc.
+//
+// Counters bumped from several threads at once are kept in a Counters set
+// instead: every writing thread gets its own shard (Counters::local), a cache
+// line only that thread writes, with a plain load and store rather than a
+// locked read-modify-write. The report sums the shards, so writers never
+// contend with each other or with the reader for a cache line. A dropped
+// shard keeps its counts and goes back to the set, to be handed to the next
+// thread asking for one, so threads that come and go (e.g. restarted ingest
+// threads) don't grow the set.
+
+use std::cell::Cell;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Counters per set, as many as fit a cache line.
+pub const MAX_COUNTERS: usize = 8;
+
+#[derive(Default)]
+#[repr(align(64))]
+struct Shard
+{
+    counts: [AtomicU64; MAX_COUNTERS],
+}
+
+// A set of up to MAX_COUNTERS counters, written through per-thread shards.
+pub struct Counters
+{
+    shards: Mutex<Vec<Arc<Shard>>>,
+    // Shards no thread holds.
+    free: Arc<Mutex<Vec<Arc<Shard>>>>,
+    // Totals at the last take of each counter.
+    taken: Mutex<[u64; MAX_COUNTERS]>,
+}
+
+// One thread's shard of a Counters set. Send but not Sync: only the thread
+// holding it writes the shard.
+pub struct LocalCounters
+{
+    shard: Arc<Shard>,
+    free: Arc<Mutex<Vec<Arc<Shard>>>>,
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+impl Counters
+{
+    pub fn new() -> Counters {
+        Counters {
+            shards: Mutex::new(Vec::new()),
+            free: Arc::new(Mutex::new(Vec::new())),
+            taken: Mutex::new([0; MAX_COUNTERS]),
+        }
+    }
+
+    /// A shard for the calling thread (or the thread it's handed to), one
+    /// dropped before if there is one. Its counts stay in the totals after
+    /// it's dropped.
+    pub fn local(&self) -> LocalCounters {
+        let reused = self.free.lock().unwrap_or_else(|e| e.into_inner()).pop();
+        let shard = match reused {
+            Some(shard) => shard,
+            None => {
+                let shard = Arc::new(Shard::default());
+                self.shards.lock().unwrap_or_else(|e| e.into_inner()).push(Arc::clone(&shard));
+                shard
+            },
+        };
+        LocalCounters { shard: shard, free: Arc::clone(&self.free), _not_sync: PhantomData }
+    }
+
+    /// Total of `counter` over every shard since the last call.
+    pub fn take(&self, counter: usize) -> u64 {
+        let total = self.shards.lock().unwrap_or_else(|e| e.into_inner()).iter()
+            .fold(0u64, |sum, s| sum.wrapping_add(s.counts[counter].load(Ordering::Relaxed)));
+        let mut taken = self.taken.lock().unwrap_or_else(|e| e.into_inner());
+        let since = total.wrapping_sub(taken[counter]);
+        taken[counter] = total;
+        since
+    }
+}
+
+impl LocalCounters
+{
+    pub fn add(&self, counter: usize, n: u64) {
+        let c = &self.shard.counts[counter];
+        c.store(c.load(Ordering::Relaxed).wrapping_add(n), Ordering::Relaxed);
+    }
+}
+
+impl Drop for LocalCounters
+{
+    fn drop(&mut self) {
+        self.free.lock().unwrap_or_else(|e| e.into_inner()).push(Arc::clone(&self.shard));
+    }
+}
+
+// Histogram with fixed bucket upper bounds. A value v is counted in the first
+// bucket with v < bound, or in the final overflow bucket.
+pub struct Histogram
+{
+    bounds: Vec<u64>,
+    counts: Vec<AtomicU64>,
+}
+
+impl Histogram
+{
+    pub fn new(bounds: &[u64]) -> Histogram {
+        Histogram {
+            bounds: bounds.to_vec(),
+            counts: (0..bounds.len() + 1).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+    pub fn record(&self, value: u64) {
+        let bucket = self.bounds.iter()
+            .position(|b| value < *b)
+            .unwrap_or(self.bounds.len());
+        self.counts[bucket].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts per bucket since the last call, overflow bucket last.
+    pub fn take(&self) -> Vec<u64> {
+        self.counts.iter().map(|c| c.swap(0, Ordering::Relaxed)).collect()
+    }
+
+    /// Like take, formatted as "<{bound} {count} ... >={last bound} {count}"
+    /// with bounds passed through `label`.
+    pub fn take_formatted<F: Fn(u64) -> String>(&self, label: F) -> String {
+        format_buckets(&self.bounds, &self.take(), label)
+    }
+}
+
+// Formats per bucket `counts` (one more than `bounds`, overflow last) as
+// Histogram::take_formatted does.
+pub fn format_buckets<F: Fn(u64) -> String>(bounds: &[u64], counts: &[u64], label: F) -> String {
+    let mut parts: Vec<String> = bounds.iter().zip(counts.iter())
+        .map(|(b, c)| format!("<{} {}", label(*b), c))
+        .collect();
+    match bounds.last() {
+        Some(b) => parts.push(format!(">={} {}", label(*b), counts[bounds.len()])),
+        None => parts.push(format!("{}", counts[0])),
+    }
+    parts.join(" ")
+}
+
+// Formats a duration in ns with the largest whole unit (ns, us, ms, s).
+pub fn ns_label(ns: u64) -> String {
+    if ns >= 1000*1000*1000 && ns % (1000*1000*1000) == 0 {
+        format!("{}s", ns / (1000*1000*1000))
+    } else if ns >= 1000*1000 && ns % (1000*1000) == 0 {
+        format!("{}ms", ns / (1000*1000))
+    } else if ns >= 1000 && ns % 1000 == 0 {
+        format!("{}us", ns / 1000)
+    } else {
+        format!("{}ns", ns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use metrics::*;
+
+    #[test]
+    fn test_histogram() {
+        let h = Histogram::new(&[1000, 1000*1000, 1000*1000*1000]);
+        h.record(0);
+        h.record(999);
+        h.record(1000);
+        h.record(5*1000*1000*1000);
+        assert_eq!(h.take(), vec![2, 1, 0, 1]);
+        assert_eq!(h.take(), vec![0, 0, 0, 0]);
+
+        h.record(2000);
+        assert_eq!(h.take_formatted(ns_label), "<1us 0 <1ms 1 <1s 0 >=1s 0");
+        assert_eq!(ns_label(1500), "1500ns");
+    }
+
+    #[test]
+    fn test_counters() {
+        use std::thread;
+
+        let counters = Arc::new(Counters::new());
+        let main = counters.local();
+        main.add(0, 1);
+        let writers: Vec<_> = (0..4).map(|_| {
+            let local = counters.local();
+            thread::spawn(move || {
+                for _ in 0..1000 {
+                    local.add(0, 1);
+                    local.add(3, 2);
+                }
+            })
+        }).collect();
+        for w in writers {
+            w.join().unwrap();
+        }
+        assert_eq!(counters.take(0), 4001);
+        assert_eq!(counters.take(3), 8000);
+        assert_eq!(counters.take(0), 0);
+
+        // counts of dropped shards are kept, and the shards reused
+        main.add(0, 5);
+        drop(main);
+        assert_eq!(counters.take(0), 5);
+        assert_eq!(counters.take(1), 0);
+        for _ in 0..10 {
+            counters.local().add(1, 1);
+        }
+        assert_eq!(counters.take(1), 10);
+        assert_eq!(counters.shards.lock().unwrap().len(), 5);
+    }
+}
@@ -0,0 +1,117 @@
+//
+// Negative Lookup Cache
+//
+// Every TCP packet to port 443 or a phantom port is looked up in the session
+// map (FlowTracker::is_phantom_flow), taking its read lock or loading the
+// snapshot, and nearly all of them belong to no session: registration
+// traffic to decoys, scans, and bursts of unmatched flows. With a
+// NegativeCache a core remembers the flows it recently found untracked, and
+// skips the lookup for their later packets.
+//
+// - A miss is only valid until a session is added: each is stamped with the
+//   SessionTracker's insert generation (see insert_generation) read before
+//   the lookup, and ignored once that has moved on. Removals can't turn a
+//   miss into a match, so they don't invalidate anything.
+// - The cache is a fixed array of slots, picked by a keyed hash of the flow
+//   (its source port included, as sessions may restrict their client's
+//   ports). A slot holds one flow, so a flow only hits on an exact match and
+//   colliding flows just take each other's place.
+// - Packets of cached flows aren't counted again in the client port refusals
+//   (SessionTracker::take_client_port_refusals).
+//
+// Reported per period: lookups skipped for a cached miss, and lookups made.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash, Hasher};
+
+use flow_tracker::Flow;
+
+pub struct NegativeCache
+{
+    // Flows found untracked, and the insert generation they were looked up
+    // at.
+    slots: Vec<Option<(Flow, u64)>>,
+    key: RandomState,
+    // Since the last take_report.
+    hits: u64,
+    lookups: u64,
+}
+
+impl NegativeCache
+{
+    /// A cache of `slots` flows.
+    pub fn new(slots: usize) -> NegativeCache {
+        NegativeCache {
+            slots: vec![None; slots.max(1)],
+            key: RandomState::new(),
+            hits: 0,
+            lookups: 0,
+        }
+    }
+
+    fn slot(&self, flow: &Flow) -> usize {
+        let mut hasher = self.key.build_hasher();
+        flow.hash(&mut hasher);
+        (hasher.finish() % self.slots.len() as u64) as usize
+    }
+
+    /// Whether `flow` was found untracked at insert generation `generation`,
+    /// so its lookup can be skipped. Counts a lookup otherwise.
+    pub fn untracked(&mut self, flow: &Flow, generation: u64) -> bool {
+        match self.slots[self.slot(flow)] {
+            Some((ref cached, at)) if cached == flow && at == generation => {
+                self.hits += 1;
+                true
+            },
+            _ => {
+                self.lookups += 1;
+                false
+            },
+        }
+    }
+
+    /// Remembers that `flow` was found untracked at `generation`, read
+    /// before its lookup.
+    pub fn insert(&mut self, flow: &Flow, generation: u64) {
+        let i = self.slot(flow);
+        self.slots[i] = Some((*flow, generation));
+    }
+
+    /// Lookups skipped and made since the last call, formatted for the
+    /// periodic report.
+    pub fn take_report(&mut self) -> String {
+        let report = format!("negative cache hits {} lookups {}", self.hits, self.lookups);
+        self.hits = 0;
+        self.lookups = 0;
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use negative_cache::*;
+
+    fn flow(port: u16) -> Flow {
+        Flow::from_parts("10.22.0.1".parse().unwrap(), "128.138.97.6".parse().unwrap(), port, 443)
+    }
+
+    #[test]
+    fn test_negative_cache() {
+        let mut cache = NegativeCache::new(64);
+        assert!(!cache.untracked(&flow(1), 0));
+        cache.insert(&flow(1), 0);
+        assert!(cache.untracked(&flow(1), 0));
+        // another source port is another flow
+        assert!(!cache.untracked(&flow(2), 0));
+        // stale once a session was added
+        assert!(!cache.untracked(&flow(1), 1));
+        assert_eq!(cache.take_report(), "negative cache hits 1 lookups 3");
+
+        // flows sharing a slot replace each other, never hitting for another
+        let mut cache = NegativeCache::new(1);
+        cache.insert(&flow(1), 5);
+        cache.insert(&flow(2), 5);
+        assert!(!cache.untracked(&flow(1), 5));
+        assert!(cache.untracked(&flow(2), 5));
+    }
+}
@@ -0,0 +1,116 @@
+//
+// Phantom Blocks
+//
+// During incident response an operator can cut a compromised phantom pool off
+// with the admin socket's flush command: every session whose phantom is in
+// the given subnet is removed at once (and, with conntrack teardown enabled,
+// its forwarded connections torn down, see conntrack.rs), and optionally new
+// registrations for phantoms in the subnet are refused for a while so the
+// station can't bring them back before it has been fixed.
+//
+// Blocks are per core, as is everything the admin socket changes, and are
+// forgotten once they run out or when the detector restarts. Refused
+// registrations are counted for the periodic report.
+
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use ipnetwork::IpNetwork;
+
+use timekeeping::MonotonicNs;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhantomBlock
+{
+    pub subnet: IpNetwork,
+    // Clock time the block runs out.
+    pub until: MonotonicNs,
+}
+
+#[derive(Default)]
+pub struct PhantomBlocks
+{
+    blocks: Mutex<Vec<PhantomBlock>>,
+    // Registrations refused since the last report.
+    refused: AtomicU64,
+}
+
+impl PhantomBlocks
+{
+    pub fn new() -> PhantomBlocks {
+        PhantomBlocks::default()
+    }
+
+    /// Refuses registrations for phantoms in `subnet` until `until`,
+    /// replacing any block of the same subnet.
+    pub fn block(&self, subnet: IpNetwork, until: MonotonicNs) {
+        let mut blocks = self.blocks.lock().unwrap_or_else(|e| e.into_inner());
+        blocks.retain(|b| b.subnet != subnet);
+        blocks.push(PhantomBlock { subnet: subnet, until: until });
+    }
+
+    /// Lifts the block of `subnet`, returning whether there was one.
+    pub fn unblock(&self, subnet: &IpNetwork) -> bool {
+        let mut blocks = self.blocks.lock().unwrap_or_else(|e| e.into_inner());
+        let before = blocks.len();
+        blocks.retain(|b| b.subnet != *subnet);
+        blocks.len() != before
+    }
+
+    /// The block refusing `phantom` at `now`, if any, counted as a refusal.
+    pub fn refuses(&self, phantom: &IpAddr, now: MonotonicNs) -> Option<PhantomBlock> {
+        let mut blocks = self.blocks.lock().unwrap_or_else(|e| e.into_inner());
+        if blocks.is_empty() {
+            return None
+        }
+        blocks.retain(|b| b.until > now);
+        let block = blocks.iter().find(|b| b.subnet.contains(*phantom)).cloned();
+        if block.is_some() {
+            self.refused.fetch_add(1, Ordering::Relaxed);
+        }
+        block
+    }
+
+    /// Blocks in force at `now`.
+    pub fn active(&self, now: MonotonicNs) -> Vec<PhantomBlock> {
+        let blocks = self.blocks.lock().unwrap_or_else(|e| e.into_inner());
+        blocks.iter().filter(|b| b.until > now).cloned().collect()
+    }
+
+    /// Registrations refused since the last call.
+    pub fn take_refused(&self) -> u64 {
+        self.refused.swap(0, Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use phantom_block::*;
+
+    #[test]
+    fn test_phantom_blocks() {
+        let blocks = PhantomBlocks::new();
+        let phantom: IpAddr = "10.10.3.4".parse().unwrap();
+        assert!(blocks.refuses(&phantom, MonotonicNs(0)).is_none());
+
+        let subnet: IpNetwork = "10.10.0.0/16".parse().unwrap();
+        blocks.block(subnet, MonotonicNs(100));
+        blocks.block("2001::/64".parse().unwrap(), MonotonicNs(50));
+        assert_eq!(blocks.refuses(&phantom, MonotonicNs(10)), Some(PhantomBlock { subnet: subnet, until: MonotonicNs(100) }));
+        assert!(blocks.refuses(&"10.11.0.1".parse().unwrap(), MonotonicNs(10)).is_none());
+        assert_eq!(blocks.active(MonotonicNs(60)).len(), 1);
+        // extended
+        blocks.block(subnet, MonotonicNs(200));
+        assert_eq!(blocks.active(MonotonicNs(150)).len(), 1);
+        assert!(blocks.refuses(&phantom, MonotonicNs(150)).is_some());
+        assert!(blocks.refuses(&phantom, MonotonicNs(200)).is_none());
+        assert_eq!(blocks.take_refused(), 2);
+        assert_eq!(blocks.take_refused(), 0);
+
+        blocks.block(subnet, MonotonicNs(300));
+        assert!(blocks.unblock(&subnet));
+        assert!(!blocks.unblock(&subnet));
+        assert!(blocks.refuses(&phantom, MonotonicNs(250)).is_none());
+    }
+}
@@ -0,0 +1,254 @@
+//
+// Phantom Subnet Statistics
+//
+// The application picks phantom addresses from the weighted subnets listed in
+// PHANTOM_SUBNET_LOCATION (phantom_subnets.toml). With phantom subnet stats
+// enabled the detector loads the same file and breaks its view of the phantom
+// traffic down by subnet, so hot, exhausted or dead pools stand out:
+//
+//  - sessions: registered sessions whose phantom is in the subnet
+//  - syns: new connections to an address in the subnet, and how many of them
+//    matched a registered session
+//  - bytes: bytes forwarded to phantoms in the subnet
+//
+// The same subnet usually appears in several generations (and subnets of
+// different generations may overlap), so each distinct subnet is counted once
+// and an address is attributed to the most specific subnet containing it.
+// Lookups (one per new connection to a session's phantom and per forwarded
+// packet) take a hash lookup per distinct prefix length, not a scan of the
+// subnets. A SYN refused at its session's connection limit counts as
+// unmatched.
+//
+// When the station pushes a set of phantom subnets (config_push.rs) it
+// replaces the file's from then on. Subnets in both keep their counts.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::net::IpAddr;
+
+use ipnetwork::IpNetwork;
+use serde_derive::Deserialize;
+use toml;
+
+use sessions::SessionTracker;
+
+// Layout of phantom_subnets.toml, only the parts the detector needs.
+#[derive(Deserialize)]
+struct SubnetFile {
+    #[serde(rename = "Networks")]
+    networks: HashMap<String, SubnetGeneration>,
+}
+
+#[derive(Deserialize)]
+struct SubnetGeneration {
+    #[serde(rename = "WeightedSubnets")]
+    weighted_subnets: Vec<WeightedSubnets>,
+}
+
+#[derive(Deserialize)]
+struct WeightedSubnets {
+    #[serde(rename = "Subnets")]
+    subnets: Vec<String>,
+}
+
+struct SubnetCounters
+{
+    net: IpNetwork,
+    syns: u64,
+    matched: u64,
+    bytes: u64,
+}
+
+pub struct PhantomSubnets
+{
+    // Most specific (longest prefix) first.
+    subnets: Vec<SubnetCounters>,
+    // For each prefix length, longest first, the index in subnets of the
+    // subnet with each network address.
+    prefixes: Vec<(u8, HashMap<IpAddr, usize>)>,
+}
+
+impl PhantomSubnets
+{
+    pub fn new(nets: &[IpNetwork]) -> PhantomSubnets {
+        let mut unique: Vec<IpNetwork> = Vec::new();
+        for net in nets.iter() {
+            if !unique.contains(net) {
+                unique.push(*net);
+            }
+        }
+        unique.sort_by(|a, b| b.prefix().cmp(&a.prefix()));
+        let mut prefixes: Vec<(u8, HashMap<IpAddr, usize>)> = Vec::new();
+        for (i, net) in unique.iter().enumerate() {
+            if prefixes.last().map(|p| p.0) != Some(net.prefix()) {
+                prefixes.push((net.prefix(), HashMap::new()));
+            }
+            let index = &mut prefixes.last_mut().unwrap().1;
+            index.entry(net.network()).or_insert(i);
+        }
+        PhantomSubnets {
+            subnets: unique.into_iter()
+                .map(|net| SubnetCounters { net: net, syns: 0, matched: 0, bytes: 0 })
+                .collect(),
+            prefixes: prefixes,
+        }
+    }
+
+    /// Counts for `nets` from now on, keeping the counts of the subnets
+    /// already counted.
+    pub fn replace(&mut self, nets: &[IpNetwork]) {
+        let mut replaced = PhantomSubnets::new(nets);
+        for s in replaced.subnets.iter_mut() {
+            if let Some(old) = self.subnets.iter().find(|o| o.net == s.net) {
+                s.syns = old.syns;
+                s.matched = old.matched;
+                s.bytes = old.bytes;
+            }
+        }
+        *self = replaced;
+    }
+
+    /// Subnets of every generation in a phantom_subnets.toml file.
+    pub fn load(path: &str) -> Result<PhantomSubnets, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("can't read {}: {}", path, e))?;
+        let file: SubnetFile = toml::from_str(&contents)
+            .map_err(|e| format!("can't parse {}: {}", path, e))?;
+
+        let mut nets = Vec::new();
+        for generation in file.networks.values() {
+            for weighted in generation.weighted_subnets.iter() {
+                for s in weighted.subnets.iter() {
+                    nets.push(s.parse::<IpNetwork>()
+                        .map_err(|e| format!("bad subnet {} in {}: {}", s, path, e))?);
+                }
+            }
+        }
+        Ok(PhantomSubnets::new(&nets))
+    }
+
+    /// Every distinct subnet, most specific first.
+    pub fn nets(&self) -> Vec<IpNetwork> {
+        self.subnets.iter().map(|s| s.net).collect()
+    }
+
+    fn find(&self, ip: &IpAddr) -> Option<usize> {
+        self.prefixes.iter().filter_map(|&(prefix, ref index)| {
+            // prefixes longer than the address's family don't apply to it
+            let net = IpNetwork::new(*ip, prefix).ok()?;
+            index.get(&net.network()).cloned()
+        }).next()
+    }
+
+    /// Most specific subnet containing `ip`, if any.
+    pub fn subnet_of(&self, ip: &IpAddr) -> Option<IpNetwork> {
+        self.find(ip).map(|i| self.subnets[i].net)
+    }
+
+    /// Counts a new connection to `phantom`, and whether it matched a session.
+    pub fn count_syn(&mut self, phantom: &IpAddr, matched: bool) {
+        if let Some(i) = self.find(phantom) {
+            let s = &mut self.subnets[i];
+            s.syns += 1;
+            if matched {
+                s.matched += 1;
+            }
+        }
+    }
+
+    pub fn count_bytes(&mut self, phantom: &IpAddr, bytes: usize) {
+        if let Some(i) = self.find(phantom) {
+            self.subnets[i].bytes += bytes as u64;
+        }
+    }
+
+    // Sessions, and distinct phantom addresses in use, per subnet.
+    fn count_sessions(&self, tracker: &SessionTracker) -> Vec<(usize, usize)> {
+        let mut sessions = vec![0; self.subnets.len()];
+        let mut phantoms: Vec<HashSet<IpAddr>> = vec![HashSet::new(); self.subnets.len()];
+        tracker.for_each_session(|entry| {
+            if let Some(i) = self.find(&entry.details.phantom_ip) {
+                sessions[i] += 1;
+                phantoms[i].insert(entry.details.phantom_ip);
+            }
+        });
+        sessions.into_iter().zip(phantoms.iter().map(|p| p.len())).collect()
+    }
+
+    /// One line per subnet for the periodic report, resetting the counters.
+    pub fn take_report(&mut self, tracker: &SessionTracker) -> Vec<String> {
+        let sessions = self.count_sessions(tracker);
+        self.subnets.iter_mut().zip(sessions.iter())
+            .map(|(s, n)| {
+                let line = format!("phantom subnet {} sessions {} syns {} matched {} bytes {}",
+                    s.net, n.0, s.syns, s.matched, s.bytes);
+                s.syns = 0;
+                s.matched = 0;
+                s.bytes = 0;
+                line
+            })
+            .collect()
+    }
+
+    /// One line per subnet for the session dump.
+    pub fn dump(&self, tracker: &SessionTracker) -> Vec<String> {
+        let sessions = self.count_sessions(tracker);
+        self.subnets.iter().zip(sessions.iter())
+            .map(|(s, n)| format!("{}: {} sessions on {} phantoms", s.net, n.0, n.1))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use phantom_subnets::*;
+    use sessions::{SessionDetails, SessionTracker};
+    use std::time::Duration;
+
+    #[test]
+    fn test_phantom_subnet_stats() {
+        let nets: Vec<IpNetwork> = ["192.122.190.0/24", "2001:48a8:687f:1::/64",
+                                    "192.122.190.0/28", "192.122.190.0/24"]
+            .iter().map(|s| s.parse().unwrap()).collect();
+        let mut subnets = PhantomSubnets::new(&nets);
+
+        let mut st = SessionTracker::new();
+        for (client, phantom) in [("10.0.0.1", "192.122.190.1"), ("10.0.0.2", "192.122.190.1"),
+                                  ("10.0.0.3", "192.122.190.200"), ("10.0.0.4", "8.8.8.8")].iter() {
+            st.add_session(SessionDetails::new(client, phantom, 443, Duration::from_secs(1)).unwrap());
+        }
+
+        subnets.count_syn(&"192.122.190.1".parse().unwrap(), true);
+        subnets.count_syn(&"192.122.190.2".parse().unwrap(), false);
+        subnets.count_syn(&"2001:48a8:687f:1::5".parse().unwrap(), false);
+        subnets.count_syn(&"8.8.8.8".parse().unwrap(), true);
+        assert_eq!(subnets.subnet_of(&"192.122.190.15".parse().unwrap()), Some("192.122.190.0/28".parse().unwrap()));
+        assert_eq!(subnets.subnet_of(&"192.122.190.16".parse().unwrap()), Some("192.122.190.0/24".parse().unwrap()));
+        assert_eq!(subnets.subnet_of(&"192.122.191.1".parse().unwrap()), None);
+        subnets.count_bytes(&"192.122.190.200".parse().unwrap(), 1500);
+
+        // duplicates removed, most specific subnet first
+        assert_eq!(subnets.dump(&st), vec![
+            "2001:48a8:687f:1::/64: 0 sessions on 0 phantoms",
+            "192.122.190.0/28: 2 sessions on 1 phantoms",
+            "192.122.190.0/24: 1 sessions on 1 phantoms",
+        ]);
+        assert_eq!(subnets.take_report(&st), vec![
+            "phantom subnet 2001:48a8:687f:1::/64 sessions 0 syns 1 matched 0 bytes 0",
+            "phantom subnet 192.122.190.0/28 sessions 2 syns 2 matched 1 bytes 0",
+            "phantom subnet 192.122.190.0/24 sessions 1 syns 0 matched 0 bytes 1500",
+        ]);
+        assert_eq!(subnets.take_report(&st)[1],
+            "phantom subnet 192.122.190.0/28 sessions 2 syns 0 matched 0 bytes 0");
+
+        // a pushed set replaces the subnets, keeping the counts of those in both
+        subnets.count_syn(&"192.122.190.1".parse().unwrap(), true);
+        subnets.count_syn(&"192.122.190.100".parse().unwrap(), true);
+        subnets.replace(&["192.122.190.0/28".parse().unwrap(), "8.8.0.0/16".parse().unwrap()]);
+        assert_eq!(subnets.nets(), vec!["192.122.190.0/28".parse::<IpNetwork>().unwrap(), "8.8.0.0/16".parse().unwrap()]);
+        assert_eq!(subnets.take_report(&st), vec![
+            "phantom subnet 192.122.190.0/28 sessions 2 syns 1 matched 1 bytes 0",
+            "phantom subnet 8.8.0.0/16 sessions 1 syns 0 matched 0 bytes 0",
+        ]);
+    }
+}
@@ -0,0 +1,256 @@
+//
+// Readiness and Liveness Probes
+//
+// For container orchestrators each core can answer HTTP probes on its own
+// port, DETECTOR_PROBE_ADDR's plus the core number (as for the control
+// address):
+//
+//   GET /readyz   200 once the core can serve sessions: the registrations the
+//                 station had stored have been read (see
+//                 SessionTracker::is_primed) and the capture loop has run
+//   GET /livez    200 as long as every watched loop is making progress
+//
+// and 503 otherwise, 404 for anything else. The body lists the checks, one
+// line each as in healthcheck.rs.
+//
+// Liveness is judged by heartbeats. The capture loop beats on every event
+// loop tick, which it runs between bursts whether or not packets arrive, and
+// the redis ingest thread beats on every message and every read timeout
+// (HEARTBEAT_INTERVAL) while idle; its supervisor also fails liveness once
+// restarting it keeps failing (see supervisor.rs). A loop that hasn't beaten for
+// DETECTOR_PROBE_STALL_MS is stalled, and logged once when found to be. A
+// loop that never beat is measured from when the probes started, so slow
+// startups aren't killed.
+//
+// Probes are served one at a time by a thread of their own, and only read
+// shared state, so a stalled core still answers them.
+
+use std::cmp;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+use error::{DetectorError, DetectorResult, error_chain};
+use healthcheck::Check;
+use sessions::SessionTracker;
+use supervisor::Supervisor;
+use timekeeping::{self, MonotonicNs};
+
+/// How often an idle loop beats.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+pub const DEFAULT_STALL_MS: u64 = 10*1000;
+
+// Longest a client may take to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProbeConfig
+{
+    pub addr: SocketAddr,
+    // Time (ns) without a beat after which a loop is stalled.
+    pub stall_ns: u64,
+}
+
+/// Time of a loop's last beat, shared with whoever watches it.
+#[derive(Clone, Default)]
+pub struct Heartbeat
+{
+    // 0 until the first beat.
+    last: Arc<AtomicU64>,
+    // Set once the watchdog has logged the loop as stalled.
+    stalled: Arc<AtomicBool>,
+}
+
+impl Heartbeat
+{
+    pub fn new() -> Heartbeat {
+        Heartbeat::default()
+    }
+
+    pub fn beat(&self, now: MonotonicNs) {
+        // 0 is never, a beat at the very start counts as the next ns
+        self.last.store(cmp::max(now.0, 1), Ordering::Release);
+    }
+
+    pub fn last(&self) -> Option<MonotonicNs> {
+        match self.last.load(Ordering::Acquire) {
+            0 => None,
+            t => Some(MonotonicNs(t)),
+        }
+    }
+}
+
+pub struct Watchdog
+{
+    stall_ns: u64,
+    started: MonotonicNs,
+    beats: Vec<(&'static str, Heartbeat)>,
+    supervisors: Vec<Arc<Supervisor>>,
+}
+
+impl Watchdog
+{
+    pub fn new(stall_ns: u64, started: MonotonicNs) -> Watchdog {
+        Watchdog {
+            stall_ns: stall_ns,
+            started: started,
+            beats: Vec::new(),
+            supervisors: Vec::new(),
+        }
+    }
+
+    /// Watches the loop beating `heartbeat`, named `name` in the checks.
+    pub fn watch(&mut self, name: &'static str, heartbeat: Heartbeat) {
+        self.beats.push((name, heartbeat));
+    }
+
+    /// Also fails liveness if the thread `supervisor` restarts keeps failing.
+    pub fn supervise(&mut self, supervisor: Arc<Supervisor>) {
+        self.supervisors.push(supervisor);
+    }
+
+    /// Whether the loop named `name` has beaten at all.
+    pub fn has_beaten(&self, name: &str) -> bool {
+        self.beats.iter().any(|b| b.0 == name && b.1.last().is_some())
+    }
+
+    /// One check per watched loop, failing if it stalled, and per supervisor,
+    /// failing if its thread keeps failing.
+    pub fn liveness(&self, now: MonotonicNs) -> Vec<Check> {
+        let supervised = self.supervisors.iter()
+            .map(|s| Check { name: s.name(), result: s.status(now) });
+        self.beats.iter().map(|&(name, ref heartbeat)| {
+            let since = heartbeat.last().unwrap_or(self.started);
+            let quiet_ms = now.saturating_since(since) / (1000*1000);
+            let result = if now.saturating_since(since) > self.stall_ns {
+                if !heartbeat.stalled.swap(true, Ordering::AcqRel) {
+                    error!("{} loop stalled, no progress for {}ms", name, quiet_ms);
+                }
+                Err(format!("no progress for {}ms", quiet_ms))
+            } else {
+                if heartbeat.stalled.swap(false, Ordering::AcqRel) {
+                    info!("{} loop making progress again", name);
+                }
+                match heartbeat.last() {
+                    Some(_) => Ok(format!("beat {}ms ago", quiet_ms)),
+                    None => Ok("starting".to_string()),
+                }
+            };
+            Check { name: name, result: result }
+        }).chain(supervised).collect()
+    }
+}
+
+/// Readiness of a core whose sessions are `tracker`'s and whose capture loop
+/// is watched as "capture".
+pub fn readiness(tracker: &SessionTracker, watchdog: &Watchdog) -> Vec<Check> {
+    let primed = match tracker.is_primed() {
+        true => Ok("stored registrations read".to_string()),
+        false => Err("reading stored registrations".to_string()),
+    };
+    let capture = match watchdog.has_beaten("capture") {
+        true => Ok("running".to_string()),
+        false => Err("not started".to_string()),
+    };
+    vec![
+        Check { name: "primed", result: primed },
+        Check { name: "capture", result: capture },
+    ]
+}
+
+/// Listens on `addr`.
+pub fn bind(addr: &SocketAddr) -> DetectorResult<TcpListener> {
+    TcpListener::bind(addr).map_err(|e| DetectorError::Probe { context: "bind", source: e })
+}
+
+// Serves one client at a time for the lifetime of the process.
+pub fn serve(listener: TcpListener, tracker: SessionTracker, watchdog: Watchdog) {
+    for stream in listener.incoming() {
+        let res = stream.and_then(|s| handle_client(s, &tracker, &watchdog));
+        if let Err(e) = res {
+            debug!("{}", error_chain(&DetectorError::Probe { context: "request", source: e }));
+        }
+    }
+}
+
+fn handle_client(stream: TcpStream, tracker: &SessionTracker, watchdog: &Watchdog) -> io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    let (status, body) = respond(&line, tracker, watchdog, timekeeping::now());
+    let mut stream = stream;
+    write!(stream, "HTTP/1.0 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, body.len(), body)?;
+    stream.flush()
+}
+
+/// Status line and body answering the request line `request`.
+pub fn respond(request: &str, tracker: &SessionTracker, watchdog: &Watchdog, now: MonotonicNs) -> (&'static str, String) {
+    let mut words = request.split_whitespace();
+    let checks = match (words.next(), words.next()) {
+        (Some("GET"), Some("/readyz")) => readiness(tracker, watchdog),
+        (Some("GET"), Some("/livez")) => watchdog.liveness(now),
+        _ => return ("404 Not Found", "not found\n".to_string()),
+    };
+    let mut body = String::new();
+    for check in checks.iter() {
+        body.push_str(&format!("{}\n", check));
+    }
+    match checks.iter().all(|c| c.result.is_ok()) {
+        true => ("200 OK", body),
+        false => ("503 Service Unavailable", body),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use probes::*;
+    use std::sync::mpsc;
+    use ingest::ChannelFeed;
+    use sessions::SessionConfig;
+    use timekeeping::Clock;
+
+    const MS: u64 = 1000*1000;
+
+    #[test]
+    fn test_probes() {
+        let config = SessionConfig { prime_key: Some("registrations".to_string()), ..SessionConfig::default() };
+        let tracker = SessionTracker::with_config(config, Clock::Monotonic);
+        let capture = Heartbeat::new();
+        let ingest = Heartbeat::new();
+        let mut watchdog = Watchdog::new(100*MS, MonotonicNs(1000*MS));
+        watchdog.watch("capture", capture.clone());
+        watchdog.watch("ingest", ingest.clone());
+        watchdog.supervise(Arc::new(Supervisor::new("ingest supervisor")));
+
+        // starting up
+        let now = MonotonicNs(1050*MS);
+        assert_eq!(respond("GET /livez HTTP/1.1", &tracker, &watchdog, now),
+            ("200 OK", "ok capture: starting\nok ingest: starting\nok ingest supervisor: 0 restarts\n".to_string()));
+        assert_eq!(respond("GET /readyz HTTP/1.1", &tracker, &watchdog, now),
+            ("503 Service Unavailable",
+             "FAIL primed: reading stored registrations\nFAIL capture: not started\n".to_string()));
+        assert_eq!(respond("GET / HTTP/1.1", &tracker, &watchdog, now).0, "404 Not Found");
+        assert_eq!(respond("POST /livez HTTP/1.1", &tracker, &watchdog, now).0, "404 Not Found");
+
+        // the capture loop runs, ingest never started
+        capture.beat(MonotonicNs(1100*MS));
+        let now = MonotonicNs(1150*MS);
+        assert_eq!(respond("GET /livez HTTP/1.1", &tracker, &watchdog, now),
+            ("503 Service Unavailable", "ok capture: beat 50ms ago\nFAIL ingest: no progress for 150ms\n\
+             ok ingest supervisor: 0 restarts\n".to_string()));
+
+        ingest.beat(now);
+        assert_eq!(respond("GET /livez HTTP/1.1", &tracker, &watchdog, now).0, "200 OK");
+        // ready once primed
+        assert_eq!(respond("GET /readyz HTTP/1.1", &tracker, &watchdog, now).0, "503 Service Unavailable");
+        let (tx, rx) = mpsc::channel::<Vec<u8>>();
+        drop(tx);
+        tracker.spawn_prime_thread(ChannelFeed::new(rx)).join().unwrap();
+        assert_eq!(respond("GET /readyz HTTP/1.1", &tracker, &watchdog, now),
+            ("200 OK", "ok primed: stored registrations read\nok capture: running\n".to_string()));
+    }
+}
@@ -5,7 +5,7 @@ use std::slice;
 use std:: str;
 
 use pnet::packet::Packet;
-use pnet::packet::ethernet::{EthernetPacket, EtherTypes};
+use pnet::packet::ethernet::{EthernetPacket, EtherType, EtherTypes};
 use pnet::packet::ip::IpNextHeaderProtocols;
 use pnet::packet::ipv4::Ipv4Packet;
 use pnet::packet::ipv6::Ipv6Packet;
@@ -14,14 +14,23 @@ use pnet::packet::udp::UdpPacket;
 // use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 use std::u8;
+use std::net::IpAddr;
 //use elligator;
 use flow_tracker::{Flow, FlowNoSrcPort};
 // use dd_selector::DDIpSelector;
 use PerCoreGlobal;
-use util::IpPacket;
+use util::{IpPacket, V6ChainError};
+use fragments::FragmentKey;
+use timekeeping;
+use latency::Stage;
+use transport_detector::Verdict;
+use tls_fingerprint;
+use quic;
+use encap::EncapHeader;
 use elligator;
+use error::{DetectorError, DetectorResult, error_chain};
 use protobuf::{Message};
-use signalling::{C2SWrapper, RegistrationSource};
+use signalling::{C2SWrapper, RegistrationSource, DetectorToStation, DetectorEvent};
 
 
 const TLS_TYPE_APPLICATION_DATA: u8 = 0x17;
@@ -33,38 +42,13 @@ const SPECIAL_UDP_PAYLOAD: &'static [u8] = b"\x38xCKe9ECO5lNwXgd5Q25w0C2qUR7whlt
 
 //const STREAM_TIMEOUT_NS: u64 = 120*1000*1000*1000; // 120 seconds
 
-fn get_ip_packet<'p>(eth_pkt: &'p EthernetPacket) -> Option<IpPacket<'p>>
+// The IP packet in an Ethernet payload of type `ethertype`, once VLAN tags
+// (see vlan.rs) and encapsulation (see decap.rs) are removed.
+fn get_ip_packet<'p>(ethertype: EtherType, payload: &'p [u8]) -> Option<IpPacket<'p>>
 {
-    let payload = eth_pkt.payload();
-
-    fn parse_v4<'a>(p: &[u8]) -> Option<IpPacket> {
-        match Ipv4Packet::new(p) {
-            Some(pkt) => Some(IpPacket::V4(pkt)),
-            None => None
-        }
-    }
-
-    fn parse_v6(p: &[u8]) -> Option<IpPacket> {
-        match Ipv6Packet::new(p) {
-            Some(pkt) => Some(IpPacket::V6(pkt)),
-            None => None
-        }
-    }
-
-    match eth_pkt.get_ethertype() {
-        EtherTypes::Vlan => {
-            if payload[2] == 0x08 && payload[3] == 0x00 {
-                //let vlan_id: u16 = (payload[0] as u16)*256
-                //                 + (payload[1] as u16);
-                parse_v4(&payload[4..])
-            } else if payload[2] == 0x86 && payload[3] == 0xdd {
-                parse_v6(&payload[4..])
-            } else {
-                None
-            }
-        },
-        EtherTypes::Ipv4 => parse_v4(&payload[0..]),
-        EtherTypes::Ipv6 => parse_v6(&payload[0..]),
+    match ethertype {
+        EtherTypes::Ipv4 => Ipv4Packet::new(payload).map(IpPacket::V4),
+        EtherTypes::Ipv6 => Ipv6Packet::new(payload).map(IpPacket::V6),
         _ => None,
     }
 }
@@ -88,19 +72,42 @@ pub extern "C" fn rust_process_packet(ptr: *mut PerCoreGlobal,
     // If this is a GRE, we want to ignore the GRE overhead in our packets
     rust_view_len -= global.gre_offset;
 
+    // A copy of a frame the paired core (or this one) just saw isn't counted
+    if let Some(ref mut dedup) = global.dedup {
+        if dedup.duplicate(&rust_view[global.gre_offset..], timekeeping::now()) {
+            return
+        }
+    }
+
     global.stats.packets_this_period += 1;
     global.stats.bytes_this_period += rust_view_len as u64;
 
+    global.latency.begin_packet();
+    let total = global.latency.start();
+
     let eth_pkt = match EthernetPacket::new(&rust_view[global.gre_offset..]) {
         Some(pkt) => pkt,
         None => return,
     };
 
-    match get_ip_packet(&eth_pkt) {
+    let ip_pkt = match global.vlans.untag(eth_pkt.get_ethertype(), eth_pkt.payload()) {
+        Some((ethertype, payload)) if global.decap.enabled() => {
+            match global.decap.unwrap(ethertype, payload) {
+                Some((ethertype, payload)) => get_ip_packet(ethertype, payload),
+                None => None,
+            }
+        },
+        Some((ethertype, payload)) => get_ip_packet(ethertype, payload),
+        None => None,
+    };
+    global.latency.finish(Stage::Capture, total);
+
+    match ip_pkt {
         Some(IpPacket::V4(pkt)) => global.process_ipv4_packet(pkt, rust_view_len),
         Some(IpPacket::V6(pkt)) => global.process_ipv6_packet(pkt, rust_view_len),
         None => return,
     }
+    global.latency.finish(Stage::Total, total);
 }
 
 fn is_tls_app_pkt(tcp_pkt: &TcpPacket) -> bool
@@ -117,11 +124,20 @@ impl PerCoreGlobal
     {
         self.stats.ipv4_packets_this_period += 1;
 
+        // Later fragments have no transport header to match on
+        if ip_pkt.get_fragment_offset() != 0 {
+            self.later_fragment(&IpPacket::V4(ip_pkt));
+            return;
+        }
+
         // If the packet isn't TCP, first check for a UDP special payload, then return
         if ip_pkt.get_next_level_protocol() != IpNextHeaderProtocols::Tcp {
             let ip = IpPacket::V4(ip_pkt);
             match ip.udp() {
                 Some(pkt) => {
+                    if self.phantom_udp_pkt(&ip, &pkt) {
+                        return;
+                    }
                     // Special payloads are only sent as DNS on port 53
                     if pkt.get_destination() != 53 {
                         return;
@@ -136,34 +152,52 @@ impl PerCoreGlobal
         }
         let ip = IpPacket::V4(ip_pkt);
 
-        {
-            // Check TCP/443
-            let tcp_pkt = match ip.tcp() {
-                Some(pkt) => pkt,
-                None => return,
-            };
-            self.stats.tcp_packets_this_period += 1;
-
-            // Log packets that are -> 443.
-            // libpnet getters all return host order. Ignore the "u16be" in their
-            // docs; interactions with pnet are purely host order.
-            if tcp_pkt.get_destination() == 443 {
-                self.stats.tls_packets_this_period += 1; // (HTTPS, really)
-                self.stats.tls_bytes_this_period += frame_len as u64;
-            }
+        // Check TCP/443
+        let tcp_pkt = match ip.tcp() {
+            Some(pkt) => pkt,
+            None => return,
+        };
+        // A GRO superpacket counts as the segments it was on the wire
+        let segments = self.gro.segments(&ip) as u64;
+        self.stats.packets_this_period += segments - 1;
+        self.stats.tcp_packets_this_period += segments;
+
+        // Log packets that are -> 443.
+        // libpnet getters all return host order. Ignore the "u16be" in their
+        // docs; interactions with pnet are purely host order.
+        if tcp_pkt.get_destination() == 443 {
+            self.stats.tls_packets_this_period += segments; // (HTTPS, really)
+            self.stats.tls_bytes_this_period += frame_len as u64;
         }
-        self.process_pkt(ip);
+        self.process_pkt(&ip, &tcp_pkt);
     }
 
     fn process_ipv6_packet(&mut self, ip_pkt: Ipv6Packet, frame_len: usize)
     {
         self.stats.ipv6_packets_this_period += 1;
 
+        // Find the transport header past any extension headers
+        let ip = IpPacket::V6(ip_pkt);
+        let next_header = match ip.transport() {
+            Ok((next_header, _)) => next_header,
+            Err(V6ChainError::Unparseable) => {
+                self.stats.v6_unparseable_this_period += 1;
+                return;
+            },
+            Err(V6ChainError::LaterFragment) => {
+                self.stats.v6_later_fragments_this_period += 1;
+                self.later_fragment(&ip);
+                return;
+            },
+        };
+
         // If the packet isn't TCP, first check for a UDP special payload, then return
-        if ip_pkt.get_next_header() != IpNextHeaderProtocols::Tcp {
-            let ip = IpPacket::V6(ip_pkt);
+        if next_header != IpNextHeaderProtocols::Tcp {
             match ip.udp() {
                 Some(pkt) => {
+                    if self.phantom_udp_pkt(&ip, &pkt) {
+                        return;
+                    }
                     // Special payloads are only sent as DNS on port 53
                     if pkt.get_destination() != 53 {
                         return;
@@ -176,33 +210,32 @@ impl PerCoreGlobal
             }
             return;
         }
-        let ip = IpPacket::V6(ip_pkt);
 
-        {
-            let tcp_pkt = match ip.tcp() {
-                Some(pkt) => pkt,
-                None => return,
-            };
-            self.stats.tcp_packets_this_period += 1;
+        let tcp_pkt = match ip.tcp() {
+            Some(pkt) => pkt,
+            None => return,
+        };
+        let segments = self.gro.segments(&ip) as u64;
+        self.stats.packets_this_period += segments - 1;
+        self.stats.tcp_packets_this_period += segments;
 
-            if tcp_pkt.get_destination() != 443 {
-                return;
-            }
+        // Registrations are detected on 443, but sessions may be on any port
+        // (an explicit phantom port, or one of the default phantom ports), so
+        // every TCP packet is looked up, as for v4.
+        if tcp_pkt.get_destination() == 443 {
+            self.stats.tls_packets_this_period += segments;
+            self.stats.tls_bytes_this_period += frame_len as u64;
         }
-        self.stats.tls_packets_this_period += 1;
-        self.stats.tls_bytes_this_period += frame_len as u64;
 
         //debug!("v6 -> {} {} bytes", ip_pkt.get_destination(), ip_pkt.get_payload_length());
-        self.process_pkt(ip);
+        self.process_pkt(&ip, &tcp_pkt);
     }
 
-    fn process_pkt(&mut self, ip_pkt: IpPacket){
-        let tcp_pkt = match ip_pkt.tcp() {
-            Some(pkt) => pkt,
-            None => return,
-        };
-
-        let flow = Flow::new(&ip_pkt, &tcp_pkt);
+    // Takes the packet and its TCP header as parsed by the caller, both
+    // borrowed from the capture buffer. Nothing is copied out of them unless
+    // the packet belongs to a tracked flow or session.
+    fn process_pkt(&mut self, ip_pkt: &IpPacket, tcp_pkt: &TcpPacket){
+        let flow = Flow::new(ip_pkt, tcp_pkt);
         let tcp_flags = tcp_pkt.get_flags();
 
         if panic::catch_unwind(||{ tcp_pkt.payload(); }).is_err() {
@@ -210,48 +243,132 @@ impl PerCoreGlobal
         }
 
         let dd_flow = FlowNoSrcPort::from_flow(&flow);
-        if self.flow_tracker.is_phantom_session(&dd_flow) {
+        let span = self.latency.start();
+        let is_phantom_session = self.flow_tracker.is_phantom_flow(&flow);
+        self.latency.finish(Stage::SessionLookup, span);
+        if is_phantom_session {
 
             // Handle packet destined for registered IP
-            match self.filter_station_traffic(flow.src_ip.to_string()) {
+            match self.filter_station_traffic(&flow.src_ip) {
                 // traffic was sent by another station, likely liveness testing.
                 None => {},
 
                 // Non station traffic, forward to application to handle
                 Some(_) => {
-                    if  (tcp_flags & TcpFlags::SYN) != 0  && (tcp_flags & TcpFlags::ACK) == 0 {
-                        debug!("Connection for registered Phantom {}", flow);
+                    let syn = (tcp_flags & TcpFlags::SYN) != 0 && (tcp_flags & TcpFlags::ACK) == 0;
+                    if syn {
+                        // Session at its connection limit, don't forward
+                        let opened = self.flow_tracker.open_phantom_connection(&flow);
+                        if let Some(ref mut subnets) = self.phantom_subnets {
+                            subnets.count_syn(&flow.dst_ip, opened);
+                        }
+                        if !opened {
+                            self.stats.conn_limit_rejects_this_period += 1;
+                            debug!("Connection limit reached for registered Phantom {}", flow);
+                            self.unmatched_pkt(&flow, ip_pkt, tcp_pkt);
+                            return;
+                        }
+                        self.phantom_connection(&flow, &dd_flow);
+                    } else if self.flow_tracker.is_asymmetric() && (tcp_flags & TcpFlags::RST) == 0
+                        && !self.flow_tracker.is_phantom_connection(&flow) {
+                        // The SYN wasn't captured, the connection opens here
+                        if !self.flow_tracker.open_phantom_midstream(&flow, tcp_pkt.payload().len()) {
+                            self.stats.conn_limit_rejects_this_period += 1;
+                            debug!("Connection limit reached for registered Phantom {}", flow);
+                            self.unmatched_pkt(&flow, ip_pkt, tcp_pkt);
+                            return;
+                        }
+                        if self.flow_tracker.is_phantom_connection(&flow) {
+                            self.phantom_connection(&flow, &dd_flow);
+                        }
+                    }
+                    // Later packets of a connection that was refused are
+                    // neither forwarded nor extend the session. Those of one
+                    // that isn't open otherwise (evicted, idle, or opened
+                    // before the registration) are still forwarded.
+                    if self.flow_tracker.is_refused_phantom_connection(&flow) {
+                        self.unmatched_pkt(&flow, ip_pkt, tcp_pkt);
+                        return;
+                    }
+
+                    // Packets outside the client's sequence window are still
+                    // forwarded, but don't count as the connection's activity
+                    let in_window = self.flow_tracker.check_phantom_seq(&flow, tcp_pkt);
+                    if in_window && !syn && (tcp_flags & TcpFlags::RST) == 0 {
+                        // A reset (a scanner answering the SYN-ACK) doesn't
+                        // carry the connection past its SYN
+                        self.flow_tracker.touch_phantom_connection(&flow);
+                    }
+
+                    // Connections a transport detector rejected aren't forwarded
+                    let payload = if in_window { tcp_pkt.payload() } else { &[] };
+                    if self.inspect_phantom_payload(&flow, &dd_flow, payload, Some(tcp_pkt.get_sequence())) {
+                        if in_window {
+                            // Update expire time if necessary
+                            self.flow_tracker.update_phantom_flow(&flow);
+                            self.flow_tracker.count_phantom_packet(&flow, ip_pkt.packet().len());
+                        }
+
+                        // Forward packet...
+                        self.forward_pkt(ip_pkt, Some(&dd_flow));
+                        self.remember_fragment(ip_pkt, &dd_flow);
+                        if in_window {
+                            if let Some(ref mut qa) = self.qa {
+                                qa.packet(&flow, tcp_flags, tcp_pkt.payload().len(), ip_pkt.packet().len(), timekeeping::now());
+                            }
+                        }
+                    }
+
+                    if in_window && (tcp_flags & TcpFlags::RST) != 0 {
+                        self.flow_tracker.reset_phantom_connection(&flow);
+                    } else if in_window && (tcp_flags & TcpFlags::FIN) != 0 {
+                        self.flow_tracker.fin_phantom_connection(&flow);
                     }
-                
-                    // Update expire time if necessary
-                    self.flow_tracker.update_phantom_flow(&dd_flow);
-    
-                    // Forward packet...
-                    self.forward_pkt(&ip_pkt);
-                    // TODO: if it was RST or FIN, close things
                     return;
                 }
             }
         }
         
+        // A phantom's SYN-ACK, whose ISN the client has to acknowledge for
+        // its packets to count (see check_phantom_seq)
+        if (tcp_flags & TcpFlags::SYN) != 0 && (tcp_flags & TcpFlags::ACK) != 0 {
+            self.flow_tracker.note_phantom_syn_ack(&flow, tcp_pkt.get_sequence());
+        }
+
+        // Unmatched new connection, counted if it was to watched phantom space
+        if (tcp_flags & TcpFlags::SYN) != 0 && (tcp_flags & TcpFlags::ACK) == 0 {
+            if let Some(ref mut scan) = self.dark_scan {
+                scan.observe(&flow.src_ip, &flow.dst_ip, flow.dst_port);
+            }
+        }
+        self.unmatched_pkt(&flow, ip_pkt, tcp_pkt);
+
         if tcp_pkt.get_destination() == 443 {
             if (tcp_flags & TcpFlags::SYN) != 0 && (tcp_flags & TcpFlags::ACK) == 0
             {
                 self.stats.port_443_syns_this_period += 1;
+                if let Some(ref mut subnets) = self.phantom_subnets {
+                    subnets.count_syn(&flow.dst_ip, false);
+                }
 
-                self.flow_tracker.begin_tracking_flow(&flow);
+                let span = self.latency.start();
+                self.flow_tracker.begin_tracking_flow(&flow, tcp_pkt.get_sequence());
+                self.latency.finish(Stage::FlowLookup, span);
                 return;
             } else if (tcp_flags & TcpFlags::RST) != 0 || (tcp_flags & TcpFlags::FIN) != 0 {
                 self.flow_tracker.stop_tracking_flow(&flow);
                 return;
             }
 
-            if !self.flow_tracker.is_tracked_flow(&flow) {
+            let span = self.latency.start();
+            let tracked = self.flow_tracker.track_progress(&flow, tcp_pkt.get_sequence());
+            self.latency.finish(Stage::FlowLookup, span);
+            if !tracked {
                 return;
             }
 
-            if  is_tls_app_pkt(&tcp_pkt) {
-                match self.check_dark_decoy_tag(&flow, &tcp_pkt) {
+            if  is_tls_app_pkt(tcp_pkt) {
+                match self.check_dark_decoy_tag(&flow, tcp_pkt) {
                     true => {
                         // debug!("New Conjure registration detected in {},", flow);
                         // self.flow_tracker.mark_dark_decoy(&dd_flow);
@@ -262,108 +379,279 @@ impl PerCoreGlobal
                 };
                 self.flow_tracker.stop_tracking_flow(&flow);
             }else {
-                self.check_connect_test_str(&flow, &tcp_pkt);
+                self.check_connect_test_str(&flow, tcp_pkt);
             }
         }
     }
 
-    // Takes an IPv4 packet
-    // Assumes (for now) that TLS records are in a single TCP packet
-    // (no fragmentation).
-    // Fragments could be stored in the flow_tracker if needed.
-    pub fn process_tls_pkt(&mut self,
-                           ip_pkt: IpPacket)
+    // Applies the unmatched action, if any, to a packet that didn't match a
+    // session, or that a session turned away. Only packets to the configured
+    // phantom space that don't come from another station are handled, and
+    // nothing is sent in shadow mode.
+    fn unmatched_pkt(&mut self, flow: &Flow, ip_pkt: &IpPacket, tcp_pkt: &TcpPacket)
     {
-        let tcp_pkt = match ip_pkt.tcp() {
-            Some(pkt) => pkt,
-            None => return,
-        };
-
-        let flow = Flow::new(&ip_pkt, &tcp_pkt);
-        let tcp_flags = tcp_pkt.get_flags();
-
-        if panic::catch_unwind(||{ tcp_pkt.payload(); }).is_err() {
+        if self.shadow_mode {
+            return;
+        }
+        match self.unmatched {
+            Some(ref u) if u.covers(&flow.dst_ip) => {},
+            _ => return,
+        }
+        if self.filter_station_traffic(&flow.src_ip).is_none() {
             return;
         }
+        if let Some(ref mut unmatched) = self.unmatched {
+            unmatched.handle(ip_pkt, tcp_pkt, timekeeping::now());
+        }
+    }
 
+    // Forwards a UDP packet to a registered phantom, if UDP is forwarded at
+    // all. Returns false if the packet isn't for a registered phantom.
+    fn phantom_udp_pkt(&mut self, ip_pkt: &IpPacket, udp_pkt: &UdpPacket) -> bool
+    {
+        if !self.phantom_udp {
+            return false;
+        }
+        let flow = Flow::new_udp(ip_pkt, udp_pkt);
         let dd_flow = FlowNoSrcPort::from_flow(&flow);
-        if self.flow_tracker.is_phantom_session(&dd_flow) {
+        let span = self.latency.start();
+        let is_phantom_session = self.flow_tracker.is_phantom_flow(&flow);
+        self.latency.finish(Stage::SessionLookup, span);
+        if !is_phantom_session {
+            // a phantom answering its client confirms the client's flow
+            self.flow_tracker.note_phantom_udp_reply(&flow);
+            return false;
+        }
 
-            // Handle packet destined for registered IP
-            match self.filter_station_traffic(flow.src_ip.to_string()) {
-                // traffic was sent by another station, likely liveness testing.
-                None => {},
+        // traffic sent by another station isn't forwarded
+        if self.filter_station_traffic(&flow.src_ip).is_none() {
+            return true;
+        }
+        let is_new = !self.flow_tracker.is_phantom_connection(&flow);
+        if !self.flow_tracker.open_phantom_udp_flow(&flow) {
+            self.stats.conn_limit_rejects_this_period += 1;
+            debug!("Connection limit reached for registered Phantom {}", flow);
+            return true;
+        }
+        if is_new {
+            self.phantom_connection(&flow, &dd_flow);
+        }
+        if !self.inspect_phantom_payload(&flow, &dd_flow, udp_pkt.payload(), None) {
+            return true;
+        }
+        // as does a QUIC Initial, where the replies go uncaptured
+        if !self.flow_tracker.is_confirmed_phantom_udp_flow(&flow) && quic::parse_initial(udp_pkt.payload()).is_some() {
+            self.flow_tracker.confirm_phantom_udp_flow(&flow);
+        }
+        self.flow_tracker.update_phantom_flow(&flow);
+        self.flow_tracker.count_phantom_packet(&flow, ip_pkt.packet().len());
+        self.forward_pkt(ip_pkt, Some(&dd_flow));
+        self.remember_fragment(ip_pkt, &dd_flow);
+        true
+    }
 
-                // Non station traffic, forward to application to handle
-                Some(_) => {
-                    if  (tcp_flags & TcpFlags::SYN) != 0  && (tcp_flags & TcpFlags::ACK) == 0 {
-                        debug!("Connection for registered Phantom {}", flow);
-                    }
-                
-                    // Update expire time if necessary
-                    self.flow_tracker.update_phantom_flow(&dd_flow);
-    
-                    // Forward packet...
-                    self.forward_pkt(&ip_pkt);
-                    // TODO: if it was RST or FIN, close things
-                    return;
+    // Shows the first payload of a phantom connection (and while they need
+    // more, its next segments) to the transport detectors and, for TCP (with
+    // the segment's `seq`), fingerprints the ClientHello it may carry, or for
+    // UDP parses the QUIC Initial it may carry. False if the detectors
+    // rejected the connection, now or with an earlier packet, in which case
+    // it isn't forwarded.
+    fn inspect_phantom_payload(&mut self, flow: &Flow, dd_flow: &FlowNoSrcPort, payload: &[u8], seq: Option<u32>) -> bool
+    {
+        if self.transport_detectors.is_empty() && !self.tls_fingerprints && !self.quic_initials {
+            return true;
+        }
+        if payload.is_empty() {
+            return !self.flow_tracker.is_rejected_phantom_connection(flow);
+        }
+        let first = self.flow_tracker.first_phantom_payload(flow);
+        let tls = seq.is_some();
+        if first && tls && self.tls_fingerprints {
+            if let Some(fingerprint) = tls_fingerprint::fingerprint(payload) {
+                debug!("ClientHello {} for registered Phantom {}", fingerprint, flow);
+                self.flow_tracker.set_phantom_fingerprint(flow, fingerprint);
+                if let Some(ref mut qa) = self.qa {
+                    qa.tls_fingerprint(flow, &fingerprint);
+                }
+            }
+        }
+        if first && !tls && self.quic_initials {
+            match quic::parse_initial(payload) {
+                Some(initial) => {
+                    debug!("QUIC Initial version {:08x} DCID {} for registered Phantom {}",
+                        initial.version, initial.dcid_hash, flow);
+                    self.stats.quic_initials_this_period += 1;
+                    self.flow_tracker.set_phantom_dcid_hash(flow, initial.dcid_hash);
                 }
+                None => self.stats.quic_other_this_period += 1,
+            }
+        }
+        if self.transport_detectors.is_empty() {
+            return true;
+        }
+        let next_seq = seq.map(|s| s.wrapping_add(payload.len() as u32));
+        let payload = match self.flow_tracker.phantom_payload_to_inspect(flow, first, seq, payload) {
+            Some(p) => p,
+            None => return !self.flow_tracker.is_rejected_phantom_connection(flow),
+        };
+        let entry = match self.flow_tracker.get_phantom_session(dd_flow) {
+            Some(e) => e,
+            None => return true,
+        };
+        let mut verdict = self.transport_detectors.inspect(&entry, flow, &payload);
+        if verdict == Verdict::NeedMore {
+            if self.flow_tracker.hold_phantom_payload(flow, payload, next_seq) {
+                return true;
             }
+            // past its first flight without enough to tell
+            debug!("Rejecting registered Phantom {} short of a verdict after its first flight", flow);
+            verdict = Verdict::Reject;
+        }
+        if verdict != Verdict::Pass {
+            self.flow_tracker.apply_phantom_verdict(flow, verdict);
         }
+        verdict != Verdict::Reject
+    }
 
-        if (tcp_flags & TcpFlags::SYN) != 0 && (tcp_flags & TcpFlags::ACK) == 0
-        {
-            self.stats.port_443_syns_this_period += 1;
+    // Logs the decision to forward a new connection to a registered phantom,
+    // tagged with the id of the session it matched, and tells the application
+    // which session it belongs to.
+    fn phantom_connection(&mut self, flow: &Flow, dd_flow: &FlowNoSrcPort)
+    {
+        let entry = match self.flow_tracker.get_phantom_session(dd_flow) {
+            Some(e) => e,
+            // expired between the lookup and now, forwarding anyway
+            None => {
+                debug!("Connection for registered Phantom {} [expired]", flow);
+                return
+            },
+        };
 
-            self.flow_tracker.begin_tracking_flow(&flow);
-            return;
-        } else if (tcp_flags & TcpFlags::RST) != 0 || (tcp_flags & TcpFlags::FIN) != 0 {
-            self.flow_tracker.stop_tracking_flow(&flow);
-            return;
+        if self.shadow_mode {
+            info!("shadow: would forward connection for registered Phantom {} [{:016x}]", flow, entry.session_id);
+            return
+        }
+        debug!("Connection for registered Phantom {} [{:016x}]", flow, entry.session_id);
+        if let Some(ref mut qa) = self.qa {
+            qa.connection_opened(flow, &entry, timekeeping::now());
         }
 
-        if !self.flow_tracker.is_tracked_flow(&flow) {
-            return;
+        // The station hears about the session's first connection only
+        let entry = match self.flow_tracker.latch_phantom_match(dd_flow) {
+            Some(e) => e,
+            None => return,
+        };
+        self.stats.sessions_matched_this_period += 1;
+        let mut event = DetectorToStation::new();
+        event.set_event(DetectorEvent::SessionMatched);
+        event.set_session_id(entry.session_id);
+        event.set_phantom_ip(flow.dst_ip.to_string());
+        event.set_phantom_port(flow.dst_port as u32);
+        event.set_client_ip(flow.src_ip.to_string());
+        event.set_client_port(flow.src_port as u32);
+        event.set_registration_source(entry.details.registration_source);
+        event.set_affinity(entry.affinity);
+        if let Some(group) = entry.details.group {
+            event.set_group_id(group);
         }
+        self.events.publish(&event);
+    }
 
-        if  is_tls_app_pkt(&tcp_pkt) {
-            match self.check_dark_decoy_tag(&flow, &tcp_pkt) {
-                true => {
-                    // debug!("New Conjure registration detected in {},", flow);
-                    // self.flow_tracker.mark_dark_decoy(&dd_flow);
-                    // not removing flow from stale_tracked_flows for optimization reasons:
-                    // it will be removed later
-                },
-                false => {}
-            };
-            self.flow_tracker.stop_tracking_flow(&flow);
-        }else {
-            self.check_connect_test_str(&flow, &tcp_pkt);
+    // Remembers a first fragment forwarded for the session `dd_flow` belongs
+    // to, so the rest of the packet follows it.
+    fn remember_fragment(&mut self, ip_pkt: &IpPacket, dd_flow: &FlowNoSrcPort)
+    {
+        if let Some(ref mut cache) = self.fragments {
+            if let Some(frag) = ip_pkt.fragment() {
+                let (src, dst) = ip_pkt.addresses();
+                cache.remember(FragmentKey::new(src, dst, &frag), *dd_flow, timekeeping::now());
+            }
         }
     }
 
-    fn forward_pkt(&mut self, ip_pkt: &IpPacket)
+    // Forwards a fragment after the first like the first if it was forwarded
+    // for a matched session. Otherwise it is dropped.
+    fn later_fragment(&mut self, ip_pkt: &IpPacket)
     {
-        let data = match ip_pkt {
-            IpPacket::V4(p) => p.packet(),
-            IpPacket::V6(p) => p.packet(),
+        let frag = match ip_pkt.fragment() {
+            Some(f) => f,
+            None => return,
+        };
+        let (src, dst) = ip_pkt.addresses();
+        let session = match self.fragments {
+            Some(ref mut cache) => cache.lookup(&FragmentKey::new(src, dst, &frag), timekeeping::now()),
+            None => None,
         };
+        if let Some(dd_flow) = session {
+            self.forward_pkt(ip_pkt, Some(&dd_flow));
+        }
+    }
 
-        let mut tun_pkt = Vec::with_capacity(data.len()+4);
-        // These mystery bytes are a link-layer header; the kernel "receives"
-        // tun packets as if they were really physically "received". Since they
-        // weren't physically received, they do not have an Ethernet header. It
-        // looks like the tun setup has its own type of header, rather than just
-        // making up a fake Ethernet header.
-        let raw_hdr = match ip_pkt {
-            IpPacket::V4(_p) => [0x00, 0x01, 0x08, 0x00],
-            IpPacket::V6(_p) => [0x00, 0x01, 0x86, 0xdd],
+    // Forwards a packet of the session `dd_flow` belongs to (if known) into
+    // the tun device its route picks, or onto the proxy ring in place of
+    // tun<core>.
+    fn forward_pkt(&mut self, ip_pkt: &IpPacket, dd_flow: Option<&FlowNoSrcPort>)
+    {
+        if self.shadow_mode {
+            self.stats.shadow_forwards_this_period += 1;
+            return;
+        }
+
+        let phantom = match ip_pkt {
+            IpPacket::V4(p) => IpAddr::V4(p.get_destination()),
+            IpPacket::V6(p) => IpAddr::V6(p.get_destination()),
+        };
+        if let Some(ref mut subnets) = self.phantom_subnets {
+            subnets.count_bytes(&phantom, ip_pkt.packet().len());
+        }
+        let route = match self.tun_routes {
+            Some(ref router) => {
+                let transport = match dd_flow {
+                    Some(f) if router.needs_transport() =>
+                        self.flow_tracker.get_phantom_session(f).map(|e| e.details.transport),
+                    _ => None,
+                };
+                router.route(&phantom, transport)
+            },
+            None => None,
+        };
+        if let Some(ref mut marks) = self.fwmarks {
+            marks.count(route, ip_pkt.packet().len());
+        }
+        // Named in encapsulated frames and on the proxy ring
+        let header = match dd_flow {
+            Some(f) if cfg!(feature = "encap-header") || self.proxy_ring.is_some() =>
+                self.flow_tracker.get_phantom_session(f).map_or(EncapHeader::no_session(), |e| EncapHeader::for_session(&e)),
+            _ => EncapHeader::no_session(),
         };
-        tun_pkt.extend_from_slice(&raw_hdr);
-        tun_pkt.extend_from_slice(data);
 
-        self.tun.send(tun_pkt).unwrap_or_else(|e|{
-            warn!("failed to send packet into tun: {}", e); 0});
+        let span = self.latency.start();
+        // Superpackets are split to fit the tun MTU
+        let frames = match self.gro.split(ip_pkt) {
+            Some(frames) => frames,
+            None => vec![ip_pkt.tun_frame()],
+        };
+        for frame in frames {
+            // the proxy ring takes what would go into tun<core>, the IP
+            // packet past the tun frame's packet information
+            match self.proxy_ring {
+                Some(ref mut ring) if route.is_none() => {
+                    ring.send(&header, frame.get(4..).unwrap_or(&[]));
+                    continue;
+                },
+                _ => {},
+            }
+            #[cfg(feature = "encap-header")]
+            let frame = header.wrap(&frame);
+            let res = match self.tun_routes {
+                Some(ref mut router) => router.send(route, &mut self.tun, frame),
+                None => self.tun.send(frame),
+            };
+            if let Err(e) = res {
+                warn!("{}", e);
+            }
+        }
+        self.latency.finish(Stage::Forward, span);
 
     }
 
@@ -394,18 +682,22 @@ impl PerCoreGlobal
                 let repr_str = hex::encode(res.0);
                 debug!("New registration {}, {}", flow, repr_str);
 
-                let zmq_payload = match zmq_msg.write_to_bytes() {
-                    Ok(b) => b,
-                    Err(e) => {
-                        warn!("Failed to generate ZMQ payload: {}", e);
-                        return false
-                    },
-                };
+                if self.shadow_mode {
+                    info!("shadow: would register {}, {}", flow, repr_str);
+                    self.stats.shadow_registrations_this_period += 1;
+                    return true;
+                }
 
-                match self.zmq_sock.send(&zmq_payload, 0){
-                    Ok(_)=> return true,
+                if self.flow_tracker.phantom_flows.is_draining() {
+                    debug!("draining, not registering {}, {}", flow, repr_str);
+                    self.flow_tracker.phantom_flows.count_drained();
+                    return true;
+                }
+
+                match self.send_registration(&zmq_msg) {
+                    Ok(_) => return true,
                     Err(e) => {
-                        warn!("Failed to send registration information over ZMQ: {}", e);
+                        warn!("{}", error_chain(&e));
                         return false
                     },
                 }
@@ -416,6 +708,14 @@ impl PerCoreGlobal
         }
     }
 
+    // Passes a registration found in a packet on to the application.
+    fn send_registration(&self, msg: &C2SWrapper) -> DetectorResult<()> {
+        let payload = msg.write_to_bytes()
+            .map_err(|e| DetectorError::Serialize { message: "C2SWrapper", source: e })?;
+        self.zmq_sock.send(&payload, 0)?;
+        Ok(())
+    }
+
     fn check_connect_test_str(&mut self, flow: &Flow, tcp_pkt: &TcpPacket) {
         match str::from_utf8(tcp_pkt.payload()) {
             Ok(payload) => {
@@ -446,19 +746,19 @@ impl PerCoreGlobal
     /// # Examples
     ///
     /// ```compile_fail
-    /// let flow_src_station = String::from("192.122.200.231");
-    /// let flow_src_client = String::from("128.138.89.172");
+    /// let flow_src_station: IpAddr = "192.122.200.231".parse().unwrap();
+    /// let flow_src_client: IpAddr = "128.138.89.172".parse().unwrap();
     /// 
-    /// let station = filter_station_traffic(flow_src_station);
-    /// let client = filter_station_traffic(flow_src_client);
+    /// let station = filter_station_traffic(&flow_src_station);
+    /// let client = filter_station_traffic(&flow_src_client);
     ///
     /// assert_eq!(None, station);
     /// assert_eq!(Some(()), client);
     /// ```
-    fn filter_station_traffic(&mut self, src: String) -> Option<()> {
+    fn filter_station_traffic(&mut self, src: &IpAddr) -> Option<()> {
 
         for addr in self.filter_list.iter() {
-            if src == *addr {
+            if src == addr {
                 return None
             }
         }
@@ -0,0 +1,620 @@
+//
+// Shared Memory Ring to the Proxy
+//
+// Every forwarded packet written into tun<core> costs a write syscall. With
+// DETECTOR_PROXY_RING_SOCKET set, each core instead offers the application's
+// proxy a ring in shared memory at startup, and hands it the packets it would
+// have written into tun<core> (those the tun routes send elsewhere still go
+// to their devices), each with the header of encap.rs naming its session.
+//
+// Negotiation, over a unix stream socket the proxy listens on:
+//
+// - The core creates the ring (a memfd) and an eventfd, connects, and sends
+//   a 16 byte offer with both descriptors attached (SCM_RIGHTS): MAGIC, the
+//   ring version, reserved, the core (u16) and the ring's data bytes (u64),
+//   integers big endian.
+// - The proxy maps the ring and answers ACCEPT, or anything else to decline.
+// - Until the proxy answers, it declines, or the exchange fails or takes
+//   longer than NEGOTIATE_TIMEOUT, the core keeps forwarding into tun<core>.
+//   It goes back to tun<core> for good if the proxy later closes the socket.
+//
+// The ring has one producer, the core, and one consumer. Its memory starts
+// with a header of HEADER_BYTES, integers in native byte order:
+//
+//   0       MAGIC (u32)
+//   4       version (u32)
+//   8       data bytes (u64), a multiple of 8
+//   HEAD    bytes produced (u64), written by the core
+//   TAIL    bytes consumed (u64), written by the proxy
+//   WAITING set (u32) by the proxy before it sleeps on the eventfd
+//
+// followed by the data, where records are written at HEAD modulo the data
+// bytes, 8 byte aligned: the length (u32) of what follows the record header,
+// its kind (u32), then for KIND_PACKET an encap.rs header and the IP packet.
+// A record that doesn't fit before the end of the data is preceded by a
+// KIND_PADDING record to the end. A packet that doesn't fit in the ring's free
+// space is dropped, as tun_queue.rs drops frames over its queue.
+//
+// The core only rings the eventfd (a syscall) when the proxy set WAITING, so
+// a proxy that keeps up is never woken. TAIL is the proxy's to write, so the
+// core checks it before relying on it: one past HEAD, or more than the data
+// bytes behind it, means the proxy is broken, and the core stops using the
+// ring and goes back to tun<core>, as if the proxy had closed the socket. RingReader and accept_offer are the
+// proxy's end, public for it to reuse.
+//
+// Reported per period: packets sent and dropped, wakeups, and how full the
+// ring is.
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::mem;
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::os::unix::net::UnixStream;
+use std::ptr;
+use std::slice;
+use std::sync::atomic::{fence, AtomicU32, AtomicU64, Ordering};
+use std::time::Duration;
+
+use libc;
+
+use encap::{self, EncapHeader};
+use error::{DetectorError, DetectorResult};
+
+/// "CJRG", at the start of the offer and of the ring.
+pub const MAGIC: u32 = 0x434a_5247;
+pub const VERSION: u8 = 1;
+/// The proxy's answer to an offer it takes.
+pub const ACCEPT: u8 = 1;
+pub const DECLINE: u8 = 0;
+const OFFER_LEN: usize = 16;
+pub const NEGOTIATE_TIMEOUT: Duration = Duration::from_secs(1);
+
+// Offsets in the ring's header, those both ends write a cache line apart.
+const HEAD: usize = 64;
+const TAIL: usize = 128;
+const WAITING: usize = 192;
+pub const HEADER_BYTES: usize = 256;
+
+const RECORD_HEADER: usize = 8;
+pub const KIND_PACKET: u32 = 1;
+pub const KIND_PADDING: u32 = 2;
+
+pub const DEFAULT_RING_BYTES: usize = 4 << 20;
+pub const MIN_RING_BYTES: usize = 64 << 10;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProxyRingConfig
+{
+    // Unix socket the proxy listens on for offers.
+    pub socket: String,
+    // Data bytes of each core's ring.
+    pub bytes: usize,
+}
+
+fn align8(n: usize) -> usize {
+    (n + 7) & !7
+}
+
+// A shared mapping of a ring, unmapped when dropped.
+struct Mapping
+{
+    ptr: *mut u8,
+    len: usize,
+}
+
+impl Mapping
+{
+    fn map(file: &File, len: usize) -> io::Result<Mapping> {
+        let ptr = unsafe {
+            libc::mmap(ptr::null_mut(), len, libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED, file.as_raw_fd(), 0)
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error())
+        }
+        Ok(Mapping { ptr: ptr as *mut u8, len: len })
+    }
+
+    fn u64_at(&self, offset: usize) -> &AtomicU64 {
+        unsafe { &*(self.ptr.add(offset) as *const AtomicU64) }
+    }
+
+    fn u32_at(&self, offset: usize) -> &AtomicU32 {
+        unsafe { &*(self.ptr.add(offset) as *const AtomicU32) }
+    }
+}
+
+// The mapping is owned, and the memory behind it only reached through
+// atomics or by the one end writing it.
+unsafe impl Send for Mapping {}
+
+impl Drop for Mapping
+{
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.ptr as *mut libc::c_void, self.len) };
+    }
+}
+
+fn eventfd() -> io::Result<File> {
+    let fd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error())
+    }
+    Ok(unsafe { File::from_raw_fd(fd) })
+}
+
+/// The core's end of a ring.
+pub struct RingWriter
+{
+    map: Mapping,
+    size: u64,
+    head: u64,
+    doorbell: File,
+    // The consumer wrote a TAIL that can't be right.
+    broken: bool,
+}
+
+impl RingWriter
+{
+    /// A new ring of `bytes` data bytes (rounded up to a multiple of 8), and
+    /// the memfd holding it for the consumer to map.
+    pub fn create(bytes: usize) -> io::Result<(RingWriter, File)> {
+        let size = align8(bytes.max(RECORD_HEADER));
+        let fd = unsafe { libc::memfd_create(b"conjure-proxy-ring\0".as_ptr() as *const libc::c_char, libc::MFD_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error())
+        }
+        let memfd = unsafe { File::from_raw_fd(fd) };
+        memfd.set_len((HEADER_BYTES + size) as u64)?;
+        let map = Mapping::map(&memfd, HEADER_BYTES + size)?;
+        map.u32_at(0).store(MAGIC, Ordering::Relaxed);
+        map.u32_at(4).store(VERSION as u32, Ordering::Relaxed);
+        map.u64_at(8).store(size as u64, Ordering::Release);
+        let writer = RingWriter { map: map, size: size as u64, head: 0, doorbell: eventfd()?, broken: false };
+        Ok((writer, memfd))
+    }
+
+    /// The eventfd the consumer sleeps on.
+    pub fn doorbell(&self) -> &File {
+        &self.doorbell
+    }
+
+    fn write_record(&mut self, offset: usize, len: usize, kind: u32, parts: &[&[u8]]) {
+        unsafe {
+            let record = self.map.ptr.add(HEADER_BYTES + offset);
+            ptr::write(record as *mut u32, len as u32);
+            ptr::write(record.add(4) as *mut u32, kind);
+            let mut at = record.add(RECORD_HEADER);
+            for part in parts {
+                ptr::copy_nonoverlapping(part.as_ptr(), at, part.len());
+                at = at.add(part.len());
+            }
+        }
+    }
+
+    // Bytes consumed, None if the consumer wrote more than was produced or
+    // less than the data bytes behind it.
+    fn tail(&self) -> Option<u64> {
+        let tail = self.map.u64_at(TAIL).load(Ordering::Acquire);
+        if tail > self.head || self.head - tail > self.size {
+            return None
+        }
+        Some(tail)
+    }
+
+    /// Appends `packet` with its session `header`. False, writing nothing, if
+    /// the ring hasn't room for it or is broken.
+    pub fn push(&mut self, header: &EncapHeader, packet: &[u8]) -> bool {
+        if self.broken {
+            return false
+        }
+        let tail = match self.tail() {
+            Some(t) => t,
+            None => {
+                self.broken = true;
+                return false
+            },
+        };
+        let len = encap::HEADER_LEN + packet.len();
+        let need = (RECORD_HEADER + align8(len)) as u64;
+        let offset = self.head % self.size;
+        let pad = if need > self.size - offset { self.size - offset } else { 0 };
+        if self.head + pad + need - tail > self.size {
+            return false
+        }
+        if pad > 0 {
+            self.write_record(offset as usize, pad as usize - RECORD_HEADER, KIND_PADDING, &[]);
+            self.head += pad;
+        }
+        let mut encoded = Vec::with_capacity(encap::HEADER_LEN);
+        header.encode(&mut encoded);
+        let offset = (self.head % self.size) as usize;
+        self.write_record(offset, len, KIND_PACKET, &[&encoded, packet]);
+        self.head += need;
+        self.map.u64_at(HEAD).store(self.head, Ordering::Release);
+        true
+    }
+
+    /// Wakes the consumer if it is waiting for records. Whether it was.
+    pub fn ring(&mut self) -> bool {
+        fence(Ordering::SeqCst);
+        if self.map.u32_at(WAITING).swap(0, Ordering::SeqCst) == 0 {
+            return false
+        }
+        let _ = self.doorbell.write(&1u64.to_ne_bytes());
+        true
+    }
+
+    /// Bytes written and not yet consumed, none once broken.
+    pub fn used(&self) -> u64 {
+        match self.tail() {
+            Some(tail) if !self.broken => self.head - tail,
+            _ => 0,
+        }
+    }
+
+    /// Whether the consumer wrote a TAIL past HEAD, or more than the ring
+    /// behind it, so the ring can't be used any more.
+    pub fn is_broken(&self) -> bool {
+        self.broken
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+/// The proxy's end of a ring.
+pub struct RingReader
+{
+    map: Mapping,
+    size: u64,
+    tail: u64,
+    doorbell: File,
+}
+
+impl RingReader
+{
+    /// Maps the ring in `memfd`, woken through `doorbell`.
+    pub fn attach(memfd: File, doorbell: File) -> io::Result<RingReader> {
+        let len = memfd.metadata()?.len() as usize;
+        let invalid = |what| io::Error::new(io::ErrorKind::InvalidData, what);
+        if len < HEADER_BYTES {
+            return Err(invalid("ring too short"))
+        }
+        let map = Mapping::map(&memfd, len)?;
+        if map.u32_at(0).load(Ordering::Relaxed) != MAGIC || map.u32_at(4).load(Ordering::Relaxed) != VERSION as u32 {
+            return Err(invalid("not a version 1 ring"))
+        }
+        let size = map.u64_at(8).load(Ordering::Acquire);
+        if size % 8 != 0 || HEADER_BYTES as u64 + size != len as u64 {
+            return Err(invalid("bad ring size"))
+        }
+        let tail = map.u64_at(TAIL).load(Ordering::Acquire);
+        Ok(RingReader { map: map, size: size, tail: tail, doorbell: doorbell })
+    }
+
+    /// The next packet and its session header, if any was written. Records
+    /// whose header doesn't decode are skipped.
+    pub fn pop(&mut self) -> Option<(EncapHeader, Vec<u8>)> {
+        loop {
+            if self.tail == self.map.u64_at(HEAD).load(Ordering::Acquire) {
+                return None
+            }
+            let offset = (self.tail % self.size) as usize;
+            let (len, kind, body) = unsafe {
+                let record = self.map.ptr.add(HEADER_BYTES + offset);
+                let len = ptr::read(record as *const u32) as usize;
+                let kind = ptr::read(record.add(4) as *const u32);
+                let body_len = len.min(self.size as usize - offset - RECORD_HEADER);
+                (len, kind, slice::from_raw_parts(record.add(RECORD_HEADER), body_len))
+            };
+            let packet = match kind {
+                KIND_PACKET => EncapHeader::decode(body).ok().map(|(h, p)| (h, p.to_vec())),
+                _ => None,
+            };
+            self.tail += (RECORD_HEADER + align8(len)) as u64;
+            self.map.u64_at(TAIL).store(self.tail, Ordering::Release);
+            if packet.is_some() {
+                return packet
+            }
+        }
+    }
+
+    /// Sleeps until the writer rings, unless records are waiting.
+    pub fn wait(&mut self) -> io::Result<()> {
+        self.map.u32_at(WAITING).store(1, Ordering::SeqCst);
+        fence(Ordering::SeqCst);
+        if self.tail != self.map.u64_at(HEAD).load(Ordering::Acquire) {
+            self.map.u32_at(WAITING).store(0, Ordering::SeqCst);
+            return Ok(())
+        }
+        let mut count = [0; 8];
+        self.doorbell.read_exact(&mut count)
+    }
+}
+
+// Sends `data` over `stream` with `fds` attached.
+fn send_fds(stream: &UnixStream, data: &[u8], fds: &[RawFd]) -> io::Result<()> {
+    let fds_len = mem::size_of_val(fds) as libc::c_uint;
+    let mut control = vec![0u8; unsafe { libc::CMSG_SPACE(fds_len) } as usize];
+    let mut iov = libc::iovec { iov_base: data.as_ptr() as *mut libc::c_void, iov_len: data.len() };
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+    msg.msg_controllen = control.len() as _;
+    let sent = unsafe {
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        (*cmsg).cmsg_level = libc::SOL_SOCKET;
+        (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+        (*cmsg).cmsg_len = libc::CMSG_LEN(fds_len) as _;
+        ptr::copy_nonoverlapping(fds.as_ptr() as *const u8, libc::CMSG_DATA(cmsg), fds_len as usize);
+        libc::sendmsg(stream.as_raw_fd(), &msg, 0)
+    };
+    match sent {
+        n if n < 0 => Err(io::Error::last_os_error()),
+        n if (n as usize) < data.len() => Err(io::Error::new(io::ErrorKind::WriteZero, "short offer")),
+        _ => Ok(()),
+    }
+}
+
+// Receives `data` from `stream` with the descriptors attached to it, up to
+// `max_fds`.
+fn recv_fds(stream: &UnixStream, data: &mut [u8], max_fds: usize) -> io::Result<Vec<File>> {
+    let fds_len = (max_fds * mem::size_of::<RawFd>()) as libc::c_uint;
+    let mut control = vec![0u8; unsafe { libc::CMSG_SPACE(fds_len) } as usize];
+    let mut iov = libc::iovec { iov_base: data.as_mut_ptr() as *mut libc::c_void, iov_len: data.len() };
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+    msg.msg_controllen = control.len() as _;
+    let received = unsafe { libc::recvmsg(stream.as_raw_fd(), &mut msg, 0) };
+    if received < 0 {
+        return Err(io::Error::last_os_error())
+    }
+    let mut files = Vec::new();
+    unsafe {
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        if !cmsg.is_null() && (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_RIGHTS {
+            let n = ((*cmsg).cmsg_len as usize - libc::CMSG_LEN(0) as usize) / mem::size_of::<RawFd>();
+            let data = libc::CMSG_DATA(cmsg) as *const RawFd;
+            for i in 0..n.min(max_fds) {
+                files.push(File::from_raw_fd(ptr::read_unaligned(data.add(i))));
+            }
+        }
+    }
+    if (received as usize) < data.len() {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "short offer"))
+    }
+    Ok(files)
+}
+
+/// A ring offered by a core, taken by the proxy.
+pub struct RingOffer
+{
+    pub core: u16,
+    pub reader: RingReader,
+}
+
+/// The proxy's end of the negotiation: receives a core's offer on `stream`,
+/// maps its ring and accepts it, or declines it if it can't.
+pub fn accept_offer(stream: &UnixStream) -> io::Result<RingOffer> {
+    let mut offer = [0u8; OFFER_LEN];
+    let mut files = recv_fds(stream, &mut offer, 2)?;
+    let reply = |answer: u8| (&*stream).write_all(&[answer]);
+    if offer[..4] != MAGIC.to_be_bytes() || offer[4] != VERSION || files.len() != 2 {
+        reply(DECLINE)?;
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "bad ring offer"))
+    }
+    let doorbell = files.pop().unwrap();
+    let memfd = files.pop().unwrap();
+    let reader = match RingReader::attach(memfd, doorbell) {
+        Ok(reader) => reader,
+        Err(e) => {
+            reply(DECLINE)?;
+            return Err(e)
+        },
+    };
+    reply(ACCEPT)?;
+    Ok(RingOffer { core: u16::from_be_bytes([offer[6], offer[7]]), reader: reader })
+}
+
+/// A core's ring to the proxy, in place of tun<core> once negotiated.
+pub struct ProxyRing
+{
+    writer: RingWriter,
+    // Closed by the proxy when it stops reading.
+    control: UnixStream,
+
+    // Since the last report.
+    sent: u64,
+    dropped: u64,
+    wakeups: u64,
+}
+
+impl ProxyRing
+{
+    /// Offers core `lcore`'s ring to the proxy listening at config.socket.
+    /// An error if it can't be set up or the proxy doesn't take it.
+    pub fn negotiate(config: &ProxyRingConfig, lcore: i32) -> DetectorResult<ProxyRing> {
+        let err = |context| move |e| DetectorError::ProxyRing { context: context, source: e };
+        let (writer, memfd) = RingWriter::create(config.bytes).map_err(err("setup"))?;
+        let control = UnixStream::connect(&config.socket).map_err(err("connect"))?;
+        control.set_read_timeout(Some(NEGOTIATE_TIMEOUT)).map_err(err("connect"))?;
+        control.set_write_timeout(Some(NEGOTIATE_TIMEOUT)).map_err(err("connect"))?;
+
+        let mut offer = Vec::with_capacity(OFFER_LEN);
+        offer.extend_from_slice(&MAGIC.to_be_bytes());
+        offer.extend_from_slice(&[VERSION, 0]);
+        offer.extend_from_slice(&(lcore as u16).to_be_bytes());
+        offer.extend_from_slice(&writer.size().to_be_bytes());
+        send_fds(&control, &offer, &[memfd.as_raw_fd(), writer.doorbell().as_raw_fd()]).map_err(err("offer"))?;
+        let mut answer = [DECLINE];
+        (&control).read_exact(&mut answer).map_err(err("offer"))?;
+        if answer[0] != ACCEPT {
+            return Err(err("offer")(io::Error::new(io::ErrorKind::ConnectionRefused, "declined by the proxy")))
+        }
+        control.set_nonblocking(true).map_err(err("connect"))?;
+        Ok(ProxyRing { writer: writer, control: control, sent: 0, dropped: 0, wakeups: 0 })
+    }
+
+    /// Hands `packet` (IP) of the session `header` to the proxy. False if it
+    /// was dropped, the ring being full.
+    pub fn send(&mut self, header: &EncapHeader, packet: &[u8]) -> bool {
+        if !self.writer.push(header, packet) {
+            self.dropped += 1;
+            return false
+        }
+        self.sent += 1;
+        if self.writer.ring() {
+            self.wakeups += 1;
+        }
+        true
+    }
+
+    /// False once the proxy closed its end, or broke the ring, when packets
+    /// should go back to tun<core>.
+    pub fn connected(&mut self) -> bool {
+        if self.writer.is_broken() {
+            return false
+        }
+        let mut buf = [0; 1];
+        match (&self.control).read(&mut buf) {
+            Ok(0) => false,
+            Ok(_) => true,
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => true,
+            Err(_) => false,
+        }
+    }
+
+    /// Packets sent and dropped, and wakeups since the last call, formatted
+    /// for the periodic report.
+    pub fn take_report(&mut self) -> String {
+        let report = format!("proxy ring sent {} dropped {} wakeups {} used {}%",
+            self.sent, self.dropped, self.wakeups, self.writer.used() * 100 / self.writer.size());
+        self.sent = 0;
+        self.dropped = 0;
+        self.wakeups = 0;
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::UnixListener;
+    use std::thread;
+    use proxy_ring::*;
+
+    fn header(session_id: u64) -> EncapHeader {
+        EncapHeader { transport: 1, flags: encap::FLAG_CONFIRMED, affinity: 7, session_id: session_id }
+    }
+
+    #[test]
+    fn test_proxy_ring() {
+        let (mut writer, memfd) = RingWriter::create(1024).unwrap();
+        let doorbell = writer.doorbell().try_clone().unwrap();
+        let mut reader = RingReader::attach(memfd, doorbell).unwrap();
+        assert!(reader.pop().is_none());
+
+        // records of 8 + 16 + 100 bytes, rounded to 128: eight fill the ring
+        for i in 0..8 {
+            assert!(writer.push(&header(i), &[i as u8; 100]));
+        }
+        assert!(!writer.push(&header(8), &[8; 100]));
+        assert_eq!(writer.used(), 1024);
+        for i in 0..8 {
+            assert_eq!(reader.pop(), Some((header(i), vec![i as u8; 100])));
+        }
+        assert!(reader.pop().is_none());
+        assert_eq!(writer.used(), 0);
+
+        // a record not fitting before the end is padded over to the start
+        for i in 0..7 {
+            assert!(writer.push(&header(i), &[0; 100]));
+            assert!(reader.pop().is_some());
+        }
+        assert!(writer.push(&header(7), &[7; 232]));
+        assert_eq!(writer.used(), 128 + 256);
+        assert!(writer.push(&header(8), &[8; 100]));
+        assert_eq!(reader.pop(), Some((header(7), vec![7; 232])));
+        assert_eq!(reader.pop(), Some((header(8), vec![8; 100])));
+        assert!(reader.pop().is_none());
+
+        // too big for a ring of this size
+        assert!(!writer.push(&header(9), &[0; 1024]));
+
+        // the writer only rings a waiting reader, which doesn't miss records
+        assert!(!writer.ring());
+        let waiter = thread::spawn(move || {
+            loop {
+                if let Some(popped) = reader.pop() {
+                    return popped
+                }
+                reader.wait().unwrap();
+            }
+        });
+        thread::sleep(Duration::from_millis(10));
+        assert!(writer.push(&header(10), &[10]));
+        writer.ring();
+        assert_eq!(waiter.join().unwrap(), (header(10), vec![10]));
+    }
+
+    #[test]
+    fn test_proxy_ring_broken_tail() {
+        let (mut writer, _memfd) = RingWriter::create(1024).unwrap();
+        assert!(writer.push(&header(1), &[1; 100]));
+        let head = writer.head;
+
+        // a consumer claiming more than was written breaks the ring for good
+        writer.map.u64_at(TAIL).store(head + 8, Ordering::Release);
+        assert_eq!(writer.used(), 0);
+        assert!(!writer.is_broken());
+        assert!(!writer.push(&header(2), &[2; 100]));
+        assert!(writer.is_broken());
+        writer.map.u64_at(TAIL).store(head, Ordering::Release);
+        assert!(!writer.push(&header(3), &[3; 100]));
+        assert_eq!(writer.used(), 0);
+
+        // as does one further behind than the ring holds
+        let (mut writer, _memfd) = RingWriter::create(1024).unwrap();
+        for i in 0..8 {
+            assert!(writer.push(&header(i), &[0; 100]));
+            writer.map.u64_at(TAIL).store(writer.head, Ordering::Release);
+        }
+        writer.map.u64_at(TAIL).store(writer.head - 1032, Ordering::Release);
+        assert!(!writer.push(&header(8), &[0; 100]));
+        assert!(writer.is_broken());
+    }
+
+    #[test]
+    fn test_proxy_ring_negotiate() {
+        let dir = ::std::env::temp_dir().join(format!("proxy-ring-test-{}", ::std::process::id()));
+        let _ = ::std::fs::remove_file(&dir);
+        let listener = UnixListener::bind(&dir).unwrap();
+        let config = ProxyRingConfig { socket: dir.to_string_lossy().into_owned(), bytes: 4096 };
+
+        let proxy = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut offer = accept_offer(&stream).unwrap();
+            assert_eq!(offer.core, 3);
+            while offer.reader.pop().is_none() {
+                offer.reader.wait().unwrap();
+            }
+            let (declined, _) = listener.accept().unwrap();
+            (&declined).write_all(&[DECLINE]).unwrap();
+            offer.reader.pop()
+        });
+
+        let mut ring = ProxyRing::negotiate(&config, 3).unwrap();
+        assert!(ring.connected());
+        assert!(ring.send(&header(1), &[0x45, 1]));
+        assert!(ring.send(&header(2), &[0x45, 2]));
+        assert!(ProxyRing::negotiate(&config, 4).is_err());
+        assert_eq!(proxy.join().unwrap(), Some((header(2), vec![0x45, 2])));
+        assert!(!ring.connected());
+        assert!(ring.take_report().starts_with("proxy ring sent 2 dropped 0 wakeups "));
+        let _ = ::std::fs::remove_file(&dir);
+    }
+}
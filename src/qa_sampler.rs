@@ -0,0 +1,343 @@
+//
+// QA Sampling
+//
+// Continuous, low volume quality sampling of matched sessions. With a sample
+// rate configured (DETECTOR_QA_SAMPLE_RATE) each newly matched session, on
+// its first forwarded connection, is picked with probability
+//
+//   rate * weight(transport)
+//
+// (capped at 1), the weights (DETECTOR_QA_SAMPLE_WEIGHTS) letting rarely used
+// transports be sampled more than common ones. For a picked session the
+// detector follows the connections it forwards during the QA window
+// (QaSamplerConfig.window_ns, a minute by default) and records:
+//
+//  - for the first connection, the time from the client's SYN to its
+//    handshake ACK, about one round trip between the detector and the client,
+//    and to its first data;
+//  - connections opened and packets and bytes forwarded from the client;
+//  - the fingerprint of the first connection's TLS ClientHello, if the
+//    detector fingerprints them (see tls_fingerprint.rs).
+//
+// Once the window is over the record is published as a QaSample on the
+// stats channel (STATS_CHANNEL). Records are anonymized: they carry the
+// transport, registration source, phantom port and IP version of the
+// session, but no addresses and no session id.
+//
+// At most max_sessions sessions are followed at once; sessions picked while
+// that many are being followed are counted as skipped.
+
+use std::collections::HashMap;
+
+use pnet::packet::tcp::TcpFlags;
+use rand;
+
+use feedback::EventPublisher;
+use flow_tracker::Flow;
+use ingest::REDIS_URL;
+use sessions::SessionEntry;
+use signalling::{QaSample, TransportType};
+use timekeeping::MonotonicNs;
+use tls_fingerprint::TlsFingerprint;
+
+pub const STATS_CHANNEL: &'static str = "detector_stats";
+
+// Connections of followed sessions whose packets are counted, at most.
+const MAX_CONNECTIONS: usize = 4096;
+
+#[derive(Clone)]
+pub struct QaSamplerConfig
+{
+    // Fraction of newly matched sessions sampled, before weighting.
+    pub rate: f64,
+    // Weight of sessions for each transport, 1 for those not listed.
+    pub weights: Vec<(TransportType, f64)>,
+    // How long (ns) after its first connection a sampled session is followed.
+    pub window_ns: u64,
+    // Sessions followed at once.
+    pub max_sessions: usize,
+}
+
+impl Default for QaSamplerConfig
+{
+    fn default() -> QaSamplerConfig {
+        QaSamplerConfig {
+            rate: 0.0,
+            weights: Vec::new(),
+            window_ns: 60*1000*1000*1000,
+            max_sessions: 256,
+        }
+    }
+}
+
+impl QaSamplerConfig
+{
+    /// Probability a newly matched session for `transport` is sampled.
+    pub fn probability(&self, transport: TransportType) -> f64 {
+        let weight = self.weights.iter()
+            .find(|&&(t, _)| t == transport)
+            .map_or(1.0, |&(_, w)| w);
+        (self.rate * weight).max(0.0).min(1.0)
+    }
+}
+
+// A followed session.
+struct Record
+{
+    sample: QaSample,
+    started: MonotonicNs,
+}
+
+// A connection of a followed session.
+struct Connection
+{
+    session_id: u64,
+    // Whether the handshake and first data times go to the record, for its
+    // first connection only, and the SYN time they are measured from.
+    timed: bool,
+    syn: MonotonicNs,
+}
+
+pub struct QaSampler
+{
+    config: QaSamplerConfig,
+    publisher: EventPublisher,
+
+    records: HashMap<u64, Record>,
+    connections: HashMap<Flow, Connection>,
+
+    // Since the last take_report.
+    sampled: u64,
+    skipped: u64,
+    published: u64,
+}
+
+impl QaSampler
+{
+    /// Sampler publishing on the stats channel.
+    pub fn spawn(config: QaSamplerConfig) -> QaSampler {
+        QaSampler::new(config, EventPublisher::spawn(REDIS_URL, STATS_CHANNEL))
+    }
+
+    pub fn new(config: QaSamplerConfig, publisher: EventPublisher) -> QaSampler {
+        QaSampler {
+            config: config,
+            publisher: publisher,
+            records: HashMap::new(),
+            connections: HashMap::new(),
+            sampled: 0,
+            skipped: 0,
+            published: 0,
+        }
+    }
+
+    /// A connection `flow` was forwarded for the session `entry`, its SYN
+    /// seen at `now`. The session is considered for sampling on its first
+    /// connection; later ones are followed if it was picked.
+    pub fn connection_opened(&mut self, flow: &Flow, entry: &SessionEntry, now: MonotonicNs) {
+        let id = entry.session_id;
+        let timed = if entry.connections == 1 {
+            if rand::random::<f64>() >= self.config.probability(entry.details.transport) {
+                return
+            }
+            if self.records.len() >= self.config.max_sessions {
+                self.skipped += 1;
+                return
+            }
+            self.sampled += 1;
+            self.records.insert(id, Record { sample: new_sample(entry, self.config.window_ns), started: now });
+            true
+        } else {
+            match self.records.get(&id) {
+                Some(r) if now.saturating_since(r.started) < self.config.window_ns => false,
+                _ => return,
+            }
+        };
+
+        if let Some(r) = self.records.get_mut(&id) {
+            r.sample.set_connections(r.sample.get_connections() + 1);
+        }
+        if self.connections.len() < MAX_CONNECTIONS {
+            self.connections.insert(*flow, Connection { session_id: id, timed: timed, syn: now });
+        }
+    }
+
+    /// A packet of `len` bytes, with TCP flags `flags` and `payload_len`
+    /// bytes of data, was forwarded for `flow` at `now`.
+    pub fn packet(&mut self, flow: &Flow, flags: u16, payload_len: usize, len: usize, now: MonotonicNs) {
+        if self.connections.is_empty() {
+            return
+        }
+        let conn = match self.connections.get_mut(flow) {
+            Some(c) => c,
+            None => return,
+        };
+        let record = match self.records.get_mut(&conn.session_id) {
+            Some(r) if now.saturating_since(r.started) < self.config.window_ns => r,
+            _ => return,
+        };
+        let sample = &mut record.sample;
+        sample.set_packets(sample.get_packets() + 1);
+        sample.set_bytes(sample.get_bytes() + len as u64);
+        if !conn.timed {
+            return
+        }
+        let since_syn_us = now.saturating_since(conn.syn) / 1000;
+        if flags & TcpFlags::SYN == 0 && flags & TcpFlags::ACK != 0 && !sample.has_handshake_rtt_us() {
+            sample.set_handshake_rtt_us(since_syn_us);
+        }
+        if payload_len > 0 && !sample.has_first_data_us() {
+            sample.set_first_data_us(since_syn_us);
+        }
+    }
+
+    /// The connection `flow` opened with a ClientHello with `fingerprint`,
+    /// recorded if it is the first connection of a followed session.
+    pub fn tls_fingerprint(&mut self, flow: &Flow, fingerprint: &TlsFingerprint) {
+        let conn = match self.connections.get(flow) {
+            Some(c) if c.timed => c,
+            _ => return,
+        };
+        if let Some(r) = self.records.get_mut(&conn.session_id) {
+            r.sample.set_tls_fingerprint(fingerprint.to_string());
+        }
+    }
+
+    /// Publishes the records whose window ended by `now`, returning how many.
+    pub fn flush(&mut self, now: MonotonicNs) -> usize {
+        let window_ns = self.config.window_ns;
+        let done: Vec<u64> = self.records.iter()
+            .filter(|&(_, r)| now.saturating_since(r.started) >= window_ns)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in done.iter() {
+            if let Some(r) = self.records.remove(id) {
+                self.publisher.publish(&r.sample);
+            }
+        }
+        let records = &self.records;
+        self.connections.retain(|_, c| records.contains_key(&c.session_id));
+        self.published += done.len() as u64;
+        done.len()
+    }
+
+    /// Sessions sampled, skipped and published since the last call, and
+    /// followed now, formatted for the periodic report.
+    pub fn take_report(&mut self) -> String {
+        let line = format!("qa samples sampled {} skipped {} published {} following {} dropped {}",
+            self.sampled, self.skipped, self.published, self.records.len(),
+            self.publisher.take_dropped());
+        self.sampled = 0;
+        self.skipped = 0;
+        self.published = 0;
+        line
+    }
+}
+
+fn new_sample(entry: &SessionEntry, window_ns: u64) -> QaSample {
+    let d = &entry.details;
+    let mut sample = QaSample::new();
+    sample.set_transport(d.transport);
+    sample.set_registration_source(d.registration_source);
+    sample.set_phantom_port(d.phantom_port);
+    sample.set_ipv6(d.phantom_ip.is_ipv6());
+    sample.set_window_ms((window_ns / (1000*1000)) as u32);
+    sample
+}
+
+#[cfg(test)]
+mod tests {
+    use qa_sampler::*;
+    use protobuf::Message;
+    use sessions::{SessionDetails, SessionEntry};
+    use tls_fingerprint::fingerprint;
+    use std::sync::mpsc::sync_channel;
+    use std::time::Duration;
+
+    const MS: u64 = 1000*1000;
+
+    fn entry(transport: TransportType, session_id: u64, connections: u32) -> SessionEntry {
+        let mut details = SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_secs(0)).unwrap();
+        details.transport = transport;
+        SessionEntry {
+            session_id: session_id,
+            active_connections: 1,
+            connections: connections,
+            ..SessionEntry::new(details, MonotonicNs(0))
+        }
+    }
+
+    fn flow(src_port: u16) -> Flow {
+        Flow {
+            src_ip: "192.168.0.1".parse().unwrap(),
+            dst_ip: "10.10.0.1".parse().unwrap(),
+            src_port: src_port,
+            dst_port: 443,
+        }
+    }
+
+    #[test]
+    fn test_qa_probability() {
+        let config = QaSamplerConfig {
+            rate: 0.01,
+            weights: vec![(TransportType::Obfs4, 10.0), (TransportType::Null, 0.0)],
+            ..QaSamplerConfig::default()
+        };
+        assert_eq!(config.probability(TransportType::Min), 0.01);
+        assert_eq!(config.probability(TransportType::Obfs4), 0.1);
+        assert_eq!(config.probability(TransportType::Null), 0.0);
+        let capped = QaSamplerConfig { rate: 0.5, ..config };
+        assert_eq!(capped.probability(TransportType::Obfs4), 1.0);
+    }
+
+    #[test]
+    fn test_qa_sampler_records() {
+        let (tx, rx) = sync_channel(8);
+        let config = QaSamplerConfig {
+            rate: 1.0,
+            weights: vec![(TransportType::Obfs4, 0.0)],
+            window_ns: 1000*MS,
+            max_sessions: 1,
+        };
+        let mut qa = QaSampler::new(config, EventPublisher::from_sender(tx));
+
+        // first connection: SYN, handshake ACK 20ms later, data at 25ms
+        qa.connection_opened(&flow(1000), &entry(TransportType::Min, 1, 1), MonotonicNs(0));
+        qa.packet(&flow(1000), TcpFlags::SYN, 0, 60, MonotonicNs(0));
+        qa.packet(&flow(1000), TcpFlags::ACK, 0, 52, MonotonicNs(20*MS));
+        qa.packet(&flow(1000), TcpFlags::ACK, 500, 552, MonotonicNs(25*MS));
+        // TLS 1.2 ClientHello offering one cipher suite, no extensions
+        let mut hello = vec![0x16, 0x03, 0x01, 0, 45, 0x01, 0, 0, 41, 0x03, 0x03];
+        hello.extend_from_slice(&[0; 32]);
+        hello.extend_from_slice(&[0, 0, 2, 0x13, 0x01, 1, 0]);
+        let fp = fingerprint(&hello).unwrap();
+        qa.tls_fingerprint(&flow(1000), &fp);
+        // second connection only adds to the counts
+        qa.connection_opened(&flow(1001), &entry(TransportType::Min, 1, 2), MonotonicNs(100*MS));
+        qa.packet(&flow(1001), TcpFlags::SYN, 0, 60, MonotonicNs(100*MS));
+        qa.packet(&flow(1001), TcpFlags::ACK, 0, 52, MonotonicNs(300*MS));
+        qa.tls_fingerprint(&flow(1001), &fp);
+        // a weight of 0 never samples, and only one session is followed
+        qa.connection_opened(&flow(2000), &entry(TransportType::Obfs4, 2, 1), MonotonicNs(0));
+        qa.connection_opened(&flow(3000), &entry(TransportType::Min, 3, 1), MonotonicNs(0));
+        qa.packet(&flow(3000), TcpFlags::SYN, 0, 60, MonotonicNs(0));
+        // after the window nothing is counted
+        qa.packet(&flow(1000), TcpFlags::ACK, 500, 552, MonotonicNs(1000*MS));
+
+        assert_eq!(qa.flush(MonotonicNs(999*MS)), 0);
+        assert_eq!(qa.flush(MonotonicNs(1000*MS)), 1);
+        let sample: QaSample = Message::parse_from_bytes(&rx.try_recv().unwrap()).unwrap();
+        assert_eq!(sample.get_transport(), TransportType::Min);
+        assert_eq!(sample.get_phantom_port(), 443);
+        assert!(!sample.get_ipv6());
+        assert_eq!(sample.get_window_ms(), 1000);
+        assert_eq!((sample.get_connections(), sample.get_packets(), sample.get_bytes()), (2, 5, 776));
+        assert_eq!(sample.get_handshake_rtt_us(), 20*1000);
+        assert_eq!(sample.get_first_data_us(), 25*1000);
+        assert_eq!(sample.get_tls_fingerprint(), "t12i010000_0f2cb44170f4_000000000000");
+        assert!(rx.try_recv().is_err());
+        assert!(qa.connections.is_empty());
+
+        assert_eq!(qa.take_report(), "qa samples sampled 1 skipped 1 published 1 following 0 dropped 0");
+    }
+}
@@ -0,0 +1,202 @@
+//
+// QUIC Initial Recognition
+//
+// A registered phantom's UDP traffic should start with the client's QUIC
+// Initial, but anything sent to the phantom from the registered client is
+// forwarded. To tell the two apart the detector can parse the long header of
+// the first datagram of each UDP connection of a tracked session
+// (DETECTOR_QUIC_INITIALS). It is an Initial if:
+//
+//  - the header form and fixed bits are set and the packet type is Initial
+//    (0 for QUIC v1 and the drafts, 1 for QUIC v2, RFC 9369);
+//  - the version is one of those, version negotiation (0) isn't sent by
+//    clients;
+//  - the connection IDs are at most 20 bytes, and the token and length
+//    fields fit in the datagram;
+//  - the datagram is at least 1200 bytes, the size clients must pad their
+//    Initials to (RFC 9000 14.1).
+//
+// Nothing past the header is looked at, the payload is encrypted. Datagrams
+// that aren't Initials are still forwarded, only counted.
+//
+// The hash of an Initial's destination connection ID, the first 8 bytes of
+// its SHA256 as 16 hex digits, is kept in the session's SessionEntry so
+// sessions can be matched with the station's proxy logs when they record the
+// same hash of the connection IDs they see.
+
+use std::fmt;
+
+use sha2::{Digest, Sha256};
+
+/// Smallest datagram a client may carry its Initial in.
+pub const MIN_INITIAL_LEN: usize = 1200;
+
+const MAX_CID_LEN: usize = 20;
+
+const VERSION_1: u32 = 0x0000_0001;
+const VERSION_2: u32 = 0x6b33_43cf;
+const DRAFT_MASK: u32 = 0xffff_ff00;
+const DRAFT_VERSIONS: u32 = 0xff00_0000;
+
+/// The long header fields of a client's Initial.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct QuicInitial
+{
+    pub version: u32,
+    pub dcid_hash: DcidHash,
+}
+
+/// Hash of a destination connection ID, see above.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct DcidHash(pub u64);
+
+impl DcidHash
+{
+    pub fn of(dcid: &[u8]) -> DcidHash {
+        let digest = Sha256::digest(dcid);
+        let mut hash = 0u64;
+        for b in digest.iter().take(8) {
+            hash = hash << 8 | u64::from(*b);
+        }
+        DcidHash(hash)
+    }
+}
+
+impl fmt::Display for DcidHash
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl fmt::Debug for DcidHash
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DcidHash({})", self)
+    }
+}
+
+// Packet type of an Initial in `version`, None for versions that aren't
+// recognized.
+fn initial_type(version: u32) -> Option<u8> {
+    match version {
+        VERSION_1 => Some(0),
+        VERSION_2 => Some(1),
+        v if v & DRAFT_MASK == DRAFT_VERSIONS => Some(0),
+        _ => None,
+    }
+}
+
+// Variable length integer (RFC 9000 16) at the start of `buf`, and its length.
+fn varint(buf: &[u8]) -> Option<(u64, usize)> {
+    let first = *buf.first()?;
+    let len = 1 << (first >> 6);
+    if buf.len() < len {
+        return None;
+    }
+    let mut value = u64::from(first & 0x3f);
+    for b in &buf[1..len] {
+        value = value << 8 | u64::from(*b);
+    }
+    Some((value, len))
+}
+
+/// The Initial `payload` carries, None if it isn't one.
+pub fn parse_initial(payload: &[u8]) -> Option<QuicInitial> {
+    if payload.len() < MIN_INITIAL_LEN {
+        return None;
+    }
+    let first = payload[0];
+    // long header, fixed bit
+    if first & 0xc0 != 0xc0 {
+        return None;
+    }
+    let version = u32::from(payload[1]) << 24 | u32::from(payload[2]) << 16
+        | u32::from(payload[3]) << 8 | u32::from(payload[4]);
+    if (first >> 4) & 0x03 != initial_type(version)? {
+        return None;
+    }
+
+    let mut at = 5;
+    let dcid_len = payload[at] as usize;
+    at += 1;
+    if dcid_len > MAX_CID_LEN {
+        return None;
+    }
+    let dcid = payload.get(at..at + dcid_len)?;
+    at += dcid_len;
+    let scid_len = *payload.get(at)? as usize;
+    at += 1 + scid_len;
+    if scid_len > MAX_CID_LEN {
+        return None;
+    }
+
+    let (token_len, n) = varint(payload.get(at..)?)?;
+    at = at.checked_add(n)?.checked_add(token_len as usize)?;
+    let (len, n) = varint(payload.get(at..)?)?;
+    at += n;
+    if (len as usize) > payload.len().saturating_sub(at) {
+        return None;
+    }
+
+    Some(QuicInitial {
+        version: version,
+        dcid_hash: DcidHash::of(dcid),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use quic::*;
+
+    fn initial(first: u8, version: u32, dcid: &[u8], token: &[u8], size: usize) -> Vec<u8> {
+        let mut pkt = vec![first];
+        pkt.extend_from_slice(&[(version >> 24) as u8, (version >> 16) as u8,
+            (version >> 8) as u8, version as u8]);
+        pkt.push(dcid.len() as u8);
+        pkt.extend_from_slice(dcid);
+        pkt.extend_from_slice(&[4, 1, 2, 3, 4]);
+        pkt.push(token.len() as u8);
+        pkt.extend_from_slice(token);
+        // 2 byte length, the rest of the datagram
+        let rest = size - pkt.len() - 2;
+        pkt.extend_from_slice(&[0x40 | (rest >> 8) as u8, rest as u8]);
+        pkt.resize(size, 0);
+        pkt
+    }
+
+    #[test]
+    fn test_parse_initial() {
+        let dcid = [0x83, 0x94, 0xc8, 0xf0, 0x3e, 0x51, 0x57, 0x08];
+        let v1 = parse_initial(&initial(0xc3, VERSION_1, &dcid, &[], 1200)).unwrap();
+        assert_eq!(v1.version, VERSION_1);
+        assert_eq!(v1.dcid_hash, DcidHash::of(&dcid));
+        assert_eq!(v1.dcid_hash.to_string().len(), 16);
+        assert_ne!(v1.dcid_hash, DcidHash::of(&dcid[1..]));
+
+        // v2 Initials have type 1, drafts are v1's
+        let v2 = parse_initial(&initial(0xd3, VERSION_2, &dcid, b"token", 1350)).unwrap();
+        assert_eq!(v2.version, VERSION_2);
+        assert_eq!(v2.dcid_hash, v1.dcid_hash);
+        assert!(parse_initial(&initial(0xc3, 0xff00_001d, &dcid, &[], 1200)).is_some());
+
+        // too short for an Initial
+        assert_eq!(parse_initial(&initial(0xc3, VERSION_1, &dcid, &[], 1199)), None);
+        // 0-RTT, Handshake, and v1's Initial type with v2
+        assert_eq!(parse_initial(&initial(0xd3, VERSION_1, &dcid, &[], 1200)), None);
+        assert_eq!(parse_initial(&initial(0xe3, VERSION_1, &dcid, &[], 1200)), None);
+        assert_eq!(parse_initial(&initial(0xc3, VERSION_2, &dcid, &[], 1200)), None);
+        // short header, missing fixed bit, unknown version, long connection ID
+        assert_eq!(parse_initial(&initial(0x43, VERSION_1, &dcid, &[], 1200)), None);
+        assert_eq!(parse_initial(&initial(0x83, VERSION_1, &dcid, &[], 1200)), None);
+        assert_eq!(parse_initial(&initial(0xc3, 0x1a2a_3a4a, &dcid, &[], 1200)), None);
+        assert_eq!(parse_initial(&initial(0xc3, VERSION_1, &[0; 21], &[], 1200)), None);
+
+        // a length past the end of the datagram
+        let mut pkt = initial(0xc3, VERSION_1, &dcid, &[], 1200);
+        let len_at = 1 + 4 + 1 + dcid.len() + 5 + 1;
+        pkt[len_at] = 0x7f;
+        assert_eq!(parse_initial(&pkt), None);
+        assert_eq!(parse_initial(&[0xff; 1500]), None);
+    }
+}
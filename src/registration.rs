@@ -0,0 +1,331 @@
+//
+// Registrations
+//
+// Every message the station sends the detector is converted into a
+// Registration as soon as it is parsed, and the ingest path only looks at
+// that: the sequence tracker, the ingest pool, the journal, the checks in
+// sessions.rs and the sessions a registration produces (sessions()). Only
+// the conversions (From<&StationToDetector> here, and any other source's)
+// know about wire formats, so schema changes, other sources and test
+// fixtures don't ripple through the tracker.
+//
+// Addresses are kept as sent, as strings, so a registration whose addresses
+// don't parse can still be journaled; they are parsed when its sessions are
+// built. Messages that act on more than one session are told apart by their
+// Command. Configuration pushes are carried as the station sent them, as
+// StationConfig applies them.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+
+use sessions::{ClientPorts, SessionDetails, SessionError};
+use signalling::{ConfigPush, PhantomSubnetSet, RegistrationSource, SessionMode, StationToDetector, TransportType};
+use tenants::TenantId;
+use timekeeping::WallClockNs;
+
+// Largest difference between the station's clock at sending and ours at
+// receipt for which absolute expirations are trusted.
+const MAX_TRUSTED_SKEW_NS: u64 = 60 * 1000*1000*1000;
+
+// What a message from the station asks of the detector. A message carries at
+// most one command; the other fields of a message with anything but
+// Register or Update are ignored, except the tenant of DeleteTenantSessions.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command
+{
+    // Track (or extend) the sessions of the registration.
+    Register,
+    // Apply the station's configuration, see config_push.rs.
+    ConfigPush(ConfigPush),
+    // Apply the station's phantom subnets, see phantom_subnets.rs.
+    PhantomSubnets(PhantomSubnetSet),
+    // Delete every session of the tenant, see tenants.rs.
+    DeleteTenantSessions,
+    // Reset the expiry of the registration's sessions, if tracked, to its
+    // lifetime from receipt (see SessionTracker::refresh_sessions).
+    Update,
+}
+
+impl Default for Command
+{
+    fn default() -> Command {
+        Command::Register
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Registration
+{
+    pub command: Command,
+    pub client_ip: String,
+    pub phantom_ip: String,
+    // Set for dual-stack registrations.
+    pub phantom_ip_v6: Option<String>,
+    // 0 for the configured ports.
+    pub phantom_port: u32,
+    // Lifetime relative to receipt, and absolute expiration, when sent.
+    pub timeout: Option<Duration>,
+    pub expires_at: Option<WallClockNs>,
+    // Station's clock at sending.
+    pub station_time: Option<WallClockNs>,
+    pub source: RegistrationSource,
+    pub transport: TransportType,
+    // Simultaneous connection limit (0 = none).
+    pub max_connections: u32,
+    pub mode: SessionMode,
+    pub tenant: TenantId,
+    // Time activity extends the sessions by, None for the configured one.
+    pub extension: Option<Duration>,
+    // Id for the replay guard, empty if none.
+    pub message_id: Vec<u8>,
+    // Publisher of the message and its number, see sequence.rs.
+    pub sequence: Option<(u32, u64)>,
+    // First and last source port the client connects from, if restricted.
+    pub client_ports: Option<(u32, u32)>,
+}
+
+impl Registration
+{
+    /// Lifetime of the registration received at `now`.
+    ///
+    /// Without an absolute expiration this is timeout. With one, it is the
+    /// time left until it, unless the station's timestamp shows our clocks are
+    /// more than MAX_TRUSTED_SKEW_NS apart (or the message was delayed that
+    /// long), in which case the lifetime the registration had when it was
+    /// sent is used.
+    pub fn lifetime(&self, now: WallClockNs) -> Duration {
+        let expires_at = match self.expires_at {
+            Some(t) => t,
+            None => return self.timeout.unwrap_or_default(),
+        };
+        if let Some(sent) = self.station_time {
+            let skew = now.distance(sent);
+            if skew > MAX_TRUSTED_SKEW_NS {
+                debug!("Station clock skew {}ms, using registration lifetime as sent", skew / (1000*1000));
+                return Duration::from_nanos(expires_at.saturating_since(sent))
+            }
+        }
+        Duration::from_nanos(expires_at.saturating_since(now))
+    }
+
+    /// Group linking the sessions of a dual-stack registration, a hash of
+    /// its client, phantoms, port and tenant, so a repeated registration (or
+    /// a peer's copy of it) joins the same group.
+    pub fn group_id(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.client_ip.hash(&mut hasher);
+        self.phantom_ip.hash(&mut hasher);
+        self.phantom_ip_v6.hash(&mut hasher);
+        self.phantom_port.hash(&mut hasher);
+        self.tenant.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Identifies the message for telling a redelivery of it from another
+    /// registration: a hash of its message_id, or else of its publisher and
+    /// sequence number. None if it carries neither.
+    pub fn message_hash(&self) -> Option<u64> {
+        let mut hasher = DefaultHasher::new();
+        match self.sequence {
+            _ if !self.message_id.is_empty() => self.message_id.hash(&mut hasher),
+            Some(seq) => seq.hash(&mut hasher),
+            None => return None,
+        }
+        Some(hasher.finish())
+    }
+
+    /// Every session the registration, received at `now`, carries: the
+    /// session for phantom_ip and, for dual-stack registrations, the one for
+    /// phantom_ip_v6, both in the registration's group. If any of them is
+    /// invalid the whole registration is rejected.
+    pub fn sessions(&self, now: WallClockNs) -> Result<Vec<SessionDetails>, SessionError> {
+        let timeout = self.lifetime(now);
+        let client_ports = match self.client_ports {
+            Some((first, last)) => Some(ClientPorts::new(first, last)?),
+            None => None,
+        };
+        let mut primary = SessionDetails::new(&self.client_ip, &self.phantom_ip, self.phantom_port, timeout)?;
+        primary.registration_source = self.source;
+        primary.transport = self.transport;
+        primary.max_connections = self.max_connections;
+        primary.mode = self.mode;
+        primary.tenant = self.tenant;
+        primary.extension = self.extension;
+        primary.client_ports = client_ports;
+        primary.message = self.message_hash();
+        let mut sessions = vec![primary];
+        if let Some(ref phantom_v6) = self.phantom_ip_v6 {
+            let mut sd = SessionDetails::new(&self.client_ip, phantom_v6, self.phantom_port, timeout)?;
+            sd.registration_source = primary.registration_source;
+            sd.transport = primary.transport;
+            sd.max_connections = primary.max_connections;
+            sd.mode = primary.mode;
+            sd.tenant = primary.tenant;
+            sd.extension = primary.extension;
+            sd.client_ports = primary.client_ports;
+            sd.message = primary.message;
+            sd.group = Some(self.group_id());
+            sessions[0].group = sd.group;
+            sessions.push(sd);
+        }
+        Ok(sessions)
+    }
+}
+
+impl<'a> From<&'a StationToDetector> for Registration
+{
+    fn from(s2d: &StationToDetector) -> Registration {
+        let command = if s2d.has_config_push() {
+            Command::ConfigPush(s2d.get_config_push().clone())
+        } else if s2d.get_delete_tenant_sessions() {
+            Command::DeleteTenantSessions
+        } else if s2d.has_phantom_subnets() {
+            Command::PhantomSubnets(s2d.get_phantom_subnets().clone())
+        } else if s2d.get_update_expiry() {
+            Command::Update
+        } else {
+            Command::Register
+        };
+        Registration {
+            command: command,
+            client_ip: s2d.get_client_ip().to_string(),
+            phantom_ip: s2d.get_phantom_ip().to_string(),
+            phantom_ip_v6: match s2d.has_phantom_ip_v6() {
+                true => Some(s2d.get_phantom_ip_v6().to_string()),
+                false => None,
+            },
+            phantom_port: s2d.get_phantom_port(),
+            timeout: match s2d.has_timeout_ns() {
+                true => Some(Duration::from_nanos(s2d.get_timeout_ns())),
+                false => None,
+            },
+            expires_at: match s2d.has_expires_at_unix_ns() {
+                true => Some(WallClockNs(s2d.get_expires_at_unix_ns())),
+                false => None,
+            },
+            station_time: match s2d.has_station_time_unix_ns() {
+                true => Some(WallClockNs(s2d.get_station_time_unix_ns())),
+                false => None,
+            },
+            source: s2d.get_registration_source(),
+            transport: s2d.get_transport(),
+            max_connections: s2d.get_max_connections(),
+            mode: s2d.get_mode(),
+            tenant: s2d.get_tenant_id(),
+            extension: match s2d.get_extension_ns() {
+                0 => None,
+                ns => Some(Duration::from_nanos(ns)),
+            },
+            message_id: s2d.get_message_id().to_vec(),
+            sequence: match s2d.has_sequence() {
+                true => Some((s2d.get_publisher_id(), s2d.get_sequence())),
+                false => None,
+            },
+            client_ports: match s2d.has_client_ports() {
+                true => Some((s2d.get_client_ports().get_first(), s2d.get_client_ports().get_last())),
+                false => None,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use registration::*;
+
+    const S2NS: u64 = 1000*1000*1000;
+
+    #[test]
+    fn test_registration_from_s2d() {
+        let mut s2d = StationToDetector::new();
+        s2d.set_client_ip("192.168.0.1".to_string());
+        s2d.set_phantom_ip("10.10.0.1".to_string());
+        s2d.set_phantom_ip_v6("2001::1234".to_string());
+        s2d.set_phantom_port(8443);
+        s2d.set_timeout_ns(30*S2NS);
+        s2d.set_transport(TransportType::Min);
+        s2d.set_max_connections(4);
+        s2d.set_extension_ns(90*S2NS);
+        s2d.set_publisher_id(3);
+        s2d.set_sequence(12);
+        let reg = Registration::from(&s2d);
+        assert_eq!(reg.command, Command::Register);
+        assert_eq!(reg.phantom_ip_v6, Some("2001::1234".to_string()));
+        assert_eq!(reg.timeout, Some(Duration::from_secs(30)));
+        assert_eq!(reg.expires_at, None);
+        assert_eq!(reg.extension, Some(Duration::from_secs(90)));
+        assert_eq!(reg.sequence, Some((3, 12)));
+
+        let sessions = reg.sessions(WallClockNs(0)).unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].transport, TransportType::Min);
+        assert_eq!(sessions[0].phantom_port, 8443);
+        assert_eq!(sessions[1].phantom_ip, "2001::1234".parse::<::std::net::IpAddr>().unwrap());
+        assert_eq!(sessions[1].transport, TransportType::Min);
+        assert_eq!(sessions[1].max_connections, 4);
+        assert_eq!(sessions[1].timeout(), Duration::from_secs(30));
+
+        assert_eq!(sessions[0].client_ports, None);
+
+        // redeliveries of the message are recognized
+        let message = reg.message_hash();
+        assert!(message.is_some());
+        assert_eq!((sessions[0].message, sessions[1].message), (message, message));
+        assert_eq!(Registration::from(&s2d).message_hash(), message);
+        assert!(Registration { sequence: Some((3, 13)), ..reg.clone() }.message_hash() != message);
+        assert!(Registration { message_id: b"id".to_vec(), ..reg.clone() }.message_hash() != message);
+        assert_eq!(Registration { sequence: None, ..reg.clone() }.message_hash(), None);
+
+        // both sessions are in the registration's group, the same whenever
+        // it is registered again
+        let group = Some(reg.group_id());
+        assert_eq!((sessions[0].group, sessions[1].group), (group, group));
+        assert_eq!(Registration::from(&s2d).sessions(WallClockNs(0)).unwrap()[1].group, group);
+        let v4_only = Registration { phantom_ip_v6: None, ..reg.clone() };
+        assert_eq!(v4_only.sessions(WallClockNs(0)).unwrap()[0].group, None);
+        let other = Registration { phantom_ip_v6: Some("2001::1235".to_string()), ..reg.clone() };
+        assert!(other.group_id() != reg.group_id());
+
+        // client ports apply to both sessions, and have to make a range
+        s2d.mut_client_ports().set_first(40000);
+        s2d.mut_client_ports().set_last(40999);
+        let reg = Registration::from(&s2d);
+        assert_eq!(reg.client_ports, Some((40000, 40999)));
+        let sessions = reg.sessions(WallClockNs(0)).unwrap();
+        let ports = Some(ClientPorts { first: 40000, last: 40999 });
+        assert_eq!((sessions[0].client_ports, sessions[1].client_ports), (ports, ports));
+        for &(first, last) in [(41000, 40999), (0, 65536)].iter() {
+            let bad = Registration { client_ports: Some((first, last)), ..reg.clone() };
+            match bad.sessions(WallClockNs(0)) {
+                Err(SessionError::InvalidClientPorts(f, l)) => assert_eq!((f, l), (first, last)),
+                _ => panic!("client ports {}-{} accepted", first, last),
+            }
+        }
+
+        // a bad v6 phantom rejects both
+        let reg = Registration { phantom_ip_v6: Some("nope".to_string()), ..reg };
+        assert!(reg.sessions(WallClockNs(0)).is_err());
+
+        // commands
+        let mut s2d = StationToDetector::new();
+        s2d.set_tenant_id(7);
+        s2d.set_delete_tenant_sessions(true);
+        let reg = Registration::from(&s2d);
+        assert_eq!((reg.command, reg.tenant), (Command::DeleteTenantSessions, 7));
+        s2d.mut_config_push().set_default_timeout_ns(S2NS);
+        match Registration::from(&s2d).command {
+            Command::ConfigPush(push) => assert_eq!(push.get_default_timeout_ns(), S2NS),
+            c => panic!("expected a config push, got {:?}", c),
+        }
+        assert_eq!(Registration::default().command, Command::Register);
+
+        let mut s2d = StationToDetector::new();
+        s2d.set_client_ip("192.168.0.1".to_string());
+        s2d.set_phantom_ip("10.10.0.1".to_string());
+        s2d.set_timeout_ns(30*S2NS);
+        s2d.set_update_expiry(true);
+        let reg = Registration::from(&s2d);
+        assert_eq!((reg.command, reg.timeout), (Command::Update, Some(Duration::from_secs(30))));
+    }
+}
@@ -0,0 +1,227 @@
+//
+// Registration Replay Protection
+//
+// A StationToDetector captured off the wire (or replayed by a misbehaving
+// broker) would otherwise recreate its session every time it is received,
+// even after the session was revoked or expired. With a replay window
+// configured (DETECTOR_REPLAY_WINDOW_MS) the ingest path checks each message
+// that carries a message_id:
+//
+//  - it must carry a station_time_unix_ns, within the window of our clock
+//    either way. A message timed only by its arrival could be replayed as
+//    new once its id is forgotten, after the window;
+//  - its message_id must not have been seen within the window.
+//
+// Rejected messages are counted as duplicates (id already seen), stale
+// (outside the window) or unstamped (no station time). Messages without a
+// message_id are accepted, and counted as unidentified, unless
+// ReplayConfig.require_id is set.
+//
+// At most ReplayConfig.max_ids ids are remembered. When the set is full the
+// oldest id is forgotten and the window's lower edge moves up to its station
+// time, so a forgotten id can't be replayed either: anything sent at or
+// before it is rejected as stale.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use thiserror::Error;
+
+use timekeeping::WallClockNs;
+
+#[derive(Clone)]
+pub struct ReplayConfig
+{
+    // Largest accepted difference between a message's station time and ours.
+    pub window_ns: u64,
+    // Message ids remembered.
+    pub max_ids: usize,
+    // Reject messages without a message_id.
+    pub require_id: bool,
+}
+
+impl Default for ReplayConfig
+{
+    fn default() -> ReplayConfig {
+        ReplayConfig {
+            window_ns: 5*60*1000*1000*1000,
+            max_ids: 1 << 20,
+            require_id: false,
+        }
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ReplayError {
+    #[error("duplicate message id")]
+    Duplicate,
+    #[error("message outside the replay window")]
+    Stale,
+    #[error("message without a station time")]
+    Unstamped,
+    #[error("message without an id")]
+    Unidentified,
+}
+
+#[derive(Default)]
+struct SeenIds
+{
+    // id -> station time it was sent at
+    times: HashMap<Vec<u8>, u64>,
+    // ids in the order they were first seen
+    order: VecDeque<Vec<u8>>,
+    // Station time at or before which messages are rejected, raised as ids
+    // are evicted from a full set.
+    floor: u64,
+}
+
+pub struct ReplayGuard
+{
+    config: ReplayConfig,
+    seen: Mutex<SeenIds>,
+
+    // Since the last report.
+    duplicates: AtomicU64,
+    stale: AtomicU64,
+    unstamped: AtomicU64,
+    unidentified: AtomicU64,
+}
+
+impl ReplayGuard
+{
+    pub fn new(config: ReplayConfig) -> ReplayGuard {
+        ReplayGuard {
+            config: config,
+            seen: Mutex::new(SeenIds::default()),
+            duplicates: AtomicU64::new(0),
+            stale: AtomicU64::new(0),
+            unstamped: AtomicU64::new(0),
+            unidentified: AtomicU64::new(0),
+        }
+    }
+
+    /// Checks a message with id `id` (empty if it has none) sent at
+    /// `station_time` (unix ns, if stamped) and received at `now`, and
+    /// remembers its id if it is accepted.
+    pub fn check(&self, id: &[u8], station_time: Option<WallClockNs>, now: WallClockNs) -> Result<(), ReplayError> {
+        let res = self.check_inner(id, station_time, now);
+        let counter = match res {
+            Err(ReplayError::Duplicate) => &self.duplicates,
+            Err(ReplayError::Stale) => &self.stale,
+            Err(ReplayError::Unstamped) => &self.unstamped,
+            _ if id.is_empty() => &self.unidentified,
+            Ok(()) | Err(ReplayError::Unidentified) => return res,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        res
+    }
+
+    fn check_inner(&self, id: &[u8], station_time: Option<WallClockNs>, now: WallClockNs) -> Result<(), ReplayError> {
+        if id.is_empty() {
+            return if self.config.require_id { Err(ReplayError::Unidentified) } else { Ok(()) }
+        }
+        let sent = match station_time {
+            Some(t) => t.0,
+            None => return Err(ReplayError::Unstamped),
+        };
+        let now = now.0;
+        let window = self.config.window_ns;
+        if sent.saturating_add(window) < now || sent > now.saturating_add(window) {
+            return Err(ReplayError::Stale)
+        }
+
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        // forget ids that would now be rejected as stale anyway
+        while let Some(oldest) = seen.order.front().cloned() {
+            match seen.times.get(&oldest) {
+                Some(t) if t.saturating_add(window) >= now => break,
+                _ => {},
+            }
+            seen.order.pop_front();
+            seen.times.remove(&oldest);
+        }
+
+        if sent <= seen.floor {
+            return Err(ReplayError::Stale)
+        }
+        if seen.times.contains_key(id) {
+            return Err(ReplayError::Duplicate)
+        }
+        while seen.order.len() >= self.config.max_ids.max(1) {
+            let evicted = match seen.order.pop_front() {
+                Some(e) => e,
+                None => break,
+            };
+            if let Some(t) = seen.times.remove(&evicted) {
+                seen.floor = seen.floor.max(t);
+            }
+        }
+        seen.times.insert(id.to_vec(), sent);
+        seen.order.push_back(id.to_vec());
+        Ok(())
+    }
+
+    /// Report line, resetting the counters.
+    pub fn take_report(&self) -> String {
+        let tracked = self.seen.lock().unwrap_or_else(|e| e.into_inner()).times.len();
+        format!("replay guard duplicates {} stale {} unstamped {} unidentified {} tracked ids {}",
+            self.duplicates.swap(0, Ordering::Relaxed),
+            self.stale.swap(0, Ordering::Relaxed),
+            self.unstamped.swap(0, Ordering::Relaxed),
+            self.unidentified.swap(0, Ordering::Relaxed),
+            tracked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use replay::*;
+
+    const S: u64 = 1000*1000*1000;
+
+    fn check(guard: &ReplayGuard, id: &[u8], sent: Option<u64>, now: u64) -> Result<(), ReplayError> {
+        guard.check(id, sent.map(WallClockNs), WallClockNs(now))
+    }
+
+    #[test]
+    fn test_replay_window() {
+        let guard = ReplayGuard::new(ReplayConfig { window_ns: 60*S, ..ReplayConfig::default() });
+        let now = 1000*S;
+
+        assert_eq!(check(&guard, b"a", Some(now - 5*S), now), Ok(()));
+        assert_eq!(check(&guard, b"a", Some(now - 5*S), now + S), Err(ReplayError::Duplicate));
+        // outside the window either way
+        assert_eq!(check(&guard, b"b", Some(now - 61*S), now), Err(ReplayError::Stale));
+        assert_eq!(check(&guard, b"c", Some(now + 61*S), now), Err(ReplayError::Stale));
+        // unstamped messages could be replayed once their id is forgotten
+        assert_eq!(check(&guard, b"d", None, now), Err(ReplayError::Unstamped));
+        assert_eq!(check(&guard, b"d", Some(now), now), Ok(()));
+        assert_eq!(check(&guard, b"d", Some(now), now + 30*S), Err(ReplayError::Duplicate));
+        // no id
+        assert_eq!(check(&guard, b"", Some(now), now), Ok(()));
+
+        // once outside the window a replay is stale, and the id is forgotten
+        assert_eq!(check(&guard, b"a", Some(now - 5*S), now + 56*S), Err(ReplayError::Stale));
+        assert_eq!(check(&guard, b"x", Some(now + 200*S), now + 200*S), Ok(()));
+        assert_eq!(guard.take_report(), "replay guard duplicates 2 stale 3 unstamped 1 unidentified 1 tracked ids 1");
+        assert_eq!(guard.take_report(), "replay guard duplicates 0 stale 0 unstamped 0 unidentified 0 tracked ids 1");
+
+        let strict = ReplayGuard::new(ReplayConfig { require_id: true, ..ReplayConfig::default() });
+        assert_eq!(check(&strict, b"", Some(now), now), Err(ReplayError::Unidentified));
+    }
+
+    #[test]
+    fn test_replay_full_set() {
+        let guard = ReplayGuard::new(ReplayConfig { window_ns: 60*S, max_ids: 2, ..ReplayConfig::default() });
+        let now = 1000*S;
+        assert_eq!(check(&guard, b"a", Some(now - 3*S), now), Ok(()));
+        assert_eq!(check(&guard, b"b", Some(now - 2*S), now), Ok(()));
+        // evicts a, so nothing sent at or before it is accepted any more
+        assert_eq!(check(&guard, b"c", Some(now - S), now), Ok(()));
+        assert_eq!(check(&guard, b"a", Some(now - 3*S), now), Err(ReplayError::Stale));
+        assert_eq!(check(&guard, b"e", Some(now - 4*S), now), Err(ReplayError::Stale));
+        assert_eq!(check(&guard, b"b", Some(now - 2*S), now), Err(ReplayError::Duplicate));
+        assert_eq!(check(&guard, b"f", Some(now), now), Ok(()));
+    }
+}
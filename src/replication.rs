@@ -0,0 +1,391 @@
+//
+// Session Replication
+//
+// Detectors behind asymmetric routing can see a client's packets without
+// having received its registration from their own station. With replication
+// enabled, the detector that accepts a registration publishes the resulting
+// sessions as a SessionReplication protobuf on its redis (REPLICATION_CHANNEL)
+// and every peer ingests them from there alongside its own registrations.
+//
+// - Only one core per detector publishes (every core receives the same
+//   registrations), but every core ingests from every peer.
+// - Loops are suppressed in two ways: sessions received from a peer are never
+//   published again, and messages carrying our own id as their origin are
+//   discarded, so a peer list that (mistakenly) includes ourselves is harmless.
+// - Replicated sessions carry the lifetime they were accepted with. The time
+//   spent reaching the peer is not subtracted, so replicated sessions may
+//   outlive the original by that much.
+// - The station's updates are published too, flagged as such, with the new
+//   lifetime: peers refresh the sessions they track, like our own station's
+//   update, rather than registering them.
+//
+// Handing sessions off to a standby detector uses the same message: the
+// draining detector publishes its whole session map, in chunks of
+// HANDOFF_CHUNK sessions with their remaining lifetimes, on HANDOFF_CHANNEL of
+// the standby's redis. Pubsub is not durable, so the standby must already be
+// running (with DETECTOR_ACCEPT_HANDOFF) when the handoff is started.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use protobuf::{Message, RepeatedField};
+
+use redis;
+
+use error::{DetectorError, DetectorResult, error_chain};
+use feedback::EventPublisher;
+use ingest::{REDIS_URL, get_redis_conn, parse_message};
+use sessions::{ClientPorts, SessionDetails, SessionError, SessionResult};
+use signalling::{ReplicatedSession, SessionReplication};
+use timekeeping::duration_ns;
+
+pub const REPLICATION_CHANNEL: &'static str = "detector_replication";
+pub const HANDOFF_CHANNEL: &'static str = "detector_handoff";
+
+// Sessions per handoff message.
+const HANDOFF_CHUNK: usize = 1000;
+
+#[derive(Clone)]
+pub struct ReplicationConfig
+{
+    // Identifies this detector as the origin of the sessions it publishes.
+    pub detector_id: u32,
+
+    // Redis urls of the peer detectors to ingest sessions from.
+    pub peers: Vec<String>,
+
+    // Whether this tracker publishes the sessions it accepts.
+    pub publish: bool,
+}
+
+// Counters for the sessions ingested from one peer, reset by take_report.
+pub struct PeerStats
+{
+    pub url: String,
+    received: AtomicU64,
+    accepted: AtomicU64,
+    looped: AtomicU64,
+    invalid: AtomicU64,
+}
+
+impl PeerStats
+{
+    fn new(url: &str) -> PeerStats {
+        PeerStats {
+            url: url.to_string(),
+            received: AtomicU64::new(0),
+            accepted: AtomicU64::new(0),
+            looped: AtomicU64::new(0),
+            invalid: AtomicU64::new(0),
+        }
+    }
+}
+
+pub struct Replication
+{
+    detector_id: u32,
+
+    // None if this tracker doesn't publish.
+    publisher: Option<Mutex<EventPublisher>>,
+    published: AtomicU64,
+
+    peers: Vec<PeerStats>,
+}
+
+impl Replication
+{
+    /// Replication for `config`, publishing on the local redis if enabled.
+    pub fn spawn(config: &ReplicationConfig) -> Replication {
+        let publisher = match config.publish {
+            true => Some(EventPublisher::spawn(REDIS_URL, REPLICATION_CHANNEL)),
+            false => None,
+        };
+        Replication::new(config.detector_id, &config.peers, publisher)
+    }
+
+    pub fn new(detector_id: u32, peers: &[String], publisher: Option<EventPublisher>) -> Replication {
+        Replication {
+            detector_id: detector_id,
+            publisher: publisher.map(Mutex::new),
+            published: AtomicU64::new(0),
+            peers: peers.iter().map(|p| PeerStats::new(p)).collect(),
+        }
+    }
+
+    pub fn peers(&self) -> &[PeerStats] {
+        &self.peers
+    }
+
+    /// Publishes sessions accepted from our station. Does nothing if this
+    /// tracker doesn't publish.
+    pub fn publish(&self, sessions: &[SessionDetails]) {
+        self.send(sessions, false)
+    }
+
+    /// Publishes sessions refreshed by our station's update, their timeout
+    /// the new lifetime. Does nothing if this tracker doesn't publish.
+    pub fn publish_update(&self, sessions: &[SessionDetails]) {
+        self.send(sessions, true)
+    }
+
+    fn send(&self, sessions: &[SessionDetails], update: bool) {
+        let publisher = match self.publisher {
+            Some(ref p) => p,
+            None => return,
+        };
+        let mut msg = SessionReplication::new();
+        msg.set_origin(self.detector_id);
+        msg.set_sessions(RepeatedField::from_vec(sessions.iter().map(ReplicatedSession::from).collect()));
+        if update {
+            msg.set_update(true);
+        }
+        publisher.lock().unwrap_or_else(|e| e.into_inner()).publish(&msg);
+        self.published.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Sessions to ingest from a message received from peer number `peer`,
+    /// and whether they are the station's update, or None if the message is
+    /// ours or invalid. All sessions from one message are accepted or none
+    /// are, like the v4/v6 pair of a registration.
+    pub fn receive(&self, peer: usize, payload: &[u8]) -> Option<(Vec<SessionDetails>, bool)> {
+        let stats = &self.peers[peer];
+        stats.received.fetch_add(1, Ordering::Relaxed);
+
+        let msg: SessionReplication = match parse_message(payload, "SessionReplication") {
+            Ok(m) => m,
+            Err(e) => {
+                debug!("{} from {}", error_chain(&e), stats.url);
+                stats.invalid.fetch_add(1, Ordering::Relaxed);
+                return None
+            },
+        };
+        if msg.get_origin() == self.detector_id {
+            stats.looped.fetch_add(1, Ordering::Relaxed);
+            return None
+        }
+        let sessions: Result<Vec<SessionDetails>, SessionError> =
+            msg.get_sessions().iter().map(SessionResult::from).collect();
+        match sessions {
+            Ok(s) => {
+                stats.accepted.fetch_add(s.len() as u64, Ordering::Relaxed);
+                Some((s, msg.get_update()))
+            },
+            Err(e) => {
+                debug!("{} replicated from {}", error_chain(&DetectorError::from(e)), stats.url);
+                stats.invalid.fetch_add(1, Ordering::Relaxed);
+                None
+            },
+        }
+    }
+
+    /// Counters since the last call, one line for publishing followed by one
+    /// per peer.
+    pub fn take_report(&self) -> Vec<String> {
+        let dropped = match self.publisher {
+            Some(ref p) => p.lock().unwrap_or_else(|e| e.into_inner()).take_dropped(),
+            None => 0,
+        };
+        let mut lines = vec![format!("replication published {} dropped {}",
+            self.published.swap(0, Ordering::Relaxed), dropped)];
+        for p in self.peers.iter() {
+            lines.push(format!("replication peer {} received {} accepted {} looped {} invalid {}",
+                p.url,
+                p.received.swap(0, Ordering::Relaxed),
+                p.accepted.swap(0, Ordering::Relaxed),
+                p.looped.swap(0, Ordering::Relaxed),
+                p.invalid.swap(0, Ordering::Relaxed)));
+        }
+        lines
+    }
+}
+
+fn ip_bytes(ip: &IpAddr) -> Vec<u8> {
+    match ip {
+        IpAddr::V4(a) => a.octets().to_vec(),
+        IpAddr::V6(a) => a.octets().to_vec(),
+    }
+}
+
+fn ip_from_bytes(b: &[u8]) -> Option<IpAddr> {
+    match b.len() {
+        4 => Some(IpAddr::V4(Ipv4Addr::new(b[0], b[1], b[2], b[3]))),
+        16 => {
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(b);
+            Some(IpAddr::V6(Ipv6Addr::from(octets)))
+        },
+        _ => None,
+    }
+}
+
+/// Handoff of `sessions` from detector `origin`, split into messages.
+pub fn handoff_messages(origin: u32, sessions: &[SessionDetails]) -> Vec<SessionReplication> {
+    sessions.chunks(HANDOFF_CHUNK).map(|chunk| {
+        let mut msg = SessionReplication::new();
+        msg.set_origin(origin);
+        msg.set_sessions(RepeatedField::from_vec(chunk.iter().map(ReplicatedSession::from).collect()));
+        msg
+    }).collect()
+}
+
+/// Publishes `msgs` on the handoff channel of the redis server at `url`.
+pub fn send_handoff(url: &str, msgs: &[SessionReplication]) -> DetectorResult<()> {
+    let con = get_redis_conn(url)?;
+    for msg in msgs.iter() {
+        let payload = msg.write_to_bytes()
+            .map_err(|e| DetectorError::Serialize { message: "handoff", source: e })?;
+        let _: i64 = redis::cmd("PUBLISH").arg(HANDOFF_CHANNEL).arg(payload).query(&con)
+            .map_err(|e| DetectorError::redis(&format!("publish to {}", HANDOFF_CHANNEL), e))?;
+    }
+    Ok(())
+}
+
+impl From<&SessionDetails> for ReplicatedSession {
+    fn from(sd: &SessionDetails) -> Self {
+        let mut rs = ReplicatedSession::new();
+        rs.set_client_ip(ip_bytes(&sd.client_ip));
+        rs.set_phantom_ip(ip_bytes(&sd.phantom_ip));
+        rs.set_phantom_port(sd.phantom_port);
+        rs.set_timeout_ns(sd.timeout_ns());
+        rs.set_registration_source(sd.registration_source);
+        rs.set_max_connections(sd.max_connections);
+        rs.set_mode(sd.mode);
+        rs.set_tenant_id(sd.tenant);
+        rs.set_extension_ns(sd.extension.map_or(0, duration_ns));
+        if let Some(ports) = sd.client_ports {
+            rs.mut_client_ports().set_first(ports.first as u32);
+            rs.mut_client_ports().set_last(ports.last as u32);
+        }
+        if let Some(group) = sd.group {
+            rs.set_group_id(group);
+        }
+        rs
+    }
+}
+
+impl From<&ReplicatedSession> for SessionResult {
+    fn from(rs: &ReplicatedSession) -> Self {
+        let phantom = ip_from_bytes(rs.get_phantom_ip()).ok_or(SessionError::InvalidPhantom)?;
+        let client = ip_from_bytes(rs.get_client_ip()).ok_or(SessionError::InvalidClient)?;
+        let mut sd = SessionDetails::builder()
+            .client(client)
+            .phantom(phantom)
+            .timeout(Duration::from_nanos(rs.get_timeout_ns()))
+            .source(rs.get_registration_source())
+            .max_connections(rs.get_max_connections())
+            .mode(rs.get_mode())
+            .tenant(rs.get_tenant_id())
+            .build()?;
+        sd.phantom_port = rs.get_phantom_port();
+        if rs.get_extension_ns() != 0 {
+            sd.extension = Some(Duration::from_nanos(rs.get_extension_ns()));
+        }
+        if rs.has_client_ports() {
+            sd.client_ports = Some(ClientPorts::new(rs.get_client_ports().get_first(), rs.get_client_ports().get_last())?);
+        }
+        if rs.has_group_id() {
+            sd.group = Some(rs.get_group_id());
+        }
+        Ok(sd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use replication::*;
+    use signalling::{RegistrationSource, SessionMode};
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_replication_encoding() {
+        let mut sd = SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_nanos(5000)).unwrap();
+        sd.registration_source = RegistrationSource::API;
+        sd.max_connections = 3;
+        sd.mode = SessionMode::Decoy;
+        sd.tenant = 7;
+        sd.extension = Some(Duration::from_secs(90));
+        sd.client_ports = Some(ClientPorts { first: 1024, last: 2047 });
+        sd.group = Some(0x1234);
+        let rs = ReplicatedSession::from(&sd);
+        assert_eq!(rs.get_client_ip(), &[192, 168, 0, 1]);
+
+        let decoded = SessionResult::from(&rs).unwrap();
+        assert_eq!(decoded.get_key(), sd.get_key());
+        assert_eq!(decoded.timeout_ns(), 5000);
+        assert_eq!(decoded.registration_source, RegistrationSource::API);
+        assert_eq!(decoded.max_connections, 3);
+        assert_eq!(decoded.mode, SessionMode::Decoy);
+        assert_eq!(decoded.tenant, 7);
+        assert_eq!(decoded.extension, Some(Duration::from_secs(90)));
+        assert_eq!(decoded.client_ports, Some(ClientPorts { first: 1024, last: 2047 }));
+        assert_eq!(decoded.group, Some(0x1234));
+
+        // v6 session without a client keeps the placeholder client
+        let sd = SessionDetails::new("", "2001::1234", 443, Duration::from_nanos(5000)).unwrap();
+        assert_eq!(SessionResult::from(&ReplicatedSession::from(&sd)).unwrap().get_key(), sd.get_key());
+        assert_eq!(SessionResult::from(&ReplicatedSession::from(&sd)).unwrap().extension, None);
+        assert_eq!(SessionResult::from(&ReplicatedSession::from(&sd)).unwrap().client_ports, None);
+        assert_eq!(SessionResult::from(&ReplicatedSession::from(&sd)).unwrap().group, None);
+
+        let mut bad = ReplicatedSession::from(&sd);
+        bad.set_phantom_ip(vec![1, 2, 3]);
+        assert!(SessionResult::from(&bad).is_err());
+    }
+
+    #[test]
+    fn test_replication_loop_suppression() {
+        let (tx, rx) = mpsc::sync_channel(4);
+        let peers = vec!["redis://peer/".to_string()];
+        let r = Replication::new(7, &peers, Some(EventPublisher::from_sender(tx)));
+
+        let sd = SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_nanos(5000)).unwrap();
+        r.publish(&[sd]);
+        let payload = rx.recv().unwrap();
+        let ours: SessionReplication = Message::parse_from_bytes(&payload).unwrap();
+        assert_eq!(ours.get_origin(), 7);
+        assert_eq!(ours.get_sessions().len(), 1);
+        assert!(!ours.get_update());
+
+        // our own message coming back is discarded
+        assert!(r.receive(0, &payload).is_none());
+
+        let mut theirs = ours.clone();
+        theirs.set_origin(8);
+        let (sessions, update) = r.receive(0, &theirs.write_to_bytes().unwrap()).unwrap();
+        assert_eq!((sessions.len(), update), (1, false));
+
+        theirs.mut_sessions()[0].set_client_ip(vec![]);
+        assert!(r.receive(0, &theirs.write_to_bytes().unwrap()).is_none());
+        assert!(r.receive(0, &[0xff, 0xff]).is_none());
+
+        assert_eq!(r.take_report(), vec![
+            "replication published 1 dropped 0".to_string(),
+            "replication peer redis://peer/ received 4 accepted 1 looped 1 invalid 2".to_string(),
+        ]);
+
+        // updates are flagged
+        r.publish_update(&[sd]);
+        let mut update: SessionReplication = Message::parse_from_bytes(&rx.recv().unwrap()).unwrap();
+        assert!(update.get_update());
+        update.set_origin(8);
+        assert!(r.receive(0, &update.write_to_bytes().unwrap()).unwrap().1);
+
+        // receive only trackers don't publish
+        let r = Replication::new(7, &peers, None);
+        r.publish(&[sd]);
+        assert_eq!(r.take_report()[0], "replication published 0 dropped 0");
+    }
+
+    #[test]
+    fn test_handoff_messages() {
+        let sessions: Vec<SessionDetails> = (0..2500)
+            .map(|i| SessionDetails::new(&format!("10.0.{}.{}", i / 256, i % 256), "192.0.0.1", 443, Duration::from_nanos(5000)).unwrap())
+            .collect();
+        let msgs = handoff_messages(3, &sessions);
+        assert_eq!(msgs.iter().map(|m| m.get_sessions().len()).collect::<Vec<usize>>(), vec![1000, 1000, 500]);
+        assert!(msgs.iter().all(|m| m.get_origin() == 3));
+        assert!(handoff_messages(3, &[]).is_empty());
+    }
+}
@@ -0,0 +1,161 @@
+//
+// Station Sequence Numbers
+//
+// A registration the detector never saw may have been lost between the
+// station and the detector (a redis hiccup, a dropped pubsub client) or never
+// sent at all. Station publishers number the messages they publish
+// (StationToDetector publisher_id and sequence, see signalling.proto), and the
+// ingest thread follows each publisher's numbers:
+//
+// - the next number is as expected;
+// - numbers were skipped: they are counted missing;
+// - the last number again is a duplicate, counted late;
+// - a number below the last: the publisher restarted and its stream starts
+//   over. The numbers before it in the new stream are counted missing, so a
+//   restart is noticed even if its first messages were lost. A publisher's
+//   messages arrive in order over redis, so this isn't mistaken for a
+//   reordered message.
+//
+// The first message received from a publisher starts following it, the
+// messages before it (sent before we subscribed) aren't counted missing.
+// Stored registrations read while priming carry old numbers and are skipped.
+//
+// Counts are reported per publisher once per report period, and the periods
+// with messages missing or late are sent to the station as a SequenceGaps
+// event (gaps_event).
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use signalling::{DetectorEvent, DetectorToStation, SequenceGapCount};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PublisherCounts
+{
+    // Since the last take.
+    pub received: u64,
+    pub missing: u64,
+    pub late: u64,
+    pub restarts: u64,
+    // Last sequence number received, since the publisher last restarted.
+    pub last: u64,
+}
+
+#[derive(Default)]
+pub struct SequenceTracker
+{
+    publishers: Mutex<BTreeMap<u32, PublisherCounts>>,
+}
+
+impl SequenceTracker
+{
+    pub fn new() -> SequenceTracker {
+        SequenceTracker::default()
+    }
+
+    /// Follows message number `sequence` of publisher `publisher`.
+    pub fn observe(&self, publisher: u32, sequence: u64) {
+        let mut publishers = self.publishers.lock().unwrap_or_else(|e| e.into_inner());
+        let counts = match publishers.get_mut(&publisher) {
+            Some(c) => c,
+            None => {
+                publishers.insert(publisher, PublisherCounts { received: 1, last: sequence, ..PublisherCounts::default() });
+                return
+            },
+        };
+        counts.received += 1;
+        if sequence > counts.last {
+            counts.missing += sequence - counts.last - 1;
+            counts.last = sequence;
+        } else if sequence < counts.last {
+            counts.restarts += 1;
+            counts.missing += sequence.saturating_sub(1);
+            counts.last = sequence;
+        } else {
+            counts.late += 1;
+        }
+    }
+
+    /// Counts per publisher since the last call.
+    pub fn take_counts(&self) -> Vec<(u32, PublisherCounts)> {
+        let mut publishers = self.publishers.lock().unwrap_or_else(|e| e.into_inner());
+        publishers.iter_mut().map(|(id, c)| {
+            let taken = *c;
+            *c = PublisherCounts { last: c.last, ..PublisherCounts::default() };
+            (*id, taken)
+        }).collect()
+    }
+}
+
+/// Counts per publisher, formatted for the periodic report.
+pub fn format_counts(counts: &[(u32, PublisherCounts)]) -> String {
+    let publishers: Vec<String> = counts.iter().map(|&(id, ref c)| {
+        format!("{} received {} missing {} late {} restarts {} last {}",
+            id, c.received, c.missing, c.late, c.restarts, c.last)
+    }).collect();
+    format!("sequence publishers {}", publishers.join(", "))
+}
+
+/// SequenceGaps event of core `core` for `counts`, None if nothing went
+/// missing or arrived late.
+pub fn gaps_event(counts: &[(u32, PublisherCounts)], core: u32) -> Option<DetectorToStation> {
+    if counts.iter().all(|c| c.1.missing == 0 && c.1.late == 0) {
+        return None
+    }
+    let mut event = DetectorToStation::new();
+    event.set_event(DetectorEvent::SequenceGaps);
+    event.set_core(core);
+    for &(id, ref c) in counts.iter() {
+        let mut gap = SequenceGapCount::new();
+        gap.set_publisher_id(id);
+        gap.set_received(c.received);
+        gap.set_missing(c.missing);
+        gap.set_late(c.late);
+        gap.set_last_sequence(c.last);
+        event.mut_sequence_gaps().push(gap);
+    }
+    Some(event)
+}
+
+#[cfg(test)]
+mod tests {
+    use sequence::*;
+
+    #[test]
+    fn test_sequence_tracker() {
+        let seqs = SequenceTracker::new();
+        // joined mid-stream
+        for s in [41, 42, 45, 46, 46, 47].iter() {
+            seqs.observe(7, *s);
+        }
+        seqs.observe(9, 1);
+        seqs.observe(9, 2);
+        // restarted
+        seqs.observe(9, 1);
+        seqs.observe(9, 3);
+        // and restarted again, its first message lost
+        seqs.observe(9, 2);
+
+        let counts = seqs.take_counts();
+        assert_eq!(counts, vec![
+            (7, PublisherCounts { received: 6, missing: 2, late: 1, restarts: 0, last: 47 }),
+            (9, PublisherCounts { received: 5, missing: 2, late: 0, restarts: 2, last: 2 }),
+        ]);
+        assert_eq!(format_counts(&counts), "sequence publishers \
+            7 received 6 missing 2 late 1 restarts 0 last 47, \
+            9 received 5 missing 2 late 0 restarts 2 last 2");
+
+        let event = gaps_event(&counts, 2).unwrap();
+        assert_eq!(event.get_event(), DetectorEvent::SequenceGaps);
+        assert_eq!(event.get_core(), 2);
+        assert_eq!(event.get_sequence_gaps().len(), 2);
+        assert_eq!(event.get_sequence_gaps()[0].get_missing(), 2);
+        assert_eq!(event.get_sequence_gaps()[1].get_last_sequence(), 2);
+
+        // the next period picks up where this one stopped
+        seqs.observe(7, 48);
+        let counts = seqs.take_counts();
+        assert_eq!(counts[0].1, PublisherCounts { received: 1, last: 48, ..PublisherCounts::default() });
+        assert!(gaps_event(&counts, 2).is_none());
+    }
+}
@@ -0,0 +1,144 @@
+//
+// Session Count Alerts
+//
+// Every core tracks every session of the station, so a core whose session
+// count drifts away from its peers' (or from what the station expects) has
+// most likely stopped ingesting registrations. Once per report period the
+// core checks its tracked session count and alerts the station, with a
+// SessionCountAlert event on the detector event channel and a log line:
+//
+// - when the count rises above the high watermark (DETECTOR_SESSION_ALERT_HIGH)
+//   or falls below the low one (DETECTOR_SESSION_ALERT_LOW), once per
+//   crossing, and again when it is back between them;
+// - when the count changed by more than DETECTOR_SESSION_ALERT_CHANGE_PCT
+//   percent of the previous period's, every period it does. Changes from
+//   fewer than MIN_CHANGE_BASE sessions aren't judged, so an idle station
+//   getting its first registrations doesn't alert.
+//
+// Each is disabled unless set.
+
+use signalling::{DetectorEvent, DetectorToStation, SessionCountChange};
+
+// Smallest previous count a change is judged against.
+pub const MIN_CHANGE_BASE: u64 = 10;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SessionAlertConfig
+{
+    pub high: Option<u64>,
+    pub low: Option<u64>,
+    // Largest change (percent of the previous count) per period.
+    pub max_change_pct: Option<u64>,
+}
+
+pub struct SessionAlerts
+{
+    config: SessionAlertConfig,
+    core: u32,
+    // Count at the last check, None before the first.
+    previous: Option<u64>,
+    // The watermark crossed, AboveHigh or BelowLow, if any.
+    crossed: Option<SessionCountChange>,
+}
+
+impl SessionAlerts
+{
+    /// Alerts of core `core`, None if `config` enables none.
+    pub fn new(config: SessionAlertConfig, core: u32) -> Option<SessionAlerts> {
+        if config == SessionAlertConfig::default() {
+            return None
+        }
+        Some(SessionAlerts {
+            config: config,
+            core: core,
+            previous: None,
+            crossed: None,
+        })
+    }
+
+    /// Alerts for a tracked session count of `count`, logged.
+    pub fn check(&mut self, count: u64) -> Vec<DetectorToStation> {
+        let mut alerts = Vec::new();
+        let previous = self.previous.replace(count);
+
+        let crossed = match (self.config.high, self.config.low) {
+            (Some(high), _) if count > high => Some(SessionCountChange::AboveHigh),
+            (_, Some(low)) if count < low => Some(SessionCountChange::BelowLow),
+            _ => None,
+        };
+        if crossed != self.crossed {
+            alerts.push(crossed.unwrap_or(SessionCountChange::WithinWatermarks));
+            self.crossed = crossed;
+        }
+
+        if let (Some(pct), Some(prev)) = (self.config.max_change_pct, previous) {
+            if prev >= MIN_CHANGE_BASE {
+                let change = count.abs_diff(prev);
+                if change.saturating_mul(100) > prev.saturating_mul(pct) {
+                    alerts.push(match count > prev {
+                        true => SessionCountChange::Surge,
+                        false => SessionCountChange::Drop,
+                    });
+                }
+            }
+        }
+
+        alerts.into_iter().map(|change| {
+            let prev = previous.unwrap_or(0);
+            match change {
+                SessionCountChange::WithinWatermarks =>
+                    info!("session count {} back within watermarks", count),
+                _ => warn!("session count alert {:?}: {} sessions, {} at the last check", change, count, prev),
+            }
+            let mut event = DetectorToStation::new();
+            event.set_event(DetectorEvent::SessionCountAlert);
+            event.set_alert(change);
+            event.set_session_count(count);
+            event.set_previous_session_count(prev);
+            event.set_core(self.core);
+            event
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use session_alerts::*;
+
+    fn changes(alerts: &mut SessionAlerts, count: u64) -> Vec<SessionCountChange> {
+        alerts.check(count).iter().map(|e| e.get_alert()).collect()
+    }
+
+    #[test]
+    fn test_session_alerts() {
+        assert!(SessionAlerts::new(SessionAlertConfig::default(), 0).is_none());
+
+        let config = SessionAlertConfig { high: Some(1000), low: Some(50), max_change_pct: Some(50) };
+        let mut alerts = SessionAlerts::new(config, 3).unwrap();
+        // starting below the low watermark, small counts aren't judged
+        assert_eq!(changes(&mut alerts, 0), vec![SessionCountChange::BelowLow]);
+        assert_eq!(changes(&mut alerts, 8), vec![]);
+        assert_eq!(changes(&mut alerts, 60), vec![SessionCountChange::WithinWatermarks]);
+        assert_eq!(changes(&mut alerts, 80), vec![]);
+        assert_eq!(changes(&mut alerts, 200), vec![SessionCountChange::Surge]);
+        assert_eq!(changes(&mut alerts, 1200), vec![SessionCountChange::AboveHigh, SessionCountChange::Surge]);
+        // once per crossing
+        assert_eq!(changes(&mut alerts, 1300), vec![]);
+
+        let events = alerts.check(20);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].get_event(), DetectorEvent::SessionCountAlert);
+        assert_eq!(events[0].get_alert(), SessionCountChange::BelowLow);
+        assert_eq!(events[1].get_alert(), SessionCountChange::Drop);
+        assert_eq!(events[1].get_session_count(), 20);
+        assert_eq!(events[1].get_previous_session_count(), 1300);
+        assert_eq!(events[1].get_core(), 3);
+
+        // rate of change alone
+        let config = SessionAlertConfig { max_change_pct: Some(10), ..SessionAlertConfig::default() };
+        let mut alerts = SessionAlerts::new(config, 0).unwrap();
+        assert_eq!(changes(&mut alerts, 100), vec![]);
+        assert_eq!(changes(&mut alerts, 110), vec![]);
+        assert_eq!(changes(&mut alerts, 98), vec![SessionCountChange::Drop]);
+    }
+}
@@ -0,0 +1,367 @@
+//
+// Session Export
+//
+// For offline analysis of registration dynamics the detector can write
+// periodic snapshots of its session map to disk. With an export directory
+// configured (DETECTOR_SESSION_EXPORT_DIR) a thread of each core takes a
+// snapshot every SessionExportConfig.interval and appends one SessionRecord
+// per tracked session to the core's current export file.
+//
+// Records are anonymized: a session is identified by an HMAC-SHA256 of its
+// session map key (which holds the client and phantom addresses) under
+// SessionExportConfig.key, and otherwise carries its transport, registration
+// source, phantom port, IP version, lifetime, age, time left and counters,
+// and the ClientId of its client (see client_id.rs) so the sessions of one
+// client can be linked within a day.
+// Without a configured key (DETECTOR_SESSION_EXPORT_KEY_FILE) each core draws
+// a random one at startup, so hashes can only be compared within the files
+// of one core's run.
+//
+// Files hold a sequence of length-delimited SessionRecords (a varint length
+// before each message, as protobuf's write_length_delimited_to), and are named
+//
+//   <prefix>-<unix ms of the first snapshot>.pb
+//
+// in the export directory. A file is closed and a new one started once it
+// reaches rotate_bytes, and after each rotation the oldest files of the
+// prefix are deleted until at most max_files are left, as are files older
+// than max_age if set. Snapshots are always written whole, so a file may go
+// over rotate_bytes by one snapshot.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use protobuf::Message;
+use rand::{self, Rng};
+
+use client_id::ClientId;
+use control::hmac_sha256;
+use error::{DetectorError, DetectorResult, error_chain};
+use sessions::{SessionEntry, SessionTracker};
+use signalling::SessionRecord;
+use timekeeping::{MonotonicNs, WallClockNs};
+
+#[derive(Clone)]
+pub struct SessionExportConfig
+{
+    pub dir: PathBuf,
+    // Start of the file names, e.g. "sessions-0" for core 0.
+    pub prefix: String,
+    // Time between snapshots.
+    pub interval: Duration,
+    // Size after which a new file is started.
+    pub rotate_bytes: u64,
+    // Files of the prefix kept, the current one included.
+    pub max_files: usize,
+    // Files older than this are deleted. None keeps them up to max_files.
+    pub max_age: Option<Duration>,
+    // Key of the hash identifying sessions.
+    pub key: Vec<u8>,
+}
+
+impl SessionExportConfig
+{
+    /// Export of core `lcore` into `dir` with the default schedule and
+    /// limits, under a random key.
+    pub fn new(dir: &str, lcore: i32) -> SessionExportConfig {
+        SessionExportConfig {
+            dir: PathBuf::from(dir),
+            prefix: format!("sessions-{}", lcore),
+            interval: Duration::from_secs(300),
+            rotate_bytes: 64*1024*1024,
+            max_files: 48,
+            max_age: None,
+            key: rand::thread_rng().gen::<[u8; 32]>().to_vec(),
+        }
+    }
+}
+
+/// Anonymized record of the session with map key `key`, seen at `now`
+/// (`wall_now` on our wall clock).
+pub fn record(key: &str, entry: &SessionEntry, hash_key: &[u8], now: MonotonicNs, wall_now: WallClockNs) -> SessionRecord {
+    let mut r = SessionRecord::new();
+    r.set_key_hash(hmac_sha256(hash_key, key.as_bytes()).to_vec());
+    r.set_export_time_unix_ns(wall_now.0);
+    r.set_transport(entry.details.transport);
+    r.set_registration_source(entry.details.registration_source);
+    r.set_phantom_port(entry.details.phantom_port);
+    r.set_ipv6(entry.details.phantom_ip.is_ipv6());
+    r.set_timeout_ns(entry.details.timeout_ns());
+    r.set_age_ns(now.saturating_since(entry.created_at));
+    r.set_remaining_ns(entry.expire_time.saturating_since(now));
+    r.set_registrations(entry.registrations);
+    r.set_connections(entry.connections);
+    r.set_active_connections(entry.active_connections);
+    r.set_extensions(entry.extensions);
+    r.set_client_id(ClientId::new(entry.details.client_ip).hash_at(wall_now));
+    r
+}
+
+// File being written.
+struct ExportFile
+{
+    path: PathBuf,
+    out: BufWriter<File>,
+    bytes: u64,
+}
+
+// Writes snapshots into the rotated export files.
+pub struct ExportWriter
+{
+    config: SessionExportConfig,
+    current: Option<ExportFile>,
+}
+
+impl ExportWriter
+{
+    pub fn new(config: SessionExportConfig) -> ExportWriter {
+        ExportWriter { config: config, current: None }
+    }
+
+    /// Appends `records`, taken at `now`, to the current file, starting a new
+    /// one first if needed. Returns the bytes written and the files deleted.
+    pub fn write_snapshot(&mut self, records: &[SessionRecord], now: WallClockNs) -> DetectorResult<(u64, usize)> {
+        let mut removed = 0;
+        let full = match self.current {
+            Some(ref f) => f.bytes >= self.config.rotate_bytes,
+            None => true,
+        };
+        if full {
+            self.current = None;
+            let path = self.config.dir.join(format!("{}-{}.pb", self.config.prefix, now.0 / (1000*1000)));
+            let file = OpenOptions::new().create(true).append(true).open(&path)
+                .map_err(|e| export_error("open", &path, e))?;
+            self.current = Some(ExportFile { path: path, out: BufWriter::new(file), bytes: 0 });
+            removed = self.prune(now);
+        }
+
+        let mut buf = Vec::new();
+        for r in records {
+            let bytes = r.write_length_delimited_to_bytes()
+                .map_err(|e| DetectorError::Serialize { message: "SessionRecord", source: e })?;
+            buf.extend_from_slice(&bytes);
+        }
+        let file = match self.current {
+            Some(ref mut f) => f,
+            None => return Ok((0, removed)),
+        };
+        let written = file.out.write_all(&buf).and_then(|_| file.out.flush());
+        if let Err(e) = written {
+            let err = export_error("write", &file.path, e);
+            // start over in a new file rather than appending to a torn one
+            self.current = None;
+            return Err(err)
+        }
+        file.bytes += buf.len() as u64;
+        Ok((buf.len() as u64, removed))
+    }
+
+    // Export files of our prefix, oldest first, with the unix ms in their
+    // names.
+    fn files(&self) -> Vec<(u64, PathBuf)> {
+        let start = format!("{}-", self.config.prefix);
+        let mut files: Vec<(u64, PathBuf)> = match fs::read_dir(&self.config.dir) {
+            Ok(entries) => entries.filter_map(|e| e.ok())
+                .filter_map(|e| {
+                    let name = e.file_name().into_string().ok()?;
+                    let ms = name.strip_prefix(&start)?.strip_suffix(".pb")?.parse().ok()?;
+                    Some((ms, e.path()))
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        files.sort();
+        files
+    }
+
+    // Deletes the files past max_files or max_age, never the current one.
+    fn prune(&self, now: WallClockNs) -> usize {
+        let files = self.files();
+        let excess = files.len().saturating_sub(self.config.max_files);
+        let cutoff_ms = self.config.max_age
+            .map(|age| (now.0 / (1000*1000)).saturating_sub(age.as_millis() as u64));
+        let mut removed = 0;
+        for (i, &(ms, ref path)) in files.iter().enumerate() {
+            if self.current.as_ref().map_or(false, |f| f.path == *path) {
+                continue
+            }
+            if i < excess || cutoff_ms.map_or(false, |c| ms < c) {
+                match fs::remove_file(path) {
+                    Ok(_) => removed += 1,
+                    Err(e) => warn!("{}", error_chain(&export_error("delete", path, e))),
+                }
+            }
+        }
+        removed
+    }
+}
+
+fn export_error(context: &'static str, path: &Path, source: ::std::io::Error) -> DetectorError {
+    DetectorError::SessionExport { context: context, path: path.display().to_string(), source: source }
+}
+
+#[derive(Default)]
+struct ExportStats
+{
+    snapshots: AtomicU64,
+    records: AtomicU64,
+    bytes: AtomicU64,
+    removed: AtomicU64,
+    failed: AtomicU64,
+}
+
+// Counters of the export thread.
+pub struct SessionExport
+{
+    stats: Arc<ExportStats>,
+}
+
+impl SessionExport
+{
+    /// Starts the thread exporting the sessions of `tracker`.
+    pub fn spawn(config: SessionExportConfig, tracker: SessionTracker) -> SessionExport {
+        let stats = Arc::new(ExportStats::default());
+        let thread_stats = Arc::clone(&stats);
+        thread::spawn(move || { run_export(config, tracker, &thread_stats) });
+        SessionExport { stats: stats }
+    }
+
+    /// Counters since the last call, formatted for the periodic report.
+    pub fn take_report(&self) -> String {
+        format!("session export snapshots {} records {} bytes {} files deleted {} failed {}",
+            self.stats.snapshots.swap(0, Ordering::Relaxed),
+            self.stats.records.swap(0, Ordering::Relaxed),
+            self.stats.bytes.swap(0, Ordering::Relaxed),
+            self.stats.removed.swap(0, Ordering::Relaxed),
+            self.stats.failed.swap(0, Ordering::Relaxed))
+    }
+}
+
+// Runs for the life of the process.
+fn run_export(config: SessionExportConfig, tracker: SessionTracker, stats: &ExportStats) {
+    if let Err(e) = fs::create_dir_all(&config.dir) {
+        error!("session export disabled: {}", error_chain(&export_error("create", &config.dir, e)));
+        return
+    }
+    let interval = config.interval;
+    let key = config.key.clone();
+    let mut writer = ExportWriter::new(config);
+    loop {
+        thread::sleep(interval);
+        let wall_now = WallClockNs::now();
+        let records = tracker.export_records(&key, wall_now);
+        match writer.write_snapshot(&records, wall_now) {
+            Ok((bytes, removed)) => {
+                stats.snapshots.fetch_add(1, Ordering::Relaxed);
+                stats.records.fetch_add(records.len() as u64, Ordering::Relaxed);
+                stats.bytes.fetch_add(bytes, Ordering::Relaxed);
+                stats.removed.fetch_add(removed as u64, Ordering::Relaxed);
+            },
+            Err(e) => {
+                warn!("{}", error_chain(&e));
+                stats.failed.fetch_add(1, Ordering::Relaxed);
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use session_export::*;
+    use protobuf::CodedInputStream;
+    use sessions::SessionDetails;
+    use signalling::{RegistrationSource, TransportType};
+
+    fn entry() -> SessionEntry {
+        let mut details = SessionDetails::new("192.168.0.1", "10.0.0.1", 443, Duration::from_secs(60)).unwrap();
+        details.transport = TransportType::Obfs4;
+        details.registration_source = RegistrationSource::API;
+        SessionEntry {
+            session_id: 7,
+            active_connections: 1,
+            registrations: 2,
+            connections: 3,
+            created_at: MonotonicNs(5*1000*1000*1000),
+            extensions: 4,
+            ..SessionEntry::new(details, MonotonicNs(50*1000*1000*1000))
+        }
+    }
+
+    fn read_records(path: &PathBuf) -> Vec<SessionRecord> {
+        let data = fs::read(path).unwrap();
+        let mut input = CodedInputStream::from_bytes(&data);
+        let mut records = Vec::new();
+        while !input.eof().unwrap() {
+            let bytes = input.read_bytes().unwrap();
+            records.push(SessionRecord::parse_from_bytes(&bytes).unwrap());
+        }
+        records
+    }
+
+    #[test]
+    fn test_session_export_record() {
+        let now = MonotonicNs(20*1000*1000*1000);
+        let r = record("192.168.0.1-10.0.0.1-443", &entry(), b"key", now, WallClockNs(1234));
+        assert_eq!(r.get_key_hash().len(), 32);
+        assert_eq!(r.get_export_time_unix_ns(), 1234);
+        assert_eq!((r.get_transport(), r.get_registration_source()), (TransportType::Obfs4, RegistrationSource::API));
+        assert_eq!((r.get_phantom_port(), r.get_ipv6()), (443, false));
+        assert_eq!(r.get_timeout_ns(), 60*1000*1000*1000);
+        assert_eq!((r.get_age_ns(), r.get_remaining_ns()), (15*1000*1000*1000, 30*1000*1000*1000));
+        assert_eq!((r.get_registrations(), r.get_connections(), r.get_active_connections(), r.get_extensions()),
+            (2, 3, 1, 4));
+        assert_eq!(r.get_client_id(), ClientId::new("192.168.0.1".parse().unwrap()).hash_at(WallClockNs(1234)));
+
+        // the same key hashes the same under one hash key only
+        let again = record("192.168.0.1-10.0.0.1-443", &entry(), b"key", now, WallClockNs(1234));
+        let other = record("192.168.0.1-10.0.0.1-443", &entry(), b"other key", now, WallClockNs(1234));
+        assert_eq!(r.get_key_hash(), again.get_key_hash());
+        assert!(r.get_key_hash() != other.get_key_hash());
+    }
+
+    #[test]
+    fn test_session_export_rotation() {
+        let dir = ::std::env::temp_dir().join(format!("session-export-test-{}", ::std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        // someone else's file is left alone
+        fs::write(dir.join("sessions-1-1000.pb"), b"").unwrap();
+
+        let mut config = SessionExportConfig::new(dir.to_str().unwrap(), 0);
+        config.rotate_bytes = 1;
+        config.max_files = 2;
+        let mut writer = ExportWriter::new(config);
+        let r = record("k", &entry(), b"key", MonotonicNs(0), WallClockNs(0));
+        let s = WallClockNs(1000*1000*1000);
+
+        let (bytes, removed) = writer.write_snapshot(&[r.clone(), r.clone()], s).unwrap();
+        assert!(bytes > 0);
+        assert_eq!(removed, 0);
+        let first = dir.join("sessions-0-1000.pb");
+        assert_eq!(read_records(&first), vec![r.clone(), r.clone()]);
+
+        // each snapshot goes over 1 byte, so each one starts a file, and the
+        // oldest are deleted past 2
+        assert_eq!(writer.write_snapshot(&[r.clone()], s.saturating_add(1000*1000*1000)).unwrap().1, 0);
+        assert_eq!(writer.write_snapshot(&[], s.saturating_add(2000*1000*1000)).unwrap().1, 1);
+        assert!(!first.exists());
+        assert_eq!(read_records(&dir.join("sessions-0-2000.pb")), vec![r.clone()]);
+        assert!(read_records(&dir.join("sessions-0-3000.pb")).is_empty());
+        assert!(dir.join("sessions-1-1000.pb").exists());
+
+        // and by age
+        let mut config = SessionExportConfig::new(dir.to_str().unwrap(), 0);
+        config.max_age = Some(Duration::from_secs(6));
+        let mut writer = ExportWriter::new(config);
+        assert_eq!(writer.write_snapshot(&[r.clone()], s.saturating_add(7500*1000*1000)).unwrap().1, 1);
+        assert!(!dir.join("sessions-0-2000.pb").exists());
+        assert!(dir.join("sessions-0-3000.pb").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,531 @@
+//
+// Session Map
+//
+// The SessionTracker's map from session key to SessionEntry, with secondary
+// indexes from client address and from phantom address to the keys of the
+// sessions registered for them, so the admin socket can list everything a
+// client or phantom has without walking the whole map.
+//
+// Sessions of tenants other than the default station (see tenants.rs) are
+// indexed by tenant too, so a tenant's sessions can be counted and deleted,
+// and the sessions of a dual-stack registration by their group
+// (SessionDetails.group), so each can find the others.
+//
+// The indexes live next to the map under the same lock and are maintained by
+// the methods that add and remove keys (insert, remove, retain). Reads go
+// straight to the underlying HashMap through Deref. Entries can be modified
+// in place with get_mut, but their details (and so the addresses they are
+// indexed by) never change once inserted.
+//
+// The map itself hashes with the algorithm chosen in SessionConfig.hash (see
+// hashing.rs); the indexes, only used by admin queries, use the default.
+//
+// Sessions are indexed by the addresses of the registration that created
+// them. Where several clients share a key (e.g. v4 keys on the phantom only)
+// only the first one is indexed.
+//
+// A sweepable map (with a cleanup budget configured) also keeps every key in
+// a vector, so stale sessions can be dropped a bounded number of keys at a
+// time (sweep) rather than with one retain over the whole map. Each sweep
+// picks up where the last one stopped. Removing a key moves the last key of
+// the vector into its place, so a key can move behind a sweep in progress; it
+// is then visited by the next one. Other maps don't keep the vector, and
+// their sweeps visit nothing.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::net::IpAddr;
+use std::ops::Deref;
+use std::time::{Duration, Instant};
+
+use ipnetwork::IpNetwork;
+
+use hashing::{HashAlgorithm, MapHasher};
+use sessions::SessionEntry;
+use tenants::{TenantId, DEFAULT_TENANT};
+
+// How often (in keys visited) a sweep with a time budget checks the time.
+const SWEEP_TIME_CHECK: usize = 64;
+
+#[derive(Default)]
+pub struct SessionMap
+{
+    sessions: HashMap<String, SessionEntry, MapHasher>,
+    by_client: HashMap<IpAddr, HashSet<String>>,
+    by_phantom: HashMap<IpAddr, HashSet<String>>,
+    // Default station sessions aren't indexed.
+    by_tenant: HashMap<TenantId, HashSet<String>>,
+    // Ungrouped sessions aren't indexed.
+    by_group: HashMap<u64, HashSet<String>>,
+
+    // Whether `order` and `slots` are kept, for sweeps.
+    sweepable: bool,
+    // Every key, and its position in `order`.
+    order: Vec<String>,
+    slots: HashMap<String, usize, MapHasher>,
+    // Position in `order` the next sweep starts from.
+    cursor: usize,
+
+    // Sessions visited by sweep and retain.
+    #[cfg(test)]
+    visits: usize,
+}
+
+// Work done by one sweep.
+#[derive(Clone, Debug)]
+pub struct SweepBudget
+{
+    // Keys visited, at most.
+    pub entries: usize,
+    // Time spent, roughly. None is unlimited.
+    pub time: Option<Duration>,
+}
+
+// What one sweep did.
+pub struct Sweep
+{
+    pub removed: Vec<SessionEntry>,
+    pub visited: usize,
+    // Whether the sweep reached the last key, the next one starting over.
+    pub wrapped: bool,
+}
+
+impl Deref for SessionMap
+{
+    type Target = HashMap<String, SessionEntry, MapHasher>;
+
+    fn deref(&self) -> &HashMap<String, SessionEntry, MapHasher> {
+        &self.sessions
+    }
+}
+
+impl SessionMap
+{
+    pub fn new() -> SessionMap {
+        SessionMap::default()
+    }
+
+    /// An empty map hashing with `algorithm`, keeping its keys in order for
+    /// sweeps if `sweepable`.
+    pub fn with_hasher(algorithm: HashAlgorithm, sweepable: bool) -> SessionMap {
+        SessionMap {
+            sessions: HashMap::with_hasher(MapHasher::new(algorithm)),
+            slots: HashMap::with_hasher(MapHasher::new(algorithm)),
+            sweepable: sweepable,
+            ..SessionMap::default()
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut SessionEntry> {
+        self.sessions.get_mut(key)
+    }
+
+    pub fn insert(&mut self, key: String, entry: SessionEntry) -> Option<SessionEntry> {
+        let old = self.sessions.insert(key.clone(), entry);
+        match old {
+            Some(ref o) => self.unindex(&key, o),
+            None if self.sweepable => {
+                self.slots.insert(key.clone(), self.order.len());
+                self.order.push(key.clone());
+            },
+            None => {},
+        }
+        index(&mut self.by_client, entry.details.client_ip, &key);
+        index(&mut self.by_phantom, entry.details.phantom_ip, &key);
+        if entry.details.tenant != DEFAULT_TENANT {
+            index(&mut self.by_tenant, entry.details.tenant, &key);
+        }
+        if let Some(group) = entry.details.group {
+            index(&mut self.by_group, group, &key);
+        }
+        old
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<SessionEntry> {
+        let removed = self.sessions.remove(key);
+        if let Some(ref e) = removed {
+            self.unindex(key, e);
+            self.unslot(key);
+        }
+        removed
+    }
+
+    /// Keeps only the sessions for which `keep` returns true.
+    pub fn retain<F: FnMut(&String, &mut SessionEntry) -> bool>(&mut self, mut keep: F) {
+        let mut dropped = Vec::new();
+        #[cfg(test)]
+        self.visits += self.sessions.len();
+        self.sessions.retain(|k, v| {
+            let kept = keep(k, v);
+            if !kept {
+                dropped.push((k.clone(), *v));
+            }
+            kept
+        });
+        for (k, e) in dropped.iter() {
+            self.unindex(k, e);
+            self.unslot(k);
+        }
+    }
+
+    /// Rebuilds the indexes and the key vector from the sessions, after a
+    /// panic may have left them out of step with each other.
+    pub fn repair(&mut self) {
+        self.by_client.clear();
+        self.by_phantom.clear();
+        self.by_tenant.clear();
+        self.by_group.clear();
+        self.order.clear();
+        self.slots.clear();
+        self.cursor = 0;
+        for (key, entry) in self.sessions.iter() {
+            index(&mut self.by_client, entry.details.client_ip, key);
+            index(&mut self.by_phantom, entry.details.phantom_ip, key);
+            if entry.details.tenant != DEFAULT_TENANT {
+                index(&mut self.by_tenant, entry.details.tenant, key);
+            }
+            if let Some(group) = entry.details.group {
+                index(&mut self.by_group, group, key);
+            }
+            if self.sweepable {
+                self.slots.insert(key.clone(), self.order.len());
+                self.order.push(key.clone());
+            }
+        }
+    }
+
+    /// Visits up to `budget.entries` sessions, starting where the last sweep
+    /// stopped, and removes those `stale` returns true for. Stops early once
+    /// `budget.time` has passed or `max_removals` sessions were removed.
+    pub fn sweep<F>(&mut self, budget: &SweepBudget, max_removals: usize, mut stale: F) -> Sweep
+        where F: FnMut(&SessionEntry) -> bool
+    {
+        let start = Instant::now();
+        let mut removed = Vec::new();
+        let mut visited = 0;
+        while self.cursor < self.order.len() && visited < budget.entries && removed.len() < max_removals {
+            if let Some(limit) = budget.time {
+                if visited > 0 && visited % SWEEP_TIME_CHECK == 0 && start.elapsed() >= limit {
+                    break
+                }
+            }
+            visited += 1;
+            #[cfg(test)]
+            self.visits += 1;
+            let is_stale = match self.sessions.get(&self.order[self.cursor]) {
+                Some(e) => stale(e),
+                None => false,
+            };
+            if !is_stale {
+                self.cursor += 1;
+                continue
+            }
+            // the last key takes its place, and is visited next
+            let key = self.order[self.cursor].clone();
+            if let Some(e) = self.remove(&key) {
+                removed.push(e);
+            }
+        }
+        let wrapped = self.cursor >= self.order.len();
+        if wrapped {
+            self.cursor = 0;
+        }
+        Sweep { removed: removed, visited: visited, wrapped: wrapped }
+    }
+
+    /// Keys of the sessions registered by `client`.
+    pub fn client_keys(&self, client: &IpAddr) -> Vec<&String> {
+        match self.by_client.get(client) {
+            Some(keys) => keys.iter().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Keys of the sessions registered for `phantom`.
+    pub fn phantom_keys(&self, phantom: &IpAddr) -> Vec<&String> {
+        match self.by_phantom.get(phantom) {
+            Some(keys) => keys.iter().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Keys of the sessions registered for a phantom in `subnet`.
+    pub fn subnet_keys(&self, subnet: &IpNetwork) -> Vec<&String> {
+        self.by_phantom.iter()
+            .filter(|p| subnet.contains(*p.0))
+            .flat_map(|p| p.1.iter())
+            .collect()
+    }
+
+    /// Keys of the sessions of `tenant`, not the default station.
+    pub fn tenant_keys(&self, tenant: TenantId) -> Vec<&String> {
+        match self.by_tenant.get(&tenant) {
+            Some(keys) => keys.iter().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Keys of the sessions of `group`.
+    pub fn group_keys(&self, group: u64) -> Vec<&String> {
+        match self.by_group.get(&group) {
+            Some(keys) => keys.iter().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Sessions of `tenant`.
+    pub fn tenant_len(&self, tenant: TenantId) -> usize {
+        match tenant {
+            DEFAULT_TENANT => self.sessions.len() - self.by_tenant.values().map(|k| k.len()).sum::<usize>(),
+            _ => self.by_tenant.get(&tenant).map_or(0, |k| k.len()),
+        }
+    }
+
+    fn unindex(&mut self, key: &str, entry: &SessionEntry) {
+        unindex(&mut self.by_client, entry.details.client_ip, key);
+        unindex(&mut self.by_phantom, entry.details.phantom_ip, key);
+        unindex(&mut self.by_tenant, entry.details.tenant, key);
+        if let Some(group) = entry.details.group {
+            unindex(&mut self.by_group, group, key);
+        }
+    }
+
+    fn unslot(&mut self, key: &str) {
+        let slot = match self.slots.remove(key) {
+            Some(s) => s,
+            None => return,
+        };
+        self.order.swap_remove(slot);
+        if let Some(moved) = self.order.get(slot) {
+            if let Some(s) = self.slots.get_mut(moved) {
+                *s = slot;
+            }
+        }
+    }
+}
+
+fn index<A: Hash + Eq>(idx: &mut HashMap<A, HashSet<String>>, addr: A, key: &str) {
+    idx.entry(addr).or_insert_with(HashSet::new).insert(key.to_string());
+}
+
+fn unindex<A: Hash + Eq>(idx: &mut HashMap<A, HashSet<String>>, addr: A, key: &str) {
+    let empty = match idx.get_mut(&addr) {
+        Some(keys) => {
+            keys.remove(key);
+            keys.is_empty()
+        },
+        None => false,
+    };
+    // don't keep an entry per address ever seen
+    if empty {
+        idx.remove(&addr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use session_map::*;
+    use std::sync::{Mutex, RwLock, RwLockWriteGuard};
+    use timekeeping::MonotonicNs;
+    use sessions::{SessionDetails, SessionEntry};
+
+    fn entry(client: &str, phantom: &str, expire_time: u64) -> SessionEntry {
+        let details = SessionDetails::new(client, phantom, 443, Duration::from_nanos(1)).unwrap();
+        SessionEntry::new(details, MonotonicNs(expire_time))
+    }
+
+    #[test]
+    fn test_session_map_indexes() {
+        let mut map = SessionMap::with_hasher(HashAlgorithm::Fx, true);
+        let client: IpAddr = "192.168.0.1".parse().unwrap();
+        let phantom: IpAddr = "10.10.0.1".parse().unwrap();
+        map.insert("a".to_string(), entry("192.168.0.1", "10.10.0.1", 10));
+        map.insert("b".to_string(), entry("192.168.0.1", "10.10.0.2", 20));
+        map.insert("c".to_string(), entry("192.168.0.2", "10.10.0.1", 30));
+        assert_eq!(map.len(), 3);
+
+        let mut keys = map.client_keys(&client);
+        keys.sort();
+        assert_eq!(keys, vec!["a", "b"]);
+        let mut keys = map.phantom_keys(&phantom);
+        keys.sort();
+        assert_eq!(keys, vec!["a", "c"]);
+        let mut keys = map.subnet_keys(&"10.10.0.0/30".parse().unwrap());
+        keys.sort();
+        assert_eq!(keys, vec!["a", "b", "c"]);
+        assert_eq!(map.subnet_keys(&"10.10.0.2/32".parse().unwrap()), vec!["b"]);
+        assert!(map.subnet_keys(&"2001::/16".parse().unwrap()).is_empty());
+
+        map.get_mut("a").unwrap().expire_time = MonotonicNs(40);
+        assert_eq!(map.get("a").unwrap().expire_time, MonotonicNs(40));
+
+        assert!(map.remove("a").is_some());
+        assert!(map.remove("a").is_none());
+        assert_eq!(map.client_keys(&client), vec!["b"]);
+        assert_eq!(map.phantom_keys(&phantom), vec!["c"]);
+
+        map.retain(|_, v| v.expire_time > MonotonicNs(25));
+        assert!(map.client_keys(&client).is_empty());
+        assert!(map.by_client.get(&client).is_none());
+        assert_eq!(map.phantom_keys(&phantom), vec!["c"]);
+
+        // replacing an entry moves its key to the new addresses
+        map.insert("c".to_string(), entry("192.168.0.1", "10.10.0.3", 30));
+        assert_eq!(map.client_keys(&client), vec!["c"]);
+        assert!(map.phantom_keys(&phantom).is_empty());
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.order, vec!["c"]);
+        assert_eq!(map.slots.get("c"), Some(&0));
+
+        // indexes out of step with the sessions are rebuilt
+        map.sessions.insert("d".to_string(), entry("192.168.0.4", "10.10.0.4", 40));
+        map.by_phantom.clear();
+        map.repair();
+        assert_eq!(map.client_keys(&"192.168.0.4".parse().unwrap()), vec!["d"]);
+        assert_eq!(map.phantom_keys(&"10.10.0.3".parse().unwrap()), vec!["c"]);
+        assert_eq!(map.order.len(), 2);
+        assert_eq!(map.slots.len(), 2);
+        assert!(map.remove("d").is_some());
+        assert_eq!(map.order, vec!["c"]);
+
+        // sessions of other tenants
+        let mut tenant = entry("192.168.0.1", "10.10.0.1", 50);
+        tenant.details.tenant = 7;
+        map.insert("t7|e".to_string(), tenant);
+        map.insert("t7|f".to_string(), tenant);
+        assert_eq!(map.tenant_len(7), 2);
+        assert_eq!(map.tenant_len(DEFAULT_TENANT), 1);
+        assert_eq!(map.tenant_len(9), 0);
+        assert!(map.remove("t7|e").is_some());
+        assert_eq!(map.tenant_keys(7), vec!["t7|f"]);
+        map.by_tenant.clear();
+        map.repair();
+        assert_eq!(map.tenant_keys(7), vec!["t7|f"]);
+        assert!(map.remove("t7|f").is_some());
+        assert!(map.by_tenant.is_empty());
+
+        // sessions of a dual-stack registration
+        let mut v4 = entry("192.168.0.1", "10.10.0.1", 60);
+        v4.details.group = Some(3);
+        let mut v6 = entry("2001::1", "2001::5", 60);
+        v6.details.group = Some(3);
+        map.insert("g4".to_string(), v4);
+        map.insert("g6".to_string(), v6);
+        let mut keys = map.group_keys(3);
+        keys.sort();
+        assert_eq!(keys, vec!["g4", "g6"]);
+        assert!(map.group_keys(4).is_empty());
+        map.by_group.clear();
+        map.repair();
+        assert_eq!(map.group_keys(3).len(), 2);
+        assert!(map.remove("g4").is_some());
+        assert_eq!(map.group_keys(3), vec!["g6"]);
+        assert!(map.remove("g6").is_some());
+        assert!(map.by_group.is_empty());
+    }
+
+    // The session lock, counting the sessions visited under each write hold.
+    struct CountingLock
+    {
+        lock: RwLock<SessionMap>,
+        holds: Mutex<Vec<usize>>,
+    }
+
+    struct CountingGuard<'a>
+    {
+        map: RwLockWriteGuard<'a, SessionMap>,
+        visits_before: usize,
+        holds: &'a Mutex<Vec<usize>>,
+    }
+
+    impl CountingLock
+    {
+        fn write(&self) -> CountingGuard<'_> {
+            let map = self.lock.write().unwrap();
+            let visits_before = map.visits;
+            CountingGuard { map: map, visits_before: visits_before, holds: &self.holds }
+        }
+    }
+
+    impl<'a> Drop for CountingGuard<'a>
+    {
+        fn drop(&mut self) {
+            self.holds.lock().unwrap().push(self.map.visits - self.visits_before);
+        }
+    }
+
+    fn sweepable_map() -> SessionMap {
+        let mut map = SessionMap::with_hasher(HashAlgorithm::Fx, true);
+        for i in 0..1000 {
+            // every third session is stale
+            let expire_time = if i % 3 == 0 { 0 } else { 100 };
+            map.insert(format!("k{}", i), entry("192.168.0.1", "10.10.0.1", expire_time));
+        }
+        map
+    }
+
+    #[test]
+    fn test_session_map_sweep() {
+        let lock = CountingLock { lock: RwLock::new(sweepable_map()), holds: Mutex::new(Vec::new()) };
+        let budget = SweepBudget { entries: 64, time: None };
+
+        let mut removed = 0;
+        let mut sweeps = 0;
+        loop {
+            let sweep = lock.write().map.sweep(&budget, usize::max_value(), |e| e.expire_time == MonotonicNs(0));
+            removed += sweep.removed.len();
+            sweeps += 1;
+            if sweep.wrapped {
+                break
+            }
+        }
+        // no write hold visited more than the budget
+        let holds = lock.holds.lock().unwrap().clone();
+        assert_eq!(holds.len(), 16);
+        assert!(holds.iter().all(|&n| n <= budget.entries));
+        assert_eq!(holds.iter().sum::<usize>(), 1000);
+        // where a retain visits the whole map at once
+        lock.write().map.retain(|_, _| true);
+        assert_eq!(*lock.holds.lock().unwrap().last().unwrap(), 666);
+
+        let mut map = lock.lock.into_inner().unwrap();
+        assert_eq!(removed, 334);
+        assert_eq!(sweeps, 16);
+        assert_eq!(map.len(), 666);
+        assert!(map.values().all(|e| e.expire_time == MonotonicNs(100)));
+        assert_eq!(map.order.len(), 666);
+        for (i, k) in map.order.iter().enumerate() {
+            assert_eq!(map.slots.get(k), Some(&i));
+        }
+
+        // a removal cap stops the sweep early, the next starts over
+        map.get_mut("k1").unwrap().expire_time = MonotonicNs(0);
+        map.get_mut("k2").unwrap().expire_time = MonotonicNs(0);
+        let sweep = map.sweep(&budget, 1, |e| e.expire_time == MonotonicNs(0));
+        assert_eq!(sweep.removed.len(), 1);
+
+        // and so does the time budget
+        let slow = SweepBudget { entries: 1000, time: Some(Duration::from_millis(5)) };
+        let sweep = map.sweep(&slow, usize::max_value(), |_| {
+            ::std::thread::sleep(Duration::from_micros(100));
+            false
+        });
+        assert!(sweep.visited < 1000);
+        assert!(sweep.visited >= SWEEP_TIME_CHECK);
+    }
+
+    #[test]
+    fn test_session_map_unsweepable() {
+        // without a cleanup budget no key vector is kept
+        let mut map = SessionMap::with_hasher(HashAlgorithm::Fx, false);
+        map.insert("a".to_string(), entry("192.168.0.1", "10.10.0.1", 0));
+        map.insert("b".to_string(), entry("192.168.0.1", "10.10.0.2", 100));
+        assert!(map.order.is_empty() && map.slots.is_empty());
+        map.repair();
+        assert!(map.order.is_empty() && map.slots.is_empty());
+        assert!(map.remove("a").is_some());
+        assert_eq!(map.len(), 1);
+        let sweep = map.sweep(&SweepBudget { entries: 64, time: None }, usize::max_value(), |_| true);
+        assert_eq!((sweep.visited, sweep.removed.len()), (0, 0));
+    }
+}
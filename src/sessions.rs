@@ -4,11 +4,12 @@
 // This file is used to implement session tacking for the detector. There are a
 // few specifics be to aware of if you are going to modify this file. 
 //
-// Current tracking is done as a Map of string to u64. The string is a
+// Current tracking is done as a Map of string to SessionEntry. The string is a
 // derived from IP addresses of flows so that lookups can be performed quickly
 // when we need to determine whether a flow is associated with a session. The
-// u64 value is the timeout for the session which are periodically cleaned up by
-// the FlowTracker that (currently) instantiates this.
+// SessionEntry holds the timeout for the session (which are periodically
+// cleaned up by the FlowTracker that (currently) instantiates this) along with
+// the details of the registration that created it.
 //
 // Notes:
 //  - The timeout for flows can be updated. This exists for two reasons. 
@@ -20,593 +21,5347 @@
 //         the timeout of the longer session. Keep in mind that if a new
 //         registration is received that has a shorter timeout we still need to
 //         keep the longer timeout. 
+//      The entry counts how many registrations were merged into it this way.
+//      A redelivery of the message last counted (the same message_id, or
+//      publisher and sequence number, see Registration::message_hash) extends
+//      the session like any registration but isn't counted again.
+//      remove_registration only drops the key once every one of them has been
+//      removed; the expire time stays at the longest lifetime seen, as the
+//      lifetimes of the individual registrations are not kept.
 //
 // - The key strings that are matched against are currently different for ipv4
 //   and ipv6, in v4 the string is a concatenation of the source and the
 //   destination (client and phantom) addresses. In ipv6 it is only the phantom
 //   address as the chance of phantom collisions is far lower.
-//      * While not currently in use we could add the destination (phantom) port
-//        to the key strings if we need extra specificity. 
+//      * Both keys include the phantom port. Registrations that don't name a
+//        port (phantom_port 0) are tracked on each of the default phantom
+//        ports (SessionConfig.phantom_ports, 443 unless configured).
+//        Registrations for the same phantom on different ports are separate
+//        sessions throughout: they are extended, limited, removed and expired
+//        independently, and are listed and counted per port.
+//      * Since the v6 key ignores the client, anyone scanning the v6 phantom
+//        space hits a registered phantom as well. In strict v6 mode
+//        (SessionConfig.strict_v6_prefix) the v6 key also includes the
+//        client's network, its address masked to the configured prefix length,
+//        so only the registering client's network matches. v6 registrations
+//        without a v6 client address cannot be matched this way and are not
+//        tracked in strict mode.
+//      * Where the detector never sees the client's real v4 address (it is
+//        behind a NAT only the station knows about) the v4 key can't match.
+//        With SessionConfig.v4_phantom_only the v4 key is the phantom and
+//        port, like the non-strict v6 key.
+//      * Mobile clients may move to another address in their carrier's block
+//        between registering and connecting. With
+//        SessionConfig.v4_client_prefix the v4 key holds the client's
+//        network, its address masked to the configured prefix length, as in
+//        strict v6 mode.
+//
+// - Registrations are normally for a phantom. In decoy mode
+//   (StationToDetector.mode Decoy, tracked if SessionConfig.decoy is set) the
+//   address registered is a real decoy host that many clients connect to, so
+//   the key is always the client's full address, the decoy and the port,
+//   prefixed "decoy-" whatever the phantom key options above. Packets are
+//   looked up under their phantom key first and their decoy key after it.
+//   Decoy sessions have their own timeout policy (DecoyConfig): activity
+//   extends them by a shorter time than phantom sessions and their initial
+//   lifetime can be capped separately. They are exempt from the station's
+//   phantom subnets.
 //
 // - The ingest thread is launched as a subroutine of the SessionTracker struct
-//   and pulls from redis. The messages received come in the form of
+//   and pulls from a RegistrationFeed (see ingest.rs), which in production is
+//   the redis pubsub channel. The messages received come in the form of
 //   StationToDetector protobuf, which can be modified relatively independently.
-//   Currently there is a `from` function that parses this into SessionDetails
-//   which can be directly managed by the SessionTracker.
+//   Each is converted into a Registration (see registration.rs) as soon as it
+//   is parsed, and only that is checked and turned into the SessionDetails
+//   the SessionTracker manages.
+//
+// - A dual-stack registration arrives as a single StationToDetector with the
+//   IPv4 phantom in `phantom_ip` and the IPv6 phantom in `phantom_ip_v6`.
+//   Registration::sessions parses it into both SessionDetails and the ingest
+//   thread inserts them under one write lock with a shared expire time, so
+//   either both sessions are tracked or neither is.
+//      Both are linked by a group id (SessionDetails.group, derived from the
+//      registration) and treated as one registration except for their
+//      timeouts, which activity extends per key as usual. Removing a
+//      registration (remove_registration) removes it from the whole group,
+//      the first connection to either latches both as matched (one
+//      "session matched" event), the per-source counts count the group once,
+//      and a session of the group that expires while another is tracked is
+//      folded into it (settle_groups): the group is counted as expired, and
+//      its SessionExpired event sent with the totals of both, when the last
+//      one goes. The group of the first registration of a key is kept.
+//
+// - Each session remembers the registration source (registrar path) that
+//   created it. It is included in logs, the per-source counts reported with
+//   the periodic stats, and the session dump. If a second registration maps
+//   to an existing key the source of the first one is kept.
+//
+// - Lookups from the packet path normally take a read lock on the session map.
+//   Optionally (`enable_snapshot`) they are served from an immutable map of
+//   the keys (to their client ports) held in an ArcSwap instead. A
+//   background thread rebuilds that map whenever keys are added or removed,
+//   so a new registration may not match until the next rebuild, at most one
+//   snapshot interval later. Timeout extensions do not change the key set
+//   and do not trigger a rebuild.
+//
+// - A registration can restrict the source ports its client connects from
+//   (SessionDetails.client_ports), for transports that constrain them. The
+//   packet path matches flows with is_tracked_flow, which only accepts such a
+//   session's flows from those ports, so where sessions are keyed without the
+//   client's address (v6 outside strict mode, v4 with v4_phantom_only) fewer
+//   foreign connections to the phantom match. The other lookups take any
+//   port. Packets refused for their port are counted
+//   (take_client_port_refusals). Another registration for a tracked session
+//   widens its ports to cover those of both, or to any port if it doesn't
+//   restrict them, so neither registration's client is refused.
+//
+// - Each session is given an id when it is first inserted, a hash of its key
+//   and the insertion time. The id is logged with forwarding decisions and
+//   sent to the application in the DetectorToStation "session matched" event
+//   so the logs of both can be correlated. Nothing makes ids unique beyond
+//   one detector: the insertion time is on the detector's own clock, so
+//   another detector may come up with the same id for a session of its own.
+//      That event is sent once per session, for its first connection: the
+//      first caller of latch_match sets the entry's matched_at under the
+//      write lock and gets the entry back, every later caller (another
+//      connection, a retransmitted SYN, another thread sharing the tracker)
+//      gets None, seeing matched_at under the read lock. Whatever should
+//      happen once per session hangs off it.
+//
+// - The number of simultaneous connections forwarded for a session can be
+//   capped, either per registration (StationToDetector.max_connections) or
+//   globally (SessionConfig.max_connections). The count is kept in the
+//   SessionEntry and maintained by the FlowTracker, which opens a connection on
+//   the client's SYN (or first UDP packet) and closes it on RST, when it goes
+//   idle, or a short while after the client's FIN. Closing a connection adds
+//   the packets and bytes forwarded for it to the SessionEntry. A
+//   connection refused at the limit stays refused for its later packets as
+//   well. Transport detectors' verdicts on the first payload of a connection
+//   are recorded in the SessionEntry too (see transport_detector.rs).
+//
+// - Stale sessions are dropped by whoever owns the tracker calling
+//   drop_stale_sessions (the FlowTracker periodic cleanup) unless a
+//   maintenance interval is configured, in which case spawn_maintenance_thread
+//   starts a thread that does it on its own schedule. Each pass can be capped
+//   to a maximum number of drops so the write lock is only held briefly; any
+//   remaining stale sessions are dropped on later passes. The time between
+//   passes can adapt to the size of the map and the rate sessions expire at
+//   (MaintenanceConfig.adaptive, see cleanup_interval.rs).
+//      Finding the stale sessions still means visiting the whole map, which
+//      takes milliseconds with millions of sessions. With
+//      SessionConfig.cleanup_budget each pass instead visits at most a budget
+//      of sessions (or of time) under the write lock, resuming where the last
+//      pass stopped (see SessionMap::sweep), so a session is dropped within
+//      one sweep of the whole map after it expires. The remaining lifetime
+//      distribution is then sampled over each complete sweep.
+//
+// - Registrations normally carry a lifetime relative to when they are received
+//   (timeout_ns). They may instead carry an absolute expiration and the
+//   station's clock at sending, see registration_timeout. Either way the
+//   lifetime is clamped to SessionConfig.max_lifetime when set.
+//      A misbehaving station can also send lifetimes that make no sense, 0 or
+//      centuries. With SessionConfig.timeout_bounds the ingest thread checks
+//      each registration's lifetime against a floor and a ceiling and either
+//      clamps it into range or rejects the registration with
+//      SessionError::InvalidTimeout; each outcome is counted for the periodic
+//      report (take_timeout_report).
+//
+// - Traffic to a session's phantom extends it by the extension its registration
+//   asked for (extension_ns), else the one configured for its transport
+//   (SessionConfig.transport_extensions), else SessionConfig.extension,
+//   TIMEOUT_PHANTOMS_NS by default; decoy sessions without one use
+//   DecoyConfig.extension. No extension exceeds the session's maximum
+//   lifetime (SessionConfig.max_lifetime). Extending
+//   would keep a busy session alive indefinitely. SessionConfig can cap the
+//   total lifetime (max_total_lifetime, counted from the first
+//   registration) and the number of extensions (max_extensions); past either
+//   cap activity no longer extends the session and it expires on schedule.
+//   Connections the FlowTracker already forwards are unaffected and carry on
+//   until they close, unless conntrack teardown is configured.
+//      The station can also refresh sessions it knows are still needed,
+//      whatever their traffic, with an update (Command::Update): the expiry
+//      of each tracked session is reset to the update's lifetime from receipt,
+//      within the lifetime caps but not max_extensions (refresh_sessions).
+//      Updates create no sessions. Activity extensions and refreshes are
+//      counted apart for the periodic report (take_expiry_change_report).
+//
+// - Registrations that carry the station's clock at sending also give us the
+//   delay (plus clock skew) between the station and the detector. The ingest
+//   thread records it in the tracker's IngestLatency, which is reported with
+//   the periodic stats, and warns about messages slower than
+//   SessionConfig.latency_warn.
+//
+// - With replication configured (SessionConfig.replication) the sessions
+//   accepted from our station's registrations, and those refreshed by its
+//   updates, are published to peer detectors and theirs are ingested by one
+//   thread per peer, see replication.rs.
+//
+// - For maintenance a detector can hand its sessions off to a standby: `export`
+//   lists every session with its remaining lifetime and the standby (with
+//   SessionConfig.accept_handoff) imports them from its handoff channel. While
+//   draining, the tracker ignores new registrations and updates from its
+//   station and peers but keeps serving the sessions it has, still extended
+//   by their activity, until they expire. Both are triggered from the admin
+//   socket, see admin.rs.
+//
+// - Connections already forwarded for a session outlive it by default. With
+//   SessionConfig.conntrack_teardown, sessions that expire with active
+//   connections are passed to a Teardown that deletes their conntrack
+//   entries, see conntrack.rs.
+//
+// - With SessionConfig.replay set, registrations from our station carrying a
+//   message_id are checked against the ids seen within the replay window, so
+//   a captured registration can't bring back a session once it was revoked
+//   or expired. Duplicates and messages outside the window are dropped and
+//   counted, see replay.rs. Registrations from the control listener always
+//   go through a guard of their own, which also requires the message_id.
+//
+// - Registrations that fail to parse or describe no valid session are counted
+//   and logged at a limited rate. A storm of them (more than
+//   SessionConfig.ingest_errors.breaker_rate a second) on one feed trips its
+//   breaker, slowing the feed down for a cooldown, see ingest_errors.rs.
+//
+// - With SessionConfig.journal_len set, the ingest thread records every
+//   registration it receives, and whether it was accepted or why not, in an
+//   IngestJournal the admin socket can list (see journal.rs).
+//
+// - Stale session passes also sample the remaining lifetimes of the tracked
+//   sessions (at most once per EXPIRY_SAMPLE_INTERVAL_NS) and count the
+//   sessions they drop by whether a connection was ever forwarded for them,
+//   for tuning timeouts and cleanup intervals. See ExpiryStats.
+//      The sessions dropped without ever being matched are also counted by
+//      transport, registration source and phantom, and summarized to the
+//      station once per report period (see feedback::never_matched_event):
+//      many registrations that never see a connection point at client side
+//      reachability problems.
+//      The matched ones are logged with their traffic totals and each sent to
+//      the station as a SessionExpired event (see
+//      feedback::session_expired_event). Connections still open when their
+//      session expires aren't in the totals.
+//
+// - With SessionConfig.accept_config_push the ingest threads apply the
+//   configuration the station pushes (default lifetime, allowed transports,
+//   blocklists) to the registrations they receive after it, and refuse those
+//   for phantoms outside the phantom subnets it pushed, see config_push.rs.
+//
+// - Optionally (SessionConfig.insert_queue) the ingest threads queue the
+//   sessions of each registration for an applier thread that inserts them in
+//   batches, one write lock per batch, see insert_queue.rs.
+//
+// - Pubsub only delivers the registrations published after we subscribe, so a
+//   freshly started detector would miss every session registered before it.
+//   With SessionConfig.prime_key a priming thread also reads the
+//   registrations the station keeps under that redis key and adds them,
+//   without the replay guard or the latency measurement (they may be long
+//   stored), and without counting again the keys the ingest thread added
+//   meanwhile. Each keeps what is left of its lifetime since the station
+//   sent it (station_time), those already expired are skipped. is_primed
+//   tells when it is done.
+//
+// - The redis ingest thread is supervised: when it panics or its feed fails
+//   a new one is started, with backoff, see supervisor.rs.
+//      A thread panicking while it holds the session map's write lock leaves
+//      the lock poisoned. Rather than failing every later access, the next
+//      one repairs the map's indexes and clears the poison (write_sessions),
+//      counting the recovery for the periodic report.
+//
+// - With SessionConfig.export set a thread periodically writes anonymized
+//   records of every tracked session to rotated files for offline analysis,
+//   see session_export.rs.
+//
+// - The admin socket can flush every session whose phantom is in a subnet,
+//   tearing down their connections if conntrack teardown is enabled, and
+//   block new registrations for it for a while, see phantom_block.rs.
+//
+// - With SessionConfig.tenants one detector serves several logical stations.
+//   Each session belongs to the tenant its registration named; other
+//   tenants' keys are prefixed with their id ("t7|", no address contains
+//   "|") so they never share a session with the default station's, and every
+//   tenant's prefix is tried when looking up a packet. Tenants can be
+//   limited in sessions and registration rate, and the station can delete
+//   all of a tenant's sessions (not the default station's), see tenants.rs.
 //
 // The notes above are implemented and tested below. If you modify the code
 // please make sure the tests still pass. If you modify the way this code is
 // used please update the tests. 
 
-use std::collections::{HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::hash_map::DefaultHasher;
+use std::cell::{Cell, RefCell};
+use std::cmp;
 use std::convert::From;
-use std::fmt;
-use std::net::{IpAddr};
-use std::sync::{RwLock, Arc};
+use std::fmt::{self, Write};
+use std::mem;
+use std::hash::{Hash, Hasher};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, TcpListener};
+use std::sync::{Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard, Arc};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::thread;
+use std::thread::JoinHandle;
+use std::os::unix::net::UnixListener;
+use std::time::Duration;
+
+use arc_swap::ArcSwap;
+use thiserror::Error;
+use rand::{self, Rng};
 
-use time::precise_time_ns;
-use redis;
 
-use signalling::StationToDetector;
-use protobuf::Message;
-use flow_tracker::{FlowNoSrcPort,FLOW_CLIENT_LOG};
+use signalling::{StationToDetector, RegistrationSource, SessionMode, TransportType};
+use protobuf::ProtobufEnum;
+use flow_tracker::{Flow,FlowNoSrcPort,FLOW_CLIENT_LOG};
+use error::{DetectorError, DetectorResult, error_chain};
+use ingest::{IngestLatency, PayloadFormat, RegistrationFeed, RedisFeed, RedisSnapshot, REDIS_URL, REDIS_CHANNEL, parse_message};
+use timekeeping::{Clock, MonotonicNs, WallClockNs, duration_ns};
+use replication::{Replication, ReplicationConfig, REPLICATION_CHANNEL, HANDOFF_CHANNEL};
+use replication::{handoff_messages, send_handoff};
+use signalling::SessionReplication;
+use admin;
+use healthcheck::HealthcheckConfig;
+use probes::{self, Heartbeat, Watchdog};
+use supervisor::Supervisor;
+use sequence::SequenceTracker;
+use conntrack::{NetlinkConntrack, Teardown};
+use control::{ControlConfig, ControlFeed};
+use replay::{ReplayConfig, ReplayGuard};
+use journal::IngestJournal;
+use config_push::StationConfig;
+use session_map::{SessionMap, SweepBudget};
+use hashing::{HashAlgorithm, MapHasher};
+use insert_queue::{InsertQueue, InsertQueueConfig};
+use registration::{Command, Registration};
+#[cfg(feature = "json-registrations")]
+use json_registration::parse_json_registration;
+use ingest_pool::{IngestPool, IngestPoolConfig, IngestPoolStats, is_station_wide};
+use cleanup_interval::{AdaptiveConfig, AdaptiveInterval, CleanupGauge};
+use ingest_errors::{IngestError, IngestErrorConfig, IngestErrors};
+use session_export::{self, SessionExport, SessionExportConfig};
+use signalling::SessionRecord;
+use client_id::ClientId;
+use transport_detector::Verdict;
+use tls_fingerprint::TlsFingerprint;
+use quic::DcidHash;
+use affinity::affinity_hash;
+use phantom_block::PhantomBlocks;
+use ipnetwork::IpNetwork;
+use tenants::{Refusal, TenantConfig, TenantId, Tenants, DEFAULT_TENANT};
+use metrics::{Counters, LocalCounters, format_buckets, ns_label};
 
 
 const S2NS: u64= 1000*1000*1000;
 // time to add beyond original timeout if a session is still receiving packets
 // that need to be forwarded to the data plane proxying logic. (300 s = 5 mins)
 const TIMEOUT_PHANTOMS_NS: u64 = 300 * S2NS;
+// Same for decoy sessions, unless DecoyConfig says otherwise. Shorter, as the
+// client's traffic to a decoy isn't all ours to forward.
+const DEFAULT_DECOY_EXTENSION: Duration = Duration::from_secs(60);
+
+const PHANTOM_MODE: [SessionMode; 1] = [SessionMode::Phantom];
+const ALL_MODES: [SessionMode; 2] = [SessionMode::Phantom, SessionMode::Decoy];
+
+// Port registrations without an explicit phantom port are tracked on, unless
+// SessionConfig.phantom_ports says otherwise.
+pub const DEFAULT_PHANTOM_PORT: u16 = 443;
+const DEFAULT_PHANTOM_PORTS: [u16; 1] = [DEFAULT_PHANTOM_PORT];
 
-// We _can_ filter by phantom port if we so choose, and randomize the port that
-// the clients connect to. However we are currently using exclusively port 443.
-// adding this here as a placeholder for now. 
-const DEFAULT_PHANTOM_PORT: u16 = 443;
+// Buckets of the remaining lifetime distribution, and the shortest time
+// between samples of it.
+const REMAINING_BUCKETS_NS: [u64; 8] = [10 * S2NS, 30 * S2NS, 60 * S2NS, 120 * S2NS,
+                                        300 * S2NS, 600 * S2NS, 1800 * S2NS, 3600 * S2NS];
+const EXPIRY_SAMPLE_INTERVAL_NS: u64 = 10 * S2NS;
+thread_local! {
+    // Buffer the session keys of packets are formatted into, see
+    // SessionConfig::find_flow_key.
+    static FLOW_KEY: RefCell<String> = RefCell::new(String::with_capacity(64));
+}
+
+// Matched sessions that expired kept for the station per report period, more
+// are only counted.
+const MAX_EXPIRED_MATCHED: usize = 4096;
 
 
 // "errors" we want to catch
-#[derive(Debug)]
+#[derive(Debug, Error)]
 pub enum SessionError {
+    #[error("Invalid phantom address")]
     InvalidPhantom,
+    #[error("Invalid client address")]
     InvalidClient,
+    #[error("Client/Phantom v4/v6 mismatch")]
     MixedV4V6Error,
+    #[error("Invalid timeout {0:?}")]
+    InvalidTimeout(Duration),
+    #[error("Invalid client port range {0}-{1}")]
+    InvalidClientPorts(u32, u32),
 }
 
 pub type SessionResult = Result<SessionDetails, SessionError>; 
 
-
-impl fmt::Display for SessionError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            SessionError::InvalidClient => {
-                write!(f, "Invalid client address")
-            },
-            SessionError::InvalidPhantom => {
-                write!(f, "Invalid phantom address")
-            },
-            SessionError::MixedV4V6Error => {
-                write!(f, "Client/Phantom v4/v6 mismatch")
-            },
-        }
-    }
-}
-
 #[derive(Copy, Clone)]
 pub struct SessionDetails
 {
     pub client_ip: IpAddr,
     pub phantom_ip: IpAddr,
     pub phantom_port: u32,
+    pub registration_source: RegistrationSource,
+    // Transport the client registered for (Null if the station didn't say).
+    pub transport: TransportType,
+    // Simultaneous connection limit requested by the registration (0 = none).
+    pub max_connections: u32,
+    // Whether phantom_ip is a phantom or a decoy host.
+    pub mode: SessionMode,
+    // Station the session was registered for, see tenants.rs.
+    pub tenant: TenantId,
+    // Time activity extends the session by, when the registration asked.
+    // None uses the configured extension, see SessionConfig::extension_ns.
+    pub extension: Option<Duration>,
+    // Source ports the client may connect from, None for any.
+    pub client_ports: Option<ClientPorts>,
+    // Shared by the sessions of one dual-stack registration, None for the
+    // rest, see Registration::group_id.
+    pub group: Option<u64>,
+    // The message the session was registered by, None if it can't be told
+    // from another, see Registration::message_hash.
+    pub message: Option<u64>,
     timeout: u64,
 }
 
+// Inclusive range of source ports a session's client connects from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClientPorts
+{
+    pub first: u16,
+    pub last: u16,
+}
+
+impl ClientPorts
+{
+    /// The range first-last, as registrations carry it.
+    pub fn new(first: u32, last: u32) -> Result<ClientPorts, SessionError> {
+        if first > last || last > u16::MAX as u32 {
+            return Err(SessionError::InvalidClientPorts(first, last))
+        }
+        Ok(ClientPorts { first: first as u16, last: last as u16 })
+    }
+
+    pub fn contains(&self, port: u16) -> bool {
+        self.first <= port && port <= self.last
+    }
+
+    /// The range covering both `self` and `other`.
+    pub fn widen(self, other: ClientPorts) -> ClientPorts {
+        ClientPorts { first: cmp::min(self.first, other.first), last: cmp::max(self.last, other.last) }
+    }
+}
+
+impl fmt::Display for ClientPorts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", self.first, self.last)
+    }
+}
+
 
 impl SessionDetails
 {
     // This function parses acceptable Session Details and returns an error if
     // the details provided do not fit current requirements for parsing 
-    pub fn new(client_ip: &str, phantom_ip: &str, phantom_port: u32, timeout: u64) -> SessionResult {
+    // (registrations carry addresses as strings). Sessions built from typed
+    // addresses should use builder().
+    pub fn new(client_ip: &str, phantom_ip: &str, phantom_port: u32, timeout: Duration) -> SessionResult {
         let phantom: IpAddr = match phantom_ip.parse() {
             Ok(ip) => ip,
             Err(_) => {return Err(SessionError::InvalidPhantom)},
         };
 
-        let src: IpAddr = match client_ip.parse() {
-            Ok(ip) => ip,
-            Err(_) => {
-                if client_ip == "" && phantom.is_ipv6() {
-                    "::1".parse().unwrap()
-                } else {
-                    return Err(SessionError::InvalidClient)
-                }
-            },
+        let src: Option<IpAddr> = match client_ip.parse() {
+            Ok(ip) => Some(ip),
+            Err(_) if client_ip == "" => None,
+            Err(_) => return Err(SessionError::InvalidClient),
         };
 
-        if phantom.is_ipv4() && !src.is_ipv4() {
-            return Err(SessionError::MixedV4V6Error)
-        }
+        let mut builder = SessionDetails::builder().phantom(phantom).timeout(timeout);
+        builder.client = src;
+        builder.port = phantom_port; //TODO: change u32 to u16 or add error catching
+        builder.build()
+    }
 
-        let s = SessionDetails {
-            client_ip: src,
-            phantom_ip: phantom,
-            phantom_port: phantom_port, //TODO: change u32 to u16 or add error catching
-            timeout: timeout,
-        };
-        Ok(s)
+    /// Builder taking typed addresses and lifetime, e.g.
+    /// `SessionDetails::builder().client(ip).phantom(ip).port(443).timeout(d).build()`.
+    pub fn builder() -> SessionDetailsBuilder {
+        SessionDetailsBuilder::default()
     }
 
+    /// Session map key under the default (non-strict) configuration, with
+    /// decoy sessions enabled.
     pub fn get_key(&self) -> String {
-        match self.phantom_ip.is_ipv6() {
-            true => format!("{}-{}", self.phantom_ip, self.phantom_port),
-            false => format!("{}-{}-{}", self.client_ip, self.phantom_ip, self.phantom_port)
-        }
+        let config = SessionConfig { decoy: Some(DecoyConfig::default()), ..SessionConfig::default() };
+        self.key_in(&config)
+            .expect("default session keys are always defined")
     }
+
+    /// Session map key under `config`, None if the session can't be tracked
+    /// under it.
+    pub fn key_in(&self, config: &SessionConfig) -> Option<String> {
+        config.key(self.mode, self.tenant, &self.client_ip, &self.phantom_ip, self.phantom_port)
+    }
+
+    /// Lifetime requested for the session.
+    pub fn timeout(&self) -> Duration {
+        Duration::from_nanos(self.timeout)
+    }
+
+    /// timeout() in ns, as registrations carry it.
+    pub fn timeout_ns(&self) -> u64 {
+        self.timeout
+    }
+}
+
+/// Lifetime of the registration in `s2d` received at `now`, see
+/// Registration::lifetime.
+pub fn registration_timeout(s2d: &StationToDetector, now: WallClockNs) -> Duration {
+    Registration::from(s2d).lifetime(now)
 }
 
 impl From<&StationToDetector> for SessionResult {
     fn from(s2d: &StationToDetector) -> Self {
-        let source = s2d.get_client_ip();
-        let phantom = s2d.get_phantom_ip();
-        let phantom_port = s2d.get_phantom_port();
-        return SessionDetails::new(source, phantom, phantom_port, s2d.get_timeout_ns())
+        let mut sessions = Registration { phantom_ip_v6: None, ..Registration::from(s2d) }
+            .sessions(WallClockNs::now())?;
+        Ok(sessions.remove(0))
     }
 }
 
-// TODO - make accessible
-impl fmt::Display for SessionDetails {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        unsafe {
-            match FLOW_CLIENT_LOG {
-                true => write!(f, "{} -> {}:{} ({}ns)", self.client_ip.to_string(), self.phantom_ip.to_string(), self.phantom_port.to_string(), self.timeout),
-                false => write!(f, "_ -> {}:{} ({}ns)", self.phantom_ip.to_string(), self.phantom_port.to_string(), self.timeout),
-            }
-        }
-    }
+/// Parses every session carried by a StationToDetector message, see
+/// Registration::sessions.
+pub fn sessions_from(s2d: &StationToDetector) -> Result<Vec<SessionDetails>, SessionError> {
+    Registration::from(s2d).sessions(WallClockNs::now())
 }
 
-pub struct SessionTracker
+// Builds a SessionDetails, see SessionDetails::builder. The phantom is
+// required; without a client only v6 phantoms are valid, as in registrations
+// without a client address. Everything else defaults as in
+// SessionDetails::new: port 0 (the default ports), a lifetime of 0, and an
+// Unspecified source, Null transport, unlimited phantom session of our own
+// station, extended as configured.
+#[derive(Clone, Copy, Default)]
+pub struct SessionDetailsBuilder
 {
-    // Sessions cannot be tracked by registration because we will not be
-    // receiving registration information in order to identify the sessions. As
-    // such sessions are stored as a thread safe map with keys dependent on the
-    // ip version:
-    // v4 "{}-{}-{}", client_ip, phantom_ip, phantom_port
-    // v6 "{}-{}", phantom_ip, phantom_port
-    // TODO: ADDITION OF PORT IS WIP
-    // The value stored for each of these is a timestamp to compare for timeout.
-    pub tracked_sessions: Arc<RwLock<HashMap<String, u64>>>,
+    client: Option<IpAddr>,
+    phantom: Option<IpAddr>,
+    port: u32,
+    timeout: u64,
+    source: RegistrationSource,
+    transport: TransportType,
+    max_connections: u32,
+    mode: SessionMode,
+    tenant: TenantId,
+    extension: Option<Duration>,
+    client_ports: Option<ClientPorts>,
+    group: Option<u64>,
 }
 
-impl<'a> SessionTracker 
+impl SessionDetailsBuilder
 {
-    pub fn new() -> SessionTracker {
-        SessionTracker{
-            tracked_sessions: Arc::new(RwLock::new(HashMap::new())),
-        }
+    pub fn client<A: Into<IpAddr>>(mut self, client: A) -> SessionDetailsBuilder {
+        self.client = Some(client.into());
+        self
     }
 
-    pub fn add_session(&mut self, det: SessionDetails) {
-        self.insert_session(det)
+    pub fn phantom<A: Into<IpAddr>>(mut self, phantom: A) -> SessionDetailsBuilder {
+        self.phantom = Some(phantom.into());
+        self
     }
 
-    pub fn spawn_update_thread(&self) {
-        let write_map = Arc::clone(&self.tracked_sessions);
-        thread::spawn(move || { ingest_from_pubsub(write_map) });
+    pub fn port(mut self, port: u16) -> SessionDetailsBuilder {
+        self.port = port as u32;
+        self
     }
 
-    pub fn is_tracked_session(&self, flow: &FlowNoSrcPort) -> bool {
-        let key = match flow.dst_ip.is_ipv6() {
-            true => format!("{}-{}", flow.dst_ip, flow.dst_port),
-            false => format!("{}-{}-{}", flow.src_ip, flow.dst_ip, flow.dst_port)
-        };
-        self.session_exists(&key)
+    /// Lifetime, saturating at u64::MAX ns.
+    pub fn timeout(mut self, timeout: Duration) -> SessionDetailsBuilder {
+        self.timeout = duration_ns(timeout);
+        self
     }
 
-    pub fn len(&self) -> usize {
-        let map = self.tracked_sessions.read().expect("RwLock Broken");
-        let res = map.len();
-        drop(map);
-        return res
+    pub fn source(mut self, source: RegistrationSource) -> SessionDetailsBuilder {
+        self.source = source;
+        self
     }
 
-    pub fn drop_stale_sessions(&mut self) -> usize {
-        let right_now = precise_time_ns();
-
-        let mut map = self.tracked_sessions.write().expect("RwLock Broken");
-        let num_sessions_before = map.len();
-        // Dark Decoys Map is not sorted by timeout, so need to check all
-        map.retain(|_, v| ( *v > right_now));
-        let num_sessions_after = map.len();
-        if num_sessions_before != num_sessions_after {
-            debug!("Dark Decoys drops: {} - > {}", num_sessions_before, num_sessions_after);
-        }
-        num_sessions_before - num_sessions_after
+    pub fn transport(mut self, transport: TransportType) -> SessionDetailsBuilder {
+        self.transport = transport;
+        self
     }
 
-    /// Used to update (increase) the time that we  consider a session 
-    /// valid for tracking purposes. Called when packets from a session are
-    /// seen so that forwarding continues past the original registration timeout.
-    pub fn update_session(&mut self, flow: &FlowNoSrcPort) {
+    pub fn max_connections(mut self, max: u32) -> SessionDetailsBuilder {
+        self.max_connections = max;
+        self
+    }
 
-        let key = match flow.dst_ip.is_ipv6() {
-            true => format!("{}-{}", flow.dst_ip, flow.dst_port),
-            false => format!("{}-{}-{}", flow.src_ip, flow.dst_ip, flow.dst_port)
-        };
+    pub fn mode(mut self, mode: SessionMode) -> SessionDetailsBuilder {
+        self.mode = mode;
+        self
+    }
 
-        if !self.session_exists(&key) {
-            return
-        }
+    pub fn tenant(mut self, tenant: TenantId) -> SessionDetailsBuilder {
+        self.tenant = tenant;
+        self
+    }
 
-        self.try_update_session_timeout(key, TIMEOUT_PHANTOMS_NS);
+    pub fn extension(mut self, extension: Duration) -> SessionDetailsBuilder {
+        self.extension = Some(extension);
+        self
     }
 
-   
-    
-    fn try_update_session_timeout(&mut self, key: String, extra_time: u64) {
-        // Get writable map
-        let mut mmap = self.tracked_sessions.write().expect("RwLock broken");
+    pub fn client_ports(mut self, ports: ClientPorts) -> SessionDetailsBuilder {
+        self.client_ports = Some(ports);
+        self
+    }
 
-        // Set timeout
-        let expire_time = precise_time_ns() + extra_time;
+    pub fn group(mut self, group: u64) -> SessionDetailsBuilder {
+        self.group = Some(group);
+        self
+    }
 
-        // compare and keep the longer
-        match mmap.get_mut(&key){
-            Some(v)=> {
-                // compare and keep the longer
-                if *v < expire_time {
-                    *v = expire_time;
-                }
-            },
-            None => {},
+    pub fn build(self) -> SessionResult {
+        let phantom = self.phantom.ok_or(SessionError::InvalidPhantom)?;
+        let client = match self.client {
+            Some(ip) => ip,
+            // ::1 stands in for a missing v6 client, see SessionConfig.write_key
+            None if phantom.is_ipv6() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+            None => return Err(SessionError::InvalidClient),
         };
+        if phantom.is_ipv4() && !client.is_ipv4() {
+            return Err(SessionError::MixedV4V6Error)
+        }
+        Ok(SessionDetails {
+            client_ip: client,
+            phantom_ip: phantom,
+            phantom_port: self.port,
+            registration_source: self.source,
+            transport: self.transport,
+            max_connections: self.max_connections,
+            mode: self.mode,
+            tenant: self.tenant,
+            extension: self.extension,
+            client_ports: self.client_ports,
+            group: self.group,
+            message: None,
+            timeout: self.timeout,
+        })
     }
+}
 
-    fn insert_session(&mut self, session: SessionDetails) {
-        // is this already in the map? 
-        let key = session.get_key();
-        if self.session_exists(&key) {
-            self.try_update_session_timeout(key, session.timeout);
-            return
+// TODO - make accessible
+impl fmt::Display for SessionDetails {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        unsafe {
+            match FLOW_CLIENT_LOG {
+                true => write!(f, "{} -> {}:{} ({}ns, {:?})", ClientId::new(self.client_ip), self.phantom_ip.to_string(), self.phantom_port.to_string(), self.timeout, self.registration_source),
+                false => write!(f, "_ -> {}:{} ({}ns, {:?})", self.phantom_ip.to_string(), self.phantom_port.to_string(), self.timeout, self.registration_source),
+            }
         }
+    }
+}
 
-        // Get writable map
-        let mut mmap = self.tracked_sessions.write().expect("RwLock broken");
+// Value stored in the session map for each tracked key.
+#[derive(Copy, Clone)]
+pub struct SessionEntry
+{
+    // Details of the (first) registration that created this session.
+    pub details: SessionDetails,
+    // Clock time after which the session is considered stale.
+    pub expire_time: MonotonicNs,
+    // Correlation id, see session_id().
+    pub session_id: u64,
+    // Connections currently being forwarded for this session.
+    pub active_connections: u32,
+    // Registrations that mapped to this key (the first one and every
+    // duplicate since, not counting redeliveries).
+    pub registrations: u32,
+    // Connections forwarded for this session so far, 0 if it never matched.
+    pub connections: u32,
+    // Clock time the session was first registered.
+    pub created_at: MonotonicNs,
+    // Times activity has extended the session.
+    pub extensions: u32,
+    // Packets and bytes (IP) forwarded from the client over the session's
+    // connections, added as each one closes.
+    pub packets: u64,
+    pub bytes: u64,
+    // A transport detector confirmed one of the session's connections, and
+    // the tag one annotated it with last (see transport_detector.rs).
+    pub confirmed: bool,
+    pub annotation: Option<u32>,
+    // Fingerprint of the TLS ClientHello the latest fingerprinted connection
+    // opened with (see tls_fingerprint.rs).
+    pub tls_fingerprint: Option<TlsFingerprint>,
+    // Hash of the destination connection ID of the QUIC Initial the latest
+    // recognized connection opened with (see quic.rs).
+    pub quic_dcid_hash: Option<DcidHash>,
+    // Affinity hash of the session's key (see affinity.rs).
+    pub affinity: u32,
+    // Clock time of the session's first connection, set by latch_match. None
+    // if it never matched.
+    pub matched_at: Option<MonotonicNs>,
+}
 
-        // Set timeout
-        let expire_time = precise_time_ns() + session.timeout;
+impl SessionEntry
+{
+    /// Entry for a session registered once with `details`, expiring at
+    /// `expire_time`, that hasn't matched or counted anything yet.
+    pub fn new(details: SessionDetails, expire_time: MonotonicNs) -> SessionEntry {
+        SessionEntry {
+            details: details,
+            expire_time: expire_time,
+            session_id: 0,
+            active_connections: 0,
+            registrations: 1,
+            connections: 0,
+            created_at: MonotonicNs(0),
+            extensions: 0,
+            packets: 0,
+            bytes: 0,
+            confirmed: false,
+            annotation: None,
+            tls_fingerprint: None,
+            quic_dcid_hash: None,
+            affinity: 0,
+            matched_at: None,
+        }
+    }
+}
 
-        // Insert
-        *mmap.entry(key).or_insert(expire_time) = expire_time;
+// Id for the session with `key` first registered at `registered_at` (clock ns).
+// Only distinct among one detector's sessions.
+pub fn session_id(key: &str, registered_at: MonotonicNs) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    registered_at.hash(&mut hasher);
+    hasher.finish()
+}
 
-        // Get rid of writable reference to map.
-        drop(mmap);
+// Options controlling how a SessionTracker tracks sessions. Fixed when the
+// tracker is created.
+#[derive(Clone, Default)]
+pub struct SessionConfig
+{
+    // Strict v6 mode: prefix length of the client network included in v6 keys.
+    // None keys v6 sessions on the phantom only.
+    pub strict_v6_prefix: Option<u8>,
 
-        debug!("Added registered ip {} from redis", session);
-    }
+    // Key v4 sessions on the phantom (and port) only, ignoring the client.
+    pub v4_phantom_only: bool,
 
-    // explicitly used for testing
-    fn _delete_session(&mut self, session: SessionDetails) {
-        let key = &session.get_key();
-        if ! self.session_exists(key) {
-            return
-        }
-        let mut mmap = self.tracked_sessions.write().expect("RwLock broken");
-        mmap.remove(key);
-        // mmap.retain(|_, v| ( v.client_ip != session.client_ip || v.phantom_ip != session.phantom_ip));
-    }
+    // Prefix length of the client network included in v4 keys. None uses the
+    // client's address. Ignored with v4_phantom_only.
+    pub v4_client_prefix: Option<u8>,
 
-    // lookup session by identifier
-    fn session_exists(&self, id: &String) -> bool
-    { 
-        let rmap = self.tracked_sessions.read().expect("RwLock broken");
-        let res = rmap.contains_key(id);
-        drop(rmap);
-        return res
-     }
+    // Simultaneous connection limit for sessions whose registration did not
+    // set one. None is unlimited.
+    pub max_connections: Option<u32>,
 
+    pub maintenance: MaintenanceConfig,
 
-}
+    // Work a stale session pass may do under the write lock. None visits the
+    // whole map every pass.
+    pub cleanup_budget: Option<SweepBudget>,
 
-// No returns in this function so that it runs for the lifetime of the process.
-fn ingest_from_pubsub(map: Arc<RwLock<HashMap<String, u64>>>) {
-    let mut con = get_redis_conn();
-    let mut pubsub = con.as_pubsub();
-    pubsub.subscribe("dark_decoy_map").expect("Can't subscribe to Redis");
+    // Longest lifetime given to a new session. None is unlimited.
+    pub max_lifetime: Option<Duration>,
 
-    loop {
-        let msg = match pubsub.get_message(){
-            Ok(m) => m,
-            Err(e) => {
-                debug!("Error reading message from redis: {}", e);
-                continue
-            }
-        };
-        let payload : Vec<u8> = match msg.get_payload(){
-            Ok(m) => m,
-            Err(e) => {
-                debug!("Error reading payload: {}", e);
-                continue
-            }
-        };
-        let station_to_det: StationToDetector = match Message::parse_from_bytes::<>(&payload) {
-            Ok(s2d) => s2d,
-            Err(e) => {
-                debug!("failed to parse StationToDetector message {}", e);
-                continue
-            },
-        };
-        let sd = match SessionResult::from(&station_to_det){
-            Ok(m) => m,
-            Err(e) => {
-                debug!("Error converting S2D to SD: {}", e);
-                continue
-            }
-        };
+    // Lifetimes registrations may ask for, checked at ingest. None accepts
+    // any.
+    pub timeout_bounds: Option<TimeoutBounds>,
 
-        // is this already in the map? 
-        let key = sd.get_key();
-        // Get writable map
-        let mut mmap = map.write().expect("RwLock broken");
-        let exists = mmap.contains_key(&key);
+    // Longest a session can be kept alive by activity, counted from its
+    // first registration. None is unlimited.
+    pub max_total_lifetime: Option<Duration>,
 
-        if exists {
-            // Set timeout
-            let expire_time = precise_time_ns() + sd.timeout;
+    // Times activity can extend a session. None is unlimited.
+    pub max_extensions: Option<u32>,
 
-            match mmap.get_mut(&key){
-                Some(v)=> {
-                    // compare and keep the longer
-                    if *v < expire_time {
-                        *v = expire_time;
-                    }
-                },
-                None => {},
-            };
+    // Time activity extends phantom sessions by. None uses
+    // TIMEOUT_PHANTOMS_NS.
+    pub extension: Option<Duration>,
 
-            // Explicitly drop map write lock here (locks are automatically dropped
-            // when they fall out of scope but this is more clear.)
-            drop(mmap);
-            continue
-        }
+    // Extensions of phantom sessions of these transports, overriding
+    // extension.
+    pub transport_extensions: Vec<(TransportType, Duration)>,
 
-        // Set timeout
-        let expire_time = precise_time_ns() + sd.timeout;
+    // Extend sessions on every packet forwarded for them, rather than only
+    // on packets of connections the client opened and carried past their
+    // SYN, and of UDP flows the phantom answered or that carried a QUIC
+    // Initial (see FlowTracker::update_phantom_flow).
+    pub extend_on_any_packet: bool,
 
-        // Insert
-        *mmap.entry(key).or_insert(expire_time) = expire_time;
+    // Registrations that took longer than this to arrive from the station
+    // are logged with a warning. None never warns.
+    pub latency_warn: Option<Duration>,
 
-        // Get rid of writable reference to map. (locks are automatically dropped
-        // when they fall out of scope but this is more clear.)
-        drop(mmap);
+    // Ports registrations without a phantom port are tracked on. Empty uses
+    // DEFAULT_PHANTOM_PORT.
+    pub phantom_ports: Vec<u16>,
 
-        debug!("Added registered ip {} from redis", sd);
-    }
-}
+    // Replicate sessions with peer detectors. None disables replication.
+    pub replication: Option<ReplicationConfig>,
 
-fn get_redis_conn() -> redis::Connection
-{
-    let client = redis::Client::open("redis://127.0.0.1/").expect("Can't open Redis");
-    let con = client.get_connection().expect("Can't get Redis connection");
-    con
-}
+    // Import sessions handed off by another detector.
+    pub accept_handoff: bool,
 
+    // Delete the conntrack entries of sessions that expire with active
+    // connections, rather than letting the connections drain.
+    pub conntrack_teardown: bool,
 
-#[cfg(test)]
-mod tests {
-    // use std::fmt::Write;
-    use sessions::*;
-    use signalling::StationToDetector;
-    use flow_tracker::FlowNoSrcPort;
-    use std::{thread, time};
+    // Also receive registrations directly from the station on a UDP control
+    // address. None only uses redis.
+    pub control: Option<ControlConfig>,
 
-    #[test]
-    fn test_session_tracker_pubsub(){
-        // // Publish to redis
-        // let octs = match flow.dst_ip {
-        //     IpAddr::V4(a) => a.octets().to_vec(),
-        //     IpAddr::V6(a) => a.octets().to_vec(),
-        // };
-        // redis::cmd("PUBLISH").arg("dark_decoy_map").arg(octs).execute(&self.redis_conn);
-        let st = SessionTracker::new();
+    // Reject replayed registrations. None accepts every registration.
+    pub replay: Option<ReplayConfig>,
 
-        let test_tuples = [
-            // (client_ip, phantom_ip, timeout)
-            ("172.128.0.2", "8.0.0.1", 1),            // timeout immediately
-            ("192.168.0.1", "10.10.0.1", 5*S2NS),
-            ("192.168.0.1", "192.0.0.127", 5*S2NS),   
-            ("", "2345::6789", 5*S2NS),
-            
-            // duplicate with shorter timeout should not drop
-            ("2601::123:abcd", "2001::1234", 5*S2NS),
-            ("::1", "2001::1234", 1*S2NS),
-            
-            // duplicate with long timeout should prevent drop
-            ("7.0.0.2", "8.8.8.8", 1),
-            ("7.0.0.2", "8.8.8.8", 5*S2NS),
-        ];
-    
-        st.spawn_update_thread();
-       
-        let dur = time::Duration::new(3, 0);
-        thread::sleep(dur);
+    // Registrations remembered by the ingest journal. None keeps no journal.
+    pub journal_len: Option<usize>,
 
-        for entry in &test_tuples {
-            let mut s2d = StationToDetector::new();
-            s2d.set_client_ip(entry.0.to_string());
-            s2d.set_phantom_ip(entry.1.to_string());
-            s2d.set_timeout_ns(entry.2);
+    // Apply the configuration pushed by the station. Pushes are ignored
+    // otherwise.
+    pub accept_config_push: bool,
 
-            let msg:Vec<u8> = s2d.write_to_bytes().unwrap();
+    // Queue registrations for a batching applier thread rather than
+    // inserting them from the ingest threads. None inserts directly.
+    pub insert_queue: Option<InsertQueueConfig>,
 
-            let redis_conn = get_redis_conn();
-            redis::cmd("PUBLISH").arg("dark_decoy_map").arg(msg).execute(&redis_conn);
-        }
+    // Check and apply registrations from the station's pubsub channel on a
+    // pool of worker threads rather than the ingest thread alone. None
+    // applies them on the ingest thread.
+    pub ingest_pool: Option<IngestPoolConfig>,
 
-        thread::sleep(dur);
+    // How failed registrations are logged, and when a storm of them sheds
+    // ingest for a while, see ingest_errors.rs.
+    pub ingest_errors: IngestErrorConfig,
 
-        if st.len() != 6 {
-            panic!("Failed to ingest from pubsub: {}", st.len());
-        } 
-    }
+    // Hash algorithm of the session map and lookup snapshot, and of the
+    // FlowTracker's flow maps.
+    pub hash: HashAlgorithm,
 
+    // Periodically export anonymized session records. None exports nothing.
+    pub export: Option<SessionExportConfig>,
 
-    #[test]
-    fn test_session_details_from() {
-        let test_tuples = [
-            // (client_ip, phantom_ip, timeout)
-            ("192.168.0.1", "10.10.0.1", 100000),
-            ("2601::123:abcd", "2001::1234", 100000),
-            ("", "2001::1234", 100000),
- 
-            // client registering with v4 will also create registrations for v6 just in-case
-             ("192.168.0.1", "2801::1234", 100000),
-        ];
-        let test_tuples_bad = [
-            // Mixed ipv4/ipv6 phantom/client
-            ("2001::1234", "10.10.0.1", 100000, SessionError::MixedV4V6Error),
+    // Track decoy mode registrations. None only tracks phantom sessions.
+    pub decoy: Option<DecoyConfig>,
 
-            // no phantom provided
+    // Redis key (hash or set) of the registrations the station keeps, read
+    // at startup. None starts with no sessions.
+    pub prime_key: Option<String>,
+
+    // Stations other than our own sessions are tracked for, and their
+    // limits. None tracks every session as our station's.
+    pub tenants: Option<TenantConfig>,
+}
+
+// Timeout policy of decoy sessions. Phantom sessions use the limits and
+// extensions in SessionConfig.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecoyConfig
+{
+    // Time activity extends a decoy session by.
+    pub extension: Duration,
+    // Longest lifetime given to a new decoy session. None uses
+    // SessionConfig.max_lifetime.
+    pub max_lifetime: Option<Duration>,
+}
+
+impl Default for DecoyConfig
+{
+    fn default() -> DecoyConfig {
+        DecoyConfig {
+            extension: DEFAULT_DECOY_EXTENSION,
+            max_lifetime: None,
+        }
+    }
+}
+
+// Schedule for the maintenance thread started by spawn_maintenance_thread.
+#[derive(Clone, Default)]
+pub struct MaintenanceConfig
+{
+    // Time between cleanup passes. None means there is no maintenance thread
+    // and the owner of the tracker calls drop_stale_sessions itself.
+    pub interval: Option<Duration>,
+    // A random delay of up to this much is added to every interval so the
+    // trackers of different cores don't all take their write locks in step.
+    pub jitter: Duration,
+    // Most sessions dropped in one pass. None drops every stale session.
+    pub max_drops_per_tick: Option<usize>,
+    // Adapt the time between passes to the map, interval being the shortest,
+    // see cleanup_interval.rs.
+    pub adaptive: Option<AdaptiveConfig>,
+}
+
+// Floor and ceiling on registration lifetimes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TimeoutBounds
+{
+    // Shortest lifetime accepted.
+    pub min: Duration,
+    // Longest lifetime accepted. None is unlimited.
+    pub max: Option<Duration>,
+    // Reject registrations out of bounds rather than clamping their lifetime.
+    pub reject: bool,
+}
+
+impl TimeoutBounds
+{
+    /// The lifetime a registration asking for `timeout` is given, or
+    /// InvalidTimeout if it is out of bounds and those are rejected.
+    pub fn enforce(&self, timeout: Duration) -> Result<Duration, SessionError> {
+        let bounded = match self.max {
+            Some(max) if timeout > max => max,
+            _ if timeout < self.min => self.min,
+            _ => timeout,
+        };
+        if bounded != timeout && self.reject {
+            return Err(SessionError::InvalidTimeout(timeout))
+        }
+        Ok(bounded)
+    }
+}
+
+impl SessionConfig
+{
+    // Key in the session map for a `mode` session of `tenant` from `client`
+    // to phantom:port (or a packet from client to phantom:port). None if such
+    // a session can't be tracked under this configuration.
+    fn key(&self, mode: SessionMode, tenant: TenantId, client: &IpAddr, phantom: &IpAddr, port: u32) -> Option<String> {
+        let mut key = String::new();
+        match self.write_key(mode, tenant, client, phantom, port, &mut key) {
+            true => Some(key),
+            false => None,
+        }
+    }
+
+    // Writes the key for the addresses into `buf` (cleared first), returning
+    // false if they have none.
+    fn write_key(&self, mode: SessionMode, tenant: TenantId, client: &IpAddr, phantom: &IpAddr, port: u32, buf: &mut String) -> bool {
+        buf.clear();
+        if tenant != DEFAULT_TENANT && self.tenants.is_some() {
+            let _ = write!(buf, "t{}|", tenant);
+        }
+        if mode == SessionMode::Decoy {
+            // Decoys are real hosts other clients connect to as well, only the
+            // client's own address tells its connections apart.
+            return match (client, phantom) {
+                _ if self.decoy.is_none() => false,
+                (IpAddr::V6(c), _) if c.is_loopback() => false,
+                (IpAddr::V4(_), IpAddr::V4(_)) | (IpAddr::V6(_), IpAddr::V6(_)) => {
+                    let _ = write!(buf, "decoy-{}-{}-{}", client, phantom, port);
+                    true
+                },
+                _ => false,
+            }
+        }
+        // (writing to a String can't fail)
+        let _ = match (phantom, self.strict_v6_prefix) {
+            (IpAddr::V4(_), _) if self.v4_phantom_only => write!(buf, "{}-{}", phantom, port),
+            (IpAddr::V4(_), _) => match (client, self.v4_client_prefix) {
+                (IpAddr::V4(c), Some(len)) => {
+                    let len = cmp::min(len, 32);
+                    let mask = match len {
+                        0 => 0,
+                        _ => !0u32 << (32 - len as u32),
+                    };
+                    let net = Ipv4Addr::from(u32::from(*c) & mask);
+                    write!(buf, "{}/{}-{}-{}", net, len, phantom, port)
+                },
+                _ => write!(buf, "{}-{}-{}", client, phantom, port),
+            },
+            (IpAddr::V6(_), None) => write!(buf, "{}-{}", phantom, port),
+            (IpAddr::V6(_), Some(len)) => match client {
+                // ::1 stands in for a missing client address (see SessionDetails::new)
+                IpAddr::V6(c) if c.is_loopback() => return false,
+                IpAddr::V6(c) => {
+                    let len = cmp::min(len, 128);
+                    let mask = match len {
+                        0 => 0,
+                        _ => !0u128 << (128 - len as u32),
+                    };
+                    let net = Ipv6Addr::from(u128::from(*c) & mask);
+                    write!(buf, "{}/{}-{}-{}", net, len, phantom, port)
+                },
+                IpAddr::V4(_) => return false,
+            },
+        };
+        true
+    }
+
+    // Modes sessions are tracked in, phantom first.
+    fn modes(&self) -> &'static [SessionMode] {
+        match self.decoy {
+            Some(_) => &ALL_MODES,
+            None => &PHANTOM_MODE,
+        }
+    }
+
+    // Tenants sessions are tracked for, the default station first.
+    fn tenant_ids(&self) -> impl Iterator<Item = TenantId> + '_ {
+        Some(DEFAULT_TENANT).into_iter().chain(self.tenants.iter().flat_map(|t| t.ids()))
+    }
+
+    // Calls `f` with the key `flow` has in each mode (and for each tenant),
+    // until it returns Some. The keys are formatted into a per thread buffer
+    // so the lookups done for every packet don't allocate. `f` must not look
+    // up another flow.
+    fn find_flow_key<T, F: FnMut(&str) -> Option<T>>(&self, flow: &FlowNoSrcPort, mut f: F) -> Option<T> {
+        FLOW_KEY.with(|buf| {
+            let mut buf = buf.borrow_mut();
+            for mode in self.modes() {
+                for tenant in self.tenant_ids() {
+                    if !self.write_key(*mode, tenant, &flow.src_ip, &flow.dst_ip, flow.dst_port as u32, &mut buf) {
+                        continue
+                    }
+                    if let Some(t) = f(&buf) {
+                        return Some(t)
+                    }
+                }
+            }
+            None
+        })
+    }
+
+    // Time activity extends the session `details` by, capped at its maximum
+    // lifetime.
+    fn extension_ns(&self, details: &SessionDetails) -> u64 {
+        let extension = match (details.extension, details.mode, &self.decoy) {
+            (Some(e), _, _) => duration_ns(e),
+            (None, SessionMode::Decoy, Some(decoy)) => duration_ns(decoy.extension),
+            _ => match self.transport_extensions.iter().find(|t| t.0 == details.transport) {
+                Some(t) => duration_ns(t.1),
+                None => self.extension.map_or(TIMEOUT_PHANTOMS_NS, duration_ns),
+            },
+        };
+        match self.max_lifetime_ns(details.mode) {
+            Some(max) => cmp::min(extension, max),
+            None => extension,
+        }
+    }
+
+    /// Configured extensions that are 0 or exceed the maximum lifetime of
+    /// the sessions they apply to, as configuration errors.
+    pub fn extension_errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut check = |name: String, extension: Duration, mode: SessionMode| {
+            if extension == Duration::from_secs(0) {
+                errors.push(format!("{} extension is 0", name));
+            } else if let Some(max) = self.max_lifetime_ns(mode) {
+                if duration_ns(extension) > max {
+                    errors.push(format!("{} extension {}s exceeds the maximum session lifetime {}s",
+                        name, extension.as_secs(), max / S2NS));
+                }
+            }
+        };
+        if let Some(e) = self.extension {
+            check("session".to_string(), e, SessionMode::Phantom);
+        }
+        for &(transport, e) in self.transport_extensions.iter() {
+            check(format!("{:?}", transport), e, SessionMode::Phantom);
+        }
+        if let Some(ref decoy) = self.decoy {
+            check("decoy".to_string(), decoy.extension, SessionMode::Decoy);
+        }
+        errors
+    }
+
+    // Longest lifetime given to a new `mode` session.
+    fn max_lifetime_ns(&self, mode: SessionMode) -> Option<u64> {
+        match (mode, &self.decoy) {
+            (SessionMode::Decoy, Some(decoy)) => decoy.max_lifetime.or(self.max_lifetime),
+            _ => self.max_lifetime,
+        }.map(duration_ns)
+    }
+
+    /// Ports sessions are tracked on when their registration has no port.
+    pub fn default_ports(&self) -> &[u16] {
+        match self.phantom_ports.is_empty() {
+            true => &DEFAULT_PHANTOM_PORTS,
+            false => &self.phantom_ports,
+        }
+    }
+
+    // The sessions a registration is tracked as: itself if it has a phantom
+    // port, otherwise one for each default port.
+    fn expand_ports(&self, sessions: &[SessionDetails]) -> Vec<SessionDetails> {
+        let mut expanded = Vec::with_capacity(sessions.len());
+        for session in sessions.iter() {
+            if session.phantom_port != 0 {
+                expanded.push(*session);
+                continue
+            }
+            for port in self.default_ports() {
+                let mut sd = *session;
+                sd.phantom_port = *port as u32;
+                expanded.push(sd);
+            }
+        }
+        expanded
+    }
+}
+
+pub struct SessionTracker
+{
+    // Sessions cannot be tracked by registration because we will not be
+    // receiving registration information in order to identify the sessions. As
+    // such sessions are stored as a thread safe map with keys dependent on the
+    // ip version:
+    // v4 "{}-{}-{}", client_ip, phantom_ip, phantom_port
+    // v4 phantom only "{}-{}", phantom_ip, phantom_port
+    // v4 client prefix "{}/{}-{}-{}", client_net, prefix_len, phantom_ip, phantom_port
+    // v6 "{}-{}", phantom_ip, phantom_port
+    // strict v6 "{}/{}-{}-{}", client_net, prefix_len, phantom_ip, phantom_port
+    // decoy "decoy-{}-{}-{}", client_ip, decoy_ip, decoy_port
+    // The value stored for each of these is a SessionEntry holding the
+    // timestamp to compare for timeout. The map also indexes the keys by
+    // client and phantom address, see session_map.rs.
+    pub tracked_sessions: Arc<RwLock<SessionMap>>,
+
+    // Incremented after keys are added to or removed from tracked_sessions so
+    // the snapshot thread knows when to rebuild.
+    generation: Arc<AtomicUsize>,
+    // Incremented after keys are added to tracked_sessions, and after the
+    // snapshot is rebuilt, so lookups that found nothing before are known to
+    // be stale (see negative_cache.rs).
+    insert_generation: Arc<AtomicU64>,
+
+    // Tracked keys (and their sessions' client ports) used for lock-free
+    // lookups when snapshots are enabled. May lag tracked_sessions by up to
+    // one snapshot interval.
+    snapshot: Option<Arc<ArcSwap<SnapshotKeys>>>,
+
+    // Source of the timestamps used for expire times.
+    clock: Clock,
+
+    config: SessionConfig,
+
+    // Set once a maintenance thread is dropping stale sessions for this tracker.
+    maintained: bool,
+
+    // Station to detector delay of ingested registrations.
+    latency: Arc<IngestLatency>,
+    errors: Arc<IngestErrors>,
+
+    // Set once replication is enabled.
+    replication: Option<Arc<Replication>>,
+
+    // Drain mode, see set_draining.
+    drain: Arc<DrainState>,
+
+    // Set once conntrack teardown is enabled.
+    teardown: Option<Arc<Teardown>>,
+
+    // Set if SessionConfig.replay is.
+    replay: Option<Arc<ReplayGuard>>,
+
+    // Checks the registrations from the control listener, set if
+    // SessionConfig.control is.
+    control_replay: Option<Arc<ReplayGuard>>,
+
+    // Set if SessionConfig.journal_len is.
+    journal: Option<Arc<IngestJournal>>,
+
+    // Set once station config pushes are enabled.
+    station_config: Option<Arc<StationConfig>>,
+
+    // Remaining lifetimes and expirations seen by stale session passes.
+    expiry: Arc<ExpiryStats>,
+
+    // Set if SessionConfig.insert_queue is.
+    insert_queue: Option<Arc<InsertQueue>>,
+
+    // Set if SessionConfig.ingest_pool is, shared by the pools of every
+    // ingest thread.
+    ingest_pool: Option<Arc<IngestPoolStats>>,
+
+    // Set if MaintenanceConfig.adaptive is.
+    cleanup_gauge: Option<Arc<CleanupGauge>>,
+
+    // Set once the session export is started.
+    export: Option<Arc<SessionExport>>,
+
+    // Set once the stored registrations have been read, see is_primed.
+    prime: Arc<AtomicBool>,
+
+    // Beaten by the redis ingest thread, see probes.rs.
+    ingest_heartbeat: Heartbeat,
+
+    // Restarts the redis ingest thread, see supervisor.rs.
+    ingest_supervisor: Arc<Supervisor>,
+
+    // Follows the station publishers' sequence numbers, see sequence.rs.
+    sequences: Arc<SequenceTracker>,
+
+    // Set if SessionConfig.tenants is.
+    tenants: Option<Arc<Tenants>>,
+
+    // Phantom subnets registrations are refused for, see flush_phantom_subnet.
+    phantom_blocks: Arc<PhantomBlocks>,
+
+    // Expiry changes by activity and by the station's updates, and the
+    // activity extensions by this thread not yet added to them.
+    expiry_changes: Arc<ExpiryChanges>,
+    extensions_pending: Cell<u64>,
+
+    // Counters shared by every thread using this tracker (indexed by the
+    // *_COUNTER constants), and this thread's shard of them.
+    counters: Arc<Counters>,
+    local_counters: LocalCounters,
+}
+
+const EXPIRED_MATCHED_COUNTER: usize = 0;
+const EXPIRED_UNMATCHED_COUNTER: usize = 1;
+const DRAIN_IGNORED_COUNTER: usize = 2;
+const TIMEOUT_RAISED_COUNTER: usize = 3;
+const TIMEOUT_LOWERED_COUNTER: usize = 4;
+const TIMEOUT_REJECTED_COUNTER: usize = 5;
+const LOCK_RECOVERED_COUNTER: usize = 6;
+const CLIENT_PORT_REFUSED_COUNTER: usize = 7;
+
+// Activity extensions a thread counts before adding them to ExpiryChanges,
+// so the packet path rarely writes the shared counter.
+const EXTENSION_BATCH: u64 = 64;
+
+// Tracked keys of a lookup snapshot, with their sessions' client ports.
+type SnapshotKeys = HashMap<String, Option<ClientPorts>, MapHasher>;
+
+// Filled in by the stale session passes, taken by the periodic report.
+pub struct ExpiryStats
+{
+    // Sessions per REMAINING_BUCKETS_NS bucket of remaining lifetime at the
+    // last sample.
+    remaining: Mutex<Vec<u64>>,
+    // Clock time (ns) from which the next pass samples.
+    next_sample: AtomicU64,
+    // Counts of the incremental sweep in progress, with a cleanup budget.
+    sweep_remaining: Mutex<Vec<u64>>,
+
+    // Sessions dropped that had a connection forwarded, and that never did,
+    // are counted in the tracker's counters.
+    counters: Arc<Counters>,
+    // The unmatched ones, by transport, registration source and phantom.
+    never_matched: Mutex<HashMap<NeverMatchedKey, u64>>,
+    // The matched ones, up to MAX_EXPIRED_MATCHED, and how many more.
+    expired_matched: Mutex<(Vec<SessionEntry>, u64)>,
+}
+
+pub type NeverMatchedKey = (TransportType, RegistrationSource, IpAddr);
+
+// Bucket of the remaining lifetime distribution for `remaining` ns.
+fn remaining_bucket(remaining: u64) -> usize {
+    REMAINING_BUCKETS_NS.iter()
+        .position(|b| remaining < *b)
+        .unwrap_or(REMAINING_BUCKETS_NS.len())
+}
+
+impl ExpiryStats
+{
+    fn new(counters: Arc<Counters>) -> ExpiryStats {
+        ExpiryStats {
+            remaining: Mutex::new(vec![0; REMAINING_BUCKETS_NS.len() + 1]),
+            next_sample: AtomicU64::new(0),
+            sweep_remaining: Mutex::new(vec![0; REMAINING_BUCKETS_NS.len() + 1]),
+            counters: counters,
+            never_matched: Mutex::new(HashMap::new()),
+            expired_matched: Mutex::new((Vec::new(), 0)),
+        }
+    }
+
+    fn sample_due(&self, right_now: MonotonicNs) -> bool {
+        right_now >= MonotonicNs(self.next_sample.load(Ordering::Relaxed))
+    }
+
+    // Replaces the distribution with that of the live sessions in `entries`.
+    fn sample<'a, I: Iterator<Item = &'a SessionEntry>>(&self, entries: I, right_now: MonotonicNs) {
+        let mut counts = vec![0; REMAINING_BUCKETS_NS.len() + 1];
+        for e in entries.filter(|e| e.expire_time > right_now) {
+            counts[remaining_bucket(e.expire_time.saturating_since(right_now))] += 1;
+        }
+        *self.remaining.lock().unwrap_or_else(|e| e.into_inner()) = counts;
+        self.next_sample.store(right_now.saturating_add(EXPIRY_SAMPLE_INTERVAL_NS).0, Ordering::Relaxed);
+    }
+
+    // Adds the counts of the live sessions visited by one incremental pass.
+    // Once a sweep has covered the whole map they replace the distribution.
+    fn swept(&self, counts: &[u64], wrapped: bool) {
+        let mut sweep = self.sweep_remaining.lock().unwrap_or_else(|e| e.into_inner());
+        for (total, n) in sweep.iter_mut().zip(counts.iter()) {
+            *total += n;
+        }
+        if wrapped {
+            let counts = mem::replace(&mut *sweep, vec![0; REMAINING_BUCKETS_NS.len() + 1]);
+            *self.remaining.lock().unwrap_or_else(|e| e.into_inner()) = counts;
+        }
+    }
+
+    fn expired(&self, local: &LocalCounters, entry: &SessionEntry) {
+        if entry.connections > 0 {
+            local.add(EXPIRED_MATCHED_COUNTER, 1);
+            debug!("Session expired {} [{:016x}] connections {} packets {} bytes {}",
+                entry.details, entry.session_id, entry.connections, entry.packets, entry.bytes);
+            let mut expired = self.expired_matched.lock().unwrap_or_else(|e| e.into_inner());
+            if expired.0.len() < MAX_EXPIRED_MATCHED {
+                expired.0.push(*entry);
+            } else {
+                expired.1 += 1;
+            }
+            return
+        }
+        local.add(EXPIRED_UNMATCHED_COUNTER, 1);
+        let d = &entry.details;
+        let mut never_matched = self.never_matched.lock().unwrap_or_else(|e| e.into_inner());
+        *never_matched.entry((d.transport, d.registration_source, d.phantom_ip)).or_insert(0) += 1;
+    }
+
+    /// Sessions dropped without ever being matched since the last call.
+    pub fn take_never_matched(&self) -> HashMap<NeverMatchedKey, u64> {
+        let mut never_matched = self.never_matched.lock().unwrap_or_else(|e| e.into_inner());
+        mem::replace(&mut *never_matched, HashMap::new())
+    }
+
+    /// Sessions dropped after being matched since the last call, and how
+    /// many more beyond MAX_EXPIRED_MATCHED weren't kept.
+    pub fn take_expired_matched(&self) -> (Vec<SessionEntry>, u64) {
+        let mut expired = self.expired_matched.lock().unwrap_or_else(|e| e.into_inner());
+        mem::replace(&mut *expired, (Vec::new(), 0))
+    }
+
+    /// Remaining lifetime distribution at the last sample, and expirations
+    /// since the last call, formatted for the periodic report.
+    pub fn take_report(&self) -> String {
+        let remaining = self.remaining.lock().unwrap_or_else(|e| e.into_inner());
+        format!("remaining {} expired matched {} unmatched {}",
+            format_buckets(&REMAINING_BUCKETS_NS, &remaining, ns_label),
+            self.counters.take(EXPIRED_MATCHED_COUNTER),
+            self.counters.take(EXPIRED_UNMATCHED_COUNTER))
+    }
+}
+
+struct DrainState
+{
+    active: AtomicBool,
+}
+
+// Sessions whose expiry activity moved, added in batches by the threads
+// extending them, and the station's updates (see refresh_sessions) by
+// outcome.
+#[derive(Default)]
+struct ExpiryChanges
+{
+    extended: AtomicU64,
+    refreshed: AtomicU64,
+    // Refreshes cut short by a lifetime cap.
+    refresh_capped: AtomicU64,
+    // Updates for sessions not tracked.
+    refresh_untracked: AtomicU64,
+}
+
+impl<'a> SessionTracker 
+{
+    pub fn new() -> SessionTracker {
+        SessionTracker::with_clock(Clock::Monotonic)
+    }
+
+    pub fn with_clock(clock: Clock) -> SessionTracker {
+        SessionTracker::with_config(SessionConfig::default(), clock)
+    }
+
+    pub fn with_config(config: SessionConfig, clock: Clock) -> SessionTracker {
+        let replay = config.replay.clone().map(|c| Arc::new(ReplayGuard::new(c)));
+        // control datagrams are always checked, and must carry an id
+        let control_replay = config.control.as_ref().map(|_| Arc::new(ReplayGuard::new(ReplayConfig {
+            require_id: true,
+            ..config.replay.clone().unwrap_or_default()
+        })));
+        let journal = config.journal_len.map(|n| Arc::new(IngestJournal::new(n)));
+        let counters = Arc::new(Counters::new());
+        let primed = config.prime_key.is_none();
+        let tenants = config.tenants.clone().map(|t| Arc::new(Tenants::new(t)));
+        let ingest_pool = config.ingest_pool.as_ref().map(|c| Arc::new(IngestPoolStats::new(c)));
+        let ingest_errors = config.ingest_errors.clone();
+        let cleanup_gauge = match (config.maintenance.interval, config.maintenance.adaptive.as_ref()) {
+            (Some(interval), Some(_)) => Some(Arc::new(CleanupGauge::new(interval))),
+            _ => None,
+        };
+        let mut tracker = SessionTracker{
+            tracked_sessions: Arc::new(RwLock::new(SessionMap::with_hasher(config.hash, config.cleanup_budget.is_some()))),
+            generation: Arc::new(AtomicUsize::new(0)),
+            insert_generation: Arc::new(AtomicU64::new(0)),
+            snapshot: None,
+            clock: clock,
+            config: config,
+            maintained: false,
+            latency: Arc::new(IngestLatency::new()),
+            errors: Arc::new(IngestErrors::new(ingest_errors)),
+            replication: None,
+            drain: Arc::new(DrainState { active: AtomicBool::new(false) }),
+            teardown: None,
+            replay: replay,
+            control_replay: control_replay,
+            journal: journal,
+            station_config: None,
+            expiry: Arc::new(ExpiryStats::new(Arc::clone(&counters))),
+            insert_queue: None,
+            ingest_pool: ingest_pool,
+            cleanup_gauge: cleanup_gauge,
+            export: None,
+            prime: Arc::new(AtomicBool::new(primed)),
+            ingest_heartbeat: Heartbeat::new(),
+            ingest_supervisor: Arc::new(Supervisor::new("ingest supervisor")),
+            sequences: Arc::new(SequenceTracker::new()),
+            tenants: tenants,
+            phantom_blocks: Arc::new(PhantomBlocks::new()),
+            expiry_changes: Arc::new(ExpiryChanges::default()),
+            extensions_pending: Cell::new(0),
+            local_counters: counters.local(),
+            counters: counters,
+        };
+        if let Some(ref queue_config) = tracker.config.insert_queue {
+            // The applier's own copy has no queue, so the applier exits once
+            // every tracker sharing the queue is dropped.
+            let mut applier = tracker.shared();
+            let queue = InsertQueue::spawn(queue_config, move |registrations| {
+                applier.insert_registrations(registrations)
+            });
+            tracker.insert_queue = Some(Arc::new(queue));
+        }
+        tracker
+    }
+
+    pub fn add_session(&mut self, det: SessionDetails) {
+        self.insert_session(det)
+    }
+
+    pub fn spawn_update_thread(&self) {
+        let heartbeat = self.ingest_heartbeat.clone();
+        self.spawn_supervised_ingest(move || RedisFeed::new(REDIS_URL, REDIS_CHANNEL).with_heartbeat(heartbeat.clone()));
+        if let Some(ref key) = self.config.prime_key {
+            self.spawn_prime_thread(RedisSnapshot::new(REDIS_URL, key));
+        }
+    }
+
+    /// Spawns a thread that runs an ingest thread (see spawn_ingest_thread)
+    /// on a feed from `make_feed`, and restarts it on a new feed whenever it
+    /// panics or its feed fails, see supervisor.rs.
+    pub fn spawn_supervised_ingest<M, F>(&self, mut make_feed: M) -> JoinHandle<()>
+        where M: FnMut() -> F + Send + 'static, F: RegistrationFeed + Send + 'static
+    {
+        let tracker = self.shared();
+        let supervisor = Arc::clone(&self.ingest_supervisor);
+        thread::spawn(move || supervisor.supervise(|| {
+            let ingest = tracker.shared();
+            let mut feed = make_feed();
+            thread::spawn(move || run_ingest(ingest, &mut feed))
+        }))
+    }
+
+    /// Restarts of the redis ingest thread.
+    pub fn ingest_supervisor(&self) -> &Supervisor {
+        &self.ingest_supervisor
+    }
+
+    /// The station publishers' sequence numbers ingested so far.
+    pub fn sequences(&self) -> &SequenceTracker {
+        &self.sequences
+    }
+
+    /// Sessions, registrations and deletions per tenant since the last call,
+    /// None without SessionConfig.tenants.
+    pub fn take_tenant_report(&self) -> Option<String> {
+        let tenants = match self.tenants {
+            Some(ref t) => t,
+            None => return None,
+        };
+        let (counts, unknown) = tenants.take_counts();
+        let rmap = self.read_sessions();
+        let per_tenant: Vec<String> = counts.iter().map(|&(id, ref c)| {
+            format!("{} sessions {} accepted {} over session limit {} over rate limit {} deleted {}",
+                id, rmap.tenant_len(id), c.accepted, c.over_session_limit, c.over_rate_limit, c.deleted)
+        }).collect();
+        Some(format!("tenants {}, unknown tenant refused {}", per_tenant.join(", "), unknown))
+    }
+
+    /// Removes every session whose phantom is in `subnet`, whatever their
+    /// registrations, handing those with active connections to the teardown
+    /// thread if enabled. With `block` set, registrations for phantoms in
+    /// `subnet` are also refused for that long. Returns how many sessions
+    /// were removed.
+    pub fn flush_phantom_subnet(&mut self, subnet: IpNetwork, block: Option<Duration>) -> usize {
+        if let Some(b) = block {
+            let until = self.clock.now().saturating_add(b.as_nanos() as u64);
+            self.phantom_blocks.block(subnet, until);
+        }
+        let mut mmap = self.write_sessions();
+        let keys: Vec<String> = mmap.subnet_keys(&subnet).into_iter().cloned().collect();
+        let mut connected = Vec::new();
+        for key in keys.iter() {
+            if let Some(e) = mmap.remove(key) {
+                if e.active_connections > 0 {
+                    connected.push(e.details);
+                }
+            }
+        }
+        drop(mmap);
+        if !keys.is_empty() {
+            self.generation.fetch_add(1, Ordering::Release);
+        }
+        if !connected.is_empty() {
+            self.tear_down(connected);
+        }
+        warn!("flushed {} sessions for phantoms in {}", keys.len(), subnet);
+        keys.len()
+    }
+
+    /// Lifts the block flush_phantom_subnet put on `subnet`, returning
+    /// whether there was one.
+    pub fn unblock_phantom_subnet(&self, subnet: &IpNetwork) -> bool {
+        self.phantom_blocks.unblock(subnet)
+    }
+
+    /// One line per phantom subnet blocked, with the time left.
+    pub fn describe_phantom_blocks(&self) -> Vec<String> {
+        let right_now = self.clock.now();
+        self.phantom_blocks.active(right_now).iter().map(|b| {
+            format!("{} blocked for {}s", b.subnet, b.until.saturating_since(right_now) / S2NS)
+        }).collect()
+    }
+
+    /// Registrations refused for blocked phantoms since the last call.
+    pub fn take_phantom_blocks_refused(&self) -> u64 {
+        self.phantom_blocks.take_refused()
+    }
+
+    // The subnet blocking one of `sessions`' phantoms, if any.
+    fn blocked_phantom(&self, sessions: &[SessionDetails]) -> Option<IpNetwork> {
+        let right_now = self.clock.now();
+        sessions.iter()
+            .filter_map(|sd| self.phantom_blocks.refuses(&sd.phantom_ip, right_now))
+            .map(|b| b.subnet)
+            .next()
+    }
+
+    /// Deletes every session of `tenant`, whatever their registrations.
+    /// The default station's sessions are never deleted this way. Returns
+    /// how many were.
+    pub fn delete_tenant_sessions(&mut self, tenant: TenantId) -> usize {
+        if tenant == DEFAULT_TENANT {
+            return 0
+        }
+        let mut mmap = self.write_sessions();
+        let keys: Vec<String> = mmap.tenant_keys(tenant).into_iter().cloned().collect();
+        for key in keys.iter() {
+            mmap.remove(key);
+        }
+        drop(mmap);
+        if !keys.is_empty() {
+            self.generation.fetch_add(1, Ordering::Release);
+        }
+        if let Some(ref t) = self.tenants {
+            t.count_deleted(tenant, keys.len());
+        }
+        keys.len()
+    }
+
+    /// Spawns a thread adding the registrations the station has stored, read
+    /// from `feed`, to this tracker. The tracker is primed once the feed
+    /// closes or fails.
+    pub fn spawn_prime_thread<F>(&self, mut feed: F) -> JoinHandle<()>
+        where F: RegistrationFeed + Send + 'static
+    {
+        let tracker = self.shared();
+        thread::spawn(move || { prime_from_feed(tracker, &mut feed) })
+    }
+
+    /// Whether the registrations the station had stored before we subscribed
+    /// have been read (always, without SessionConfig.prime_key). Until then
+    /// the tracker misses sessions registered before startup.
+    pub fn is_primed(&self) -> bool {
+        self.prime.load(Ordering::Acquire)
+    }
+
+    /// Spawns a thread adding the registrations received from `feed` to this
+    /// tracker. The thread exits when the feed closes or fails.
+    pub fn spawn_ingest_thread<F>(&self, mut feed: F) -> JoinHandle<()>
+        where F: RegistrationFeed + Send + 'static
+    {
+        let tracker = self.shared();
+        thread::spawn(move || { ingest_from_feed(tracker, &mut feed) })
+    }
+
+    /// Starts replicating sessions as configured in SessionConfig.replication:
+    /// publishing what our ingest thread accepts (if enabled) and ingesting
+    /// from every peer's redis. Call before spawn_update_thread so the ingest
+    /// thread has the publisher. Does nothing if replication is not configured
+    /// or already enabled.
+    pub fn spawn_replication(&mut self) {
+        let config = match self.config.replication {
+            Some(ref c) if self.replication.is_none() => c.clone(),
+            _ => return,
+        };
+        self.enable_replication(Replication::spawn(&config));
+        for (i, peer) in config.peers.iter().enumerate() {
+            self.spawn_peer_thread(i, RedisFeed::new(peer, REPLICATION_CHANNEL));
+        }
+    }
+
+    /// Uses `replication` for the sessions accepted from now on, and for the
+    /// counters of threads started by spawn_peer_thread.
+    pub fn enable_replication(&mut self, replication: Replication) {
+        self.replication = Some(Arc::new(replication));
+    }
+
+    /// Spawns a thread adding the sessions replicated by peer number `peer`
+    /// (of the enabled Replication) from `feed`. The thread exits when the
+    /// feed closes or fails.
+    pub fn spawn_peer_thread<F>(&self, peer: usize, mut feed: F) -> JoinHandle<()>
+        where F: RegistrationFeed + Send + 'static
+    {
+        let tracker = self.shared();
+        thread::spawn(move || { ingest_from_peer(tracker, peer, &mut feed) })
+    }
+
+    /// Replication counters, if replication is enabled.
+    pub fn replication(&self) -> Option<&Replication> {
+        match self.replication {
+            Some(ref r) => Some(r),
+            None => None,
+        }
+    }
+
+    /// Starts the conntrack teardown thread if SessionConfig.conntrack_teardown
+    /// is set. Call before spawn_maintenance_thread so the maintenance thread
+    /// tears down what it drops.
+    pub fn spawn_conntrack_teardown(&mut self) {
+        if self.config.conntrack_teardown && self.teardown.is_none() {
+            self.enable_conntrack_teardown(Teardown::spawn(NetlinkConntrack));
+        }
+    }
+
+    /// Passes the sessions dropped from now on that still had active
+    /// connections to `teardown`.
+    pub fn enable_conntrack_teardown(&mut self, teardown: Teardown) {
+        self.teardown = Some(Arc::new(teardown));
+    }
+
+    /// Teardown counters, if conntrack teardown is enabled.
+    pub fn conntrack_teardown(&self) -> Option<&Teardown> {
+        match self.teardown {
+            Some(ref t) => Some(t),
+            None => None,
+        }
+    }
+
+    /// Starts acknowledging station config pushes if
+    /// SessionConfig.accept_config_push is set. Call before
+    /// spawn_update_thread so the ingest threads apply them.
+    pub fn spawn_station_config(&mut self) {
+        if self.config.accept_config_push && self.station_config.is_none() {
+            self.enable_station_config(StationConfig::spawn());
+        }
+    }
+
+    /// Applies the config pushes received from now on to `station_config`.
+    pub fn enable_station_config(&mut self, station_config: StationConfig) {
+        self.station_config = Some(Arc::new(station_config));
+    }
+
+    /// Configuration pushed by the station, if pushes are enabled.
+    pub fn station_config(&self) -> Option<&StationConfig> {
+        match self.station_config {
+            Some(ref c) => Some(c),
+            None => None,
+        }
+    }
+
+    /// Replay counters, if SessionConfig.replay is set.
+    pub fn replay_guard(&self) -> Option<&ReplayGuard> {
+        match self.replay {
+            Some(ref r) => Some(r),
+            None => None,
+        }
+    }
+
+    /// Replay counters of the control listener, if SessionConfig.control is
+    /// set.
+    pub fn control_replay_guard(&self) -> Option<&ReplayGuard> {
+        match self.control_replay {
+            Some(ref r) => Some(r),
+            None => None,
+        }
+    }
+
+    /// Registrations recently received from the station, if
+    /// SessionConfig.journal_len is set.
+    pub fn journal(&self) -> Option<&IngestJournal> {
+        match self.journal {
+            Some(ref j) => Some(j),
+            None => None,
+        }
+    }
+
+    // Records in the journal, if any, that `reg` was accepted (`rejected`
+    // None) or why it wasn't.
+    fn journal_record(&self, reg: &Registration, rejected: Option<String>) {
+        if let Some(ref journal) = self.journal {
+            journal.record(reg, rejected);
+        }
+    }
+
+    /// Spawns a thread ingesting the registrations sent to the control address
+    /// in SessionConfig.control, if set.
+    pub fn spawn_control_listener(&self) {
+        if let Some(ref config) = self.config.control {
+            match ControlFeed::bind(config) {
+                Ok(feed) => { self.spawn_control_thread(feed); },
+                Err(e) => error!("control listener on {} disabled: {}", config.addr, error_chain(&e)),
+            }
+        }
+    }
+
+    /// Spawns a thread adding the registrations received from `feed`, the
+    /// control listener's, to this tracker. They are handled like those from
+    /// redis, but always checked by the control replay guard (accepting
+    /// everything if SessionConfig.control isn't set). The thread exits when
+    /// the feed closes or fails.
+    pub fn spawn_control_thread<F>(&self, mut feed: F) -> JoinHandle<()>
+        where F: RegistrationFeed + Send + 'static
+    {
+        let mut tracker = self.shared();
+        tracker.replay = self.control_replay.clone();
+        thread::spawn(move || { ingest_from_feed(tracker, &mut feed) })
+    }
+
+    /// Spawns a thread importing sessions handed off by another detector on
+    /// our redis, if SessionConfig.accept_handoff is set.
+    pub fn spawn_handoff_import(&self) {
+        if self.config.accept_handoff {
+            self.spawn_handoff_thread(RedisFeed::new(REDIS_URL, HANDOFF_CHANNEL));
+        }
+    }
+
+    /// Spawns a thread importing the handed off sessions received from
+    /// `feed`. The thread exits when the feed closes or fails.
+    pub fn spawn_handoff_thread<F>(&self, mut feed: F) -> JoinHandle<()>
+        where F: RegistrationFeed + Send + 'static
+    {
+        let tracker = self.shared();
+        thread::spawn(move || { import_handoff(tracker, &mut feed) })
+    }
+
+    /// Spawns the thread serving admin commands for this tracker on
+    /// `listener`.
+    pub fn spawn_admin_thread(&self, listener: UnixListener, health: Option<HealthcheckConfig>) -> JoinHandle<()> {
+        let tracker = self.shared();
+        thread::spawn(move || { admin::serve(listener, tracker, health) })
+    }
+
+    /// Spawns the thread answering readiness and liveness probes for this
+    /// tracker's core on `listener`. The redis ingest thread and its
+    /// supervisor are added to what `watchdog` watches.
+    pub fn spawn_probe_thread(&self, listener: TcpListener, mut watchdog: Watchdog) -> JoinHandle<()> {
+        watchdog.watch("ingest", self.ingest_heartbeat.clone());
+        watchdog.supervise(Arc::clone(&self.ingest_supervisor));
+        let tracker = self.shared();
+        thread::spawn(move || { probes::serve(listener, tracker, watchdog) })
+    }
+
+    /// In drain mode new registrations and updates (from our station or
+    /// replicated by peers) are ignored, while existing sessions are served,
+    /// and extended by activity as usual, until they expire. Applies to every
+    /// thread sharing this tracker.
+    pub fn set_draining(&self, draining: bool) {
+        self.drain.active.store(draining, Ordering::Release);
+    }
+
+    pub fn config(&self) -> &SessionConfig {
+        &self.config
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.drain.active.load(Ordering::Acquire)
+    }
+
+    /// Counts a registration ignored because of drain mode.
+    pub fn count_drained(&self) {
+        self.local_counters.add(DRAIN_IGNORED_COUNTER, 1);
+    }
+
+    /// Registrations ignored in drain mode since the last call.
+    pub fn take_drained(&self) -> u64 {
+        self.counters.take(DRAIN_IGNORED_COUNTER)
+    }
+
+    /// Applies SessionConfig.timeout_bounds to the sessions of a
+    /// registration, clamping their lifetimes in place. If any is rejected the
+    /// whole registration is, and the sessions are left as they were.
+    pub fn bound_timeouts(&self, sessions: &mut [SessionDetails]) -> Result<(), SessionError> {
+        let bounds = match self.config.timeout_bounds {
+            Some(ref b) => b,
+            None => return Ok(()),
+        };
+        let mut bounded = Vec::with_capacity(sessions.len());
+        for sd in sessions.iter() {
+            match bounds.enforce(sd.timeout()).map(duration_ns) {
+                Ok(t) => bounded.push(t),
+                Err(e) => {
+                    self.local_counters.add(TIMEOUT_REJECTED_COUNTER, 1);
+                    return Err(e)
+                },
+            }
+        }
+        // the sessions of a registration share its lifetime, count it once
+        match (sessions.first(), bounded.first()) {
+            (Some(sd), Some(&t)) if t > sd.timeout => self.local_counters.add(TIMEOUT_RAISED_COUNTER, 1),
+            (Some(sd), Some(&t)) if t < sd.timeout => self.local_counters.add(TIMEOUT_LOWERED_COUNTER, 1),
+            _ => {},
+        }
+        for (sd, t) in sessions.iter_mut().zip(bounded) {
+            sd.timeout = t;
+        }
+        Ok(())
+    }
+
+    /// Registrations whose lifetime was raised to the floor, lowered to the
+    /// ceiling and rejected since the last call, formatted for the periodic
+    /// report.
+    pub fn take_timeout_report(&self) -> String {
+        format!("raised {} lowered {} rejected {}",
+            self.counters.take(TIMEOUT_RAISED_COUNTER),
+            self.counters.take(TIMEOUT_LOWERED_COUNTER),
+            self.counters.take(TIMEOUT_REJECTED_COUNTER))
+    }
+
+    // The session map for reading. See write_sessions for a lock left
+    // poisoned by a panic.
+    fn read_sessions(&self) -> RwLockReadGuard<'_, SessionMap> {
+        if let Ok(map) = self.tracked_sessions.read() {
+            return map
+        }
+        drop(self.write_sessions());
+        self.tracked_sessions.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // The session map for writing. A thread that panicked holding the write
+    // lock poisons it, and would take every thread touching sessions down
+    // with it; instead the map's indexes are rebuilt from its entries, which
+    // a panic can't leave half inserted, and the lock is cleared.
+    fn write_sessions(&self) -> RwLockWriteGuard<'_, SessionMap> {
+        match self.tracked_sessions.write() {
+            Ok(map) => map,
+            Err(e) => {
+                let mut map = e.into_inner();
+                map.repair();
+                self.tracked_sessions.clear_poison();
+                // the snapshot may have missed the half finished change
+                self.generation.fetch_add(1, Ordering::Release);
+                self.insert_generation.fetch_add(1, Ordering::Release);
+                self.local_counters.add(LOCK_RECOVERED_COUNTER, 1);
+                error!("session map lock poisoned by a panic, recovered {} sessions", map.len());
+                map
+            },
+        }
+    }
+
+    /// Times the session map was recovered after a panic since the last
+    /// call.
+    pub fn take_lock_recoveries(&self) -> u64 {
+        self.counters.take(LOCK_RECOVERED_COUNTER)
+    }
+
+    /// Every tracked session that hasn't expired, with its timeout set to the
+    /// remaining lifetime, for handing off to another detector.
+    pub fn export(&self) -> Vec<SessionDetails> {
+        let right_now = self.clock.now();
+        let map = self.read_sessions();
+        map.values()
+            .filter(|e| e.expire_time > right_now)
+            .map(|e| {
+                let mut details = e.details;
+                details.timeout = e.expire_time.saturating_since(right_now);
+                details
+            })
+            .collect()
+    }
+
+    /// Enters drain mode and hands every session off to the detector whose
+    /// redis is at `url`. Returns the number of sessions sent.
+    pub fn handoff(&self, url: &str) -> DetectorResult<usize> {
+        self.set_draining(true);
+        let sessions = self.export();
+        let origin = match self.config.replication {
+            Some(ref r) => r.detector_id,
+            None => 0,
+        };
+        send_handoff(url, &handoff_messages(origin, &sessions))?;
+        Ok(sessions.len())
+    }
+
+    /// Starts the thread that drops stale sessions according to the configured
+    /// MaintenanceConfig, adapting the interval after each pass if it is
+    /// adaptive. Does nothing (returns None) if no interval is set or the
+    /// thread is already running.
+    pub fn spawn_maintenance_thread(&mut self) -> Option<JoinHandle<()>> {
+        let schedule = self.config.maintenance.clone();
+        let interval = match schedule.interval {
+            Some(i) if !self.maintained => i,
+            _ => return None,
+        };
+        self.maintained = true;
+
+        let mut tracker = self.shared();
+        tracker.maintained = true;
+        let mut adaptive = match (schedule.adaptive.as_ref(), self.cleanup_gauge.as_ref()) {
+            (Some(config), Some(gauge)) => Some(AdaptiveInterval::new(interval, config, Arc::clone(gauge))),
+            _ => None,
+        };
+        Some(thread::spawn(move || {
+            let jitter_ns = schedule.jitter.as_secs() * S2NS + schedule.jitter.subsec_nanos() as u64;
+            let mut rng = rand::thread_rng();
+            let mut interval = interval;
+            let mut last_pass = tracker.clock.now();
+            loop {
+                let delay = match jitter_ns {
+                    0 => interval,
+                    j => interval + Duration::from_nanos(rng.gen_range(0, j)),
+                };
+                thread::sleep(delay);
+                let dropped = tracker.drop_stale_sessions_bounded(schedule.max_drops_per_tick);
+                if let Some(ref mut adaptive) = adaptive {
+                    let now = tracker.clock.now();
+                    let elapsed = Duration::from_nanos(now.saturating_since(last_pass));
+                    last_pass = now;
+                    let capped = schedule.max_drops_per_tick.map_or(false, |m| dropped >= m);
+                    interval = adaptive.next(tracker.len(), dropped, capped, elapsed);
+                }
+            }
+        }))
+    }
+
+    /// True if a maintenance thread is dropping stale sessions, so the owner
+    /// doesn't need to call drop_stale_sessions.
+    pub fn is_maintained(&self) -> bool {
+        self.maintained
+    }
+
+    /// Switches is_tracked_session to read from a snapshot of the tracked keys
+    /// and spawns the thread that rebuilds it, checking for changes every
+    /// `interval`.
+    pub fn enable_snapshot(&mut self, interval: Duration) {
+        if self.snapshot.is_some() {
+            return
+        }
+        let built_generation = self.generation.load(Ordering::Acquire);
+        let snapshot = Arc::new(ArcSwap::from_pointee(self.snapshot_keys()));
+        self.snapshot = Some(Arc::clone(&snapshot));
+
+        let mut tracker = self.shared();
+        tracker.snapshot = Some(snapshot);
+        thread::spawn(move || { refresh_snapshot(tracker, built_generation, interval) });
+    }
+
+    /// Delay of registrations received by the ingest thread.
+    pub fn ingest_latency(&self) -> &IngestLatency {
+        &self.latency
+    }
+
+    /// Failed registrations, and messages shed because of them.
+    pub fn ingest_errors(&self) -> &IngestErrors {
+        &self.errors
+    }
+
+    /// Ingest worker pool counters, if SessionConfig.ingest_pool is set.
+    pub fn ingest_pool(&self) -> Option<&IngestPoolStats> {
+        match self.ingest_pool {
+            Some(ref p) => Some(p),
+            None => None,
+        }
+    }
+
+    /// Interval the maintenance thread chose last, if MaintenanceConfig.adaptive
+    /// is set.
+    pub fn cleanup_gauge(&self) -> Option<&CleanupGauge> {
+        match self.cleanup_gauge {
+            Some(ref g) => Some(g),
+            None => None,
+        }
+    }
+
+    /// Insertion queue counters, if SessionConfig.insert_queue is set.
+    pub fn insert_queue(&self) -> Option<&InsertQueue> {
+        match self.insert_queue {
+            Some(ref q) => Some(q),
+            None => None,
+        }
+    }
+
+    /// Starts the thread exporting session records if SessionConfig.export
+    /// is set. Does nothing if it is already running.
+    pub fn spawn_session_export(&mut self) {
+        if let Some(ref config) = self.config.export {
+            if self.export.is_none() {
+                self.export = Some(Arc::new(SessionExport::spawn(config.clone(), self.shared())));
+            }
+        }
+    }
+
+    /// Export counters, if the session export is running.
+    pub fn session_export(&self) -> Option<&SessionExport> {
+        match self.export {
+            Some(ref e) => Some(e),
+            None => None,
+        }
+    }
+
+    /// Anonymized records of every tracked session, identified by their keys
+    /// hashed under `hash_key`. The sessions are copied under the read lock
+    /// and hashed once it is released.
+    pub fn export_records(&self, hash_key: &[u8], wall_now: WallClockNs) -> Vec<SessionRecord> {
+        let right_now = self.clock.now();
+        let snapshot: Vec<(String, SessionEntry)> = self.read_sessions().iter()
+            .map(|(key, entry)| (key.clone(), *entry))
+            .collect();
+        snapshot.iter().map(|(key, entry)| session_export::record(key, entry, hash_key, right_now, wall_now)).collect()
+    }
+
+    /// Remaining lifetimes and expirations seen by the stale session passes.
+    pub fn expiry_stats(&self) -> &ExpiryStats {
+        &self.expiry
+    }
+
+    // Another handle on the same sessions for a background thread. Snapshot
+    // lookups and the maintained flag are not shared.
+    fn shared(&self) -> SessionTracker {
+        SessionTracker{
+            tracked_sessions: Arc::clone(&self.tracked_sessions),
+            generation: Arc::clone(&self.generation),
+            insert_generation: Arc::clone(&self.insert_generation),
+            snapshot: None,
+            clock: self.clock.clone(),
+            config: self.config.clone(),
+            maintained: false,
+            latency: Arc::clone(&self.latency),
+            errors: Arc::clone(&self.errors),
+            replication: self.replication.clone(),
+            drain: Arc::clone(&self.drain),
+            teardown: self.teardown.clone(),
+            replay: self.replay.clone(),
+            control_replay: self.control_replay.clone(),
+            journal: self.journal.clone(),
+            station_config: self.station_config.clone(),
+            expiry: Arc::clone(&self.expiry),
+            insert_queue: self.insert_queue.clone(),
+            ingest_pool: self.ingest_pool.clone(),
+            cleanup_gauge: self.cleanup_gauge.clone(),
+            export: self.export.clone(),
+            prime: Arc::clone(&self.prime),
+            ingest_heartbeat: self.ingest_heartbeat.clone(),
+            ingest_supervisor: Arc::clone(&self.ingest_supervisor),
+            sequences: Arc::clone(&self.sequences),
+            tenants: self.tenants.clone(),
+            phantom_blocks: Arc::clone(&self.phantom_blocks),
+            expiry_changes: Arc::clone(&self.expiry_changes),
+            extensions_pending: Cell::new(0),
+            counters: Arc::clone(&self.counters),
+            local_counters: self.counters.local(),
+        }
+    }
+
+    /// Changes whenever a session is added, or becomes visible to lookups
+    /// through the snapshot. Read before a lookup that found nothing, the
+    /// result holds until this changes.
+    pub fn insert_generation(&self) -> u64 {
+        self.insert_generation.load(Ordering::Acquire)
+    }
+
+    /// Whether a session is tracked for `flow`, whatever its client ports.
+    pub fn is_tracked_session(&self, flow: &FlowNoSrcPort) -> bool {
+        self.tracks(flow, None)
+    }
+
+    /// Whether `flow` matches a tracked session: one is tracked for it and,
+    /// if it restricts its client's source ports, they include the flow's.
+    /// Flows from other ports are counted (take_client_port_refusals).
+    pub fn is_tracked_flow(&self, flow: &Flow) -> bool {
+        self.tracks(&FlowNoSrcPort::from_flow(flow), Some(flow.src_port))
+    }
+
+    fn tracks(&self, flow: &FlowNoSrcPort, src_port: Option<u16>) -> bool {
+        let mut refused = false;
+        let mut allows = |ports: Option<ClientPorts>| match (ports, src_port) {
+            (Some(ports), Some(port)) if !ports.contains(port) => {
+                refused = true;
+                None
+            },
+            _ => Some(()),
+        };
+        let tracked = self.config.find_flow_key(flow, |key| {
+            match self.snapshot {
+                Some(ref snapshot) => snapshot.load().get(key).and_then(|ports| allows(*ports)),
+                None => self.read_sessions().get(key).and_then(|e| allows(e.details.client_ports)),
+            }
+        }).is_some();
+        if !tracked && refused {
+            self.local_counters.add(CLIENT_PORT_REFUSED_COUNTER, 1);
+        }
+        tracked
+    }
+
+    /// Packets that matched a session's key but not its client ports since
+    /// the last call.
+    pub fn take_client_port_refusals(&self) -> u64 {
+        self.counters.take(CLIENT_PORT_REFUSED_COUNTER)
+    }
+
+    /// Copy of the entry `flow` matches, if any. Always reads the session map
+    /// (never the snapshot) so only call this when the details are needed.
+    pub fn get_session(&self, flow: &FlowNoSrcPort) -> Option<SessionEntry> {
+        self.config.find_flow_key(flow, |key| {
+            self.read_sessions().get(key).cloned()
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        let map = self.read_sessions();
+        let res = map.len();
+        drop(map);
+        return res
+    }
+
+    /// Number of currently tracked sessions attributed to each registration
+    /// source, in the order the sources are declared in the protobuf. The
+    /// sessions of a group count once.
+    pub fn count_by_source(&self) -> Vec<(RegistrationSource, usize)> {
+        let map = self.read_sessions();
+        let mut counts: HashMap<RegistrationSource, usize> = HashMap::new();
+        let mut groups = HashSet::new();
+        for entry in map.values() {
+            if let Some(group) = entry.details.group {
+                if !groups.insert(group) {
+                    continue
+                }
+            }
+            *counts.entry(entry.details.registration_source).or_insert(0) += 1;
+        }
+        drop(map);
+
+        RegistrationSource::values().iter()
+            .map(|src| (*src, *counts.get(src).unwrap_or(&0)))
+            .collect()
+    }
+
+    /// Calls `f` with every tracked session, holding the read lock throughout.
+    pub fn for_each_session<F: FnMut(&SessionEntry)>(&self, mut f: F) {
+        let map = self.read_sessions();
+        for entry in map.values() {
+            f(entry);
+        }
+    }
+
+    /// Tracked sessions per phantom port, by port.
+    pub fn sessions_per_port(&self) -> Vec<(u16, usize)> {
+        let mut ports = BTreeMap::new();
+        self.for_each_session(|entry| {
+            *ports.entry(entry.details.phantom_port as u16).or_insert(0) += 1;
+        });
+        ports.into_iter().collect()
+    }
+
+    /// One line per tracked session describing the registration and how long
+    /// until it expires, sorted by remaining lifetime.
+    pub fn dump(&self) -> Vec<String> {
+        let right_now = self.clock.now();
+        let map = self.read_sessions();
+        let mut entries: Vec<SessionEntry> = map.values().cloned().collect();
+        drop(map);
+
+        entries.sort_by_key(|e| e.expire_time);
+        entries.iter().map(|e| describe_entry(e, right_now)).collect()
+    }
+
+    /// Dump lines of the sessions `session` maps to (one per default port if
+    /// it has no port), for checking that a registration landed.
+    pub fn describe_registration(&self, session: &SessionDetails) -> Vec<String> {
+        let right_now = self.clock.now();
+        let map = self.read_sessions();
+        self.config.expand_ports(&[*session]).iter()
+            .filter_map(|sd| map.get(&self.session_key(sd)?))
+            .map(|e| describe_entry(e, right_now))
+            .collect()
+    }
+
+    /// Dump lines of the sessions registered by `client`, sorted by remaining
+    /// lifetime.
+    pub fn describe_client(&self, client: &IpAddr) -> Vec<String> {
+        let map = self.read_sessions();
+        let entries = map.client_keys(client).into_iter().filter_map(|k| map.get(k)).cloned().collect();
+        drop(map);
+        self.describe_entries(entries)
+    }
+
+    /// Dump lines of the sessions registered for `phantom` (on `port` if
+    /// given), sorted by remaining lifetime.
+    pub fn describe_phantom(&self, phantom: &IpAddr, port: Option<u16>) -> Vec<String> {
+        let map = self.read_sessions();
+        let entries = map.phantom_keys(phantom).into_iter()
+            .filter_map(|k| map.get(k))
+            .filter(|e| port.map_or(true, |p| e.details.phantom_port == p as u32))
+            .cloned()
+            .collect();
+        drop(map);
+        self.describe_entries(entries)
+    }
+
+    fn describe_entries(&self, mut entries: Vec<SessionEntry>) -> Vec<String> {
+        let right_now = self.clock.now();
+        entries.sort_by_key(|e| e.expire_time);
+        entries.iter().map(|e| describe_entry(e, right_now)).collect()
+    }
+
+    pub fn drop_stale_sessions(&mut self) -> usize {
+        self.drop_stale_sessions_bounded(None)
+    }
+
+    /// Drops at most `max_drops` stale sessions (all of them if None) and
+    /// returns the number dropped.
+    pub fn drop_stale_sessions_bounded(&mut self, max_drops: Option<usize>) -> usize {
+        if let Some(budget) = self.config.cleanup_budget.clone() {
+            return self.sweep_stale_sessions(&budget, max_drops)
+        }
+        let max_drops = match max_drops {
+            Some(m) => m,
+            None => return self.drop_all_stale_sessions(),
+        };
+        let right_now = self.clock.now();
+
+        // Find candidates under the read lock so lookups continue meanwhile.
+        let rmap = self.read_sessions();
+        if self.expiry.sample_due(right_now) {
+            self.expiry.sample(rmap.values(), right_now);
+        }
+        let stale: Vec<String> = rmap.iter()
+            .filter(|&(_, v)| v.expire_time <= right_now)
+            .take(max_drops)
+            .map(|(k, _)| k.clone())
+            .collect();
+        drop(rmap);
+        if stale.is_empty() {
+            return 0
+        }
+
+        // Sessions may have been extended since, so check again.
+        let mut map = self.write_sessions();
+        let mut dropped = 0;
+        let mut connected = Vec::new();
+        let mut removed = Vec::new();
+        for key in stale.iter() {
+            let expired = match map.get(key) {
+                Some(v) => v.expire_time <= right_now,
+                None => false,
+            };
+            if expired {
+                if let Some(v) = map.remove(key) {
+                    if v.active_connections > 0 {
+                        connected.push(v.details);
+                    }
+                    removed.push(v);
+                }
+                dropped += 1;
+            }
+        }
+        for v in settle_groups(&mut map, removed).iter() {
+            self.expiry.expired(&self.local_counters, v);
+        }
+        let num_sessions_after = map.len();
+        drop(map);
+        self.tear_down(connected);
+        if dropped > 0 {
+            self.generation.fetch_add(1, Ordering::Release);
+            debug!("Dark Decoys drops: {} - > {}", num_sessions_after + dropped, num_sessions_after);
+        }
+        dropped
+    }
+
+    // Stale session pass with a cleanup budget, see SessionMap::sweep.
+    fn sweep_stale_sessions(&mut self, budget: &SweepBudget, max_drops: Option<usize>) -> usize {
+        let right_now = self.clock.now();
+        let mut counts = vec![0; REMAINING_BUCKETS_NS.len() + 1];
+
+        let mut map = self.write_sessions();
+        let sweep = map.sweep(budget, max_drops.unwrap_or(usize::max_value()), |e| {
+            if e.expire_time <= right_now {
+                return true
+            }
+            counts[remaining_bucket(e.expire_time.saturating_since(right_now))] += 1;
+            false
+        });
+        let dropped = sweep.removed.len();
+        let connected: Vec<SessionDetails> = sweep.removed.iter()
+            .filter(|v| v.active_connections > 0)
+            .map(|v| v.details)
+            .collect();
+        let settled = settle_groups(&mut map, sweep.removed);
+        let num_sessions_after = map.len();
+        drop(map);
+
+        self.expiry.swept(&counts, sweep.wrapped);
+        for v in settled.iter() {
+            self.expiry.expired(&self.local_counters, v);
+        }
+        self.tear_down(connected);
+        if dropped > 0 {
+            self.generation.fetch_add(1, Ordering::Release);
+            debug!("Dark Decoys drops: {} - > {}", num_sessions_after + dropped, num_sessions_after);
+        }
+        dropped
+    }
+
+    fn drop_all_stale_sessions(&mut self) -> usize {
+        let right_now = self.clock.now();
+
+        let mut map = self.write_sessions();
+        let num_sessions_before = map.len();
+        // Dark Decoys Map is not sorted by timeout, so need to check all
+        let mut connected = Vec::new();
+        let mut removed = Vec::new();
+        map.retain(|_, v| {
+            let keep = v.expire_time > right_now;
+            if !keep {
+                if v.active_connections > 0 {
+                    connected.push(v.details);
+                }
+                removed.push(*v);
+            }
+            keep
+        });
+        let expiry = &self.expiry;
+        for v in settle_groups(&mut map, removed).iter() {
+            expiry.expired(&self.local_counters, v);
+        }
+        if expiry.sample_due(right_now) {
+            expiry.sample(map.values(), right_now);
+        }
+        let num_sessions_after = map.len();
+        drop(map);
+        self.tear_down(connected);
+        if num_sessions_before != num_sessions_after {
+            self.generation.fetch_add(1, Ordering::Release);
+            debug!("Dark Decoys drops: {} - > {}", num_sessions_before, num_sessions_after);
+        }
+        num_sessions_before - num_sessions_after
+    }
+
+    // Hands sessions dropped with active connections to the teardown thread,
+    // if enabled.
+    fn tear_down(&self, connected: Vec<SessionDetails>) {
+        if let Some(ref t) = self.teardown {
+            t.expired(connected);
+        }
+    }
+
+    /// Used to update (increase) the time that we  consider a session 
+    /// valid for tracking purposes. Called when packets from a session are
+    /// seen so that forwarding continues past the original registration timeout.
+    pub fn update_session(&mut self, flow: &FlowNoSrcPort) {
+        self.config.find_flow_key(flow, |key| {
+            if !self.session_exists(key) {
+                return None
+            }
+            self.try_update_session_timeout(key);
+            Some(())
+        });
+    }
+
+   
+    
+    // Extends the session by the time activity extends sessions of its mode.
+    fn try_update_session_timeout(&self, key: &str) {
+        // Get writable map
+        let mut mmap = self.write_sessions();
+
+        // compare and keep the longer
+        match mmap.get_mut(key){
+            Some(v)=> {
+                // Set timeout
+                let extra_time = self.config.extension_ns(&v.details);
+                let expire_time = self.clock.now().saturating_add(extra_time);
+                if let Some(max) = self.config.max_extensions {
+                    if v.extensions >= max {
+                        return
+                    }
+                }
+                let expire_time = match self.config.max_total_lifetime {
+                    Some(max) => cmp::min(expire_time, v.created_at.saturating_add(duration_ns(max))),
+                    None => expire_time,
+                };
+                // compare and keep the longer
+                if v.expire_time < expire_time {
+                    v.expire_time = expire_time;
+                    v.extensions = v.extensions.saturating_add(1);
+                    self.count_extension();
+                }
+            },
+            None => {},
+        };
+    }
+
+    /// Resets the expiry of the tracked sessions among `sessions` (for each
+    /// default port if they have none) to their timeout from now, for the
+    /// station's updates. Unlike activity the new expiry may be earlier, and
+    /// doesn't count towards max_extensions; the maximum lifetime and total
+    /// lifetime caps still apply. Sessions not tracked, or expired already,
+    /// are left alone.
+    /// Returns how many were refreshed.
+    pub fn refresh_sessions(&mut self, sessions: &[SessionDetails]) -> usize {
+        let sessions = self.config.expand_ports(sessions);
+        let right_now = self.clock.now();
+        let mut mmap = self.write_sessions();
+        let mut refreshed = 0;
+        for session in sessions.iter() {
+            let v = match self.session_key(session).and_then(|key| mmap.get_mut(&key)) {
+                Some(v) if v.expire_time > right_now => v,
+                _ => {
+                    self.expiry_changes.refresh_untracked.fetch_add(1, Ordering::Relaxed);
+                    continue
+                },
+            };
+            let mut expire_time = right_now.saturating_add(session.timeout);
+            if let Some(max) = self.config.max_lifetime_ns(v.details.mode) {
+                expire_time = cmp::min(expire_time, right_now.saturating_add(max));
+            }
+            if let Some(max) = self.config.max_total_lifetime {
+                expire_time = cmp::min(expire_time, v.created_at.saturating_add(duration_ns(max)));
+            }
+            if expire_time < right_now.saturating_add(session.timeout) {
+                self.expiry_changes.refresh_capped.fetch_add(1, Ordering::Relaxed);
+            }
+            debug!("Station update refreshes session {} [{:016x}], expiring in {}ms instead of {}ms",
+                v.details, v.session_id, expire_time.saturating_since(right_now) / (1000*1000),
+                v.expire_time.saturating_since(right_now) / (1000*1000));
+            v.expire_time = expire_time;
+            self.expiry_changes.refreshed.fetch_add(1, Ordering::Relaxed);
+            refreshed += 1;
+        }
+        refreshed
+    }
+
+    // Counts an activity extension, adding this thread's to ExpiryChanges
+    // once it has EXTENSION_BATCH of them.
+    fn count_extension(&self) {
+        let pending = self.extensions_pending.get() + 1;
+        if pending < EXTENSION_BATCH {
+            self.extensions_pending.set(pending);
+            return
+        }
+        self.expiry_changes.extended.fetch_add(pending, Ordering::Relaxed);
+        self.extensions_pending.set(0);
+    }
+
+    /// Sessions extended by activity and refreshed by the station's updates
+    /// since the last call, formatted for the periodic report. Extensions
+    /// by other threads are counted in batches, so some may only show in a
+    /// later report.
+    pub fn take_expiry_change_report(&self) -> String {
+        let changes = &self.expiry_changes;
+        changes.extended.fetch_add(self.extensions_pending.replace(0), Ordering::Relaxed);
+        format!("activity extensions {} station updates {} (capped {} untracked {})",
+            changes.extended.swap(0, Ordering::Relaxed),
+            changes.refreshed.swap(0, Ordering::Relaxed),
+            changes.refresh_capped.swap(0, Ordering::Relaxed),
+            changes.refresh_untracked.swap(0, Ordering::Relaxed))
+    }
+
+    fn insert_session(&mut self, session: SessionDetails) {
+        self.insert_sessions(&[session])
+    }
+
+    // Inserts the sessions of a registration received by an ingest thread,
+    // through the insertion queue if there is one (which logs refusals
+    // itself), see insert_registration.
+    fn apply_registration(&mut self, sessions: &[SessionDetails]) -> Result<(), Refusal> {
+        match self.insert_queue {
+            Some(ref q) => {
+                q.push(sessions.to_vec());
+                Ok(())
+            },
+            None => self.insert_registration(sessions),
+        }
+    }
+
+    // Inserts the sessions of a registration as insert_sessions does, unless
+    // its tenant is at its session limit. The limit is checked under the same
+    // write lock, so registrations inserted concurrently (by ingest workers)
+    // can't take a tenant past it.
+    fn insert_registration(&mut self, sessions: &[SessionDetails]) -> Result<(), Refusal> {
+        let mut result = Ok(());
+        self.insert_admitted(&[sessions], true, |_, refusal| result = Err(refusal));
+        result
+    }
+
+    // Inserts a batch of registrations from the insertion queue under one
+    // write lock, admitting each on its own as insert_registration does.
+    fn insert_registrations(&mut self, registrations: &[Vec<SessionDetails>]) {
+        self.insert_admitted(registrations, true, |sessions, refusal| {
+            debug!("refused queued registration: {}", refusal.reason(sessions[0].tenant));
+        });
+    }
+
+    // Inserts all sessions from a single registration while holding the write
+    // lock so they become visible together. Every session gets the same expire
+    // time; keys that are already tracked only have their timeout extended.
+    fn insert_sessions(&mut self, sessions: &[SessionDetails]) {
+        self.insert_admitted(&[sessions], false, |_, _| {});
+    }
+
+    // As insert_sessions for each of `registrations`, under a single write
+    // lock. If `limited` and tenants are configured each is first admitted to
+    // its tenant's session limit, and passed to `refused` instead if it isn't.
+    fn insert_admitted<S, F>(&mut self, registrations: &[S], limited: bool, mut refused: F)
+        where S: AsRef<[SessionDetails]>, F: FnMut(&[SessionDetails], Refusal)
+    {
+        // Get writable map
+        let mut mmap = self.write_sessions();
+
+        let right_now = self.clock.now();
+        let mut added = Vec::new();
+        let mut widened = false;
+        for registration in registrations.iter() {
+            let sessions = self.config.expand_ports(registration.as_ref());
+            if let (true, Some(tenants), Some(first)) = (limited, self.tenants.as_ref(), sessions.first()) {
+                // only keys not tracked yet count against the limit
+                let new_sessions = sessions.iter()
+                    .filter_map(|sd| self.session_key(sd))
+                    .filter(|key| !mmap.contains_key(key))
+                    .count();
+                if let Err(refusal) = tenants.admit_sessions(first.tenant, mmap.tenant_len(first.tenant), new_sessions) {
+                    refused(registration.as_ref(), refusal);
+                    continue
+                }
+            }
+            widened |= self.insert_locked(&mut mmap, &sessions, right_now, &mut added);
+        }
+
+        // Get rid of writable reference to map.
+        drop(mmap);
+
+        // (widened client ports may match flows that didn't before)
+        if !added.is_empty() || widened {
+            self.generation.fetch_add(1, Ordering::Release);
+            self.insert_generation.fetch_add(1, Ordering::Release);
+        }
+        for entry in added.iter() {
+            debug!("Added registered ip {} from redis [{:016x}]", entry.details, entry.session_id);
+        }
+    }
+
+    // Inserts the (expanded) sessions of one registration into the locked
+    // map, adding the new entries to `added`. Returns whether the client
+    // ports of a session already tracked were widened.
+    fn insert_locked(&self, mmap: &mut SessionMap, sessions: &[SessionDetails], right_now: MonotonicNs,
+        added: &mut Vec<SessionEntry>) -> bool
+    {
+        let mut widened = false;
+        for session in sessions.iter() {
+            // Set timeout
+            let timeout = match self.config.max_lifetime_ns(session.mode) {
+                Some(max) => cmp::min(session.timeout, max),
+                None => session.timeout,
+            };
+            let expire_time = right_now.saturating_add(timeout);
+
+            let key = match self.session_key(session) {
+                Some(k) => k,
+                None => {
+                    match session.mode {
+                        SessionMode::Decoy => debug!("Not tracking decoy session {}, decoy sessions disabled or no client address", session),
+                        _ => debug!("Not tracking {}, strict v6 mode needs a v6 client address", session),
+                    }
+                    continue
+                },
+            };
+
+            // is this already in the map? compare and keep the longer
+            match mmap.get_mut(&key) {
+                Some(v) => {
+                    if v.expire_time < expire_time {
+                        v.expire_time = expire_time;
+                    }
+                    // a redelivery of the message last counted isn't another
+                    // registration
+                    if session.message.is_none() || session.message != v.details.message {
+                        v.registrations = v.registrations.saturating_add(1);
+                        v.details.message = session.message;
+                        debug!("Registration {} shares session [{:016x}] with {} others",
+                            session, v.session_id, v.registrations - 1);
+                    }
+                    // the clients of both registrations can connect
+                    let ports = match (v.details.client_ports, session.client_ports) {
+                        (Some(a), Some(b)) => Some(a.widen(b)),
+                        _ => None,
+                    };
+                    if ports != v.details.client_ports {
+                        debug!("Widened client ports of session [{:016x}] to {}", v.session_id,
+                            ports.map_or("any".to_string(), |p| p.to_string()));
+                        v.details.client_ports = ports;
+                        widened = true;
+                    }
+                    continue
+                },
+                None => {},
+            };
+
+            // Insert
+            let entry = SessionEntry {
+                session_id: session_id(&key, right_now),
+                created_at: right_now,
+                affinity: affinity_hash(&key),
+                ..SessionEntry::new(*session, expire_time)
+            };
+            mmap.insert(key, entry);
+            added.push(entry);
+        }
+        widened
+    }
+
+    /// Removes one registration for the session `session` maps to (for each
+    /// default port if it has no port), and for the rest of its group. A
+    /// session is only dropped once all its registrations are removed.
+    /// Returns the most registrations left on any of the sessions, or None if
+    /// none of them is tracked.
+    pub fn remove_registration(&mut self, session: &SessionDetails) -> Option<u32> {
+        let mut mmap = self.write_sessions();
+        let mut keys = Vec::new();
+        for sd in self.config.expand_ports(&[*session]).iter() {
+            if let Some(key) = self.session_key(sd) {
+                keys.push(key);
+            }
+        }
+        let groups: HashSet<u64> = keys.iter()
+            .filter_map(|k| mmap.get(k).and_then(|e| e.details.group))
+            .collect();
+        for group in groups.iter() {
+            for key in mmap.group_keys(*group) {
+                if !keys.contains(key) {
+                    keys.push(key.clone());
+                }
+            }
+        }
+
+        let mut most_left = None;
+        let mut removed = false;
+        for key in keys.iter() {
+            let left = match mmap.get_mut(key) {
+                Some(entry) => {
+                    entry.registrations = entry.registrations.saturating_sub(1);
+                    entry.registrations
+                },
+                None => continue,
+            };
+            if left == 0 {
+                mmap.remove(key);
+                removed = true;
+            }
+            most_left = cmp::max(most_left, Some(left));
+        }
+        drop(mmap);
+        if removed {
+            self.generation.fetch_add(1, Ordering::Release);
+        }
+        most_left
+    }
+
+    // explicitly used for testing. Deletes the session(s) `session` maps to
+    // (one per default port if it has no port) whatever their registrations.
+    fn _delete_session(&mut self, session: SessionDetails) {
+        for sd in self.config.expand_ports(&[session]).iter() {
+            let key = &match self.session_key(sd) {
+                Some(k) => k,
+                None => continue,
+            };
+            if ! self.session_exists(key) {
+                continue
+            }
+            let mut mmap = self.write_sessions();
+            mmap.remove(key);
+            drop(mmap);
+            self.generation.fetch_add(1, Ordering::Release);
+        }
+    }
+
+    // lookup session by identifier
+    fn session_exists(&self, id: &str) -> bool
+    { 
+        let rmap = self.read_sessions();
+        let res = rmap.contains_key(id);
+        drop(rmap);
+        return res
+     }
+
+    /// Counts a new connection for the session `flow` belongs to. Returns
+    /// false, without counting it, if the session is already at its
+    /// connection limit. Connections to untracked sessions are not limited.
+    pub fn open_connection(&mut self, flow: &FlowNoSrcPort) -> bool {
+        let max_connections = self.config.max_connections;
+        self.config.find_flow_key(flow, |key| {
+            let mut mmap = self.write_sessions();
+            let entry = match mmap.get_mut(key) {
+                Some(e) => e,
+                None => return None,
+            };
+
+            let limit = match entry.details.max_connections {
+                0 => max_connections,
+                n => Some(n),
+            };
+            if let Some(limit) = limit {
+                if entry.active_connections >= limit {
+                    return Some(false)
+                }
+            }
+            entry.active_connections += 1;
+            entry.connections = entry.connections.saturating_add(1);
+            Some(true)
+        }).unwrap_or(true)
+    }
+
+    /// Marks the session `flow` belongs to, and the rest of its group, as
+    /// matched, returning it if this is the group's first match. None if it
+    /// matched before or isn't tracked.
+    pub fn latch_match(&self, flow: &FlowNoSrcPort) -> Option<SessionEntry> {
+        let now = self.clock.now();
+        self.config.find_flow_key(flow, |key| {
+            // Most connections are to sessions that matched already, which
+            // the read lock is enough to tell.
+            if self.read_sessions().get(key)?.matched_at.is_some() {
+                return Some(None)
+            }
+            let mut mmap = self.write_sessions();
+            // the session may have expired or matched since
+            let entry = mmap.get_mut(key)?;
+            if entry.matched_at.is_some() {
+                return Some(None)
+            }
+            entry.matched_at = Some(now);
+            let entry = *entry;
+            if let Some(group) = entry.details.group {
+                let keys: Vec<String> = mmap.group_keys(group).into_iter().cloned().collect();
+                for k in keys.iter() {
+                    if let Some(e) = mmap.get_mut(k) {
+                        e.matched_at = e.matched_at.or(Some(now));
+                    }
+                }
+            }
+            Some(Some(entry))
+        }).and_then(|e| e)
+    }
+
+    /// Releases a connection counted by open_connection.
+    pub fn close_connection(&mut self, flow: &FlowNoSrcPort) {
+        self.close_connection_with(flow, 0, 0)
+    }
+
+    /// Releases a connection counted by open_connection, adding the packets
+    /// and bytes forwarded for it to the session's totals.
+    pub fn close_connection_with(&mut self, flow: &FlowNoSrcPort, packets: u64, bytes: u64) {
+        self.config.find_flow_key(flow, |key| {
+            let mut mmap = self.write_sessions();
+            let entry = mmap.get_mut(key)?;
+            entry.active_connections = entry.active_connections.saturating_sub(1);
+            entry.packets = entry.packets.saturating_add(packets);
+            entry.bytes = entry.bytes.saturating_add(bytes);
+            Some(())
+        });
+    }
+
+    /// Records a transport detector's verdict on a connection of the session
+    /// `flow` belongs to: Confirm marks it confirmed, Annotate sets its
+    /// annotation.
+    pub fn apply_verdict(&mut self, flow: &FlowNoSrcPort, verdict: Verdict) {
+        self.config.find_flow_key(flow, |key| {
+            let mut mmap = self.write_sessions();
+            let entry = mmap.get_mut(key)?;
+            match verdict {
+                Verdict::Confirm => entry.confirmed = true,
+                Verdict::Annotate(tag) => entry.annotation = Some(tag),
+                Verdict::Pass | Verdict::Reject | Verdict::NeedMore => {},
+            }
+            Some(())
+        });
+    }
+
+    /// Records the fingerprint of a connection's ClientHello in the session
+    /// `flow` belongs to.
+    pub fn set_tls_fingerprint(&mut self, flow: &FlowNoSrcPort, fingerprint: TlsFingerprint) {
+        self.config.find_flow_key(flow, |key| {
+            let mut mmap = self.write_sessions();
+            mmap.get_mut(key)?.tls_fingerprint = Some(fingerprint);
+            Some(())
+        });
+    }
+
+    /// Records the destination connection ID hash of a connection's QUIC
+    /// Initial in the session `flow` belongs to, listed with it (see dump())
+    /// for matching with the station's proxy logs.
+    pub fn set_quic_dcid_hash(&mut self, flow: &FlowNoSrcPort, hash: DcidHash) {
+        self.config.find_flow_key(flow, |key| {
+            let mut mmap = self.write_sessions();
+            mmap.get_mut(key)?.quic_dcid_hash = Some(hash);
+            Some(())
+        });
+    }
+
+    // Key for `session` under this tracker's configuration.
+    fn session_key(&self, session: &SessionDetails) -> Option<String> {
+        session.key_in(&self.config)
+    }
+
+    // Copy of the currently tracked keys, with their client ports.
+    fn snapshot_keys(&self) -> SnapshotKeys {
+        let rmap = self.read_sessions();
+        let mut keys = HashMap::with_capacity_and_hasher(rmap.len(), MapHasher::new(self.config.hash));
+        keys.extend(rmap.iter().map(|(k, e)| (k.clone(), e.details.client_ports)));
+        keys
+    }
+
+}
+
+// Session dump line for `e` at clock time `right_now`.
+fn describe_entry(e: &SessionEntry, right_now: MonotonicNs) -> String {
+    let mut line = format!("[{:016x}] {} expires in {}ms", e.session_id, e.details, e.expire_time.saturating_since(right_now) / (1000*1000));
+    if let Some(ports) = e.details.client_ports {
+        line.push_str(&format!(" client ports {}", ports));
+    }
+    if let Some(group) = e.details.group {
+        line.push_str(&format!(" group {:016x}", group));
+    }
+    if let Some(dcid) = e.quic_dcid_hash {
+        line.push_str(&format!(" quic dcid {}", dcid));
+    }
+    match e.registrations {
+        0 | 1 => line,
+        n => format!("{} ({} registrations)", line, n),
+    }
+}
+
+// Folds the sessions of each group among `removed` (just dropped from `map`)
+// into one entry, and that into a session of the group still in the map if
+// there is one, so a group is counted and reported once, when its last
+// session expires, with the totals of all of them. Returns the entries left
+// to count.
+fn settle_groups(map: &mut SessionMap, removed: Vec<SessionEntry>) -> Vec<SessionEntry> {
+    let mut settled = Vec::with_capacity(removed.len());
+    let mut groups: HashMap<u64, SessionEntry> = HashMap::new();
+    for e in removed.into_iter() {
+        match e.details.group {
+            Some(group) => {
+                let folded = groups.entry(group).or_insert(SessionEntry { connections: 0, packets: 0, bytes: 0, ..e });
+                fold_entry(folded, &e);
+            },
+            None => settled.push(e),
+        }
+    }
+    for (group, folded) in groups.into_iter() {
+        let survivor = map.group_keys(group).first().map(|k| (*k).clone());
+        match survivor.and_then(|k| map.get_mut(&k)) {
+            Some(e) => fold_entry(e, &folded),
+            None => settled.push(folded),
+        }
+    }
+    settled
+}
+
+// Adds the traffic of `from`, a session of the same group, to `into`.
+fn fold_entry(into: &mut SessionEntry, from: &SessionEntry) {
+    into.connections = into.connections.saturating_add(from.connections);
+    into.packets = into.packets.saturating_add(from.packets);
+    into.bytes = into.bytes.saturating_add(from.bytes);
+    into.matched_at = match (into.matched_at, from.matched_at) {
+        (Some(a), Some(b)) => Some(cmp::min(a, b)),
+        (a, b) => a.or(b),
+    };
+}
+
+// Logs why a feed stopped, unless it was closed normally.
+fn log_feed_exit(what: &str, result: DetectorResult<()>) {
+    if let Err(e) = result {
+        error!("{} stopped: {}", what, error_chain(&e));
+    }
+}
+
+// Runs until the feed closes, which for redis is the lifetime of the process.
+fn ingest_from_feed(tracker: SessionTracker, feed: &mut dyn RegistrationFeed) {
+    log_feed_exit("registration ingest", run_ingest(tracker, feed));
+}
+
+// As ingest_from_feed, for a supervisor to log the feed's failure. With an
+// ingest pool the registrations are applied by its workers, see
+// ingest_pool.rs.
+fn run_ingest(mut tracker: SessionTracker, feed: &mut dyn RegistrationFeed) -> DetectorResult<()> {
+    let format = feed.format();
+    // (the workers share the feed's breaker)
+    tracker.errors = Arc::new(tracker.errors.for_feed());
+    let (config, stats) = match (tracker.config.ingest_pool.as_ref(), tracker.ingest_pool.as_ref()) {
+        (Some(c), Some(s)) => (c.clone(), Arc::clone(s)),
+        _ => return feed.for_each_payload(&mut |payload| {
+            ingest_registration(&mut tracker, payload, format, false);
+        }),
+    };
+    let pool = IngestPool::spawn(&config, stats, |_| {
+        let mut worker = tracker.shared();
+        move |reg: &Registration| { apply_registration_message(&mut worker, reg, false); }
+    });
+    feed.for_each_payload(&mut |payload| {
+        let reg = match parse_registration(&tracker, payload, format, false) {
+            Some(reg) => reg,
+            None => return,
+        };
+        if is_station_wide(&reg) {
+            pool.barrier();
+            apply_registration_message(&mut tracker, &reg, false);
+        } else {
+            let shard = ingest_shard(&tracker, &reg);
+            pool.dispatch(reg, shard);
+        }
+    })
+}
+
+// Hash of the session key of the first session `reg` registers, picking the
+// ingest worker that applies it. Registrations whose sessions have no key
+// (they aren't tracked) go by their addresses.
+fn ingest_shard(tracker: &SessionTracker, reg: &Registration) -> u64 {
+    let key = reg.sessions(WallClockNs::now()).ok()
+        .and_then(|sds| tracker.config.expand_ports(&sds).first().and_then(|sd| tracker.session_key(sd)));
+    let mut hasher = DefaultHasher::new();
+    match key {
+        Some(key) => key.hash(&mut hasher),
+        None => (reg.tenant, &reg.client_ip, &reg.phantom_ip, &reg.phantom_ip_v6).hash(&mut hasher),
+    }
+    hasher.finish()
+}
+
+// Adds the registrations the station has stored, then marks the tracker
+// primed. Keys already tracked, e.g. from registrations the ingest thread
+// received meanwhile, are left alone.
+fn prime_from_feed(mut tracker: SessionTracker, feed: &mut dyn RegistrationFeed) {
+    let (mut primed, mut skipped) = (0, 0);
+    let format = feed.format();
+    tracker.errors = Arc::new(tracker.errors.for_feed());
+    let result = feed.for_each_payload(&mut |payload| {
+        match ingest_registration(&mut tracker, payload, format, true) {
+            true => primed += 1,
+            false => skipped += 1,
+        }
+    });
+    match result {
+        Ok(()) => info!("primed sessions from {} registrations, {} skipped", primed, skipped),
+        Err(e) => error!("priming stopped after {} registrations: {}", primed, error_chain(&e)),
+    }
+    tracker.prime.store(true, Ordering::Release);
+}
+
+// Checks and applies one serialized registration from our station. Returns
+// whether the registration was applied.
+fn ingest_registration(tracker: &mut SessionTracker, payload: &[u8], format: PayloadFormat, priming: bool) -> bool {
+    match parse_registration(tracker, payload, format, priming) {
+        Some(reg) => apply_registration_message(tracker, &reg, priming),
+        None => false,
+    }
+}
+
+// Parses one serialized message from our station, serialized as `format`,
+// and follows its sequence number, in the order the messages are received.
+// Stored registrations read while priming carry old numbers and are skipped.
+fn parse_registration(tracker: &SessionTracker, payload: &[u8], format: PayloadFormat, priming: bool) -> Option<Registration> {
+    // slow the feed down, rather than drop what it sent
+    if tracker.errors.slowing(tracker.clock.now()) {
+        thread::sleep(tracker.errors.config().slow_poll);
+    }
+    let parsed = match format {
+        PayloadFormat::Protobuf => parse_message::<StationToDetector>(payload, "StationToDetector")
+            .map(|s2d| Registration::from(&s2d)),
+        #[cfg(feature = "json-registrations")]
+        PayloadFormat::Json => parse_json_registration(payload),
+    };
+    let reg = match parsed {
+        Ok(reg) => reg,
+        Err(e) => {
+            let reason = error_chain(&e);
+            tracker.errors.failed(IngestError::Parse, tracker.clock.now(), &reason);
+            // nothing of it can be read, record an empty registration
+            tracker.journal_record(&Registration::default(), Some(reason));
+            return None
+        },
+    };
+    match reg.sequence {
+        Some((publisher, sequence)) if !priming => tracker.sequences.observe(publisher, sequence),
+        _ => {},
+    }
+    Some(reg)
+}
+
+// Checks and applies one parsed message from our station. Stored
+// registrations read while priming may be older than the replay window and
+// say nothing about the ingest latency, so they skip both. Returns whether
+// the registration was applied.
+fn apply_registration_message(tracker: &mut SessionTracker, reg: &Registration, priming: bool) -> bool {
+    match tracker.replay {
+        Some(ref guard) if !priming => {
+            if let Err(e) = guard.check(&reg.message_id, reg.station_time, WallClockNs::now()) {
+                debug!("rejected registration: {} ({:?})", e, reg.source);
+                tracker.journal_record(reg, Some(format!("replay guard: {}", e)));
+                return false
+            }
+        },
+        _ => {},
+    }
+    match reg.command {
+        Command::Register => {},
+        Command::Update => return apply_update(tracker, reg),
+        Command::ConfigPush(ref push) => {
+            match tracker.station_config {
+                Some(ref sc) => match sc.apply(push) {
+                    Ok(_) => info!("applied station config epoch {}", sc.epoch()),
+                    Err(e) => warn!("rejected station config: {}", e),
+                },
+                None => debug!("ignoring station config push, not enabled"),
+            }
+            return false
+        },
+        Command::DeleteTenantSessions => {
+            let tenant = reg.tenant;
+            match tracker.tenants {
+                Some(_) if tenant == DEFAULT_TENANT => {
+                    warn!("refusing to delete the default station's sessions ({:?})", reg.source);
+                },
+                Some(_) => {
+                    let deleted = tracker.delete_tenant_sessions(tenant);
+                    info!("deleted {} sessions of tenant {}", deleted, tenant);
+                },
+                None => debug!("ignoring tenant {} session delete, tenants not enabled", tenant),
+            }
+            return false
+        },
+        Command::PhantomSubnets(ref subnets) => {
+            match tracker.station_config {
+                Some(ref sc) => match sc.apply_subnets(subnets) {
+                    Ok(_) => info!("applied phantom subnets generation {}", sc.generation()),
+                    Err(e) => warn!("rejected phantom subnets: {}", e),
+                },
+                None => debug!("ignoring phantom subnets push, not enabled"),
+            }
+            return false
+        },
+    }
+    if let (Some(sent), false) = (reg.station_time, priming) {
+        let delay = tracker.latency.record(sent, WallClockNs::now());
+        match (delay, tracker.config.latency_warn) {
+            (Some(d), Some(limit)) if d > duration_ns(limit) => {
+                warn!("registration took {}ms to arrive from the station ({:?})",
+                    d / (1000*1000), reg.source);
+            },
+            _ => {},
+        }
+    }
+    // Stored registrations get the lifetime they had when the station sent
+    // them, aged below by the time since
+    let now = WallClockNs::now();
+    let sent = match (priming, reg.station_time) {
+        (true, Some(sent)) => cmp::min(sent, now),
+        _ => now,
+    };
+    let mut sds = match reg.sessions(sent) {
+        Ok(m) => m,
+        Err(e) => {
+            let reason = error_chain(&DetectorError::from(e));
+            tracker.errors.failed(IngestError::Invalid, tracker.clock.now(), &format!("{} ({:?})", reason, reg.source));
+            tracker.journal_record(reg, Some(reason));
+            return false
+        }
+    };
+    if tracker.tenants.is_none() {
+        for sd in sds.iter_mut() {
+            sd.tenant = DEFAULT_TENANT;
+        }
+    }
+
+    if let Some(ref sc) = tracker.station_config {
+        let pushed = sc.current();
+        if let Some(reason) = sds.iter().filter_map(|sd| pushed.refuses(sd)).next() {
+            debug!("refused registration: {} ({:?})", reason, reg.source);
+            sc.count_refused();
+            tracker.journal_record(reg, Some(format!("refused by station config: {}", reason)));
+            return false
+        }
+        let subnets = sc.phantom_subnets();
+        // decoys are real hosts, outside the phantom subnets
+        let phantom = |sd: &&SessionDetails| sd.mode == SessionMode::Phantom;
+        if let Some(sd) = sds.iter().filter(phantom).find(|sd| !subnets.allows(&sd.phantom_ip)) {
+            debug!("refused registration: phantom {} outside phantom subnets generation {} ({:?})",
+                sd.phantom_ip, subnets.generation, reg.source);
+            sc.count_refused_phantom();
+            tracker.journal_record(reg, Some("refused by station config: phantom outside phantom subnets".to_string()));
+            return false
+        }
+        if reg.timeout.is_none() && reg.expires_at.is_none() {
+            for sd in sds.iter_mut() {
+                sd.timeout = pushed.default_timeout_ns;
+            }
+        }
+    }
+
+    if let Some(subnet) = tracker.blocked_phantom(&sds) {
+        debug!("refused registration: phantom in blocked subnet {} ({:?})", subnet, reg.source);
+        tracker.journal_record(reg, Some(format!("phantom subnet {} blocked", subnet)));
+        return false
+    }
+
+    if let Err(e) = tracker.bound_timeouts(&mut sds) {
+        let reason = error_chain(&DetectorError::from(e));
+        tracker.errors.failed(IngestError::Invalid, tracker.clock.now(), &format!("{} ({:?})", reason, reg.source));
+        tracker.journal_record(reg, Some(reason));
+        return false
+    }
+
+    if tracker.is_draining() {
+        debug!("draining, ignoring registration ({:?})", reg.source);
+        tracker.count_drained();
+        tracker.journal_record(reg, Some("draining".to_string()));
+        return false
+    }
+
+    if priming {
+        let age = now.saturating_since(sent);
+        if sds.iter().any(|sd| sd.timeout <= age) {
+            debug!("skipping stored registration, expired {}ms ago ({:?})",
+                (age - sds[0].timeout) / (1000*1000), reg.source);
+            tracker.journal_record(reg, Some("expired while stored".to_string()));
+            return false
+        }
+        for sd in sds.iter_mut() {
+            sd.timeout -= age;
+        }
+        let expanded = tracker.config.expand_ports(&sds);
+        sds = expanded.into_iter()
+            .filter(|sd| !tracker.session_key(sd).map_or(false, |k| tracker.session_exists(&k)))
+            .collect();
+        if sds.is_empty() {
+            return false
+        }
+    }
+
+    if let Some(ref tenants) = tracker.tenants {
+        let tenant = sds[0].tenant;
+        if let Err(refusal) = tenants.admit(tenant, tracker.clock.now(), priming) {
+            let reason = refusal.reason(tenant);
+            debug!("refused registration: {} ({:?})", reason, reg.source);
+            tracker.journal_record(reg, Some(reason));
+            return false
+        }
+    }
+
+    // Adds the session(s), or extends the timeout if the key is already
+    // tracked.
+    if let Err(refusal) = tracker.apply_registration(&sds) {
+        let reason = refusal.reason(sds[0].tenant);
+        debug!("refused registration: {} ({:?})", reason, reg.source);
+        tracker.journal_record(reg, Some(reason));
+        return false
+    }
+    tracker.journal_record(reg, None);
+    if let Some(ref r) = tracker.replication {
+        r.publish(&sds);
+    }
+    true
+}
+
+// Refreshes the tracked sessions of the station's update `reg`. Its lifetime
+// is checked like a registration's, and one without a lifetime is invalid.
+// Returns whether any session was refreshed, and if so publishes the update to
+// the replication peers.
+fn apply_update(tracker: &mut SessionTracker, reg: &Registration) -> bool {
+    let checked = match (reg.timeout, reg.expires_at) {
+        (None, None) => Err(SessionError::InvalidTimeout(Duration::from_secs(0))),
+        _ => reg.sessions(WallClockNs::now()),
+    };
+    let mut sds = match checked.and_then(|mut sds| tracker.bound_timeouts(&mut sds).map(|_| sds)) {
+        Ok(sds) => sds,
+        Err(e) => {
+            let reason = error_chain(&DetectorError::from(e));
+            tracker.errors.failed(IngestError::Invalid, tracker.clock.now(), &format!("update: {} ({:?})", reason, reg.source));
+            tracker.journal_record(reg, Some(reason));
+            return false
+        },
+    };
+    if tracker.tenants.is_none() {
+        for sd in sds.iter_mut() {
+            sd.tenant = DEFAULT_TENANT;
+        }
+    }
+    if tracker.is_draining() {
+        debug!("draining, ignoring update ({:?})", reg.source);
+        tracker.count_drained();
+        tracker.journal_record(reg, Some("draining".to_string()));
+        return false
+    }
+    if tracker.refresh_sessions(&sds) == 0 {
+        debug!("ignoring update, no session of {} tracked ({:?})", sds[0], reg.source);
+        tracker.journal_record(reg, Some("update for untracked sessions".to_string()));
+        return false
+    }
+    tracker.journal_record(reg, None);
+    if let Some(ref r) = tracker.replication {
+        r.publish_update(&sds);
+    }
+    true
+}
+
+// Adds the sessions replicated by a peer, or refreshes those of its station's
+// updates that are tracked. They are never published again.
+fn ingest_from_peer(mut tracker: SessionTracker, peer: usize, feed: &mut dyn RegistrationFeed) {
+    let replication = match tracker.replication {
+        Some(ref r) => Arc::clone(r),
+        None => return,
+    };
+    let result = feed.for_each_payload(&mut |payload| {
+        if let Some((sds, update)) = replication.receive(peer, payload) {
+            if tracker.is_draining() {
+                tracker.count_drained();
+                return
+            }
+            if update {
+                tracker.refresh_sessions(&sds);
+                return
+            }
+            if tracker.blocked_phantom(&sds).is_some() {
+                return
+            }
+            tracker.apply_registration(&sds);
+        }
+    });
+    log_feed_exit(&format!("replication from {}", replication.peers()[peer].url), result);
+}
+
+// Adds the sessions handed off by another detector, even while draining.
+fn import_handoff(mut tracker: SessionTracker, feed: &mut dyn RegistrationFeed) {
+    let result = feed.for_each_payload(&mut |payload| {
+        let msg: SessionReplication = match parse_message(payload, "handed off sessions") {
+            Ok(m) => m,
+            Err(e) => {
+                warn!("{}", error_chain(&e));
+                return
+            },
+        };
+        let mut sds = Vec::with_capacity(msg.get_sessions().len());
+        for rs in msg.get_sessions() {
+            match SessionResult::from(rs) {
+                Ok(sd) => sds.push(sd),
+                Err(e) => debug!("invalid handed off session: {}", e),
+            }
+        }
+        info!("importing {} of {} sessions handed off by detector {}",
+            sds.len(), msg.get_sessions().len(), msg.get_origin());
+        tracker.insert_sessions(&sds);
+    });
+    log_feed_exit("handoff import", result);
+}
+
+// Rebuilds the lookup snapshot whenever the set of tracked keys has changed.
+// Runs for the lifetime of the process.
+fn refresh_snapshot(tracker: SessionTracker, mut built_generation: usize, interval: Duration) {
+    let snapshot = match tracker.snapshot {
+        Some(ref s) => Arc::clone(s),
+        None => return,
+    };
+
+    loop {
+        thread::sleep(interval);
+
+        // Read the generation before the map so a change that lands while we
+        // copy the keys triggers another rebuild on the next pass.
+        let generation = tracker.generation.load(Ordering::Acquire);
+        if generation == built_generation {
+            continue
+        }
+        snapshot.store(Arc::new(tracker.snapshot_keys()));
+        tracker.insert_generation.fetch_add(1, Ordering::Release);
+        built_generation = generation;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    // use std::fmt::Write;
+    use sessions::*;
+    use signalling::{StationToDetector, RegistrationSource, SessionMode, TransportType};
+    use flow_tracker::{Flow, FlowNoSrcPort};
+    use ingest::ChannelFeed;
+    use feedback::EventPublisher;
+    use replication::handoff_messages;
+    use conntrack::Teardown;
+    use signalling::{ReplicatedSession, SessionReplication};
+    use timekeeping::{Clock, MonotonicNs, WallClockNs};
+    use protobuf::Message;
+    use std::{thread, time};
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_session_tracker_pubsub(){
+        let st = SessionTracker::new();
+
+        let test_tuples = [
+            // (client_ip, phantom_ip, timeout)
+            ("172.128.0.2", "8.0.0.1", 1),            // timeout immediately
+            ("192.168.0.1", "10.10.0.1", 5*S2NS),
+            ("192.168.0.1", "192.0.0.127", 5*S2NS),   
+            ("", "2345::6789", 5*S2NS),
+            
+            // duplicate with shorter timeout should not drop
+            ("2601::123:abcd", "2001::1234", 5*S2NS),
+            ("::1", "2001::1234", 1*S2NS),
+            
+            // duplicate with long timeout should prevent drop
+            ("7.0.0.2", "8.8.8.8", 1),
+            ("7.0.0.2", "8.8.8.8", 5*S2NS),
+        ];
+    
+        let (tx, rx) = mpsc::channel();
+        let ingest = st.spawn_ingest_thread(ChannelFeed::new(rx));
+
+        for entry in &test_tuples {
+            let mut s2d = StationToDetector::new();
+            s2d.set_client_ip(entry.0.to_string());
+            s2d.set_phantom_ip(entry.1.to_string());
+            s2d.set_timeout_ns(entry.2);
+
+            let msg:Vec<u8> = s2d.write_to_bytes().unwrap();
+            tx.send(msg).unwrap();
+        }
+
+        // Messages that fail to parse or convert are skipped.
+        tx.send(vec![0xff, 0xff, 0xff]).unwrap();
+        let mut bad = StationToDetector::new();
+        bad.set_client_ip("2001::1234".to_string());
+        bad.set_phantom_ip("10.10.0.1".to_string());
+        tx.send(bad.write_to_bytes().unwrap()).unwrap();
+
+        // Closing the channel ends the ingest thread once everything sent has
+        // been processed.
+        drop(tx);
+        ingest.join().unwrap();
+
+        if st.len() != 6 {
+            panic!("Failed to ingest from pubsub: {}", st.len());
+        } 
+    }
+
+
+    #[test]
+    fn test_session_details_from() {
+        let test_tuples = [
+            // (client_ip, phantom_ip, timeout)
+            ("192.168.0.1", "10.10.0.1", 100000),
+            ("2601::123:abcd", "2001::1234", 100000),
+            ("", "2001::1234", 100000),
+ 
+            // client registering with v4 will also create registrations for v6 just in-case
+             ("192.168.0.1", "2801::1234", 100000),
+        ];
+        let test_tuples_bad = [
+            // Mixed ipv4/ipv6 phantom/client
+            ("2001::1234", "10.10.0.1", 100000, SessionError::MixedV4V6Error),
+
+            // no phantom provided
             ("192.168.0.1", "", 100000, SessionError::InvalidPhantom),
             ("2601::123:abcd", "", 100000, SessionError::InvalidPhantom),
 
-            // malformed addresses
-            ("192.1", "10.0.0.1", 100000, SessionError::InvalidClient),
-            ("2001::1234", "abcd::123::wrong", 100000, SessionError::InvalidPhantom),
+            // malformed addresses
+            ("192.1", "10.0.0.1", 100000, SessionError::InvalidClient),
+            ("2001::1234", "abcd::123::wrong", 100000, SessionError::InvalidPhantom),
+
+            // No client provided in ipv4
+            ("", "10.10.0.1", 100000, SessionError::InvalidClient),
+        ];
+
+
+        for entry in &test_tuples {
+            let mut s2d = StationToDetector::new();
+            s2d.set_client_ip(entry.0.to_string());
+            s2d.set_phantom_ip(entry.1.to_string());
+            s2d.set_timeout_ns(entry.2);
+    
+            let sd = match SessionResult::from(&s2d) {
+                Ok(sd) => sd,
+                Err(e) => {
+                    panic!("Failed to parse StationToDetector: {}, {}", e, s2d.get_client_ip());
+                }
+            };
+    
+            // assert_eq!(entry.0, sd.client_ip.to_string());
+            assert_eq!(entry.1, sd.phantom_ip.to_string());
+            assert_eq!(entry.2, sd.timeout)
+        }
+
+        for entry in &test_tuples_bad {
+            let mut s2d = StationToDetector::new();
+            s2d.set_client_ip(entry.0.to_string());
+            s2d.set_phantom_ip(entry.1.to_string());
+            s2d.set_timeout_ns(entry.2);
+    
+            match SessionResult::from(&s2d) {
+                Ok(_) => {
+                    panic!("Should have failed");
+                },
+                Err(e) => {
+                    assert_eq!(format!("{}",e), format!("{}",entry.3));
+                },
+            };
+        }
+    }
+
+    #[test]
+    fn test_session_details_builder() {
+        let client = Ipv4Addr::new(192, 168, 0, 1);
+        let phantom = Ipv4Addr::new(10, 10, 0, 1);
+        let sd = SessionDetails::builder()
+            .client(client)
+            .phantom(phantom)
+            .port(8443)
+            .timeout(Duration::from_secs(5))
+            .build()
+            .unwrap();
+        assert_eq!(sd.get_key(), SessionDetails::new("192.168.0.1", "10.10.0.1", 8443, Duration::from_secs(5)).unwrap().get_key());
+        assert_eq!(sd.timeout_ns(), 5*S2NS);
+        assert_eq!(sd.registration_source, RegistrationSource::Unspecified);
+        assert_eq!(sd.transport, TransportType::Null);
+        assert_eq!(sd.mode, SessionMode::Phantom);
+        assert_eq!(sd.tenant, DEFAULT_TENANT);
+
+        let sd = SessionDetails::builder()
+            .phantom("2001::1234".parse::<IpAddr>().unwrap())
+            .timeout(Duration::from_secs(u64::MAX))
+            .source(RegistrationSource::API)
+            .transport(TransportType::Min)
+            .max_connections(2)
+            .mode(SessionMode::Decoy)
+            .tenant(7)
+            .build()
+            .unwrap();
+        assert_eq!(sd.client_ip, "::1".parse::<IpAddr>().unwrap());
+        assert_eq!(sd.phantom_port, 0);
+        assert_eq!(sd.timeout_ns(), u64::MAX);
+        assert_eq!((sd.registration_source, sd.transport, sd.max_connections, sd.mode, sd.tenant),
+            (RegistrationSource::API, TransportType::Min, 2, SessionMode::Decoy, 7));
+
+        let v6: IpAddr = "2001::1".parse().unwrap();
+        let cases: [(Option<IpAddr>, Option<IpAddr>, SessionError); 3] = [
+            (Some(client.into()), None, SessionError::InvalidPhantom),
+            (Some(v6), Some(phantom.into()), SessionError::MixedV4V6Error),
+            (None, Some(phantom.into()), SessionError::InvalidClient),
+        ];
+        for &(c, p, ref err) in cases.iter() {
+            let mut b = SessionDetails::builder();
+            if let Some(c) = c {
+                b = b.client(c);
+            }
+            if let Some(p) = p {
+                b = b.phantom(p);
+            }
+            assert_eq!(b.build().err().map(|e| e.to_string()), Some(err.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_session_tracker_basics() {
+
+        let mut st = SessionTracker::new();
+
+        let test_tuples = [
+            // (client_ip, phantom_ip, timeout)
+            ("192.168.0.1", "10.10.0.1", 443, 100000),
+            ("192.168.0.1", "192.0.0.127", 443, 100000),     // duplicate client_addr
+            ("2601::123:abcd", "2001::1234", 443, 100000),
+            ("", "2001::1234", 443, 100000),                 // duplicate phantom Addr
+            ("172.128.0.2", "8.0.0.1", 443, 1),              // timeout immediately
+            
+            // client registering with v4 will also create registrations for v6 just in-case
+            ("192.168.0.1", "2801::1234", 443, 100000),
+        ];
+
+        for entry in &test_tuples {
+            let s1 = SessionDetails::new(entry.0, entry.1, entry.2, Duration::from_nanos(entry.3)).unwrap();
+            st.insert_session(s1);
+        }
+
+        if st.len() != 5 {
+            panic!("Either len is not working or insert is broken")
+        };
+
+        for entry in &test_tuples {
+            let src = match entry.0 {
+                "" => "::1".parse().unwrap(),
+                _ => entry.0.parse().unwrap(),
+            };
+            let f = &FlowNoSrcPort{
+                src_ip: src,
+                dst_ip: entry.1.parse().unwrap(), 
+                dst_port: DEFAULT_PHANTOM_PORT,
+            };
+            if !st.is_tracked_session(f) {
+                panic!("Session should be tracked")
+            }
+        }
+
+        let tt = test_tuples[0];
+        let sd = SessionDetails::new(tt.0, tt.1, tt.2, Duration::from_nanos(tt.3)).unwrap();
+        st._delete_session(sd);
+
+
+        if st.len() != 4 {
+            panic!("Either len is not working or delete is broken")
+        };
+
+        // Oversized timeouts saturate instead of overflowing the expire time.
+        let sd = SessionDetails::new("192.168.0.1", "10.10.0.2", 443, Duration::from_nanos(u64::max_value())).unwrap();
+        st.insert_session(sd);
+        st.insert_session(sd);
+        assert_eq!(st.len(), 5);
+    }
+
+    #[test]
+    fn test_session_tracker_timeouts() {
+        let mut st = SessionTracker::new();
+
+        let test_tuples = [
+            // (client_ip, phantom_ip, phantom_port, timeout)
+            ("172.128.0.2", "8.0.0.1", 443, 1, false),            // timeout immediately
+            ("192.168.0.1", "10.10.0.1", 443, 5*S2NS, true),
+            ("192.168.0.1", "192.0.0.127", 443, 5*S2NS, true),    
+ 
+            // client registering with v4 will also create registrations for v6 just in-case
+             ("192.168.0.1", "2801::1234", 443, 5*S2NS, true),
+            
+            // duplicate with shorter timeout should not drop
+            ("2601::123:abcd", "2001::1234", 443, 5*S2NS, true),
+            ("::1", "2001::1234", 443, 1*S2NS, true),
+            
+            // duplicate with long timeout should prevent drop
+            ("7.0.0.2", "8.8.8.8", 443, 1, true),
+            ("7.0.0.2", "8.8.8.8", 443, 5*S2NS, true),
+        ];
+    
+        for entry in &test_tuples {
+            let s1 = SessionDetails::new(entry.0, entry.1, entry.2, Duration::from_nanos(entry.3)).unwrap();
+            st.insert_session(s1);
+        }
+
+        let dur = time::Duration::new(3, 0);
+        thread::sleep(dur);
+
+        assert_eq!(st.drop_stale_sessions(), 1);
+
+        for entry in &test_tuples {
+            let f = &FlowNoSrcPort{
+                src_ip: entry.0.parse().unwrap(),
+                dst_ip: entry.1.parse().unwrap(), 
+                dst_port: DEFAULT_PHANTOM_PORT,
+            };
+            assert_eq!(st.is_tracked_session(f), entry.4)
+        }
+
+        thread::sleep(dur);
+        
+        assert_eq!(st.drop_stale_sessions(), 5);
+    }
+
+    #[test]
+    fn test_session_tracker_simulated_time() {
+        let clock = Clock::simulated(0);
+        let mut st = SessionTracker::with_clock(clock.clone());
+
+        let short = SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_secs(1)).unwrap();
+        let long = SessionDetails::new("", "2001::1234", 443, Duration::from_secs(10)).unwrap();
+        st.insert_session(short);
+        st.insert_session(long);
+
+        clock.advance(1*S2NS - 1);
+        assert_eq!(st.drop_stale_sessions(), 0);
+        clock.advance(1);
+        assert_eq!(st.drop_stale_sessions(), 1);
+
+        // Traffic extends the session by TIMEOUT_PHANTOMS_NS from "now".
+        let f = FlowNoSrcPort{
+            src_ip: "::1".parse().unwrap(),
+            dst_ip: "2001::1234".parse().unwrap(),
+            dst_port: DEFAULT_PHANTOM_PORT,
+        };
+        st.update_session(&f);
+        clock.advance(TIMEOUT_PHANTOMS_NS - 1);
+        assert_eq!(st.drop_stale_sessions(), 0);
+        clock.advance(1);
+        assert_eq!(st.drop_stale_sessions(), 1);
+    }
+
+    #[test]
+    fn test_session_extension_caps() {
+        let f = |phantom: &str| FlowNoSrcPort{
+            src_ip: "192.168.0.1".parse().unwrap(),
+            dst_ip: phantom.parse().unwrap(),
+            dst_port: DEFAULT_PHANTOM_PORT,
+        };
+
+        // total lifetime: extensions stop at 400s after registration
+        let clock = Clock::simulated(0);
+        let config = SessionConfig { max_total_lifetime: Some(Duration::from_secs(400)), ..SessionConfig::default() };
+        let mut st = SessionTracker::with_config(config, clock.clone());
+        st.insert_session(SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_secs(10)).unwrap());
+        clock.advance(5*S2NS);
+        st.update_session(&f("10.10.0.1"));
+        clock.advance(290*S2NS);
+        st.update_session(&f("10.10.0.1"));
+        clock.advance(105*S2NS - 1);
+        st.update_session(&f("10.10.0.1"));
+        assert_eq!(st.drop_stale_sessions(), 0);
+        clock.advance(1);
+        assert_eq!(st.drop_stale_sessions(), 1);
+
+        // extension count: the third extension is ignored
+        let clock = Clock::simulated(0);
+        let config = SessionConfig { max_extensions: Some(2), ..SessionConfig::default() };
+        let mut st = SessionTracker::with_config(config, clock.clone());
+        st.insert_session(SessionDetails::new("192.168.0.1", "10.10.0.2", 443, Duration::from_secs(10)).unwrap());
+        st.update_session(&f("10.10.0.2"));
+        clock.advance(100*S2NS);
+        st.update_session(&f("10.10.0.2"));
+        clock.advance(100*S2NS);
+        st.update_session(&f("10.10.0.2"));
+        clock.advance(200*S2NS - 1);
+        assert_eq!(st.drop_stale_sessions(), 0);
+        clock.advance(1);
+        assert_eq!(st.drop_stale_sessions(), 1);
+    }
+
+    #[test]
+    fn test_session_station_update() {
+        let flow = FlowNoSrcPort {
+            src_ip: "192.168.0.1".parse().unwrap(),
+            dst_ip: "10.10.0.1".parse().unwrap(),
+            dst_port: 443,
+        };
+        let update = |phantom: &str, timeout: Option<u64>| Registration {
+            command: Command::Update,
+            client_ip: "192.168.0.1".to_string(),
+            phantom_ip: phantom.to_string(),
+            phantom_port: 443,
+            timeout: timeout.map(Duration::from_secs),
+            ..Registration::default()
+        };
+
+        let clock = Clock::simulated(0);
+        let config = SessionConfig {
+            max_extensions: Some(1),
+            max_total_lifetime: Some(Duration::from_secs(400)),
+            ..SessionConfig::default()
+        };
+        let mut st = SessionTracker::with_config(config, clock.clone());
+        st.insert_session(SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_secs(10)).unwrap());
+        st.update_session(&flow);
+
+        // refreshed past the extension count
+        clock.advance(5*S2NS);
+        assert!(apply_registration_message(&mut st, &update("10.10.0.1", Some(100)), false));
+        clock.advance(100*S2NS - 1);
+        assert_eq!(st.drop_stale_sessions(), 0);
+
+        // and shortened
+        assert!(apply_registration_message(&mut st, &update("10.10.0.1", Some(5)), false));
+        clock.advance(5*S2NS - 1);
+        assert_eq!(st.drop_stale_sessions(), 0);
+
+        // capped by the total lifetime
+        assert!(apply_registration_message(&mut st, &update("10.10.0.1", Some(1000)), false));
+        clock.advance(290*S2NS + 1);
+        assert_eq!(st.drop_stale_sessions(), 0);
+        clock.advance(1);
+        assert_eq!(st.drop_stale_sessions(), 1);
+
+        // updates create no sessions, and need a lifetime
+        assert!(!apply_registration_message(&mut st, &update("10.10.0.1", Some(100)), false));
+        assert_eq!(st.len(), 0);
+        st.insert_session(SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_secs(10)).unwrap());
+        assert!(!apply_registration_message(&mut st, &update("10.10.0.1", None), false));
+        assert!(st.ingest_errors().take_report().starts_with("ingest errors parse 0 invalid 1 "));
+
+        assert_eq!(st.take_expiry_change_report(),
+            "activity extensions 1 station updates 3 (capped 1 untracked 1)");
+        assert_eq!(st.take_expiry_change_report(),
+            "activity extensions 0 station updates 0 (capped 0 untracked 0)");
+    }
+
+    #[test]
+    fn test_session_configured_extensions() {
+        let f = |phantom: &str| FlowNoSrcPort{
+            src_ip: "192.168.0.1".parse().unwrap(),
+            dst_ip: phantom.parse().unwrap(),
+            dst_port: DEFAULT_PHANTOM_PORT,
+        };
+        let session = |phantom: &str, transport: TransportType| SessionDetails::builder()
+            .client(Ipv4Addr::new(192, 168, 0, 1))
+            .phantom(phantom.parse::<IpAddr>().unwrap())
+            .port(DEFAULT_PHANTOM_PORT)
+            .timeout(Duration::from_secs(1))
+            .transport(transport);
+
+        let clock = Clock::simulated(0);
+        let config = SessionConfig {
+            extension: Some(Duration::from_secs(60)),
+            transport_extensions: vec![(TransportType::Min, Duration::from_secs(3600))],
+            max_lifetime: Some(Duration::from_secs(7200)),
+            ..SessionConfig::default()
+        };
+        assert!(config.extension_errors().is_empty());
+        let mut st = SessionTracker::with_config(config, clock.clone());
+        st.insert_session(session("10.10.0.1", TransportType::Null).build().unwrap());
+        st.insert_session(session("10.10.0.2", TransportType::Min).build().unwrap());
+        st.insert_session(session("10.10.0.3", TransportType::Min).extension(Duration::from_secs(5)).build().unwrap());
+        st.insert_session(session("10.10.0.4", TransportType::Null).extension(Duration::from_secs(86400)).build().unwrap());
+        for p in ["10.10.0.1", "10.10.0.2", "10.10.0.3", "10.10.0.4"].iter() {
+            st.update_session(&f(p));
+        }
+        let expiry = |p| st.get_session(&f(p)).unwrap().expire_time;
+        // configured, per transport, asked for, and capped at the lifetime
+        assert_eq!(expiry("10.10.0.1"), MonotonicNs(60*S2NS));
+        assert_eq!(expiry("10.10.0.2"), MonotonicNs(3600*S2NS));
+        assert_eq!(expiry("10.10.0.3"), MonotonicNs(5*S2NS));
+        assert_eq!(expiry("10.10.0.4"), MonotonicNs(7200*S2NS));
+
+        let config = SessionConfig {
+            extension: Some(Duration::from_secs(600)),
+            transport_extensions: vec![(TransportType::Prefix, Duration::from_secs(0))],
+            max_lifetime: Some(Duration::from_secs(300)),
+            ..SessionConfig::default()
+        };
+        assert_eq!(config.extension_errors(), vec![
+            "session extension 600s exceeds the maximum session lifetime 300s".to_string(),
+            "Prefix extension is 0".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn test_session_match_latch() {
+        let f = FlowNoSrcPort{
+            src_ip: "192.168.0.1".parse().unwrap(),
+            dst_ip: "10.10.0.1".parse().unwrap(),
+            dst_port: DEFAULT_PHANTOM_PORT,
+        };
+        let clock = Clock::simulated(0);
+        let mut st = SessionTracker::with_config(SessionConfig::default(), clock.clone());
+        assert!(st.latch_match(&f).is_none());
+        st.insert_session(SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_secs(10)).unwrap());
+        assert_eq!(st.get_session(&f).unwrap().matched_at, None);
+
+        // one winner among workers racing for the first match
+        clock.advance(S2NS);
+        let workers: Vec<_> = (0..8).map(|_| {
+            let st = st.shared();
+            thread::spawn(move || st.latch_match(&f).is_some())
+        }).collect();
+        let won = workers.into_iter().map(|w| w.join().unwrap()).filter(|won| *won).count();
+        assert_eq!(won, 1);
+        assert_eq!(st.get_session(&f).unwrap().matched_at, Some(MonotonicNs(S2NS)));
+
+        // a new registration of the same session doesn't rearm it
+        st.insert_session(SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_secs(10)).unwrap());
+        assert!(st.latch_match(&f).is_none());
+    }
+
+    #[test]
+    fn test_session_id() {
+        let clock = Clock::simulated(1000);
+        let mut st = SessionTracker::with_clock(clock.clone());
+
+        let sd = SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_secs(5)).unwrap();
+        let f = FlowNoSrcPort{
+            src_ip: "192.168.0.1".parse().unwrap(),
+            dst_ip: "10.10.0.1".parse().unwrap(),
+            dst_port: DEFAULT_PHANTOM_PORT,
+        };
+        assert!(st.get_session(&f).is_none());
+
+        st.insert_session(sd);
+        let id = st.get_session(&f).unwrap().session_id;
+        assert_eq!(id, session_id(&sd.get_key(), MonotonicNs(1000)));
+        assert_eq!(st.get_session(&f).unwrap().affinity, affinity_hash(&sd.get_key()));
+
+        // A duplicate registration extends the session but keeps its id.
+        clock.advance(S2NS);
+        st.insert_session(sd);
+        assert_eq!(st.get_session(&f).unwrap().session_id, id);
+
+        // The same key registered at a different time is a different session.
+        st._delete_session(sd);
+        st.insert_session(sd);
+        assert!(st.get_session(&f).unwrap().session_id != id);
+        assert!(st.dump()[0].starts_with(&format!("[{:016x}]", session_id(&sd.get_key(), MonotonicNs(1000 + S2NS)))));
+    }
+
+    #[test]
+    fn test_session_lock_recovery() {
+        let clock = Clock::simulated(0);
+        let mut st = SessionTracker::with_clock(clock.clone());
+        st.insert_session(SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_secs(5)).unwrap());
+
+        // a writer panics holding the lock
+        let sessions = Arc::clone(&st.tracked_sessions);
+        let writer = thread::spawn(move || {
+            let _map = sessions.write().unwrap();
+            panic!("writer failed");
+        });
+        assert!(writer.join().is_err());
+        assert!(st.tracked_sessions.is_poisoned());
+
+        // every thread carries on
+        assert_eq!(st.len(), 1);
+        assert!(!st.tracked_sessions.is_poisoned());
+        assert_eq!(st.take_lock_recoveries(), 1);
+        st.insert_session(SessionDetails::new("192.168.0.2", "10.10.0.1", 443, Duration::from_secs(5)).unwrap());
+        let f = FlowNoSrcPort{
+            src_ip: "192.168.0.2".parse().unwrap(),
+            dst_ip: "10.10.0.1".parse().unwrap(),
+            dst_port: DEFAULT_PHANTOM_PORT,
+        };
+        assert!(st.is_tracked_session(&f));
+        // the indexes were rebuilt
+        assert_eq!(st.describe_phantom(&"10.10.0.1".parse().unwrap(), None).len(), 2);
+        clock.advance(6*S2NS);
+        st.drop_stale_sessions();
+        assert_eq!(st.len(), 0);
+        assert_eq!(st.take_lock_recoveries(), 0);
+    }
+
+    #[test]
+    fn test_session_shared_registrations() {
+        let clock = Clock::simulated(0);
+        let mut st = SessionTracker::with_clock(clock.clone());
+        let f = FlowNoSrcPort{
+            src_ip: "192.168.0.1".parse().unwrap(),
+            dst_ip: "10.10.0.1".parse().unwrap(),
+            dst_port: DEFAULT_PHANTOM_PORT,
+        };
+
+        // two registrations from the same client for the same phantom:port
+        let first = SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_secs(5)).unwrap();
+        let second = SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_secs(10)).unwrap();
+        st.insert_session(first);
+        st.insert_session(second);
+        assert_eq!(st.len(), 1);
+        let entry = st.get_session(&f).unwrap();
+        assert_eq!(entry.registrations, 2);
+        assert_eq!(entry.expire_time, MonotonicNs(10*S2NS));
+        assert!(st.dump()[0].ends_with("(2 registrations)"));
+
+        // the QUIC Initial's DCID hash is listed with the session
+        st.set_quic_dcid_hash(&f, DcidHash(0x1234));
+        assert!(st.dump()[0].contains(" quic dcid 0000000000001234 "));
+
+        // a redelivered message extends the session but counts once
+        let mut third = SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_secs(20)).unwrap();
+        third.message = Some(3);
+        st.insert_session(third);
+        st.insert_session(third);
+        let entry = st.get_session(&f).unwrap();
+        assert_eq!(entry.registrations, 3);
+        assert_eq!(entry.expire_time, MonotonicNs(20*S2NS));
+        assert_eq!(st.remove_registration(&third), Some(2));
+
+        // removing one keeps the session for the other
+        assert_eq!(st.remove_registration(&first), Some(1));
+        assert!(st.is_tracked_session(&f));
+        assert_eq!(st.get_session(&f).unwrap().expire_time, MonotonicNs(20*S2NS));
+
+        assert_eq!(st.remove_registration(&second), Some(0));
+        assert!(!st.is_tracked_session(&f));
+        assert_eq!(st.remove_registration(&second), None);
+    }
+
+    #[test]
+    fn test_session_default_ports() {
+        let config = SessionConfig { phantom_ports: vec![443, 80], ..SessionConfig::default() };
+        let mut st = SessionTracker::with_config(config, Clock::Monotonic);
+        let flow = |src: &str, dst: &str, port: u16| FlowNoSrcPort{
+            src_ip: src.parse().unwrap(),
+            dst_ip: dst.parse().unwrap(),
+            dst_port: port,
+        };
+
+        // no port: tracked on every default port
+        let any_port = SessionDetails::new("192.168.0.1", "10.10.0.1", 0, Duration::from_secs(5)).unwrap();
+        st.add_session(any_port);
+        assert_eq!(st.len(), 2);
+        assert!(st.is_tracked_session(&flow("192.168.0.1", "10.10.0.1", 443)));
+        assert!(st.is_tracked_session(&flow("192.168.0.1", "10.10.0.1", 80)));
+        assert!(!st.is_tracked_session(&flow("192.168.0.1", "10.10.0.1", 8443)));
+
+        // explicit port: only that one
+        st.add_session(SessionDetails::new("2601::123:abcd", "2001::1234", 8443, Duration::from_secs(5)).unwrap());
+        assert_eq!(st.len(), 3);
+        assert!(st.is_tracked_session(&flow("2601::123:abcd", "2001::1234", 8443)));
+        assert!(!st.is_tracked_session(&flow("2601::123:abcd", "2001::1234", 443)));
+
+        assert_eq!(st.remove_registration(&any_port), Some(0));
+        assert_eq!(st.len(), 1);
+
+        // unconfigured trackers use 443
+        let mut st = SessionTracker::new();
+        st.add_session(any_port);
+        assert!(st.is_tracked_session(&flow("192.168.0.1", "10.10.0.1", 443)));
+        assert_eq!(st.len(), 1);
+    }
+
+    #[test]
+    fn test_session_same_phantom_ports() {
+        let clock = Clock::simulated(0);
+        let config = SessionConfig { max_connections: Some(1), ..SessionConfig::default() };
+        let mut st = SessionTracker::with_config(config, clock.clone());
+        let flow = |src: &str, dst: &str, port: u16| FlowNoSrcPort{
+            src_ip: src.parse().unwrap(),
+            dst_ip: dst.parse().unwrap(),
+            dst_port: port,
+        };
+
+        let https = SessionDetails::new("2601::123:abcd", "2001::1234", 443, Duration::from_secs(5)).unwrap();
+        let alt = SessionDetails::new("2601::123:abcd", "2001::1234", 8443, Duration::from_secs(5)).unwrap();
+        let v4_https = SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_secs(5)).unwrap();
+        let v4_alt = SessionDetails::new("192.168.0.1", "10.10.0.1", 8443, Duration::from_secs(5)).unwrap();
+        for sd in &[https, alt, v4_https, v4_alt] {
+            st.add_session(*sd);
+        }
+        assert_eq!(st.len(), 4);
+        assert_eq!(st.sessions_per_port(), vec![(443, 2), (8443, 2)]);
+        assert_eq!(st.describe_phantom(&"2001::1234".parse().unwrap(), None).len(), 2);
+        assert_eq!(st.describe_phantom(&"2001::1234".parse().unwrap(), Some(8443)).len(), 1);
+        assert!(st.describe_phantom(&"2001::1234".parse().unwrap(), Some(80)).is_empty());
+
+        // connection limits are per port
+        assert!(st.open_connection(&flow("2601::123:abcd", "2001::1234", 443)));
+        assert!(!st.open_connection(&flow("2601::123:abcd", "2001::1234", 443)));
+        assert!(st.open_connection(&flow("2601::123:abcd", "2001::1234", 8443)));
+
+        // traffic on one port only extends that port's session
+        clock.advance(4*S2NS);
+        st.update_session(&flow("2601::123:abcd", "2001::1234", 8443));
+        st.update_session(&flow("192.168.0.1", "10.10.0.1", 443));
+        clock.advance(1*S2NS);
+        assert_eq!(st.drop_stale_sessions(), 2);
+        assert!(!st.is_tracked_session(&flow("2601::123:abcd", "2001::1234", 443)));
+        assert!(st.is_tracked_session(&flow("2601::123:abcd", "2001::1234", 8443)));
+        assert!(st.is_tracked_session(&flow("192.168.0.1", "10.10.0.1", 443)));
+        assert!(!st.is_tracked_session(&flow("192.168.0.1", "10.10.0.1", 8443)));
+
+        // removing and deleting are per port too
+        st.add_session(https);
+        st.add_session(v4_alt);
+        assert_eq!(st.remove_registration(&https), Some(0));
+        assert!(st.is_tracked_session(&flow("2601::123:abcd", "2001::1234", 8443)));
+        st._delete_session(v4_alt);
+        assert!(st.is_tracked_session(&flow("192.168.0.1", "10.10.0.1", 443)));
+        assert_eq!(st.sessions_per_port(), vec![(443, 1), (8443, 1)]);
+
+        // and in strict v6 mode
+        let config = SessionConfig { strict_v6_prefix: Some(64), ..SessionConfig::default() };
+        let mut st = SessionTracker::with_config(config, Clock::Monotonic);
+        st.add_session(https);
+        st.add_session(alt);
+        assert_eq!(st.len(), 2);
+        assert!(st.is_tracked_session(&flow("2601::123:1", "2001::1234", 443)));
+        assert!(st.is_tracked_session(&flow("2601::123:1", "2001::1234", 8443)));
+        assert_eq!(st.remove_registration(&alt), Some(0));
+        assert!(st.is_tracked_session(&flow("2601::123:1", "2001::1234", 443)));
+        assert!(!st.is_tracked_session(&flow("2601::123:1", "2001::1234", 8443)));
+    }
+
+    #[test]
+    fn test_session_tracker_strict_v6() {
+        let config = SessionConfig { strict_v6_prefix: Some(64), ..SessionConfig::default() };
+        let mut st = SessionTracker::with_config(config, Clock::Monotonic);
+
+        let test_tuples = [
+            // (client_ip, phantom_ip)
+            ("2601:1:2:3:abcd::1", "2001::1234"),
+            ("192.168.0.1", "10.10.0.1"),
+
+            // no v6 client address, can't be tracked in strict mode
+            ("", "2001::5678"),
+            ("192.168.0.1", "2801::1234"),
+        ];
+        for entry in &test_tuples {
+            let sd = SessionDetails::new(entry.0, entry.1, 443, Duration::from_secs(5)).unwrap();
+            st.insert_session(sd);
+        }
+        assert_eq!(st.len(), 2);
+
+        let flow = |src: &str, dst: &str| FlowNoSrcPort{
+            src_ip: src.parse().unwrap(),
+            dst_ip: dst.parse().unwrap(),
+            dst_port: DEFAULT_PHANTOM_PORT,
+        };
+
+        // Any address in the registering client's /64 matches
+        assert!(st.is_tracked_session(&flow("2601:1:2:3:abcd::1", "2001::1234")));
+        assert!(st.is_tracked_session(&flow("2601:1:2:3::ffff", "2001::1234")));
+        assert!(st.get_session(&flow("2601:1:2:3::ffff", "2001::1234")).is_some());
+
+        // Scanners outside it do not
+        assert!(!st.is_tracked_session(&flow("2601:1:2:4::1", "2001::1234")));
+        assert!(!st.is_tracked_session(&flow("2a00::1", "2001::1234")));
+        assert!(!st.is_tracked_session(&flow("2a00::1", "2001::5678")));
+
+        // v4 keys are unchanged
+        assert!(st.is_tracked_session(&flow("192.168.0.1", "10.10.0.1")));
+
+        // Non strict trackers still key v6 sessions on the phantom only
+        let key = SessionDetails::new("2601:1:2:3:abcd::1", "2001::1234", 443, Duration::from_nanos(1)).unwrap().get_key();
+        assert_eq!(key, "2001::1234-443");
+        let strict = SessionConfig { strict_v6_prefix: Some(48), ..SessionConfig::default() };
+        assert_eq!(strict.key(SessionMode::Phantom, DEFAULT_TENANT, &"2601:1:2:3:abcd::1".parse().unwrap(), &"2001::1234".parse().unwrap(), 443),
+                   Some("2601:1:2::/48-2001::1234-443".to_string()));
+
+        // packet lookups format the same keys into a reused buffer
+        let key_of = |f: &FlowNoSrcPort| strict.find_flow_key(f, |key| Some(key.to_string()));
+        assert_eq!(key_of(&flow("2601:1:2:3:abcd::1", "2001::1234")), Some("2601:1:2::/48-2001::1234-443".to_string()));
+        assert_eq!(key_of(&flow("192.168.0.1", "10.10.0.1")), Some("192.168.0.1-10.10.0.1-443".to_string()));
+        assert_eq!(key_of(&flow("::1", "2001::1234")), None);
+        assert_eq!(key_of(&flow("192.168.0.1", "2001::1234")), None);
+    }
+
+    #[test]
+    fn test_session_tracker_v4_phantom_only() {
+        let config = SessionConfig { v4_phantom_only: true, ..SessionConfig::default() };
+        let mut st = SessionTracker::with_config(config.clone(), Clock::Monotonic);
+
+        let sd = SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_secs(5)).unwrap();
+        assert_eq!(sd.key_in(&config), Some("10.10.0.1-443".to_string()));
+        assert_eq!(sd.get_key(), "192.168.0.1-10.10.0.1-443");
+        st.insert_session(sd);
+
+        let flow = |src: &str, dst: &str, port: u16| FlowNoSrcPort{
+            src_ip: src.parse().unwrap(),
+            dst_ip: dst.parse().unwrap(),
+            dst_port: port,
+        };
+        // the client's address as the detector sees it doesn't matter
+        assert!(st.is_tracked_session(&flow("192.168.0.1", "10.10.0.1", 443)));
+        assert!(st.is_tracked_session(&flow("172.16.5.5", "10.10.0.1", 443)));
+        assert!(st.get_session(&flow("172.16.5.5", "10.10.0.1", 443)).is_some());
+        assert!(!st.is_tracked_session(&flow("172.16.5.5", "10.10.0.1", 80)));
+        assert!(!st.is_tracked_session(&flow("192.168.0.1", "10.10.0.2", 443)));
+
+        // a second client registering the same phantom shares the session
+        st.insert_session(SessionDetails::new("192.168.0.2", "10.10.0.1", 443, Duration::from_secs(5)).unwrap());
+        assert_eq!(st.len(), 1);
+        assert_eq!(st.remove_registration(&sd), Some(1));
+    }
+
+    #[test]
+    fn test_session_tracker_v4_client_prefix() {
+        let config = SessionConfig { v4_client_prefix: Some(20), ..SessionConfig::default() };
+        let mut st = SessionTracker::with_config(config.clone(), Clock::Monotonic);
+
+        let sd = SessionDetails::new("100.64.17.9", "10.10.0.1", 443, Duration::from_secs(5)).unwrap();
+        assert_eq!(sd.key_in(&config), Some("100.64.16.0/20-10.10.0.1-443".to_string()));
+        st.insert_session(sd);
+
+        let flow = |src: &str| FlowNoSrcPort{
+            src_ip: src.parse().unwrap(),
+            dst_ip: "10.10.0.1".parse().unwrap(),
+            dst_port: DEFAULT_PHANTOM_PORT,
+        };
+        // the client moved within its /20
+        assert!(st.is_tracked_session(&flow("100.64.17.9")));
+        assert!(st.is_tracked_session(&flow("100.64.31.200")));
+        assert!(st.get_session(&flow("100.64.16.1")).is_some());
+        assert!(!st.is_tracked_session(&flow("100.64.32.1")));
+        assert!(!st.is_tracked_session(&flow("100.65.17.9")));
+
+        // v6 keys are unchanged, and phantom only keys take precedence
+        let v6 = SessionDetails::new("2601::1", "2001::1234", 443, Duration::from_nanos(1)).unwrap();
+        assert_eq!(v6.key_in(&config), Some("2001::1234-443".to_string()));
+        let both = SessionConfig { v4_phantom_only: true, ..config };
+        assert_eq!(sd.key_in(&both), Some("10.10.0.1-443".to_string()));
+    }
+
+    #[test]
+    fn test_session_tracker_decoy_mode() {
+        let decoy = DecoyConfig { extension: Duration::from_secs(20), max_lifetime: Some(Duration::from_secs(30)) };
+        let config = SessionConfig { v4_phantom_only: true, decoy: Some(decoy), ..SessionConfig::default() };
+        let clock = Clock::simulated(0);
+        let mut st = SessionTracker::with_config(config, clock.clone());
+        let flow = |src: &str, dst: &str| FlowNoSrcPort{
+            src_ip: src.parse().unwrap(),
+            dst_ip: dst.parse().unwrap(),
+            dst_port: 443,
+        };
+
+        let mut s2d = StationToDetector::new();
+        s2d.set_client_ip("192.168.0.1".to_string());
+        s2d.set_phantom_ip("198.51.100.7".to_string());
+        s2d.set_phantom_ip_v6("2001:db8::7".to_string());
+        s2d.set_phantom_port(443);
+        s2d.set_timeout_ns(60*S2NS);
+        s2d.set_mode(SessionMode::Decoy);
+        let sds = sessions_from(&s2d).unwrap();
+        assert_eq!((sds[0].mode, sds[1].mode), (SessionMode::Decoy, SessionMode::Decoy));
+        assert_eq!(sds[0].key_in(st.config()), Some("decoy-192.168.0.1-198.51.100.7-443".to_string()));
+        // no v6 client address to tell the client apart
+        assert_eq!(sds[1].key_in(st.config()), None);
+        st.insert_sessions(&sds);
+        assert_eq!(st.len(), 1);
+
+        // phantom keys ignore the client here, decoy keys never do
+        assert!(st.is_tracked_session(&flow("192.168.0.1", "198.51.100.7")));
+        assert!(!st.is_tracked_session(&flow("192.168.0.2", "198.51.100.7")));
+        st.insert_session(SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_secs(60)).unwrap());
+        assert!(st.is_tracked_session(&flow("192.168.0.2", "10.10.0.1")));
+
+        // decoy lifetimes are capped separately and extended by less
+        let entry = st.get_session(&flow("192.168.0.1", "198.51.100.7")).unwrap();
+        assert_eq!(entry.expire_time, MonotonicNs(30*S2NS));
+        clock.advance(15*S2NS);
+        st.update_session(&flow("192.168.0.1", "198.51.100.7"));
+        st.update_session(&flow("192.168.0.1", "10.10.0.1"));
+        assert_eq!(st.get_session(&flow("192.168.0.1", "198.51.100.7")).unwrap().expire_time, MonotonicNs(35*S2NS));
+        assert_eq!(st.get_session(&flow("192.168.0.1", "10.10.0.1")).unwrap().expire_time,
+                   MonotonicNs(15*S2NS + TIMEOUT_PHANTOMS_NS));
+
+        assert!(st.open_connection(&flow("192.168.0.1", "198.51.100.7")));
+        st.close_connection_with(&flow("192.168.0.1", "198.51.100.7"), 3, 300);
+        let entry = st.get_session(&flow("192.168.0.1", "198.51.100.7")).unwrap();
+        assert_eq!((entry.connections, entry.active_connections, entry.packets), (1, 0, 3));
+
+        // without decoy sessions enabled decoy registrations aren't tracked
+        let mut st = SessionTracker::with_config(SessionConfig::default(), Clock::Monotonic);
+        st.insert_sessions(&sds);
+        assert_eq!(st.len(), 0);
+        assert!(!st.is_tracked_session(&flow("192.168.0.1", "198.51.100.7")));
+    }
+
+    #[test]
+    fn test_session_expiry_stats() {
+        let clock = Clock::simulated(0);
+        let mut st = SessionTracker::with_clock(clock.clone());
+        st.insert_session(SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_secs(5)).unwrap());
+        st.insert_session(SessionDetails::new("192.168.0.2", "10.10.0.1", 443, Duration::from_secs(5)).unwrap());
+        st.insert_session(SessionDetails::new("192.168.0.3", "10.10.0.1", 443, Duration::from_secs(90)).unwrap());
+        st.insert_session(SessionDetails::new("192.168.0.4", "10.10.0.1", 443, Duration::from_secs(7200)).unwrap());
+        let matched = FlowNoSrcPort{
+            src_ip: "192.168.0.1".parse().unwrap(),
+            dst_ip: "10.10.0.1".parse().unwrap(),
+            dst_port: DEFAULT_PHANTOM_PORT,
+        };
+        assert!(st.open_connection(&matched));
+        st.close_connection_with(&matched, 10, 5000);
+        assert!(st.open_connection(&matched));
+        st.close_connection_with(&matched, 2, 120);
+        let entry = st.get_session(&matched).unwrap();
+        assert_eq!((entry.connections, entry.packets, entry.bytes), (2, 12, 5120));
+
+        assert_eq!(st.drop_stale_sessions(), 0);
+        assert_eq!(st.expiry_stats().take_report(),
+            "remaining <10s 2 <30s 0 <60s 0 <120s 1 <300s 0 <600s 0 <1800s 0 <3600s 0 >=3600s 1 expired matched 0 unmatched 0");
+
+        // the distribution is only sampled every EXPIRY_SAMPLE_INTERVAL_NS
+        clock.advance(5*S2NS);
+        assert_eq!(st.drop_stale_sessions_bounded(Some(10)), 2);
+        assert_eq!(st.expiry_stats().take_report(),
+            "remaining <10s 2 <30s 0 <60s 0 <120s 1 <300s 0 <600s 0 <1800s 0 <3600s 0 >=3600s 1 expired matched 1 unmatched 1");
+        let never_matched = st.expiry_stats().take_never_matched();
+        assert_eq!(never_matched.len(), 1);
+        assert_eq!(never_matched.get(&(TransportType::Null, RegistrationSource::Unspecified, "10.10.0.1".parse().unwrap())), Some(&1));
+        assert!(st.expiry_stats().take_never_matched().is_empty());
+        let (expired, unreported) = st.expiry_stats().take_expired_matched();
+        assert_eq!(unreported, 0);
+        assert_eq!(expired.len(), 1);
+        assert_eq!((expired[0].details.client_ip, expired[0].packets, expired[0].bytes),
+            ("192.168.0.1".parse().unwrap(), 12, 5120));
+        assert!(st.expiry_stats().take_expired_matched().0.is_empty());
+        clock.advance(5*S2NS);
+        assert_eq!(st.drop_stale_sessions(), 0);
+        assert_eq!(st.expiry_stats().take_report(),
+            "remaining <10s 0 <30s 0 <60s 0 <120s 1 <300s 0 <600s 0 <1800s 0 <3600s 0 >=3600s 1 expired matched 0 unmatched 0");
+    }
+
+    #[test]
+    fn test_session_connection_limits() {
+        let config = SessionConfig { max_connections: Some(2), ..SessionConfig::default() };
+        let mut st = SessionTracker::with_config(config, Clock::Monotonic);
+
+        let mut s2d = StationToDetector::new();
+        s2d.set_client_ip("192.168.0.1".to_string());
+        s2d.set_phantom_ip("10.10.0.1".to_string());
+        s2d.set_phantom_ip_v6("2801::1234".to_string());
+        s2d.set_phantom_port(443);
+        s2d.set_timeout_ns(5*S2NS);
+        s2d.set_max_connections(1);
+        let sds = sessions_from(&s2d).unwrap();
+        assert_eq!(sds[0].max_connections, 1);
+        assert_eq!(sds[1].max_connections, 1);
+        st.insert_sessions(&sds);
+
+        // no per-registration limit, global limit applies
+        st.insert_session(SessionDetails::new("192.168.0.1", "192.0.0.127", 443, Duration::from_secs(5)).unwrap());
+
+        let flow = |src: &str, dst: &str| FlowNoSrcPort{
+            src_ip: src.parse().unwrap(),
+            dst_ip: dst.parse().unwrap(),
+            dst_port: DEFAULT_PHANTOM_PORT,
+        };
+        let registered = flow("192.168.0.1", "10.10.0.1");
+        let global = flow("192.168.0.1", "192.0.0.127");
+
+        assert!(st.open_connection(&registered));
+        assert!(!st.open_connection(&registered));
+        st.close_connection(&registered);
+        assert!(st.open_connection(&registered));
+        assert_eq!(st.get_session(&registered).unwrap().active_connections, 1);
+
+        assert!(st.open_connection(&global));
+        assert!(st.open_connection(&global));
+        assert!(!st.open_connection(&global));
+
+        // untracked sessions are not limited, extra closes don't underflow
+        assert!(st.open_connection(&flow("192.168.0.2", "10.10.0.1")));
+        st.close_connection(&global);
+        st.close_connection(&global);
+        st.close_connection(&global);
+        assert_eq!(st.get_session(&global).unwrap().active_connections, 0);
+    }
+
+    #[test]
+    fn test_session_tracker_maintenance() {
+        let clock = Clock::simulated(0);
+        let mut st = SessionTracker::with_clock(clock.clone());
+        for i in 0..5 {
+            let phantom = format!("10.10.0.{}", i);
+            st.insert_session(SessionDetails::new("192.168.0.1", &phantom, 443, Duration::from_secs(1)).unwrap());
+        }
+        st.insert_session(SessionDetails::new("192.168.0.1", "10.10.1.1", 443, Duration::from_secs(10)).unwrap());
+        clock.advance(S2NS);
+
+        assert_eq!(st.drop_stale_sessions_bounded(Some(2)), 2);
+        assert_eq!(st.len(), 4);
+        assert_eq!(st.drop_stale_sessions_bounded(Some(2)), 2);
+        assert_eq!(st.drop_stale_sessions_bounded(Some(2)), 1);
+        assert_eq!(st.drop_stale_sessions_bounded(Some(2)), 0);
+        assert_eq!(st.len(), 1);
+
+        // Without an interval there is no maintenance thread
+        assert!(st.spawn_maintenance_thread().is_none());
+        assert!(!st.is_maintained());
+
+        let config = SessionConfig {
+            maintenance: MaintenanceConfig {
+                interval: Some(time::Duration::from_millis(1)),
+                jitter: time::Duration::from_millis(1),
+                max_drops_per_tick: Some(1),
+                adaptive: None,
+            },
+            ..SessionConfig::default()
+        };
+        let mut st = SessionTracker::with_config(config, clock.clone());
+        for i in 0..5 {
+            let phantom = format!("10.10.0.{}", i);
+            st.insert_session(SessionDetails::new("192.168.0.1", &phantom, 443, Duration::from_secs(1)).unwrap());
+        }
+        st.insert_session(SessionDetails::new("192.168.0.1", "10.10.1.1", 443, Duration::from_secs(10)).unwrap());
+        assert!(st.spawn_maintenance_thread().is_some());
+        assert!(st.spawn_maintenance_thread().is_none());
+        assert!(st.is_maintained());
+
+        clock.advance(S2NS);
+        thread::sleep(time::Duration::from_millis(200));
+        assert_eq!(st.len(), 1);
+        assert!(st.cleanup_gauge().is_none());
+
+        // Adaptive: the quiet map is left alone for the longest interval
+        let config = SessionConfig {
+            maintenance: MaintenanceConfig {
+                interval: Some(time::Duration::from_millis(1)),
+                adaptive: Some(AdaptiveConfig { max: time::Duration::from_secs(60), max_stale_percent: 1.0 }),
+                ..MaintenanceConfig::default()
+            },
+            ..SessionConfig::default()
+        };
+        let mut st = SessionTracker::with_config(config, clock.clone());
+        st.insert_session(SessionDetails::new("192.168.0.1", "10.10.1.1", 443, Duration::from_secs(10)).unwrap());
+        st.spawn_maintenance_thread().unwrap();
+        clock.advance(S2NS);
+        thread::sleep(time::Duration::from_millis(100));
+        assert_eq!(st.cleanup_gauge().unwrap().interval(), time::Duration::from_secs(60));
+        assert!(st.cleanup_gauge().unwrap().take_report().ends_with("passes 1"));
+    }
+
+    #[test]
+    fn test_session_cleanup_budget() {
+        let clock = Clock::simulated(0);
+        let budget = SweepBudget { entries: 100, time: None };
+        let config = SessionConfig { cleanup_budget: Some(budget), ..SessionConfig::default() };
+        let mut st = SessionTracker::with_config(config, clock.clone());
+        let sessions: Vec<SessionDetails> = (0..1000).map(|i| {
+            let client = format!("192.168.{}.{}", i / 256, i % 256);
+            let timeout = if i % 4 == 0 { 60*S2NS } else { S2NS };
+            SessionDetails::new(&client, "10.10.0.1", 443, Duration::from_nanos(timeout)).unwrap()
+        }).collect();
+        st.insert_sessions(&sessions);
+        clock.advance(S2NS);
+
+        // each pass visits at most 100 sessions, so drops at most 100
+        let mut passes = 0;
+        while st.len() > 250 {
+            assert!(st.drop_stale_sessions() <= 100);
+            passes += 1;
+            assert!(passes <= 10);
+        }
+        assert_eq!(st.len(), 250);
+        assert_eq!(st.expiry_stats().take_report(),
+            "remaining <10s 0 <30s 0 <60s 250 <120s 0 <300s 0 <600s 0 <1800s 0 <3600s 0 >=3600s 0 expired matched 0 unmatched 750");
+
+        // a drop cap still applies
+        clock.advance(60*S2NS);
+        assert_eq!(st.drop_stale_sessions_bounded(Some(10)), 10);
+        assert_eq!(st.drop_stale_sessions(), 100);
+        assert_eq!(st.drop_stale_sessions(), 100);
+        assert_eq!(st.drop_stale_sessions(), 40);
+        assert_eq!(st.len(), 0);
+    }
+
+    #[test]
+    fn test_session_absolute_expiry() {
+        let now = 1_600_000_000 * S2NS;
+        let mut s2d = StationToDetector::new();
+        s2d.set_client_ip("192.168.0.1".to_string());
+        s2d.set_phantom_ip("10.10.0.1".to_string());
+        s2d.set_timeout_ns(30*S2NS);
+
+        // relative only
+        assert_eq!(registration_timeout(&s2d, WallClockNs(now)), Duration::from_secs(30));
+
+        // absolute takes precedence, time spent in transit is not added
+        s2d.set_expires_at_unix_ns(now + 20*S2NS);
+        assert_eq!(registration_timeout(&s2d, WallClockNs(now)), Duration::from_secs(20));
+        s2d.set_station_time_unix_ns(now - 2*S2NS);
+        assert_eq!(registration_timeout(&s2d, WallClockNs(now)), Duration::from_secs(20));
+
+        // already expired
+        assert_eq!(registration_timeout(&s2d, WallClockNs(now + 25*S2NS)), Duration::from_secs(0));
+
+        // clocks too far apart, fall back to the lifetime when sent
+        s2d.set_station_time_unix_ns(now - 600*S2NS);
+        assert_eq!(registration_timeout(&s2d, WallClockNs(now)), Duration::from_secs(620));
+        s2d.set_station_time_unix_ns(now + 600*S2NS);
+        assert_eq!(registration_timeout(&s2d, WallClockNs(now)), Duration::from_secs(0));
+
+        // lifetimes are clamped to the configured maximum
+        let clock = Clock::simulated(0);
+        let config = SessionConfig { max_lifetime: Some(Duration::from_secs(10)), ..SessionConfig::default() };
+        let mut st = SessionTracker::with_config(config, clock.clone());
+        st.insert_session(SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_secs(30)).unwrap());
+        st.insert_session(SessionDetails::new("192.168.0.1", "10.10.0.2", 443, Duration::from_secs(5)).unwrap());
+        clock.advance(5*S2NS);
+        assert_eq!(st.drop_stale_sessions(), 1);
+        clock.advance(5*S2NS);
+        assert_eq!(st.drop_stale_sessions(), 1);
+    }
+
+    #[test]
+    fn test_session_ingest_latency() {
+        let st = SessionTracker::new();
+        let (tx, rx) = mpsc::channel();
+        let ingest = st.spawn_ingest_thread(ChannelFeed::new(rx));
+
+        let now = WallClockNs::now().0;
+        let sent = [
+            Some(now - 2*S2NS),     // 1s-10s
+            Some(now - 20*S2NS),    // over 10s
+            Some(now + 3600*S2NS),  // station clock ahead
+            None,                   // not stamped, not recorded
+        ];
+        for (i, station_time) in sent.iter().enumerate() {
+            let mut s2d = StationToDetector::new();
+            s2d.set_client_ip(format!("192.168.0.{}", i));
+            s2d.set_phantom_ip("10.10.0.1".to_string());
+            s2d.set_timeout_ns(5*S2NS);
+            if let Some(t) = station_time {
+                s2d.set_station_time_unix_ns(*t);
+            }
+            tx.send(s2d.write_to_bytes().unwrap()).unwrap();
+        }
+        drop(tx);
+        ingest.join().unwrap();
+
+        assert_eq!(st.len(), 4);
+        assert_eq!(st.ingest_latency().take_report(),
+            "<1ms 0 <10ms 0 <100ms 0 <1s 0 <10s 1 >=10s 1 ahead 1");
+    }
+
+    #[test]
+    fn test_session_ingest_replay() {
+        let config = SessionConfig { replay: Some(ReplayConfig::default()), ..SessionConfig::default() };
+        let mut st = SessionTracker::with_config(config, Clock::Monotonic);
+        let mut s2d = StationToDetector::new();
+        s2d.set_client_ip("192.168.0.1".to_string());
+        s2d.set_phantom_ip("10.10.0.1".to_string());
+        s2d.set_timeout_ns(5*S2NS);
+        s2d.set_station_time_unix_ns(WallClockNs::now().0);
+        s2d.set_message_id(b"registration 1".to_vec());
+
+        let (tx, rx) = mpsc::channel();
+        let ingest = st.spawn_ingest_thread(ChannelFeed::new(rx));
+        tx.send(s2d.write_to_bytes().unwrap()).unwrap();
+        drop(tx);
+        ingest.join().unwrap();
+        assert_eq!(st.len(), 1);
 
-            // No client provided in ipv4
-            ("", "10.10.0.1", 100000, SessionError::InvalidClient),
-        ];
+        // once revoked, replaying the registration doesn't bring it back
+        let sd = SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_secs(5)).unwrap();
+        st.remove_registration(&sd);
+        assert_eq!(st.len(), 0);
+        let (tx, rx) = mpsc::channel();
+        let ingest = st.spawn_ingest_thread(ChannelFeed::new(rx));
+        tx.send(s2d.write_to_bytes().unwrap()).unwrap();
+        s2d.clear_message_id();
+        tx.send(s2d.write_to_bytes().unwrap()).unwrap();
+        drop(tx);
+        ingest.join().unwrap();
+        // only the registration without an id is accepted
+        assert_eq!(st.len(), 1);
+        assert_eq!(st.replay_guard().unwrap().take_report(),
+            "replay guard duplicates 1 stale 0 unstamped 0 unidentified 1 tracked ids 1");
+    }
+
+    #[test]
+    fn test_session_control_replay() {
+        let config = SessionConfig {
+            control: Some(ControlConfig {
+                addr: "127.0.0.1:0".parse().unwrap(),
+                key: b"station key".to_vec(),
+                multicast_interface: "0.0.0.0".parse().unwrap(),
+            }),
+            ..SessionConfig::default()
+        };
+        let st = SessionTracker::with_config(config, Clock::Monotonic);
+        assert!(st.replay_guard().is_none());
+        let registration = |client: &str, id: &[u8], station_time: Option<u64>| {
+            let mut s2d = StationToDetector::new();
+            s2d.set_client_ip(client.to_string());
+            s2d.set_phantom_ip("10.10.0.1".to_string());
+            s2d.set_timeout_ns(5*S2NS);
+            if let Some(t) = station_time {
+                s2d.set_station_time_unix_ns(t);
+            }
+            s2d.set_message_id(id.to_vec());
+            s2d.write_to_bytes().unwrap()
+        };
 
+        // checked even without a replay guard configured for redis
+        let now = WallClockNs::now().0;
+        let (tx, rx) = mpsc::channel();
+        let ingest = st.spawn_control_thread(ChannelFeed::new(rx));
+        tx.send(registration("192.168.0.1", b"1", Some(now))).unwrap();
+        tx.send(registration("192.168.0.1", b"1", Some(now))).unwrap();
+        tx.send(registration("192.168.0.2", b"", Some(now))).unwrap();
+        tx.send(registration("192.168.0.3", b"3", None)).unwrap();
+        tx.send(registration("192.168.0.4", b"4", Some(now - 3600*S2NS))).unwrap();
+        drop(tx);
+        ingest.join().unwrap();
+        assert_eq!(st.len(), 1);
+        assert_eq!(st.control_replay_guard().unwrap().take_report(),
+            "replay guard duplicates 1 stale 1 unstamped 1 unidentified 1 tracked ids 1");
+    }
 
-        for entry in &test_tuples {
+    #[test]
+    fn test_session_prime() {
+        let config = SessionConfig {
+            replay: Some(ReplayConfig::default()),
+            prime_key: Some("registrations".to_string()),
+            ..SessionConfig::default()
+        };
+        let st = SessionTracker::with_config(config, Clock::Monotonic);
+        assert!(!st.is_primed());
+        let registration = |client: &str, station_time: u64| {
             let mut s2d = StationToDetector::new();
-            s2d.set_client_ip(entry.0.to_string());
-            s2d.set_phantom_ip(entry.1.to_string());
-            s2d.set_timeout_ns(entry.2);
-    
-            let sd = match SessionResult::from(&s2d) {
-                Ok(sd) => sd,
-                Err(e) => {
-                    panic!("Failed to parse StationToDetector: {}, {}", e, s2d.get_client_ip());
-                }
-            };
-    
-            // assert_eq!(entry.0, sd.client_ip.to_string());
-            assert_eq!(entry.1, sd.phantom_ip.to_string());
-            assert_eq!(entry.2, sd.timeout)
+            s2d.set_client_ip(client.to_string());
+            s2d.set_phantom_ip("10.10.0.1".to_string());
+            s2d.set_timeout_ns(5*S2NS);
+            s2d.set_station_time_unix_ns(station_time);
+            s2d.set_message_id(client.as_bytes().to_vec());
+            s2d.write_to_bytes().unwrap()
+        };
+
+        // received live while priming
+        let (tx, rx) = mpsc::channel();
+        let ingest = st.spawn_ingest_thread(ChannelFeed::new(rx));
+        tx.send(registration("192.168.0.1", WallClockNs::now().0)).unwrap();
+        drop(tx);
+        ingest.join().unwrap();
+
+        // stored registrations skip the replay guard however old, keep what
+        // is left of their lifetime, are skipped once expired, and those
+        // already tracked aren't counted again
+        let now = WallClockNs::now().0;
+        let (tx, rx) = mpsc::channel();
+        let prime = st.spawn_prime_thread(ChannelFeed::new(rx));
+        tx.send(registration("192.168.0.1", now - 3*S2NS)).unwrap();
+        tx.send(registration("192.168.0.2", now - 3*S2NS)).unwrap();
+        tx.send(registration("192.168.0.3", now - 3600*S2NS)).unwrap();
+        tx.send(vec![0xff]).unwrap();
+        drop(tx);
+        let started = st.clock.now();
+        prime.join().unwrap();
+        assert!(st.is_primed());
+        assert_eq!(st.len(), 2);
+        let flow = |client: &str| FlowNoSrcPort{
+            src_ip: client.parse().unwrap(),
+            dst_ip: "10.10.0.1".parse().unwrap(),
+            dst_port: DEFAULT_PHANTOM_PORT,
+        };
+        assert_eq!(st.get_session(&flow("192.168.0.1")).unwrap().registrations, 1);
+        let aged = st.get_session(&flow("192.168.0.2")).unwrap().expire_time;
+        assert!(aged <= st.clock.now().saturating_add(2*S2NS), "{:?}", aged);
+        assert!(aged >= started.saturating_add(S2NS), "{:?}", aged);
+        assert!(st.get_session(&flow("192.168.0.3")).is_none());
+        assert_eq!(st.replay_guard().unwrap().take_report(),
+            "replay guard duplicates 0 stale 0 unstamped 0 unidentified 0 tracked ids 1");
+        assert!(SessionTracker::new().is_primed());
+    }
+
+    #[test]
+    fn test_session_tenants() {
+        let config = SessionConfig { tenants: Some("7:max=2,9".parse().unwrap()), ..SessionConfig::default() };
+        let st = SessionTracker::with_config(config, Clock::Monotonic);
+        let registration = |client: &str, tenant: Option<u32>| {
+            let mut s2d = StationToDetector::new();
+            s2d.set_client_ip(client.to_string());
+            s2d.set_phantom_ip("10.10.0.1".to_string());
+            s2d.set_phantom_port(443);
+            s2d.set_timeout_ns(5*S2NS);
+            if let Some(t) = tenant {
+                s2d.set_tenant_id(t);
+            }
+            s2d.write_to_bytes().unwrap()
+        };
+        let delete = |tenant: u32| {
+            let mut s2d = StationToDetector::new();
+            s2d.set_tenant_id(tenant);
+            s2d.set_delete_tenant_sessions(true);
+            s2d.write_to_bytes().unwrap()
+        };
+        let flow = |client: &str| FlowNoSrcPort {
+            src_ip: client.parse().unwrap(),
+            dst_ip: "10.10.0.1".parse().unwrap(),
+            dst_port: 443,
+        };
+        let ingest = |payloads: Vec<Vec<u8>>| {
+            let (tx, rx) = mpsc::channel();
+            let thread = st.spawn_ingest_thread(ChannelFeed::new(rx));
+            for p in payloads {
+                tx.send(p).unwrap();
+            }
+            drop(tx);
+            thread.join().unwrap();
+        };
+
+        ingest(vec![
+            registration("192.168.0.1", None),
+            // the same session, another tenant's
+            registration("192.168.0.1", Some(7)),
+            registration("192.168.0.2", Some(7)),
+            // over tenant 7's limit
+            registration("192.168.0.3", Some(7)),
+            registration("192.168.0.1", Some(8)),
+            registration("192.168.0.4", Some(9)),
+        ]);
+        assert_eq!(st.len(), 4);
+        {
+            let rmap = st.read_sessions();
+            assert_eq!(rmap.get("192.168.0.1-10.10.0.1-443").unwrap().details.tenant, DEFAULT_TENANT);
+            assert_eq!(rmap.get("t7|192.168.0.1-10.10.0.1-443").unwrap().registrations, 1);
+            assert_eq!(rmap.tenant_len(7), 2);
         }
+        // packets find every tenant's sessions, the default station's first
+        assert_eq!(st.get_session(&flow("192.168.0.1")).unwrap().details.tenant, DEFAULT_TENANT);
+        assert_eq!(st.get_session(&flow("192.168.0.2")).unwrap().details.tenant, 7);
+        assert_eq!(st.get_session(&flow("192.168.0.4")).unwrap().details.tenant, 9);
+        assert!(st.get_session(&flow("192.168.0.3")).is_none());
 
-        for entry in &test_tuples_bad {
+        // a tenant's v6 key can't be taken for the default station's
+        let client: IpAddr = "2601::1".parse().unwrap();
+        let tenant_key = st.config().key(SessionMode::Phantom, 7, &client, &"2001::1".parse().unwrap(), 443);
+        let default_key = st.config().key(SessionMode::Phantom, DEFAULT_TENANT, &client, &"7:2001::1".parse().unwrap(), 443);
+        assert!(tenant_key.is_some() && default_key.is_some());
+        assert!(tenant_key != default_key);
+
+        // the default station's sessions can't be deleted
+        ingest(vec![delete(0)]);
+        assert_eq!(st.len(), 4);
+
+        ingest(vec![delete(7)]);
+        assert_eq!(st.len(), 2);
+        assert!(st.get_session(&flow("192.168.0.2")).is_none());
+        assert_eq!(st.take_tenant_report().unwrap(), "tenants \
+            0 sessions 1 accepted 1 over session limit 0 over rate limit 0 deleted 0, \
+            7 sessions 0 accepted 2 over session limit 1 over rate limit 0 deleted 2, \
+            9 sessions 1 accepted 1 over session limit 0 over rate limit 0 deleted 0, \
+            unknown tenant refused 1");
+
+        // without tenants every session is the default station's
+        let st = SessionTracker::new();
+        let (tx, rx) = mpsc::channel();
+        let thread = st.spawn_ingest_thread(ChannelFeed::new(rx));
+        tx.send(registration("192.168.0.1", Some(7))).unwrap();
+        tx.send(delete(7)).unwrap();
+        drop(tx);
+        thread.join().unwrap();
+        assert_eq!(st.get_session(&flow("192.168.0.1")).unwrap().details.tenant, DEFAULT_TENANT);
+        assert!(st.take_tenant_report().is_none());
+    }
+
+    #[test]
+    fn test_timeout_bounds() {
+        let secs = Duration::from_secs;
+        let clamp = TimeoutBounds { min: secs(1), max: Some(secs(3600)), reject: false };
+        assert_eq!(clamp.enforce(secs(0)).unwrap(), secs(1));
+        assert_eq!(clamp.enforce(secs(1)).unwrap(), secs(1));
+        assert_eq!(clamp.enforce(secs(120)).unwrap(), secs(120));
+        assert_eq!(clamp.enforce(secs(1_000_000_000)).unwrap(), secs(3600));
+        let reject = TimeoutBounds { reject: true, ..clamp };
+        assert_eq!(reject.enforce(secs(120)).unwrap(), secs(120));
+        match reject.enforce(secs(0)) {
+            Err(SessionError::InvalidTimeout(t)) => assert_eq!(t, secs(0)),
+            _ => panic!("expected InvalidTimeout"),
+        }
+        assert_eq!(reject.enforce(secs(0)).unwrap_err().to_string(), "Invalid timeout 0ns");
+        assert!(reject.enforce(secs(3601)).is_err());
+        // no ceiling
+        let floor = TimeoutBounds { min: secs(1), max: None, reject: false };
+        assert_eq!(floor.enforce(Duration::from_nanos(u64::MAX)).unwrap(), Duration::from_nanos(u64::MAX));
+    }
+
+    #[test]
+    fn test_session_ingest_timeout_bounds() {
+        let registration = |client: &str, phantom_v6: &str, timeout: u64| {
             let mut s2d = StationToDetector::new();
-            s2d.set_client_ip(entry.0.to_string());
-            s2d.set_phantom_ip(entry.1.to_string());
-            s2d.set_timeout_ns(entry.2);
-    
-            match SessionResult::from(&s2d) {
-                Ok(_) => {
-                    panic!("Should have failed");
-                },
-                Err(e) => {
-                    assert_eq!(format!("{}",e), format!("{}",entry.3));
-                },
+            s2d.set_client_ip(client.to_string());
+            s2d.set_phantom_ip("10.10.0.1".to_string());
+            s2d.set_phantom_ip_v6(phantom_v6.to_string());
+            s2d.set_timeout_ns(timeout);
+            s2d.write_to_bytes().unwrap()
+        };
+        let timeout_of = |st: &SessionTracker, client: &str| {
+            let f = FlowNoSrcPort{
+                src_ip: client.parse().unwrap(),
+                dst_ip: "10.10.0.1".parse().unwrap(),
+                dst_port: DEFAULT_PHANTOM_PORT,
             };
+            st.get_session(&f).map(|e| e.details.timeout_ns())
+        };
+        let bounds = TimeoutBounds { min: Duration::from_secs(1), max: Some(Duration::from_secs(3600)), reject: false };
+        let ingest = |bounds: &TimeoutBounds| {
+            let config = SessionConfig { timeout_bounds: Some(bounds.clone()), ..SessionConfig::default() };
+            let st = SessionTracker::with_config(config, Clock::simulated(0));
+            let (tx, rx) = mpsc::channel();
+            let ingest = st.spawn_ingest_thread(ChannelFeed::new(rx));
+            tx.send(registration("192.168.0.1", "2001::1", 0)).unwrap();
+            tx.send(registration("192.168.0.2", "2001::2", 60*S2NS)).unwrap();
+            tx.send(registration("192.168.0.3", "2001::3", 1_000_000_000_000_000_000)).unwrap();
+            drop(tx);
+            ingest.join().unwrap();
+            st
+        };
+
+        let st = ingest(&bounds);
+        assert_eq!(timeout_of(&st, "192.168.0.1"), Some(S2NS));
+        assert_eq!(timeout_of(&st, "192.168.0.2"), Some(60*S2NS));
+        assert_eq!(timeout_of(&st, "192.168.0.3"), Some(3600*S2NS));
+        // both sessions of a dual-stack registration are bounded
+        assert_eq!(st.len(), 6);
+        assert_eq!(st.take_timeout_report(), "raised 1 lowered 1 rejected 0");
+        assert_eq!(st.take_timeout_report(), "raised 0 lowered 0 rejected 0");
+
+        let st = ingest(&TimeoutBounds { reject: true, ..bounds });
+        assert_eq!(timeout_of(&st, "192.168.0.1"), None);
+        assert_eq!(timeout_of(&st, "192.168.0.2"), Some(60*S2NS));
+        assert_eq!(timeout_of(&st, "192.168.0.3"), None);
+        assert_eq!(st.len(), 2);
+        assert_eq!(st.take_timeout_report(), "raised 0 lowered 0 rejected 2");
+    }
+
+    #[test]
+    fn test_session_ingest_journal() {
+        let config = SessionConfig { journal_len: Some(4), ..SessionConfig::default() };
+        let st = SessionTracker::with_config(config, Clock::simulated(0));
+        let registration = |client: &str, phantom: &str| {
+            let mut s2d = StationToDetector::new();
+            s2d.set_client_ip(client.to_string());
+            s2d.set_phantom_ip(phantom.to_string());
+            s2d.set_timeout_ns(5*S2NS);
+            s2d.write_to_bytes().unwrap()
+        };
+
+        let (tx, rx) = mpsc::channel();
+        let ingest = st.spawn_ingest_thread(ChannelFeed::new(rx));
+        tx.send(registration("192.168.0.1", "10.10.0.1")).unwrap();
+        tx.send(vec![0xff; 4]).unwrap();
+        tx.send(registration("2001::2", "10.10.0.1")).unwrap();
+        tx.send(registration("192.168.0.3", "10.10.0.1")).unwrap();
+        drop(tx);
+        ingest.join().unwrap();
+        assert_eq!(st.len(), 2);
+
+        st.set_draining(true);
+        let (tx, rx) = mpsc::channel();
+        let ingest = st.spawn_ingest_thread(ChannelFeed::new(rx));
+        tx.send(registration("192.168.0.4", "10.10.0.1")).unwrap();
+        drop(tx);
+        ingest.join().unwrap();
+
+        // the first registration was pushed out
+        let outcomes: Vec<(String, Option<String>)> = st.journal().unwrap().entries(None).into_iter()
+            .map(|e| (e.client, e.rejected))
+            .collect();
+        assert_eq!(outcomes.len(), 4);
+        assert_eq!(outcomes[0].0, "");
+        assert!(outcomes[0].1.is_some());
+        assert_eq!(outcomes[1], ("2001::2".to_string(),
+            Some("invalid registration: Client/Phantom v4/v6 mismatch".to_string())));
+        assert_eq!(outcomes[2], ("192.168.0.3".to_string(), None));
+        assert_eq!(outcomes[3], ("192.168.0.4".to_string(), Some("draining".to_string())));
+    }
+
+    #[test]
+    fn test_session_ingest_config_push() {
+        let mut st = SessionTracker::new();
+        st.enable_station_config(StationConfig::new(None));
+        let registration = |client: &str, transport: TransportType| {
+            let mut s2d = StationToDetector::new();
+            s2d.set_client_ip(client.to_string());
+            s2d.set_phantom_ip("10.10.0.1".to_string());
+            s2d.set_transport(transport);
+            s2d.write_to_bytes().unwrap()
+        };
+        let mut config = StationToDetector::new();
+        {
+            let push = config.mut_config_push();
+            push.set_epoch(1);
+            push.set_default_timeout_ns(30*S2NS);
+            push.set_allowed_transports(vec![TransportType::Min]);
+            push.mut_client_blocklist().push("192.0.2.0/24".to_string());
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let ingest = st.spawn_ingest_thread(ChannelFeed::new(rx));
+        tx.send(config.write_to_bytes().unwrap()).unwrap();
+        tx.send(registration("192.168.0.1", TransportType::Min)).unwrap();
+        tx.send(registration("192.168.0.2", TransportType::Obfs4)).unwrap();
+        tx.send(registration("192.0.2.1", TransportType::Min)).unwrap();
+        drop(tx);
+        ingest.join().unwrap();
+
+        // only the first registration is accepted, with the pushed lifetime
+        assert_eq!(st.len(), 1);
+        let f = FlowNoSrcPort{
+            src_ip: "192.168.0.1".parse().unwrap(),
+            dst_ip: "10.10.0.1".parse().unwrap(),
+            dst_port: DEFAULT_PHANTOM_PORT,
+        };
+        assert_eq!(st.get_session(&f).unwrap().details.timeout_ns(), 30*S2NS);
+        assert_eq!(st.station_config().unwrap().take_report(),
+            "station config epoch 1 rejected pushes 0 refused registrations 2");
+
+        // once phantom subnets are pushed, registrations for phantoms
+        // outside them are refused
+        let mut subnets = StationToDetector::new();
+        subnets.mut_phantom_subnets().set_generation(1);
+        subnets.mut_phantom_subnets().mut_subnets().push("10.20.0.0/16".to_string());
+        let registration_to = |phantom: &str| {
+            let mut s2d = StationToDetector::new();
+            s2d.set_client_ip("192.168.0.5".to_string());
+            s2d.set_phantom_ip(phantom.to_string());
+            s2d.set_transport(TransportType::Min);
+            s2d.write_to_bytes().unwrap()
+        };
+        let (tx, rx) = mpsc::channel();
+        let ingest = st.spawn_ingest_thread(ChannelFeed::new(rx));
+        tx.send(registration_to("10.10.0.5")).unwrap();
+        tx.send(subnets.write_to_bytes().unwrap()).unwrap();
+        tx.send(registration_to("10.10.0.6")).unwrap();
+        tx.send(registration_to("10.20.0.7")).unwrap();
+        drop(tx);
+        ingest.join().unwrap();
+        assert_eq!(st.len(), 3);
+        assert_eq!(st.station_config().unwrap().take_subnets_report(),
+            "phantom subnets generation 1 subnets 1 rejected pushes 0 refused registrations 1");
+
+        // without pushes enabled they're ignored
+        let st = SessionTracker::new();
+        let (tx, rx) = mpsc::channel();
+        let ingest = st.spawn_ingest_thread(ChannelFeed::new(rx));
+        tx.send(config.write_to_bytes().unwrap()).unwrap();
+        tx.send(registration("192.0.2.1", TransportType::Obfs4)).unwrap();
+        drop(tx);
+        ingest.join().unwrap();
+        assert_eq!(st.len(), 1);
+    }
+
+    #[test]
+    fn test_session_ingest_queue() {
+        let config = SessionConfig { insert_queue: Some(InsertQueueConfig::default()), ..SessionConfig::default() };
+        let st = SessionTracker::with_config(config, Clock::Monotonic);
+        let (tx, rx) = mpsc::channel();
+        let ingest = st.spawn_ingest_thread(ChannelFeed::new(rx));
+        for i in 0..20 {
+            let mut s2d = StationToDetector::new();
+            s2d.set_client_ip(format!("192.168.0.{}", i));
+            s2d.set_phantom_ip("10.10.0.1".to_string());
+            s2d.set_phantom_ip_v6("2001::1234".to_string());
+            s2d.set_timeout_ns(5*S2NS);
+            tx.send(s2d.write_to_bytes().unwrap()).unwrap();
+        }
+        drop(tx);
+        ingest.join().unwrap();
+
+        // applied by the queue's own thread
+        for _ in 0..100 {
+            if st.insert_queue().unwrap().depth() == 0 {
+                break
+            }
+            thread::sleep(time::Duration::from_millis(10));
         }
+        assert_eq!(st.len(), 21);
+        assert!(st.insert_queue().unwrap().take_report().contains(" registrations 20 "));
     }
 
     #[test]
-    fn test_session_tracker_basics() {
+    fn test_session_ingest_pool() {
+        let config = SessionConfig {
+            ingest_pool: Some(IngestPoolConfig { workers: 3, capacity: 4 }),
+            tenants: Some("7".parse().unwrap()),
+            ..SessionConfig::default()
+        };
+        let st = SessionTracker::with_config(config, Clock::Monotonic);
+        let (tx, rx) = mpsc::channel();
+        let ingest = st.spawn_ingest_thread(ChannelFeed::new(rx));
+        let registration = |i: usize, sequence: u64| {
+            let mut s2d = StationToDetector::new();
+            s2d.set_client_ip(format!("192.168.0.{}", i));
+            s2d.set_phantom_ip("10.10.0.1".to_string());
+            s2d.set_timeout_ns(5*S2NS);
+            s2d.set_tenant_id(7);
+            s2d.set_publisher_id(1);
+            s2d.set_sequence(sequence);
+            s2d.write_to_bytes().unwrap()
+        };
+        for i in 0..20 {
+            tx.send(registration(i, i as u64 + 1)).unwrap();
+        }
+        // applied once everything before it is, and before anything after it
+        let mut delete = StationToDetector::new();
+        delete.set_tenant_id(7);
+        delete.set_delete_tenant_sessions(true);
+        tx.send(delete.write_to_bytes().unwrap()).unwrap();
+        for i in 20..25 {
+            tx.send(registration(i, i as u64 + 1)).unwrap();
+        }
+        drop(tx);
+        ingest.join().unwrap();
+
+        assert_eq!(st.len(), 5);
+        assert_eq!(st.read_sessions().tenant_len(7), 5);
+        assert_eq!(st.sequences().take_counts()[0].1.missing, 0);
+        let report = st.ingest_pool().unwrap().take_report();
+        assert!(report.ends_with(" barriers 1"), "{}", report);
+    }
+
+    #[test]
+    fn test_session_ingest_pool_limit() {
+        // workers and the queue applying registrations concurrently don't
+        // take a tenant past its limit
+        let config = SessionConfig {
+            ingest_pool: Some(IngestPoolConfig { workers: 3, capacity: 4 }),
+            insert_queue: Some(InsertQueueConfig { capacity: 8, max_batch: 4 }),
+            tenants: Some("7:max=12".parse().unwrap()),
+            ..SessionConfig::default()
+        };
+        let st = SessionTracker::with_config(config, Clock::Monotonic);
+        let (tx, rx) = mpsc::channel();
+        let ingest = st.spawn_ingest_thread(ChannelFeed::new(rx));
+        for i in 0..40 {
+            let mut s2d = StationToDetector::new();
+            s2d.set_client_ip(format!("192.168.0.{}", i % 30));
+            s2d.set_phantom_ip("10.10.0.1".to_string());
+            s2d.set_timeout_ns(5*S2NS);
+            s2d.set_tenant_id(7);
+            tx.send(s2d.write_to_bytes().unwrap()).unwrap();
+        }
+        drop(tx);
+        ingest.join().unwrap();
+        for _ in 0..100 {
+            if st.insert_queue().unwrap().depth() == 0 {
+                break
+            }
+            thread::sleep(time::Duration::from_millis(10));
+        }
+        assert_eq!(st.read_sessions().tenant_len(7), 12);
+    }
+
+    #[test]
+    fn test_session_ingest_error_storm() {
+        let clock = Clock::simulated(0);
+        let config = SessionConfig {
+            ingest_errors: IngestErrorConfig {
+                breaker_rate: Some(3),
+                slow_poll: time::Duration::from_millis(0),
+                ..IngestErrorConfig::default()
+            },
+            ..SessionConfig::default()
+        };
+        let mut st = SessionTracker::with_config(config, clock.clone());
+        let mut s2d = StationToDetector::new();
+        s2d.set_client_ip("192.168.0.1".to_string());
+        s2d.set_phantom_ip("10.10.0.1".to_string());
+        s2d.set_timeout_ns(60*S2NS);
+        let good = s2d.write_to_bytes().unwrap();
+        s2d.set_client_ip("2001::1234".to_string());
+        let invalid = s2d.write_to_bytes().unwrap();
+
+        for _ in 0..3 {
+            assert!(!ingest_registration(&mut st, &[0xff, 0xff, 0xff], PayloadFormat::Protobuf, false));
+        }
+        assert!(!ingest_registration(&mut st, &invalid, PayloadFormat::Protobuf, false));
+        // tripped: the feed is slowed down until the cooldown is over, but
+        // good registrations are still applied
+        assert!(ingest_registration(&mut st, &good, PayloadFormat::Protobuf, false));
+        assert_eq!(st.len(), 1);
+        assert_eq!(st.ingest_errors().take_report(),
+            "ingest errors parse 3 invalid 1 suppressed logs 0 slowed 1 breaker trips 1 (open)");
 
+        clock.advance(10*S2NS);
+        assert!(ingest_registration(&mut st, &good, PayloadFormat::Protobuf, false));
+        assert!(st.ingest_errors().take_report().ends_with(" slowed 0 breaker trips 0"));
+    }
+
+    #[test]
+    fn test_session_replication() {
+        let (pub_tx, pub_rx) = mpsc::sync_channel(16);
+        let mut st = SessionTracker::new();
+        st.enable_replication(Replication::new(1, &["peer".to_string()],
+            Some(EventPublisher::from_sender(pub_tx))));
+
+        // registration from our station is tracked and published
+        let (tx, rx) = mpsc::channel();
+        let ingest = st.spawn_ingest_thread(ChannelFeed::new(rx));
+        let mut s2d = StationToDetector::new();
+        s2d.set_client_ip("192.168.0.1".to_string());
+        s2d.set_phantom_ip("10.10.0.1".to_string());
+        s2d.set_phantom_ip_v6("2001::1234".to_string());
+        s2d.set_timeout_ns(5*S2NS);
+        tx.send(s2d.write_to_bytes().unwrap()).unwrap();
+        drop(tx);
+        ingest.join().unwrap();
+        assert_eq!(st.len(), 2);
+        let published = pub_rx.try_recv().unwrap();
+
+        // a peer's session is tracked but not published again, and our own
+        // message echoed back by a peer is ignored
+        let peer_sd = SessionDetails::new("172.16.0.1", "10.10.0.2", 443, Duration::from_secs(5)).unwrap();
+        let mut msg = SessionReplication::new();
+        msg.set_origin(2);
+        msg.mut_sessions().push(ReplicatedSession::from(&peer_sd));
+
+        let (tx, rx) = mpsc::channel();
+        let peer = st.spawn_peer_thread(0, ChannelFeed::new(rx));
+        tx.send(msg.write_to_bytes().unwrap()).unwrap();
+        tx.send(published).unwrap();
+        drop(tx);
+        peer.join().unwrap();
+
+        assert_eq!(st.len(), 3);
+        assert!(st.is_tracked_session(&FlowNoSrcPort{
+            src_ip: "172.16.0.1".parse().unwrap(),
+            dst_ip: "10.10.0.2".parse().unwrap(),
+            dst_port: 443,
+        }));
+        assert!(pub_rx.try_recv().is_err());
+        assert_eq!(st.replication().unwrap().take_report()[1],
+            "replication peer peer received 2 accepted 1 looped 1 invalid 0");
+
+        // our station's update is published flagged, a peer's refreshes only
+        // the sessions we track
+        let update = Registration {
+            command: Command::Update,
+            client_ip: "192.168.0.1".to_string(),
+            phantom_ip: "10.10.0.1".to_string(),
+            phantom_port: 443,
+            timeout: Some(Duration::from_secs(50)),
+            ..Registration::default()
+        };
+        assert!(apply_registration_message(&mut st, &update, false));
+        let published: SessionReplication = Message::parse_from_bytes(&pub_rx.try_recv().unwrap()).unwrap();
+        assert!(published.get_update());
+
+        msg.set_update(true);
+        msg.mut_sessions().push(ReplicatedSession::from(&SessionDetails::new("172.16.0.9", "10.10.0.2", 443, Duration::from_secs(5)).unwrap()));
+        let (tx, rx) = mpsc::channel();
+        let peer = st.spawn_peer_thread(0, ChannelFeed::new(rx));
+        tx.send(msg.write_to_bytes().unwrap()).unwrap();
+        drop(tx);
+        peer.join().unwrap();
+        assert_eq!(st.len(), 3);
+        assert!(pub_rx.try_recv().is_err());
+        assert_eq!(st.take_expiry_change_report(),
+            "activity extensions 0 station updates 2 (capped 0 untracked 1)");
+    }
+
+    #[test]
+    fn test_session_drain_and_handoff() {
+        let clock = Clock::simulated(0);
+        let mut st = SessionTracker::with_clock(clock.clone());
+        let s2d_for = |client: &str| {
+            let mut s2d = StationToDetector::new();
+            s2d.set_client_ip(client.to_string());
+            s2d.set_phantom_ip("10.10.0.1".to_string());
+            s2d.set_timeout_ns(10*S2NS);
+            s2d.write_to_bytes().unwrap()
+        };
+
+        let (tx, rx) = mpsc::channel();
+        let ingest = st.spawn_ingest_thread(ChannelFeed::new(rx));
+        tx.send(s2d_for("192.168.0.1")).unwrap();
+        tx.send(s2d_for("192.168.0.2")).unwrap();
+        while st.len() < 2 {
+            thread::sleep(time::Duration::from_millis(1));
+        }
+
+        // new registrations are ignored while draining
+        st.set_draining(true);
+        tx.send(s2d_for("192.168.0.3")).unwrap();
+        drop(tx);
+        ingest.join().unwrap();
+        assert_eq!(st.len(), 2);
+        assert_eq!(st.take_drained(), 1);
+
+        // exported sessions carry their remaining lifetime
+        clock.advance(4*S2NS);
+        let exported = st.export();
+        assert_eq!(exported.len(), 2);
+        assert!(exported.iter().all(|sd| sd.timeout_ns() == 6*S2NS));
+
+        // activity still extends them
+        st.update_session(&FlowNoSrcPort {
+            src_ip: "192.168.0.1".parse().unwrap(),
+            dst_ip: "10.10.0.1".parse().unwrap(),
+            dst_port: 443,
+        });
+        assert_eq!(st.export().iter().filter(|sd| sd.timeout_ns() > 6*S2NS).count(), 1);
+
+        // and are imported by the standby even if it is draining too
+        let standby_clock = Clock::simulated(100*S2NS);
+        let mut standby = SessionTracker::with_clock(standby_clock.clone());
+        standby.set_draining(true);
+        let (tx, rx) = mpsc::channel();
+        let import = standby.spawn_handoff_thread(ChannelFeed::new(rx));
+        for msg in handoff_messages(0, &exported) {
+            tx.send(msg.write_to_bytes().unwrap()).unwrap();
+        }
+        drop(tx);
+        import.join().unwrap();
+        assert_eq!(standby.len(), 2);
+
+        standby_clock.advance(6*S2NS - 1);
+        assert_eq!(standby.drop_stale_sessions(), 0);
+        standby_clock.advance(1);
+        assert_eq!(standby.drop_stale_sessions(), 2);
+    }
+
+    #[test]
+    fn test_session_registration_source() {
         let mut st = SessionTracker::new();
 
         let test_tuples = [
-            // (client_ip, phantom_ip, timeout)
-            ("192.168.0.1", "10.10.0.1", 443, 100000),
-            ("192.168.0.1", "192.0.0.127", 443, 100000),     // duplicate client_addr
-            ("2601::123:abcd", "2001::1234", 443, 100000),
-            ("", "2001::1234", 443, 100000),                 // duplicate phantom Addr
-            ("172.128.0.2", "8.0.0.1", 443, 1),              // timeout immediately
-            
-            // client registering with v4 will also create registrations for v6 just in-case
-            ("192.168.0.1", "2801::1234", 100000, 443),
+            // (client_ip, phantom_ip, registration_source)
+            ("192.168.0.1", "10.10.0.1", Some(RegistrationSource::Detector)),
+            ("192.168.0.1", "192.0.0.127", Some(RegistrationSource::API)),
+            ("", "2001::1234", Some(RegistrationSource::API)),
+            ("172.128.0.2", "8.0.0.1", None),
+
+            // duplicate keeps the source of the first registration
+            ("192.168.0.1", "10.10.0.1", Some(RegistrationSource::DetectorPrescan)),
         ];
 
         for entry in &test_tuples {
-            let s1 = SessionDetails::new(entry.0, entry.1, entry.2, entry.3).unwrap();
-            st.insert_session(s1);
+            let mut s2d = StationToDetector::new();
+            s2d.set_client_ip(entry.0.to_string());
+            s2d.set_phantom_ip(entry.1.to_string());
+            s2d.set_phantom_port(443);
+            s2d.set_timeout_ns(5*S2NS);
+            if let Some(src) = entry.2 {
+                s2d.set_registration_source(src);
+            }
+
+            let sd = SessionResult::from(&s2d).unwrap();
+            assert_eq!(sd.registration_source, entry.2.unwrap_or(RegistrationSource::Unspecified));
+            st.insert_session(sd);
         }
 
-        if st.len() != 5 {
-            panic!("Either len is not working or insert is broken")
-        };
+        assert_eq!(st.count_by_source(), vec![
+            (RegistrationSource::Unspecified, 1),
+            (RegistrationSource::Detector, 1),
+            (RegistrationSource::API, 2),
+            (RegistrationSource::DetectorPrescan, 0),
+        ]);
 
-        for entry in &test_tuples {
-            let src = match entry.0 {
-                "" => "::1".parse().unwrap(),
-                _ => entry.0.parse().unwrap(),
-            };
+        let dump = st.dump();
+        assert_eq!(dump.len(), 4);
+        assert!(dump.iter().any(|l| l.contains("-> 10.10.0.1:443") && l.contains("Detector)")));
+    }
+
+    #[test]
+    fn test_session_dual_stack() {
+        let mut st = SessionTracker::new();
+
+        let mut s2d = StationToDetector::new();
+        s2d.set_client_ip("192.168.0.1".to_string());
+        s2d.set_phantom_ip("10.10.0.1".to_string());
+        s2d.set_phantom_ip_v6("2801::1234".to_string());
+        s2d.set_phantom_port(443);
+        s2d.set_timeout_ns(5*S2NS);
+        s2d.set_registration_source(RegistrationSource::API);
+
+        let sds = sessions_from(&s2d).unwrap();
+        assert_eq!(sds.len(), 2);
+        assert_eq!(sds[1].phantom_ip.to_string(), "2801::1234");
+        assert_eq!(sds[1].registration_source, RegistrationSource::API);
+        st.insert_sessions(&sds);
+
+        for phantom in &["10.10.0.1", "2801::1234"] {
             let f = &FlowNoSrcPort{
-                src_ip: src,
-                dst_ip: entry.1.parse().unwrap(), 
+                src_ip: "192.168.0.1".parse().unwrap(),
+                dst_ip: phantom.parse().unwrap(),
                 dst_port: DEFAULT_PHANTOM_PORT,
             };
-            if !st.is_tracked_session(f) {
-                panic!("Session should be tracked")
-            }
+            assert!(st.is_tracked_session(f));
         }
 
-        let tt = test_tuples[0];
-        let sd = SessionDetails::new(tt.0, tt.1, tt.2, tt.3).unwrap();
-        st._delete_session(sd);
+        // Both sessions share the same lifetime.
+        let map = st.tracked_sessions.read().unwrap();
+        let expire_times: Vec<MonotonicNs> = map.values().map(|e| e.expire_time).collect();
+        assert_eq!(expire_times.len(), 2);
+        assert_eq!(expire_times[0], expire_times[1]);
+        drop(map);
+        // one registration, counted once
+        assert_eq!(st.count_by_source()[2], (RegistrationSource::API, 1));
 
+        // A bad v6 phantom rejects the whole registration.
+        s2d.set_phantom_ip_v6("abcd::123::wrong".to_string());
+        match sessions_from(&s2d) {
+            Ok(_) => panic!("Should have failed"),
+            Err(e) => assert_eq!(format!("{}", e), format!("{}", SessionError::InvalidPhantom)),
+        };
 
-        if st.len() != 4 {
-            panic!("Either len is not working or delete is broken")
+        // Without a v6 phantom only the v4 session is produced.
+        s2d.clear_phantom_ip_v6();
+        assert_eq!(sessions_from(&s2d).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_session_dual_stack_group() {
+        let clock = Clock::simulated(0);
+        let mut st = SessionTracker::with_clock(clock.clone());
+        let mut s2d = StationToDetector::new();
+        s2d.set_client_ip("192.168.0.1".to_string());
+        s2d.set_phantom_ip("10.10.0.1".to_string());
+        s2d.set_phantom_ip_v6("2801::1234".to_string());
+        s2d.set_phantom_port(443);
+        s2d.set_timeout_ns(5*S2NS);
+        let sds = sessions_from(&s2d).unwrap();
+        let flow = |client: &str, phantom: &str| FlowNoSrcPort {
+            src_ip: client.parse().unwrap(),
+            dst_ip: phantom.parse().unwrap(),
+            dst_port: DEFAULT_PHANTOM_PORT,
         };
+        let v4 = flow("192.168.0.1", "10.10.0.1");
+        let v6 = flow("2001::9", "2801::1234");
+        st.insert_sessions(&sds);
+        assert!(st.dump().iter().all(|l| l.contains(&format!("group {:016x}", sds[0].group.unwrap()))));
+
+        // the first connection to either matches both
+        assert!(st.latch_match(&v6).is_some());
+        assert!(st.latch_match(&v4).is_none());
+        assert!(st.get_session(&v4).unwrap().matched_at.is_some());
+
+        // timeouts stay per key: the v4 session expires first, into the v6
+        assert!(st.open_connection(&v4));
+        st.close_connection_with(&v4, 4, 400);
+        st.update_session(&v6);
+        clock.advance(5*S2NS);
+        assert_eq!(st.drop_stale_sessions(), 1);
+        assert!(st.expiry_stats().take_expired_matched().0.is_empty());
+        assert!(st.expiry_stats().take_never_matched().is_empty());
+        let entry = st.get_session(&v6).unwrap();
+        assert_eq!((entry.connections, entry.packets, entry.bytes), (1, 4, 400));
+
+        // and the group is reported once the v6 one expires too
+        clock.advance(3600*S2NS);
+        assert_eq!(st.drop_stale_sessions(), 1);
+        let (expired, _) = st.expiry_stats().take_expired_matched();
+        assert_eq!(expired.len(), 1);
+        assert_eq!((expired[0].details.group, expired[0].packets), (sds[0].group, 4));
+        assert!(st.expiry_stats().take_report().ends_with("expired matched 1 unmatched 0"));
+
+        // a group that never matched counts once, expiring together
+        st.insert_sessions(&sds);
+        clock.advance(5*S2NS);
+        assert_eq!(st.drop_stale_sessions(), 2);
+        assert_eq!(st.expiry_stats().take_never_matched().values().sum::<u64>(), 1);
+
+        // deleting either session deletes the registration
+        st.insert_sessions(&sds);
+        let v6_only = SessionDetails::new("", "2801::1234", 443, Duration::from_secs(5)).unwrap();
+        assert_eq!(st.remove_registration(&v6_only), Some(0));
+        assert_eq!(st.len(), 0);
+        st.insert_sessions(&sds);
+        st.insert_sessions(&sds);
+        assert_eq!(st.remove_registration(&sds[0]), Some(1));
+        assert_eq!(st.len(), 2);
     }
 
     #[test]
-    fn test_session_tracker_timeouts() {
+    fn test_session_tracker_snapshot() {
         let mut st = SessionTracker::new();
 
+        let before = SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_secs(5)).unwrap();
+        st.insert_session(before);
+        st.enable_snapshot(time::Duration::from_millis(1));
+
         let test_tuples = [
-            // (client_ip, phantom_ip, phantom_port, timeout)
-            ("172.128.0.2", "8.0.0.1", 443, 1, false),            // timeout immediately
-            ("192.168.0.1", "10.10.0.1", 443, 5*S2NS, true),
-            ("192.168.0.1", "192.0.0.127", 443, 5*S2NS, true),    
- 
-            // client registering with v4 will also create registrations for v6 just in-case
-             ("192.168.0.1", "2801::1234", 443, 5*S2NS, true),
-            
-            // duplicate with shorter timeout should not drop
-            ("2601::123:abcd", "2001::1234", 443, 5*S2NS, true),
-            ("::1", "2001::1234", 443, 1*S2NS, true),
-            
-            // duplicate with long timeout should prevent drop
-            ("7.0.0.2", "8.8.8.8", 443, 1, true),
-            ("7.0.0.2", "8.8.8.8", 443, 5*S2NS, true),
+            // (client_ip, phantom_ip, timeout)
+            ("192.168.0.1", "192.0.0.127", 5*S2NS),
+            ("", "2001::1234", 5*S2NS),
+            ("172.128.0.2", "8.0.0.1", 1),            // timeout immediately
         ];
-    
         for entry in &test_tuples {
-            let s1 = SessionDetails::new(entry.0, entry.1, entry.2, entry.3).unwrap();
+            let s1 = SessionDetails::new(entry.0, entry.1, 443, Duration::from_nanos(entry.2)).unwrap();
             st.insert_session(s1);
         }
 
-        let dur = time::Duration::new(3, 0);
-        thread::sleep(dur);
+        let flow = |src: &str, dst: &str| FlowNoSrcPort{
+            src_ip: src.parse().unwrap(),
+            dst_ip: dst.parse().unwrap(),
+            dst_port: DEFAULT_PHANTOM_PORT,
+        };
+
+        thread::sleep(time::Duration::from_millis(100));
+        assert!(st.is_tracked_session(&flow("192.168.0.1", "10.10.0.1")));
+        assert!(st.is_tracked_session(&flow("192.168.0.1", "192.0.0.127")));
+        assert!(st.is_tracked_session(&flow("::1", "2001::1234")));
+        assert!(st.is_tracked_session(&flow("172.128.0.2", "8.0.0.1")));
 
         assert_eq!(st.drop_stale_sessions(), 1);
+        thread::sleep(time::Duration::from_millis(100));
+        assert!(!st.is_tracked_session(&flow("172.128.0.2", "8.0.0.1")));
+        assert!(st.is_tracked_session(&flow("192.168.0.1", "10.10.0.1")));
+    }
 
-        for entry in &test_tuples {
-            let f = &FlowNoSrcPort{
-                src_ip: entry.0.parse().unwrap(),
-                dst_ip: entry.1.parse().unwrap(), 
-                dst_port: DEFAULT_PHANTOM_PORT,
-            };
-            assert_eq!(st.is_tracked_session(f), entry.3)
+    #[test]
+    fn test_session_client_ports() {
+        let flow = |src: &str, src_port: u16, dst: &str| Flow {
+            src_ip: src.parse().unwrap(),
+            dst_ip: dst.parse().unwrap(),
+            src_port: src_port,
+            dst_port: 443,
+        };
+        for &snapshot in [false, true].iter() {
+            let mut st = SessionTracker::new();
+            // keyed on the phantom only, any client's flow to it would match
+            let mut sd = SessionDetails::new("", "2001::1234", 443, Duration::from_secs(5)).unwrap();
+            sd.client_ports = Some(ClientPorts::new(40000, 40999).unwrap());
+            st.insert_session(sd);
+            st.insert_session(SessionDetails::new("", "2001::5678", 443, Duration::from_secs(5)).unwrap());
+            if snapshot {
+                st.enable_snapshot(time::Duration::from_millis(1));
+            }
+
+            assert!(st.is_tracked_flow(&flow("2601::1", 40000, "2001::1234")));
+            assert!(st.is_tracked_flow(&flow("2601::2", 40999, "2001::1234")));
+            assert!(!st.is_tracked_flow(&flow("2601::3", 39999, "2001::1234")));
+            assert!(!st.is_tracked_flow(&flow("2601::3", 50000, "2001::1234")));
+            // other lookups, and sessions without client ports, take any port
+            assert!(st.is_tracked_session(&FlowNoSrcPort::from_flow(&flow("2601::3", 50000, "2001::1234"))));
+            assert!(st.is_tracked_flow(&flow("2601::3", 50000, "2001::5678")));
+            // untracked flows aren't refusals
+            assert!(!st.is_tracked_flow(&flow("2601::3", 50000, "2001::9")));
+            assert_eq!(st.take_client_port_refusals(), 2);
+            assert_eq!(st.dump().iter().filter(|l| l.ends_with("client ports 40000-40999")).count(), 1);
         }
 
-        thread::sleep(dur);
-        
-        assert_eq!(st.drop_stale_sessions(), 5);
+        // another registration for the session widens its ports to cover
+        // both, and one taking any port lets it take any
+        let mut st = SessionTracker::new();
+        let mut sd = SessionDetails::new("", "2001::1234", 443, Duration::from_secs(5)).unwrap();
+        sd.client_ports = Some(ClientPorts::new(40000, 40999).unwrap());
+        st.insert_session(sd);
+        sd.client_ports = Some(ClientPorts::new(42000, 42999).unwrap());
+        st.insert_session(sd);
+        assert!(st.is_tracked_flow(&flow("2601::1", 40000, "2001::1234")));
+        assert!(st.is_tracked_flow(&flow("2601::1", 42999, "2001::1234")));
+        assert!(!st.is_tracked_flow(&flow("2601::1", 43000, "2001::1234")));
+        assert_eq!(st.dump().iter().filter(|l| l.ends_with("client ports 40000-42999")).count(), 1);
+        sd.client_ports = None;
+        st.insert_session(sd);
+        assert!(st.is_tracked_flow(&flow("2601::1", 50000, "2001::1234")));
+    }
+
+    #[test]
+    fn test_session_conntrack_teardown() {
+        let clock = Clock::simulated(0);
+        let mut st = SessionTracker::with_clock(clock.clone());
+        let (tx, rx) = mpsc::sync_channel(4);
+        st.enable_conntrack_teardown(Teardown::from_sender(tx));
+
+        for phantom in ["10.10.0.1", "10.10.0.2", "10.10.0.3"].iter() {
+            st.insert_session(SessionDetails::new("192.168.0.1", phantom, 443, Duration::from_secs(1)).unwrap());
+        }
+        let flow = |dst: &str| FlowNoSrcPort{
+            src_ip: "192.168.0.1".parse().unwrap(),
+            dst_ip: dst.parse().unwrap(),
+            dst_port: DEFAULT_PHANTOM_PORT,
+        };
+        assert!(st.open_connection(&flow("10.10.0.1")));
+        assert!(st.open_connection(&flow("10.10.0.2")));
+        st.close_connection(&flow("10.10.0.2"));
+        assert!(st.open_connection(&flow("10.10.0.3")));
+        clock.advance(S2NS);
+
+        // only sessions that still have connections are torn down
+        assert_eq!(st.drop_stale_sessions_bounded(Some(3)), 3);
+        let mut torn: Vec<String> = rx.try_recv().unwrap().iter().map(|s| s.phantom_ip.to_string()).collect();
+        torn.sort();
+        assert_eq!(torn, vec!["10.10.0.1", "10.10.0.3"]);
+
+        st.insert_session(SessionDetails::new("192.168.0.1", "10.10.0.4", 443, Duration::from_secs(1)).unwrap());
+        assert!(st.open_connection(&flow("10.10.0.4")));
+        clock.advance(S2NS);
+        assert_eq!(st.drop_stale_sessions(), 1);
+        assert_eq!(rx.try_recv().unwrap()[0].phantom_ip.to_string(), "10.10.0.4");
+
+        // nothing is queued for passes without connected sessions
+        st.insert_session(SessionDetails::new("192.168.0.1", "10.10.0.5", 443, Duration::from_secs(1)).unwrap());
+        clock.advance(S2NS);
+        assert_eq!(st.drop_stale_sessions(), 1);
+        assert!(rx.try_recv().is_err());
+        assert_eq!(st.conntrack_teardown().unwrap().take_report(),
+            "conntrack teardown sessions 0 flushed 0 failed 0 dropped 0");
     }
 }
\ No newline at end of file
@@ -2288,7 +2288,7 @@ pub struct ClientToStation {
     v6_support: ::std::option::Option<bool>,
     v4_support: ::std::option::Option<bool>,
     pub flags: ::protobuf::SingularPtrField<RegistrationFlags>,
-    decoy_port: ::std::option::Option<u32>,
+    phantom_port: ::std::option::Option<u32>,
     padding: ::protobuf::SingularField<::std::vec::Vec<u8>>,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
@@ -2602,23 +2602,23 @@ impl ClientToStation {
         self.flags.take().unwrap_or_else(|| RegistrationFlags::new())
     }
 
-    // optional uint32 decoy_port = 25;
+    // optional uint32 phantom_port = 25;
 
 
-    pub fn get_decoy_port(&self) -> u32 {
-        self.decoy_port.unwrap_or(0)
+    pub fn get_phantom_port(&self) -> u32 {
+        self.phantom_port.unwrap_or(0)
     }
-    pub fn clear_decoy_port(&mut self) {
-        self.decoy_port = ::std::option::Option::None;
+    pub fn clear_phantom_port(&mut self) {
+        self.phantom_port = ::std::option::Option::None;
     }
 
-    pub fn has_decoy_port(&self) -> bool {
-        self.decoy_port.is_some()
+    pub fn has_phantom_port(&self) -> bool {
+        self.phantom_port.is_some()
     }
 
     // Param is passed by value, moved
-    pub fn set_decoy_port(&mut self, v: u32) {
-        self.decoy_port = ::std::option::Option::Some(v);
+    pub fn set_phantom_port(&mut self, v: u32) {
+        self.phantom_port = ::std::option::Option::Some(v);
     }
 
     // optional bytes padding = 100;
@@ -2738,7 +2738,7 @@ impl ::protobuf::Message for ClientToStation {
                         return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
                     }
                     let tmp = is.read_uint32()?;
-                    self.decoy_port = ::std::option::Option::Some(tmp);
+                    self.phantom_port = ::std::option::Option::Some(tmp);
                 },
                 100 => {
                     ::protobuf::rt::read_singular_bytes_into(wire_type, is, &mut self.padding)?;
@@ -2793,7 +2793,7 @@ impl ::protobuf::Message for ClientToStation {
             let len = v.compute_size();
             my_size += 2 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
         }
-        if let Some(v) = self.decoy_port {
+        if let Some(v) = self.phantom_port {
             my_size += ::protobuf::rt::value_size(25, v, ::protobuf::wire_format::WireTypeVarint);
         }
         if let Some(ref v) = self.padding.as_ref() {
@@ -2845,7 +2845,7 @@ impl ::protobuf::Message for ClientToStation {
             os.write_raw_varint32(v.get_cached_size())?;
             v.write_to_with_cached_sizes(os)?;
         }
-        if let Some(v) = self.decoy_port {
+        if let Some(v) = self.phantom_port {
             os.write_uint32(25, v)?;
         }
         if let Some(ref v) = self.padding.as_ref() {
@@ -2950,9 +2950,9 @@ impl ::protobuf::Message for ClientToStation {
                 |m: &mut ClientToStation| { &mut m.flags },
             ));
             fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
-                "decoy_port",
-                |m: &ClientToStation| { &m.decoy_port },
-                |m: &mut ClientToStation| { &mut m.decoy_port },
+                "phantom_port",
+                |m: &ClientToStation| { &m.phantom_port },
+                |m: &mut ClientToStation| { &mut m.phantom_port },
             ));
             fields.push(::protobuf::reflect::accessor::make_singular_field_accessor::<_, ::protobuf::types::ProtobufTypeBytes>(
                 "padding",
@@ -2987,7 +2987,7 @@ impl ::protobuf::Clear for ClientToStation {
         self.v6_support = ::std::option::Option::None;
         self.v4_support = ::std::option::Option::None;
         self.flags.clear();
-        self.decoy_port = ::std::option::Option::None;
+        self.phantom_port = ::std::option::Option::None;
         self.padding.clear();
         self.unknown_fields.clear();
     }
@@ -3683,140 +3683,144 @@ impl ::protobuf::reflect::ProtobufValue for SessionStats {
 }
 
 #[derive(PartialEq,Clone,Default)]
-pub struct StationToDetector {
+pub struct ConfigPush {
     // message fields
-    phantom_ip: ::protobuf::SingularField<::std::string::String>,
-    client_ip: ::protobuf::SingularField<::std::string::String>,
-    timeout_ns: ::std::option::Option<u64>,
-    phantom_port: ::std::option::Option<u32>,
+    epoch: ::std::option::Option<u64>,
+    default_timeout_ns: ::std::option::Option<u64>,
+    pub allowed_transports: ::std::vec::Vec<TransportType>,
+    pub client_blocklist: ::protobuf::RepeatedField<::std::string::String>,
+    pub phantom_blocklist: ::protobuf::RepeatedField<::std::string::String>,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a StationToDetector {
-    fn default() -> &'a StationToDetector {
-        <StationToDetector as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a ConfigPush {
+    fn default() -> &'a ConfigPush {
+        <ConfigPush as ::protobuf::Message>::default_instance()
     }
 }
 
-impl StationToDetector {
-    pub fn new() -> StationToDetector {
+impl ConfigPush {
+    pub fn new() -> ConfigPush {
         ::std::default::Default::default()
     }
 
-    // optional string phantom_ip = 1;
+    // optional uint64 epoch = 1;
 
 
-    pub fn get_phantom_ip(&self) -> &str {
-        match self.phantom_ip.as_ref() {
-            Some(v) => &v,
-            None => "",
-        }
+    pub fn get_epoch(&self) -> u64 {
+        self.epoch.unwrap_or(0)
     }
-    pub fn clear_phantom_ip(&mut self) {
-        self.phantom_ip.clear();
+    pub fn clear_epoch(&mut self) {
+        self.epoch = ::std::option::Option::None;
     }
 
-    pub fn has_phantom_ip(&self) -> bool {
-        self.phantom_ip.is_some()
+    pub fn has_epoch(&self) -> bool {
+        self.epoch.is_some()
     }
 
     // Param is passed by value, moved
-    pub fn set_phantom_ip(&mut self, v: ::std::string::String) {
-        self.phantom_ip = ::protobuf::SingularField::some(v);
+    pub fn set_epoch(&mut self, v: u64) {
+        self.epoch = ::std::option::Option::Some(v);
     }
 
-    // Mutable pointer to the field.
-    // If field is not initialized, it is initialized with default value first.
-    pub fn mut_phantom_ip(&mut self) -> &mut ::std::string::String {
-        if self.phantom_ip.is_none() {
-            self.phantom_ip.set_default();
-        }
-        self.phantom_ip.as_mut().unwrap()
+    // optional uint64 default_timeout_ns = 2;
+
+
+    pub fn get_default_timeout_ns(&self) -> u64 {
+        self.default_timeout_ns.unwrap_or(0)
+    }
+    pub fn clear_default_timeout_ns(&mut self) {
+        self.default_timeout_ns = ::std::option::Option::None;
     }
 
-    // Take field
-    pub fn take_phantom_ip(&mut self) -> ::std::string::String {
-        self.phantom_ip.take().unwrap_or_else(|| ::std::string::String::new())
+    pub fn has_default_timeout_ns(&self) -> bool {
+        self.default_timeout_ns.is_some()
     }
 
-    // optional string client_ip = 2;
+    // Param is passed by value, moved
+    pub fn set_default_timeout_ns(&mut self, v: u64) {
+        self.default_timeout_ns = ::std::option::Option::Some(v);
+    }
 
+    // repeated .tapdance.TransportType allowed_transports = 3;
 
-    pub fn get_client_ip(&self) -> &str {
-        match self.client_ip.as_ref() {
-            Some(v) => &v,
-            None => "",
-        }
-    }
-    pub fn clear_client_ip(&mut self) {
-        self.client_ip.clear();
-    }
 
-    pub fn has_client_ip(&self) -> bool {
-        self.client_ip.is_some()
+    pub fn get_allowed_transports(&self) -> &[TransportType] {
+        &self.allowed_transports
+    }
+    pub fn clear_allowed_transports(&mut self) {
+        self.allowed_transports.clear();
     }
 
     // Param is passed by value, moved
-    pub fn set_client_ip(&mut self, v: ::std::string::String) {
-        self.client_ip = ::protobuf::SingularField::some(v);
+    pub fn set_allowed_transports(&mut self, v: ::std::vec::Vec<TransportType>) {
+        self.allowed_transports = v;
     }
 
     // Mutable pointer to the field.
-    // If field is not initialized, it is initialized with default value first.
-    pub fn mut_client_ip(&mut self) -> &mut ::std::string::String {
-        if self.client_ip.is_none() {
-            self.client_ip.set_default();
-        }
-        self.client_ip.as_mut().unwrap()
+    pub fn mut_allowed_transports(&mut self) -> &mut ::std::vec::Vec<TransportType> {
+        &mut self.allowed_transports
     }
 
     // Take field
-    pub fn take_client_ip(&mut self) -> ::std::string::String {
-        self.client_ip.take().unwrap_or_else(|| ::std::string::String::new())
+    pub fn take_allowed_transports(&mut self) -> ::std::vec::Vec<TransportType> {
+        ::std::mem::replace(&mut self.allowed_transports, ::std::vec::Vec::new())
     }
 
-    // optional uint64 timeout_ns = 3;
+    // repeated string client_blocklist = 4;
 
 
-    pub fn get_timeout_ns(&self) -> u64 {
-        self.timeout_ns.unwrap_or(0)
+    pub fn get_client_blocklist(&self) -> &[::std::string::String] {
+        &self.client_blocklist
     }
-    pub fn clear_timeout_ns(&mut self) {
-        self.timeout_ns = ::std::option::Option::None;
+    pub fn clear_client_blocklist(&mut self) {
+        self.client_blocklist.clear();
     }
 
-    pub fn has_timeout_ns(&self) -> bool {
-        self.timeout_ns.is_some()
+    // Param is passed by value, moved
+    pub fn set_client_blocklist(&mut self, v: ::protobuf::RepeatedField<::std::string::String>) {
+        self.client_blocklist = v;
     }
 
-    // Param is passed by value, moved
-    pub fn set_timeout_ns(&mut self, v: u64) {
-        self.timeout_ns = ::std::option::Option::Some(v);
+    // Mutable pointer to the field.
+    pub fn mut_client_blocklist(&mut self) -> &mut ::protobuf::RepeatedField<::std::string::String> {
+        &mut self.client_blocklist
     }
 
-    // optional uint32 phantom_port = 4;
+    // Take field
+    pub fn take_client_blocklist(&mut self) -> ::protobuf::RepeatedField<::std::string::String> {
+        ::std::mem::replace(&mut self.client_blocklist, ::protobuf::RepeatedField::new())
+    }
 
+    // repeated string phantom_blocklist = 5;
 
-    pub fn get_phantom_port(&self) -> u32 {
-        self.phantom_port.unwrap_or(0)
+
+    pub fn get_phantom_blocklist(&self) -> &[::std::string::String] {
+        &self.phantom_blocklist
     }
-    pub fn clear_phantom_port(&mut self) {
-        self.phantom_port = ::std::option::Option::None;
+    pub fn clear_phantom_blocklist(&mut self) {
+        self.phantom_blocklist.clear();
     }
 
-    pub fn has_phantom_port(&self) -> bool {
-        self.phantom_port.is_some()
+    // Param is passed by value, moved
+    pub fn set_phantom_blocklist(&mut self, v: ::protobuf::RepeatedField<::std::string::String>) {
+        self.phantom_blocklist = v;
     }
 
-    // Param is passed by value, moved
-    pub fn set_phantom_port(&mut self, v: u32) {
-        self.phantom_port = ::std::option::Option::Some(v);
+    // Mutable pointer to the field.
+    pub fn mut_phantom_blocklist(&mut self) -> &mut ::protobuf::RepeatedField<::std::string::String> {
+        &mut self.phantom_blocklist
+    }
+
+    // Take field
+    pub fn take_phantom_blocklist(&mut self) -> ::protobuf::RepeatedField<::std::string::String> {
+        ::std::mem::replace(&mut self.phantom_blocklist, ::protobuf::RepeatedField::new())
     }
 }
 
-impl ::protobuf::Message for StationToDetector {
+impl ::protobuf::Message for ConfigPush {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -3826,24 +3830,27 @@ impl ::protobuf::Message for StationToDetector {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_singular_string_into(wire_type, is, &mut self.phantom_ip)?;
-                },
-                2 => {
-                    ::protobuf::rt::read_singular_string_into(wire_type, is, &mut self.client_ip)?;
-                },
-                3 => {
                     if wire_type != ::protobuf::wire_format::WireTypeVarint {
                         return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
                     }
                     let tmp = is.read_uint64()?;
-                    self.timeout_ns = ::std::option::Option::Some(tmp);
+                    self.epoch = ::std::option::Option::Some(tmp);
                 },
-                4 => {
+                2 => {
                     if wire_type != ::protobuf::wire_format::WireTypeVarint {
                         return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
                     }
-                    let tmp = is.read_uint32()?;
-                    self.phantom_port = ::std::option::Option::Some(tmp);
+                    let tmp = is.read_uint64()?;
+                    self.default_timeout_ns = ::std::option::Option::Some(tmp);
+                },
+                3 => {
+                    ::protobuf::rt::read_repeated_enum_with_unknown_fields_into(wire_type, is, &mut self.allowed_transports, 3, &mut self.unknown_fields)?
+                },
+                4 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.client_blocklist)?;
+                },
+                5 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.phantom_blocklist)?;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -3857,36 +3864,42 @@ impl ::protobuf::Message for StationToDetector {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        if let Some(ref v) = self.phantom_ip.as_ref() {
-            my_size += ::protobuf::rt::string_size(1, &v);
-        }
-        if let Some(ref v) = self.client_ip.as_ref() {
-            my_size += ::protobuf::rt::string_size(2, &v);
-        }
-        if let Some(v) = self.timeout_ns {
-            my_size += ::protobuf::rt::value_size(3, v, ::protobuf::wire_format::WireTypeVarint);
+        if let Some(v) = self.epoch {
+            my_size += ::protobuf::rt::value_size(1, v, ::protobuf::wire_format::WireTypeVarint);
         }
-        if let Some(v) = self.phantom_port {
-            my_size += ::protobuf::rt::value_size(4, v, ::protobuf::wire_format::WireTypeVarint);
+        if let Some(v) = self.default_timeout_ns {
+            my_size += ::protobuf::rt::value_size(2, v, ::protobuf::wire_format::WireTypeVarint);
         }
+        for value in &self.allowed_transports {
+            my_size += ::protobuf::rt::enum_size(3, *value);
+        };
+        for value in &self.client_blocklist {
+            my_size += ::protobuf::rt::string_size(4, &value);
+        };
+        for value in &self.phantom_blocklist {
+            my_size += ::protobuf::rt::string_size(5, &value);
+        };
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        if let Some(ref v) = self.phantom_ip.as_ref() {
-            os.write_string(1, &v)?;
-        }
-        if let Some(ref v) = self.client_ip.as_ref() {
-            os.write_string(2, &v)?;
-        }
-        if let Some(v) = self.timeout_ns {
-            os.write_uint64(3, v)?;
+        if let Some(v) = self.epoch {
+            os.write_uint64(1, v)?;
         }
-        if let Some(v) = self.phantom_port {
-            os.write_uint32(4, v)?;
+        if let Some(v) = self.default_timeout_ns {
+            os.write_uint64(2, v)?;
         }
+        for v in &self.allowed_transports {
+            os.write_enum(3, ::protobuf::ProtobufEnum::value(v))?;
+        };
+        for v in &self.client_blocklist {
+            os.write_string(4, &v)?;
+        };
+        for v in &self.phantom_blocklist {
+            os.write_string(5, &v)?;
+        };
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -3917,125 +3930,5171 @@ impl ::protobuf::Message for StationToDetector {
         Self::descriptor_static()
     }
 
-    fn new() -> StationToDetector {
-        StationToDetector::new()
+    fn new() -> ConfigPush {
+        ConfigPush::new()
     }
 
     fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
         static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
         descriptor.get(|| {
             let mut fields = ::std::vec::Vec::new();
-            fields.push(::protobuf::reflect::accessor::make_singular_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "phantom_ip",
-                |m: &StationToDetector| { &m.phantom_ip },
-                |m: &mut StationToDetector| { &mut m.phantom_ip },
-            ));
-            fields.push(::protobuf::reflect::accessor::make_singular_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "client_ip",
-                |m: &StationToDetector| { &m.client_ip },
-                |m: &mut StationToDetector| { &mut m.client_ip },
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "epoch",
+                |m: &ConfigPush| { &m.epoch },
+                |m: &mut ConfigPush| { &mut m.epoch },
             ));
             fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
-                "timeout_ns",
-                |m: &StationToDetector| { &m.timeout_ns },
-                |m: &mut StationToDetector| { &mut m.timeout_ns },
+                "default_timeout_ns",
+                |m: &ConfigPush| { &m.default_timeout_ns },
+                |m: &mut ConfigPush| { &mut m.default_timeout_ns },
             ));
-            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
-                "phantom_port",
-                |m: &StationToDetector| { &m.phantom_port },
-                |m: &mut StationToDetector| { &mut m.phantom_port },
+            fields.push(::protobuf::reflect::accessor::make_vec_accessor::<_, ::protobuf::types::ProtobufTypeEnum<TransportType>>(
+                "allowed_transports",
+                |m: &ConfigPush| { &m.allowed_transports },
+                |m: &mut ConfigPush| { &mut m.allowed_transports },
             ));
-            ::protobuf::reflect::MessageDescriptor::new_pb_name::<StationToDetector>(
-                "StationToDetector",
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "client_blocklist",
+                |m: &ConfigPush| { &m.client_blocklist },
+                |m: &mut ConfigPush| { &mut m.client_blocklist },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "phantom_blocklist",
+                |m: &ConfigPush| { &m.phantom_blocklist },
+                |m: &mut ConfigPush| { &mut m.phantom_blocklist },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<ConfigPush>(
+                "ConfigPush",
                 fields,
                 file_descriptor_proto()
             )
         })
     }
 
-    fn default_instance() -> &'static StationToDetector {
-        static instance: ::protobuf::rt::LazyV2<StationToDetector> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(StationToDetector::new)
+    fn default_instance() -> &'static ConfigPush {
+        static instance: ::protobuf::rt::LazyV2<ConfigPush> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(ConfigPush::new)
     }
 }
 
-impl ::protobuf::Clear for StationToDetector {
+impl ::protobuf::Clear for ConfigPush {
     fn clear(&mut self) {
-        self.phantom_ip.clear();
-        self.client_ip.clear();
-        self.timeout_ns = ::std::option::Option::None;
-        self.phantom_port = ::std::option::Option::None;
+        self.epoch = ::std::option::Option::None;
+        self.default_timeout_ns = ::std::option::Option::None;
+        self.allowed_transports.clear();
+        self.client_blocklist.clear();
+        self.phantom_blocklist.clear();
         self.unknown_fields.clear();
     }
 }
 
-impl ::std::fmt::Debug for StationToDetector {
+impl ::std::fmt::Debug for ConfigPush {
     fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
         ::protobuf::text_format::fmt(self, f)
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for StationToDetector {
+impl ::protobuf::reflect::ProtobufValue for ConfigPush {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
-#[derive(Clone,PartialEq,Eq,Debug,Hash)]
-pub enum KeyType {
-    AES_GCM_128 = 90,
-    AES_GCM_256 = 91,
+#[derive(PartialEq,Clone,Default)]
+pub struct PhantomSubnetSet {
+    // message fields
+    generation: ::std::option::Option<u64>,
+    pub subnets: ::protobuf::RepeatedField<::std::string::String>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
 }
 
-impl ::protobuf::ProtobufEnum for KeyType {
-    fn value(&self) -> i32 {
-        *self as i32
+impl<'a> ::std::default::Default for &'a PhantomSubnetSet {
+    fn default() -> &'a PhantomSubnetSet {
+        <PhantomSubnetSet as ::protobuf::Message>::default_instance()
     }
+}
 
-    fn from_i32(value: i32) -> ::std::option::Option<KeyType> {
-        match value {
-            90 => ::std::option::Option::Some(KeyType::AES_GCM_128),
-            91 => ::std::option::Option::Some(KeyType::AES_GCM_256),
-            _ => ::std::option::Option::None
-        }
+impl PhantomSubnetSet {
+    pub fn new() -> PhantomSubnetSet {
+        ::std::default::Default::default()
     }
 
-    fn values() -> &'static [Self] {
-        static values: &'static [KeyType] = &[
-            KeyType::AES_GCM_128,
-            KeyType::AES_GCM_256,
-        ];
-        values
+    // optional uint64 generation = 1;
+
+
+    pub fn get_generation(&self) -> u64 {
+        self.generation.unwrap_or(0)
+    }
+    pub fn clear_generation(&mut self) {
+        self.generation = ::std::option::Option::None;
     }
 
-    fn enum_descriptor_static() -> &'static ::protobuf::reflect::EnumDescriptor {
-        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::EnumDescriptor> = ::protobuf::rt::LazyV2::INIT;
-        descriptor.get(|| {
-            ::protobuf::reflect::EnumDescriptor::new_pb_name::<KeyType>("KeyType", file_descriptor_proto())
-        })
+    pub fn has_generation(&self) -> bool {
+        self.generation.is_some()
     }
-}
 
-impl ::std::marker::Copy for KeyType {
-}
+    // Param is passed by value, moved
+    pub fn set_generation(&mut self, v: u64) {
+        self.generation = ::std::option::Option::Some(v);
+    }
 
-// Note, `Default` is implemented although default value is not 0
-impl ::std::default::Default for KeyType {
-    fn default() -> Self {
-        KeyType::AES_GCM_128
+    // repeated string subnets = 2;
+
+
+    pub fn get_subnets(&self) -> &[::std::string::String] {
+        &self.subnets
+    }
+    pub fn clear_subnets(&mut self) {
+        self.subnets.clear();
     }
-}
 
-impl ::protobuf::reflect::ProtobufValue for KeyType {
-    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
-        ::protobuf::reflect::ReflectValueRef::Enum(::protobuf::ProtobufEnum::descriptor(self))
+    // Param is passed by value, moved
+    pub fn set_subnets(&mut self, v: ::protobuf::RepeatedField<::std::string::String>) {
+        self.subnets = v;
     }
-}
 
-#[derive(Clone,PartialEq,Eq,Debug,Hash)]
-pub enum C2S_Transition {
-    C2S_NO_CHANGE = 0,
-    C2S_SESSION_INIT = 1,
+    // Mutable pointer to the field.
+    pub fn mut_subnets(&mut self) -> &mut ::protobuf::RepeatedField<::std::string::String> {
+        &mut self.subnets
+    }
+
+    // Take field
+    pub fn take_subnets(&mut self) -> ::protobuf::RepeatedField<::std::string::String> {
+        ::std::mem::replace(&mut self.subnets, ::protobuf::RepeatedField::new())
+    }
+}
+
+impl ::protobuf::Message for PhantomSubnetSet {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.generation = ::std::option::Option::Some(tmp);
+                },
+                2 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.subnets)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if let Some(v) = self.generation {
+            my_size += ::protobuf::rt::value_size(1, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        for value in &self.subnets {
+            my_size += ::protobuf::rt::string_size(2, &value);
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if let Some(v) = self.generation {
+            os.write_uint64(1, v)?;
+        }
+        for v in &self.subnets {
+            os.write_string(2, &v)?;
+        };
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> PhantomSubnetSet {
+        PhantomSubnetSet::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "generation",
+                |m: &PhantomSubnetSet| { &m.generation },
+                |m: &mut PhantomSubnetSet| { &mut m.generation },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "subnets",
+                |m: &PhantomSubnetSet| { &m.subnets },
+                |m: &mut PhantomSubnetSet| { &mut m.subnets },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<PhantomSubnetSet>(
+                "PhantomSubnetSet",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static PhantomSubnetSet {
+        static instance: ::protobuf::rt::LazyV2<PhantomSubnetSet> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(PhantomSubnetSet::new)
+    }
+}
+
+impl ::protobuf::Clear for PhantomSubnetSet {
+    fn clear(&mut self) {
+        self.generation = ::std::option::Option::None;
+        self.subnets.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for PhantomSubnetSet {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for PhantomSubnetSet {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct StationToDetector {
+    // message fields
+    phantom_ip: ::protobuf::SingularField<::std::string::String>,
+    client_ip: ::protobuf::SingularField<::std::string::String>,
+    timeout_ns: ::std::option::Option<u64>,
+    phantom_port: ::std::option::Option<u32>,
+    registration_source: ::std::option::Option<RegistrationSource>,
+    phantom_ip_v6: ::protobuf::SingularField<::std::string::String>,
+    max_connections: ::std::option::Option<u32>,
+    expires_at_unix_ns: ::std::option::Option<u64>,
+    station_time_unix_ns: ::std::option::Option<u64>,
+    message_id: ::protobuf::SingularField<::std::vec::Vec<u8>>,
+    transport: ::std::option::Option<TransportType>,
+    pub config_push: ::protobuf::SingularPtrField<ConfigPush>,
+    pub phantom_subnets: ::protobuf::SingularPtrField<PhantomSubnetSet>,
+    mode: ::std::option::Option<SessionMode>,
+    publisher_id: ::std::option::Option<u32>,
+    sequence: ::std::option::Option<u64>,
+    tenant_id: ::std::option::Option<u32>,
+    delete_tenant_sessions: ::std::option::Option<bool>,
+    extension_ns: ::std::option::Option<u64>,
+    update_expiry: ::std::option::Option<bool>,
+    pub client_ports: ::protobuf::SingularPtrField<PortRange>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a StationToDetector {
+    fn default() -> &'a StationToDetector {
+        <StationToDetector as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl StationToDetector {
+    pub fn new() -> StationToDetector {
+        ::std::default::Default::default()
+    }
+
+    // optional string phantom_ip = 1;
+
+
+    pub fn get_phantom_ip(&self) -> &str {
+        match self.phantom_ip.as_ref() {
+            Some(v) => &v,
+            None => "",
+        }
+    }
+    pub fn clear_phantom_ip(&mut self) {
+        self.phantom_ip.clear();
+    }
+
+    pub fn has_phantom_ip(&self) -> bool {
+        self.phantom_ip.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_phantom_ip(&mut self, v: ::std::string::String) {
+        self.phantom_ip = ::protobuf::SingularField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_phantom_ip(&mut self) -> &mut ::std::string::String {
+        if self.phantom_ip.is_none() {
+            self.phantom_ip.set_default();
+        }
+        self.phantom_ip.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_phantom_ip(&mut self) -> ::std::string::String {
+        self.phantom_ip.take().unwrap_or_else(|| ::std::string::String::new())
+    }
+
+    // optional string client_ip = 2;
+
+
+    pub fn get_client_ip(&self) -> &str {
+        match self.client_ip.as_ref() {
+            Some(v) => &v,
+            None => "",
+        }
+    }
+    pub fn clear_client_ip(&mut self) {
+        self.client_ip.clear();
+    }
+
+    pub fn has_client_ip(&self) -> bool {
+        self.client_ip.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_client_ip(&mut self, v: ::std::string::String) {
+        self.client_ip = ::protobuf::SingularField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_client_ip(&mut self) -> &mut ::std::string::String {
+        if self.client_ip.is_none() {
+            self.client_ip.set_default();
+        }
+        self.client_ip.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_client_ip(&mut self) -> ::std::string::String {
+        self.client_ip.take().unwrap_or_else(|| ::std::string::String::new())
+    }
+
+    // optional uint64 timeout_ns = 3;
+
+
+    pub fn get_timeout_ns(&self) -> u64 {
+        self.timeout_ns.unwrap_or(0)
+    }
+    pub fn clear_timeout_ns(&mut self) {
+        self.timeout_ns = ::std::option::Option::None;
+    }
+
+    pub fn has_timeout_ns(&self) -> bool {
+        self.timeout_ns.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_timeout_ns(&mut self, v: u64) {
+        self.timeout_ns = ::std::option::Option::Some(v);
+    }
+
+    // optional uint32 phantom_port = 4;
+
+
+    pub fn get_phantom_port(&self) -> u32 {
+        self.phantom_port.unwrap_or(0)
+    }
+    pub fn clear_phantom_port(&mut self) {
+        self.phantom_port = ::std::option::Option::None;
+    }
+
+    pub fn has_phantom_port(&self) -> bool {
+        self.phantom_port.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_phantom_port(&mut self, v: u32) {
+        self.phantom_port = ::std::option::Option::Some(v);
+    }
+
+    // optional .tapdance.RegistrationSource registration_source = 5;
+
+
+    pub fn get_registration_source(&self) -> RegistrationSource {
+        self.registration_source.unwrap_or(RegistrationSource::Unspecified)
+    }
+    pub fn clear_registration_source(&mut self) {
+        self.registration_source = ::std::option::Option::None;
+    }
+
+    pub fn has_registration_source(&self) -> bool {
+        self.registration_source.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_registration_source(&mut self, v: RegistrationSource) {
+        self.registration_source = ::std::option::Option::Some(v);
+    }
+
+    // optional string phantom_ip_v6 = 6;
+
+
+    pub fn get_phantom_ip_v6(&self) -> &str {
+        match self.phantom_ip_v6.as_ref() {
+            Some(v) => &v,
+            None => "",
+        }
+    }
+    pub fn clear_phantom_ip_v6(&mut self) {
+        self.phantom_ip_v6.clear();
+    }
+
+    pub fn has_phantom_ip_v6(&self) -> bool {
+        self.phantom_ip_v6.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_phantom_ip_v6(&mut self, v: ::std::string::String) {
+        self.phantom_ip_v6 = ::protobuf::SingularField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_phantom_ip_v6(&mut self) -> &mut ::std::string::String {
+        if self.phantom_ip_v6.is_none() {
+            self.phantom_ip_v6.set_default();
+        }
+        self.phantom_ip_v6.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_phantom_ip_v6(&mut self) -> ::std::string::String {
+        self.phantom_ip_v6.take().unwrap_or_else(|| ::std::string::String::new())
+    }
+
+    // optional uint32 max_connections = 7;
+
+
+    pub fn get_max_connections(&self) -> u32 {
+        self.max_connections.unwrap_or(0)
+    }
+    pub fn clear_max_connections(&mut self) {
+        self.max_connections = ::std::option::Option::None;
+    }
+
+    pub fn has_max_connections(&self) -> bool {
+        self.max_connections.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_max_connections(&mut self, v: u32) {
+        self.max_connections = ::std::option::Option::Some(v);
+    }
+
+    // optional uint64 expires_at_unix_ns = 8;
+
+
+    pub fn get_expires_at_unix_ns(&self) -> u64 {
+        self.expires_at_unix_ns.unwrap_or(0)
+    }
+    pub fn clear_expires_at_unix_ns(&mut self) {
+        self.expires_at_unix_ns = ::std::option::Option::None;
+    }
+
+    pub fn has_expires_at_unix_ns(&self) -> bool {
+        self.expires_at_unix_ns.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_expires_at_unix_ns(&mut self, v: u64) {
+        self.expires_at_unix_ns = ::std::option::Option::Some(v);
+    }
+
+    // optional uint64 station_time_unix_ns = 9;
+
+
+    pub fn get_station_time_unix_ns(&self) -> u64 {
+        self.station_time_unix_ns.unwrap_or(0)
+    }
+    pub fn clear_station_time_unix_ns(&mut self) {
+        self.station_time_unix_ns = ::std::option::Option::None;
+    }
+
+    pub fn has_station_time_unix_ns(&self) -> bool {
+        self.station_time_unix_ns.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_station_time_unix_ns(&mut self, v: u64) {
+        self.station_time_unix_ns = ::std::option::Option::Some(v);
+    }
+
+    // optional bytes message_id = 10;
+
+
+    pub fn get_message_id(&self) -> &[u8] {
+        match self.message_id.as_ref() {
+            Some(v) => &v,
+            None => &[],
+        }
+    }
+    pub fn clear_message_id(&mut self) {
+        self.message_id.clear();
+    }
+
+    pub fn has_message_id(&self) -> bool {
+        self.message_id.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_message_id(&mut self, v: ::std::vec::Vec<u8>) {
+        self.message_id = ::protobuf::SingularField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_message_id(&mut self) -> &mut ::std::vec::Vec<u8> {
+        if self.message_id.is_none() {
+            self.message_id.set_default();
+        }
+        self.message_id.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_message_id(&mut self) -> ::std::vec::Vec<u8> {
+        self.message_id.take().unwrap_or_else(|| ::std::vec::Vec::new())
+    }
+
+    // optional .tapdance.TransportType transport = 11;
+
+
+    pub fn get_transport(&self) -> TransportType {
+        self.transport.unwrap_or(TransportType::Null)
+    }
+    pub fn clear_transport(&mut self) {
+        self.transport = ::std::option::Option::None;
+    }
+
+    pub fn has_transport(&self) -> bool {
+        self.transport.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_transport(&mut self, v: TransportType) {
+        self.transport = ::std::option::Option::Some(v);
+    }
+
+    // optional .tapdance.ConfigPush config_push = 12;
+
+
+    pub fn get_config_push(&self) -> &ConfigPush {
+        self.config_push.as_ref().unwrap_or_else(|| <ConfigPush as ::protobuf::Message>::default_instance())
+    }
+    pub fn clear_config_push(&mut self) {
+        self.config_push.clear();
+    }
+
+    pub fn has_config_push(&self) -> bool {
+        self.config_push.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_config_push(&mut self, v: ConfigPush) {
+        self.config_push = ::protobuf::SingularPtrField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_config_push(&mut self) -> &mut ConfigPush {
+        if self.config_push.is_none() {
+            self.config_push.set_default();
+        }
+        self.config_push.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_config_push(&mut self) -> ConfigPush {
+        self.config_push.take().unwrap_or_else(|| ConfigPush::new())
+    }
+
+    // optional .tapdance.PhantomSubnetSet phantom_subnets = 13;
+
+
+    pub fn get_phantom_subnets(&self) -> &PhantomSubnetSet {
+        self.phantom_subnets.as_ref().unwrap_or_else(|| <PhantomSubnetSet as ::protobuf::Message>::default_instance())
+    }
+    pub fn clear_phantom_subnets(&mut self) {
+        self.phantom_subnets.clear();
+    }
+
+    pub fn has_phantom_subnets(&self) -> bool {
+        self.phantom_subnets.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_phantom_subnets(&mut self, v: PhantomSubnetSet) {
+        self.phantom_subnets = ::protobuf::SingularPtrField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_phantom_subnets(&mut self) -> &mut PhantomSubnetSet {
+        if self.phantom_subnets.is_none() {
+            self.phantom_subnets.set_default();
+        }
+        self.phantom_subnets.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_phantom_subnets(&mut self) -> PhantomSubnetSet {
+        self.phantom_subnets.take().unwrap_or_else(|| PhantomSubnetSet::new())
+    }
+
+    // optional .tapdance.SessionMode mode = 14;
+
+
+    pub fn get_mode(&self) -> SessionMode {
+        self.mode.unwrap_or(SessionMode::Phantom)
+    }
+    pub fn clear_mode(&mut self) {
+        self.mode = ::std::option::Option::None;
+    }
+
+    pub fn has_mode(&self) -> bool {
+        self.mode.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_mode(&mut self, v: SessionMode) {
+        self.mode = ::std::option::Option::Some(v);
+    }
+
+    // optional uint32 publisher_id = 15;
+
+
+    pub fn get_publisher_id(&self) -> u32 {
+        self.publisher_id.unwrap_or(0)
+    }
+    pub fn clear_publisher_id(&mut self) {
+        self.publisher_id = ::std::option::Option::None;
+    }
+
+    pub fn has_publisher_id(&self) -> bool {
+        self.publisher_id.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_publisher_id(&mut self, v: u32) {
+        self.publisher_id = ::std::option::Option::Some(v);
+    }
+
+    // optional uint64 sequence = 16;
+
+
+    pub fn get_sequence(&self) -> u64 {
+        self.sequence.unwrap_or(0)
+    }
+    pub fn clear_sequence(&mut self) {
+        self.sequence = ::std::option::Option::None;
+    }
+
+    pub fn has_sequence(&self) -> bool {
+        self.sequence.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_sequence(&mut self, v: u64) {
+        self.sequence = ::std::option::Option::Some(v);
+    }
+
+    // optional uint32 tenant_id = 17;
+
+
+    pub fn get_tenant_id(&self) -> u32 {
+        self.tenant_id.unwrap_or(0)
+    }
+    pub fn clear_tenant_id(&mut self) {
+        self.tenant_id = ::std::option::Option::None;
+    }
+
+    pub fn has_tenant_id(&self) -> bool {
+        self.tenant_id.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_tenant_id(&mut self, v: u32) {
+        self.tenant_id = ::std::option::Option::Some(v);
+    }
+
+    // optional bool delete_tenant_sessions = 18;
+
+
+    pub fn get_delete_tenant_sessions(&self) -> bool {
+        self.delete_tenant_sessions.unwrap_or(false)
+    }
+    pub fn clear_delete_tenant_sessions(&mut self) {
+        self.delete_tenant_sessions = ::std::option::Option::None;
+    }
+
+    pub fn has_delete_tenant_sessions(&self) -> bool {
+        self.delete_tenant_sessions.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_delete_tenant_sessions(&mut self, v: bool) {
+        self.delete_tenant_sessions = ::std::option::Option::Some(v);
+    }
+
+    // optional uint64 extension_ns = 19;
+
+
+    pub fn get_extension_ns(&self) -> u64 {
+        self.extension_ns.unwrap_or(0)
+    }
+    pub fn clear_extension_ns(&mut self) {
+        self.extension_ns = ::std::option::Option::None;
+    }
+
+    pub fn has_extension_ns(&self) -> bool {
+        self.extension_ns.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_extension_ns(&mut self, v: u64) {
+        self.extension_ns = ::std::option::Option::Some(v);
+    }
+
+    // optional bool update_expiry = 20;
+
+
+    pub fn get_update_expiry(&self) -> bool {
+        self.update_expiry.unwrap_or(false)
+    }
+    pub fn clear_update_expiry(&mut self) {
+        self.update_expiry = ::std::option::Option::None;
+    }
+
+    pub fn has_update_expiry(&self) -> bool {
+        self.update_expiry.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_update_expiry(&mut self, v: bool) {
+        self.update_expiry = ::std::option::Option::Some(v);
+    }
+
+    // optional .tapdance.PortRange client_ports = 21;
+
+
+    pub fn get_client_ports(&self) -> &PortRange {
+        self.client_ports.as_ref().unwrap_or_else(|| <PortRange as ::protobuf::Message>::default_instance())
+    }
+    pub fn clear_client_ports(&mut self) {
+        self.client_ports.clear();
+    }
+
+    pub fn has_client_ports(&self) -> bool {
+        self.client_ports.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_client_ports(&mut self, v: PortRange) {
+        self.client_ports = ::protobuf::SingularPtrField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_client_ports(&mut self) -> &mut PortRange {
+        if self.client_ports.is_none() {
+            self.client_ports.set_default();
+        }
+        self.client_ports.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_client_ports(&mut self) -> PortRange {
+        self.client_ports.take().unwrap_or_else(|| PortRange::new())
+    }
+}
+
+impl ::protobuf::Message for StationToDetector {
+    fn is_initialized(&self) -> bool {
+        for v in &self.config_push {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        for v in &self.phantom_subnets {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        for v in &self.client_ports {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_string_into(wire_type, is, &mut self.phantom_ip)?;
+                },
+                2 => {
+                    ::protobuf::rt::read_singular_string_into(wire_type, is, &mut self.client_ip)?;
+                },
+                3 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.timeout_ns = ::std::option::Option::Some(tmp);
+                },
+                4 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.phantom_port = ::std::option::Option::Some(tmp);
+                },
+                5 => {
+                    ::protobuf::rt::read_proto2_enum_with_unknown_fields_into(wire_type, is, &mut self.registration_source, 5, &mut self.unknown_fields)?
+                },
+                6 => {
+                    ::protobuf::rt::read_singular_string_into(wire_type, is, &mut self.phantom_ip_v6)?;
+                },
+                7 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.max_connections = ::std::option::Option::Some(tmp);
+                },
+                8 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.expires_at_unix_ns = ::std::option::Option::Some(tmp);
+                },
+                9 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.station_time_unix_ns = ::std::option::Option::Some(tmp);
+                },
+                10 => {
+                    ::protobuf::rt::read_singular_bytes_into(wire_type, is, &mut self.message_id)?;
+                },
+                11 => {
+                    ::protobuf::rt::read_proto2_enum_with_unknown_fields_into(wire_type, is, &mut self.transport, 11, &mut self.unknown_fields)?
+                },
+                12 => {
+                    ::protobuf::rt::read_singular_message_into(wire_type, is, &mut self.config_push)?;
+                },
+                13 => {
+                    ::protobuf::rt::read_singular_message_into(wire_type, is, &mut self.phantom_subnets)?;
+                },
+                14 => {
+                    ::protobuf::rt::read_proto2_enum_with_unknown_fields_into(wire_type, is, &mut self.mode, 14, &mut self.unknown_fields)?
+                },
+                15 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.publisher_id = ::std::option::Option::Some(tmp);
+                },
+                16 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.sequence = ::std::option::Option::Some(tmp);
+                },
+                17 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.tenant_id = ::std::option::Option::Some(tmp);
+                },
+                18 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.delete_tenant_sessions = ::std::option::Option::Some(tmp);
+                },
+                19 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.extension_ns = ::std::option::Option::Some(tmp);
+                },
+                20 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.update_expiry = ::std::option::Option::Some(tmp);
+                },
+                21 => {
+                    ::protobuf::rt::read_singular_message_into(wire_type, is, &mut self.client_ports)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if let Some(ref v) = self.phantom_ip.as_ref() {
+            my_size += ::protobuf::rt::string_size(1, &v);
+        }
+        if let Some(ref v) = self.client_ip.as_ref() {
+            my_size += ::protobuf::rt::string_size(2, &v);
+        }
+        if let Some(v) = self.timeout_ns {
+            my_size += ::protobuf::rt::value_size(3, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.phantom_port {
+            my_size += ::protobuf::rt::value_size(4, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.registration_source {
+            my_size += ::protobuf::rt::enum_size(5, v);
+        }
+        if let Some(ref v) = self.phantom_ip_v6.as_ref() {
+            my_size += ::protobuf::rt::string_size(6, &v);
+        }
+        if let Some(v) = self.max_connections {
+            my_size += ::protobuf::rt::value_size(7, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.expires_at_unix_ns {
+            my_size += ::protobuf::rt::value_size(8, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.station_time_unix_ns {
+            my_size += ::protobuf::rt::value_size(9, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(ref v) = self.message_id.as_ref() {
+            my_size += ::protobuf::rt::bytes_size(10, &v);
+        }
+        if let Some(v) = self.transport {
+            my_size += ::protobuf::rt::enum_size(11, v);
+        }
+        if let Some(ref v) = self.config_push.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        }
+        if let Some(ref v) = self.phantom_subnets.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        }
+        if let Some(v) = self.mode {
+            my_size += ::protobuf::rt::enum_size(14, v);
+        }
+        if let Some(v) = self.publisher_id {
+            my_size += ::protobuf::rt::value_size(15, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.sequence {
+            my_size += ::protobuf::rt::value_size(16, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.tenant_id {
+            my_size += ::protobuf::rt::value_size(17, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.delete_tenant_sessions {
+            my_size += 3;
+        }
+        if let Some(v) = self.extension_ns {
+            my_size += ::protobuf::rt::value_size(19, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.update_expiry {
+            my_size += 3;
+        }
+        if let Some(ref v) = self.client_ports.as_ref() {
+            let len = v.compute_size();
+            my_size += 2 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if let Some(ref v) = self.phantom_ip.as_ref() {
+            os.write_string(1, &v)?;
+        }
+        if let Some(ref v) = self.client_ip.as_ref() {
+            os.write_string(2, &v)?;
+        }
+        if let Some(v) = self.timeout_ns {
+            os.write_uint64(3, v)?;
+        }
+        if let Some(v) = self.phantom_port {
+            os.write_uint32(4, v)?;
+        }
+        if let Some(v) = self.registration_source {
+            os.write_enum(5, ::protobuf::ProtobufEnum::value(&v))?;
+        }
+        if let Some(ref v) = self.phantom_ip_v6.as_ref() {
+            os.write_string(6, &v)?;
+        }
+        if let Some(v) = self.max_connections {
+            os.write_uint32(7, v)?;
+        }
+        if let Some(v) = self.expires_at_unix_ns {
+            os.write_uint64(8, v)?;
+        }
+        if let Some(v) = self.station_time_unix_ns {
+            os.write_uint64(9, v)?;
+        }
+        if let Some(ref v) = self.message_id.as_ref() {
+            os.write_bytes(10, &v)?;
+        }
+        if let Some(v) = self.transport {
+            os.write_enum(11, ::protobuf::ProtobufEnum::value(&v))?;
+        }
+        if let Some(ref v) = self.config_push.as_ref() {
+            os.write_tag(12, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        }
+        if let Some(ref v) = self.phantom_subnets.as_ref() {
+            os.write_tag(13, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        }
+        if let Some(v) = self.mode {
+            os.write_enum(14, ::protobuf::ProtobufEnum::value(&v))?;
+        }
+        if let Some(v) = self.publisher_id {
+            os.write_uint32(15, v)?;
+        }
+        if let Some(v) = self.sequence {
+            os.write_uint64(16, v)?;
+        }
+        if let Some(v) = self.tenant_id {
+            os.write_uint32(17, v)?;
+        }
+        if let Some(v) = self.delete_tenant_sessions {
+            os.write_bool(18, v)?;
+        }
+        if let Some(v) = self.extension_ns {
+            os.write_uint64(19, v)?;
+        }
+        if let Some(v) = self.update_expiry {
+            os.write_bool(20, v)?;
+        }
+        if let Some(ref v) = self.client_ports.as_ref() {
+            os.write_tag(21, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> StationToDetector {
+        StationToDetector::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_singular_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "phantom_ip",
+                |m: &StationToDetector| { &m.phantom_ip },
+                |m: &mut StationToDetector| { &mut m.phantom_ip },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_singular_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "client_ip",
+                |m: &StationToDetector| { &m.client_ip },
+                |m: &mut StationToDetector| { &mut m.client_ip },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "timeout_ns",
+                |m: &StationToDetector| { &m.timeout_ns },
+                |m: &mut StationToDetector| { &mut m.timeout_ns },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "phantom_port",
+                |m: &StationToDetector| { &m.phantom_port },
+                |m: &mut StationToDetector| { &mut m.phantom_port },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeEnum<RegistrationSource>>(
+                "registration_source",
+                |m: &StationToDetector| { &m.registration_source },
+                |m: &mut StationToDetector| { &mut m.registration_source },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_singular_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "phantom_ip_v6",
+                |m: &StationToDetector| { &m.phantom_ip_v6 },
+                |m: &mut StationToDetector| { &mut m.phantom_ip_v6 },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "max_connections",
+                |m: &StationToDetector| { &m.max_connections },
+                |m: &mut StationToDetector| { &mut m.max_connections },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "expires_at_unix_ns",
+                |m: &StationToDetector| { &m.expires_at_unix_ns },
+                |m: &mut StationToDetector| { &mut m.expires_at_unix_ns },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "station_time_unix_ns",
+                |m: &StationToDetector| { &m.station_time_unix_ns },
+                |m: &mut StationToDetector| { &mut m.station_time_unix_ns },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_singular_field_accessor::<_, ::protobuf::types::ProtobufTypeBytes>(
+                "message_id",
+                |m: &StationToDetector| { &m.message_id },
+                |m: &mut StationToDetector| { &mut m.message_id },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeEnum<TransportType>>(
+                "transport",
+                |m: &StationToDetector| { &m.transport },
+                |m: &mut StationToDetector| { &mut m.transport },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_singular_ptr_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<ConfigPush>>(
+                "config_push",
+                |m: &StationToDetector| { &m.config_push },
+                |m: &mut StationToDetector| { &mut m.config_push },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_singular_ptr_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<PhantomSubnetSet>>(
+                "phantom_subnets",
+                |m: &StationToDetector| { &m.phantom_subnets },
+                |m: &mut StationToDetector| { &mut m.phantom_subnets },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeEnum<SessionMode>>(
+                "mode",
+                |m: &StationToDetector| { &m.mode },
+                |m: &mut StationToDetector| { &mut m.mode },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "publisher_id",
+                |m: &StationToDetector| { &m.publisher_id },
+                |m: &mut StationToDetector| { &mut m.publisher_id },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "sequence",
+                |m: &StationToDetector| { &m.sequence },
+                |m: &mut StationToDetector| { &mut m.sequence },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "tenant_id",
+                |m: &StationToDetector| { &m.tenant_id },
+                |m: &mut StationToDetector| { &mut m.tenant_id },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeBool>(
+                "delete_tenant_sessions",
+                |m: &StationToDetector| { &m.delete_tenant_sessions },
+                |m: &mut StationToDetector| { &mut m.delete_tenant_sessions },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "extension_ns",
+                |m: &StationToDetector| { &m.extension_ns },
+                |m: &mut StationToDetector| { &mut m.extension_ns },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeBool>(
+                "update_expiry",
+                |m: &StationToDetector| { &m.update_expiry },
+                |m: &mut StationToDetector| { &mut m.update_expiry },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_singular_ptr_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<PortRange>>(
+                "client_ports",
+                |m: &StationToDetector| { &m.client_ports },
+                |m: &mut StationToDetector| { &mut m.client_ports },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<StationToDetector>(
+                "StationToDetector",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static StationToDetector {
+        static instance: ::protobuf::rt::LazyV2<StationToDetector> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(StationToDetector::new)
+    }
+}
+
+impl ::protobuf::Clear for StationToDetector {
+    fn clear(&mut self) {
+        self.phantom_ip.clear();
+        self.client_ip.clear();
+        self.timeout_ns = ::std::option::Option::None;
+        self.phantom_port = ::std::option::Option::None;
+        self.registration_source = ::std::option::Option::None;
+        self.phantom_ip_v6.clear();
+        self.max_connections = ::std::option::Option::None;
+        self.expires_at_unix_ns = ::std::option::Option::None;
+        self.station_time_unix_ns = ::std::option::Option::None;
+        self.message_id.clear();
+        self.transport = ::std::option::Option::None;
+        self.config_push.clear();
+        self.phantom_subnets.clear();
+        self.mode = ::std::option::Option::None;
+        self.publisher_id = ::std::option::Option::None;
+        self.sequence = ::std::option::Option::None;
+        self.tenant_id = ::std::option::Option::None;
+        self.delete_tenant_sessions = ::std::option::Option::None;
+        self.extension_ns = ::std::option::Option::None;
+        self.update_expiry = ::std::option::Option::None;
+        self.client_ports.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for StationToDetector {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for StationToDetector {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct PortRange {
+    // message fields
+    first: ::std::option::Option<u32>,
+    last: ::std::option::Option<u32>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a PortRange {
+    fn default() -> &'a PortRange {
+        <PortRange as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl PortRange {
+    pub fn new() -> PortRange {
+        ::std::default::Default::default()
+    }
+
+    // optional uint32 first = 1;
+
+
+    pub fn get_first(&self) -> u32 {
+        self.first.unwrap_or(0)
+    }
+    pub fn clear_first(&mut self) {
+        self.first = ::std::option::Option::None;
+    }
+
+    pub fn has_first(&self) -> bool {
+        self.first.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_first(&mut self, v: u32) {
+        self.first = ::std::option::Option::Some(v);
+    }
+
+    // optional uint32 last = 2;
+
+
+    pub fn get_last(&self) -> u32 {
+        self.last.unwrap_or(0)
+    }
+    pub fn clear_last(&mut self) {
+        self.last = ::std::option::Option::None;
+    }
+
+    pub fn has_last(&self) -> bool {
+        self.last.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_last(&mut self, v: u32) {
+        self.last = ::std::option::Option::Some(v);
+    }
+}
+
+impl ::protobuf::Message for PortRange {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.first = ::std::option::Option::Some(tmp);
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.last = ::std::option::Option::Some(tmp);
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if let Some(v) = self.first {
+            my_size += ::protobuf::rt::value_size(1, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.last {
+            my_size += ::protobuf::rt::value_size(2, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if let Some(v) = self.first {
+            os.write_uint32(1, v)?;
+        }
+        if let Some(v) = self.last {
+            os.write_uint32(2, v)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> PortRange {
+        PortRange::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "first",
+                |m: &PortRange| { &m.first },
+                |m: &mut PortRange| { &mut m.first },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "last",
+                |m: &PortRange| { &m.last },
+                |m: &mut PortRange| { &mut m.last },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<PortRange>(
+                "PortRange",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static PortRange {
+        static instance: ::protobuf::rt::LazyV2<PortRange> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(PortRange::new)
+    }
+}
+
+impl ::protobuf::Clear for PortRange {
+    fn clear(&mut self) {
+        self.first = ::std::option::Option::None;
+        self.last = ::std::option::Option::None;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for PortRange {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for PortRange {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct SequenceGapCount {
+    // message fields
+    publisher_id: ::std::option::Option<u32>,
+    received: ::std::option::Option<u64>,
+    missing: ::std::option::Option<u64>,
+    late: ::std::option::Option<u64>,
+    last_sequence: ::std::option::Option<u64>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a SequenceGapCount {
+    fn default() -> &'a SequenceGapCount {
+        <SequenceGapCount as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl SequenceGapCount {
+    pub fn new() -> SequenceGapCount {
+        ::std::default::Default::default()
+    }
+
+    // optional uint32 publisher_id = 1;
+
+
+    pub fn get_publisher_id(&self) -> u32 {
+        self.publisher_id.unwrap_or(0)
+    }
+    pub fn clear_publisher_id(&mut self) {
+        self.publisher_id = ::std::option::Option::None;
+    }
+
+    pub fn has_publisher_id(&self) -> bool {
+        self.publisher_id.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_publisher_id(&mut self, v: u32) {
+        self.publisher_id = ::std::option::Option::Some(v);
+    }
+
+    // optional uint64 received = 2;
+
+
+    pub fn get_received(&self) -> u64 {
+        self.received.unwrap_or(0)
+    }
+    pub fn clear_received(&mut self) {
+        self.received = ::std::option::Option::None;
+    }
+
+    pub fn has_received(&self) -> bool {
+        self.received.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_received(&mut self, v: u64) {
+        self.received = ::std::option::Option::Some(v);
+    }
+
+    // optional uint64 missing = 3;
+
+
+    pub fn get_missing(&self) -> u64 {
+        self.missing.unwrap_or(0)
+    }
+    pub fn clear_missing(&mut self) {
+        self.missing = ::std::option::Option::None;
+    }
+
+    pub fn has_missing(&self) -> bool {
+        self.missing.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_missing(&mut self, v: u64) {
+        self.missing = ::std::option::Option::Some(v);
+    }
+
+    // optional uint64 late = 4;
+
+
+    pub fn get_late(&self) -> u64 {
+        self.late.unwrap_or(0)
+    }
+    pub fn clear_late(&mut self) {
+        self.late = ::std::option::Option::None;
+    }
+
+    pub fn has_late(&self) -> bool {
+        self.late.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_late(&mut self, v: u64) {
+        self.late = ::std::option::Option::Some(v);
+    }
+
+    // optional uint64 last_sequence = 5;
+
+
+    pub fn get_last_sequence(&self) -> u64 {
+        self.last_sequence.unwrap_or(0)
+    }
+    pub fn clear_last_sequence(&mut self) {
+        self.last_sequence = ::std::option::Option::None;
+    }
+
+    pub fn has_last_sequence(&self) -> bool {
+        self.last_sequence.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_last_sequence(&mut self, v: u64) {
+        self.last_sequence = ::std::option::Option::Some(v);
+    }
+}
+
+impl ::protobuf::Message for SequenceGapCount {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.publisher_id = ::std::option::Option::Some(tmp);
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.received = ::std::option::Option::Some(tmp);
+                },
+                3 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.missing = ::std::option::Option::Some(tmp);
+                },
+                4 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.late = ::std::option::Option::Some(tmp);
+                },
+                5 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.last_sequence = ::std::option::Option::Some(tmp);
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if let Some(v) = self.publisher_id {
+            my_size += ::protobuf::rt::value_size(1, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.received {
+            my_size += ::protobuf::rt::value_size(2, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.missing {
+            my_size += ::protobuf::rt::value_size(3, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.late {
+            my_size += ::protobuf::rt::value_size(4, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.last_sequence {
+            my_size += ::protobuf::rt::value_size(5, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if let Some(v) = self.publisher_id {
+            os.write_uint32(1, v)?;
+        }
+        if let Some(v) = self.received {
+            os.write_uint64(2, v)?;
+        }
+        if let Some(v) = self.missing {
+            os.write_uint64(3, v)?;
+        }
+        if let Some(v) = self.late {
+            os.write_uint64(4, v)?;
+        }
+        if let Some(v) = self.last_sequence {
+            os.write_uint64(5, v)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> SequenceGapCount {
+        SequenceGapCount::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "publisher_id",
+                |m: &SequenceGapCount| { &m.publisher_id },
+                |m: &mut SequenceGapCount| { &mut m.publisher_id },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "received",
+                |m: &SequenceGapCount| { &m.received },
+                |m: &mut SequenceGapCount| { &mut m.received },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "missing",
+                |m: &SequenceGapCount| { &m.missing },
+                |m: &mut SequenceGapCount| { &mut m.missing },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "late",
+                |m: &SequenceGapCount| { &m.late },
+                |m: &mut SequenceGapCount| { &mut m.late },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "last_sequence",
+                |m: &SequenceGapCount| { &m.last_sequence },
+                |m: &mut SequenceGapCount| { &mut m.last_sequence },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<SequenceGapCount>(
+                "SequenceGapCount",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static SequenceGapCount {
+        static instance: ::protobuf::rt::LazyV2<SequenceGapCount> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(SequenceGapCount::new)
+    }
+}
+
+impl ::protobuf::Clear for SequenceGapCount {
+    fn clear(&mut self) {
+        self.publisher_id = ::std::option::Option::None;
+        self.received = ::std::option::Option::None;
+        self.missing = ::std::option::Option::None;
+        self.late = ::std::option::Option::None;
+        self.last_sequence = ::std::option::Option::None;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for SequenceGapCount {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for SequenceGapCount {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct NeverMatchedCount {
+    // message fields
+    transport: ::std::option::Option<TransportType>,
+    registration_source: ::std::option::Option<RegistrationSource>,
+    phantom_subnet: ::protobuf::SingularField<::std::string::String>,
+    sessions: ::std::option::Option<u64>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a NeverMatchedCount {
+    fn default() -> &'a NeverMatchedCount {
+        <NeverMatchedCount as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl NeverMatchedCount {
+    pub fn new() -> NeverMatchedCount {
+        ::std::default::Default::default()
+    }
+
+    // optional .tapdance.TransportType transport = 1;
+
+
+    pub fn get_transport(&self) -> TransportType {
+        self.transport.unwrap_or(TransportType::Null)
+    }
+    pub fn clear_transport(&mut self) {
+        self.transport = ::std::option::Option::None;
+    }
+
+    pub fn has_transport(&self) -> bool {
+        self.transport.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_transport(&mut self, v: TransportType) {
+        self.transport = ::std::option::Option::Some(v);
+    }
+
+    // optional .tapdance.RegistrationSource registration_source = 2;
+
+
+    pub fn get_registration_source(&self) -> RegistrationSource {
+        self.registration_source.unwrap_or(RegistrationSource::Unspecified)
+    }
+    pub fn clear_registration_source(&mut self) {
+        self.registration_source = ::std::option::Option::None;
+    }
+
+    pub fn has_registration_source(&self) -> bool {
+        self.registration_source.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_registration_source(&mut self, v: RegistrationSource) {
+        self.registration_source = ::std::option::Option::Some(v);
+    }
+
+    // optional string phantom_subnet = 3;
+
+
+    pub fn get_phantom_subnet(&self) -> &str {
+        match self.phantom_subnet.as_ref() {
+            Some(v) => &v,
+            None => "",
+        }
+    }
+    pub fn clear_phantom_subnet(&mut self) {
+        self.phantom_subnet.clear();
+    }
+
+    pub fn has_phantom_subnet(&self) -> bool {
+        self.phantom_subnet.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_phantom_subnet(&mut self, v: ::std::string::String) {
+        self.phantom_subnet = ::protobuf::SingularField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_phantom_subnet(&mut self) -> &mut ::std::string::String {
+        if self.phantom_subnet.is_none() {
+            self.phantom_subnet.set_default();
+        }
+        self.phantom_subnet.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_phantom_subnet(&mut self) -> ::std::string::String {
+        self.phantom_subnet.take().unwrap_or_else(|| ::std::string::String::new())
+    }
+
+    // optional uint64 sessions = 4;
+
+
+    pub fn get_sessions(&self) -> u64 {
+        self.sessions.unwrap_or(0)
+    }
+    pub fn clear_sessions(&mut self) {
+        self.sessions = ::std::option::Option::None;
+    }
+
+    pub fn has_sessions(&self) -> bool {
+        self.sessions.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_sessions(&mut self, v: u64) {
+        self.sessions = ::std::option::Option::Some(v);
+    }
+}
+
+impl ::protobuf::Message for NeverMatchedCount {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_proto2_enum_with_unknown_fields_into(wire_type, is, &mut self.transport, 1, &mut self.unknown_fields)?
+                },
+                2 => {
+                    ::protobuf::rt::read_proto2_enum_with_unknown_fields_into(wire_type, is, &mut self.registration_source, 2, &mut self.unknown_fields)?
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_string_into(wire_type, is, &mut self.phantom_subnet)?;
+                },
+                4 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.sessions = ::std::option::Option::Some(tmp);
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if let Some(v) = self.transport {
+            my_size += ::protobuf::rt::enum_size(1, v);
+        }
+        if let Some(v) = self.registration_source {
+            my_size += ::protobuf::rt::enum_size(2, v);
+        }
+        if let Some(ref v) = self.phantom_subnet.as_ref() {
+            my_size += ::protobuf::rt::string_size(3, &v);
+        }
+        if let Some(v) = self.sessions {
+            my_size += ::protobuf::rt::value_size(4, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if let Some(v) = self.transport {
+            os.write_enum(1, ::protobuf::ProtobufEnum::value(&v))?;
+        }
+        if let Some(v) = self.registration_source {
+            os.write_enum(2, ::protobuf::ProtobufEnum::value(&v))?;
+        }
+        if let Some(ref v) = self.phantom_subnet.as_ref() {
+            os.write_string(3, &v)?;
+        }
+        if let Some(v) = self.sessions {
+            os.write_uint64(4, v)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> NeverMatchedCount {
+        NeverMatchedCount::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeEnum<TransportType>>(
+                "transport",
+                |m: &NeverMatchedCount| { &m.transport },
+                |m: &mut NeverMatchedCount| { &mut m.transport },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeEnum<RegistrationSource>>(
+                "registration_source",
+                |m: &NeverMatchedCount| { &m.registration_source },
+                |m: &mut NeverMatchedCount| { &mut m.registration_source },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_singular_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "phantom_subnet",
+                |m: &NeverMatchedCount| { &m.phantom_subnet },
+                |m: &mut NeverMatchedCount| { &mut m.phantom_subnet },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "sessions",
+                |m: &NeverMatchedCount| { &m.sessions },
+                |m: &mut NeverMatchedCount| { &mut m.sessions },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<NeverMatchedCount>(
+                "NeverMatchedCount",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static NeverMatchedCount {
+        static instance: ::protobuf::rt::LazyV2<NeverMatchedCount> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(NeverMatchedCount::new)
+    }
+}
+
+impl ::protobuf::Clear for NeverMatchedCount {
+    fn clear(&mut self) {
+        self.transport = ::std::option::Option::None;
+        self.registration_source = ::std::option::Option::None;
+        self.phantom_subnet.clear();
+        self.sessions = ::std::option::Option::None;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for NeverMatchedCount {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for NeverMatchedCount {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct DetectorToStation {
+    // message fields
+    event: ::std::option::Option<DetectorEvent>,
+    session_id: ::std::option::Option<u64>,
+    phantom_ip: ::protobuf::SingularField<::std::string::String>,
+    client_ip: ::protobuf::SingularField<::std::string::String>,
+    phantom_port: ::std::option::Option<u32>,
+    client_port: ::std::option::Option<u32>,
+    registration_source: ::std::option::Option<RegistrationSource>,
+    pub never_matched: ::protobuf::RepeatedField<NeverMatchedCount>,
+    config_epoch: ::std::option::Option<u64>,
+    config_error: ::protobuf::SingularField<::std::string::String>,
+    phantom_subnets_generation: ::std::option::Option<u64>,
+    connections: ::std::option::Option<u32>,
+    packets: ::std::option::Option<u64>,
+    bytes: ::std::option::Option<u64>,
+    affinity: ::std::option::Option<u32>,
+    alert: ::std::option::Option<SessionCountChange>,
+    session_count: ::std::option::Option<u64>,
+    previous_session_count: ::std::option::Option<u64>,
+    core: ::std::option::Option<u32>,
+    pub sequence_gaps: ::protobuf::RepeatedField<SequenceGapCount>,
+    group_id: ::std::option::Option<u64>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a DetectorToStation {
+    fn default() -> &'a DetectorToStation {
+        <DetectorToStation as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl DetectorToStation {
+    pub fn new() -> DetectorToStation {
+        ::std::default::Default::default()
+    }
+
+    // optional .tapdance.DetectorEvent event = 1;
+
+
+    pub fn get_event(&self) -> DetectorEvent {
+        self.event.unwrap_or(DetectorEvent::UnknownEvent)
+    }
+    pub fn clear_event(&mut self) {
+        self.event = ::std::option::Option::None;
+    }
+
+    pub fn has_event(&self) -> bool {
+        self.event.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_event(&mut self, v: DetectorEvent) {
+        self.event = ::std::option::Option::Some(v);
+    }
+
+    // optional uint64 session_id = 2;
+
+
+    pub fn get_session_id(&self) -> u64 {
+        self.session_id.unwrap_or(0)
+    }
+    pub fn clear_session_id(&mut self) {
+        self.session_id = ::std::option::Option::None;
+    }
+
+    pub fn has_session_id(&self) -> bool {
+        self.session_id.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_session_id(&mut self, v: u64) {
+        self.session_id = ::std::option::Option::Some(v);
+    }
+
+    // optional string phantom_ip = 3;
+
+
+    pub fn get_phantom_ip(&self) -> &str {
+        match self.phantom_ip.as_ref() {
+            Some(v) => &v,
+            None => "",
+        }
+    }
+    pub fn clear_phantom_ip(&mut self) {
+        self.phantom_ip.clear();
+    }
+
+    pub fn has_phantom_ip(&self) -> bool {
+        self.phantom_ip.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_phantom_ip(&mut self, v: ::std::string::String) {
+        self.phantom_ip = ::protobuf::SingularField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_phantom_ip(&mut self) -> &mut ::std::string::String {
+        if self.phantom_ip.is_none() {
+            self.phantom_ip.set_default();
+        }
+        self.phantom_ip.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_phantom_ip(&mut self) -> ::std::string::String {
+        self.phantom_ip.take().unwrap_or_else(|| ::std::string::String::new())
+    }
+
+    // optional string client_ip = 4;
+
+
+    pub fn get_client_ip(&self) -> &str {
+        match self.client_ip.as_ref() {
+            Some(v) => &v,
+            None => "",
+        }
+    }
+    pub fn clear_client_ip(&mut self) {
+        self.client_ip.clear();
+    }
+
+    pub fn has_client_ip(&self) -> bool {
+        self.client_ip.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_client_ip(&mut self, v: ::std::string::String) {
+        self.client_ip = ::protobuf::SingularField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_client_ip(&mut self) -> &mut ::std::string::String {
+        if self.client_ip.is_none() {
+            self.client_ip.set_default();
+        }
+        self.client_ip.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_client_ip(&mut self) -> ::std::string::String {
+        self.client_ip.take().unwrap_or_else(|| ::std::string::String::new())
+    }
+
+    // optional uint32 phantom_port = 5;
+
+
+    pub fn get_phantom_port(&self) -> u32 {
+        self.phantom_port.unwrap_or(0)
+    }
+    pub fn clear_phantom_port(&mut self) {
+        self.phantom_port = ::std::option::Option::None;
+    }
+
+    pub fn has_phantom_port(&self) -> bool {
+        self.phantom_port.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_phantom_port(&mut self, v: u32) {
+        self.phantom_port = ::std::option::Option::Some(v);
+    }
+
+    // optional uint32 client_port = 6;
+
+
+    pub fn get_client_port(&self) -> u32 {
+        self.client_port.unwrap_or(0)
+    }
+    pub fn clear_client_port(&mut self) {
+        self.client_port = ::std::option::Option::None;
+    }
+
+    pub fn has_client_port(&self) -> bool {
+        self.client_port.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_client_port(&mut self, v: u32) {
+        self.client_port = ::std::option::Option::Some(v);
+    }
+
+    // optional .tapdance.RegistrationSource registration_source = 7;
+
+
+    pub fn get_registration_source(&self) -> RegistrationSource {
+        self.registration_source.unwrap_or(RegistrationSource::Unspecified)
+    }
+    pub fn clear_registration_source(&mut self) {
+        self.registration_source = ::std::option::Option::None;
+    }
+
+    pub fn has_registration_source(&self) -> bool {
+        self.registration_source.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_registration_source(&mut self, v: RegistrationSource) {
+        self.registration_source = ::std::option::Option::Some(v);
+    }
+
+    // repeated .tapdance.NeverMatchedCount never_matched = 8;
+
+
+    pub fn get_never_matched(&self) -> &[NeverMatchedCount] {
+        &self.never_matched
+    }
+    pub fn clear_never_matched(&mut self) {
+        self.never_matched.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_never_matched(&mut self, v: ::protobuf::RepeatedField<NeverMatchedCount>) {
+        self.never_matched = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_never_matched(&mut self) -> &mut ::protobuf::RepeatedField<NeverMatchedCount> {
+        &mut self.never_matched
+    }
+
+    // Take field
+    pub fn take_never_matched(&mut self) -> ::protobuf::RepeatedField<NeverMatchedCount> {
+        ::std::mem::replace(&mut self.never_matched, ::protobuf::RepeatedField::new())
+    }
+
+    // optional uint64 config_epoch = 9;
+
+
+    pub fn get_config_epoch(&self) -> u64 {
+        self.config_epoch.unwrap_or(0)
+    }
+    pub fn clear_config_epoch(&mut self) {
+        self.config_epoch = ::std::option::Option::None;
+    }
+
+    pub fn has_config_epoch(&self) -> bool {
+        self.config_epoch.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_config_epoch(&mut self, v: u64) {
+        self.config_epoch = ::std::option::Option::Some(v);
+    }
+
+    // optional string config_error = 10;
+
+
+    pub fn get_config_error(&self) -> &str {
+        match self.config_error.as_ref() {
+            Some(v) => &v,
+            None => "",
+        }
+    }
+    pub fn clear_config_error(&mut self) {
+        self.config_error.clear();
+    }
+
+    pub fn has_config_error(&self) -> bool {
+        self.config_error.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_config_error(&mut self, v: ::std::string::String) {
+        self.config_error = ::protobuf::SingularField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_config_error(&mut self) -> &mut ::std::string::String {
+        if self.config_error.is_none() {
+            self.config_error.set_default();
+        }
+        self.config_error.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_config_error(&mut self) -> ::std::string::String {
+        self.config_error.take().unwrap_or_else(|| ::std::string::String::new())
+    }
+
+    // optional uint64 phantom_subnets_generation = 11;
+
+
+    pub fn get_phantom_subnets_generation(&self) -> u64 {
+        self.phantom_subnets_generation.unwrap_or(0)
+    }
+    pub fn clear_phantom_subnets_generation(&mut self) {
+        self.phantom_subnets_generation = ::std::option::Option::None;
+    }
+
+    pub fn has_phantom_subnets_generation(&self) -> bool {
+        self.phantom_subnets_generation.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_phantom_subnets_generation(&mut self, v: u64) {
+        self.phantom_subnets_generation = ::std::option::Option::Some(v);
+    }
+
+    // optional uint32 connections = 12;
+
+
+    pub fn get_connections(&self) -> u32 {
+        self.connections.unwrap_or(0)
+    }
+    pub fn clear_connections(&mut self) {
+        self.connections = ::std::option::Option::None;
+    }
+
+    pub fn has_connections(&self) -> bool {
+        self.connections.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_connections(&mut self, v: u32) {
+        self.connections = ::std::option::Option::Some(v);
+    }
+
+    // optional uint64 packets = 13;
+
+
+    pub fn get_packets(&self) -> u64 {
+        self.packets.unwrap_or(0)
+    }
+    pub fn clear_packets(&mut self) {
+        self.packets = ::std::option::Option::None;
+    }
+
+    pub fn has_packets(&self) -> bool {
+        self.packets.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_packets(&mut self, v: u64) {
+        self.packets = ::std::option::Option::Some(v);
+    }
+
+    // optional uint64 bytes = 14;
+
+
+    pub fn get_bytes(&self) -> u64 {
+        self.bytes.unwrap_or(0)
+    }
+    pub fn clear_bytes(&mut self) {
+        self.bytes = ::std::option::Option::None;
+    }
+
+    pub fn has_bytes(&self) -> bool {
+        self.bytes.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_bytes(&mut self, v: u64) {
+        self.bytes = ::std::option::Option::Some(v);
+    }
+
+    // optional uint32 affinity = 15;
+
+
+    pub fn get_affinity(&self) -> u32 {
+        self.affinity.unwrap_or(0)
+    }
+    pub fn clear_affinity(&mut self) {
+        self.affinity = ::std::option::Option::None;
+    }
+
+    pub fn has_affinity(&self) -> bool {
+        self.affinity.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_affinity(&mut self, v: u32) {
+        self.affinity = ::std::option::Option::Some(v);
+    }
+
+    // optional .tapdance.SessionCountChange alert = 16;
+
+
+    pub fn get_alert(&self) -> SessionCountChange {
+        self.alert.unwrap_or(SessionCountChange::UnknownChange)
+    }
+    pub fn clear_alert(&mut self) {
+        self.alert = ::std::option::Option::None;
+    }
+
+    pub fn has_alert(&self) -> bool {
+        self.alert.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_alert(&mut self, v: SessionCountChange) {
+        self.alert = ::std::option::Option::Some(v);
+    }
+
+    // optional uint64 session_count = 17;
+
+
+    pub fn get_session_count(&self) -> u64 {
+        self.session_count.unwrap_or(0)
+    }
+    pub fn clear_session_count(&mut self) {
+        self.session_count = ::std::option::Option::None;
+    }
+
+    pub fn has_session_count(&self) -> bool {
+        self.session_count.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_session_count(&mut self, v: u64) {
+        self.session_count = ::std::option::Option::Some(v);
+    }
+
+    // optional uint64 previous_session_count = 18;
+
+
+    pub fn get_previous_session_count(&self) -> u64 {
+        self.previous_session_count.unwrap_or(0)
+    }
+    pub fn clear_previous_session_count(&mut self) {
+        self.previous_session_count = ::std::option::Option::None;
+    }
+
+    pub fn has_previous_session_count(&self) -> bool {
+        self.previous_session_count.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_previous_session_count(&mut self, v: u64) {
+        self.previous_session_count = ::std::option::Option::Some(v);
+    }
+
+    // optional uint32 core = 19;
+
+
+    pub fn get_core(&self) -> u32 {
+        self.core.unwrap_or(0)
+    }
+    pub fn clear_core(&mut self) {
+        self.core = ::std::option::Option::None;
+    }
+
+    pub fn has_core(&self) -> bool {
+        self.core.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_core(&mut self, v: u32) {
+        self.core = ::std::option::Option::Some(v);
+    }
+
+    // repeated .tapdance.SequenceGapCount sequence_gaps = 20;
+
+
+    pub fn get_sequence_gaps(&self) -> &[SequenceGapCount] {
+        &self.sequence_gaps
+    }
+    pub fn clear_sequence_gaps(&mut self) {
+        self.sequence_gaps.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_sequence_gaps(&mut self, v: ::protobuf::RepeatedField<SequenceGapCount>) {
+        self.sequence_gaps = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_sequence_gaps(&mut self) -> &mut ::protobuf::RepeatedField<SequenceGapCount> {
+        &mut self.sequence_gaps
+    }
+
+    // Take field
+    pub fn take_sequence_gaps(&mut self) -> ::protobuf::RepeatedField<SequenceGapCount> {
+        ::std::mem::replace(&mut self.sequence_gaps, ::protobuf::RepeatedField::new())
+    }
+
+    // optional uint64 group_id = 21;
+
+
+    pub fn get_group_id(&self) -> u64 {
+        self.group_id.unwrap_or(0)
+    }
+    pub fn clear_group_id(&mut self) {
+        self.group_id = ::std::option::Option::None;
+    }
+
+    pub fn has_group_id(&self) -> bool {
+        self.group_id.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_group_id(&mut self, v: u64) {
+        self.group_id = ::std::option::Option::Some(v);
+    }
+}
+
+impl ::protobuf::Message for DetectorToStation {
+    fn is_initialized(&self) -> bool {
+        for v in &self.never_matched {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        for v in &self.sequence_gaps {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_proto2_enum_with_unknown_fields_into(wire_type, is, &mut self.event, 1, &mut self.unknown_fields)?
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.session_id = ::std::option::Option::Some(tmp);
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_string_into(wire_type, is, &mut self.phantom_ip)?;
+                },
+                4 => {
+                    ::protobuf::rt::read_singular_string_into(wire_type, is, &mut self.client_ip)?;
+                },
+                5 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.phantom_port = ::std::option::Option::Some(tmp);
+                },
+                6 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.client_port = ::std::option::Option::Some(tmp);
+                },
+                7 => {
+                    ::protobuf::rt::read_proto2_enum_with_unknown_fields_into(wire_type, is, &mut self.registration_source, 7, &mut self.unknown_fields)?
+                },
+                8 => {
+                    ::protobuf::rt::read_repeated_message_into(wire_type, is, &mut self.never_matched)?;
+                },
+                9 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.config_epoch = ::std::option::Option::Some(tmp);
+                },
+                10 => {
+                    ::protobuf::rt::read_singular_string_into(wire_type, is, &mut self.config_error)?;
+                },
+                11 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.phantom_subnets_generation = ::std::option::Option::Some(tmp);
+                },
+                12 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.connections = ::std::option::Option::Some(tmp);
+                },
+                13 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.packets = ::std::option::Option::Some(tmp);
+                },
+                14 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.bytes = ::std::option::Option::Some(tmp);
+                },
+                15 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.affinity = ::std::option::Option::Some(tmp);
+                },
+                16 => {
+                    ::protobuf::rt::read_proto2_enum_with_unknown_fields_into(wire_type, is, &mut self.alert, 16, &mut self.unknown_fields)?
+                },
+                17 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.session_count = ::std::option::Option::Some(tmp);
+                },
+                18 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.previous_session_count = ::std::option::Option::Some(tmp);
+                },
+                19 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.core = ::std::option::Option::Some(tmp);
+                },
+                20 => {
+                    ::protobuf::rt::read_repeated_message_into(wire_type, is, &mut self.sequence_gaps)?;
+                },
+                21 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.group_id = ::std::option::Option::Some(tmp);
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if let Some(v) = self.event {
+            my_size += ::protobuf::rt::enum_size(1, v);
+        }
+        if let Some(v) = self.session_id {
+            my_size += ::protobuf::rt::value_size(2, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(ref v) = self.phantom_ip.as_ref() {
+            my_size += ::protobuf::rt::string_size(3, &v);
+        }
+        if let Some(ref v) = self.client_ip.as_ref() {
+            my_size += ::protobuf::rt::string_size(4, &v);
+        }
+        if let Some(v) = self.phantom_port {
+            my_size += ::protobuf::rt::value_size(5, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.client_port {
+            my_size += ::protobuf::rt::value_size(6, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.registration_source {
+            my_size += ::protobuf::rt::enum_size(7, v);
+        }
+        for value in &self.never_matched {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        };
+        if let Some(v) = self.config_epoch {
+            my_size += ::protobuf::rt::value_size(9, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(ref v) = self.config_error.as_ref() {
+            my_size += ::protobuf::rt::string_size(10, &v);
+        }
+        if let Some(v) = self.phantom_subnets_generation {
+            my_size += ::protobuf::rt::value_size(11, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.connections {
+            my_size += ::protobuf::rt::value_size(12, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.packets {
+            my_size += ::protobuf::rt::value_size(13, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.bytes {
+            my_size += ::protobuf::rt::value_size(14, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.affinity {
+            my_size += ::protobuf::rt::value_size(15, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.alert {
+            my_size += ::protobuf::rt::enum_size(16, v);
+        }
+        if let Some(v) = self.session_count {
+            my_size += ::protobuf::rt::value_size(17, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.previous_session_count {
+            my_size += ::protobuf::rt::value_size(18, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.core {
+            my_size += ::protobuf::rt::value_size(19, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        for value in &self.sequence_gaps {
+            let len = value.compute_size();
+            my_size += 2 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        };
+        if let Some(v) = self.group_id {
+            my_size += ::protobuf::rt::value_size(21, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if let Some(v) = self.event {
+            os.write_enum(1, ::protobuf::ProtobufEnum::value(&v))?;
+        }
+        if let Some(v) = self.session_id {
+            os.write_uint64(2, v)?;
+        }
+        if let Some(ref v) = self.phantom_ip.as_ref() {
+            os.write_string(3, &v)?;
+        }
+        if let Some(ref v) = self.client_ip.as_ref() {
+            os.write_string(4, &v)?;
+        }
+        if let Some(v) = self.phantom_port {
+            os.write_uint32(5, v)?;
+        }
+        if let Some(v) = self.client_port {
+            os.write_uint32(6, v)?;
+        }
+        if let Some(v) = self.registration_source {
+            os.write_enum(7, ::protobuf::ProtobufEnum::value(&v))?;
+        }
+        for v in &self.never_matched {
+            os.write_tag(8, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        };
+        if let Some(v) = self.config_epoch {
+            os.write_uint64(9, v)?;
+        }
+        if let Some(ref v) = self.config_error.as_ref() {
+            os.write_string(10, &v)?;
+        }
+        if let Some(v) = self.phantom_subnets_generation {
+            os.write_uint64(11, v)?;
+        }
+        if let Some(v) = self.connections {
+            os.write_uint32(12, v)?;
+        }
+        if let Some(v) = self.packets {
+            os.write_uint64(13, v)?;
+        }
+        if let Some(v) = self.bytes {
+            os.write_uint64(14, v)?;
+        }
+        if let Some(v) = self.affinity {
+            os.write_uint32(15, v)?;
+        }
+        if let Some(v) = self.alert {
+            os.write_enum(16, ::protobuf::ProtobufEnum::value(&v))?;
+        }
+        if let Some(v) = self.session_count {
+            os.write_uint64(17, v)?;
+        }
+        if let Some(v) = self.previous_session_count {
+            os.write_uint64(18, v)?;
+        }
+        if let Some(v) = self.core {
+            os.write_uint32(19, v)?;
+        }
+        for v in &self.sequence_gaps {
+            os.write_tag(20, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        };
+        if let Some(v) = self.group_id {
+            os.write_uint64(21, v)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> DetectorToStation {
+        DetectorToStation::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeEnum<DetectorEvent>>(
+                "event",
+                |m: &DetectorToStation| { &m.event },
+                |m: &mut DetectorToStation| { &mut m.event },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "session_id",
+                |m: &DetectorToStation| { &m.session_id },
+                |m: &mut DetectorToStation| { &mut m.session_id },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_singular_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "phantom_ip",
+                |m: &DetectorToStation| { &m.phantom_ip },
+                |m: &mut DetectorToStation| { &mut m.phantom_ip },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_singular_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "client_ip",
+                |m: &DetectorToStation| { &m.client_ip },
+                |m: &mut DetectorToStation| { &mut m.client_ip },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "phantom_port",
+                |m: &DetectorToStation| { &m.phantom_port },
+                |m: &mut DetectorToStation| { &mut m.phantom_port },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "client_port",
+                |m: &DetectorToStation| { &m.client_port },
+                |m: &mut DetectorToStation| { &mut m.client_port },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeEnum<RegistrationSource>>(
+                "registration_source",
+                |m: &DetectorToStation| { &m.registration_source },
+                |m: &mut DetectorToStation| { &mut m.registration_source },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<NeverMatchedCount>>(
+                "never_matched",
+                |m: &DetectorToStation| { &m.never_matched },
+                |m: &mut DetectorToStation| { &mut m.never_matched },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "config_epoch",
+                |m: &DetectorToStation| { &m.config_epoch },
+                |m: &mut DetectorToStation| { &mut m.config_epoch },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_singular_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "config_error",
+                |m: &DetectorToStation| { &m.config_error },
+                |m: &mut DetectorToStation| { &mut m.config_error },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "phantom_subnets_generation",
+                |m: &DetectorToStation| { &m.phantom_subnets_generation },
+                |m: &mut DetectorToStation| { &mut m.phantom_subnets_generation },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "connections",
+                |m: &DetectorToStation| { &m.connections },
+                |m: &mut DetectorToStation| { &mut m.connections },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "packets",
+                |m: &DetectorToStation| { &m.packets },
+                |m: &mut DetectorToStation| { &mut m.packets },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "bytes",
+                |m: &DetectorToStation| { &m.bytes },
+                |m: &mut DetectorToStation| { &mut m.bytes },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "affinity",
+                |m: &DetectorToStation| { &m.affinity },
+                |m: &mut DetectorToStation| { &mut m.affinity },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeEnum<SessionCountChange>>(
+                "alert",
+                |m: &DetectorToStation| { &m.alert },
+                |m: &mut DetectorToStation| { &mut m.alert },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "session_count",
+                |m: &DetectorToStation| { &m.session_count },
+                |m: &mut DetectorToStation| { &mut m.session_count },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "previous_session_count",
+                |m: &DetectorToStation| { &m.previous_session_count },
+                |m: &mut DetectorToStation| { &mut m.previous_session_count },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "core",
+                |m: &DetectorToStation| { &m.core },
+                |m: &mut DetectorToStation| { &mut m.core },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<SequenceGapCount>>(
+                "sequence_gaps",
+                |m: &DetectorToStation| { &m.sequence_gaps },
+                |m: &mut DetectorToStation| { &mut m.sequence_gaps },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "group_id",
+                |m: &DetectorToStation| { &m.group_id },
+                |m: &mut DetectorToStation| { &mut m.group_id },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<DetectorToStation>(
+                "DetectorToStation",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static DetectorToStation {
+        static instance: ::protobuf::rt::LazyV2<DetectorToStation> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(DetectorToStation::new)
+    }
+}
+
+impl ::protobuf::Clear for DetectorToStation {
+    fn clear(&mut self) {
+        self.event = ::std::option::Option::None;
+        self.session_id = ::std::option::Option::None;
+        self.phantom_ip.clear();
+        self.client_ip.clear();
+        self.phantom_port = ::std::option::Option::None;
+        self.client_port = ::std::option::Option::None;
+        self.registration_source = ::std::option::Option::None;
+        self.never_matched.clear();
+        self.config_epoch = ::std::option::Option::None;
+        self.config_error.clear();
+        self.phantom_subnets_generation = ::std::option::Option::None;
+        self.connections = ::std::option::Option::None;
+        self.packets = ::std::option::Option::None;
+        self.bytes = ::std::option::Option::None;
+        self.affinity = ::std::option::Option::None;
+        self.alert = ::std::option::Option::None;
+        self.session_count = ::std::option::Option::None;
+        self.previous_session_count = ::std::option::Option::None;
+        self.core = ::std::option::Option::None;
+        self.sequence_gaps.clear();
+        self.group_id = ::std::option::Option::None;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for DetectorToStation {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for DetectorToStation {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct QaSample {
+    // message fields
+    transport: ::std::option::Option<TransportType>,
+    registration_source: ::std::option::Option<RegistrationSource>,
+    phantom_port: ::std::option::Option<u32>,
+    ipv6: ::std::option::Option<bool>,
+    window_ms: ::std::option::Option<u32>,
+    connections: ::std::option::Option<u32>,
+    packets: ::std::option::Option<u64>,
+    bytes: ::std::option::Option<u64>,
+    handshake_rtt_us: ::std::option::Option<u64>,
+    first_data_us: ::std::option::Option<u64>,
+    tls_fingerprint: ::protobuf::SingularField<::std::string::String>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a QaSample {
+    fn default() -> &'a QaSample {
+        <QaSample as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl QaSample {
+    pub fn new() -> QaSample {
+        ::std::default::Default::default()
+    }
+
+    // optional .tapdance.TransportType transport = 1;
+
+
+    pub fn get_transport(&self) -> TransportType {
+        self.transport.unwrap_or(TransportType::Null)
+    }
+    pub fn clear_transport(&mut self) {
+        self.transport = ::std::option::Option::None;
+    }
+
+    pub fn has_transport(&self) -> bool {
+        self.transport.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_transport(&mut self, v: TransportType) {
+        self.transport = ::std::option::Option::Some(v);
+    }
+
+    // optional .tapdance.RegistrationSource registration_source = 2;
+
+
+    pub fn get_registration_source(&self) -> RegistrationSource {
+        self.registration_source.unwrap_or(RegistrationSource::Unspecified)
+    }
+    pub fn clear_registration_source(&mut self) {
+        self.registration_source = ::std::option::Option::None;
+    }
+
+    pub fn has_registration_source(&self) -> bool {
+        self.registration_source.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_registration_source(&mut self, v: RegistrationSource) {
+        self.registration_source = ::std::option::Option::Some(v);
+    }
+
+    // optional uint32 phantom_port = 3;
+
+
+    pub fn get_phantom_port(&self) -> u32 {
+        self.phantom_port.unwrap_or(0)
+    }
+    pub fn clear_phantom_port(&mut self) {
+        self.phantom_port = ::std::option::Option::None;
+    }
+
+    pub fn has_phantom_port(&self) -> bool {
+        self.phantom_port.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_phantom_port(&mut self, v: u32) {
+        self.phantom_port = ::std::option::Option::Some(v);
+    }
+
+    // optional bool ipv6 = 4;
+
+
+    pub fn get_ipv6(&self) -> bool {
+        self.ipv6.unwrap_or(false)
+    }
+    pub fn clear_ipv6(&mut self) {
+        self.ipv6 = ::std::option::Option::None;
+    }
+
+    pub fn has_ipv6(&self) -> bool {
+        self.ipv6.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_ipv6(&mut self, v: bool) {
+        self.ipv6 = ::std::option::Option::Some(v);
+    }
+
+    // optional uint32 window_ms = 5;
+
+
+    pub fn get_window_ms(&self) -> u32 {
+        self.window_ms.unwrap_or(0)
+    }
+    pub fn clear_window_ms(&mut self) {
+        self.window_ms = ::std::option::Option::None;
+    }
+
+    pub fn has_window_ms(&self) -> bool {
+        self.window_ms.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_window_ms(&mut self, v: u32) {
+        self.window_ms = ::std::option::Option::Some(v);
+    }
+
+    // optional uint32 connections = 6;
+
+
+    pub fn get_connections(&self) -> u32 {
+        self.connections.unwrap_or(0)
+    }
+    pub fn clear_connections(&mut self) {
+        self.connections = ::std::option::Option::None;
+    }
+
+    pub fn has_connections(&self) -> bool {
+        self.connections.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_connections(&mut self, v: u32) {
+        self.connections = ::std::option::Option::Some(v);
+    }
+
+    // optional uint64 packets = 7;
+
+
+    pub fn get_packets(&self) -> u64 {
+        self.packets.unwrap_or(0)
+    }
+    pub fn clear_packets(&mut self) {
+        self.packets = ::std::option::Option::None;
+    }
+
+    pub fn has_packets(&self) -> bool {
+        self.packets.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_packets(&mut self, v: u64) {
+        self.packets = ::std::option::Option::Some(v);
+    }
+
+    // optional uint64 bytes = 8;
+
+
+    pub fn get_bytes(&self) -> u64 {
+        self.bytes.unwrap_or(0)
+    }
+    pub fn clear_bytes(&mut self) {
+        self.bytes = ::std::option::Option::None;
+    }
+
+    pub fn has_bytes(&self) -> bool {
+        self.bytes.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_bytes(&mut self, v: u64) {
+        self.bytes = ::std::option::Option::Some(v);
+    }
+
+    // optional uint64 handshake_rtt_us = 9;
+
+
+    pub fn get_handshake_rtt_us(&self) -> u64 {
+        self.handshake_rtt_us.unwrap_or(0)
+    }
+    pub fn clear_handshake_rtt_us(&mut self) {
+        self.handshake_rtt_us = ::std::option::Option::None;
+    }
+
+    pub fn has_handshake_rtt_us(&self) -> bool {
+        self.handshake_rtt_us.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_handshake_rtt_us(&mut self, v: u64) {
+        self.handshake_rtt_us = ::std::option::Option::Some(v);
+    }
+
+    // optional uint64 first_data_us = 10;
+
+
+    pub fn get_first_data_us(&self) -> u64 {
+        self.first_data_us.unwrap_or(0)
+    }
+    pub fn clear_first_data_us(&mut self) {
+        self.first_data_us = ::std::option::Option::None;
+    }
+
+    pub fn has_first_data_us(&self) -> bool {
+        self.first_data_us.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_first_data_us(&mut self, v: u64) {
+        self.first_data_us = ::std::option::Option::Some(v);
+    }
+
+    // optional string tls_fingerprint = 11;
+
+
+    pub fn get_tls_fingerprint(&self) -> &str {
+        match self.tls_fingerprint.as_ref() {
+            Some(v) => &v,
+            None => "",
+        }
+    }
+    pub fn clear_tls_fingerprint(&mut self) {
+        self.tls_fingerprint.clear();
+    }
+
+    pub fn has_tls_fingerprint(&self) -> bool {
+        self.tls_fingerprint.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_tls_fingerprint(&mut self, v: ::std::string::String) {
+        self.tls_fingerprint = ::protobuf::SingularField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_tls_fingerprint(&mut self) -> &mut ::std::string::String {
+        if self.tls_fingerprint.is_none() {
+            self.tls_fingerprint.set_default();
+        }
+        self.tls_fingerprint.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_tls_fingerprint(&mut self) -> ::std::string::String {
+        self.tls_fingerprint.take().unwrap_or_else(|| ::std::string::String::new())
+    }
+}
+
+impl ::protobuf::Message for QaSample {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_proto2_enum_with_unknown_fields_into(wire_type, is, &mut self.transport, 1, &mut self.unknown_fields)?
+                },
+                2 => {
+                    ::protobuf::rt::read_proto2_enum_with_unknown_fields_into(wire_type, is, &mut self.registration_source, 2, &mut self.unknown_fields)?
+                },
+                3 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.phantom_port = ::std::option::Option::Some(tmp);
+                },
+                4 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.ipv6 = ::std::option::Option::Some(tmp);
+                },
+                5 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.window_ms = ::std::option::Option::Some(tmp);
+                },
+                6 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.connections = ::std::option::Option::Some(tmp);
+                },
+                7 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.packets = ::std::option::Option::Some(tmp);
+                },
+                8 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.bytes = ::std::option::Option::Some(tmp);
+                },
+                9 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.handshake_rtt_us = ::std::option::Option::Some(tmp);
+                },
+                10 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.first_data_us = ::std::option::Option::Some(tmp);
+                },
+                11 => {
+                    ::protobuf::rt::read_singular_string_into(wire_type, is, &mut self.tls_fingerprint)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if let Some(v) = self.transport {
+            my_size += ::protobuf::rt::enum_size(1, v);
+        }
+        if let Some(v) = self.registration_source {
+            my_size += ::protobuf::rt::enum_size(2, v);
+        }
+        if let Some(v) = self.phantom_port {
+            my_size += ::protobuf::rt::value_size(3, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.ipv6 {
+            my_size += 2;
+        }
+        if let Some(v) = self.window_ms {
+            my_size += ::protobuf::rt::value_size(5, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.connections {
+            my_size += ::protobuf::rt::value_size(6, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.packets {
+            my_size += ::protobuf::rt::value_size(7, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.bytes {
+            my_size += ::protobuf::rt::value_size(8, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.handshake_rtt_us {
+            my_size += ::protobuf::rt::value_size(9, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.first_data_us {
+            my_size += ::protobuf::rt::value_size(10, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(ref v) = self.tls_fingerprint.as_ref() {
+            my_size += ::protobuf::rt::string_size(11, &v);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if let Some(v) = self.transport {
+            os.write_enum(1, ::protobuf::ProtobufEnum::value(&v))?;
+        }
+        if let Some(v) = self.registration_source {
+            os.write_enum(2, ::protobuf::ProtobufEnum::value(&v))?;
+        }
+        if let Some(v) = self.phantom_port {
+            os.write_uint32(3, v)?;
+        }
+        if let Some(v) = self.ipv6 {
+            os.write_bool(4, v)?;
+        }
+        if let Some(v) = self.window_ms {
+            os.write_uint32(5, v)?;
+        }
+        if let Some(v) = self.connections {
+            os.write_uint32(6, v)?;
+        }
+        if let Some(v) = self.packets {
+            os.write_uint64(7, v)?;
+        }
+        if let Some(v) = self.bytes {
+            os.write_uint64(8, v)?;
+        }
+        if let Some(v) = self.handshake_rtt_us {
+            os.write_uint64(9, v)?;
+        }
+        if let Some(v) = self.first_data_us {
+            os.write_uint64(10, v)?;
+        }
+        if let Some(ref v) = self.tls_fingerprint.as_ref() {
+            os.write_string(11, &v)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> QaSample {
+        QaSample::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeEnum<TransportType>>(
+                "transport",
+                |m: &QaSample| { &m.transport },
+                |m: &mut QaSample| { &mut m.transport },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeEnum<RegistrationSource>>(
+                "registration_source",
+                |m: &QaSample| { &m.registration_source },
+                |m: &mut QaSample| { &mut m.registration_source },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "phantom_port",
+                |m: &QaSample| { &m.phantom_port },
+                |m: &mut QaSample| { &mut m.phantom_port },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeBool>(
+                "ipv6",
+                |m: &QaSample| { &m.ipv6 },
+                |m: &mut QaSample| { &mut m.ipv6 },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "window_ms",
+                |m: &QaSample| { &m.window_ms },
+                |m: &mut QaSample| { &mut m.window_ms },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "connections",
+                |m: &QaSample| { &m.connections },
+                |m: &mut QaSample| { &mut m.connections },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "packets",
+                |m: &QaSample| { &m.packets },
+                |m: &mut QaSample| { &mut m.packets },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "bytes",
+                |m: &QaSample| { &m.bytes },
+                |m: &mut QaSample| { &mut m.bytes },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "handshake_rtt_us",
+                |m: &QaSample| { &m.handshake_rtt_us },
+                |m: &mut QaSample| { &mut m.handshake_rtt_us },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "first_data_us",
+                |m: &QaSample| { &m.first_data_us },
+                |m: &mut QaSample| { &mut m.first_data_us },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_singular_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "tls_fingerprint",
+                |m: &QaSample| { &m.tls_fingerprint },
+                |m: &mut QaSample| { &mut m.tls_fingerprint },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<QaSample>(
+                "QaSample",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static QaSample {
+        static instance: ::protobuf::rt::LazyV2<QaSample> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(QaSample::new)
+    }
+}
+
+impl ::protobuf::Clear for QaSample {
+    fn clear(&mut self) {
+        self.transport = ::std::option::Option::None;
+        self.registration_source = ::std::option::Option::None;
+        self.phantom_port = ::std::option::Option::None;
+        self.ipv6 = ::std::option::Option::None;
+        self.window_ms = ::std::option::Option::None;
+        self.connections = ::std::option::Option::None;
+        self.packets = ::std::option::Option::None;
+        self.bytes = ::std::option::Option::None;
+        self.handshake_rtt_us = ::std::option::Option::None;
+        self.first_data_us = ::std::option::Option::None;
+        self.tls_fingerprint.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for QaSample {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for QaSample {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct ReplicatedSession {
+    // message fields
+    client_ip: ::protobuf::SingularField<::std::vec::Vec<u8>>,
+    phantom_ip: ::protobuf::SingularField<::std::vec::Vec<u8>>,
+    phantom_port: ::std::option::Option<u32>,
+    timeout_ns: ::std::option::Option<u64>,
+    registration_source: ::std::option::Option<RegistrationSource>,
+    max_connections: ::std::option::Option<u32>,
+    mode: ::std::option::Option<SessionMode>,
+    tenant_id: ::std::option::Option<u32>,
+    extension_ns: ::std::option::Option<u64>,
+    pub client_ports: ::protobuf::SingularPtrField<PortRange>,
+    group_id: ::std::option::Option<u64>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a ReplicatedSession {
+    fn default() -> &'a ReplicatedSession {
+        <ReplicatedSession as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl ReplicatedSession {
+    pub fn new() -> ReplicatedSession {
+        ::std::default::Default::default()
+    }
+
+    // optional bytes client_ip = 1;
+
+
+    pub fn get_client_ip(&self) -> &[u8] {
+        match self.client_ip.as_ref() {
+            Some(v) => &v,
+            None => &[],
+        }
+    }
+    pub fn clear_client_ip(&mut self) {
+        self.client_ip.clear();
+    }
+
+    pub fn has_client_ip(&self) -> bool {
+        self.client_ip.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_client_ip(&mut self, v: ::std::vec::Vec<u8>) {
+        self.client_ip = ::protobuf::SingularField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_client_ip(&mut self) -> &mut ::std::vec::Vec<u8> {
+        if self.client_ip.is_none() {
+            self.client_ip.set_default();
+        }
+        self.client_ip.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_client_ip(&mut self) -> ::std::vec::Vec<u8> {
+        self.client_ip.take().unwrap_or_else(|| ::std::vec::Vec::new())
+    }
+
+    // optional bytes phantom_ip = 2;
+
+
+    pub fn get_phantom_ip(&self) -> &[u8] {
+        match self.phantom_ip.as_ref() {
+            Some(v) => &v,
+            None => &[],
+        }
+    }
+    pub fn clear_phantom_ip(&mut self) {
+        self.phantom_ip.clear();
+    }
+
+    pub fn has_phantom_ip(&self) -> bool {
+        self.phantom_ip.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_phantom_ip(&mut self, v: ::std::vec::Vec<u8>) {
+        self.phantom_ip = ::protobuf::SingularField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_phantom_ip(&mut self) -> &mut ::std::vec::Vec<u8> {
+        if self.phantom_ip.is_none() {
+            self.phantom_ip.set_default();
+        }
+        self.phantom_ip.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_phantom_ip(&mut self) -> ::std::vec::Vec<u8> {
+        self.phantom_ip.take().unwrap_or_else(|| ::std::vec::Vec::new())
+    }
+
+    // optional uint32 phantom_port = 3;
+
+
+    pub fn get_phantom_port(&self) -> u32 {
+        self.phantom_port.unwrap_or(0)
+    }
+    pub fn clear_phantom_port(&mut self) {
+        self.phantom_port = ::std::option::Option::None;
+    }
+
+    pub fn has_phantom_port(&self) -> bool {
+        self.phantom_port.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_phantom_port(&mut self, v: u32) {
+        self.phantom_port = ::std::option::Option::Some(v);
+    }
+
+    // optional uint64 timeout_ns = 4;
+
+
+    pub fn get_timeout_ns(&self) -> u64 {
+        self.timeout_ns.unwrap_or(0)
+    }
+    pub fn clear_timeout_ns(&mut self) {
+        self.timeout_ns = ::std::option::Option::None;
+    }
+
+    pub fn has_timeout_ns(&self) -> bool {
+        self.timeout_ns.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_timeout_ns(&mut self, v: u64) {
+        self.timeout_ns = ::std::option::Option::Some(v);
+    }
+
+    // optional .tapdance.RegistrationSource registration_source = 5;
+
+
+    pub fn get_registration_source(&self) -> RegistrationSource {
+        self.registration_source.unwrap_or(RegistrationSource::Unspecified)
+    }
+    pub fn clear_registration_source(&mut self) {
+        self.registration_source = ::std::option::Option::None;
+    }
+
+    pub fn has_registration_source(&self) -> bool {
+        self.registration_source.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_registration_source(&mut self, v: RegistrationSource) {
+        self.registration_source = ::std::option::Option::Some(v);
+    }
+
+    // optional uint32 max_connections = 6;
+
+
+    pub fn get_max_connections(&self) -> u32 {
+        self.max_connections.unwrap_or(0)
+    }
+    pub fn clear_max_connections(&mut self) {
+        self.max_connections = ::std::option::Option::None;
+    }
+
+    pub fn has_max_connections(&self) -> bool {
+        self.max_connections.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_max_connections(&mut self, v: u32) {
+        self.max_connections = ::std::option::Option::Some(v);
+    }
+
+    // optional .tapdance.SessionMode mode = 7;
+
+
+    pub fn get_mode(&self) -> SessionMode {
+        self.mode.unwrap_or(SessionMode::Phantom)
+    }
+    pub fn clear_mode(&mut self) {
+        self.mode = ::std::option::Option::None;
+    }
+
+    pub fn has_mode(&self) -> bool {
+        self.mode.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_mode(&mut self, v: SessionMode) {
+        self.mode = ::std::option::Option::Some(v);
+    }
+
+    // optional uint32 tenant_id = 8;
+
+
+    pub fn get_tenant_id(&self) -> u32 {
+        self.tenant_id.unwrap_or(0)
+    }
+    pub fn clear_tenant_id(&mut self) {
+        self.tenant_id = ::std::option::Option::None;
+    }
+
+    pub fn has_tenant_id(&self) -> bool {
+        self.tenant_id.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_tenant_id(&mut self, v: u32) {
+        self.tenant_id = ::std::option::Option::Some(v);
+    }
+
+    // optional uint64 extension_ns = 9;
+
+
+    pub fn get_extension_ns(&self) -> u64 {
+        self.extension_ns.unwrap_or(0)
+    }
+    pub fn clear_extension_ns(&mut self) {
+        self.extension_ns = ::std::option::Option::None;
+    }
+
+    pub fn has_extension_ns(&self) -> bool {
+        self.extension_ns.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_extension_ns(&mut self, v: u64) {
+        self.extension_ns = ::std::option::Option::Some(v);
+    }
+
+    // optional .tapdance.PortRange client_ports = 10;
+
+
+    pub fn get_client_ports(&self) -> &PortRange {
+        self.client_ports.as_ref().unwrap_or_else(|| <PortRange as ::protobuf::Message>::default_instance())
+    }
+    pub fn clear_client_ports(&mut self) {
+        self.client_ports.clear();
+    }
+
+    pub fn has_client_ports(&self) -> bool {
+        self.client_ports.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_client_ports(&mut self, v: PortRange) {
+        self.client_ports = ::protobuf::SingularPtrField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_client_ports(&mut self) -> &mut PortRange {
+        if self.client_ports.is_none() {
+            self.client_ports.set_default();
+        }
+        self.client_ports.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_client_ports(&mut self) -> PortRange {
+        self.client_ports.take().unwrap_or_else(|| PortRange::new())
+    }
+
+    // optional uint64 group_id = 11;
+
+
+    pub fn get_group_id(&self) -> u64 {
+        self.group_id.unwrap_or(0)
+    }
+    pub fn clear_group_id(&mut self) {
+        self.group_id = ::std::option::Option::None;
+    }
+
+    pub fn has_group_id(&self) -> bool {
+        self.group_id.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_group_id(&mut self, v: u64) {
+        self.group_id = ::std::option::Option::Some(v);
+    }
+}
+
+impl ::protobuf::Message for ReplicatedSession {
+    fn is_initialized(&self) -> bool {
+        for v in &self.client_ports {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_bytes_into(wire_type, is, &mut self.client_ip)?;
+                },
+                2 => {
+                    ::protobuf::rt::read_singular_bytes_into(wire_type, is, &mut self.phantom_ip)?;
+                },
+                3 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.phantom_port = ::std::option::Option::Some(tmp);
+                },
+                4 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.timeout_ns = ::std::option::Option::Some(tmp);
+                },
+                5 => {
+                    ::protobuf::rt::read_proto2_enum_with_unknown_fields_into(wire_type, is, &mut self.registration_source, 5, &mut self.unknown_fields)?
+                },
+                6 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.max_connections = ::std::option::Option::Some(tmp);
+                },
+                7 => {
+                    ::protobuf::rt::read_proto2_enum_with_unknown_fields_into(wire_type, is, &mut self.mode, 7, &mut self.unknown_fields)?
+                },
+                8 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.tenant_id = ::std::option::Option::Some(tmp);
+                },
+                9 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.extension_ns = ::std::option::Option::Some(tmp);
+                },
+                10 => {
+                    ::protobuf::rt::read_singular_message_into(wire_type, is, &mut self.client_ports)?;
+                },
+                11 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.group_id = ::std::option::Option::Some(tmp);
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if let Some(ref v) = self.client_ip.as_ref() {
+            my_size += ::protobuf::rt::bytes_size(1, &v);
+        }
+        if let Some(ref v) = self.phantom_ip.as_ref() {
+            my_size += ::protobuf::rt::bytes_size(2, &v);
+        }
+        if let Some(v) = self.phantom_port {
+            my_size += ::protobuf::rt::value_size(3, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.timeout_ns {
+            my_size += ::protobuf::rt::value_size(4, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.registration_source {
+            my_size += ::protobuf::rt::enum_size(5, v);
+        }
+        if let Some(v) = self.max_connections {
+            my_size += ::protobuf::rt::value_size(6, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.mode {
+            my_size += ::protobuf::rt::enum_size(7, v);
+        }
+        if let Some(v) = self.tenant_id {
+            my_size += ::protobuf::rt::value_size(8, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.extension_ns {
+            my_size += ::protobuf::rt::value_size(9, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(ref v) = self.client_ports.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        }
+        if let Some(v) = self.group_id {
+            my_size += ::protobuf::rt::value_size(11, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if let Some(ref v) = self.client_ip.as_ref() {
+            os.write_bytes(1, &v)?;
+        }
+        if let Some(ref v) = self.phantom_ip.as_ref() {
+            os.write_bytes(2, &v)?;
+        }
+        if let Some(v) = self.phantom_port {
+            os.write_uint32(3, v)?;
+        }
+        if let Some(v) = self.timeout_ns {
+            os.write_uint64(4, v)?;
+        }
+        if let Some(v) = self.registration_source {
+            os.write_enum(5, ::protobuf::ProtobufEnum::value(&v))?;
+        }
+        if let Some(v) = self.max_connections {
+            os.write_uint32(6, v)?;
+        }
+        if let Some(v) = self.mode {
+            os.write_enum(7, ::protobuf::ProtobufEnum::value(&v))?;
+        }
+        if let Some(v) = self.tenant_id {
+            os.write_uint32(8, v)?;
+        }
+        if let Some(v) = self.extension_ns {
+            os.write_uint64(9, v)?;
+        }
+        if let Some(ref v) = self.client_ports.as_ref() {
+            os.write_tag(10, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        }
+        if let Some(v) = self.group_id {
+            os.write_uint64(11, v)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> ReplicatedSession {
+        ReplicatedSession::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_singular_field_accessor::<_, ::protobuf::types::ProtobufTypeBytes>(
+                "client_ip",
+                |m: &ReplicatedSession| { &m.client_ip },
+                |m: &mut ReplicatedSession| { &mut m.client_ip },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_singular_field_accessor::<_, ::protobuf::types::ProtobufTypeBytes>(
+                "phantom_ip",
+                |m: &ReplicatedSession| { &m.phantom_ip },
+                |m: &mut ReplicatedSession| { &mut m.phantom_ip },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "phantom_port",
+                |m: &ReplicatedSession| { &m.phantom_port },
+                |m: &mut ReplicatedSession| { &mut m.phantom_port },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "timeout_ns",
+                |m: &ReplicatedSession| { &m.timeout_ns },
+                |m: &mut ReplicatedSession| { &mut m.timeout_ns },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeEnum<RegistrationSource>>(
+                "registration_source",
+                |m: &ReplicatedSession| { &m.registration_source },
+                |m: &mut ReplicatedSession| { &mut m.registration_source },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "max_connections",
+                |m: &ReplicatedSession| { &m.max_connections },
+                |m: &mut ReplicatedSession| { &mut m.max_connections },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeEnum<SessionMode>>(
+                "mode",
+                |m: &ReplicatedSession| { &m.mode },
+                |m: &mut ReplicatedSession| { &mut m.mode },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "tenant_id",
+                |m: &ReplicatedSession| { &m.tenant_id },
+                |m: &mut ReplicatedSession| { &mut m.tenant_id },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "extension_ns",
+                |m: &ReplicatedSession| { &m.extension_ns },
+                |m: &mut ReplicatedSession| { &mut m.extension_ns },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_singular_ptr_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<PortRange>>(
+                "client_ports",
+                |m: &ReplicatedSession| { &m.client_ports },
+                |m: &mut ReplicatedSession| { &mut m.client_ports },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "group_id",
+                |m: &ReplicatedSession| { &m.group_id },
+                |m: &mut ReplicatedSession| { &mut m.group_id },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<ReplicatedSession>(
+                "ReplicatedSession",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static ReplicatedSession {
+        static instance: ::protobuf::rt::LazyV2<ReplicatedSession> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(ReplicatedSession::new)
+    }
+}
+
+impl ::protobuf::Clear for ReplicatedSession {
+    fn clear(&mut self) {
+        self.client_ip.clear();
+        self.phantom_ip.clear();
+        self.phantom_port = ::std::option::Option::None;
+        self.timeout_ns = ::std::option::Option::None;
+        self.registration_source = ::std::option::Option::None;
+        self.max_connections = ::std::option::Option::None;
+        self.mode = ::std::option::Option::None;
+        self.tenant_id = ::std::option::Option::None;
+        self.extension_ns = ::std::option::Option::None;
+        self.client_ports.clear();
+        self.group_id = ::std::option::Option::None;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for ReplicatedSession {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ReplicatedSession {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct SessionReplication {
+    // message fields
+    origin: ::std::option::Option<u32>,
+    pub sessions: ::protobuf::RepeatedField<ReplicatedSession>,
+    update: ::std::option::Option<bool>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a SessionReplication {
+    fn default() -> &'a SessionReplication {
+        <SessionReplication as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl SessionReplication {
+    pub fn new() -> SessionReplication {
+        ::std::default::Default::default()
+    }
+
+    // optional uint32 origin = 1;
+
+
+    pub fn get_origin(&self) -> u32 {
+        self.origin.unwrap_or(0)
+    }
+    pub fn clear_origin(&mut self) {
+        self.origin = ::std::option::Option::None;
+    }
+
+    pub fn has_origin(&self) -> bool {
+        self.origin.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_origin(&mut self, v: u32) {
+        self.origin = ::std::option::Option::Some(v);
+    }
+
+    // repeated .tapdance.ReplicatedSession sessions = 2;
+
+
+    pub fn get_sessions(&self) -> &[ReplicatedSession] {
+        &self.sessions
+    }
+    pub fn clear_sessions(&mut self) {
+        self.sessions.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_sessions(&mut self, v: ::protobuf::RepeatedField<ReplicatedSession>) {
+        self.sessions = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_sessions(&mut self) -> &mut ::protobuf::RepeatedField<ReplicatedSession> {
+        &mut self.sessions
+    }
+
+    // Take field
+    pub fn take_sessions(&mut self) -> ::protobuf::RepeatedField<ReplicatedSession> {
+        ::std::mem::replace(&mut self.sessions, ::protobuf::RepeatedField::new())
+    }
+
+    // optional bool update = 3;
+
+
+    pub fn get_update(&self) -> bool {
+        self.update.unwrap_or(false)
+    }
+    pub fn clear_update(&mut self) {
+        self.update = ::std::option::Option::None;
+    }
+
+    pub fn has_update(&self) -> bool {
+        self.update.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_update(&mut self, v: bool) {
+        self.update = ::std::option::Option::Some(v);
+    }
+}
+
+impl ::protobuf::Message for SessionReplication {
+    fn is_initialized(&self) -> bool {
+        for v in &self.sessions {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.origin = ::std::option::Option::Some(tmp);
+                },
+                2 => {
+                    ::protobuf::rt::read_repeated_message_into(wire_type, is, &mut self.sessions)?;
+                },
+                3 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.update = ::std::option::Option::Some(tmp);
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if let Some(v) = self.origin {
+            my_size += ::protobuf::rt::value_size(1, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        for value in &self.sessions {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        };
+        if let Some(v) = self.update {
+            my_size += 2;
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if let Some(v) = self.origin {
+            os.write_uint32(1, v)?;
+        }
+        for v in &self.sessions {
+            os.write_tag(2, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        };
+        if let Some(v) = self.update {
+            os.write_bool(3, v)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> SessionReplication {
+        SessionReplication::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "origin",
+                |m: &SessionReplication| { &m.origin },
+                |m: &mut SessionReplication| { &mut m.origin },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<ReplicatedSession>>(
+                "sessions",
+                |m: &SessionReplication| { &m.sessions },
+                |m: &mut SessionReplication| { &mut m.sessions },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeBool>(
+                "update",
+                |m: &SessionReplication| { &m.update },
+                |m: &mut SessionReplication| { &mut m.update },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<SessionReplication>(
+                "SessionReplication",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static SessionReplication {
+        static instance: ::protobuf::rt::LazyV2<SessionReplication> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(SessionReplication::new)
+    }
+}
+
+impl ::protobuf::Clear for SessionReplication {
+    fn clear(&mut self) {
+        self.origin = ::std::option::Option::None;
+        self.sessions.clear();
+        self.update = ::std::option::Option::None;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for SessionReplication {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for SessionReplication {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct SessionRecord {
+    // message fields
+    key_hash: ::protobuf::SingularField<::std::vec::Vec<u8>>,
+    export_time_unix_ns: ::std::option::Option<u64>,
+    transport: ::std::option::Option<TransportType>,
+    registration_source: ::std::option::Option<RegistrationSource>,
+    phantom_port: ::std::option::Option<u32>,
+    ipv6: ::std::option::Option<bool>,
+    timeout_ns: ::std::option::Option<u64>,
+    age_ns: ::std::option::Option<u64>,
+    remaining_ns: ::std::option::Option<u64>,
+    registrations: ::std::option::Option<u32>,
+    connections: ::std::option::Option<u32>,
+    active_connections: ::std::option::Option<u32>,
+    extensions: ::std::option::Option<u32>,
+    client_id: ::std::option::Option<u64>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a SessionRecord {
+    fn default() -> &'a SessionRecord {
+        <SessionRecord as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl SessionRecord {
+    pub fn new() -> SessionRecord {
+        ::std::default::Default::default()
+    }
+
+    // optional bytes key_hash = 1;
+
+
+    pub fn get_key_hash(&self) -> &[u8] {
+        match self.key_hash.as_ref() {
+            Some(v) => &v,
+            None => &[],
+        }
+    }
+    pub fn clear_key_hash(&mut self) {
+        self.key_hash.clear();
+    }
+
+    pub fn has_key_hash(&self) -> bool {
+        self.key_hash.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_key_hash(&mut self, v: ::std::vec::Vec<u8>) {
+        self.key_hash = ::protobuf::SingularField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_key_hash(&mut self) -> &mut ::std::vec::Vec<u8> {
+        if self.key_hash.is_none() {
+            self.key_hash.set_default();
+        }
+        self.key_hash.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_key_hash(&mut self) -> ::std::vec::Vec<u8> {
+        self.key_hash.take().unwrap_or_else(|| ::std::vec::Vec::new())
+    }
+
+    // optional uint64 export_time_unix_ns = 2;
+
+
+    pub fn get_export_time_unix_ns(&self) -> u64 {
+        self.export_time_unix_ns.unwrap_or(0)
+    }
+    pub fn clear_export_time_unix_ns(&mut self) {
+        self.export_time_unix_ns = ::std::option::Option::None;
+    }
+
+    pub fn has_export_time_unix_ns(&self) -> bool {
+        self.export_time_unix_ns.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_export_time_unix_ns(&mut self, v: u64) {
+        self.export_time_unix_ns = ::std::option::Option::Some(v);
+    }
+
+    // optional .tapdance.TransportType transport = 3;
+
+
+    pub fn get_transport(&self) -> TransportType {
+        self.transport.unwrap_or(TransportType::Null)
+    }
+    pub fn clear_transport(&mut self) {
+        self.transport = ::std::option::Option::None;
+    }
+
+    pub fn has_transport(&self) -> bool {
+        self.transport.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_transport(&mut self, v: TransportType) {
+        self.transport = ::std::option::Option::Some(v);
+    }
+
+    // optional .tapdance.RegistrationSource registration_source = 4;
+
+
+    pub fn get_registration_source(&self) -> RegistrationSource {
+        self.registration_source.unwrap_or(RegistrationSource::Unspecified)
+    }
+    pub fn clear_registration_source(&mut self) {
+        self.registration_source = ::std::option::Option::None;
+    }
+
+    pub fn has_registration_source(&self) -> bool {
+        self.registration_source.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_registration_source(&mut self, v: RegistrationSource) {
+        self.registration_source = ::std::option::Option::Some(v);
+    }
+
+    // optional uint32 phantom_port = 5;
+
+
+    pub fn get_phantom_port(&self) -> u32 {
+        self.phantom_port.unwrap_or(0)
+    }
+    pub fn clear_phantom_port(&mut self) {
+        self.phantom_port = ::std::option::Option::None;
+    }
+
+    pub fn has_phantom_port(&self) -> bool {
+        self.phantom_port.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_phantom_port(&mut self, v: u32) {
+        self.phantom_port = ::std::option::Option::Some(v);
+    }
+
+    // optional bool ipv6 = 6;
+
+
+    pub fn get_ipv6(&self) -> bool {
+        self.ipv6.unwrap_or(false)
+    }
+    pub fn clear_ipv6(&mut self) {
+        self.ipv6 = ::std::option::Option::None;
+    }
+
+    pub fn has_ipv6(&self) -> bool {
+        self.ipv6.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_ipv6(&mut self, v: bool) {
+        self.ipv6 = ::std::option::Option::Some(v);
+    }
+
+    // optional uint64 timeout_ns = 7;
+
+
+    pub fn get_timeout_ns(&self) -> u64 {
+        self.timeout_ns.unwrap_or(0)
+    }
+    pub fn clear_timeout_ns(&mut self) {
+        self.timeout_ns = ::std::option::Option::None;
+    }
+
+    pub fn has_timeout_ns(&self) -> bool {
+        self.timeout_ns.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_timeout_ns(&mut self, v: u64) {
+        self.timeout_ns = ::std::option::Option::Some(v);
+    }
+
+    // optional uint64 age_ns = 8;
+
+
+    pub fn get_age_ns(&self) -> u64 {
+        self.age_ns.unwrap_or(0)
+    }
+    pub fn clear_age_ns(&mut self) {
+        self.age_ns = ::std::option::Option::None;
+    }
+
+    pub fn has_age_ns(&self) -> bool {
+        self.age_ns.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_age_ns(&mut self, v: u64) {
+        self.age_ns = ::std::option::Option::Some(v);
+    }
+
+    // optional uint64 remaining_ns = 9;
+
+
+    pub fn get_remaining_ns(&self) -> u64 {
+        self.remaining_ns.unwrap_or(0)
+    }
+    pub fn clear_remaining_ns(&mut self) {
+        self.remaining_ns = ::std::option::Option::None;
+    }
+
+    pub fn has_remaining_ns(&self) -> bool {
+        self.remaining_ns.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_remaining_ns(&mut self, v: u64) {
+        self.remaining_ns = ::std::option::Option::Some(v);
+    }
+
+    // optional uint32 registrations = 10;
+
+
+    pub fn get_registrations(&self) -> u32 {
+        self.registrations.unwrap_or(0)
+    }
+    pub fn clear_registrations(&mut self) {
+        self.registrations = ::std::option::Option::None;
+    }
+
+    pub fn has_registrations(&self) -> bool {
+        self.registrations.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_registrations(&mut self, v: u32) {
+        self.registrations = ::std::option::Option::Some(v);
+    }
+
+    // optional uint32 connections = 11;
+
+
+    pub fn get_connections(&self) -> u32 {
+        self.connections.unwrap_or(0)
+    }
+    pub fn clear_connections(&mut self) {
+        self.connections = ::std::option::Option::None;
+    }
+
+    pub fn has_connections(&self) -> bool {
+        self.connections.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_connections(&mut self, v: u32) {
+        self.connections = ::std::option::Option::Some(v);
+    }
+
+    // optional uint32 active_connections = 12;
+
+
+    pub fn get_active_connections(&self) -> u32 {
+        self.active_connections.unwrap_or(0)
+    }
+    pub fn clear_active_connections(&mut self) {
+        self.active_connections = ::std::option::Option::None;
+    }
+
+    pub fn has_active_connections(&self) -> bool {
+        self.active_connections.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_active_connections(&mut self, v: u32) {
+        self.active_connections = ::std::option::Option::Some(v);
+    }
+
+    // optional uint32 extensions = 13;
+
+
+    pub fn get_extensions(&self) -> u32 {
+        self.extensions.unwrap_or(0)
+    }
+    pub fn clear_extensions(&mut self) {
+        self.extensions = ::std::option::Option::None;
+    }
+
+    pub fn has_extensions(&self) -> bool {
+        self.extensions.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_extensions(&mut self, v: u32) {
+        self.extensions = ::std::option::Option::Some(v);
+    }
+
+    // optional fixed64 client_id = 14;
+
+
+    pub fn get_client_id(&self) -> u64 {
+        self.client_id.unwrap_or(0)
+    }
+    pub fn clear_client_id(&mut self) {
+        self.client_id = ::std::option::Option::None;
+    }
+
+    pub fn has_client_id(&self) -> bool {
+        self.client_id.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_client_id(&mut self, v: u64) {
+        self.client_id = ::std::option::Option::Some(v);
+    }
+}
+
+impl ::protobuf::Message for SessionRecord {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_bytes_into(wire_type, is, &mut self.key_hash)?;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.export_time_unix_ns = ::std::option::Option::Some(tmp);
+                },
+                3 => {
+                    ::protobuf::rt::read_proto2_enum_with_unknown_fields_into(wire_type, is, &mut self.transport, 3, &mut self.unknown_fields)?
+                },
+                4 => {
+                    ::protobuf::rt::read_proto2_enum_with_unknown_fields_into(wire_type, is, &mut self.registration_source, 4, &mut self.unknown_fields)?
+                },
+                5 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.phantom_port = ::std::option::Option::Some(tmp);
+                },
+                6 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.ipv6 = ::std::option::Option::Some(tmp);
+                },
+                7 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.timeout_ns = ::std::option::Option::Some(tmp);
+                },
+                8 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.age_ns = ::std::option::Option::Some(tmp);
+                },
+                9 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.remaining_ns = ::std::option::Option::Some(tmp);
+                },
+                10 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.registrations = ::std::option::Option::Some(tmp);
+                },
+                11 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.connections = ::std::option::Option::Some(tmp);
+                },
+                12 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.active_connections = ::std::option::Option::Some(tmp);
+                },
+                13 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.extensions = ::std::option::Option::Some(tmp);
+                },
+                14 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeFixed64 {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_fixed64()?;
+                    self.client_id = ::std::option::Option::Some(tmp);
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if let Some(ref v) = self.key_hash.as_ref() {
+            my_size += ::protobuf::rt::bytes_size(1, &v);
+        }
+        if let Some(v) = self.export_time_unix_ns {
+            my_size += ::protobuf::rt::value_size(2, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.transport {
+            my_size += ::protobuf::rt::enum_size(3, v);
+        }
+        if let Some(v) = self.registration_source {
+            my_size += ::protobuf::rt::enum_size(4, v);
+        }
+        if let Some(v) = self.phantom_port {
+            my_size += ::protobuf::rt::value_size(5, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.ipv6 {
+            my_size += 2;
+        }
+        if let Some(v) = self.timeout_ns {
+            my_size += ::protobuf::rt::value_size(7, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.age_ns {
+            my_size += ::protobuf::rt::value_size(8, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.remaining_ns {
+            my_size += ::protobuf::rt::value_size(9, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.registrations {
+            my_size += ::protobuf::rt::value_size(10, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.connections {
+            my_size += ::protobuf::rt::value_size(11, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.active_connections {
+            my_size += ::protobuf::rt::value_size(12, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.extensions {
+            my_size += ::protobuf::rt::value_size(13, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.client_id {
+            my_size += 9;
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if let Some(ref v) = self.key_hash.as_ref() {
+            os.write_bytes(1, &v)?;
+        }
+        if let Some(v) = self.export_time_unix_ns {
+            os.write_uint64(2, v)?;
+        }
+        if let Some(v) = self.transport {
+            os.write_enum(3, ::protobuf::ProtobufEnum::value(&v))?;
+        }
+        if let Some(v) = self.registration_source {
+            os.write_enum(4, ::protobuf::ProtobufEnum::value(&v))?;
+        }
+        if let Some(v) = self.phantom_port {
+            os.write_uint32(5, v)?;
+        }
+        if let Some(v) = self.ipv6 {
+            os.write_bool(6, v)?;
+        }
+        if let Some(v) = self.timeout_ns {
+            os.write_uint64(7, v)?;
+        }
+        if let Some(v) = self.age_ns {
+            os.write_uint64(8, v)?;
+        }
+        if let Some(v) = self.remaining_ns {
+            os.write_uint64(9, v)?;
+        }
+        if let Some(v) = self.registrations {
+            os.write_uint32(10, v)?;
+        }
+        if let Some(v) = self.connections {
+            os.write_uint32(11, v)?;
+        }
+        if let Some(v) = self.active_connections {
+            os.write_uint32(12, v)?;
+        }
+        if let Some(v) = self.extensions {
+            os.write_uint32(13, v)?;
+        }
+        if let Some(v) = self.client_id {
+            os.write_fixed64(14, v)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> SessionRecord {
+        SessionRecord::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_singular_field_accessor::<_, ::protobuf::types::ProtobufTypeBytes>(
+                "key_hash",
+                |m: &SessionRecord| { &m.key_hash },
+                |m: &mut SessionRecord| { &mut m.key_hash },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "export_time_unix_ns",
+                |m: &SessionRecord| { &m.export_time_unix_ns },
+                |m: &mut SessionRecord| { &mut m.export_time_unix_ns },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeEnum<TransportType>>(
+                "transport",
+                |m: &SessionRecord| { &m.transport },
+                |m: &mut SessionRecord| { &mut m.transport },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeEnum<RegistrationSource>>(
+                "registration_source",
+                |m: &SessionRecord| { &m.registration_source },
+                |m: &mut SessionRecord| { &mut m.registration_source },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "phantom_port",
+                |m: &SessionRecord| { &m.phantom_port },
+                |m: &mut SessionRecord| { &mut m.phantom_port },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeBool>(
+                "ipv6",
+                |m: &SessionRecord| { &m.ipv6 },
+                |m: &mut SessionRecord| { &mut m.ipv6 },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "timeout_ns",
+                |m: &SessionRecord| { &m.timeout_ns },
+                |m: &mut SessionRecord| { &mut m.timeout_ns },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "age_ns",
+                |m: &SessionRecord| { &m.age_ns },
+                |m: &mut SessionRecord| { &mut m.age_ns },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "remaining_ns",
+                |m: &SessionRecord| { &m.remaining_ns },
+                |m: &mut SessionRecord| { &mut m.remaining_ns },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "registrations",
+                |m: &SessionRecord| { &m.registrations },
+                |m: &mut SessionRecord| { &mut m.registrations },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "connections",
+                |m: &SessionRecord| { &m.connections },
+                |m: &mut SessionRecord| { &mut m.connections },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "active_connections",
+                |m: &SessionRecord| { &m.active_connections },
+                |m: &mut SessionRecord| { &mut m.active_connections },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "extensions",
+                |m: &SessionRecord| { &m.extensions },
+                |m: &mut SessionRecord| { &mut m.extensions },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeFixed64>(
+                "client_id",
+                |m: &SessionRecord| { &m.client_id },
+                |m: &mut SessionRecord| { &mut m.client_id },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<SessionRecord>(
+                "SessionRecord",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static SessionRecord {
+        static instance: ::protobuf::rt::LazyV2<SessionRecord> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(SessionRecord::new)
+    }
+}
+
+impl ::protobuf::Clear for SessionRecord {
+    fn clear(&mut self) {
+        self.key_hash.clear();
+        self.export_time_unix_ns = ::std::option::Option::None;
+        self.transport = ::std::option::Option::None;
+        self.registration_source = ::std::option::Option::None;
+        self.phantom_port = ::std::option::Option::None;
+        self.ipv6 = ::std::option::Option::None;
+        self.timeout_ns = ::std::option::Option::None;
+        self.age_ns = ::std::option::Option::None;
+        self.remaining_ns = ::std::option::Option::None;
+        self.registrations = ::std::option::Option::None;
+        self.connections = ::std::option::Option::None;
+        self.active_connections = ::std::option::Option::None;
+        self.extensions = ::std::option::Option::None;
+        self.client_id = ::std::option::Option::None;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for SessionRecord {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for SessionRecord {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(Clone,PartialEq,Eq,Debug,Hash)]
+pub enum KeyType {
+    AES_GCM_128 = 90,
+    AES_GCM_256 = 91,
+}
+
+impl ::protobuf::ProtobufEnum for KeyType {
+    fn value(&self) -> i32 {
+        *self as i32
+    }
+
+    fn from_i32(value: i32) -> ::std::option::Option<KeyType> {
+        match value {
+            90 => ::std::option::Option::Some(KeyType::AES_GCM_128),
+            91 => ::std::option::Option::Some(KeyType::AES_GCM_256),
+            _ => ::std::option::Option::None
+        }
+    }
+
+    fn values() -> &'static [Self] {
+        static values: &'static [KeyType] = &[
+            KeyType::AES_GCM_128,
+            KeyType::AES_GCM_256,
+        ];
+        values
+    }
+
+    fn enum_descriptor_static() -> &'static ::protobuf::reflect::EnumDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::EnumDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            ::protobuf::reflect::EnumDescriptor::new_pb_name::<KeyType>("KeyType", file_descriptor_proto())
+        })
+    }
+}
+
+impl ::std::marker::Copy for KeyType {
+}
+
+// Note, `Default` is implemented although default value is not 0
+impl ::std::default::Default for KeyType {
+    fn default() -> Self {
+        KeyType::AES_GCM_128
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for KeyType {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Enum(::protobuf::ProtobufEnum::descriptor(self))
+    }
+}
+
+#[derive(Clone,PartialEq,Eq,Debug,Hash)]
+pub enum C2S_Transition {
+    C2S_NO_CHANGE = 0,
+    C2S_SESSION_INIT = 1,
     C2S_SESSION_COVERT_INIT = 11,
     C2S_EXPECT_RECONNECT = 2,
     C2S_SESSION_CLOSE = 3,
@@ -4238,6 +9297,7 @@ pub enum TransportType {
     Null = 0,
     Min = 1,
     Obfs4 = 2,
+    Prefix = 3,
 }
 
 impl ::protobuf::ProtobufEnum for TransportType {
@@ -4250,6 +9310,7 @@ impl ::protobuf::ProtobufEnum for TransportType {
             0 => ::std::option::Option::Some(TransportType::Null),
             1 => ::std::option::Option::Some(TransportType::Min),
             2 => ::std::option::Option::Some(TransportType::Obfs4),
+            3 => ::std::option::Option::Some(TransportType::Prefix),
             _ => ::std::option::Option::None
         }
     }
@@ -4259,6 +9320,7 @@ impl ::protobuf::ProtobufEnum for TransportType {
             TransportType::Null,
             TransportType::Min,
             TransportType::Obfs4,
+            TransportType::Prefix,
         ];
         values
     }
@@ -4286,6 +9348,56 @@ impl ::protobuf::reflect::ProtobufValue for TransportType {
     }
 }
 
+#[derive(Clone,PartialEq,Eq,Debug,Hash)]
+pub enum SessionMode {
+    Phantom = 0,
+    Decoy = 1,
+}
+
+impl ::protobuf::ProtobufEnum for SessionMode {
+    fn value(&self) -> i32 {
+        *self as i32
+    }
+
+    fn from_i32(value: i32) -> ::std::option::Option<SessionMode> {
+        match value {
+            0 => ::std::option::Option::Some(SessionMode::Phantom),
+            1 => ::std::option::Option::Some(SessionMode::Decoy),
+            _ => ::std::option::Option::None
+        }
+    }
+
+    fn values() -> &'static [Self] {
+        static values: &'static [SessionMode] = &[
+            SessionMode::Phantom,
+            SessionMode::Decoy,
+        ];
+        values
+    }
+
+    fn enum_descriptor_static() -> &'static ::protobuf::reflect::EnumDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::EnumDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            ::protobuf::reflect::EnumDescriptor::new_pb_name::<SessionMode>("SessionMode", file_descriptor_proto())
+        })
+    }
+}
+
+impl ::std::marker::Copy for SessionMode {
+}
+
+impl ::std::default::Default for SessionMode {
+    fn default() -> Self {
+        SessionMode::Phantom
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for SessionMode {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Enum(::protobuf::ProtobufEnum::descriptor(self))
+    }
+}
+
 #[derive(Clone,PartialEq,Eq,Debug,Hash)]
 pub enum RegistrationSource {
     Unspecified = 0,
@@ -4342,6 +9454,139 @@ impl ::protobuf::reflect::ProtobufValue for RegistrationSource {
     }
 }
 
+#[derive(Clone,PartialEq,Eq,Debug,Hash)]
+pub enum DetectorEvent {
+    UnknownEvent = 0,
+    SessionMatched = 1,
+    NeverMatchedSummary = 2,
+    ConfigAck = 3,
+    Heartbeat = 4,
+    PhantomSubnetsAck = 5,
+    SessionExpired = 6,
+    SessionCountAlert = 7,
+    SequenceGaps = 8,
+}
+
+impl ::protobuf::ProtobufEnum for DetectorEvent {
+    fn value(&self) -> i32 {
+        *self as i32
+    }
+
+    fn from_i32(value: i32) -> ::std::option::Option<DetectorEvent> {
+        match value {
+            0 => ::std::option::Option::Some(DetectorEvent::UnknownEvent),
+            1 => ::std::option::Option::Some(DetectorEvent::SessionMatched),
+            2 => ::std::option::Option::Some(DetectorEvent::NeverMatchedSummary),
+            3 => ::std::option::Option::Some(DetectorEvent::ConfigAck),
+            4 => ::std::option::Option::Some(DetectorEvent::Heartbeat),
+            5 => ::std::option::Option::Some(DetectorEvent::PhantomSubnetsAck),
+            6 => ::std::option::Option::Some(DetectorEvent::SessionExpired),
+            7 => ::std::option::Option::Some(DetectorEvent::SessionCountAlert),
+            8 => ::std::option::Option::Some(DetectorEvent::SequenceGaps),
+            _ => ::std::option::Option::None
+        }
+    }
+
+    fn values() -> &'static [Self] {
+        static values: &'static [DetectorEvent] = &[
+            DetectorEvent::UnknownEvent,
+            DetectorEvent::SessionMatched,
+            DetectorEvent::NeverMatchedSummary,
+            DetectorEvent::ConfigAck,
+            DetectorEvent::Heartbeat,
+            DetectorEvent::PhantomSubnetsAck,
+            DetectorEvent::SessionExpired,
+            DetectorEvent::SessionCountAlert,
+            DetectorEvent::SequenceGaps,
+        ];
+        values
+    }
+
+    fn enum_descriptor_static() -> &'static ::protobuf::reflect::EnumDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::EnumDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            ::protobuf::reflect::EnumDescriptor::new_pb_name::<DetectorEvent>("DetectorEvent", file_descriptor_proto())
+        })
+    }
+}
+
+impl ::std::marker::Copy for DetectorEvent {
+}
+
+impl ::std::default::Default for DetectorEvent {
+    fn default() -> Self {
+        DetectorEvent::UnknownEvent
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for DetectorEvent {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Enum(::protobuf::ProtobufEnum::descriptor(self))
+    }
+}
+
+#[derive(Clone,PartialEq,Eq,Debug,Hash)]
+pub enum SessionCountChange {
+    UnknownChange = 0,
+    AboveHigh = 1,
+    BelowLow = 2,
+    WithinWatermarks = 3,
+    Surge = 4,
+    Drop = 5,
+}
+
+impl ::protobuf::ProtobufEnum for SessionCountChange {
+    fn value(&self) -> i32 {
+        *self as i32
+    }
+
+    fn from_i32(value: i32) -> ::std::option::Option<SessionCountChange> {
+        match value {
+            0 => ::std::option::Option::Some(SessionCountChange::UnknownChange),
+            1 => ::std::option::Option::Some(SessionCountChange::AboveHigh),
+            2 => ::std::option::Option::Some(SessionCountChange::BelowLow),
+            3 => ::std::option::Option::Some(SessionCountChange::WithinWatermarks),
+            4 => ::std::option::Option::Some(SessionCountChange::Surge),
+            5 => ::std::option::Option::Some(SessionCountChange::Drop),
+            _ => ::std::option::Option::None
+        }
+    }
+
+    fn values() -> &'static [Self] {
+        static values: &'static [SessionCountChange] = &[
+            SessionCountChange::UnknownChange,
+            SessionCountChange::AboveHigh,
+            SessionCountChange::BelowLow,
+            SessionCountChange::WithinWatermarks,
+            SessionCountChange::Surge,
+            SessionCountChange::Drop,
+        ];
+        values
+    }
+
+    fn enum_descriptor_static() -> &'static ::protobuf::reflect::EnumDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::EnumDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            ::protobuf::reflect::EnumDescriptor::new_pb_name::<SessionCountChange>("SessionCountChange", file_descriptor_proto())
+        })
+    }
+}
+
+impl ::std::marker::Copy for SessionCountChange {
+}
+
+impl ::std::default::Default for SessionCountChange {
+    fn default() -> Self {
+        SessionCountChange::UnknownChange
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for SessionCountChange {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Enum(::protobuf::ProtobufEnum::descriptor(self))
+    }
+}
+
 static file_descriptor_proto_data: &'static [u8] = b"\
     \n\x10signalling.proto\x12\x08tapdance\"A\n\x06PubKey\x12\x10\n\x03key\
     \x18\x01\x20\x01(\x0cR\x03key\x12%\n\x04type\x18\x02\x20\x01(\x0e2\x11.t\
@@ -4372,7 +9617,7 @@ static file_descriptor_proto_data: &'static [u8] = b"\
     \x1f\n\x0bupload_only\x18\x01\x20\x01(\x08R\nuploadOnly\x12\x1d\n\ndark_\
     decoy\x18\x02\x20\x01(\x08R\tdarkDecoy\x12!\n\x0cproxy_header\x18\x03\
     \x20\x01(\x08R\x0bproxyHeader\x12\x17\n\x07use_TIL\x18\x04\x20\x01(\x08R\
-    \x06useTIL\x12\x1e\n\nprescanned\x18\x05\x20\x01(\x08R\nprescanned\"\xea\
+    \x06useTIL\x12\x1e\n\nprescanned\x18\x05\x20\x01(\x08R\nprescanned\"\xee\
     \x04\n\x0fClientToStation\x12)\n\x10protocol_version\x18\x01\x20\x01(\rR\
     \x0fprotocolVersion\x122\n\x15decoy_list_generation\x18\x02\x20\x01(\rR\
     \x13decoyListGeneration\x12C\n\x10state_transition\x18\x03\x20\x01(\x0e2\
@@ -4385,439 +9630,1141 @@ static file_descriptor_proto_data: &'static [u8] = b"\
     \x15maskedDecoyServerName\x12\x1d\n\nv6_support\x18\x16\x20\x01(\x08R\tv\
     6Support\x12\x1d\n\nv4_support\x18\x17\x20\x01(\x08R\tv4Support\x121\n\
     \x05flags\x18\x18\x20\x01(\x0b2\x1b.tapdance.RegistrationFlagsR\x05flags\
-    \x12\x1d\n\ndecoy_port\x18\x19\x20\x01(\rR\tdecoyPort\x12\x18\n\x07paddi\
-    ng\x18d\x20\x01(\x0cR\x07padding\"\xa6\x02\n\nC2SWrapper\x12#\n\rshared_\
-    secret\x18\x01\x20\x01(\x0cR\x0csharedSecret\x12L\n\x14registration_payl\
-    oad\x18\x03\x20\x01(\x0b2\x19.tapdance.ClientToStationR\x13registrationP\
-    ayload\x12M\n\x13registration_source\x18\x04\x20\x01(\x0e2\x1c.tapdance.\
-    RegistrationSourceR\x12registrationSource\x121\n\x14registration_address\
-    \x18\x06\x20\x01(\x0cR\x13registrationAddress\x12#\n\rdecoy_address\x18\
-    \x07\x20\x01(\x0cR\x0cdecoyAddress\"\xdd\x01\n\x0cSessionStats\x120\n\
-    \x14failed_decoys_amount\x18\x14\x20\x01(\rR\x12failedDecoysAmount\x121\
-    \n\x15total_time_to_connect\x18\x1f\x20\x01(\rR\x12totalTimeToConnect\
+    \x12!\n\x0cphantom_port\x18\x19\x20\x01(\rR\x0bphantomPort\x12\x18\n\x07\
+    padding\x18d\x20\x01(\x0cR\x07padding\"\xa6\x02\n\nC2SWrapper\x12#\n\rsh\
+    ared_secret\x18\x01\x20\x01(\x0cR\x0csharedSecret\x12L\n\x14registration\
+    _payload\x18\x03\x20\x01(\x0b2\x19.tapdance.ClientToStationR\x13registra\
+    tionPayload\x12M\n\x13registration_source\x18\x04\x20\x01(\x0e2\x1c.tapd\
+    ance.RegistrationSourceR\x12registrationSource\x121\n\x14registration_ad\
+    dress\x18\x06\x20\x01(\x0cR\x13registrationAddress\x12#\n\rdecoy_address\
+    \x18\x07\x20\x01(\x0cR\x0cdecoyAddress\"\xdd\x01\n\x0cSessionStats\x120\
+    \n\x14failed_decoys_amount\x18\x14\x20\x01(\rR\x12failedDecoysAmount\x12\
+    1\n\x15total_time_to_connect\x18\x1f\x20\x01(\rR\x12totalTimeToConnect\
     \x12$\n\x0ertt_to_station\x18!\x20\x01(\rR\x0crttToStation\x12\x20\n\x0c\
     tls_to_decoy\x18&\x20\x01(\rR\ntlsToDecoy\x12\x20\n\x0ctcp_to_decoy\x18'\
-    \x20\x01(\rR\ntcpToDecoy\"\x91\x01\n\x11StationToDetector\x12\x1d\n\npha\
-    ntom_ip\x18\x01\x20\x01(\tR\tphantomIp\x12\x1b\n\tclient_ip\x18\x02\x20\
-    \x01(\tR\x08clientIp\x12\x1d\n\ntimeout_ns\x18\x03\x20\x01(\x04R\ttimeou\
-    tNs\x12!\n\x0cphantom_port\x18\x04\x20\x01(\rR\x0bphantomPort*+\n\x07Key\
-    Type\x12\x0f\n\x0bAES_GCM_128\x10Z\x12\x0f\n\x0bAES_GCM_256\x10[*\xe7\
-    \x01\n\x0eC2S_Transition\x12\x11\n\rC2S_NO_CHANGE\x10\0\x12\x14\n\x10C2S\
-    _SESSION_INIT\x10\x01\x12\x1b\n\x17C2S_SESSION_COVERT_INIT\x10\x0b\x12\
-    \x18\n\x14C2S_EXPECT_RECONNECT\x10\x02\x12\x15\n\x11C2S_SESSION_CLOSE\
-    \x10\x03\x12\x14\n\x10C2S_YIELD_UPLOAD\x10\x04\x12\x16\n\x12C2S_ACQUIRE_\
-    UPLOAD\x10\x05\x12\x20\n\x1cC2S_EXPECT_UPLOADONLY_RECONN\x10\x06\x12\x0e\
-    \n\tC2S_ERROR\x10\xff\x01*\x98\x01\n\x0eS2C_Transition\x12\x11\n\rS2C_NO\
-    _CHANGE\x10\0\x12\x14\n\x10S2C_SESSION_INIT\x10\x01\x12\x1b\n\x17S2C_SES\
-    SION_COVERT_INIT\x10\x0b\x12\x19\n\x15S2C_CONFIRM_RECONNECT\x10\x02\x12\
-    \x15\n\x11S2C_SESSION_CLOSE\x10\x03\x12\x0e\n\tS2C_ERROR\x10\xff\x01*\
-    \xac\x01\n\x0eErrorReasonS2C\x12\x0c\n\x08NO_ERROR\x10\0\x12\x11\n\rCOVE\
-    RT_STREAM\x10\x01\x12\x13\n\x0fCLIENT_REPORTED\x10\x02\x12\x13\n\x0fCLIE\
-    NT_PROTOCOL\x10\x03\x12\x14\n\x10STATION_INTERNAL\x10\x04\x12\x12\n\x0eD\
-    ECOY_OVERLOAD\x10\x05\x12\x11\n\rCLIENT_STREAM\x10d\x12\x12\n\x0eCLIENT_\
-    TIMEOUT\x10e*-\n\rTransportType\x12\x08\n\x04Null\x10\0\x12\x07\n\x03Min\
-    \x10\x01\x12\t\n\x05Obfs4\x10\x02*Q\n\x12RegistrationSource\x12\x0f\n\
-    \x0bUnspecified\x10\0\x12\x0c\n\x08Detector\x10\x01\x12\x07\n\x03API\x10\
-    \x02\x12\x13\n\x0fDetectorPrescan\x10\x03J\xbbW\n\x07\x12\x05\0\0\x89\
-    \x02\x01\n\x08\n\x01\x0c\x12\x03\0\0\x12\n\xb0\x01\n\x01\x02\x12\x03\x06\
-    \0\x112\xa5\x01\x20TODO:\x20We're\x20using\x20proto2\x20because\x20it's\
-    \x20the\x20default\x20on\x20Ubuntu\x2016.04.\n\x20At\x20some\x20point\
-    \x20we\x20will\x20want\x20to\x20migrate\x20to\x20proto3,\x20but\x20we\
-    \x20are\x20not\n\x20using\x20any\x20proto3\x20features\x20yet.\n\n\n\n\
-    \x02\x05\0\x12\x04\x08\0\x0b\x01\n\n\n\x03\x05\0\x01\x12\x03\x08\x05\x0c\
-    \n\x0b\n\x04\x05\0\x02\0\x12\x03\t\x04\x15\n\x0c\n\x05\x05\0\x02\0\x01\
-    \x12\x03\t\x04\x0f\n\x0c\n\x05\x05\0\x02\0\x02\x12\x03\t\x12\x14\n\x20\n\
-    \x04\x05\0\x02\x01\x12\x03\n\x04\x15\"\x13\x20not\x20supported\x20atm\n\
-    \n\x0c\n\x05\x05\0\x02\x01\x01\x12\x03\n\x04\x0f\n\x0c\n\x05\x05\0\x02\
-    \x01\x02\x12\x03\n\x12\x14\n\n\n\x02\x04\0\x12\x04\r\0\x12\x01\n\n\n\x03\
-    \x04\0\x01\x12\x03\r\x08\x0e\n4\n\x04\x04\0\x02\0\x12\x03\x0f\x04\x1b\
-    \x1a'\x20A\x20public\x20key,\x20as\x20used\x20by\x20the\x20station.\n\n\
-    \x0c\n\x05\x04\0\x02\0\x04\x12\x03\x0f\x04\x0c\n\x0c\n\x05\x04\0\x02\0\
-    \x05\x12\x03\x0f\r\x12\n\x0c\n\x05\x04\0\x02\0\x01\x12\x03\x0f\x13\x16\n\
-    \x0c\n\x05\x04\0\x02\0\x03\x12\x03\x0f\x19\x1a\n\x0b\n\x04\x04\0\x02\x01\
-    \x12\x03\x11\x04\x1e\n\x0c\n\x05\x04\0\x02\x01\x04\x12\x03\x11\x04\x0c\n\
-    \x0c\n\x05\x04\0\x02\x01\x06\x12\x03\x11\r\x14\n\x0c\n\x05\x04\0\x02\x01\
-    \x01\x12\x03\x11\x15\x19\n\x0c\n\x05\x04\0\x02\x01\x03\x12\x03\x11\x1c\
-    \x1d\n\n\n\x02\x04\x01\x12\x04\x14\0:\x01\n\n\n\x03\x04\x01\x01\x12\x03\
-    \x14\x08\x14\n\xa1\x01\n\x04\x04\x01\x02\0\x12\x03\x19\x04!\x1a\x93\x01\
-    \x20The\x20hostname/SNI\x20to\x20use\x20for\x20this\x20host\n\n\x20The\
-    \x20hostname\x20is\x20the\x20only\x20required\x20field,\x20although\x20o\
-    ther\n\x20fields\x20are\x20expected\x20to\x20be\x20present\x20in\x20most\
-    \x20cases.\n\n\x0c\n\x05\x04\x01\x02\0\x04\x12\x03\x19\x04\x0c\n\x0c\n\
-    \x05\x04\x01\x02\0\x05\x12\x03\x19\r\x13\n\x0c\n\x05\x04\x01\x02\0\x01\
-    \x12\x03\x19\x14\x1c\n\x0c\n\x05\x04\x01\x02\0\x03\x12\x03\x19\x1f\x20\n\
-    \xf7\x01\n\x04\x04\x01\x02\x01\x12\x03\x20\x04\"\x1a\xe9\x01\x20The\x203\
-    2-bit\x20ipv4\x20address,\x20in\x20network\x20byte\x20order\n\n\x20If\
-    \x20the\x20IPv4\x20address\x20is\x20absent,\x20then\x20it\x20may\x20be\
-    \x20resolved\x20via\n\x20DNS\x20by\x20the\x20client,\x20or\x20the\x20cli\
-    ent\x20may\x20discard\x20this\x20decoy\x20spec\n\x20if\x20local\x20DNS\
-    \x20is\x20untrusted,\x20or\x20the\x20service\x20may\x20be\x20multihomed.\
-    \n\n\x0c\n\x05\x04\x01\x02\x01\x04\x12\x03\x20\x04\x0c\n\x0c\n\x05\x04\
-    \x01\x02\x01\x05\x12\x03\x20\r\x14\n\x0c\n\x05\x04\x01\x02\x01\x01\x12\
-    \x03\x20\x15\x1d\n\x0c\n\x05\x04\x01\x02\x01\x03\x12\x03\x20\x20!\n>\n\
-    \x04\x04\x01\x02\x02\x12\x03#\x04\x20\x1a1\x20The\x20128-bit\x20ipv6\x20\
-    address,\x20in\x20network\x20byte\x20order\n\n\x0c\n\x05\x04\x01\x02\x02\
-    \x04\x12\x03#\x04\x0c\n\x0c\n\x05\x04\x01\x02\x02\x05\x12\x03#\r\x12\n\
-    \x0c\n\x05\x04\x01\x02\x02\x01\x12\x03#\x13\x1b\n\x0c\n\x05\x04\x01\x02\
-    \x02\x03\x12\x03#\x1e\x1f\n\x91\x01\n\x04\x04\x01\x02\x03\x12\x03)\x04\
-    \x1f\x1a\x83\x01\x20The\x20Tapdance\x20station\x20public\x20key\x20to\
-    \x20use\x20when\x20contacting\x20this\n\x20decoy\n\n\x20If\x20omitted,\
-    \x20the\x20default\x20station\x20public\x20key\x20(if\x20any)\x20is\x20u\
-    sed.\n\n\x0c\n\x05\x04\x01\x02\x03\x04\x12\x03)\x04\x0c\n\x0c\n\x05\x04\
-    \x01\x02\x03\x06\x12\x03)\r\x13\n\x0c\n\x05\x04\x01\x02\x03\x01\x12\x03)\
-    \x14\x1a\n\x0c\n\x05\x04\x01\x02\x03\x03\x12\x03)\x1d\x1e\n\xee\x01\n\
-    \x04\x04\x01\x02\x04\x12\x030\x04\x20\x1a\xe0\x01\x20The\x20maximum\x20d\
-    uration,\x20in\x20milliseconds,\x20to\x20maintain\x20an\x20open\n\x20con\
-    nection\x20to\x20this\x20decoy\x20(because\x20the\x20decoy\x20may\x20clo\
-    se\x20the\n\x20connection\x20itself\x20after\x20this\x20length\x20of\x20\
-    time)\n\n\x20If\x20omitted,\x20a\x20default\x20of\x2030,000\x20milliseco\
-    nds\x20is\x20assumed.\n\n\x0c\n\x05\x04\x01\x02\x04\x04\x12\x030\x04\x0c\
-    \n\x0c\n\x05\x04\x01\x02\x04\x05\x12\x030\r\x13\n\x0c\n\x05\x04\x01\x02\
-    \x04\x01\x12\x030\x14\x1b\n\x0c\n\x05\x04\x01\x02\x04\x03\x12\x030\x1e\
-    \x1f\n\xb0\x02\n\x04\x04\x01\x02\x05\x12\x039\x04\x1f\x1a\xa2\x02\x20The\
-    \x20maximum\x20TCP\x20window\x20size\x20to\x20attempt\x20to\x20use\x20fo\
-    r\x20this\x20decoy.\n\n\x20If\x20omitted,\x20a\x20default\x20of\x2015360\
-    \x20is\x20assumed.\n\n\x20TODO:\x20the\x20default\x20is\x20based\x20on\
-    \x20the\x20current\x20heuristic\x20of\x20only\n\x20using\x20decoys\x20th\
-    at\x20permit\x20windows\x20of\x2015KB\x20or\x20larger.\x20\x20If\x20this\
-    \n\x20heuristic\x20changes,\x20then\x20this\x20default\x20doesn't\x20mak\
-    e\x20sense.\n\n\x0c\n\x05\x04\x01\x02\x05\x04\x12\x039\x04\x0c\n\x0c\n\
-    \x05\x04\x01\x02\x05\x05\x12\x039\r\x13\n\x0c\n\x05\x04\x01\x02\x05\x01\
-    \x12\x039\x14\x1a\n\x0c\n\x05\x04\x01\x02\x05\x03\x12\x039\x1d\x1e\n\x83\
-    \x08\n\x02\x04\x02\x12\x04Q\0W\x012\xf6\x07\x20In\x20version\x201,\x20th\
-    e\x20request\x20is\x20very\x20simple:\x20when\n\x20the\x20client\x20send\
-    s\x20a\x20MSG_PROTO\x20to\x20the\x20station,\x20if\x20the\n\x20generatio\
-    n\x20number\x20is\x20present,\x20then\x20this\x20request\x20includes\n\
-    \x20(in\x20addition\x20to\x20whatever\x20other\x20operations\x20are\x20p\
-    art\x20of\x20the\n\x20request)\x20a\x20request\x20for\x20the\x20station\
-    \x20to\x20send\x20a\x20copy\x20of\n\x20the\x20current\x20decoy\x20set\
-    \x20that\x20has\x20a\x20generation\x20number\x20greater\n\x20than\x20the\
-    \x20generation\x20number\x20in\x20its\x20request.\n\n\x20If\x20the\x20re\
-    sponse\x20contains\x20a\x20DecoyListUpdate\x20with\x20a\x20generation\
-    \x20number\x20equal\n\x20to\x20that\x20which\x20the\x20client\x20sent,\
-    \x20then\x20the\x20client\x20is\x20\"caught\x20up\"\x20with\n\x20the\x20\
-    station\x20and\x20the\x20response\x20contains\x20no\x20new\x20informatio\
-    n\n\x20(and\x20all\x20other\x20fields\x20may\x20be\x20omitted\x20or\x20e\
-    mpty).\x20\x20Otherwise,\n\x20the\x20station\x20will\x20send\x20the\x20l\
-    atest\x20configuration\x20information,\n\x20along\x20with\x20its\x20gene\
-    ration\x20number.\n\n\x20The\x20station\x20can\x20also\x20send\x20Client\
-    Conf\x20messages\n\x20(as\x20part\x20of\x20Station2Client\x20messages)\
-    \x20whenever\x20it\x20wants.\n\x20The\x20client\x20is\x20expected\x20to\
-    \x20react\x20as\x20if\x20it\x20had\x20requested\n\x20such\x20messages\
-    \x20--\x20possibly\x20by\x20ignoring\x20them,\x20if\x20the\x20client\n\
-    \x20is\x20already\x20up-to-date\x20according\x20to\x20the\x20generation\
-    \x20number.\n\n\n\n\x03\x04\x02\x01\x12\x03Q\x08\x12\n\x0b\n\x04\x04\x02\
-    \x02\0\x12\x03R\x04&\n\x0c\n\x05\x04\x02\x02\0\x04\x12\x03R\x04\x0c\n\
-    \x0c\n\x05\x04\x02\x02\0\x06\x12\x03R\r\x16\n\x0c\n\x05\x04\x02\x02\0\
-    \x01\x12\x03R\x17!\n\x0c\n\x05\x04\x02\x02\0\x03\x12\x03R$%\n\x0b\n\x04\
-    \x04\x02\x02\x01\x12\x03S\x04#\n\x0c\n\x05\x04\x02\x02\x01\x04\x12\x03S\
-    \x04\x0c\n\x0c\n\x05\x04\x02\x02\x01\x05\x12\x03S\r\x13\n\x0c\n\x05\x04\
-    \x02\x02\x01\x01\x12\x03S\x14\x1e\n\x0c\n\x05\x04\x02\x02\x01\x03\x12\
-    \x03S!\"\n\x0b\n\x04\x04\x02\x02\x02\x12\x03T\x04'\n\x0c\n\x05\x04\x02\
-    \x02\x02\x04\x12\x03T\x04\x0c\n\x0c\n\x05\x04\x02\x02\x02\x06\x12\x03T\r\
-    \x13\n\x0c\n\x05\x04\x02\x02\x02\x01\x12\x03T\x14\"\n\x0c\n\x05\x04\x02\
-    \x02\x02\x03\x12\x03T%&\n\x0b\n\x04\x04\x02\x02\x03\x12\x03U\x049\n\x0c\
-    \n\x05\x04\x02\x02\x03\x04\x12\x03U\x04\x0c\n\x0c\n\x05\x04\x02\x02\x03\
-    \x06\x12\x03U\r\x1f\n\x0c\n\x05\x04\x02\x02\x03\x01\x12\x03U\x204\n\x0c\
-    \n\x05\x04\x02\x02\x03\x03\x12\x03U78\n\x0b\n\x04\x04\x02\x02\x04\x12\
-    \x03V\x04'\n\x0c\n\x05\x04\x02\x02\x04\x04\x12\x03V\x04\x0c\n\x0c\n\x05\
-    \x04\x02\x02\x04\x06\x12\x03V\r\x13\n\x0c\n\x05\x04\x02\x02\x04\x01\x12\
-    \x03V\x14\"\n\x0c\n\x05\x04\x02\x02\x04\x03\x12\x03V%&\n\n\n\x02\x04\x03\
-    \x12\x04Y\0[\x01\n\n\n\x03\x04\x03\x01\x12\x03Y\x08\x11\n\x0b\n\x04\x04\
-    \x03\x02\0\x12\x03Z\x04)\n\x0c\n\x05\x04\x03\x02\0\x04\x12\x03Z\x04\x0c\
-    \n\x0c\n\x05\x04\x03\x02\0\x06\x12\x03Z\r\x19\n\x0c\n\x05\x04\x03\x02\0\
-    \x01\x12\x03Z\x1a$\n\x0c\n\x05\x04\x03\x02\0\x03\x12\x03Z'(\n\n\n\x02\
-    \x04\x04\x12\x04]\0_\x01\n\n\n\x03\x04\x04\x01\x12\x03]\x08\x1a\n\x0b\n\
-    \x04\x04\x04\x02\0\x12\x03^\x041\n\x0c\n\x05\x04\x04\x02\0\x04\x12\x03^\
-    \x04\x0c\n\x0c\n\x05\x04\x04\x02\0\x06\x12\x03^\r\x1b\n\x0c\n\x05\x04\
-    \x04\x02\0\x01\x12\x03^\x1c,\n\x0c\n\x05\x04\x04\x02\0\x03\x12\x03^/0\n\
-    \n\n\x02\x04\x05\x12\x04a\0d\x01\n\n\n\x03\x04\x05\x01\x12\x03a\x08\x16\
-    \n\x0b\n\x04\x04\x05\x02\0\x12\x03b\x04\x1f\n\x0c\n\x05\x04\x05\x02\0\
-    \x04\x12\x03b\x04\x0c\n\x0c\n\x05\x04\x05\x02\0\x05\x12\x03b\r\x13\n\x0c\
-    \n\x05\x04\x05\x02\0\x01\x12\x03b\x14\x1a\n\x0c\n\x05\x04\x05\x02\0\x03\
-    \x12\x03b\x1d\x1e\n\x0b\n\x04\x04\x05\x02\x01\x12\x03c\x04\x20\n\x0c\n\
-    \x05\x04\x05\x02\x01\x04\x12\x03c\x04\x0c\n\x0c\n\x05\x04\x05\x02\x01\
-    \x05\x12\x03c\r\x13\n\x0c\n\x05\x04\x05\x02\x01\x01\x12\x03c\x14\x1b\n\
-    \x0c\n\x05\x04\x05\x02\x01\x03\x12\x03c\x1e\x1f\n-\n\x02\x05\x01\x12\x04\
-    g\0q\x01\x1a!\x20State\x20transitions\x20of\x20the\x20client\n\n\n\n\x03\
-    \x05\x01\x01\x12\x03g\x05\x13\n\x0b\n\x04\x05\x01\x02\0\x12\x03h\x04\x16\
-    \n\x0c\n\x05\x05\x01\x02\0\x01\x12\x03h\x04\x11\n\x0c\n\x05\x05\x01\x02\
-    \0\x02\x12\x03h\x14\x15\n\"\n\x04\x05\x01\x02\x01\x12\x03i\x04\x19\"\x15\
-    \x20connect\x20me\x20to\x20squid\n\n\x0c\n\x05\x05\x01\x02\x01\x01\x12\
-    \x03i\x04\x14\n\x0c\n\x05\x05\x01\x02\x01\x02\x12\x03i\x17\x18\n,\n\x04\
-    \x05\x01\x02\x02\x12\x03j\x04!\"\x1f\x20connect\x20me\x20to\x20provided\
-    \x20covert\n\n\x0c\n\x05\x05\x01\x02\x02\x01\x12\x03j\x04\x1b\n\x0c\n\
-    \x05\x05\x01\x02\x02\x02\x12\x03j\x1e\x20\n\x0b\n\x04\x05\x01\x02\x03\
-    \x12\x03k\x04\x1d\n\x0c\n\x05\x05\x01\x02\x03\x01\x12\x03k\x04\x18\n\x0c\
-    \n\x05\x05\x01\x02\x03\x02\x12\x03k\x1b\x1c\n\x0b\n\x04\x05\x01\x02\x04\
-    \x12\x03l\x04\x1a\n\x0c\n\x05\x05\x01\x02\x04\x01\x12\x03l\x04\x15\n\x0c\
-    \n\x05\x05\x01\x02\x04\x02\x12\x03l\x18\x19\n\x0b\n\x04\x05\x01\x02\x05\
-    \x12\x03m\x04\x19\n\x0c\n\x05\x05\x01\x02\x05\x01\x12\x03m\x04\x14\n\x0c\
-    \n\x05\x05\x01\x02\x05\x02\x12\x03m\x17\x18\n\x0b\n\x04\x05\x01\x02\x06\
-    \x12\x03n\x04\x1b\n\x0c\n\x05\x05\x01\x02\x06\x01\x12\x03n\x04\x16\n\x0c\
-    \n\x05\x05\x01\x02\x06\x02\x12\x03n\x19\x1a\n\x0b\n\x04\x05\x01\x02\x07\
-    \x12\x03o\x04%\n\x0c\n\x05\x05\x01\x02\x07\x01\x12\x03o\x04\x20\n\x0c\n\
-    \x05\x05\x01\x02\x07\x02\x12\x03o#$\n\x0b\n\x04\x05\x01\x02\x08\x12\x03p\
-    \x04\x14\n\x0c\n\x05\x05\x01\x02\x08\x01\x12\x03p\x04\r\n\x0c\n\x05\x05\
-    \x01\x02\x08\x02\x12\x03p\x10\x13\n-\n\x02\x05\x02\x12\x04t\0|\x01\x1a!\
-    \x20State\x20transitions\x20of\x20the\x20server\n\n\n\n\x03\x05\x02\x01\
-    \x12\x03t\x05\x13\n\x0b\n\x04\x05\x02\x02\0\x12\x03u\x04\x16\n\x0c\n\x05\
-    \x05\x02\x02\0\x01\x12\x03u\x04\x11\n\x0c\n\x05\x05\x02\x02\0\x02\x12\
-    \x03u\x14\x15\n!\n\x04\x05\x02\x02\x01\x12\x03v\x04\x19\"\x14\x20connect\
-    ed\x20to\x20squid\n\n\x0c\n\x05\x05\x02\x02\x01\x01\x12\x03v\x04\x14\n\
-    \x0c\n\x05\x05\x02\x02\x01\x02\x12\x03v\x17\x18\n'\n\x04\x05\x02\x02\x02\
-    \x12\x03w\x04!\"\x1a\x20connected\x20to\x20covert\x20host\n\n\x0c\n\x05\
-    \x05\x02\x02\x02\x01\x12\x03w\x04\x1b\n\x0c\n\x05\x05\x02\x02\x02\x02\
-    \x12\x03w\x1e\x20\n\x0b\n\x04\x05\x02\x02\x03\x12\x03x\x04\x1e\n\x0c\n\
-    \x05\x05\x02\x02\x03\x01\x12\x03x\x04\x19\n\x0c\n\x05\x05\x02\x02\x03\
-    \x02\x12\x03x\x1c\x1d\n\x0b\n\x04\x05\x02\x02\x04\x12\x03y\x04\x1a\n\x0c\
-    \n\x05\x05\x02\x02\x04\x01\x12\x03y\x04\x15\n\x0c\n\x05\x05\x02\x02\x04\
-    \x02\x12\x03y\x18\x19\nR\n\x04\x05\x02\x02\x05\x12\x03{\x04\x14\x1aE\x20\
-    TODO\x20should\x20probably\x20also\x20allow\x20EXPECT_RECONNECT\x20here,\
-    \x20for\x20DittoTap\n\n\x0c\n\x05\x05\x02\x02\x05\x01\x12\x03{\x04\r\n\
-    \x0c\n\x05\x05\x02\x02\x05\x02\x12\x03{\x10\x13\n7\n\x02\x05\x03\x12\x05\
-    \x7f\0\x89\x01\x01\x1a*\x20Should\x20accompany\x20all\x20S2C_ERROR\x20me\
-    ssages.\n\n\n\n\x03\x05\x03\x01\x12\x03\x7f\x05\x13\n\x0c\n\x04\x05\x03\
-    \x02\0\x12\x04\x80\x01\x04\x11\n\r\n\x05\x05\x03\x02\0\x01\x12\x04\x80\
-    \x01\x04\x0c\n\r\n\x05\x05\x03\x02\0\x02\x12\x04\x80\x01\x0f\x10\n*\n\
-    \x04\x05\x03\x02\x01\x12\x04\x81\x01\x04\x16\"\x1c\x20Squid\x20TCP\x20co\
-    nnection\x20broke\n\n\r\n\x05\x05\x03\x02\x01\x01\x12\x04\x81\x01\x04\
-    \x11\n\r\n\x05\x05\x03\x02\x01\x02\x12\x04\x81\x01\x14\x15\n7\n\x04\x05\
-    \x03\x02\x02\x12\x04\x82\x01\x04\x18\")\x20You\x20told\x20me\x20somethin\
-    g\x20was\x20wrong,\x20client\n\n\r\n\x05\x05\x03\x02\x02\x01\x12\x04\x82\
-    \x01\x04\x13\n\r\n\x05\x05\x03\x02\x02\x02\x12\x04\x82\x01\x16\x17\n@\n\
-    \x04\x05\x03\x02\x03\x12\x04\x83\x01\x04\x18\"2\x20You\x20messed\x20up,\
-    \x20client\x20(e.g.\x20sent\x20a\x20bad\x20protobuf)\n\n\r\n\x05\x05\x03\
-    \x02\x03\x01\x12\x04\x83\x01\x04\x13\n\r\n\x05\x05\x03\x02\x03\x02\x12\
-    \x04\x83\x01\x16\x17\n\x17\n\x04\x05\x03\x02\x04\x12\x04\x84\x01\x04\x19\
-    \"\t\x20I\x20broke\n\n\r\n\x05\x05\x03\x02\x04\x01\x12\x04\x84\x01\x04\
-    \x14\n\r\n\x05\x05\x03\x02\x04\x02\x12\x04\x84\x01\x17\x18\nE\n\x04\x05\
-    \x03\x02\x05\x12\x04\x85\x01\x04\x17\"7\x20Everything's\x20fine,\x20but\
-    \x20don't\x20use\x20this\x20decoy\x20right\x20now\n\n\r\n\x05\x05\x03\
-    \x02\x05\x01\x12\x04\x85\x01\x04\x12\n\r\n\x05\x05\x03\x02\x05\x02\x12\
-    \x04\x85\x01\x15\x16\nD\n\x04\x05\x03\x02\x06\x12\x04\x87\x01\x04\x18\"6\
-    \x20My\x20stream\x20to\x20you\x20broke.\x20(This\x20is\x20impossible\x20\
-    to\x20send)\n\n\r\n\x05\x05\x03\x02\x06\x01\x12\x04\x87\x01\x04\x11\n\r\
-    \n\x05\x05\x03\x02\x06\x02\x12\x04\x87\x01\x14\x17\nA\n\x04\x05\x03\x02\
-    \x07\x12\x04\x88\x01\x04\x19\"3\x20You\x20never\x20came\x20back.\x20(Thi\
-    s\x20is\x20impossible\x20to\x20send)\n\n\r\n\x05\x05\x03\x02\x07\x01\x12\
-    \x04\x88\x01\x04\x12\n\r\n\x05\x05\x03\x02\x07\x02\x12\x04\x88\x01\x15\
-    \x18\n\x0c\n\x02\x05\x04\x12\x06\x8b\x01\0\x8f\x01\x01\n\x0b\n\x03\x05\
-    \x04\x01\x12\x04\x8b\x01\x05\x12\n\x0c\n\x04\x05\x04\x02\0\x12\x04\x8c\
-    \x01\x04\r\n\r\n\x05\x05\x04\x02\0\x01\x12\x04\x8c\x01\x04\x08\n\r\n\x05\
-    \x05\x04\x02\0\x02\x12\x04\x8c\x01\x0b\x0c\n`\n\x04\x05\x04\x02\x01\x12\
-    \x04\x8d\x01\x04\x0c\"R\x20Send\x20a\x2032-byte\x20HMAC\x20id\x20to\x20l\
-    et\x20the\x20station\x20distinguish\x20registrations\x20to\x20same\x20ho\
-    st\n\n\r\n\x05\x05\x04\x02\x01\x01\x12\x04\x8d\x01\x04\x07\n\r\n\x05\x05\
-    \x04\x02\x01\x02\x12\x04\x8d\x01\n\x0b\n$\n\x04\x05\x04\x02\x02\x12\x04\
-    \x8e\x01\x04\x0e\"\x16\x20Not\x20implemented\x20yet?\n\n\r\n\x05\x05\x04\
-    \x02\x02\x01\x12\x04\x8e\x01\x04\t\n\r\n\x05\x05\x04\x02\x02\x02\x12\x04\
-    \x8e\x01\x0c\r\n\x0c\n\x02\x04\x06\x12\x06\x91\x01\0\xa8\x01\x01\n\x0b\n\
-    \x03\x04\x06\x01\x12\x04\x91\x01\x08\x17\nO\n\x04\x04\x06\x02\0\x12\x04\
-    \x93\x01\x04)\x1aA\x20Should\x20accompany\x20(at\x20least)\x20SESSION_IN\
-    IT\x20and\x20CONFIRM_RECONNECT.\n\n\r\n\x05\x04\x06\x02\0\x04\x12\x04\
-    \x93\x01\x04\x0c\n\r\n\x05\x04\x06\x02\0\x05\x12\x04\x93\x01\r\x13\n\r\n\
-    \x05\x04\x06\x02\0\x01\x12\x04\x93\x01\x14$\n\r\n\x05\x04\x06\x02\0\x03\
-    \x12\x04\x93\x01'(\nv\n\x04\x04\x06\x02\x01\x12\x04\x97\x01\x041\x1ah\
-    \x20There\x20might\x20be\x20a\x20state\x20transition.\x20May\x20be\x20ab\
-    sent;\x20absence\x20should\x20be\n\x20treated\x20identically\x20to\x20NO\
-    _CHANGE.\n\n\r\n\x05\x04\x06\x02\x01\x04\x12\x04\x97\x01\x04\x0c\n\r\n\
-    \x05\x04\x06\x02\x01\x06\x12\x04\x97\x01\r\x1b\n\r\n\x05\x04\x06\x02\x01\
-    \x01\x12\x04\x97\x01\x1c,\n\r\n\x05\x04\x06\x02\x01\x03\x12\x04\x97\x01/\
-    0\nc\n\x04\x04\x06\x02\x02\x12\x04\x9b\x01\x04(\x1aU\x20The\x20station\
-    \x20can\x20send\x20client\x20config\x20info\x20piggybacked\n\x20on\x20an\
-    y\x20message,\x20as\x20it\x20sees\x20fit\n\n\r\n\x05\x04\x06\x02\x02\x04\
-    \x12\x04\x9b\x01\x04\x0c\n\r\n\x05\x04\x06\x02\x02\x06\x12\x04\x9b\x01\r\
-    \x17\n\r\n\x05\x04\x06\x02\x02\x01\x12\x04\x9b\x01\x18#\n\r\n\x05\x04\
-    \x06\x02\x02\x03\x12\x04\x9b\x01&'\nP\n\x04\x04\x06\x02\x03\x12\x04\x9e\
-    \x01\x04+\x1aB\x20If\x20state_transition\x20==\x20S2C_ERROR,\x20this\x20\
-    field\x20is\x20the\x20explanation.\n\n\r\n\x05\x04\x06\x02\x03\x04\x12\
-    \x04\x9e\x01\x04\x0c\n\r\n\x05\x04\x06\x02\x03\x06\x12\x04\x9e\x01\r\x1b\
-    \n\r\n\x05\x04\x06\x02\x03\x01\x12\x04\x9e\x01\x1c&\n\r\n\x05\x04\x06\
-    \x02\x03\x03\x12\x04\x9e\x01)*\nQ\n\x04\x04\x06\x02\x04\x12\x04\xa1\x01\
-    \x04$\x1aC\x20Signals\x20client\x20to\x20stop\x20connecting\x20for\x20fo\
-    llowing\x20amount\x20of\x20seconds\n\n\r\n\x05\x04\x06\x02\x04\x04\x12\
-    \x04\xa1\x01\x04\x0c\n\r\n\x05\x04\x06\x02\x04\x05\x12\x04\xa1\x01\r\x13\
-    \n\r\n\x05\x04\x06\x02\x04\x01\x12\x04\xa1\x01\x14\x1f\n\r\n\x05\x04\x06\
-    \x02\x04\x03\x12\x04\xa1\x01\"#\nK\n\x04\x04\x06\x02\x05\x12\x04\xa4\x01\
-    \x04#\x1a=\x20Sent\x20in\x20SESSION_INIT,\x20identifies\x20the\x20statio\
-    n\x20that\x20picked\x20up\n\n\r\n\x05\x04\x06\x02\x05\x04\x12\x04\xa4\
-    \x01\x04\x0c\n\r\n\x05\x04\x06\x02\x05\x05\x12\x04\xa4\x01\r\x13\n\r\n\
-    \x05\x04\x06\x02\x05\x01\x12\x04\xa4\x01\x14\x1e\n\r\n\x05\x04\x06\x02\
-    \x05\x03\x12\x04\xa4\x01!\"\nG\n\x04\x04\x06\x02\x06\x12\x04\xa7\x01\x04\
-    !\x1a9\x20Random-sized\x20junk\x20to\x20defeat\x20packet\x20size\x20fing\
-    erprinting.\n\n\r\n\x05\x04\x06\x02\x06\x04\x12\x04\xa7\x01\x04\x0c\n\r\
-    \n\x05\x04\x06\x02\x06\x05\x12\x04\xa7\x01\r\x12\n\r\n\x05\x04\x06\x02\
-    \x06\x01\x12\x04\xa7\x01\x13\x1a\n\r\n\x05\x04\x06\x02\x06\x03\x12\x04\
-    \xa7\x01\x1d\x20\n\x0c\n\x02\x04\x07\x12\x06\xaa\x01\0\xb0\x01\x01\n\x0b\
-    \n\x03\x04\x07\x01\x12\x04\xaa\x01\x08\x19\n\x0c\n\x04\x04\x07\x02\0\x12\
-    \x04\xab\x01\x08&\n\r\n\x05\x04\x07\x02\0\x04\x12\x04\xab\x01\x08\x10\n\
-    \r\n\x05\x04\x07\x02\0\x05\x12\x04\xab\x01\x11\x15\n\r\n\x05\x04\x07\x02\
-    \0\x01\x12\x04\xab\x01\x16!\n\r\n\x05\x04\x07\x02\0\x03\x12\x04\xab\x01$\
-    %\n\x0c\n\x04\x04\x07\x02\x01\x12\x04\xac\x01\x08%\n\r\n\x05\x04\x07\x02\
-    \x01\x04\x12\x04\xac\x01\x08\x10\n\r\n\x05\x04\x07\x02\x01\x05\x12\x04\
-    \xac\x01\x11\x15\n\r\n\x05\x04\x07\x02\x01\x01\x12\x04\xac\x01\x16\x20\n\
-    \r\n\x05\x04\x07\x02\x01\x03\x12\x04\xac\x01#$\n\x0c\n\x04\x04\x07\x02\
-    \x02\x12\x04\xad\x01\x08'\n\r\n\x05\x04\x07\x02\x02\x04\x12\x04\xad\x01\
-    \x08\x10\n\r\n\x05\x04\x07\x02\x02\x05\x12\x04\xad\x01\x11\x15\n\r\n\x05\
-    \x04\x07\x02\x02\x01\x12\x04\xad\x01\x16\"\n\r\n\x05\x04\x07\x02\x02\x03\
-    \x12\x04\xad\x01%&\n\x0c\n\x04\x04\x07\x02\x03\x12\x04\xae\x01\x04\x1e\n\
-    \r\n\x05\x04\x07\x02\x03\x04\x12\x04\xae\x01\x04\x0c\n\r\n\x05\x04\x07\
-    \x02\x03\x05\x12\x04\xae\x01\r\x11\n\r\n\x05\x04\x07\x02\x03\x01\x12\x04\
-    \xae\x01\x12\x19\n\r\n\x05\x04\x07\x02\x03\x03\x12\x04\xae\x01\x1c\x1d\n\
-    \x0c\n\x04\x04\x07\x02\x04\x12\x04\xaf\x01\x04!\n\r\n\x05\x04\x07\x02\
-    \x04\x04\x12\x04\xaf\x01\x04\x0c\n\r\n\x05\x04\x07\x02\x04\x05\x12\x04\
-    \xaf\x01\r\x11\n\r\n\x05\x04\x07\x02\x04\x01\x12\x04\xaf\x01\x12\x1c\n\r\
-    \n\x05\x04\x07\x02\x04\x03\x12\x04\xaf\x01\x1f\x20\n\x0c\n\x02\x04\x08\
-    \x12\x06\xb2\x01\0\xe1\x01\x01\n\x0b\n\x03\x04\x08\x01\x12\x04\xb2\x01\
-    \x08\x17\n\x0c\n\x04\x04\x08\x02\0\x12\x04\xb3\x01\x04)\n\r\n\x05\x04\
-    \x08\x02\0\x04\x12\x04\xb3\x01\x04\x0c\n\r\n\x05\x04\x08\x02\0\x05\x12\
-    \x04\xb3\x01\r\x13\n\r\n\x05\x04\x08\x02\0\x01\x12\x04\xb3\x01\x14$\n\r\
-    \n\x05\x04\x08\x02\0\x03\x12\x04\xb3\x01'(\n\xd0\x01\n\x04\x04\x08\x02\
-    \x01\x12\x04\xb8\x01\x04.\x1a\xc1\x01\x20The\x20client\x20reports\x20its\
-    \x20decoy\x20list's\x20version\x20number\x20here,\x20which\x20the\n\x20s\
-    tation\x20can\x20use\x20to\x20decide\x20whether\x20to\x20send\x20an\x20u\
-    pdated\x20one.\x20The\x20station\n\x20should\x20always\x20send\x20a\x20l\
-    ist\x20if\x20this\x20field\x20is\x20set\x20to\x200.\n\n\r\n\x05\x04\x08\
-    \x02\x01\x04\x12\x04\xb8\x01\x04\x0c\n\r\n\x05\x04\x08\x02\x01\x05\x12\
-    \x04\xb8\x01\r\x13\n\r\n\x05\x04\x08\x02\x01\x01\x12\x04\xb8\x01\x14)\n\
-    \r\n\x05\x04\x08\x02\x01\x03\x12\x04\xb8\x01,-\n\x0c\n\x04\x04\x08\x02\
-    \x02\x12\x04\xba\x01\x041\n\r\n\x05\x04\x08\x02\x02\x04\x12\x04\xba\x01\
-    \x04\x0c\n\r\n\x05\x04\x08\x02\x02\x06\x12\x04\xba\x01\r\x1b\n\r\n\x05\
-    \x04\x08\x02\x02\x01\x12\x04\xba\x01\x1c,\n\r\n\x05\x04\x08\x02\x02\x03\
-    \x12\x04\xba\x01/0\n\x80\x01\n\x04\x04\x08\x02\x03\x12\x04\xbe\x01\x04$\
-    \x1ar\x20The\x20position\x20in\x20the\x20overall\x20session's\x20upload\
-    \x20sequence\x20where\x20the\x20current\n\x20YIELD=>ACQUIRE\x20switchove\
-    r\x20is\x20happening.\n\n\r\n\x05\x04\x08\x02\x03\x04\x12\x04\xbe\x01\
-    \x04\x0c\n\r\n\x05\x04\x08\x02\x03\x05\x12\x04\xbe\x01\r\x13\n\r\n\x05\
-    \x04\x08\x02\x03\x01\x12\x04\xbe\x01\x14\x1f\n\r\n\x05\x04\x08\x02\x03\
-    \x03\x12\x04\xbe\x01\"#\nq\n\x04\x04\x08\x02\x04\x12\x04\xc3\x01\x04'\
-    \x1ac\x20List\x20of\x20decoys\x20that\x20client\x20have\x20unsuccessfull\
-    y\x20tried\x20in\x20current\x20session.\n\x20Could\x20be\x20sent\x20in\
-    \x20chunks\n\n\r\n\x05\x04\x08\x02\x04\x04\x12\x04\xc3\x01\x04\x0c\n\r\n\
-    \x05\x04\x08\x02\x04\x05\x12\x04\xc3\x01\r\x13\n\r\n\x05\x04\x08\x02\x04\
-    \x01\x12\x04\xc3\x01\x14!\n\r\n\x05\x04\x08\x02\x04\x03\x12\x04\xc3\x01$\
-    &\n\x0c\n\x04\x04\x08\x02\x05\x12\x04\xc5\x01\x04%\n\r\n\x05\x04\x08\x02\
-    \x05\x04\x12\x04\xc5\x01\x04\x0c\n\r\n\x05\x04\x08\x02\x05\x06\x12\x04\
-    \xc5\x01\r\x19\n\r\n\x05\x04\x08\x02\x05\x01\x12\x04\xc5\x01\x1a\x1f\n\r\
-    \n\x05\x04\x08\x02\x05\x03\x12\x04\xc5\x01\"$\nk\n\x04\x04\x08\x02\x06\
-    \x12\x04\xc8\x01\x04*\x1a]\x20NullTransport,\x20MinTransport,\x20Obfs4Tr\
-    ansport,\x20etc.\x20Transport\x20type\x20we\x20want\x20from\x20phantom\
-    \x20proxy\n\n\r\n\x05\x04\x08\x02\x06\x04\x12\x04\xc8\x01\x04\x0c\n\r\n\
-    \x05\x04\x08\x02\x06\x06\x12\x04\xc8\x01\r\x1a\n\r\n\x05\x04\x08\x02\x06\
-    \x01\x12\x04\xc8\x01\x1b$\n\r\n\x05\x04\x08\x02\x06\x03\x12\x04\xc8\x01'\
-    )\n\xc8\x03\n\x04\x04\x08\x02\x07\x12\x04\xd0\x01\x04(\x1a\xb9\x03\x20St\
-    ation\x20is\x20only\x20required\x20to\x20check\x20this\x20variable\x20du\
-    ring\x20session\x20initialization.\n\x20If\x20set,\x20station\x20must\
-    \x20facilitate\x20connection\x20to\x20said\x20target\x20by\x20itself,\
-    \x20i.e.\x20write\x20into\x20squid\n\x20socket\x20an\x20HTTP/SOCKS/any\
-    \x20other\x20connection\x20request.\n\x20covert_address\x20must\x20have\
-    \x20exactly\x20one\x20':'\x20colon,\x20that\x20separates\x20host\x20(lit\
-    eral\x20IP\x20address\x20or\n\x20resolvable\x20hostname)\x20and\x20port\
-    \n\x20TODO:\x20make\x20it\x20required\x20for\x20initialization,\x20and\
-    \x20stop\x20connecting\x20any\x20client\x20straight\x20to\x20squid?\n\n\
-    \r\n\x05\x04\x08\x02\x07\x04\x12\x04\xd0\x01\x04\x0c\n\r\n\x05\x04\x08\
-    \x02\x07\x05\x12\x04\xd0\x01\r\x13\n\r\n\x05\x04\x08\x02\x07\x01\x12\x04\
-    \xd0\x01\x14\"\n\r\n\x05\x04\x08\x02\x07\x03\x12\x04\xd0\x01%'\nR\n\x04\
-    \x04\x08\x02\x08\x12\x04\xd3\x01\x042\x1aD\x20Used\x20in\x20dark\x20deco\
-    ys\x20to\x20signal\x20which\x20dark\x20decoy\x20it\x20will\x20connect\
-    \x20to.\n\n\r\n\x05\x04\x08\x02\x08\x04\x12\x04\xd3\x01\x04\x0c\n\r\n\
-    \x05\x04\x08\x02\x08\x05\x12\x04\xd3\x01\r\x13\n\r\n\x05\x04\x08\x02\x08\
-    \x01\x12\x04\xd3\x01\x14,\n\r\n\x05\x04\x08\x02\x08\x03\x12\x04\xd3\x01/\
-    1\nR\n\x04\x04\x08\x02\t\x12\x04\xd6\x01\x04\"\x1aD\x20Used\x20to\x20ind\
-    icate\x20to\x20server\x20if\x20client\x20is\x20registering\x20v4,\x20v6\
-    \x20or\x20both\n\n\r\n\x05\x04\x08\x02\t\x04\x12\x04\xd6\x01\x04\x0c\n\r\
-    \n\x05\x04\x08\x02\t\x05\x12\x04\xd6\x01\r\x11\n\r\n\x05\x04\x08\x02\t\
-    \x01\x12\x04\xd6\x01\x12\x1c\n\r\n\x05\x04\x08\x02\t\x03\x12\x04\xd6\x01\
-    \x1f!\n\x0c\n\x04\x04\x08\x02\n\x12\x04\xd7\x01\x04\"\n\r\n\x05\x04\x08\
-    \x02\n\x04\x12\x04\xd7\x01\x04\x0c\n\r\n\x05\x04\x08\x02\n\x05\x12\x04\
-    \xd7\x01\r\x11\n\r\n\x05\x04\x08\x02\n\x01\x12\x04\xd7\x01\x12\x1c\n\r\n\
-    \x05\x04\x08\x02\n\x03\x12\x04\xd7\x01\x1f!\nD\n\x04\x04\x08\x02\x0b\x12\
-    \x04\xda\x01\x08.\x1a6\x20A\x20collection\x20of\x20optional\x20flags\x20\
-    for\x20the\x20registration.\n\n\r\n\x05\x04\x08\x02\x0b\x04\x12\x04\xda\
-    \x01\x08\x10\n\r\n\x05\x04\x08\x02\x0b\x06\x12\x04\xda\x01\x11\"\n\r\n\
-    \x05\x04\x08\x02\x0b\x01\x12\x04\xda\x01#(\n\r\n\x05\x04\x08\x02\x0b\x03\
-    \x12\x04\xda\x01+-\n2\n\x04\x04\x08\x02\x0c\x12\x04\xdd\x01\x04$\x1a$\
-    \x20Allow\x20client\x20to\x20specify\x20decoy\x20port\n\n\r\n\x05\x04\
-    \x08\x02\x0c\x04\x12\x04\xdd\x01\x04\x0c\n\r\n\x05\x04\x08\x02\x0c\x05\
-    \x12\x04\xdd\x01\r\x13\n\r\n\x05\x04\x08\x02\x0c\x01\x12\x04\xdd\x01\x14\
-    \x1e\n\r\n\x05\x04\x08\x02\x0c\x03\x12\x04\xdd\x01!#\nG\n\x04\x04\x08\
-    \x02\r\x12\x04\xe0\x01\x04!\x1a9\x20Random-sized\x20junk\x20to\x20defeat\
-    \x20packet\x20size\x20fingerprinting.\n\n\r\n\x05\x04\x08\x02\r\x04\x12\
-    \x04\xe0\x01\x04\x0c\n\r\n\x05\x04\x08\x02\r\x05\x12\x04\xe0\x01\r\x12\n\
-    \r\n\x05\x04\x08\x02\r\x01\x12\x04\xe0\x01\x13\x1a\n\r\n\x05\x04\x08\x02\
-    \r\x03\x12\x04\xe0\x01\x1d\x20\n\x0c\n\x02\x05\x05\x12\x06\xe3\x01\0\xe8\
-    \x01\x01\n\x0b\n\x03\x05\x05\x01\x12\x04\xe3\x01\x05\x17\n\x0c\n\x04\x05\
-    \x05\x02\0\x12\x04\xe4\x01\x04\x14\n\r\n\x05\x05\x05\x02\0\x01\x12\x04\
-    \xe4\x01\x04\x0f\n\r\n\x05\x05\x05\x02\0\x02\x12\x04\xe4\x01\x12\x13\n\
-    \x0c\n\x04\x05\x05\x02\x01\x12\x04\xe5\x01\x08\x15\n\r\n\x05\x05\x05\x02\
-    \x01\x01\x12\x04\xe5\x01\x08\x10\n\r\n\x05\x05\x05\x02\x01\x02\x12\x04\
-    \xe5\x01\x13\x14\n\x0c\n\x04\x05\x05\x02\x02\x12\x04\xe6\x01\x08\x10\n\r\
-    \n\x05\x05\x05\x02\x02\x01\x12\x04\xe6\x01\x08\x0b\n\r\n\x05\x05\x05\x02\
-    \x02\x02\x12\x04\xe6\x01\x0e\x0f\n\x0c\n\x04\x05\x05\x02\x03\x12\x04\xe7\
-    \x01\x04\x18\n\r\n\x05\x05\x05\x02\x03\x01\x12\x04\xe7\x01\x04\x13\n\r\n\
-    \x05\x05\x05\x02\x03\x02\x12\x04\xe7\x01\x16\x17\n\x0c\n\x02\x04\t\x12\
-    \x06\xea\x01\0\xf4\x01\x01\n\x0b\n\x03\x04\t\x01\x12\x04\xea\x01\x08\x12\
-    \n\x0c\n\x04\x04\t\x02\0\x12\x04\xeb\x01\x08)\n\r\n\x05\x04\t\x02\0\x04\
-    \x12\x04\xeb\x01\x08\x10\n\r\n\x05\x04\t\x02\0\x05\x12\x04\xeb\x01\x11\
-    \x16\n\r\n\x05\x04\t\x02\0\x01\x12\x04\xeb\x01\x17$\n\r\n\x05\x04\t\x02\
-    \0\x03\x12\x04\xeb\x01'(\n\x0c\n\x04\x04\t\x02\x01\x12\x04\xec\x01\x08:\
-    \n\r\n\x05\x04\t\x02\x01\x04\x12\x04\xec\x01\x08\x10\n\r\n\x05\x04\t\x02\
-    \x01\x06\x12\x04\xec\x01\x11\x20\n\r\n\x05\x04\t\x02\x01\x01\x12\x04\xec\
-    \x01!5\n\r\n\x05\x04\t\x02\x01\x03\x12\x04\xec\x0189\n\x0c\n\x04\x04\t\
-    \x02\x02\x12\x04\xed\x01\x048\n\r\n\x05\x04\t\x02\x02\x04\x12\x04\xed\
-    \x01\x04\x0c\n\r\n\x05\x04\t\x02\x02\x06\x12\x04\xed\x01\r\x1f\n\r\n\x05\
-    \x04\t\x02\x02\x01\x12\x04\xed\x01\x203\n\r\n\x05\x04\t\x02\x02\x03\x12\
-    \x04\xed\x0167\nC\n\x04\x04\t\x02\x03\x12\x04\xf0\x01\x04,\x1a5\x20clien\
-    t\x20source\x20address\x20when\x20receiving\x20a\x20registration\n\n\r\n\
-    \x05\x04\t\x02\x03\x04\x12\x04\xf0\x01\x04\x0c\n\r\n\x05\x04\t\x02\x03\
-    \x05\x12\x04\xf0\x01\r\x12\n\r\n\x05\x04\t\x02\x03\x01\x12\x04\xf0\x01\
-    \x13'\n\r\n\x05\x04\t\x02\x03\x03\x12\x04\xf0\x01*+\nH\n\x04\x04\t\x02\
-    \x04\x12\x04\xf3\x01\x04%\x1a:\x20Decoy\x20address\x20used\x20when\x20re\
-    gistering\x20over\x20Decoy\x20registrar\n\n\r\n\x05\x04\t\x02\x04\x04\
-    \x12\x04\xf3\x01\x04\x0c\n\r\n\x05\x04\t\x02\x04\x05\x12\x04\xf3\x01\r\
-    \x12\n\r\n\x05\x04\t\x02\x04\x01\x12\x04\xf3\x01\x13\x20\n\r\n\x05\x04\t\
-    \x02\x04\x03\x12\x04\xf3\x01#$\n\x0c\n\x02\x04\n\x12\x06\xf6\x01\0\x82\
-    \x02\x01\n\x0b\n\x03\x04\n\x01\x12\x04\xf6\x01\x08\x14\n9\n\x04\x04\n\
-    \x02\0\x12\x04\xf7\x01\x04.\"+\x20how\x20many\x20decoys\x20were\x20tried\
-    \x20before\x20success\n\n\r\n\x05\x04\n\x02\0\x04\x12\x04\xf7\x01\x04\
-    \x0c\n\r\n\x05\x04\n\x02\0\x05\x12\x04\xf7\x01\r\x13\n\r\n\x05\x04\n\x02\
-    \0\x01\x12\x04\xf7\x01\x14(\n\r\n\x05\x04\n\x02\0\x03\x12\x04\xf7\x01+-\
-    \nm\n\x04\x04\n\x02\x01\x12\x04\xfc\x01\x04/\x1a\x1e\x20Applicable\x20to\
-    \x20whole\x20session:\n\"\x1a\x20includes\x20failed\x20attempts\n2#\x20T\
-    imings\x20below\x20are\x20in\x20milliseconds\n\n\r\n\x05\x04\n\x02\x01\
-    \x04\x12\x04\xfc\x01\x04\x0c\n\r\n\x05\x04\n\x02\x01\x05\x12\x04\xfc\x01\
-    \r\x13\n\r\n\x05\x04\n\x02\x01\x01\x12\x04\xfc\x01\x14)\n\r\n\x05\x04\n\
-    \x02\x01\x03\x12\x04\xfc\x01,.\nR\n\x04\x04\n\x02\x02\x12\x04\xff\x01\
-    \x04(\x1a\x1f\x20Last\x20(i.e.\x20successful)\x20decoy:\n\"#\x20measured\
-    \x20during\x20initial\x20handshake\n\n\r\n\x05\x04\n\x02\x02\x04\x12\x04\
-    \xff\x01\x04\x0c\n\r\n\x05\x04\n\x02\x02\x05\x12\x04\xff\x01\r\x13\n\r\n\
-    \x05\x04\n\x02\x02\x01\x12\x04\xff\x01\x14\"\n\r\n\x05\x04\n\x02\x02\x03\
-    \x12\x04\xff\x01%'\n%\n\x04\x04\n\x02\x03\x12\x04\x80\x02\x04&\"\x17\x20\
-    includes\x20tcp\x20to\x20decoy\n\n\r\n\x05\x04\n\x02\x03\x04\x12\x04\x80\
-    \x02\x04\x0c\n\r\n\x05\x04\n\x02\x03\x05\x12\x04\x80\x02\r\x13\n\r\n\x05\
-    \x04\n\x02\x03\x01\x12\x04\x80\x02\x14\x20\n\r\n\x05\x04\n\x02\x03\x03\
-    \x12\x04\x80\x02#%\nB\n\x04\x04\n\x02\x04\x12\x04\x81\x02\x04&\"4\x20mea\
-    sured\x20when\x20establishing\x20tcp\x20connection\x20to\x20decot\n\n\r\
-    \n\x05\x04\n\x02\x04\x04\x12\x04\x81\x02\x04\x0c\n\r\n\x05\x04\n\x02\x04\
-    \x05\x12\x04\x81\x02\r\x13\n\r\n\x05\x04\n\x02\x04\x01\x12\x04\x81\x02\
-    \x14\x20\n\r\n\x05\x04\n\x02\x04\x03\x12\x04\x81\x02#%\n\x0c\n\x02\x04\
-    \x0b\x12\x06\x84\x02\0\x89\x02\x01\n\x0b\n\x03\x04\x0b\x01\x12\x04\x84\
-    \x02\x08\x19\n\x0c\n\x04\x04\x0b\x02\0\x12\x04\x85\x02\x04#\n\r\n\x05\
-    \x04\x0b\x02\0\x04\x12\x04\x85\x02\x04\x0c\n\r\n\x05\x04\x0b\x02\0\x05\
-    \x12\x04\x85\x02\r\x13\n\r\n\x05\x04\x0b\x02\0\x01\x12\x04\x85\x02\x14\
-    \x1e\n\r\n\x05\x04\x0b\x02\0\x03\x12\x04\x85\x02!\"\n\x0c\n\x04\x04\x0b\
-    \x02\x01\x12\x04\x86\x02\x04\"\n\r\n\x05\x04\x0b\x02\x01\x04\x12\x04\x86\
-    \x02\x04\x0c\n\r\n\x05\x04\x0b\x02\x01\x05\x12\x04\x86\x02\r\x13\n\r\n\
-    \x05\x04\x0b\x02\x01\x01\x12\x04\x86\x02\x14\x1d\n\r\n\x05\x04\x0b\x02\
-    \x01\x03\x12\x04\x86\x02\x20!\n\x0c\n\x04\x04\x0b\x02\x02\x12\x04\x87\
-    \x02\x04#\n\r\n\x05\x04\x0b\x02\x02\x04\x12\x04\x87\x02\x04\x0c\n\r\n\
-    \x05\x04\x0b\x02\x02\x05\x12\x04\x87\x02\r\x13\n\r\n\x05\x04\x0b\x02\x02\
-    \x01\x12\x04\x87\x02\x14\x1e\n\r\n\x05\x04\x0b\x02\x02\x03\x12\x04\x87\
-    \x02!\"\n\x0c\n\x04\x04\x0b\x02\x03\x12\x04\x88\x02\x04%\n\r\n\x05\x04\
-    \x0b\x02\x03\x04\x12\x04\x88\x02\x04\x0c\n\r\n\x05\x04\x0b\x02\x03\x05\
-    \x12\x04\x88\x02\r\x13\n\r\n\x05\x04\x0b\x02\x03\x01\x12\x04\x88\x02\x14\
-    \x20\n\r\n\x05\x04\x0b\x02\x03\x03\x12\x04\x88\x02#$\
+    \x20\x01(\rR\ntcpToDecoy\"\xf0\x01\n\nConfigPush\x12\x14\n\x05epoch\x18\
+    \x01\x20\x01(\x04R\x05epoch\x12,\n\x12default_timeout_ns\x18\x02\x20\x01\
+    (\x04R\x10defaultTimeoutNs\x12F\n\x12allowed_transports\x18\x03\x20\x03(\
+    \x0e2\x17.tapdance.TransportTypeR\x11allowedTransports\x12)\n\x10client_\
+    blocklist\x18\x04\x20\x03(\tR\x0fclientBlocklist\x12+\n\x11phantom_block\
+    list\x18\x05\x20\x03(\tR\x10phantomBlocklist\"L\n\x10PhantomSubnetSet\
+    \x12\x1e\n\ngeneration\x18\x01\x20\x01(\x04R\ngeneration\x12\x18\n\x07su\
+    bnets\x18\x02\x20\x03(\tR\x07subnets\"\x9a\x07\n\x11StationToDetector\
+    \x12\x1d\n\nphantom_ip\x18\x01\x20\x01(\tR\tphantomIp\x12\x1b\n\tclient_\
+    ip\x18\x02\x20\x01(\tR\x08clientIp\x12\x1d\n\ntimeout_ns\x18\x03\x20\x01\
+    (\x04R\ttimeoutNs\x12!\n\x0cphantom_port\x18\x04\x20\x01(\rR\x0bphantomP\
+    ort\x12M\n\x13registration_source\x18\x05\x20\x01(\x0e2\x1c.tapdance.Reg\
+    istrationSourceR\x12registrationSource\x12\"\n\rphantom_ip_v6\x18\x06\
+    \x20\x01(\tR\x0bphantomIpV6\x12'\n\x0fmax_connections\x18\x07\x20\x01(\r\
+    R\x0emaxConnections\x12+\n\x12expires_at_unix_ns\x18\x08\x20\x01(\x04R\
+    \x0fexpiresAtUnixNs\x12/\n\x14station_time_unix_ns\x18\t\x20\x01(\x04R\
+    \x11stationTimeUnixNs\x12\x1d\n\nmessage_id\x18\n\x20\x01(\x0cR\tmessage\
+    Id\x125\n\ttransport\x18\x0b\x20\x01(\x0e2\x17.tapdance.TransportTypeR\t\
+    transport\x125\n\x0bconfig_push\x18\x0c\x20\x01(\x0b2\x14.tapdance.Confi\
+    gPushR\nconfigPush\x12C\n\x0fphantom_subnets\x18\r\x20\x01(\x0b2\x1a.tap\
+    dance.PhantomSubnetSetR\x0ephantomSubnets\x12)\n\x04mode\x18\x0e\x20\x01\
+    (\x0e2\x15.tapdance.SessionModeR\x04mode\x12!\n\x0cpublisher_id\x18\x0f\
+    \x20\x01(\rR\x0bpublisherId\x12\x1a\n\x08sequence\x18\x10\x20\x01(\x04R\
+    \x08sequence\x12\x1b\n\ttenant_id\x18\x11\x20\x01(\rR\x08tenantId\x124\n\
+    \x16delete_tenant_sessions\x18\x12\x20\x01(\x08R\x14deleteTenantSessions\
+    \x12!\n\x0cextension_ns\x18\x13\x20\x01(\x04R\x0bextensionNs\x12#\n\rupd\
+    ate_expiry\x18\x14\x20\x01(\x08R\x0cupdateExpiry\x126\n\x0cclient_ports\
+    \x18\x15\x20\x01(\x0b2\x13.tapdance.PortRangeR\x0bclientPorts\"5\n\tPort\
+    Range\x12\x14\n\x05first\x18\x01\x20\x01(\rR\x05first\x12\x12\n\x04last\
+    \x18\x02\x20\x01(\rR\x04last\"\xa4\x01\n\x10SequenceGapCount\x12!\n\x0cp\
+    ublisher_id\x18\x01\x20\x01(\rR\x0bpublisherId\x12\x1a\n\x08received\x18\
+    \x02\x20\x01(\x04R\x08received\x12\x18\n\x07missing\x18\x03\x20\x01(\x04\
+    R\x07missing\x12\x12\n\x04late\x18\x04\x20\x01(\x04R\x04late\x12#\n\rlas\
+    t_sequence\x18\x05\x20\x01(\x04R\x0clastSequence\"\xdc\x01\n\x11NeverMat\
+    chedCount\x125\n\ttransport\x18\x01\x20\x01(\x0e2\x17.tapdance.Transport\
+    TypeR\ttransport\x12M\n\x13registration_source\x18\x02\x20\x01(\x0e2\x1c\
+    .tapdance.RegistrationSourceR\x12registrationSource\x12%\n\x0ephantom_su\
+    bnet\x18\x03\x20\x01(\tR\rphantomSubnet\x12\x1a\n\x08sessions\x18\x04\
+    \x20\x01(\x04R\x08sessions\"\xe3\x06\n\x11DetectorToStation\x12-\n\x05ev\
+    ent\x18\x01\x20\x01(\x0e2\x17.tapdance.DetectorEventR\x05event\x12\x1d\n\
+    \nsession_id\x18\x02\x20\x01(\x04R\tsessionId\x12\x1d\n\nphantom_ip\x18\
+    \x03\x20\x01(\tR\tphantomIp\x12\x1b\n\tclient_ip\x18\x04\x20\x01(\tR\x08\
+    clientIp\x12!\n\x0cphantom_port\x18\x05\x20\x01(\rR\x0bphantomPort\x12\
+    \x1f\n\x0bclient_port\x18\x06\x20\x01(\rR\nclientPort\x12M\n\x13registra\
+    tion_source\x18\x07\x20\x01(\x0e2\x1c.tapdance.RegistrationSourceR\x12re\
+    gistrationSource\x12@\n\rnever_matched\x18\x08\x20\x03(\x0b2\x1b.tapdanc\
+    e.NeverMatchedCountR\x0cneverMatched\x12!\n\x0cconfig_epoch\x18\t\x20\
+    \x01(\x04R\x0bconfigEpoch\x12!\n\x0cconfig_error\x18\n\x20\x01(\tR\x0bco\
+    nfigError\x12<\n\x1aphantom_subnets_generation\x18\x0b\x20\x01(\x04R\x18\
+    phantomSubnetsGeneration\x12\x20\n\x0bconnections\x18\x0c\x20\x01(\rR\
+    \x0bconnections\x12\x18\n\x07packets\x18\r\x20\x01(\x04R\x07packets\x12\
+    \x14\n\x05bytes\x18\x0e\x20\x01(\x04R\x05bytes\x12\x1a\n\x08affinity\x18\
+    \x0f\x20\x01(\rR\x08affinity\x122\n\x05alert\x18\x10\x20\x01(\x0e2\x1c.t\
+    apdance.SessionCountChangeR\x05alert\x12#\n\rsession_count\x18\x11\x20\
+    \x01(\x04R\x0csessionCount\x124\n\x16previous_session_count\x18\x12\x20\
+    \x01(\x04R\x14previousSessionCount\x12\x12\n\x04core\x18\x13\x20\x01(\rR\
+    \x04core\x12?\n\rsequence_gaps\x18\x14\x20\x03(\x0b2\x1a.tapdance.Sequen\
+    ceGapCountR\x0csequenceGaps\x12\x19\n\x08group_id\x18\x15\x20\x01(\x04R\
+    \x07groupId\"\xad\x03\n\x08QaSample\x125\n\ttransport\x18\x01\x20\x01(\
+    \x0e2\x17.tapdance.TransportTypeR\ttransport\x12M\n\x13registration_sour\
+    ce\x18\x02\x20\x01(\x0e2\x1c.tapdance.RegistrationSourceR\x12registratio\
+    nSource\x12!\n\x0cphantom_port\x18\x03\x20\x01(\rR\x0bphantomPort\x12\
+    \x12\n\x04ipv6\x18\x04\x20\x01(\x08R\x04ipv6\x12\x1b\n\twindow_ms\x18\
+    \x05\x20\x01(\rR\x08windowMs\x12\x20\n\x0bconnections\x18\x06\x20\x01(\r\
+    R\x0bconnections\x12\x18\n\x07packets\x18\x07\x20\x01(\x04R\x07packets\
+    \x12\x14\n\x05bytes\x18\x08\x20\x01(\x04R\x05bytes\x12(\n\x10handshake_r\
+    tt_us\x18\t\x20\x01(\x04R\x0ehandshakeRttUs\x12\"\n\rfirst_data_us\x18\n\
+    \x20\x01(\x04R\x0bfirstDataUs\x12'\n\x0ftls_fingerprint\x18\x0b\x20\x01(\
+    \tR\x0etlsFingerprint\"\xc7\x03\n\x11ReplicatedSession\x12\x1b\n\tclient\
+    _ip\x18\x01\x20\x01(\x0cR\x08clientIp\x12\x1d\n\nphantom_ip\x18\x02\x20\
+    \x01(\x0cR\tphantomIp\x12!\n\x0cphantom_port\x18\x03\x20\x01(\rR\x0bphan\
+    tomPort\x12\x1d\n\ntimeout_ns\x18\x04\x20\x01(\x04R\ttimeoutNs\x12M\n\
+    \x13registration_source\x18\x05\x20\x01(\x0e2\x1c.tapdance.RegistrationS\
+    ourceR\x12registrationSource\x12'\n\x0fmax_connections\x18\x06\x20\x01(\
+    \rR\x0emaxConnections\x12)\n\x04mode\x18\x07\x20\x01(\x0e2\x15.tapdance.\
+    SessionModeR\x04mode\x12\x1b\n\ttenant_id\x18\x08\x20\x01(\rR\x08tenantI\
+    d\x12!\n\x0cextension_ns\x18\t\x20\x01(\x04R\x0bextensionNs\x126\n\x0ccl\
+    ient_ports\x18\n\x20\x01(\x0b2\x13.tapdance.PortRangeR\x0bclientPorts\
+    \x12\x19\n\x08group_id\x18\x0b\x20\x01(\x04R\x07groupId\"}\n\x12Sessi\
+    onReplication\x12\x16\n\x06origin\x18\x01\x20\x01(\rR\x06origin\x127\n\
+    \x08sessions\x18\x02\x20\x03(\x0b2\x1b.tapdance.ReplicatedSessionR\x08\
+    sessions\x12\x16\n\x06update\x18\x03\x20\x01(\x08R\x06update\"\xa3\x04\
+    \n\rSessionRecord\x12\x19\n\x08key_hash\x18\x01\x20\x01(\x0cR\
+    \x07keyHash\x12-\n\x13export_time_unix_ns\x18\x02\x20\x01(\x04R\x10expor\
+    tTimeUnixNs\x125\n\ttransport\x18\x03\x20\x01(\x0e2\x17.tapdance.Transpo\
+    rtTypeR\ttransport\x12M\n\x13registration_source\x18\x04\x20\x01(\x0e2\
+    \x1c.tapdance.RegistrationSourceR\x12registrationSource\x12!\n\x0cphanto\
+    m_port\x18\x05\x20\x01(\rR\x0bphantomPort\x12\x12\n\x04ipv6\x18\x06\x20\
+    \x01(\x08R\x04ipv6\x12\x1d\n\ntimeout_ns\x18\x07\x20\x01(\x04R\ttimeoutN\
+    s\x12\x15\n\x06age_ns\x18\x08\x20\x01(\x04R\x05ageNs\x12!\n\x0cremaining\
+    _ns\x18\t\x20\x01(\x04R\x0bremainingNs\x12$\n\rregistrations\x18\n\x20\
+    \x01(\rR\rregistrations\x12\x20\n\x0bconnections\x18\x0b\x20\x01(\rR\x0b\
+    connections\x12-\n\x12active_connections\x18\x0c\x20\x01(\rR\x11activeCo\
+    nnections\x12\x1e\n\nextensions\x18\r\x20\x01(\rR\nextensions\x12\x1b\n\
+    \tclient_id\x18\x0e\x20\x01(\x06R\x08clientId*+\n\x07KeyType\x12\x0f\n\
+    \x0bAES_GCM_128\x10Z\x12\x0f\n\x0bAES_GCM_256\x10[*\xe7\x01\n\x0eC2S_Tra\
+    nsition\x12\x11\n\rC2S_NO_CHANGE\x10\0\x12\x14\n\x10C2S_SESSION_INIT\x10\
+    \x01\x12\x1b\n\x17C2S_SESSION_COVERT_INIT\x10\x0b\x12\x18\n\x14C2S_EXPEC\
+    T_RECONNECT\x10\x02\x12\x15\n\x11C2S_SESSION_CLOSE\x10\x03\x12\x14\n\x10\
+    C2S_YIELD_UPLOAD\x10\x04\x12\x16\n\x12C2S_ACQUIRE_UPLOAD\x10\x05\x12\x20\
+    \n\x1cC2S_EXPECT_UPLOADONLY_RECONN\x10\x06\x12\x0e\n\tC2S_ERROR\x10\xff\
+    \x01*\x98\x01\n\x0eS2C_Transition\x12\x11\n\rS2C_NO_CHANGE\x10\0\x12\x14\
+    \n\x10S2C_SESSION_INIT\x10\x01\x12\x1b\n\x17S2C_SESSION_COVERT_INIT\x10\
+    \x0b\x12\x19\n\x15S2C_CONFIRM_RECONNECT\x10\x02\x12\x15\n\x11S2C_SESSION\
+    _CLOSE\x10\x03\x12\x0e\n\tS2C_ERROR\x10\xff\x01*\xac\x01\n\x0eErrorReaso\
+    nS2C\x12\x0c\n\x08NO_ERROR\x10\0\x12\x11\n\rCOVERT_STREAM\x10\x01\x12\
+    \x13\n\x0fCLIENT_REPORTED\x10\x02\x12\x13\n\x0fCLIENT_PROTOCOL\x10\x03\
+    \x12\x14\n\x10STATION_INTERNAL\x10\x04\x12\x12\n\x0eDECOY_OVERLOAD\x10\
+    \x05\x12\x11\n\rCLIENT_STREAM\x10d\x12\x12\n\x0eCLIENT_TIMEOUT\x10e*9\n\
+    \rTransportType\x12\x08\n\x04Null\x10\0\x12\x07\n\x03Min\x10\x01\x12\t\n\
+    \x05Obfs4\x10\x02\x12\n\n\x06Prefix\x10\x03*%\n\x0bSessionMode\x12\x0b\n\
+    \x07Phantom\x10\0\x12\t\n\x05Decoy\x10\x01*Q\n\x12RegistrationSource\x12\
+    \x0f\n\x0bUnspecified\x10\0\x12\x0c\n\x08Detector\x10\x01\x12\x07\n\x03A\
+    PI\x10\x02\x12\x13\n\x0fDetectorPrescan\x10\x03*\xc0\x01\n\rDetectorEven\
+    t\x12\x10\n\x0cUnknownEvent\x10\0\x12\x12\n\x0eSessionMatched\x10\x01\
+    \x12\x17\n\x13NeverMatchedSummary\x10\x02\x12\r\n\tConfigAck\x10\x03\x12\
+    \r\n\tHeartbeat\x10\x04\x12\x15\n\x11PhantomSubnetsAck\x10\x05\x12\x12\n\
+    \x0eSessionExpired\x10\x06\x12\x15\n\x11SessionCountAlert\x10\x07\x12\
+    \x10\n\x0cSequenceGaps\x10\x08*o\n\x12SessionCountChange\x12\x11\n\rUnkn\
+    ownChange\x10\0\x12\r\n\tAboveHigh\x10\x01\x12\x0c\n\x08BelowLow\x10\x02\
+    \x12\x14\n\x10WithinWatermarks\x10\x03\x12\t\n\x05Surge\x10\x04\x12\x08\
+    \n\x04Drop\x10\x05J\xea\xe0\x01\n\x07\x12\x05\0\0\xdd\x04\x01\n\x08\n\
+    \x01\x0c\x12\x03\0\0\x12\n\xb0\x01\n\x01\x02\x12\x03\x06\0\x112\xa5\x01\
+    \x20TODO:\x20We're\x20using\x20proto2\x20because\x20it's\x20the\x20defau\
+    lt\x20on\x20Ubuntu\x2016.04.\n\x20At\x20some\x20point\x20we\x20will\x20w\
+    ant\x20to\x20migrate\x20to\x20proto3,\x20but\x20we\x20are\x20not\n\x20us\
+    ing\x20any\x20proto3\x20features\x20yet.\n\n\n\n\x02\x05\0\x12\x04\x08\0\
+    \x0b\x01\n\n\n\x03\x05\0\x01\x12\x03\x08\x05\x0c\n\x0b\n\x04\x05\0\x02\0\
+    \x12\x03\t\x04\x15\n\x0c\n\x05\x05\0\x02\0\x01\x12\x03\t\x04\x0f\n\x0c\n\
+    \x05\x05\0\x02\0\x02\x12\x03\t\x12\x14\n\x20\n\x04\x05\0\x02\x01\x12\x03\
+    \n\x04\x15\"\x13\x20not\x20supported\x20atm\n\n\x0c\n\x05\x05\0\x02\x01\
+    \x01\x12\x03\n\x04\x0f\n\x0c\n\x05\x05\0\x02\x01\x02\x12\x03\n\x12\x14\n\
+    \n\n\x02\x04\0\x12\x04\r\0\x12\x01\n\n\n\x03\x04\0\x01\x12\x03\r\x08\x0e\
+    \n4\n\x04\x04\0\x02\0\x12\x03\x0f\x04\x1b\x1a'\x20A\x20public\x20key,\
+    \x20as\x20used\x20by\x20the\x20station.\n\n\x0c\n\x05\x04\0\x02\0\x04\
+    \x12\x03\x0f\x04\x0c\n\x0c\n\x05\x04\0\x02\0\x05\x12\x03\x0f\r\x12\n\x0c\
+    \n\x05\x04\0\x02\0\x01\x12\x03\x0f\x13\x16\n\x0c\n\x05\x04\0\x02\0\x03\
+    \x12\x03\x0f\x19\x1a\n\x0b\n\x04\x04\0\x02\x01\x12\x03\x11\x04\x1e\n\x0c\
+    \n\x05\x04\0\x02\x01\x04\x12\x03\x11\x04\x0c\n\x0c\n\x05\x04\0\x02\x01\
+    \x06\x12\x03\x11\r\x14\n\x0c\n\x05\x04\0\x02\x01\x01\x12\x03\x11\x15\x19\
+    \n\x0c\n\x05\x04\0\x02\x01\x03\x12\x03\x11\x1c\x1d\n\n\n\x02\x04\x01\x12\
+    \x04\x14\0:\x01\n\n\n\x03\x04\x01\x01\x12\x03\x14\x08\x14\n\xa1\x01\n\
+    \x04\x04\x01\x02\0\x12\x03\x19\x04!\x1a\x93\x01\x20The\x20hostname/SNI\
+    \x20to\x20use\x20for\x20this\x20host\n\n\x20The\x20hostname\x20is\x20the\
+    \x20only\x20required\x20field,\x20although\x20other\n\x20fields\x20are\
+    \x20expected\x20to\x20be\x20present\x20in\x20most\x20cases.\n\n\x0c\n\
+    \x05\x04\x01\x02\0\x04\x12\x03\x19\x04\x0c\n\x0c\n\x05\x04\x01\x02\0\x05\
+    \x12\x03\x19\r\x13\n\x0c\n\x05\x04\x01\x02\0\x01\x12\x03\x19\x14\x1c\n\
+    \x0c\n\x05\x04\x01\x02\0\x03\x12\x03\x19\x1f\x20\n\xf7\x01\n\x04\x04\x01\
+    \x02\x01\x12\x03\x20\x04\"\x1a\xe9\x01\x20The\x2032-bit\x20ipv4\x20addre\
+    ss,\x20in\x20network\x20byte\x20order\n\n\x20If\x20the\x20IPv4\x20addres\
+    s\x20is\x20absent,\x20then\x20it\x20may\x20be\x20resolved\x20via\n\x20DN\
+    S\x20by\x20the\x20client,\x20or\x20the\x20client\x20may\x20discard\x20th\
+    is\x20decoy\x20spec\n\x20if\x20local\x20DNS\x20is\x20untrusted,\x20or\
+    \x20the\x20service\x20may\x20be\x20multihomed.\n\n\x0c\n\x05\x04\x01\x02\
+    \x01\x04\x12\x03\x20\x04\x0c\n\x0c\n\x05\x04\x01\x02\x01\x05\x12\x03\x20\
+    \r\x14\n\x0c\n\x05\x04\x01\x02\x01\x01\x12\x03\x20\x15\x1d\n\x0c\n\x05\
+    \x04\x01\x02\x01\x03\x12\x03\x20\x20!\n>\n\x04\x04\x01\x02\x02\x12\x03#\
+    \x04\x20\x1a1\x20The\x20128-bit\x20ipv6\x20address,\x20in\x20network\x20\
+    byte\x20order\n\n\x0c\n\x05\x04\x01\x02\x02\x04\x12\x03#\x04\x0c\n\x0c\n\
+    \x05\x04\x01\x02\x02\x05\x12\x03#\r\x12\n\x0c\n\x05\x04\x01\x02\x02\x01\
+    \x12\x03#\x13\x1b\n\x0c\n\x05\x04\x01\x02\x02\x03\x12\x03#\x1e\x1f\n\x91\
+    \x01\n\x04\x04\x01\x02\x03\x12\x03)\x04\x1f\x1a\x83\x01\x20The\x20Tapdan\
+    ce\x20station\x20public\x20key\x20to\x20use\x20when\x20contacting\x20thi\
+    s\n\x20decoy\n\n\x20If\x20omitted,\x20the\x20default\x20station\x20publi\
+    c\x20key\x20(if\x20any)\x20is\x20used.\n\n\x0c\n\x05\x04\x01\x02\x03\x04\
+    \x12\x03)\x04\x0c\n\x0c\n\x05\x04\x01\x02\x03\x06\x12\x03)\r\x13\n\x0c\n\
+    \x05\x04\x01\x02\x03\x01\x12\x03)\x14\x1a\n\x0c\n\x05\x04\x01\x02\x03\
+    \x03\x12\x03)\x1d\x1e\n\xee\x01\n\x04\x04\x01\x02\x04\x12\x030\x04\x20\
+    \x1a\xe0\x01\x20The\x20maximum\x20duration,\x20in\x20milliseconds,\x20to\
+    \x20maintain\x20an\x20open\n\x20connection\x20to\x20this\x20decoy\x20(be\
+    cause\x20the\x20decoy\x20may\x20close\x20the\n\x20connection\x20itself\
+    \x20after\x20this\x20length\x20of\x20time)\n\n\x20If\x20omitted,\x20a\
+    \x20default\x20of\x2030,000\x20milliseconds\x20is\x20assumed.\n\n\x0c\n\
+    \x05\x04\x01\x02\x04\x04\x12\x030\x04\x0c\n\x0c\n\x05\x04\x01\x02\x04\
+    \x05\x12\x030\r\x13\n\x0c\n\x05\x04\x01\x02\x04\x01\x12\x030\x14\x1b\n\
+    \x0c\n\x05\x04\x01\x02\x04\x03\x12\x030\x1e\x1f\n\xb0\x02\n\x04\x04\x01\
+    \x02\x05\x12\x039\x04\x1f\x1a\xa2\x02\x20The\x20maximum\x20TCP\x20window\
+    \x20size\x20to\x20attempt\x20to\x20use\x20for\x20this\x20decoy.\n\n\x20I\
+    f\x20omitted,\x20a\x20default\x20of\x2015360\x20is\x20assumed.\n\n\x20TO\
+    DO:\x20the\x20default\x20is\x20based\x20on\x20the\x20current\x20heuristi\
+    c\x20of\x20only\n\x20using\x20decoys\x20that\x20permit\x20windows\x20of\
+    \x2015KB\x20or\x20larger.\x20\x20If\x20this\n\x20heuristic\x20changes,\
+    \x20then\x20this\x20default\x20doesn't\x20make\x20sense.\n\n\x0c\n\x05\
+    \x04\x01\x02\x05\x04\x12\x039\x04\x0c\n\x0c\n\x05\x04\x01\x02\x05\x05\
+    \x12\x039\r\x13\n\x0c\n\x05\x04\x01\x02\x05\x01\x12\x039\x14\x1a\n\x0c\n\
+    \x05\x04\x01\x02\x05\x03\x12\x039\x1d\x1e\n\x83\x08\n\x02\x04\x02\x12\
+    \x04Q\0W\x012\xf6\x07\x20In\x20version\x201,\x20the\x20request\x20is\x20\
+    very\x20simple:\x20when\n\x20the\x20client\x20sends\x20a\x20MSG_PROTO\
+    \x20to\x20the\x20station,\x20if\x20the\n\x20generation\x20number\x20is\
+    \x20present,\x20then\x20this\x20request\x20includes\n\x20(in\x20addition\
+    \x20to\x20whatever\x20other\x20operations\x20are\x20part\x20of\x20the\n\
+    \x20request)\x20a\x20request\x20for\x20the\x20station\x20to\x20send\x20a\
+    \x20copy\x20of\n\x20the\x20current\x20decoy\x20set\x20that\x20has\x20a\
+    \x20generation\x20number\x20greater\n\x20than\x20the\x20generation\x20nu\
+    mber\x20in\x20its\x20request.\n\n\x20If\x20the\x20response\x20contains\
+    \x20a\x20DecoyListUpdate\x20with\x20a\x20generation\x20number\x20equal\n\
+    \x20to\x20that\x20which\x20the\x20client\x20sent,\x20then\x20the\x20clie\
+    nt\x20is\x20\"caught\x20up\"\x20with\n\x20the\x20station\x20and\x20the\
+    \x20response\x20contains\x20no\x20new\x20information\n\x20(and\x20all\
+    \x20other\x20fields\x20may\x20be\x20omitted\x20or\x20empty).\x20\x20Othe\
+    rwise,\n\x20the\x20station\x20will\x20send\x20the\x20latest\x20configura\
+    tion\x20information,\n\x20along\x20with\x20its\x20generation\x20number.\
+    \n\n\x20The\x20station\x20can\x20also\x20send\x20ClientConf\x20messages\
+    \n\x20(as\x20part\x20of\x20Station2Client\x20messages)\x20whenever\x20it\
+    \x20wants.\n\x20The\x20client\x20is\x20expected\x20to\x20react\x20as\x20\
+    if\x20it\x20had\x20requested\n\x20such\x20messages\x20--\x20possibly\x20\
+    by\x20ignoring\x20them,\x20if\x20the\x20client\n\x20is\x20already\x20up-\
+    to-date\x20according\x20to\x20the\x20generation\x20number.\n\n\n\n\x03\
+    \x04\x02\x01\x12\x03Q\x08\x12\n\x0b\n\x04\x04\x02\x02\0\x12\x03R\x04&\n\
+    \x0c\n\x05\x04\x02\x02\0\x04\x12\x03R\x04\x0c\n\x0c\n\x05\x04\x02\x02\0\
+    \x06\x12\x03R\r\x16\n\x0c\n\x05\x04\x02\x02\0\x01\x12\x03R\x17!\n\x0c\n\
+    \x05\x04\x02\x02\0\x03\x12\x03R$%\n\x0b\n\x04\x04\x02\x02\x01\x12\x03S\
+    \x04#\n\x0c\n\x05\x04\x02\x02\x01\x04\x12\x03S\x04\x0c\n\x0c\n\x05\x04\
+    \x02\x02\x01\x05\x12\x03S\r\x13\n\x0c\n\x05\x04\x02\x02\x01\x01\x12\x03S\
+    \x14\x1e\n\x0c\n\x05\x04\x02\x02\x01\x03\x12\x03S!\"\n\x0b\n\x04\x04\x02\
+    \x02\x02\x12\x03T\x04'\n\x0c\n\x05\x04\x02\x02\x02\x04\x12\x03T\x04\x0c\
+    \n\x0c\n\x05\x04\x02\x02\x02\x06\x12\x03T\r\x13\n\x0c\n\x05\x04\x02\x02\
+    \x02\x01\x12\x03T\x14\"\n\x0c\n\x05\x04\x02\x02\x02\x03\x12\x03T%&\n\x0b\
+    \n\x04\x04\x02\x02\x03\x12\x03U\x049\n\x0c\n\x05\x04\x02\x02\x03\x04\x12\
+    \x03U\x04\x0c\n\x0c\n\x05\x04\x02\x02\x03\x06\x12\x03U\r\x1f\n\x0c\n\x05\
+    \x04\x02\x02\x03\x01\x12\x03U\x204\n\x0c\n\x05\x04\x02\x02\x03\x03\x12\
+    \x03U78\n\x0b\n\x04\x04\x02\x02\x04\x12\x03V\x04'\n\x0c\n\x05\x04\x02\
+    \x02\x04\x04\x12\x03V\x04\x0c\n\x0c\n\x05\x04\x02\x02\x04\x06\x12\x03V\r\
+    \x13\n\x0c\n\x05\x04\x02\x02\x04\x01\x12\x03V\x14\"\n\x0c\n\x05\x04\x02\
+    \x02\x04\x03\x12\x03V%&\n\n\n\x02\x04\x03\x12\x04Y\0[\x01\n\n\n\x03\x04\
+    \x03\x01\x12\x03Y\x08\x11\n\x0b\n\x04\x04\x03\x02\0\x12\x03Z\x04)\n\x0c\
+    \n\x05\x04\x03\x02\0\x04\x12\x03Z\x04\x0c\n\x0c\n\x05\x04\x03\x02\0\x06\
+    \x12\x03Z\r\x19\n\x0c\n\x05\x04\x03\x02\0\x01\x12\x03Z\x1a$\n\x0c\n\x05\
+    \x04\x03\x02\0\x03\x12\x03Z'(\n\n\n\x02\x04\x04\x12\x04]\0_\x01\n\n\n\
+    \x03\x04\x04\x01\x12\x03]\x08\x1a\n\x0b\n\x04\x04\x04\x02\0\x12\x03^\x04\
+    1\n\x0c\n\x05\x04\x04\x02\0\x04\x12\x03^\x04\x0c\n\x0c\n\x05\x04\x04\x02\
+    \0\x06\x12\x03^\r\x1b\n\x0c\n\x05\x04\x04\x02\0\x01\x12\x03^\x1c,\n\x0c\
+    \n\x05\x04\x04\x02\0\x03\x12\x03^/0\n\n\n\x02\x04\x05\x12\x04a\0d\x01\n\
+    \n\n\x03\x04\x05\x01\x12\x03a\x08\x16\n\x0b\n\x04\x04\x05\x02\0\x12\x03b\
+    \x04\x1f\n\x0c\n\x05\x04\x05\x02\0\x04\x12\x03b\x04\x0c\n\x0c\n\x05\x04\
+    \x05\x02\0\x05\x12\x03b\r\x13\n\x0c\n\x05\x04\x05\x02\0\x01\x12\x03b\x14\
+    \x1a\n\x0c\n\x05\x04\x05\x02\0\x03\x12\x03b\x1d\x1e\n\x0b\n\x04\x04\x05\
+    \x02\x01\x12\x03c\x04\x20\n\x0c\n\x05\x04\x05\x02\x01\x04\x12\x03c\x04\
+    \x0c\n\x0c\n\x05\x04\x05\x02\x01\x05\x12\x03c\r\x13\n\x0c\n\x05\x04\x05\
+    \x02\x01\x01\x12\x03c\x14\x1b\n\x0c\n\x05\x04\x05\x02\x01\x03\x12\x03c\
+    \x1e\x1f\n-\n\x02\x05\x01\x12\x04g\0q\x01\x1a!\x20State\x20transitions\
+    \x20of\x20the\x20client\n\n\n\n\x03\x05\x01\x01\x12\x03g\x05\x13\n\x0b\n\
+    \x04\x05\x01\x02\0\x12\x03h\x04\x16\n\x0c\n\x05\x05\x01\x02\0\x01\x12\
+    \x03h\x04\x11\n\x0c\n\x05\x05\x01\x02\0\x02\x12\x03h\x14\x15\n\"\n\x04\
+    \x05\x01\x02\x01\x12\x03i\x04\x19\"\x15\x20connect\x20me\x20to\x20squid\
+    \n\n\x0c\n\x05\x05\x01\x02\x01\x01\x12\x03i\x04\x14\n\x0c\n\x05\x05\x01\
+    \x02\x01\x02\x12\x03i\x17\x18\n,\n\x04\x05\x01\x02\x02\x12\x03j\x04!\"\
+    \x1f\x20connect\x20me\x20to\x20provided\x20covert\n\n\x0c\n\x05\x05\x01\
+    \x02\x02\x01\x12\x03j\x04\x1b\n\x0c\n\x05\x05\x01\x02\x02\x02\x12\x03j\
+    \x1e\x20\n\x0b\n\x04\x05\x01\x02\x03\x12\x03k\x04\x1d\n\x0c\n\x05\x05\
+    \x01\x02\x03\x01\x12\x03k\x04\x18\n\x0c\n\x05\x05\x01\x02\x03\x02\x12\
+    \x03k\x1b\x1c\n\x0b\n\x04\x05\x01\x02\x04\x12\x03l\x04\x1a\n\x0c\n\x05\
+    \x05\x01\x02\x04\x01\x12\x03l\x04\x15\n\x0c\n\x05\x05\x01\x02\x04\x02\
+    \x12\x03l\x18\x19\n\x0b\n\x04\x05\x01\x02\x05\x12\x03m\x04\x19\n\x0c\n\
+    \x05\x05\x01\x02\x05\x01\x12\x03m\x04\x14\n\x0c\n\x05\x05\x01\x02\x05\
+    \x02\x12\x03m\x17\x18\n\x0b\n\x04\x05\x01\x02\x06\x12\x03n\x04\x1b\n\x0c\
+    \n\x05\x05\x01\x02\x06\x01\x12\x03n\x04\x16\n\x0c\n\x05\x05\x01\x02\x06\
+    \x02\x12\x03n\x19\x1a\n\x0b\n\x04\x05\x01\x02\x07\x12\x03o\x04%\n\x0c\n\
+    \x05\x05\x01\x02\x07\x01\x12\x03o\x04\x20\n\x0c\n\x05\x05\x01\x02\x07\
+    \x02\x12\x03o#$\n\x0b\n\x04\x05\x01\x02\x08\x12\x03p\x04\x14\n\x0c\n\x05\
+    \x05\x01\x02\x08\x01\x12\x03p\x04\r\n\x0c\n\x05\x05\x01\x02\x08\x02\x12\
+    \x03p\x10\x13\n-\n\x02\x05\x02\x12\x04t\0|\x01\x1a!\x20State\x20transiti\
+    ons\x20of\x20the\x20server\n\n\n\n\x03\x05\x02\x01\x12\x03t\x05\x13\n\
+    \x0b\n\x04\x05\x02\x02\0\x12\x03u\x04\x16\n\x0c\n\x05\x05\x02\x02\0\x01\
+    \x12\x03u\x04\x11\n\x0c\n\x05\x05\x02\x02\0\x02\x12\x03u\x14\x15\n!\n\
+    \x04\x05\x02\x02\x01\x12\x03v\x04\x19\"\x14\x20connected\x20to\x20squid\
+    \n\n\x0c\n\x05\x05\x02\x02\x01\x01\x12\x03v\x04\x14\n\x0c\n\x05\x05\x02\
+    \x02\x01\x02\x12\x03v\x17\x18\n'\n\x04\x05\x02\x02\x02\x12\x03w\x04!\"\
+    \x1a\x20connected\x20to\x20covert\x20host\n\n\x0c\n\x05\x05\x02\x02\x02\
+    \x01\x12\x03w\x04\x1b\n\x0c\n\x05\x05\x02\x02\x02\x02\x12\x03w\x1e\x20\n\
+    \x0b\n\x04\x05\x02\x02\x03\x12\x03x\x04\x1e\n\x0c\n\x05\x05\x02\x02\x03\
+    \x01\x12\x03x\x04\x19\n\x0c\n\x05\x05\x02\x02\x03\x02\x12\x03x\x1c\x1d\n\
+    \x0b\n\x04\x05\x02\x02\x04\x12\x03y\x04\x1a\n\x0c\n\x05\x05\x02\x02\x04\
+    \x01\x12\x03y\x04\x15\n\x0c\n\x05\x05\x02\x02\x04\x02\x12\x03y\x18\x19\n\
+    R\n\x04\x05\x02\x02\x05\x12\x03{\x04\x14\x1aE\x20TODO\x20should\x20proba\
+    bly\x20also\x20allow\x20EXPECT_RECONNECT\x20here,\x20for\x20DittoTap\n\n\
+    \x0c\n\x05\x05\x02\x02\x05\x01\x12\x03{\x04\r\n\x0c\n\x05\x05\x02\x02\
+    \x05\x02\x12\x03{\x10\x13\n7\n\x02\x05\x03\x12\x05\x7f\0\x89\x01\x01\x1a\
+    *\x20Should\x20accompany\x20all\x20S2C_ERROR\x20messages.\n\n\n\n\x03\
+    \x05\x03\x01\x12\x03\x7f\x05\x13\n\x0c\n\x04\x05\x03\x02\0\x12\x04\x80\
+    \x01\x04\x11\n\r\n\x05\x05\x03\x02\0\x01\x12\x04\x80\x01\x04\x0c\n\r\n\
+    \x05\x05\x03\x02\0\x02\x12\x04\x80\x01\x0f\x10\n*\n\x04\x05\x03\x02\x01\
+    \x12\x04\x81\x01\x04\x16\"\x1c\x20Squid\x20TCP\x20connection\x20broke\n\
+    \n\r\n\x05\x05\x03\x02\x01\x01\x12\x04\x81\x01\x04\x11\n\r\n\x05\x05\x03\
+    \x02\x01\x02\x12\x04\x81\x01\x14\x15\n7\n\x04\x05\x03\x02\x02\x12\x04\
+    \x82\x01\x04\x18\")\x20You\x20told\x20me\x20something\x20was\x20wrong,\
+    \x20client\n\n\r\n\x05\x05\x03\x02\x02\x01\x12\x04\x82\x01\x04\x13\n\r\n\
+    \x05\x05\x03\x02\x02\x02\x12\x04\x82\x01\x16\x17\n@\n\x04\x05\x03\x02\
+    \x03\x12\x04\x83\x01\x04\x18\"2\x20You\x20messed\x20up,\x20client\x20(e.\
+    g.\x20sent\x20a\x20bad\x20protobuf)\n\n\r\n\x05\x05\x03\x02\x03\x01\x12\
+    \x04\x83\x01\x04\x13\n\r\n\x05\x05\x03\x02\x03\x02\x12\x04\x83\x01\x16\
+    \x17\n\x17\n\x04\x05\x03\x02\x04\x12\x04\x84\x01\x04\x19\"\t\x20I\x20bro\
+    ke\n\n\r\n\x05\x05\x03\x02\x04\x01\x12\x04\x84\x01\x04\x14\n\r\n\x05\x05\
+    \x03\x02\x04\x02\x12\x04\x84\x01\x17\x18\nE\n\x04\x05\x03\x02\x05\x12\
+    \x04\x85\x01\x04\x17\"7\x20Everything's\x20fine,\x20but\x20don't\x20use\
+    \x20this\x20decoy\x20right\x20now\n\n\r\n\x05\x05\x03\x02\x05\x01\x12\
+    \x04\x85\x01\x04\x12\n\r\n\x05\x05\x03\x02\x05\x02\x12\x04\x85\x01\x15\
+    \x16\nD\n\x04\x05\x03\x02\x06\x12\x04\x87\x01\x04\x18\"6\x20My\x20stream\
+    \x20to\x20you\x20broke.\x20(This\x20is\x20impossible\x20to\x20send)\n\n\
+    \r\n\x05\x05\x03\x02\x06\x01\x12\x04\x87\x01\x04\x11\n\r\n\x05\x05\x03\
+    \x02\x06\x02\x12\x04\x87\x01\x14\x17\nA\n\x04\x05\x03\x02\x07\x12\x04\
+    \x88\x01\x04\x19\"3\x20You\x20never\x20came\x20back.\x20(This\x20is\x20i\
+    mpossible\x20to\x20send)\n\n\r\n\x05\x05\x03\x02\x07\x01\x12\x04\x88\x01\
+    \x04\x12\n\r\n\x05\x05\x03\x02\x07\x02\x12\x04\x88\x01\x15\x18\n\x0c\n\
+    \x02\x05\x04\x12\x06\x8b\x01\0\x90\x01\x01\n\x0b\n\x03\x05\x04\x01\x12\
+    \x04\x8b\x01\x05\x12\n\x0c\n\x04\x05\x04\x02\0\x12\x04\x8c\x01\x04\r\n\r\
+    \n\x05\x05\x04\x02\0\x01\x12\x04\x8c\x01\x04\x08\n\r\n\x05\x05\x04\x02\0\
+    \x02\x12\x04\x8c\x01\x0b\x0c\n`\n\x04\x05\x04\x02\x01\x12\x04\x8d\x01\
+    \x04\x0c\"R\x20Send\x20a\x2032-byte\x20HMAC\x20id\x20to\x20let\x20the\
+    \x20station\x20distinguish\x20registrations\x20to\x20same\x20host\n\n\r\
+    \n\x05\x05\x04\x02\x01\x01\x12\x04\x8d\x01\x04\x07\n\r\n\x05\x05\x04\x02\
+    \x01\x02\x12\x04\x8d\x01\n\x0b\n$\n\x04\x05\x04\x02\x02\x12\x04\x8e\x01\
+    \x04\x0e\"\x16\x20Not\x20implemented\x20yet?\n\n\r\n\x05\x05\x04\x02\x02\
+    \x01\x12\x04\x8e\x01\x04\t\n\r\n\x05\x05\x04\x02\x02\x02\x12\x04\x8e\x01\
+    \x0c\r\nD\n\x04\x05\x04\x02\x03\x12\x04\x8f\x01\x04\x0f\"6\x20Connection\
+    s\x20open\x20with\x20one\x20of\x20a\x20set\x20of\x20known\x20prefixes\n\
+    \n\r\n\x05\x05\x04\x02\x03\x01\x12\x04\x8f\x01\x04\n\n\r\n\x05\x05\x04\
+    \x02\x03\x02\x12\x04\x8f\x01\r\x0e\nC\n\x02\x05\x05\x12\x06\x93\x01\0\
+    \x9a\x01\x01\x1a5\x20What\x20a\x20registration's\x20sessions\x20are\x20m\
+    atched\x20against.\n\n\x0b\n\x03\x05\x05\x01\x12\x04\x93\x01\x05\x10\n{\
+    \n\x04\x05\x05\x02\0\x12\x04\x96\x01\x04\x10\x1am\x20Connections\x20from\
+    \x20the\x20client's\x20address\x20(or\x20network)\x20to\x20a\x20phantom,\
+    \x20an\n\x20unused\x20address\x20in\x20the\x20phantom\x20subnets.\n\n\r\
+    \n\x05\x05\x05\x02\0\x01\x12\x04\x96\x01\x04\x0b\n\r\n\x05\x05\x05\x02\0\
+    \x02\x12\x04\x96\x01\x0e\x0f\nm\n\x04\x05\x05\x02\x01\x12\x04\x99\x01\
+    \x04\x0e\x1a_\x20Connections\x20from\x20the\x20client's\x20exact\x20addr\
+    ess\x20to\x20a\x20real\x20decoy\x20host\x20that\n\x20other\x20clients\
+    \x20use\x20too.\n\n\r\n\x05\x05\x05\x02\x01\x01\x12\x04\x99\x01\x04\t\n\
+    \r\n\x05\x05\x05\x02\x01\x02\x12\x04\x99\x01\x0c\r\n\x0c\n\x02\x04\x06\
+    \x12\x06\x9c\x01\0\xb3\x01\x01\n\x0b\n\x03\x04\x06\x01\x12\x04\x9c\x01\
+    \x08\x17\nO\n\x04\x04\x06\x02\0\x12\x04\x9e\x01\x04)\x1aA\x20Should\x20a\
+    ccompany\x20(at\x20least)\x20SESSION_INIT\x20and\x20CONFIRM_RECONNECT.\n\
+    \n\r\n\x05\x04\x06\x02\0\x04\x12\x04\x9e\x01\x04\x0c\n\r\n\x05\x04\x06\
+    \x02\0\x05\x12\x04\x9e\x01\r\x13\n\r\n\x05\x04\x06\x02\0\x01\x12\x04\x9e\
+    \x01\x14$\n\r\n\x05\x04\x06\x02\0\x03\x12\x04\x9e\x01'(\nv\n\x04\x04\x06\
+    \x02\x01\x12\x04\xa2\x01\x041\x1ah\x20There\x20might\x20be\x20a\x20state\
+    \x20transition.\x20May\x20be\x20absent;\x20absence\x20should\x20be\n\x20\
+    treated\x20identically\x20to\x20NO_CHANGE.\n\n\r\n\x05\x04\x06\x02\x01\
+    \x04\x12\x04\xa2\x01\x04\x0c\n\r\n\x05\x04\x06\x02\x01\x06\x12\x04\xa2\
+    \x01\r\x1b\n\r\n\x05\x04\x06\x02\x01\x01\x12\x04\xa2\x01\x1c,\n\r\n\x05\
+    \x04\x06\x02\x01\x03\x12\x04\xa2\x01/0\nc\n\x04\x04\x06\x02\x02\x12\x04\
+    \xa6\x01\x04(\x1aU\x20The\x20station\x20can\x20send\x20client\x20config\
+    \x20info\x20piggybacked\n\x20on\x20any\x20message,\x20as\x20it\x20sees\
+    \x20fit\n\n\r\n\x05\x04\x06\x02\x02\x04\x12\x04\xa6\x01\x04\x0c\n\r\n\
+    \x05\x04\x06\x02\x02\x06\x12\x04\xa6\x01\r\x17\n\r\n\x05\x04\x06\x02\x02\
+    \x01\x12\x04\xa6\x01\x18#\n\r\n\x05\x04\x06\x02\x02\x03\x12\x04\xa6\x01&\
+    '\nP\n\x04\x04\x06\x02\x03\x12\x04\xa9\x01\x04+\x1aB\x20If\x20state_tran\
+    sition\x20==\x20S2C_ERROR,\x20this\x20field\x20is\x20the\x20explanation.\
+    \n\n\r\n\x05\x04\x06\x02\x03\x04\x12\x04\xa9\x01\x04\x0c\n\r\n\x05\x04\
+    \x06\x02\x03\x06\x12\x04\xa9\x01\r\x1b\n\r\n\x05\x04\x06\x02\x03\x01\x12\
+    \x04\xa9\x01\x1c&\n\r\n\x05\x04\x06\x02\x03\x03\x12\x04\xa9\x01)*\nQ\n\
+    \x04\x04\x06\x02\x04\x12\x04\xac\x01\x04$\x1aC\x20Signals\x20client\x20t\
+    o\x20stop\x20connecting\x20for\x20following\x20amount\x20of\x20seconds\n\
+    \n\r\n\x05\x04\x06\x02\x04\x04\x12\x04\xac\x01\x04\x0c\n\r\n\x05\x04\x06\
+    \x02\x04\x05\x12\x04\xac\x01\r\x13\n\r\n\x05\x04\x06\x02\x04\x01\x12\x04\
+    \xac\x01\x14\x1f\n\r\n\x05\x04\x06\x02\x04\x03\x12\x04\xac\x01\"#\nK\n\
+    \x04\x04\x06\x02\x05\x12\x04\xaf\x01\x04#\x1a=\x20Sent\x20in\x20SESSION_\
+    INIT,\x20identifies\x20the\x20station\x20that\x20picked\x20up\n\n\r\n\
+    \x05\x04\x06\x02\x05\x04\x12\x04\xaf\x01\x04\x0c\n\r\n\x05\x04\x06\x02\
+    \x05\x05\x12\x04\xaf\x01\r\x13\n\r\n\x05\x04\x06\x02\x05\x01\x12\x04\xaf\
+    \x01\x14\x1e\n\r\n\x05\x04\x06\x02\x05\x03\x12\x04\xaf\x01!\"\nG\n\x04\
+    \x04\x06\x02\x06\x12\x04\xb2\x01\x04!\x1a9\x20Random-sized\x20junk\x20to\
+    \x20defeat\x20packet\x20size\x20fingerprinting.\n\n\r\n\x05\x04\x06\x02\
+    \x06\x04\x12\x04\xb2\x01\x04\x0c\n\r\n\x05\x04\x06\x02\x06\x05\x12\x04\
+    \xb2\x01\r\x12\n\r\n\x05\x04\x06\x02\x06\x01\x12\x04\xb2\x01\x13\x1a\n\r\
+    \n\x05\x04\x06\x02\x06\x03\x12\x04\xb2\x01\x1d\x20\n\x0c\n\x02\x04\x07\
+    \x12\x06\xb5\x01\0\xbb\x01\x01\n\x0b\n\x03\x04\x07\x01\x12\x04\xb5\x01\
+    \x08\x19\n\x0c\n\x04\x04\x07\x02\0\x12\x04\xb6\x01\x08&\n\r\n\x05\x04\
+    \x07\x02\0\x04\x12\x04\xb6\x01\x08\x10\n\r\n\x05\x04\x07\x02\0\x05\x12\
+    \x04\xb6\x01\x11\x15\n\r\n\x05\x04\x07\x02\0\x01\x12\x04\xb6\x01\x16!\n\
+    \r\n\x05\x04\x07\x02\0\x03\x12\x04\xb6\x01$%\n\x0c\n\x04\x04\x07\x02\x01\
+    \x12\x04\xb7\x01\x08%\n\r\n\x05\x04\x07\x02\x01\x04\x12\x04\xb7\x01\x08\
+    \x10\n\r\n\x05\x04\x07\x02\x01\x05\x12\x04\xb7\x01\x11\x15\n\r\n\x05\x04\
+    \x07\x02\x01\x01\x12\x04\xb7\x01\x16\x20\n\r\n\x05\x04\x07\x02\x01\x03\
+    \x12\x04\xb7\x01#$\n\x0c\n\x04\x04\x07\x02\x02\x12\x04\xb8\x01\x08'\n\r\
+    \n\x05\x04\x07\x02\x02\x04\x12\x04\xb8\x01\x08\x10\n\r\n\x05\x04\x07\x02\
+    \x02\x05\x12\x04\xb8\x01\x11\x15\n\r\n\x05\x04\x07\x02\x02\x01\x12\x04\
+    \xb8\x01\x16\"\n\r\n\x05\x04\x07\x02\x02\x03\x12\x04\xb8\x01%&\n\x0c\n\
+    \x04\x04\x07\x02\x03\x12\x04\xb9\x01\x04\x1e\n\r\n\x05\x04\x07\x02\x03\
+    \x04\x12\x04\xb9\x01\x04\x0c\n\r\n\x05\x04\x07\x02\x03\x05\x12\x04\xb9\
+    \x01\r\x11\n\r\n\x05\x04\x07\x02\x03\x01\x12\x04\xb9\x01\x12\x19\n\r\n\
+    \x05\x04\x07\x02\x03\x03\x12\x04\xb9\x01\x1c\x1d\n\x0c\n\x04\x04\x07\x02\
+    \x04\x12\x04\xba\x01\x04!\n\r\n\x05\x04\x07\x02\x04\x04\x12\x04\xba\x01\
+    \x04\x0c\n\r\n\x05\x04\x07\x02\x04\x05\x12\x04\xba\x01\r\x11\n\r\n\x05\
+    \x04\x07\x02\x04\x01\x12\x04\xba\x01\x12\x1c\n\r\n\x05\x04\x07\x02\x04\
+    \x03\x12\x04\xba\x01\x1f\x20\n\x0c\n\x02\x04\x08\x12\x06\xbd\x01\0\xec\
+    \x01\x01\n\x0b\n\x03\x04\x08\x01\x12\x04\xbd\x01\x08\x17\n\x0c\n\x04\x04\
+    \x08\x02\0\x12\x04\xbe\x01\x04)\n\r\n\x05\x04\x08\x02\0\x04\x12\x04\xbe\
+    \x01\x04\x0c\n\r\n\x05\x04\x08\x02\0\x05\x12\x04\xbe\x01\r\x13\n\r\n\x05\
+    \x04\x08\x02\0\x01\x12\x04\xbe\x01\x14$\n\r\n\x05\x04\x08\x02\0\x03\x12\
+    \x04\xbe\x01'(\n\xd0\x01\n\x04\x04\x08\x02\x01\x12\x04\xc3\x01\x04.\x1a\
+    \xc1\x01\x20The\x20client\x20reports\x20its\x20decoy\x20list's\x20versio\
+    n\x20number\x20here,\x20which\x20the\n\x20station\x20can\x20use\x20to\
+    \x20decide\x20whether\x20to\x20send\x20an\x20updated\x20one.\x20The\x20s\
+    tation\n\x20should\x20always\x20send\x20a\x20list\x20if\x20this\x20field\
+    \x20is\x20set\x20to\x200.\n\n\r\n\x05\x04\x08\x02\x01\x04\x12\x04\xc3\
+    \x01\x04\x0c\n\r\n\x05\x04\x08\x02\x01\x05\x12\x04\xc3\x01\r\x13\n\r\n\
+    \x05\x04\x08\x02\x01\x01\x12\x04\xc3\x01\x14)\n\r\n\x05\x04\x08\x02\x01\
+    \x03\x12\x04\xc3\x01,-\n\x0c\n\x04\x04\x08\x02\x02\x12\x04\xc5\x01\x041\
+    \n\r\n\x05\x04\x08\x02\x02\x04\x12\x04\xc5\x01\x04\x0c\n\r\n\x05\x04\x08\
+    \x02\x02\x06\x12\x04\xc5\x01\r\x1b\n\r\n\x05\x04\x08\x02\x02\x01\x12\x04\
+    \xc5\x01\x1c,\n\r\n\x05\x04\x08\x02\x02\x03\x12\x04\xc5\x01/0\n\x80\x01\
+    \n\x04\x04\x08\x02\x03\x12\x04\xc9\x01\x04$\x1ar\x20The\x20position\x20i\
+    n\x20the\x20overall\x20session's\x20upload\x20sequence\x20where\x20the\
+    \x20current\n\x20YIELD=>ACQUIRE\x20switchover\x20is\x20happening.\n\n\r\
+    \n\x05\x04\x08\x02\x03\x04\x12\x04\xc9\x01\x04\x0c\n\r\n\x05\x04\x08\x02\
+    \x03\x05\x12\x04\xc9\x01\r\x13\n\r\n\x05\x04\x08\x02\x03\x01\x12\x04\xc9\
+    \x01\x14\x1f\n\r\n\x05\x04\x08\x02\x03\x03\x12\x04\xc9\x01\"#\nq\n\x04\
+    \x04\x08\x02\x04\x12\x04\xce\x01\x04'\x1ac\x20List\x20of\x20decoys\x20th\
+    at\x20client\x20have\x20unsuccessfully\x20tried\x20in\x20current\x20sess\
+    ion.\n\x20Could\x20be\x20sent\x20in\x20chunks\n\n\r\n\x05\x04\x08\x02\
+    \x04\x04\x12\x04\xce\x01\x04\x0c\n\r\n\x05\x04\x08\x02\x04\x05\x12\x04\
+    \xce\x01\r\x13\n\r\n\x05\x04\x08\x02\x04\x01\x12\x04\xce\x01\x14!\n\r\n\
+    \x05\x04\x08\x02\x04\x03\x12\x04\xce\x01$&\n\x0c\n\x04\x04\x08\x02\x05\
+    \x12\x04\xd0\x01\x04%\n\r\n\x05\x04\x08\x02\x05\x04\x12\x04\xd0\x01\x04\
+    \x0c\n\r\n\x05\x04\x08\x02\x05\x06\x12\x04\xd0\x01\r\x19\n\r\n\x05\x04\
+    \x08\x02\x05\x01\x12\x04\xd0\x01\x1a\x1f\n\r\n\x05\x04\x08\x02\x05\x03\
+    \x12\x04\xd0\x01\"$\nk\n\x04\x04\x08\x02\x06\x12\x04\xd3\x01\x04*\x1a]\
+    \x20NullTransport,\x20MinTransport,\x20Obfs4Transport,\x20etc.\x20Transp\
+    ort\x20type\x20we\x20want\x20from\x20phantom\x20proxy\n\n\r\n\x05\x04\
+    \x08\x02\x06\x04\x12\x04\xd3\x01\x04\x0c\n\r\n\x05\x04\x08\x02\x06\x06\
+    \x12\x04\xd3\x01\r\x1a\n\r\n\x05\x04\x08\x02\x06\x01\x12\x04\xd3\x01\x1b\
+    $\n\r\n\x05\x04\x08\x02\x06\x03\x12\x04\xd3\x01')\n\xc8\x03\n\x04\x04\
+    \x08\x02\x07\x12\x04\xdb\x01\x04(\x1a\xb9\x03\x20Station\x20is\x20only\
+    \x20required\x20to\x20check\x20this\x20variable\x20during\x20session\x20\
+    initialization.\n\x20If\x20set,\x20station\x20must\x20facilitate\x20conn\
+    ection\x20to\x20said\x20target\x20by\x20itself,\x20i.e.\x20write\x20into\
+    \x20squid\n\x20socket\x20an\x20HTTP/SOCKS/any\x20other\x20connection\x20\
+    request.\n\x20covert_address\x20must\x20have\x20exactly\x20one\x20':'\
+    \x20colon,\x20that\x20separates\x20host\x20(literal\x20IP\x20address\x20\
+    or\n\x20resolvable\x20hostname)\x20and\x20port\n\x20TODO:\x20make\x20it\
+    \x20required\x20for\x20initialization,\x20and\x20stop\x20connecting\x20a\
+    ny\x20client\x20straight\x20to\x20squid?\n\n\r\n\x05\x04\x08\x02\x07\x04\
+    \x12\x04\xdb\x01\x04\x0c\n\r\n\x05\x04\x08\x02\x07\x05\x12\x04\xdb\x01\r\
+    \x13\n\r\n\x05\x04\x08\x02\x07\x01\x12\x04\xdb\x01\x14\"\n\r\n\x05\x04\
+    \x08\x02\x07\x03\x12\x04\xdb\x01%'\nR\n\x04\x04\x08\x02\x08\x12\x04\xde\
+    \x01\x042\x1aD\x20Used\x20in\x20dark\x20decoys\x20to\x20signal\x20which\
+    \x20dark\x20decoy\x20it\x20will\x20connect\x20to.\n\n\r\n\x05\x04\x08\
+    \x02\x08\x04\x12\x04\xde\x01\x04\x0c\n\r\n\x05\x04\x08\x02\x08\x05\x12\
+    \x04\xde\x01\r\x13\n\r\n\x05\x04\x08\x02\x08\x01\x12\x04\xde\x01\x14,\n\
+    \r\n\x05\x04\x08\x02\x08\x03\x12\x04\xde\x01/1\nR\n\x04\x04\x08\x02\t\
+    \x12\x04\xe1\x01\x04\"\x1aD\x20Used\x20to\x20indicate\x20to\x20server\
+    \x20if\x20client\x20is\x20registering\x20v4,\x20v6\x20or\x20both\n\n\r\n\
+    \x05\x04\x08\x02\t\x04\x12\x04\xe1\x01\x04\x0c\n\r\n\x05\x04\x08\x02\t\
+    \x05\x12\x04\xe1\x01\r\x11\n\r\n\x05\x04\x08\x02\t\x01\x12\x04\xe1\x01\
+    \x12\x1c\n\r\n\x05\x04\x08\x02\t\x03\x12\x04\xe1\x01\x1f!\n\x0c\n\x04\
+    \x04\x08\x02\n\x12\x04\xe2\x01\x04\"\n\r\n\x05\x04\x08\x02\n\x04\x12\x04\
+    \xe2\x01\x04\x0c\n\r\n\x05\x04\x08\x02\n\x05\x12\x04\xe2\x01\r\x11\n\r\n\
+    \x05\x04\x08\x02\n\x01\x12\x04\xe2\x01\x12\x1c\n\r\n\x05\x04\x08\x02\n\
+    \x03\x12\x04\xe2\x01\x1f!\nD\n\x04\x04\x08\x02\x0b\x12\x04\xe5\x01\x08.\
+    \x1a6\x20A\x20collection\x20of\x20optional\x20flags\x20for\x20the\x20reg\
+    istration.\n\n\r\n\x05\x04\x08\x02\x0b\x04\x12\x04\xe5\x01\x08\x10\n\r\n\
+    \x05\x04\x08\x02\x0b\x06\x12\x04\xe5\x01\x11\"\n\r\n\x05\x04\x08\x02\x0b\
+    \x01\x12\x04\xe5\x01#(\n\r\n\x05\x04\x08\x02\x0b\x03\x12\x04\xe5\x01+-\n\
+    2\n\x04\x04\x08\x02\x0c\x12\x04\xe8\x01\x04&\x1a$\x20Allow\x20client\x20\
+    to\x20specify\x20decoy\x20port\n\n\r\n\x05\x04\x08\x02\x0c\x04\x12\x04\
+    \xe8\x01\x04\x0c\n\r\n\x05\x04\x08\x02\x0c\x05\x12\x04\xe8\x01\r\x13\n\r\
+    \n\x05\x04\x08\x02\x0c\x01\x12\x04\xe8\x01\x14\x20\n\r\n\x05\x04\x08\x02\
+    \x0c\x03\x12\x04\xe8\x01#%\nG\n\x04\x04\x08\x02\r\x12\x04\xeb\x01\x04!\
+    \x1a9\x20Random-sized\x20junk\x20to\x20defeat\x20packet\x20size\x20finge\
+    rprinting.\n\n\r\n\x05\x04\x08\x02\r\x04\x12\x04\xeb\x01\x04\x0c\n\r\n\
+    \x05\x04\x08\x02\r\x05\x12\x04\xeb\x01\r\x12\n\r\n\x05\x04\x08\x02\r\x01\
+    \x12\x04\xeb\x01\x13\x1a\n\r\n\x05\x04\x08\x02\r\x03\x12\x04\xeb\x01\x1d\
+    \x20\n\x0c\n\x02\x05\x06\x12\x06\xee\x01\0\xf3\x01\x01\n\x0b\n\x03\x05\
+    \x06\x01\x12\x04\xee\x01\x05\x17\n\x0c\n\x04\x05\x06\x02\0\x12\x04\xef\
+    \x01\x04\x14\n\r\n\x05\x05\x06\x02\0\x01\x12\x04\xef\x01\x04\x0f\n\r\n\
+    \x05\x05\x06\x02\0\x02\x12\x04\xef\x01\x12\x13\n\x0c\n\x04\x05\x06\x02\
+    \x01\x12\x04\xf0\x01\x08\x15\n\r\n\x05\x05\x06\x02\x01\x01\x12\x04\xf0\
+    \x01\x08\x10\n\r\n\x05\x05\x06\x02\x01\x02\x12\x04\xf0\x01\x13\x14\n\x0c\
+    \n\x04\x05\x06\x02\x02\x12\x04\xf1\x01\x08\x10\n\r\n\x05\x05\x06\x02\x02\
+    \x01\x12\x04\xf1\x01\x08\x0b\n\r\n\x05\x05\x06\x02\x02\x02\x12\x04\xf1\
+    \x01\x0e\x0f\n\x0c\n\x04\x05\x06\x02\x03\x12\x04\xf2\x01\x04\x18\n\r\n\
+    \x05\x05\x06\x02\x03\x01\x12\x04\xf2\x01\x04\x13\n\r\n\x05\x05\x06\x02\
+    \x03\x02\x12\x04\xf2\x01\x16\x17\n\x0c\n\x02\x04\t\x12\x06\xf5\x01\0\xff\
+    \x01\x01\n\x0b\n\x03\x04\t\x01\x12\x04\xf5\x01\x08\x12\n\x0c\n\x04\x04\t\
+    \x02\0\x12\x04\xf6\x01\x08)\n\r\n\x05\x04\t\x02\0\x04\x12\x04\xf6\x01\
+    \x08\x10\n\r\n\x05\x04\t\x02\0\x05\x12\x04\xf6\x01\x11\x16\n\r\n\x05\x04\
+    \t\x02\0\x01\x12\x04\xf6\x01\x17$\n\r\n\x05\x04\t\x02\0\x03\x12\x04\xf6\
+    \x01'(\n\x0c\n\x04\x04\t\x02\x01\x12\x04\xf7\x01\x08:\n\r\n\x05\x04\t\
+    \x02\x01\x04\x12\x04\xf7\x01\x08\x10\n\r\n\x05\x04\t\x02\x01\x06\x12\x04\
+    \xf7\x01\x11\x20\n\r\n\x05\x04\t\x02\x01\x01\x12\x04\xf7\x01!5\n\r\n\x05\
+    \x04\t\x02\x01\x03\x12\x04\xf7\x0189\n\x0c\n\x04\x04\t\x02\x02\x12\x04\
+    \xf8\x01\x048\n\r\n\x05\x04\t\x02\x02\x04\x12\x04\xf8\x01\x04\x0c\n\r\n\
+    \x05\x04\t\x02\x02\x06\x12\x04\xf8\x01\r\x1f\n\r\n\x05\x04\t\x02\x02\x01\
+    \x12\x04\xf8\x01\x203\n\r\n\x05\x04\t\x02\x02\x03\x12\x04\xf8\x0167\nC\n\
+    \x04\x04\t\x02\x03\x12\x04\xfb\x01\x04,\x1a5\x20client\x20source\x20addr\
+    ess\x20when\x20receiving\x20a\x20registration\n\n\r\n\x05\x04\t\x02\x03\
+    \x04\x12\x04\xfb\x01\x04\x0c\n\r\n\x05\x04\t\x02\x03\x05\x12\x04\xfb\x01\
+    \r\x12\n\r\n\x05\x04\t\x02\x03\x01\x12\x04\xfb\x01\x13'\n\r\n\x05\x04\t\
+    \x02\x03\x03\x12\x04\xfb\x01*+\nH\n\x04\x04\t\x02\x04\x12\x04\xfe\x01\
+    \x04%\x1a:\x20Decoy\x20address\x20used\x20when\x20registering\x20over\
+    \x20Decoy\x20registrar\n\n\r\n\x05\x04\t\x02\x04\x04\x12\x04\xfe\x01\x04\
+    \x0c\n\r\n\x05\x04\t\x02\x04\x05\x12\x04\xfe\x01\r\x12\n\r\n\x05\x04\t\
+    \x02\x04\x01\x12\x04\xfe\x01\x13\x20\n\r\n\x05\x04\t\x02\x04\x03\x12\x04\
+    \xfe\x01#$\n\x0c\n\x02\x04\n\x12\x06\x81\x02\0\x8d\x02\x01\n\x0b\n\x03\
+    \x04\n\x01\x12\x04\x81\x02\x08\x14\n9\n\x04\x04\n\x02\0\x12\x04\x82\x02\
+    \x04.\"+\x20how\x20many\x20decoys\x20were\x20tried\x20before\x20success\
+    \n\n\r\n\x05\x04\n\x02\0\x04\x12\x04\x82\x02\x04\x0c\n\r\n\x05\x04\n\x02\
+    \0\x05\x12\x04\x82\x02\r\x13\n\r\n\x05\x04\n\x02\0\x01\x12\x04\x82\x02\
+    \x14(\n\r\n\x05\x04\n\x02\0\x03\x12\x04\x82\x02+-\nm\n\x04\x04\n\x02\x01\
+    \x12\x04\x87\x02\x04/\x1a\x1e\x20Applicable\x20to\x20whole\x20session:\n\
+    \"\x1a\x20includes\x20failed\x20attempts\n2#\x20Timings\x20below\x20are\
+    \x20in\x20milliseconds\n\n\r\n\x05\x04\n\x02\x01\x04\x12\x04\x87\x02\x04\
+    \x0c\n\r\n\x05\x04\n\x02\x01\x05\x12\x04\x87\x02\r\x13\n\r\n\x05\x04\n\
+    \x02\x01\x01\x12\x04\x87\x02\x14)\n\r\n\x05\x04\n\x02\x01\x03\x12\x04\
+    \x87\x02,.\nR\n\x04\x04\n\x02\x02\x12\x04\x8a\x02\x04(\x1a\x1f\x20Last\
+    \x20(i.e.\x20successful)\x20decoy:\n\"#\x20measured\x20during\x20initial\
+    \x20handshake\n\n\r\n\x05\x04\n\x02\x02\x04\x12\x04\x8a\x02\x04\x0c\n\r\
+    \n\x05\x04\n\x02\x02\x05\x12\x04\x8a\x02\r\x13\n\r\n\x05\x04\n\x02\x02\
+    \x01\x12\x04\x8a\x02\x14\"\n\r\n\x05\x04\n\x02\x02\x03\x12\x04\x8a\x02%'\
+    \n%\n\x04\x04\n\x02\x03\x12\x04\x8b\x02\x04&\"\x17\x20includes\x20tcp\
+    \x20to\x20decoy\n\n\r\n\x05\x04\n\x02\x03\x04\x12\x04\x8b\x02\x04\x0c\n\
+    \r\n\x05\x04\n\x02\x03\x05\x12\x04\x8b\x02\r\x13\n\r\n\x05\x04\n\x02\x03\
+    \x01\x12\x04\x8b\x02\x14\x20\n\r\n\x05\x04\n\x02\x03\x03\x12\x04\x8b\x02\
+    #%\nB\n\x04\x04\n\x02\x04\x12\x04\x8c\x02\x04&\"4\x20measured\x20when\
+    \x20establishing\x20tcp\x20connection\x20to\x20decot\n\n\r\n\x05\x04\n\
+    \x02\x04\x04\x12\x04\x8c\x02\x04\x0c\n\r\n\x05\x04\n\x02\x04\x05\x12\x04\
+    \x8c\x02\r\x13\n\r\n\x05\x04\n\x02\x04\x01\x12\x04\x8c\x02\x14\x20\n\r\n\
+    \x05\x04\n\x02\x04\x03\x12\x04\x8c\x02#%\n\xa0\x01\n\x02\x04\x0b\x12\x06\
+    \x91\x02\0\xa1\x02\x01\x1a\x91\x01\x20Settings\x20the\x20station\x20cont\
+    rols\x20centrally,\x20pushed\x20to\x20every\x20detector\x20in\x20a\n\x20\
+    StationToDetector.\x20Each\x20push\x20replaces\x20the\x20whole\x20previo\
+    us\x20configuration.\n\n\x0b\n\x03\x04\x0b\x01\x12\x04\x91\x02\x08\x12\n\
+    \x86\x01\n\x04\x04\x0b\x02\0\x12\x04\x94\x02\x04\x1e\x1ax\x20Version\x20\
+    of\x20the\x20configuration,\x20increasing\x20with\x20every\x20push.\x20D\
+    etectors\n\x20ignore\x20pushes\x20no\x20newer\x20than\x20the\x20one\x20t\
+    hey\x20applied.\n\n\r\n\x05\x04\x0b\x02\0\x04\x12\x04\x94\x02\x04\x0c\n\
+    \r\n\x05\x04\x0b\x02\0\x05\x12\x04\x94\x02\r\x13\n\r\n\x05\x04\x0b\x02\0\
+    \x01\x12\x04\x94\x02\x14\x19\n\r\n\x05\x04\x0b\x02\0\x03\x12\x04\x94\x02\
+    \x1c\x1d\n\x8c\x01\n\x04\x04\x0b\x02\x01\x12\x04\x98\x02\x04+\x1a~\x20Li\
+    fetime\x20given\x20to\x20registrations\x20that\x20carry\x20neither\x20ti\
+    meout_ns\x20nor\n\x20expires_at_unix_ns.\x20Unset\x20(or\x200)\x20leaves\
+    \x20them\x20without\x20one.\n\n\r\n\x05\x04\x0b\x02\x01\x04\x12\x04\x98\
+    \x02\x04\x0c\n\r\n\x05\x04\x0b\x02\x01\x05\x12\x04\x98\x02\r\x13\n\r\n\
+    \x05\x04\x0b\x02\x01\x01\x12\x04\x98\x02\x14&\n\r\n\x05\x04\x0b\x02\x01\
+    \x03\x12\x04\x98\x02)*\nS\n\x04\x04\x0b\x02\x02\x12\x04\x9b\x02\x042\x1a\
+    E\x20Transports\x20registrations\x20may\x20be\x20for.\x20Empty\x20accept\
+    s\x20every\x20transport.\n\n\r\n\x05\x04\x0b\x02\x02\x04\x12\x04\x9b\x02\
+    \x04\x0c\n\r\n\x05\x04\x0b\x02\x02\x06\x12\x04\x9b\x02\r\x1a\n\r\n\x05\
+    \x04\x0b\x02\x02\x01\x12\x04\x9b\x02\x1b-\n\r\n\x05\x04\x0b\x02\x02\x03\
+    \x12\x04\x9b\x0201\n\x86\x01\n\x04\x04\x0b\x02\x03\x12\x04\x9f\x02\x04)\
+    \x1ax\x20Networks\x20(CIDR,\x20or\x20a\x20single\x20address)\x20whose\
+    \x20clients'\x20registrations,\x20and\n\x20registrations\x20for\x20whose\
+    \x20phantoms,\x20are\x20refused.\n\n\r\n\x05\x04\x0b\x02\x03\x04\x12\x04\
+    \x9f\x02\x04\x0c\n\r\n\x05\x04\x0b\x02\x03\x05\x12\x04\x9f\x02\r\x13\n\r\
+    \n\x05\x04\x0b\x02\x03\x01\x12\x04\x9f\x02\x14$\n\r\n\x05\x04\x0b\x02\
+    \x03\x03\x12\x04\x9f\x02'(\n\x0c\n\x04\x04\x0b\x02\x04\x12\x04\xa0\x02\
+    \x04*\n\r\n\x05\x04\x0b\x02\x04\x04\x12\x04\xa0\x02\x04\x0c\n\r\n\x05\
+    \x04\x0b\x02\x04\x05\x12\x04\xa0\x02\r\x13\n\r\n\x05\x04\x0b\x02\x04\x01\
+    \x12\x04\xa0\x02\x14%\n\r\n\x05\x04\x0b\x02\x04\x03\x12\x04\xa0\x02()\n\
+    \xc5\x01\n\x02\x04\x0c\x12\x06\xa6\x02\0\xad\x02\x01\x1a\xb6\x01\x20The\
+    \x20phantom\x20subnets\x20registrations\x20may\x20use,\x20pushed\x20to\
+    \x20every\x20detector\x20in\x20a\n\x20StationToDetector\x20whenever\x20t\
+    he\x20station's\x20phantom\x20pools\x20change.\x20Each\x20push\n\x20repl\
+    aces\x20the\x20whole\x20previous\x20set.\n\n\x0b\n\x03\x04\x0c\x01\x12\
+    \x04\xa6\x02\x08\x18\nz\n\x04\x04\x0c\x02\0\x12\x04\xa9\x02\x04#\x1al\
+    \x20Version\x20of\x20the\x20set,\x20increasing\x20with\x20every\x20push.\
+    \x20Detectors\x20ignore\x20sets\n\x20no\x20newer\x20than\x20the\x20one\
+    \x20they\x20applied.\n\n\r\n\x05\x04\x0c\x02\0\x04\x12\x04\xa9\x02\x04\
+    \x0c\n\r\n\x05\x04\x0c\x02\0\x05\x12\x04\xa9\x02\r\x13\n\r\n\x05\x04\x0c\
+    \x02\0\x01\x12\x04\xa9\x02\x14\x1e\n\r\n\x05\x04\x0c\x02\0\x03\x12\x04\
+    \xa9\x02!\"\nP\n\x04\x04\x0c\x02\x01\x12\x04\xac\x02\x04\x20\x1aB\x20Net\
+    works\x20(CIDR)\x20of\x20every\x20generation\x20of\x20phantom\x20address\
+    es\x20in\x20use.\n\n\r\n\x05\x04\x0c\x02\x01\x04\x12\x04\xac\x02\x04\x0c\
+    \n\r\n\x05\x04\x0c\x02\x01\x05\x12\x04\xac\x02\r\x13\n\r\n\x05\x04\x0c\
+    \x02\x01\x01\x12\x04\xac\x02\x14\x1b\n\r\n\x05\x04\x0c\x02\x01\x03\x12\
+    \x04\xac\x02\x1e\x1f\n\x0c\n\x02\x04\r\x12\x06\xaf\x02\0\x80\x03\x01\n\
+    \x0b\n\x03\x04\r\x01\x12\x04\xaf\x02\x08\x19\n\x0c\n\x04\x04\r\x02\0\x12\
+    \x04\xb0\x02\x04#\n\r\n\x05\x04\r\x02\0\x04\x12\x04\xb0\x02\x04\x0c\n\r\
+    \n\x05\x04\r\x02\0\x05\x12\x04\xb0\x02\r\x13\n\r\n\x05\x04\r\x02\0\x01\
+    \x12\x04\xb0\x02\x14\x1e\n\r\n\x05\x04\r\x02\0\x03\x12\x04\xb0\x02!\"\n\
+    \x0c\n\x04\x04\r\x02\x01\x12\x04\xb1\x02\x04\"\n\r\n\x05\x04\r\x02\x01\
+    \x04\x12\x04\xb1\x02\x04\x0c\n\r\n\x05\x04\r\x02\x01\x05\x12\x04\xb1\x02\
+    \r\x13\n\r\n\x05\x04\r\x02\x01\x01\x12\x04\xb1\x02\x14\x1d\n\r\n\x05\x04\
+    \r\x02\x01\x03\x12\x04\xb1\x02\x20!\n\x0c\n\x04\x04\r\x02\x02\x12\x04\
+    \xb2\x02\x04#\n\r\n\x05\x04\r\x02\x02\x04\x12\x04\xb2\x02\x04\x0c\n\r\n\
+    \x05\x04\r\x02\x02\x05\x12\x04\xb2\x02\r\x13\n\r\n\x05\x04\r\x02\x02\x01\
+    \x12\x04\xb2\x02\x14\x1e\n\r\n\x05\x04\r\x02\x02\x03\x12\x04\xb2\x02!\"\
+    \n\x0c\n\x04\x04\r\x02\x03\x12\x04\xb3\x02\x04%\n\r\n\x05\x04\r\x02\x03\
+    \x04\x12\x04\xb3\x02\x04\x0c\n\r\n\x05\x04\r\x02\x03\x05\x12\x04\xb3\x02\
+    \r\x13\n\r\n\x05\x04\r\x02\x03\x01\x12\x04\xb3\x02\x14\x20\n\r\n\x05\x04\
+    \r\x02\x03\x03\x12\x04\xb3\x02#$\n\x87\x01\n\x04\x04\r\x02\x04\x12\x04\
+    \xb7\x02\x048\x1ay\x20Registrar\x20path\x20that\x20produced\x20this\x20r\
+    egistration\x20so\x20the\x20detector\x20can\n\x20attribute\x20the\x20ses\
+    sions\x20it\x20tracks\x20in\x20logs\x20and\x20stats.\n\n\r\n\x05\x04\r\
+    \x02\x04\x04\x12\x04\xb7\x02\x04\x0c\n\r\n\x05\x04\r\x02\x04\x06\x12\x04\
+    \xb7\x02\r\x1f\n\r\n\x05\x04\r\x02\x04\x01\x12\x04\xb7\x02\x203\n\r\n\
+    \x05\x04\r\x02\x04\x03\x12\x04\xb7\x0267\n\xbc\x01\n\x04\x04\r\x02\x05\
+    \x12\x04\xbc\x02\x04&\x1a\xad\x01\x20Dual-stack\x20registrations\x20carr\
+    y\x20the\x20IPv6\x20phantom\x20here\x20alongside\x20the\x20IPv4\n\x20pha\
+    ntom\x20in\x20phantom_ip,\x20so\x20both\x20sessions\x20are\x20created\
+    \x20from\x20one\x20message\x20and\n\x20share\x20the\x20same\x20lifetime.\
+    \n\n\r\n\x05\x04\r\x02\x05\x04\x12\x04\xbc\x02\x04\x0c\n\r\n\x05\x04\r\
+    \x02\x05\x05\x12\x04\xbc\x02\r\x13\n\r\n\x05\x04\r\x02\x05\x01\x12\x04\
+    \xbc\x02\x14!\n\r\n\x05\x04\r\x02\x05\x03\x12\x04\xbc\x02$%\n\x98\x01\n\
+    \x04\x04\r\x02\x06\x12\x04\xc0\x02\x04(\x1a\x89\x01\x20Maximum\x20number\
+    \x20of\x20simultaneous\x20connections\x20the\x20detector\x20forwards\x20\
+    for\n\x20this\x20registration.\x20Unset\x20(or\x200)\x20uses\x20the\x20d\
+    etector's\x20global\x20limit.\n\n\r\n\x05\x04\r\x02\x06\x04\x12\x04\xc0\
+    \x02\x04\x0c\n\r\n\x05\x04\r\x02\x06\x05\x12\x04\xc0\x02\r\x13\n\r\n\x05\
+    \x04\r\x02\x06\x01\x12\x04\xc0\x02\x14#\n\r\n\x05\x04\r\x02\x06\x03\x12\
+    \x04\xc0\x02&'\n\xae\x01\n\x04\x04\r\x02\x07\x12\x04\xc5\x02\x04+\x1a\
+    \x9f\x01\x20Absolute\x20expiration\x20of\x20the\x20registration\x20(unix\
+    \x20time,\x20ns).\x20When\x20set\x20it\x20is\n\x20used\x20instead\x20of\
+    \x20timeout_ns,\x20so\x20time\x20spent\x20in\x20the\x20broker\x20doesn't\
+    \x20extend\n\x20the\x20session.\n\n\r\n\x05\x04\r\x02\x07\x04\x12\x04\
+    \xc5\x02\x04\x0c\n\r\n\x05\x04\r\x02\x07\x05\x12\x04\xc5\x02\r\x13\n\r\n\
+    \x05\x04\r\x02\x07\x01\x12\x04\xc5\x02\x14&\n\r\n\x05\x04\r\x02\x07\x03\
+    \x12\x04\xc5\x02)*\n\x89\x01\n\x04\x04\r\x02\x08\x12\x04\xc8\x02\x04-\
+    \x1a{\x20Station\x20clock\x20(unix\x20time,\x20ns)\x20when\x20the\x20mes\
+    sage\x20was\x20sent,\x20used\x20to\x20detect\n\x20clock\x20skew\x20betwe\
+    en\x20the\x20station\x20and\x20the\x20detector.\n\n\r\n\x05\x04\r\x02\
+    \x08\x04\x12\x04\xc8\x02\x04\x0c\n\r\n\x05\x04\r\x02\x08\x05\x12\x04\xc8\
+    \x02\r\x13\n\r\n\x05\x04\r\x02\x08\x01\x12\x04\xc8\x02\x14(\n\r\n\x05\
+    \x04\r\x02\x08\x03\x12\x04\xc8\x02+,\n\xa7\x02\n\x04\x04\r\x02\t\x12\x04\
+    \xce\x02\x04#\x1a\x98\x02\x20Identifier\x20the\x20station\x20picks\x20fo\
+    r\x20this\x20message,\x20unique\x20for\x20at\x20least\x20the\n\x20detect\
+    or's\x20replay\x20window\x20(e.g.\x2016\x20random\x20bytes).\x20The\x20d\
+    etector\x20rejects\x20a\n\x20message\x20whose\x20id\x20it\x20has\x20alre\
+    ady\x20seen,\x20or\x20whose\x20station_time_unix_ns\x20is\n\x20outside\
+    \x20the\x20window,\x20so\x20an\x20old\x20registration\x20can't\x20be\x20\
+    replayed.\n\n\r\n\x05\x04\r\x02\t\x04\x12\x04\xce\x02\x04\x0c\n\r\n\x05\
+    \x04\r\x02\t\x05\x12\x04\xce\x02\r\x12\n\r\n\x05\x04\r\x02\t\x01\x12\x04\
+    \xce\x02\x13\x1d\n\r\n\x05\x04\r\x02\t\x03\x12\x04\xce\x02\x20\"\np\n\
+    \x04\x04\r\x02\n\x12\x04\xd2\x02\x04*\x1ab\x20Transport\x20the\x20client\
+    \x20registered\x20for,\x20used\x20to\x20break\x20down\x20the\x20sessions\
+    \n\x20reported\x20as\x20never\x20matched.\n\n\r\n\x05\x04\r\x02\n\x04\
+    \x12\x04\xd2\x02\x04\x0c\n\r\n\x05\x04\r\x02\n\x06\x12\x04\xd2\x02\r\x1a\
+    \n\r\n\x05\x04\r\x02\n\x01\x12\x04\xd2\x02\x1b$\n\r\n\x05\x04\r\x02\n\
+    \x03\x12\x04\xd2\x02')\nU\n\x04\x04\r\x02\x0b\x12\x04\xd5\x02\x04)\x1aG\
+    \x20Configuration\x20update.\x20A\x20message\x20carrying\x20one\x20carri\
+    es\x20no\x20registration.\n\n\r\n\x05\x04\r\x02\x0b\x04\x12\x04\xd5\x02\
+    \x04\x0c\n\r\n\x05\x04\r\x02\x0b\x06\x12\x04\xd5\x02\r\x17\n\r\n\x05\x04\
+    \r\x02\x0b\x01\x12\x04\xd5\x02\x18#\n\r\n\x05\x04\r\x02\x0b\x03\x12\x04\
+    \xd5\x02&(\nV\n\x04\x04\r\x02\x0c\x12\x04\xd8\x02\x043\x1aH\x20Phantom\
+    \x20subnet\x20update.\x20A\x20message\x20carrying\x20one\x20carries\x20n\
+    o\x20registration.\n\n\r\n\x05\x04\r\x02\x0c\x04\x12\x04\xd8\x02\x04\x0c\
+    \n\r\n\x05\x04\r\x02\x0c\x06\x12\x04\xd8\x02\r\x1d\n\r\n\x05\x04\r\x02\
+    \x0c\x01\x12\x04\xd8\x02\x1e-\n\r\n\x05\x04\r\x02\x0c\x03\x12\x04\xd8\
+    \x0202\ni\n\x04\x04\r\x02\r\x12\x04\xdc\x02\x04#\x1a[\x20Whether\x20phan\
+    tom_ip\x20(and\x20phantom_ip_v6)\x20is\x20a\x20phantom\x20or\x20a\x20dec\
+    oy\x20host.\n\x20Unset\x20is\x20a\x20phantom.\n\n\r\n\x05\x04\r\x02\r\
+    \x04\x12\x04\xdc\x02\x04\x0c\n\r\n\x05\x04\r\x02\r\x06\x12\x04\xdc\x02\r\
+    \x18\n\r\n\x05\x04\r\x02\r\x01\x12\x04\xdc\x02\x19\x1d\n\r\n\x05\x04\r\
+    \x02\r\x03\x12\x04\xdc\x02\x20\"\n\xec\x02\n\x04\x04\r\x02\x0e\x12\x04\
+    \xe3\x02\x04&\x1a\xdd\x02\x20Identifier\x20of\x20the\x20station\x20proce\
+    ss\x20that\x20published\x20the\x20message,\x20and\x20its\n\x20number\x20\
+    in\x20that\x20publisher's\x20stream:\x201\x20for\x20the\x20first\x20mess\
+    age\x20published\n\x20after\x20it\x20starts,\x20one\x20more\x20for\x20ev\
+    ery\x20message\x20after\x20that\x20(config\x20pushes\n\x20included).\x20\
+    The\x20detector\x20counts\x20the\x20numbers\x20it\x20never\x20receives,\
+    \x20so\n\x20registrations\x20lost\x20on\x20the\x20way\x20can\x20be\x20to\
+    ld\x20apart\x20from\x20ones\x20never\x20sent.\n\n\r\n\x05\x04\r\x02\x0e\
+    \x04\x12\x04\xe3\x02\x04\x0c\n\r\n\x05\x04\r\x02\x0e\x05\x12\x04\xe3\x02\
+    \r\x13\n\r\n\x05\x04\r\x02\x0e\x01\x12\x04\xe3\x02\x14\x20\n\r\n\x05\x04\
+    \r\x02\x0e\x03\x12\x04\xe3\x02#%\n\x0c\n\x04\x04\r\x02\x0f\x12\x04\xe4\
+    \x02\x04\"\n\r\n\x05\x04\r\x02\x0f\x04\x12\x04\xe4\x02\x04\x0c\n\r\n\x05\
+    \x04\r\x02\x0f\x05\x12\x04\xe4\x02\r\x13\n\r\n\x05\x04\r\x02\x0f\x01\x12\
+    \x04\xe4\x02\x14\x1c\n\r\n\x05\x04\r\x02\x0f\x03\x12\x04\xe4\x02\x1f!\n\
+    \x8f\x01\n\x04\x04\r\x02\x10\x12\x04\xe8\x02\x04#\x1a\x80\x01\x20Logical\
+    \x20station\x20(tenant)\x20the\x20registration\x20is\x20for,\x20when\x20\
+    one\x20detector\n\x20serves\x20several.\x20Unset\x20or\x200\x20is\x20the\
+    \x20detector's\x20own\x20station.\n\n\r\n\x05\x04\r\x02\x10\x04\x12\x04\
+    \xe8\x02\x04\x0c\n\r\n\x05\x04\r\x02\x10\x05\x12\x04\xe8\x02\r\x13\n\r\n\
+    \x05\x04\r\x02\x10\x01\x12\x04\xe8\x02\x14\x1d\n\r\n\x05\x04\r\x02\x10\
+    \x03\x12\x04\xe8\x02\x20\"\nb\n\x04\x04\r\x02\x11\x12\x04\xec\x02\x04.\
+    \x1aT\x20Delete\x20every\x20session\x20of\x20tenant_id.\x20A\x20message\
+    \x20carrying\x20it\x20carries\x20no\n\x20registration.\n\n\r\n\x05\x04\r\
+    \x02\x11\x04\x12\x04\xec\x02\x04\x0c\n\r\n\x05\x04\r\x02\x11\x05\x12\x04\
+    \xec\x02\r\x11\n\r\n\x05\x04\r\x02\x11\x01\x12\x04\xec\x02\x12(\n\r\n\
+    \x05\x04\r\x02\x11\x03\x12\x04\xec\x02+-\n\xcd\x01\n\x04\x04\r\x02\x12\
+    \x12\x04\xf1\x02\x04&\x1a\xbe\x01\x20Time\x20traffic\x20extends\x20the\
+    \x20registration's\x20sessions\x20by,\x20overriding\x20the\n\x20detector\
+    's\x20configuration.\x20Unset\x20or\x200\x20uses\x20the\x20configured\
+    \x20extension.\n\x20Capped\x20at\x20the\x20detector's\x20maximum\x20sess\
+    ion\x20lifetime.\n\n\r\n\x05\x04\r\x02\x12\x04\x12\x04\xf1\x02\x04\x0c\n\
+    \r\n\x05\x04\r\x02\x12\x05\x12\x04\xf1\x02\r\x13\n\r\n\x05\x04\r\x02\x12\
+    \x01\x12\x04\xf1\x02\x14\x20\n\r\n\x05\x04\r\x02\x12\x03\x12\x04\xf1\x02\
+    #%\n\x9d\x03\n\x04\x04\r\x02\x13\x12\x04\xf9\x02\x04%\x1a\x8e\x03\x20Res\
+    et\x20the\x20expiry\x20of\x20the\x20registration's\x20sessions\x20the\
+    \x20detector\x20already\n\x20tracks\x20to\x20timeout_ns\x20(or\x20expire\
+    s_at_unix_ns)\x20from\x20receipt,\x20whether\x20or\x20not\n\x20they\x20h\
+    ave\x20seen\x20traffic,\x20within\x20the\x20detector's\x20lifetime\x20ca\
+    ps.\x20The\x20new\n\x20expiry\x20may\x20be\x20earlier\x20than\x20the\x20\
+    current\x20one.\x20Sessions\x20not\x20tracked\x20are\x20not\n\x20created\
+    ,\x20so\x20the\x20station\x20can\x20keep\x20the\x20sessions\x20it\x20kno\
+    ws\x20are\x20still\x20needed\n\x20alive\x20without\x20re-registering\x20\
+    them.\n\n\r\n\x05\x04\r\x02\x13\x04\x12\x04\xf9\x02\x04\x0c\n\r\n\x05\
+    \x04\r\x02\x13\x05\x12\x04\xf9\x02\r\x11\n\r\n\x05\x04\r\x02\x13\x01\x12\
+    \x04\xf9\x02\x12\x1f\n\r\n\x05\x04\r\x02\x13\x03\x12\x04\xf9\x02\"$\n\
+    \xa0\x02\n\x04\x04\r\x02\x14\x12\x04\xff\x02\x04)\x1a\x91\x02\x20Source\
+    \x20ports\x20the\x20client\x20connects\x20from,\x20when\x20its\x20transp\
+    ort\x20constrains\n\x20them.\x20The\x20detector\x20only\x20matches\x20co\
+    nnections\x20to\x20the\x20session's\x20phantom\n\x20from\x20ports\x20in\
+    \x20the\x20range,\x20which\x20avoids\x20false\x20matches\x20where\x20ses\
+    sions\x20are\n\x20keyed\x20without\x20the\x20client's\x20address.\x20Uns\
+    et\x20matches\x20any\x20port.\n\n\r\n\x05\x04\r\x02\x14\x04\x12\x04\xff\
+    \x02\x04\x0c\n\r\n\x05\x04\r\x02\x14\x06\x12\x04\xff\x02\r\x16\n\r\n\x05\
+    \x04\r\x02\x14\x01\x12\x04\xff\x02\x17#\n\r\n\x05\x04\r\x02\x14\x03\x12\
+    \x04\xff\x02&(\n)\n\x02\x04\x0e\x12\x06\x83\x03\0\x86\x03\x01\x1a\x1b\
+    \x20Inclusive\x20range\x20of\x20ports.\n\n\x0b\n\x03\x04\x0e\x01\x12\x04\
+    \x83\x03\x08\x11\n\x0c\n\x04\x04\x0e\x02\0\x12\x04\x84\x03\x04\x1e\n\r\n\
+    \x05\x04\x0e\x02\0\x04\x12\x04\x84\x03\x04\x0c\n\r\n\x05\x04\x0e\x02\0\
+    \x05\x12\x04\x84\x03\r\x13\n\r\n\x05\x04\x0e\x02\0\x01\x12\x04\x84\x03\
+    \x14\x19\n\r\n\x05\x04\x0e\x02\0\x03\x12\x04\x84\x03\x1c\x1d\n\x0c\n\x04\
+    \x04\x0e\x02\x01\x12\x04\x85\x03\x04\x1d\n\r\n\x05\x04\x0e\x02\x01\x04\
+    \x12\x04\x85\x03\x04\x0c\n\r\n\x05\x04\x0e\x02\x01\x05\x12\x04\x85\x03\r\
+    \x13\n\r\n\x05\x04\x0e\x02\x01\x01\x12\x04\x85\x03\x14\x18\n\r\n\x05\x04\
+    \x0e\x02\x01\x03\x12\x04\x85\x03\x1b\x1c\nP\n\x02\x05\x07\x12\x06\x89\
+    \x03\0\xa4\x03\x01\x1aB\x20Events\x20reported\x20by\x20the\x20detector\
+    \x20back\x20to\x20the\x20station\x20application.\n\n\x0b\n\x03\x05\x07\
+    \x01\x12\x04\x89\x03\x05\x12\n\x0c\n\x04\x05\x07\x02\0\x12\x04\x8a\x03\
+    \x04\x15\n\r\n\x05\x05\x07\x02\0\x01\x12\x04\x8a\x03\x04\x10\n\r\n\x05\
+    \x05\x07\x02\0\x02\x12\x04\x8a\x03\x13\x14\n|\n\x04\x05\x07\x02\x01\x12\
+    \x04\x8d\x03\x04\x17\x1an\x20A\x20client\x20connected\x20to\x20a\x20phan\
+    tom\x20with\x20a\x20registered\x20session.\x20Sent\x20once\n\x20per\x20s\
+    ession,\x20for\x20its\x20first\x20connection.\n\n\r\n\x05\x05\x07\x02\
+    \x01\x01\x12\x04\x8d\x03\x04\x12\n\r\n\x05\x05\x07\x02\x01\x02\x12\x04\
+    \x8d\x03\x15\x16\nq\n\x04\x05\x07\x02\x02\x12\x04\x90\x03\x04\x1c\x1ac\
+    \x20Sessions\x20that\x20expired\x20since\x20the\x20last\x20summary\x20wi\
+    thout\x20a\x20client\x20ever\n\x20connecting,\x20in\x20never_matched.\n\
+    \n\r\n\x05\x05\x07\x02\x02\x01\x12\x04\x90\x03\x04\x17\n\r\n\x05\x05\x07\
+    \x02\x02\x02\x12\x04\x90\x03\x1a\x1b\nf\n\x04\x05\x07\x02\x03\x12\x04\
+    \x93\x03\x04\x12\x1aX\x20Acknowledges\x20the\x20ConfigPush\x20with\x20ep\
+    och\x20config_epoch,\x20rejected\x20if\n\x20config_error\x20is\x20set.\n\
+    \n\r\n\x05\x05\x07\x02\x03\x01\x12\x04\x93\x03\x04\r\n\r\n\x05\x05\x07\
+    \x02\x03\x02\x12\x04\x93\x03\x10\x11\n\xda\x01\n\x04\x05\x07\x02\x04\x12\
+    \x04\x97\x03\x04\x12\x1a\xcb\x01\x20Sent\x20once\x20per\x20report\x20per\
+    iod\x20with\x20the\x20epoch\x20of\x20the\x20configuration\x20in\n\x20eff\
+    ect\x20in\x20config_epoch\x20(0\x20before\x20the\x20first\x20push),\x20a\
+    nd\x20the\x20generation\x20of\n\x20the\x20phantom\x20subnets\x20in\x20ef\
+    fect\x20in\x20phantom_subnets_generation.\n\n\r\n\x05\x05\x07\x02\x04\
+    \x01\x12\x04\x97\x03\x04\r\n\r\n\x05\x05\x07\x02\x04\x02\x12\x04\x97\x03\
+    \x10\x11\n\x7f\n\x04\x05\x07\x02\x05\x12\x04\x9a\x03\x04\x1a\x1aq\x20Ack\
+    nowledges\x20the\x20PhantomSubnetSet\x20with\x20generation\n\x20phantom_\
+    subnets_generation,\x20rejected\x20if\x20config_error\x20is\x20set.\n\n\
+    \r\n\x05\x05\x07\x02\x05\x01\x12\x04\x9a\x03\x04\x15\n\r\n\x05\x05\x07\
+    \x02\x05\x02\x12\x04\x9a\x03\x18\x19\n~\n\x04\x05\x07\x02\x06\x12\x04\
+    \x9d\x03\x04\x17\x1ap\x20A\x20session\x20a\x20client\x20connected\x20to\
+    \x20expired,\x20with\x20the\x20traffic\x20forwarded\n\x20for\x20it\x20in\
+    \x20connections,\x20packets\x20and\x20bytes.\n\n\r\n\x05\x05\x07\x02\x06\
+    \x01\x12\x04\x9d\x03\x04\x12\n\r\n\x05\x05\x07\x02\x06\x02\x12\x04\x9d\
+    \x03\x15\x16\nd\n\x04\x05\x07\x02\x07\x12\x04\xa0\x03\x04\x1a\x1aV\x20Th\
+    e\x20core's\x20tracked\x20session\x20count\x20crossed\x20a\x20watermark\
+    \x20or\x20changed\n\x20sharply,\x20see\x20alert.\n\n\r\n\x05\x05\x07\x02\
+    \x07\x01\x12\x04\xa0\x03\x04\x15\n\r\n\x05\x05\x07\x02\x07\x02\x12\x04\
+    \xa0\x03\x18\x19\n\x8c\x01\n\x04\x05\x07\x02\x08\x12\x04\xa3\x03\x04\x15\
+    \x1a~\x20Sent\x20once\x20per\x20report\x20period\x20in\x20which\x20messa\
+    ges\x20went\x20missing\x20or\x20arrived\n\x20late,\x20with\x20the\x20cou\
+    nts\x20per\x20publisher\x20in\x20sequence_gaps.\n\n\r\n\x05\x05\x07\x02\
+    \x08\x01\x12\x04\xa3\x03\x04\x10\n\r\n\x05\x05\x07\x02\x08\x02\x12\x04\
+    \xa3\x03\x13\x14\n\xa1\x01\n\x02\x04\x0f\x12\x06\xa8\x03\0\xaf\x03\x01\
+    \x1a\x92\x01\x20Messages\x20of\x20one\x20station\x20publisher\x20receive\
+    d\x20by\x20a\x20core\x20in\x20a\x20report\x20period,\n\x20and\x20those\
+    \x20whose\x20sequence\x20numbers\x20were\x20skipped\x20or\x20arrived\x20\
+    out\x20of\x20order.\n\n\x0b\n\x03\x04\x0f\x01\x12\x04\xa8\x03\x08\x18\n\
+    \x0c\n\x04\x04\x0f\x02\0\x12\x04\xa9\x03\x04%\n\r\n\x05\x04\x0f\x02\0\
+    \x04\x12\x04\xa9\x03\x04\x0c\n\r\n\x05\x04\x0f\x02\0\x05\x12\x04\xa9\x03\
+    \r\x13\n\r\n\x05\x04\x0f\x02\0\x01\x12\x04\xa9\x03\x14\x20\n\r\n\x05\x04\
+    \x0f\x02\0\x03\x12\x04\xa9\x03#$\n\x0c\n\x04\x04\x0f\x02\x01\x12\x04\xaa\
+    \x03\x04!\n\r\n\x05\x04\x0f\x02\x01\x04\x12\x04\xaa\x03\x04\x0c\n\r\n\
+    \x05\x04\x0f\x02\x01\x05\x12\x04\xaa\x03\r\x13\n\r\n\x05\x04\x0f\x02\x01\
+    \x01\x12\x04\xaa\x03\x14\x1c\n\r\n\x05\x04\x0f\x02\x01\x03\x12\x04\xaa\
+    \x03\x1f\x20\n\x0c\n\x04\x04\x0f\x02\x02\x12\x04\xab\x03\x04\x20\n\r\n\
+    \x05\x04\x0f\x02\x02\x04\x12\x04\xab\x03\x04\x0c\n\r\n\x05\x04\x0f\x02\
+    \x02\x05\x12\x04\xab\x03\r\x13\n\r\n\x05\x04\x0f\x02\x02\x01\x12\x04\xab\
+    \x03\x14\x1b\n\r\n\x05\x04\x0f\x02\x02\x03\x12\x04\xab\x03\x1e\x1f\n\x0c\
+    \n\x04\x04\x0f\x02\x03\x12\x04\xac\x03\x04\x1d\n\r\n\x05\x04\x0f\x02\x03\
+    \x04\x12\x04\xac\x03\x04\x0c\n\r\n\x05\x04\x0f\x02\x03\x05\x12\x04\xac\
+    \x03\r\x13\n\r\n\x05\x04\x0f\x02\x03\x01\x12\x04\xac\x03\x14\x18\n\r\n\
+    \x05\x04\x0f\x02\x03\x03\x12\x04\xac\x03\x1b\x1c\nR\n\x04\x04\x0f\x02\
+    \x04\x12\x04\xae\x03\x04&\x1aD\x20Last\x20sequence\x20number\x20recei\
+    ved,\x20since\x20the\x20publisher\x20last\x20restarted.\n\n\r\n\x05\x04\
+    \x0f\x02\x04\x04\x12\x04\xae\x03\x04\
+    \x0c\n\r\n\x05\x04\x0f\x02\x04\x05\x12\x04\xae\x03\r\x13\n\r\n\x05\x04\
+    \x0f\x02\x04\x01\x12\x04\xae\x03\x14!\n\r\n\x05\x04\x0f\x02\x04\x03\x12\
+    \x04\xae\x03$%\n1\n\x02\x05\x08\x12\x06\xb2\x03\0\xbe\x03\x01\x1a#\x20Wh\
+    y\x20a\x20SessionCountAlert\x20was\x20sent.\n\n\x0b\n\x03\x05\x08\x01\
+    \x12\x04\xb2\x03\x05\x17\n\x0c\n\x04\x05\x08\x02\0\x12\x04\xb3\x03\x04\
+    \x16\n\r\n\x05\x05\x08\x02\0\x01\x12\x04\xb3\x03\x04\x11\n\r\n\x05\x05\
+    \x08\x02\0\x02\x12\x04\xb3\x03\x14\x15\n8\n\x04\x05\x08\x02\x01\x12\x04\
+    \xb5\x03\x04\x12\x1a*\x20The\x20count\x20rose\x20above\x20the\x20high\
+    \x20watermark.\n\n\r\n\x05\x05\x08\x02\x01\x01\x12\x04\xb5\x03\x04\r\n\r\
+    \n\x05\x05\x08\x02\x01\x02\x12\x04\xb5\x03\x10\x11\n7\n\x04\x05\x08\x02\
+    \x02\x12\x04\xb7\x03\x04\x11\x1a)\x20The\x20count\x20fell\x20below\x20th\
+    e\x20low\x20watermark.\n\n\r\n\x05\x05\x08\x02\x02\x01\x12\x04\xb7\x03\
+    \x04\x0c\n\r\n\x05\x05\x08\x02\x02\x02\x12\x04\xb7\x03\x0f\x10\n9\n\x04\
+    \x05\x08\x02\x03\x12\x04\xb9\x03\x04\x19\x1a+\x20The\x20count\x20is\x20b\
+    ack\x20between\x20the\x20watermarks.\n\n\r\n\x05\x05\x08\x02\x03\x01\x12\
+    \x04\xb9\x03\x04\x14\n\r\n\x05\x05\x08\x02\x03\x02\x12\x04\xb9\x03\x17\
+    \x18\nS\n\x04\x05\x08\x02\x04\x12\x04\xbb\x03\x04\x0e\x1aE\x20The\x20cou\
+    nt\x20rose\x20by\x20more\x20than\x20the\x20allowed\x20share\x20in\x20one\
+    \x20report\x20period.\n\n\r\n\x05\x05\x08\x02\x04\x01\x12\x04\xbb\x03\
+    \x04\t\n\r\n\x05\x05\x08\x02\x04\x02\x12\x04\xbb\x03\x0c\r\nS\n\x04\x05\
+    \x08\x02\x05\x12\x04\xbd\x03\x04\r\x1aE\x20The\x20count\x20fell\x20by\
+    \x20more\x20than\x20the\x20allowed\x20share\x20in\x20one\x20report\x20pe\
+    riod.\n\n\r\n\x05\x05\x08\x02\x05\x01\x12\x04\xbd\x03\x04\x08\n\r\n\x05\
+    \x05\x08\x02\x05\x02\x12\x04\xbd\x03\x0b\x0c\nx\n\x02\x04\x10\x12\x06\
+    \xc2\x03\0\xcb\x03\x01\x1aj\x20Sessions\x20that\x20expired\x20without\
+    \x20being\x20matched,\x20for\x20one\x20transport,\n\x20registration\x20s\
+    ource\x20and\x20phantom\x20subnet.\n\n\x0b\n\x03\x04\x10\x01\x12\x04\xc2\
+    \x03\x08\x19\n\x0c\n\x04\x04\x10\x02\0\x12\x04\xc3\x03\x04)\n\r\n\x05\
+    \x04\x10\x02\0\x04\x12\x04\xc3\x03\x04\x0c\n\r\n\x05\x04\x10\x02\0\x06\
+    \x12\x04\xc3\x03\r\x1a\n\r\n\x05\x04\x10\x02\0\x01\x12\x04\xc3\x03\x1b$\
+    \n\r\n\x05\x04\x10\x02\0\x03\x12\x04\xc3\x03'(\n\x0c\n\x04\x04\x10\x02\
+    \x01\x12\x04\xc4\x03\x048\n\r\n\x05\x04\x10\x02\x01\x04\x12\x04\xc4\x03\
+    \x04\x0c\n\r\n\x05\x04\x10\x02\x01\x06\x12\x04\xc4\x03\r\x1f\n\r\n\x05\
+    \x04\x10\x02\x01\x01\x12\x04\xc4\x03\x203\n\r\n\x05\x04\x10\x02\x01\x03\
+    \x12\x04\xc4\x0367\n\x93\x02\n\x04\x04\x10\x02\x02\x12\x04\xc9\x03\x04\
+    \'\x1a\x84\x02\x20Phantom\x20subnet\x20from\x20the\x20detector\'s\x20\
+    phantom\x20subnet\x20list,\x20empty\x20if\x20the\n\x20list\x20isn\'t\x20\
+    loaded\x20or\x20no\x20subnet\x20contains\x20the\x20phantom.\x20The\x20\
+    buckets\x20the\n\x20detector\x20withholds\x20as\x20too\x20small\x20ar\
+    e\x20merged\x20into\x20one\x20with\x20the\x20subnet\n\x20\"other\",\x20\
+    and\x20no\x20transport\x20or\x20registration_source.\n\n\r\n\x05\x04\x10\
+    \x02\
+    \x02\x04\x12\x04\xc9\x03\x04\x0c\n\r\n\x05\x04\x10\x02\x02\x05\x12\x04\
+    \xc9\x03\r\x13\n\r\n\x05\x04\x10\x02\x02\x01\x12\x04\xc9\x03\x14\"\n\r\n\
+    \x05\x04\x10\x02\x02\x03\x12\x04\xc9\x03%&\n\x0c\n\x04\x04\x10\x02\x03\
+    \x12\x04\xca\x03\x04!\n\r\n\x05\x04\x10\x02\x03\x04\x12\x04\xca\x03\x04\
+    \x0c\n\r\n\x05\x04\x10\x02\x03\x05\x12\x04\xca\x03\r\x13\n\r\n\x05\x04\
+    \x10\x02\x03\x01\x12\x04\xca\x03\x14\x1c\n\r\n\x05\x04\x10\x02\x03\x03\
+    \x12\x04\xca\x03\x1f\x20\n\x0c\n\x02\x04\x11\x12\x06\xcd\x03\0\x83\x04\
+    \x01\n\x0b\n\x03\x04\x11\x01\x12\x04\xcd\x03\x08\x19\n\x0c\n\x04\x04\x11\
+    \x02\0\x12\x04\xce\x03\x04%\n\r\n\x05\x04\x11\x02\0\x04\x12\x04\xce\x03\
+    \x04\x0c\n\r\n\x05\x04\x11\x02\0\x06\x12\x04\xce\x03\r\x1a\n\r\n\x05\x04\
+    \x11\x02\0\x01\x12\x04\xce\x03\x1b\x20\n\r\n\x05\x04\x11\x02\0\x03\x12\
+    \x04\xce\x03#$\n\xec\x02\n\x04\x04\x11\x02\x01\x12\x04\xd5\x03\x04#\x1a\
+    \xdd\x02\x20Identifier\x20the\x20detector\x20assigned\x20to\x20the\x20\
+    session\x20when\x20it\x20was\x20registered\n\x20(hash\x20of\x20the\x20\
+    session\x20key\x20and\x20registration\x20time).\x20The\x20same\x20id\x20\
+    appears\x20in\n\x20the\x20detector\x20logs\x20for\x20every\x20forward\
+    ing\x20decision\x20made\x20for\x20the\x20session.\n\x20It\x20only\x20\
+    tells\x20apart\x20the\x20sessions\x20of\x20one\x20detector:\x20those\x20\
+    of\x20other\n\x20detectors,\x20or\x20registered\x20by\x20other\x20sta\
+    tions,\x20may\x20have\x20the\x20same\x20id.\n\n\r\n\x05\x04\x11\x02\x01\
+    \x04\x12\x04\xd5\x03\x04\x0c\n\r\n\x05\x04\x11\x02\x01\x05\x12\x04\xd5\
+    \x03\r\x13\n\r\n\x05\x04\x11\x02\x01\x01\x12\x04\xd5\x03\x14\x1e\n\r\n\
+    \x05\x04\x11\x02\x01\x03\x12\x04\xd5\x03!\"\n\x0c\n\x04\x04\x11\x02\x02\
+    \x12\x04\xd7\x03\x04#\n\r\n\x05\x04\x11\x02\x02\x04\x12\x04\xd7\x03\x04\
+    \x0c\n\r\n\x05\x04\x11\x02\x02\x05\x12\x04\xd7\x03\r\x13\n\r\n\x05\x04\
+    \x11\x02\x02\x01\x12\x04\xd7\x03\x14\x1e\n\r\n\x05\x04\x11\x02\x02\x03\
+    \x12\x04\xd7\x03!\"\n\x0c\n\x04\x04\x11\x02\x03\x12\x04\xd8\x03\x04\"\n\
+    \r\n\x05\x04\x11\x02\x03\x04\x12\x04\xd8\x03\x04\x0c\n\r\n\x05\x04\x11\
+    \x02\x03\x05\x12\x04\xd8\x03\r\x13\n\r\n\x05\x04\x11\x02\x03\x01\x12\x04\
+    \xd8\x03\x14\x1d\n\r\n\x05\x04\x11\x02\x03\x03\x12\x04\xd8\x03\x20!\n\
+    \x0c\n\x04\x04\x11\x02\x04\x12\x04\xd9\x03\x04%\n\r\n\x05\x04\x11\x02\
+    \x04\x04\x12\x04\xd9\x03\x04\x0c\n\r\n\x05\x04\x11\x02\x04\x05\x12\x04\
+    \xd9\x03\r\x13\n\r\n\x05\x04\x11\x02\x04\x01\x12\x04\xd9\x03\x14\x20\n\r\
+    \n\x05\x04\x11\x02\x04\x03\x12\x04\xd9\x03#$\n\x0c\n\x04\x04\x11\x02\x05\
+    \x12\x04\xda\x03\x04$\n\r\n\x05\x04\x11\x02\x05\x04\x12\x04\xda\x03\x04\
+    \x0c\n\r\n\x05\x04\x11\x02\x05\x05\x12\x04\xda\x03\r\x13\n\r\n\x05\x04\
+    \x11\x02\x05\x01\x12\x04\xda\x03\x14\x1f\n\r\n\x05\x04\x11\x02\x05\x03\
+    \x12\x04\xda\x03\"#\n\x0c\n\x04\x04\x11\x02\x06\x12\x04\xdb\x03\x048\n\r\
+    \n\x05\x04\x11\x02\x06\x04\x12\x04\xdb\x03\x04\x0c\n\r\n\x05\x04\x11\x02\
+    \x06\x06\x12\x04\xdb\x03\r\x1f\n\r\n\x05\x04\x11\x02\x06\x01\x12\x04\xdb\
+    \x03\x203\n\r\n\x05\x04\x11\x02\x06\x03\x12\x04\xdb\x0367\nK\n\x04\x04\
+    \x11\x02\x07\x12\x04\xde\x03\x041\x1a=\x20NeverMatchedSummary\x20only,\
+    \x20one\x20entry\x20per\x20distinct\x20breakdown.\n\n\r\n\x05\x04\x11\
+    \x02\x07\x04\x12\x04\xde\x03\x04\x0c\n\r\n\x05\x04\x11\x02\x07\x06\x12\
+    \x04\xde\x03\r\x1e\n\r\n\x05\x04\x11\x02\x07\x01\x12\x04\xde\x03\x1f,\n\
+    \r\n\x05\x04\x11\x02\x07\x03\x12\x04\xde\x03/0\n-\n\x04\x04\x11\x02\x08\
+    \x12\x04\xe1\x03\x04%\x1a\x1f\x20ConfigAck\x20and\x20Heartbeat\x20only.\
+    \n\n\r\n\x05\x04\x11\x02\x08\x04\x12\x04\xe1\x03\x04\x0c\n\r\n\x05\x04\
+    \x11\x02\x08\x05\x12\x04\xe1\x03\r\x13\n\r\n\x05\x04\x11\x02\x08\x01\x12\
+    \x04\xe1\x03\x14\x20\n\r\n\x05\x04\x11\x02\x08\x03\x12\x04\xe1\x03#$\nP\
+    \n\x04\x04\x11\x02\t\x12\x04\xe3\x03\x04&\x1aB\x20ConfigAck\x20and\x20Ph\
+    antomSubnetsAck\x20only,\x20why\x20the\x20push\x20was\x20rejected.\n\n\r\
+    \n\x05\x04\x11\x02\t\x04\x12\x04\xe3\x03\x04\x0c\n\r\n\x05\x04\x11\x02\t\
+    \x05\x12\x04\xe3\x03\r\x13\n\r\n\x05\x04\x11\x02\t\x01\x12\x04\xe3\x03\
+    \x14\x20\n\r\n\x05\x04\x11\x02\t\x03\x12\x04\xe3\x03#%\n5\n\x04\x04\x11\
+    \x02\n\x12\x04\xe5\x03\x044\x1a\'\x20PhantomSubnetsAck\x20and\x20Heartbea\
+    t\x20only.\n\n\r\n\x05\x04\x11\x02\n\x04\x12\x04\xe5\x03\x04\x0c\n\r\n\
+    \x05\x04\x11\x02\n\x05\x12\x04\xe5\x03\r\x13\n\r\n\x05\x04\x11\x02\n\x01\
+    \x12\x04\xe5\x03\x14.\n\r\n\x05\x04\x11\x02\n\x03\x12\x04\xe5\x0313\n\
+    \xba\x01\n\x04\x04\x11\x02\x0b\x12\x04\xea\x03\x04%\x1a\xab\x01\x20Sessi\
+    onExpired\x20only.\x20Connections\x20forwarded\x20for\x20the\x20session,\
+    \x20and\x20the\n\x20packets\x20and\x20bytes\x20(IP)\x20forwarded\x20from\
+    \x20the\x20client\x20over\x20those\x20that\x20had\n\x20closed\x20by\x20t\
+    he\x20time\x20it\x20expired.\n\n\r\n\x05\x04\x11\x02\x0b\x04\x12\x04\xea\
+    \x03\x04\x0c\n\r\n\x05\x04\x11\x02\x0b\x05\x12\x04\xea\x03\r\x13\n\r\n\
+    \x05\x04\x11\x02\x0b\x01\x12\x04\xea\x03\x14\x1f\n\r\n\x05\x04\x11\x02\
+    \x0b\x03\x12\x04\xea\x03\"$\n\x0c\n\x04\x04\x11\x02\x0c\x12\x04\xeb\x03\
+    \x04!\n\r\n\x05\x04\x11\x02\x0c\x04\x12\x04\xeb\x03\x04\x0c\n\r\n\x05\
+    \x04\x11\x02\x0c\x05\x12\x04\xeb\x03\r\x13\n\r\n\x05\x04\x11\x02\x0c\x01\
+    \x12\x04\xeb\x03\x14\x1b\n\r\n\x05\x04\x11\x02\x0c\x03\x12\x04\xeb\x03\
+    \x1e\x20\n\x0c\n\x04\x04\x11\x02\r\x12\x04\xec\x03\x04\x1f\n\r\n\x05\x04\
+    \x11\x02\r\x04\x12\x04\xec\x03\x04\x0c\n\r\n\x05\x04\x11\x02\r\x05\x12\
+    \x04\xec\x03\r\x13\n\r\n\x05\x04\x11\x02\r\x01\x12\x04\xec\x03\x14\x19\n\
+    \r\n\x05\x04\x11\x02\r\x03\x12\x04\xec\x03\x1c\x1e\n\xac\x01\n\x04\x04\
+    \x11\x02\x0e\x12\x04\xf1\x03\x04\"\x1a\x9d\x01\x20SessionMatched\x20and\
+    \x20SessionExpired\x20only.\x20Affinity\x20hash\x20of\x20the\x20session\
+    \n\x20(32\x20bit\x20FNV-1a\x20of\x20its\x20session\x20key),\x20so\x20app\
+    lication\x20workers\x20can\x20shard\n\x20sessions\x20alike.\n\n\r\n\x05\
+    \x04\x11\x02\x0e\x04\x12\x04\xf1\x03\x04\x0c\n\r\n\x05\x04\x11\x02\x0e\
+    \x05\x12\x04\xf1\x03\r\x13\n\r\n\x05\x04\x11\x02\x0e\x01\x12\x04\xf1\x03\
+    \x14\x1c\n\r\n\x05\x04\x11\x02\x0e\x03\x12\x04\xf1\x03\x1f!\n\x80\x01\n\
+    \x04\x04\x11\x02\x0f\x12\x04\xf5\x03\x04+\x1ar\x20SessionCountAlert\x20o\
+    nly.\x20Why\x20it\x20was\x20sent,\x20the\x20core's\x20tracked\x20session\
+    \n\x20count,\x20and\x20its\x20count\x20at\x20the\x20previous\x20check.\n\
+    \n\r\n\x05\x04\x11\x02\x0f\x04\x12\x04\xf5\x03\x04\x0c\n\r\n\x05\x04\x11\
+    \x02\x0f\x06\x12\x04\xf5\x03\r\x1f\n\r\n\x05\x04\x11\x02\x0f\x01\x12\x04\
+    \xf5\x03\x20%\n\r\n\x05\x04\x11\x02\x0f\x03\x12\x04\xf5\x03(*\n\x0c\n\
+    \x04\x04\x11\x02\x10\x12\x04\xf6\x03\x04\'\n\r\n\x05\x04\x11\x02\x10\x04\
+    \x12\x04\xf6\x03\x04\x0c\n\r\n\x05\x04\x11\x02\x10\x05\x12\x04\xf6\x03\r\
+    \x13\n\r\n\x05\x04\x11\x02\x10\x01\x12\x04\xf6\x03\x14!\n\r\n\x05\x04\
+    \x11\x02\x10\x03\x12\x04\xf6\x03$&\n\x0c\n\x04\x04\x11\x02\x11\x12\x04\
+    \xf7\x03\x040\n\r\n\x05\x04\x11\x02\x11\x04\x12\x04\xf7\x03\x04\x0c\n\r\
+    \n\x05\x04\x11\x02\x11\x05\x12\x04\xf7\x03\r\x13\n\r\n\x05\x04\x11\x02\
+    \x11\x01\x12\x04\xf7\x03\x14*\n\r\n\x05\x04\x11\x02\x11\x03\x12\x04\xf7\
+    \x03-/\nM\n\x04\x04\x11\x02\x12\x12\x04\xf9\x03\x04\x1e\x1a?\x20SessionC\
+    ountAlert\x20and\x20SequenceGaps\x20only,\x20the\x20core\x20sending\x20i\
+    t.\n\n\r\n\x05\x04\x11\x02\x12\x04\x12\x04\xf9\x03\x04\x0c\n\r\n\x05\x04\
+    \x11\x02\x12\x05\x12\x04\xf9\x03\r\x13\n\r\n\x05\x04\x11\x02\x12\x01\x12\
+    \x04\xf9\x03\x14\x18\n\r\n\x05\x04\x11\x02\x12\x03\x12\x04\xf9\x03\x1b\
+    \x1d\n;\n\x04\x04\x11\x02\x13\x12\x04\xfc\x03\x041\x1a-\x20SequenceGaps\
+    \x20only,\x20one\x20entry\x20per\x20publisher.\n\n\r\n\x05\x04\x11\x02\
+    \x13\x04\x12\x04\xfc\x03\x04\x0c\n\r\n\x05\x04\x11\x02\x13\x06\x12\x04\
+    \xfc\x03\r\x1d\n\r\n\x05\x04\x11\x02\x13\x01\x12\x04\xfc\x03\x1e+\n\r\n\
+    \x05\x04\x11\x02\x13\x03\x12\x04\xfc\x03.0\n\xa1\x02\n\x04\x04\x11\x02\
+    \x14\x12\x04\x82\x04\x04\"\x1a\x92\x02\x20SessionMatched\x20and\x20Sessi\
+    onExpired\x20only,\x20for\x20sessions\x20of\x20a\x20dual-stack\n\x20regi\
+    stration.\x20Shared\x20by\x20its\x20IPv4\x20and\x20IPv6\x20sessions,\x20\
+    which\x20are\x20reported\n\x20as\x20one:\x20SessionMatched\x20is\x20sent\
+    \x20for\x20the\x20first\x20connection\x20to\x20either,\x20and\n\x20Sessi\
+    onExpired\x20once\x20both\x20expired,\x20with\x20the\x20totals\x20of\x20\
+    both.\n\n\r\n\x05\x04\x11\x02\x14\x04\x12\x04\x82\x04\x04\x0c\n\r\n\x05\
+    \x04\x11\x02\x14\x05\x12\x04\x82\x04\r\x13\n\r\n\x05\x04\x11\x02\x14\x01\
+    \x12\x04\x82\x04\x14\x1c\n\r\n\x05\x04\x11\x02\x14\x03\x12\x04\x82\x04\
+    \x1f!\n\xbf\x01\n\x02\x04\x12\x12\x06\x87\x04\0\x9f\x04\x01\x1a\xb0\x01\
+    \x20Quality\x20sample\x20of\x20one\x20matched\x20session\x20over\x20the\
+    \x20first\x20minutes\x20after\x20its\n\x20first\x20connection,\x20publis\
+    hed\x20by\x20the\x20detector\x20on\x20its\x20stats\x20channel.\x20Carrie\
+    s\n\x20no\x20addresses\x20or\x20session\x20id.\n\n\x0b\n\x03\x04\x12\x01\
+    \x12\x04\x87\x04\x08\x10\n\x0c\n\x04\x04\x12\x02\0\x12\x04\x88\x04\x04)\
+    \n\r\n\x05\x04\x12\x02\0\x04\x12\x04\x88\x04\x04\x0c\n\r\n\x05\x04\x12\
+    \x02\0\x06\x12\x04\x88\x04\r\x1a\n\r\n\x05\x04\x12\x02\0\x01\x12\x04\x88\
+    \x04\x1b$\n\r\n\x05\x04\x12\x02\0\x03\x12\x04\x88\x04\'(\n\x0c\n\x04\x04\
+    \x12\x02\x01\x12\x04\x89\x04\x048\n\r\n\x05\x04\x12\x02\x01\x04\x12\x04\
+    \x89\x04\x04\x0c\n\r\n\x05\x04\x12\x02\x01\x06\x12\x04\x89\x04\r\x1f\n\r\
+    \n\x05\x04\x12\x02\x01\x01\x12\x04\x89\x04\x203\n\r\n\x05\x04\x12\x02\
+    \x01\x03\x12\x04\x89\x0467\n\x0c\n\x04\x04\x12\x02\x02\x12\x04\x8a\x04\
+    \x04%\n\r\n\x05\x04\x12\x02\x02\x04\x12\x04\x8a\x04\x04\x0c\n\r\n\x05\
+    \x04\x12\x02\x02\x05\x12\x04\x8a\x04\r\x13\n\r\n\x05\x04\x12\x02\x02\x01\
+    \x12\x04\x8a\x04\x14\x20\n\r\n\x05\x04\x12\x02\x02\x03\x12\x04\x8a\x04#$\
+    \n\x0c\n\x04\x04\x12\x02\x03\x12\x04\x8b\x04\x04\x1b\n\r\n\x05\x04\x12\
+    \x02\x03\x04\x12\x04\x8b\x04\x04\x0c\n\r\n\x05\x04\x12\x02\x03\x05\x12\
+    \x04\x8b\x04\r\x11\n\r\n\x05\x04\x12\x02\x03\x01\x12\x04\x8b\x04\x12\x16\
+    \n\r\n\x05\x04\x12\x02\x03\x03\x12\x04\x8b\x04\x19\x1a\n2\n\x04\x04\x12\
+    \x02\x04\x12\x04\x8e\x04\x04\"\x1a$\x20Length\x20of\x20the\x20sampled\
+    \x20window\x20(ms).\n\n\r\n\x05\x04\x12\x02\x04\x04\x12\x04\x8e\x04\x04\
+    \x0c\n\r\n\x05\x04\x12\x02\x04\x05\x12\x04\x8e\x04\r\x13\n\r\n\x05\x04\
+    \x12\x02\x04\x01\x12\x04\x8e\x04\x14\x1d\n\r\n\x05\x04\x12\x02\x04\x03\
+    \x12\x04\x8e\x04\x20!\nm\n\x04\x04\x12\x02\x05\x12\x04\x92\x04\x04$\x1a_\
+    \x20Connections\x20opened,\x20and\x20packets\x20and\x20bytes\x20(IP)\x20\
+    forwarded\x20from\x20the\n\x20client,\x20during\x20the\x20window.\n\n\r\
+    \n\x05\x04\x12\x02\x05\x04\x12\x04\x92\x04\x04\x0c\n\r\n\x05\x04\x12\x02\
+    \x05\x05\x12\x04\x92\x04\r\x13\n\r\n\x05\x04\x12\x02\x05\x01\x12\x04\x92\
+    \x04\x14\x1f\n\r\n\x05\x04\x12\x02\x05\x03\x12\x04\x92\x04\"#\n\x0c\n\
+    \x04\x04\x12\x02\x06\x12\x04\x93\x04\x04\x20\n\r\n\x05\x04\x12\x02\x06\
+    \x04\x12\x04\x93\x04\x04\x0c\n\r\n\x05\x04\x12\x02\x06\x05\x12\x04\x93\
+    \x04\r\x13\n\r\n\x05\x04\x12\x02\x06\x01\x12\x04\x93\x04\x14\x1b\n\r\n\
+    \x05\x04\x12\x02\x06\x03\x12\x04\x93\x04\x1e\x1f\n\x0c\n\x04\x04\x12\x02\
+    \x07\x12\x04\x94\x04\x04\x1e\n\r\n\x05\x04\x12\x02\x07\x04\x12\x04\x94\
+    \x04\x04\x0c\n\r\n\x05\x04\x12\x02\x07\x05\x12\x04\x94\x04\r\x13\n\r\n\
+    \x05\x04\x12\x02\x07\x01\x12\x04\x94\x04\x14\x19\n\r\n\x05\x04\x12\x02\
+    \x07\x03\x12\x04\x94\x04\x1c\x1d\n\xcc\x01\n\x04\x04\x12\x02\x08\x12\x04\
+    \x99\x04\x04)\x1a\xbd\x01\x20First\x20connection,\x20from\x20the\x20clie\
+    nt's\x20SYN:\x20to\x20its\x20handshake\x20ACK\x20(about\n\x20one\x20roun\
+    d\x20trip\x20between\x20the\x20detector\x20and\x20the\x20client)\x20and\
+    \x20to\x20its\x20first\n\x20data\x20(us).\x20Unset\x20if\x20not\x20seen\
+    \x20during\x20the\x20window.\n\n\r\n\x05\x04\x12\x02\x08\x04\x12\x04\x99\
+    \x04\x04\x0c\n\r\n\x05\x04\x12\x02\x08\x05\x12\x04\x99\x04\r\x13\n\r\n\
+    \x05\x04\x12\x02\x08\x01\x12\x04\x99\x04\x14$\n\r\n\x05\x04\x12\x02\x08\
+    \x03\x12\x04\x99\x04\'(\n\x0c\n\x04\x04\x12\x02\t\x12\x04\x9a\x04\x04\'\n\
+    \r\n\x05\x04\x12\x02\t\x04\x12\x04\x9a\x04\x04\x0c\n\r\n\x05\x04\x12\x02\
+    \t\x05\x12\x04\x9a\x04\r\x13\n\r\n\x05\x04\x12\x02\t\x01\x12\x04\x9a\x04\
+    \x14!\n\r\n\x05\x04\x12\x02\t\x03\x12\x04\x9a\x04$&\n\x84\x01\n\x04\x04\
+    \x12\x02\n\x12\x04\x9e\x04\x04)\x1av\x20JA4\x20style\x20fingerprint\x20o\
+    f\x20the\x20first\x20connection's\x20TLS\x20ClientHello,\x20if\x20the\n\
+    \x20detector\x20fingerprints\x20them\x20and\x20it\x20sent\x20one.\n\n\r\
+    \n\x05\x04\x12\x02\n\x04\x12\x04\x9e\x04\x04\x0c\n\r\n\x05\x04\x12\x02\n\
+    \x05\x12\x04\x9e\x04\r\x13\n\r\n\x05\x04\x12\x02\n\x01\x12\x04\x9e\x04\
+    \x14#\n\r\n\x05\x04\x12\x02\n\x03\x12\x04\x9e\x04&(\nQ\n\x02\x04\x13\x12\
+    \x06\xa2\x04\0\xb3\x04\x01\x1aC\x20Session\x20accepted\x20by\x20a\x20det\
+    ector,\x20replicated\x20to\x20its\x20peer\x20detectors.\n\n\x0b\n\x03\
+    \x04\x13\x01\x12\x04\xa2\x04\x08\x19\nM\n\x04\x04\x13\x02\0\x12\x04\xa4\
+    \x04\x04!\x1a?\x20Addresses\x20in\x20network\x20order,\x204\x20bytes\x20\
+    for\x20IPv4\x20and\x2016\x20for\x20IPv6.\n\n\r\n\x05\x04\x13\x02\0\x04\
+    \x12\x04\xa4\x04\x04\x0c\n\r\n\x05\x04\x13\x02\0\x05\x12\x04\xa4\x04\r\
+    \x12\n\r\n\x05\x04\x13\x02\0\x01\x12\x04\xa4\x04\x13\x1c\n\r\n\x05\x04\
+    \x13\x02\0\x03\x12\x04\xa4\x04\x1f\x20\n\x0c\n\x04\x04\x13\x02\x01\x12\
+    \x04\xa5\x04\x04\"\n\r\n\x05\x04\x13\x02\x01\x04\x12\x04\xa5\x04\x04\x0c\
+    \n\r\n\x05\x04\x13\x02\x01\x05\x12\x04\xa5\x04\r\x12\n\r\n\x05\x04\x13\
+    \x02\x01\x01\x12\x04\xa5\x04\x13\x1d\n\r\n\x05\x04\x13\x02\x01\x03\x12\
+    \x04\xa5\x04\x20!\n\x0c\n\x04\x04\x13\x02\x02\x12\x04\xa6\x04\x04%\n\r\n\
+    \x05\x04\x13\x02\x02\x04\x12\x04\xa6\x04\x04\x0c\n\r\n\x05\x04\x13\x02\
+    \x02\x05\x12\x04\xa6\x04\r\x13\n\r\n\x05\x04\x13\x02\x02\x01\x12\x04\xa6\
+    \x04\x14\x20\n\r\n\x05\x04\x13\x02\x02\x03\x12\x04\xa6\x04#$\n=\n\x04\
+    \x04\x13\x02\x03\x12\x04\xa8\x04\x04#\x1a/\x20Lifetime\x20of\x20the\x20s\
+    ession\x20when\x20it\x20was\x20accepted.\n\n\r\n\x05\x04\x13\x02\x03\x04\
+    \x12\x04\xa8\x04\x04\x0c\n\r\n\x05\x04\x13\x02\x03\x05\x12\x04\xa8\x04\r\
+    \x13\n\r\n\x05\x04\x13\x02\x03\x01\x12\x04\xa8\x04\x14\x1e\n\r\n\x05\x04\
+    \x13\x02\x03\x03\x12\x04\xa8\x04!\"\n\x0c\n\x04\x04\x13\x02\x04\x12\x04\
+    \xa9\x04\x048\n\r\n\x05\x04\x13\x02\x04\x04\x12\x04\xa9\x04\x04\x0c\n\r\
+    \n\x05\x04\x13\x02\x04\x06\x12\x04\xa9\x04\r\x1f\n\r\n\x05\x04\x13\x02\
+    \x04\x01\x12\x04\xa9\x04\x203\n\r\n\x05\x04\x13\x02\x04\x03\x12\x04\xa9\
+    \x0467\n\x0c\n\x04\x04\x13\x02\x05\x12\x04\xaa\x04\x04(\n\r\n\x05\x04\
+    \x13\x02\x05\x04\x12\x04\xaa\x04\x04\x0c\n\r\n\x05\x04\x13\x02\x05\x05\
+    \x12\x04\xaa\x04\r\x13\n\r\n\x05\x04\x13\x02\x05\x01\x12\x04\xaa\x04\x14\
+    #\n\r\n\x05\x04\x13\x02\x05\x03\x12\x04\xaa\x04&\'\n\x0c\n\x04\x04\x13\
+    \x02\x06\x12\x04\xab\x04\x04\"\n\r\n\x05\x04\x13\x02\x06\x04\x12\x04\xab\
+    \x04\x04\x0c\n\r\n\x05\x04\x13\x02\x06\x06\x12\x04\xab\x04\r\x18\n\r\n\
+    \x05\x04\x13\x02\x06\x01\x12\x04\xab\x04\x19\x1d\n\r\n\x05\x04\x13\x02\
+    \x06\x03\x12\x04\xab\x04\x20!\n\x0c\n\x04\x04\x13\x02\x07\x12\x04\xac\
+    \x04\x04\"\n\r\n\x05\x04\x13\x02\x07\x04\x12\x04\xac\x04\x04\x0c\n\r\n\
+    \x05\x04\x13\x02\x07\x05\x12\x04\xac\x04\r\x13\n\r\n\x05\x04\x13\x02\x07\
+    \x01\x12\x04\xac\x04\x14\x1d\n\r\n\x05\x04\x13\x02\x07\x03\x12\x04\xac\
+    \x04\x20!\nC\n\x04\x04\x13\x02\x08\x12\x04\xae\x04\x04%\x1a5\x20Extensio\
+    n\x20requested\x20by\x20the\x20registration,\x200\x20if\x20none.\n\n\r\n\
+    \x05\x04\x13\x02\x08\x04\x12\x04\xae\x04\x04\x0c\n\r\n\x05\x04\x13\x02\
+    \x08\x05\x12\x04\xae\x04\r\x13\n\r\n\x05\x04\x13\x02\x08\x01\x12\x04\xae\
+    \x04\x14\x20\n\r\n\x05\x04\x13\x02\x08\x03\x12\x04\xae\x04#$\nH\n\x04\
+    \x04\x13\x02\t\x12\x04\xb0\x04\x04)\x1a:\x20Source\x20ports\x20the\x20cl\
+    ient\x20may\x20connect\x20from,\x20if\x20restricted.\n\n\r\n\x05\x04\x13\
+    \x02\t\x04\x12\x04\xb0\x04\x04\x0c\n\r\n\x05\x04\x13\x02\t\x06\x12\x04\
+    \xb0\x04\r\x16\n\r\n\x05\x04\x13\x02\t\x01\x12\x04\xb0\x04\x17#\n\r\n\
+    \x05\x04\x13\x02\t\x03\x12\x04\xb0\x04&(\nR\n\x04\x04\x13\x02\n\x12\x04\
+    \xb2\x04\x04\"\x1aD\x20Group\x20shared\x20with\x20the\x20other\x20sessio\
+    ns\x20of\x20a\x20dual-stack\x20registration.\n\n\r\n\x05\x04\x13\x02\n\
+    \x04\x12\x04\xb2\x04\x04\x0c\n\r\n\x05\x04\x13\x02\n\x05\x12\x04\xb2\x04\
+    \r\x13\n\r\n\x05\x04\x13\x02\n\x01\x12\x04\xb2\x04\x14\x1c\n\r\n\x05\x04\
+    \x13\x02\n\x03\x12\x04\xb2\x04\x1f!\nt\n\x02\x04\x14\x12\x06\xb7\x04\0\
+    \xbf\x04\x01\x1af\x20Sessions\x20produced\x20by\x20one\x20registratio\
+    n,\
+    \x20published\x20by\x20the\x20detector\x20that\n\x20received\x20it\x20fr\
+    om\x20its\x20station.\n\n\x0b\n\x03\x04\x14\x01\x12\x04\xb7\x04\x08\x1a\
+    \n:\n\x04\x04\x14\x02\0\x12\x04\xb9\x04\x04\x1f\x1a,\x20Replication\x20i\
+    d\x20of\x20the\x20publishing\x20detector.\n\n\r\n\x05\x04\x14\x02\0\x04\
+    \x12\x04\xb9\x04\x04\x0c\n\r\n\x05\x04\x14\x02\0\x05\x12\x04\xb9\x04\r\
+    \x13\n\r\n\x05\x04\x14\x02\0\x01\x12\x04\xb9\x04\x14\x1a\n\r\n\x05\x04\
+    \x14\x02\0\x03\x12\x04\xb9\x04\x1d\x1e\n\x0c\n\x04\x04\x14\x02\x01\x12\
+    \x04\xba\x04\x04,\n\r\n\x05\x04\x14\x02\x01\x04\x12\x04\xba\x04\x04\x0c\
+    \n\r\n\x05\x04\x14\x02\x01\x06\x12\x04\xba\x04\r\x1e\n\r\n\x05\x04\x14\
+    \x02\x01\x01\x12\x04\xba\x04\x1f\'\n\r\n\x05\x04\x14\x02\x01\x03\x12\x04\
+    \xba\x04*+\n\xce\x01\n\x04\x04\x14\x02\x02\x12\x04\xbe\x04\x04\x1d\x1a\
+    \xbf\x01\x20The\x20sessions\x20were\x20refreshed\x20by\x20the\x20stat\
+    ion\'s\x20update\x20(see\n\x20StationToDetector.update_expiry),\x20wi\
+    th\x20timeout_ns\x20their\x20new\x20lifetime,\n\x20rather\x20than\x20\
+    registered:\x20peers\x20only\x20refresh\x20those\x20they\x20track.\n\n\
+    \r\n\x05\x04\x14\x02\x02\x04\x12\x04\xbe\x04\x04\x0c\n\r\n\x05\x04\x14\
+    \x02\x02\x05\x12\x04\xbe\x04\r\x11\n\r\n\x05\x04\x14\x02\x02\x01\x12\x04\
+    \xbe\x04\x12\x18\n\r\n\x05\x04\x14\x02\x02\x03\x12\x04\xbe\x04\x1b\x1c\
+    \n\x87\x02\n\x02\x04\x15\x12\x06\xc5\x04\0\xdd\x04\x01\x1a\xf8\
+    \x01\x20Anonymized\x20snapshot\x20of\x20one\x20tracked\x20session,\x20wr\
+    itten\x20by\x20the\x20detector's\n\x20session\x20export\x20for\x20offlin\
+    e\x20analysis.\x20Sessions\x20are\x20identified\x20by\x20a\x20keyed\n\
+    \x20hash\x20of\x20their\x20session\x20map\x20key\x20and\x20clients\x20by\
+    \x20their\x20ClientId;\x20no\x20addresses\n\x20or\x20session\x20id\x20ar\
+    e\x20included.\n\n\x0b\n\x03\x04\x15\x01\x12\x04\xc5\x04\x08\x15\n\x0c\
+    \n\x04\x04\x15\x02\0\x12\x04\xc6\x04\x04\x20\n\r\n\x05\x04\x15\x02\0\x04\
+    \x12\x04\xc6\x04\x04\x0c\n\r\n\x05\x04\x15\x02\0\x05\x12\x04\xc6\x04\r\
+    \x12\n\r\n\x05\x04\x15\x02\0\x01\x12\x04\xc6\x04\x13\x1b\n\r\n\x05\x04\
+    \x15\x02\0\x03\x12\x04\xc6\x04\x1e\x1f\nV\n\x04\x04\x15\x02\x01\x12\x04\
+    \xc8\x04\x04,\x1aH\x20When\x20the\x20snapshot\x20was\x20taken,\x20our\
+    \x20wall\x20clock\x20(ns\x20since\x20the\x20unix\x20epoch).\n\n\r\n\x05\
+    \x04\x15\x02\x01\x04\x12\x04\xc8\x04\x04\x0c\n\r\n\x05\x04\x15\x02\x01\
+    \x05\x12\x04\xc8\x04\r\x13\n\r\n\x05\x04\x15\x02\x01\x01\x12\x04\xc8\x04\
+    \x14\'\n\r\n\x05\x04\x15\x02\x01\x03\x12\x04\xc8\x04*+\n\x0c\n\x04\x04\
+    \x15\x02\x02\x12\x04\xc9\x04\x04)\n\r\n\x05\x04\x15\x02\x02\x04\x12\x04\
+    \xc9\x04\x04\x0c\n\r\n\x05\x04\x15\x02\x02\x06\x12\x04\xc9\x04\r\x1a\n\
+    \r\n\x05\x04\x15\x02\x02\x01\x12\x04\xc9\x04\x1b$\n\r\n\x05\x04\x15\x02\
+    \x02\x03\x12\x04\xc9\x04\'(\n\x0c\n\x04\x04\x15\x02\x03\x12\x04\xca\x04\
+    \x048\n\r\n\x05\x04\x15\x02\x03\x04\x12\x04\xca\x04\x04\x0c\n\r\n\x05\
+    \x04\x15\x02\x03\x06\x12\x04\xca\x04\r\x1f\n\r\n\x05\x04\x15\x02\x03\x01\
+    \x12\x04\xca\x04\x203\n\r\n\x05\x04\x15\x02\x03\x03\x12\x04\xca\x0467\
+    \n\x0c\n\x04\x04\x15\x02\x04\x12\x04\xcb\x04\x04%\n\r\n\x05\x04\x15\x02\
+    \x04\x04\x12\x04\xcb\x04\x04\x0c\n\r\n\x05\x04\x15\x02\x04\x05\x12\x04\
+    \xcb\x04\r\x13\n\r\n\x05\x04\x15\x02\x04\x01\x12\x04\xcb\x04\x14\x20\n\
+    \r\n\x05\x04\x15\x02\x04\x03\x12\x04\xcb\x04#$\n\x0c\n\x04\x04\x15\x02\
+    \x05\x12\x04\xcc\x04\x04\x1b\n\r\n\x05\x04\x15\x02\x05\x04\x12\x04\xcc\
+    \x04\x04\x0c\n\r\n\x05\x04\x15\x02\x05\x05\x12\x04\xcc\x04\r\x11\n\r\n\
+    \x05\x04\x15\x02\x05\x01\x12\x04\xcc\x04\x12\x16\n\r\n\x05\x04\x15\x02\
+    \x05\x03\x12\x04\xcc\x04\x19\x1a\n\x87\x01\n\x04\x04\x15\x02\x06\x12\x04\
+    \xd0\
+    \x04\x04#\x1ay\x20Lifetime\x20the\x20registration\x20asked\x20for,\x20ti\
+    me\x20since\x20the\x20session\x20was\x20first\n\x20registered,\x20and\
+    \x20time\x20left\x20until\x20it\x20expires\x20(ns).\n\n\r\n\x05\x04\x15\
+    \x02\x06\x04\x12\x04\xd0\x04\x04\x0c\n\r\n\x05\x04\x15\x02\x06\x05\x12\
+    \x04\xd0\x04\r\x13\n\r\n\x05\x04\x15\x02\x06\x01\x12\x04\xd0\x04\x14\x1e\
+    \n\r\n\x05\x04\x15\x02\x06\x03\x12\x04\xd0\x04!\"\n\x0c\n\x04\x04\x15\
+    \x02\x07\x12\x04\xd1\x04\x04\x1f\n\r\n\x05\x04\x15\x02\x07\x04\x12\x04\
+    \xd1\x04\x04\x0c\n\r\n\x05\x04\x15\x02\x07\x05\x12\x04\xd1\x04\r\x13\n\
+    \r\n\x05\x04\x15\x02\x07\x01\x12\x04\xd1\x04\x14\x1a\n\r\n\x05\x04\x15\
+    \x02\x07\x03\x12\x04\xd1\x04\x1d\x1e\n\x0c\n\x04\x04\x15\x02\x08\x12\x04\
+    \xd2\x04\x04%\n\r\n\x05\x04\x15\x02\x08\x04\x12\x04\xd2\x04\x04\x0c\n\
+    \r\n\x05\x04\x15\x02\x08\x05\x12\x04\xd2\x04\r\x13\n\r\n\x05\x04\x15\x02\
+    \x08\x01\x12\x04\xd2\x04\x14\x20\n\r\n\x05\x04\x15\x02\x08\x03\x12\x04\
+    \xd2\x04#$\n\x8d\x01\n\x04\x04\x15\x02\t\x12\x04\xd6\x04\x04\'\x1a\x7f\
+    \x20Reg\
+    istrations\x20that\x20mapped\x20to\x20the\x20session,\x20connections\x20\
+    forwarded\x20for\x20it\n\x20so\x20far\x20and\x20currently,\x20and\x20tim\
+    es\x20activity\x20extended\x20it.\n\n\r\n\x05\x04\x15\x02\t\x04\x12\x04\
+    \xd6\x04\x04\x0c\n\r\n\x05\x04\x15\x02\t\x05\x12\x04\xd6\x04\r\x13\n\r\
+    \n\x05\x04\x15\x02\t\x01\x12\x04\xd6\x04\x14!\n\r\n\x05\x04\x15\x02\t\
+    \x03\x12\x04\xd6\x04$&\n\x0c\n\x04\x04\x15\x02\n\x12\x04\xd7\x04\x04%\
+    \n\r\n\x05\x04\x15\x02\n\x04\x12\x04\xd7\x04\x04\x0c\n\r\n\x05\x04\x15\
+    \x02\n\x05\x12\x04\xd7\x04\r\x13\n\r\n\x05\x04\x15\x02\n\x01\x12\x04\xd7\
+    \x04\x14\x1f\n\r\n\x05\x04\x15\x02\n\x03\x12\x04\xd7\x04\"$\n\x0c\n\x04\
+    \x04\x15\x02\x0b\x12\x04\xd8\x04\x04,\n\r\n\x05\x04\x15\x02\x0b\x04\x12\
+    \x04\xd8\x04\x04\x0c\n\r\n\x05\x04\x15\x02\x0b\x05\x12\x04\xd8\x04\r\x13\
+    \n\r\n\x05\x04\x15\x02\x0b\x01\x12\x04\xd8\x04\x14&\n\r\n\x05\x04\x15\
+    \x02\x0b\x03\x12\x04\xd8\x04)+\n\x0c\n\x04\x04\x15\x02\x0c\x12\x04\xd9\
+    \x04\x04$\n\r\n\x05\x04\x15\x02\x0c\x04\x12\x04\xd9\x04\x04\x0c\n\r\n\
+    \x05\x04\x15\x02\x0c\x05\x12\x04\xd9\x04\r\x13\n\r\n\x05\x04\x15\x02\x0c\
+    \x01\x12\x04\xd9\x04\x14\x1e\n\r\n\x05\x04\x15\x02\x0c\x03\x12\x04\xd9\
+    \x04!#\nB\n\x04\x04\x15\x02\r\x12\x04\xdc\x04\x04$\x1a4\x20ClientId\x20\
+    of\x20t\
+    he\x20client\x20on\x20the\x20day\x20of\x20the\x20snapshot.\n\n\r\n\x05\
+    \x04\x15\x02\r\x04\x12\x04\xdc\x04\x04\x0c\n\r\n\x05\x04\x15\x02\r\x05\
+    \x12\x04\xdc\x04\r\x14\n\r\n\x05\x04\x15\x02\r\x01\x12\x04\xdc\x04\x15\
+    \x1e\n\r\n\x05\x04\x15\x02\r\x03\x12\x04\xdc\x04!#\
 ";
 
 static file_descriptor_proto_lazy: ::protobuf::rt::LazyV2<::protobuf::descriptor::FileDescriptorProto> = ::protobuf::rt::LazyV2::INIT;
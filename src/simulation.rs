@@ -0,0 +1,414 @@
+//
+// Simulation
+//
+// Detectors built with the simulation feature can model how the session
+// tracker behaves under a hypothetical load without capture hardware, redis or
+// a station. run() generates a stream of registrations and of the client
+// connections (and scans) they lead to, drawn from the distributions in a
+// SimulationConfig, and plays it against the real FlowTracker and
+// SessionTracker in virtual time: a simulated Clock is moved from event to
+// event, so hours of load take seconds.
+//
+// - Registrations arrive one interarrival time after another, each for a
+//   random client and phantom of the configured pools, and are added as the
+//   ingest thread would (Registration::sessions).
+// - A share of them see their client connect after a delay. The connection's
+//   packets are handed to the FlowTracker the way process_packet does (SYN,
+//   then packets every packet interval for the connection's duration, then
+//   FIN), so session extension, connection limits and flow timeouts apply.
+// - Scanners, from addresses no client uses, send lone SYNs to random
+//   phantoms of the pool; none of them should match.
+// - Every step of virtual time the stale flows and sessions are dropped, as
+//   the periodic cleanup does (the tracker runs none of its threads).
+//
+// The SimulationReport has the match rate (connections that found their
+// session), what missed and why, the peak session and connection counts and
+// the memory of the process before and at its peak.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::mem;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::Duration;
+
+use rand::{Rng, SeedableRng, XorShiftRng};
+use rand::distributions::{Exp, IndependentSample};
+
+use flow_tracker::{Flow, FlowNoSrcPort, FlowTracker};
+use registration::Registration;
+use sessions::{SessionConfig, SessionEntry};
+use timekeeping::{Clock, WallClockNs, duration_ns};
+use util::mem_used_kb;
+
+// Lengths of the simulated packets (IP), for the traffic counts.
+const SYN_BYTES: usize = 60;
+const PACKET_BYTES: usize = 1400;
+
+// A distribution of times, in seconds.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Distribution
+{
+    Constant(f64),
+    Uniform(f64, f64),
+    Exponential(f64),
+}
+
+impl Distribution
+{
+    /// A time drawn from the distribution, never negative.
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> Duration {
+        let secs = match *self {
+            Distribution::Constant(s) => s,
+            Distribution::Uniform(lo, hi) if hi > lo => rng.gen_range(lo, hi),
+            Distribution::Uniform(lo, _) => lo,
+            Distribution::Exponential(mean) if mean > 0.0 => Exp::new(1.0 / mean).ind_sample(rng),
+            Distribution::Exponential(_) => 0.0,
+        };
+        Duration::from_secs_f64(secs.max(0.0))
+    }
+}
+
+#[derive(Clone)]
+pub struct SimulationConfig
+{
+    pub seed: u32,
+    // Virtual time simulated, and between cleanup passes.
+    pub duration: Duration,
+    pub step: Duration,
+    // Clients and phantoms registrations are drawn from.
+    pub clients: u32,
+    pub phantoms: u32,
+    pub phantom_port: u16,
+    // Time between registrations, and the lifetime they ask for.
+    pub registration_interarrival: Distribution,
+    pub lifetime: Distribution,
+    // Share of registrations whose client connects, after connect_delay.
+    pub connect_share: f64,
+    pub connect_delay: Distribution,
+    // Connections per connecting registration, one after the other.
+    pub connections: u32,
+    pub connection_duration: Distribution,
+    pub packet_interval: Distribution,
+    // Time between scanner SYNs, None for no scans.
+    pub scan_interarrival: Option<Distribution>,
+    pub session: SessionConfig,
+}
+
+impl Default for SimulationConfig
+{
+    fn default() -> SimulationConfig {
+        SimulationConfig {
+            seed: 1,
+            duration: Duration::from_secs(600),
+            step: Duration::from_secs(1),
+            clients: 10000,
+            phantoms: 1000,
+            phantom_port: 443,
+            registration_interarrival: Distribution::Exponential(0.1),
+            lifetime: Distribution::Constant(120.0),
+            connect_share: 0.9,
+            connect_delay: Distribution::Exponential(2.0),
+            connections: 1,
+            connection_duration: Distribution::Exponential(60.0),
+            packet_interval: Distribution::Exponential(1.0),
+            scan_interarrival: None,
+            session: SessionConfig::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SimulationReport
+{
+    pub registrations: u64,
+    // Registrations whose sessions couldn't be built.
+    pub rejected: u64,
+    pub connections: u64,
+    // Connections that found their session, and sessions matched at all.
+    pub matched: u64,
+    pub sessions_matched: u64,
+    // Connections without a session (it had expired), and over its limit.
+    pub missed: u64,
+    pub limited: u64,
+    // Packets of matched connections sent after their session was dropped.
+    pub packets_orphaned: u64,
+    pub scans: u64,
+    pub scans_matched: u64,
+    pub peak_sessions: usize,
+    pub peak_connections: usize,
+    pub rss_start_kb: u64,
+    pub rss_peak_kb: u64,
+}
+
+impl SimulationReport
+{
+    /// Share of connections that found their session.
+    pub fn match_rate(&self) -> f64 {
+        match self.connections {
+            0 => 0.0,
+            n => self.matched as f64 / n as f64,
+        }
+    }
+
+    /// Report lines, as the periodic report would print them.
+    pub fn lines(&self) -> Vec<String> {
+        vec![
+            format!("simulated registrations {} rejected {}", self.registrations, self.rejected),
+            format!("simulated connections {} matched {} ({:.2}%) missed {} limited {} orphaned packets {}",
+                self.connections, self.matched, 100.0 * self.match_rate(), self.missed,
+                self.limited, self.packets_orphaned),
+            format!("simulated sessions matched {} peak {} peak connections {}",
+                self.sessions_matched, self.peak_sessions, self.peak_connections),
+            format!("simulated scans {} matched {}", self.scans, self.scans_matched),
+            format!("simulated memory rss {}kB peak {}kB session entry {}B",
+                self.rss_start_kb, self.rss_peak_kb, mem::size_of::<SessionEntry>()),
+        ]
+    }
+}
+
+enum Event
+{
+    Register,
+    Connect { client: IpAddr, phantom: IpAddr, left: u32 },
+    Packet { flow: Flow, phantom: IpAddr, end_ns: u64, left: u32 },
+    Scan,
+}
+
+struct Simulation<'a>
+{
+    config: &'a SimulationConfig,
+    rng: XorShiftRng,
+    ft: FlowTracker,
+    // Pending events by time, in order of scheduling for equal times.
+    queue: BinaryHeap<Reverse<(u64, u64)>>,
+    events: HashMap<u64, Event>,
+    scheduled: u64,
+    now_ns: u64,
+    next_sport: u16,
+    report: SimulationReport,
+}
+
+/// Runs the simulation `config` describes and reports on it.
+pub fn run(config: &SimulationConfig) -> SimulationReport {
+    let clock = Clock::simulated(0);
+    let seed = [config.seed, 0x9e37_79b9, 0x85eb_ca6b, 0xc2b2_ae35];
+    let mut sim = Simulation {
+        config: config,
+        rng: XorShiftRng::from_seed(seed),
+        ft: FlowTracker::without_threads(config.session.clone(), clock.clone()),
+        queue: BinaryHeap::new(),
+        events: HashMap::new(),
+        scheduled: 0,
+        now_ns: 0,
+        next_sport: 1024,
+        report: SimulationReport::default(),
+    };
+    sim.report.rss_start_kb = mem_used_kb();
+    sim.report.rss_peak_kb = sim.report.rss_start_kb;
+
+    sim.schedule_after(&config.registration_interarrival, Event::Register);
+    if let Some(ref scans) = config.scan_interarrival {
+        sim.schedule_after(scans, Event::Scan);
+    }
+    let end_ns = duration_ns(config.duration);
+    let step_ns = duration_ns(config.step).max(1);
+    let mut next_step_ns = step_ns;
+    while let Some(Reverse((at_ns, id))) = sim.queue.pop() {
+        if at_ns > end_ns {
+            break
+        }
+        while next_step_ns <= at_ns {
+            clock.set(next_step_ns);
+            sim.cleanup();
+            next_step_ns += step_ns;
+        }
+        clock.set(at_ns);
+        sim.now_ns = at_ns;
+        if let Some(event) = sim.events.remove(&id) {
+            sim.handle(event);
+        }
+    }
+    sim.report
+}
+
+impl<'a> Simulation<'a>
+{
+    fn schedule_after(&mut self, delay: &Distribution, event: Event) {
+        let at_ns = self.now_ns + duration_ns(delay.sample(&mut self.rng));
+        self.schedule_at(at_ns, event);
+    }
+
+    fn schedule_at(&mut self, at_ns: u64, event: Event) {
+        self.scheduled += 1;
+        self.events.insert(self.scheduled, event);
+        self.queue.push(Reverse((at_ns, self.scheduled)));
+    }
+
+    fn handle(&mut self, event: Event) {
+        let config = self.config;
+        match event {
+            Event::Register => {
+                self.register();
+                self.schedule_after(&config.registration_interarrival, Event::Register);
+            },
+            Event::Connect { client, phantom, left } => self.connect(client, phantom, left),
+            Event::Packet { flow, phantom, end_ns, left } => self.packet(flow, phantom, end_ns, left),
+            Event::Scan => {
+                self.scan();
+                if let Some(ref next) = config.scan_interarrival {
+                    self.schedule_after(next, Event::Scan);
+                }
+            },
+        }
+    }
+
+    fn register(&mut self) {
+        let client = pool_addr(192, 168, self.rng.gen_range(0, self.config.clients.max(1)));
+        let phantom = pool_addr(10, 10, self.rng.gen_range(0, self.config.phantoms.max(1)));
+        let reg = Registration {
+            client_ip: client.to_string(),
+            phantom_ip: phantom.to_string(),
+            phantom_port: self.config.phantom_port as u32,
+            timeout: Some(self.config.lifetime.sample(&mut self.rng)),
+            ..Registration::default()
+        };
+        self.report.registrations += 1;
+        match reg.sessions(WallClockNs(0)) {
+            Ok(sessions) => for sd in sessions {
+                self.ft.phantom_flows.add_session(sd);
+            },
+            Err(_) => self.report.rejected += 1,
+        }
+        let config = self.config;
+        if config.connections > 0 && self.rng.gen::<f64>() < config.connect_share {
+            self.schedule_after(&config.connect_delay, Event::Connect { client: client, phantom: phantom, left: config.connections });
+        }
+    }
+
+    // The client's SYN.
+    fn connect(&mut self, client: IpAddr, phantom: IpAddr, left: u32) {
+        self.next_sport = self.next_sport.checked_add(1).unwrap_or(1024);
+        let flow = Flow::from_parts(client, phantom, self.next_sport, self.config.phantom_port);
+        let dd_flow = FlowNoSrcPort::from_flow(&flow);
+        self.report.connections += 1;
+        let end_ns = self.now_ns + duration_ns(self.config.connection_duration.sample(&mut self.rng));
+        if !self.ft.is_phantom_session(&dd_flow) {
+            self.report.missed += 1;
+        } else if !self.ft.open_phantom_connection(&flow) {
+            self.report.limited += 1;
+        } else {
+            self.report.matched += 1;
+            if self.ft.latch_phantom_match(&dd_flow).is_some() {
+                self.report.sessions_matched += 1;
+            }
+            self.ft.update_phantom_flow(&flow);
+            self.ft.count_phantom_packet(&flow, SYN_BYTES);
+            let config = self.config;
+            self.schedule_after(&config.packet_interval, Event::Packet { flow: flow, phantom: phantom, end_ns: end_ns, left: left });
+            return
+        }
+        // the client gives up on this connection and tries its next one
+        self.next_connection(client, phantom, end_ns, left);
+    }
+
+    fn packet(&mut self, flow: Flow, phantom: IpAddr, end_ns: u64, left: u32) {
+        let matched = self.ft.is_phantom_session(&FlowNoSrcPort::from_flow(&flow));
+        if matched {
+            self.ft.touch_phantom_connection(&flow);
+            self.ft.update_phantom_flow(&flow);
+            self.ft.count_phantom_packet(&flow, PACKET_BYTES);
+        } else {
+            self.report.packets_orphaned += 1;
+        }
+        if self.now_ns < end_ns {
+            let config = self.config;
+            self.schedule_after(&config.packet_interval, Event::Packet { flow: flow, phantom: phantom, end_ns: end_ns, left: left });
+            return
+        }
+        if matched {
+            self.ft.fin_phantom_connection(&flow);
+        }
+        self.next_connection(flow.src_ip, phantom, self.now_ns, left);
+    }
+
+    fn next_connection(&mut self, client: IpAddr, phantom: IpAddr, at_ns: u64, left: u32) {
+        if left > 1 {
+            self.schedule_at(at_ns.max(self.now_ns), Event::Connect { client: client, phantom: phantom, left: left - 1 });
+        }
+    }
+
+    fn scan(&mut self) {
+        let scanner = pool_addr(172, 16, self.rng.gen_range(0, 1 << 16));
+        let phantom = pool_addr(10, 10, self.rng.gen_range(0, self.config.phantoms.max(1)));
+        let flow = Flow::from_parts(scanner, phantom, 40000, self.config.phantom_port);
+        self.report.scans += 1;
+        if self.ft.is_phantom_session(&FlowNoSrcPort::from_flow(&flow)) {
+            self.report.scans_matched += 1;
+        }
+    }
+
+    // As the periodic cleanup, sampling the peaks first.
+    fn cleanup(&mut self) {
+        self.report.peak_sessions = self.report.peak_sessions.max(self.ft.count_phantom_flows());
+        self.report.peak_connections = self.report.peak_connections.max(self.ft.count_phantom_connections());
+        self.report.rss_peak_kb = self.report.rss_peak_kb.max(mem_used_kb());
+        self.ft.drop_all_stale_flows();
+    }
+}
+
+// Address `index` of the /16 `a`.`b`.
+fn pool_addr(a: u8, b: u8, index: u32) -> IpAddr {
+    IpAddr::V4(Ipv4Addr::new(a, b, (index >> 8) as u8, index as u8))
+}
+
+#[cfg(test)]
+mod tests {
+    use simulation::*;
+
+    #[test]
+    fn test_simulation() {
+        let config = SimulationConfig {
+            duration: Duration::from_secs(300),
+            clients: 100,
+            phantoms: 50,
+            registration_interarrival: Distribution::Constant(1.0),
+            lifetime: Distribution::Constant(30.0),
+            connect_share: 1.0,
+            connect_delay: Distribution::Uniform(0.0, 10.0),
+            connection_duration: Distribution::Constant(20.0),
+            packet_interval: Distribution::Constant(5.0),
+            scan_interarrival: Some(Distribution::Exponential(0.5)),
+            ..SimulationConfig::default()
+        };
+        let report = run(&config);
+        assert_eq!(report.registrations, 300);
+        assert_eq!(report.rejected, 0);
+        // every client connects well within its registration's lifetime
+        assert!(report.connections >= 290);
+        assert_eq!(report.matched, report.connections);
+        assert_eq!(report.match_rate(), 1.0);
+        assert!(report.sessions_matched > 0 && report.sessions_matched <= report.matched);
+        assert_eq!(report.packets_orphaned, 0);
+        assert!(report.scans > 0);
+        assert_eq!(report.scans_matched, 0);
+        assert!(report.peak_sessions > 0);
+        assert_eq!(report.lines().len(), 5);
+        // same seed, same run
+        let again = run(&config);
+        assert_eq!((again.matched, again.scans, again.peak_sessions), (report.matched, report.scans, report.peak_sessions));
+
+        // clients too slow for their registration miss (and, with this many
+        // pairs, aren't saved by another registration of theirs)
+        let slow = SimulationConfig {
+            clients: 10000,
+            phantoms: 1000,
+            connect_delay: Distribution::Constant(60.0),
+            ..config
+        };
+        let report = run(&slow);
+        assert!(report.connections > 0);
+        assert_eq!(report.matched, 0);
+        assert_eq!(report.missed, report.connections);
+        assert_eq!(report.match_rate(), 0.0);
+    }
+}
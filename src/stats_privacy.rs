@@ -0,0 +1,178 @@
+//
+// Published Statistics Privacy
+//
+// Aggregate counts the detector publishes to the station (the never matched
+// summary, see feedback::never_matched_event) are bucketed by transport,
+// registration source and phantom subnet. A bucket of a handful of sessions
+// can single out the few clients behind it, so counts pass through a
+// StatsPrivacy before they leave the process:
+//
+// - With StatsPrivacyConfig.epsilon set, every count gets Laplace noise of
+//   scale 1/epsilon (a count changes by at most one per session), rounded
+//   and kept at zero or above.
+// - Buckets whose count, after noise, is under min_bucket are withheld and
+//   their sessions merged into a single "other" bucket, itself withheld if
+//   still under min_bucket.
+//
+// Counts are noised before the threshold, so whether a bucket is published
+// doesn't reveal its exact size. Reported per period: buckets withheld and
+// the sessions in them.
+
+use rand::{self, Rng, XorShiftRng};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StatsPrivacyConfig
+{
+    // Smallest count published for a bucket, 0 publishes every bucket.
+    pub min_bucket: u64,
+    // Privacy budget per published count, None adds no noise.
+    pub epsilon: Option<f64>,
+}
+
+impl Default for StatsPrivacyConfig
+{
+    fn default() -> StatsPrivacyConfig {
+        StatsPrivacyConfig { min_bucket: 0, epsilon: None }
+    }
+}
+
+impl StatsPrivacyConfig
+{
+    pub fn enabled(&self) -> bool {
+        self.min_bucket > 1 || self.epsilon.is_some()
+    }
+}
+
+pub struct StatsPrivacy
+{
+    config: StatsPrivacyConfig,
+    rng: XorShiftRng,
+    // Since the last take_report.
+    withheld_buckets: u64,
+    withheld_sessions: u64,
+}
+
+impl StatsPrivacy
+{
+    pub fn new(config: StatsPrivacyConfig) -> StatsPrivacy {
+        StatsPrivacy::with_rng(config, rand::weak_rng())
+    }
+
+    pub fn with_rng(config: StatsPrivacyConfig, rng: XorShiftRng) -> StatsPrivacy {
+        StatsPrivacy {
+            config: config,
+            rng: rng,
+            withheld_buckets: 0,
+            withheld_sessions: 0,
+        }
+    }
+
+    pub fn config(&self) -> &StatsPrivacyConfig {
+        &self.config
+    }
+
+    /// The `counts` that may be published, in their order, followed by the
+    /// `other` bucket holding the ones withheld if it is large enough.
+    pub fn bucket<K>(&mut self, counts: Vec<(K, u64)>, other: K) -> Vec<(K, u64)> {
+        if !self.config.enabled() {
+            return counts
+        }
+        let mut published = Vec::with_capacity(counts.len() + 1);
+        let mut merged = 0;
+        for (key, n) in counts.into_iter() {
+            let n = self.noise(n);
+            if n < self.config.min_bucket {
+                merged += n;
+                self.withheld_buckets += 1;
+            } else {
+                published.push((key, n));
+            }
+        }
+        if merged >= self.config.min_bucket && merged > 0 {
+            published.push((other, merged));
+        } else {
+            self.withheld_sessions += merged;
+        }
+        published
+    }
+
+    fn noise(&mut self, n: u64) -> u64 {
+        let epsilon = match self.config.epsilon {
+            Some(e) => e,
+            None => return n,
+        };
+        // Inverse CDF of Laplace(0, 1/epsilon), u uniform in (-0.5, 0.5).
+        let u = self.rng.gen::<f64>() - 0.5;
+        let x = -u.signum() * (1.0 - 2.0 * u.abs()).max(::std::f64::MIN_POSITIVE).ln() / epsilon;
+        (n as f64 + x).round().max(0.0) as u64
+    }
+
+    /// Buckets withheld and sessions left out of every published bucket
+    /// since the last call, formatted for the periodic report.
+    pub fn take_report(&mut self) -> String {
+        let report = format!("published stats buckets withheld {} sessions withheld {}",
+            self.withheld_buckets, self.withheld_sessions);
+        self.withheld_buckets = 0;
+        self.withheld_sessions = 0;
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use stats_privacy::*;
+
+    fn privacy(min_bucket: u64, epsilon: Option<f64>) -> StatsPrivacy {
+        let config = StatsPrivacyConfig { min_bucket: min_bucket, epsilon: epsilon };
+        StatsPrivacy::with_rng(config, XorShiftRng::from_seed([1, 2, 3, 4]))
+    }
+
+    #[test]
+    fn test_stats_privacy_min_bucket() {
+        let counts = vec![("a", 12), ("b", 3), ("c", 10), ("d", 4)];
+
+        // disabled: published as they are
+        let mut p = privacy(0, None);
+        assert_eq!(p.bucket(counts.clone(), "other"), counts);
+
+        let mut p = privacy(5, None);
+        assert_eq!(p.bucket(counts.clone(), "other"), vec![("a", 12), ("c", 10), ("other", 7)]);
+        assert_eq!(p.take_report(), "published stats buckets withheld 2 sessions withheld 0");
+
+        // the other bucket is withheld too when it's still small
+        let mut p = privacy(10, None);
+        assert_eq!(p.bucket(counts.clone(), "other"), vec![("a", 12), ("c", 10)]);
+        assert_eq!(p.take_report(), "published stats buckets withheld 2 sessions withheld 7");
+        assert_eq!(p.take_report(), "published stats buckets withheld 0 sessions withheld 0");
+
+        let mut p = privacy(100, None);
+        assert!(p.bucket(counts, "other").is_empty());
+    }
+
+    #[test]
+    fn test_stats_privacy_noise() {
+        let mut p = privacy(0, Some(0.5));
+        let rounds = 10000;
+        let mut total = 0;
+        let mut changed = 0;
+        for _ in 0..rounds {
+            let published = p.bucket(vec![((), 1000)], ());
+            assert_eq!(published.len(), 1);
+            let n = published[0].1;
+            total += n;
+            if n != 1000 {
+                changed += 1;
+            }
+        }
+        // scale 2: mostly within a few sessions, unbiased on average
+        let mean = total as f64 / rounds as f64;
+        assert!((mean - 1000.0).abs() < 0.5, "mean {}", mean);
+        assert!(changed > rounds / 2);
+
+        // a bucket at the threshold is sometimes withheld, sometimes not
+        let mut p = privacy(5, Some(0.5));
+        let published = (0..1000).filter(|_| !p.bucket(vec![((), 5)], ()).is_empty()).count();
+        assert!(published > 100 && published < 900, "published {}", published);
+    }
+}
@@ -0,0 +1,194 @@
+//
+// Ingest Supervisor
+//
+// The redis ingest thread is the only way new sessions reach a core. If it
+// panicked (e.g. on a poisoned lock), or its feed failed (redis restarted),
+// the core would keep serving the sessions it had and silently stop learning
+// new ones. The thread is instead run under a supervisor thread that waits
+// for it and, whenever it panics or returns an error, logs why, counts a
+// restart and starts a new one. A feed that closes normally (only test feeds
+// do) ends supervision.
+//
+// Restarts back off exponentially, from INITIAL_BACKOFF up to MAX_BACKOFF,
+// so a feed failing right away doesn't spin. A thread that ran for
+// HEALTHY_RUN before failing starts the backoff over. After FAILING_RESTARTS
+// restarts in a row without such a run the supervisor reports itself failing
+// (until the current thread has run for HEALTHY_RUN), which fails the
+// liveness probe (see probes.rs) so the orchestrator can replace the
+// detector.
+//
+// Restarts are reported per period, with the total and the last failure.
+
+use std::any::Any;
+use std::cmp;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use error::{DetectorResult, error_chain};
+use timekeeping::{self, MonotonicNs};
+
+pub const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+pub const MAX_BACKOFF: Duration = Duration::from_secs(30);
+pub const HEALTHY_RUN: Duration = Duration::from_secs(60);
+pub const FAILING_RESTARTS: u32 = 5;
+
+pub struct Supervisor
+{
+    name: &'static str,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+
+    // Restarts since the last report, and ever.
+    restarts: AtomicU64,
+    total_restarts: AtomicU64,
+    // Restarts since the last healthy run.
+    consecutive: AtomicU32,
+    // When the current thread was started.
+    run_started: AtomicU64,
+    last_failure: Mutex<Option<String>>,
+}
+
+// The message a thread panicked with.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        return s.to_string()
+    }
+    match payload.downcast_ref::<String>() {
+        Some(s) => s.clone(),
+        None => "unknown panic".to_string(),
+    }
+}
+
+impl Supervisor
+{
+    /// Supervisor of the thread named `name` in logs, reports and probes.
+    pub fn new(name: &'static str) -> Supervisor {
+        Supervisor::with_backoff(name, INITIAL_BACKOFF, MAX_BACKOFF)
+    }
+
+    pub fn with_backoff(name: &'static str, initial: Duration, max: Duration) -> Supervisor {
+        Supervisor {
+            name: name,
+            initial_backoff: initial,
+            max_backoff: max,
+            restarts: AtomicU64::new(0),
+            total_restarts: AtomicU64::new(0),
+            consecutive: AtomicU32::new(0),
+            run_started: AtomicU64::new(0),
+            last_failure: Mutex::new(None),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Starts a thread with `spawn`, and a new one each time it panics or
+    /// fails, until one returns Ok.
+    pub fn supervise<S>(&self, mut spawn: S)
+        where S: FnMut() -> JoinHandle<DetectorResult<()>>
+    {
+        let mut backoff = self.initial_backoff;
+        loop {
+            let started = timekeeping::now();
+            self.run_started.store(started.0, Ordering::Release);
+            let failure = match spawn().join() {
+                Ok(Ok(())) => return,
+                Ok(Err(e)) => format!("stopped: {}", error_chain(&e)),
+                Err(payload) => format!("panicked: {}", panic_message(&*payload)),
+            };
+            if timekeeping::now().saturating_since(started) >= HEALTHY_RUN.as_nanos() as u64 {
+                backoff = self.initial_backoff;
+                self.consecutive.store(0, Ordering::Release);
+            }
+            let n = self.consecutive.fetch_add(1, Ordering::AcqRel) + 1;
+            self.restarts.fetch_add(1, Ordering::Relaxed);
+            self.total_restarts.fetch_add(1, Ordering::Relaxed);
+            error!("{} {}, restarting in {}ms ({} in a row)", self.name, failure, backoff.as_millis(), n);
+            *self.last_failure.lock().unwrap_or_else(|e| e.into_inner()) = Some(failure);
+            thread::sleep(backoff);
+            backoff = cmp::min(backoff * 2, self.max_backoff);
+        }
+    }
+
+    /// Whether the thread keeps failing: it was restarted FAILING_RESTARTS
+    /// times in a row and the current one hasn't run for HEALTHY_RUN yet.
+    pub fn is_failing(&self, now: MonotonicNs) -> bool {
+        let started = MonotonicNs(self.run_started.load(Ordering::Acquire));
+        self.consecutive.load(Ordering::Acquire) >= FAILING_RESTARTS
+            && now.saturating_since(started) < HEALTHY_RUN.as_nanos() as u64
+    }
+
+    /// Ok, or why the supervised thread is considered failing.
+    pub fn status(&self, now: MonotonicNs) -> Result<String, String> {
+        let last = self.last_failure.lock().unwrap_or_else(|e| e.into_inner());
+        match *last {
+            Some(ref failure) if self.is_failing(now) => Err(format!("restarted {} times in a row, last {}",
+                self.consecutive.load(Ordering::Acquire), failure)),
+            _ => Ok(format!("{} restarts", self.total_restarts.load(Ordering::Relaxed))),
+        }
+    }
+
+    /// Restarts since the last call, in total, and the last failure.
+    pub fn take_report(&self) -> String {
+        let last = self.last_failure.lock().unwrap_or_else(|e| e.into_inner());
+        format!("{} restarts {} total {} in a row {} last {}", self.name,
+            self.restarts.swap(0, Ordering::Relaxed),
+            self.total_restarts.load(Ordering::Relaxed),
+            self.consecutive.load(Ordering::Acquire),
+            last.as_ref().map(|f| f.as_str()).unwrap_or("none"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use supervisor::*;
+    use std::io;
+    use error::DetectorError;
+
+    #[test]
+    fn test_supervisor() {
+        let supervisor = Supervisor::with_backoff("ingest supervisor", Duration::from_millis(1), Duration::from_millis(2));
+        assert_eq!(supervisor.status(timekeeping::now()), Ok("0 restarts".to_string()));
+
+        // panics, fails, then closes
+        let mut runs = 0;
+        supervisor.supervise(|| {
+            runs += 1;
+            let run = runs;
+            thread::spawn(move || match run {
+                1 => panic!("lock poisoned"),
+                2 => Err(DetectorError::Control {
+                    context: "receive",
+                    source: io::Error::new(io::ErrorKind::Other, "feed gone"),
+                }),
+                _ => Ok(()),
+            })
+        });
+        assert_eq!(runs, 3);
+        assert!(!supervisor.is_failing(timekeeping::now()));
+        assert_eq!(supervisor.take_report(), "ingest supervisor restarts 2 total 2 \
+            in a row 2 last stopped: control listener receive failed: feed gone");
+        assert_eq!(supervisor.take_report(), "ingest supervisor restarts 0 total 2 \
+            in a row 2 last stopped: control listener receive failed: feed gone");
+
+        // failing right away every time
+        let mut runs = 0;
+        supervisor.supervise(|| {
+            runs += 1;
+            let run = runs;
+            thread::spawn(move || match run {
+                1..=4 => panic!("run {}", run),
+                _ => Ok(()),
+            })
+        });
+        let now = timekeeping::now();
+        assert!(supervisor.is_failing(now));
+        assert_eq!(supervisor.status(now), Err("restarted 6 times in a row, last panicked: run 4".to_string()));
+        // until the thread has run for a while
+        let later = now.saturating_add(HEALTHY_RUN.as_nanos() as u64);
+        assert_eq!(supervisor.status(later), Ok("6 restarts".to_string()));
+    }
+}
@@ -0,0 +1,177 @@
+//
+// SYN Filter
+//
+// Every port 443 SYN that isn't for a tracked session is a registration
+// candidate (see process_packet.rs), and normally gets a flow entry in the
+// FlowTracker until its first app data or the SYN timeout. A SYN flood then
+// fills the flow table with flows that never progress past their SYN. With a
+// SynFilter the SYN only sets a few bits, and the FlowTracker allocates the
+// entry when a later packet of the flow (the client's ACK) shows the
+// handshake progressing and the filter remembers its SYN.
+//
+// - The filter is a Bloom filter in two generations of `bits` bits. SYNs are
+//   added to the current one and lookups check both; every window (the SYN
+//   timeout) the current generation becomes the previous one and the oldest
+//   is cleared, so a SYN is remembered for between one and two windows.
+// - Each flow sets HASHES bits, picked from a keyed hash of its addresses and
+//   ports. The key is drawn per filter, so flows can't be crafted to hit the
+//   bits of others.
+// - A SYN is noted with the sequence number that follows it, which the
+//   client's next packets (the handshake's ACK, then its first data) carry,
+//   and only a packet with that number promotes the flow. Once the flow's
+//   entry is dropped after its first app data, later packets of it are
+//   further on and don't promote it again.
+// - A false positive only gives a flow whose SYN wasn't seen an entry, as
+//   every candidate had before. Flows whose SYN is forgotten (or never seen,
+//   the capture starting mid-connection) aren't candidates.
+//
+// Reported per period: SYNs noted without an entry, flows promoted to one,
+// and how full the current generation is.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash, Hasher};
+
+use flow_tracker::Flow;
+use timekeeping::MonotonicNs;
+
+// Bits set per flow.
+const HASHES: u64 = 3;
+
+pub struct SynFilter
+{
+    generations: [Vec<u64>; 2],
+    // Index of the generation SYNs are added to.
+    current: usize,
+    bits: u64,
+    window_ns: u64,
+    rotated_at: MonotonicNs,
+    key: RandomState,
+    // Since the last take_report.
+    noted: u64,
+    promoted: u64,
+}
+
+impl SynFilter
+{
+    /// A filter of `bits` bits per generation, remembering SYNs for at least
+    /// `window_ns` from `now`.
+    pub fn new(bits: usize, window_ns: u64, now: MonotonicNs) -> SynFilter {
+        let words = (bits.max(1) + 63) / 64;
+        SynFilter {
+            generations: [vec![0; words], vec![0; words]],
+            current: 0,
+            bits: words as u64 * 64,
+            window_ns: window_ns,
+            rotated_at: now,
+            key: RandomState::new(),
+            noted: 0,
+            promoted: 0,
+        }
+    }
+
+    /// Remembers the SYN of `flow`, seen at `now`, followed by sequence
+    /// number `next_seq`.
+    pub fn insert(&mut self, flow: &Flow, next_seq: u32, now: MonotonicNs) {
+        self.rotate(now);
+        let (h1, h2) = self.hashes(flow, next_seq);
+        let generation = &mut self.generations[self.current];
+        for i in 0..HASHES {
+            let bit = h1.wrapping_add(i.wrapping_mul(h2)) % self.bits;
+            generation[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+        self.noted += 1;
+    }
+
+    /// Whether the SYN of `flow` was seen within the window, followed by
+    /// `seq`, counting it as promoted to a flow entry if so.
+    pub fn promote(&mut self, flow: &Flow, seq: u32, now: MonotonicNs) -> bool {
+        self.rotate(now);
+        let (h1, h2) = self.hashes(flow, seq);
+        let seen = self.generations.iter().any(|generation| {
+            (0..HASHES).all(|i| {
+                let bit = h1.wrapping_add(i.wrapping_mul(h2)) % self.bits;
+                generation[(bit / 64) as usize] & (1 << (bit % 64)) != 0
+            })
+        });
+        if seen {
+            self.promoted += 1;
+        }
+        seen
+    }
+
+    // Starts a new generation every window, clearing the oldest.
+    fn rotate(&mut self, now: MonotonicNs) {
+        let age = now.saturating_since(self.rotated_at);
+        if age < self.window_ns {
+            return
+        }
+        self.current = 1 - self.current;
+        clear(&mut self.generations[self.current]);
+        if age >= 2 * self.window_ns {
+            clear(&mut self.generations[1 - self.current]);
+        }
+        self.rotated_at = now;
+    }
+
+    // Two hashes of `flow` at `seq`, combined into the HASHES bit positions.
+    fn hashes(&self, flow: &Flow, seq: u32) -> (u64, u64) {
+        let mut hasher = self.key.build_hasher();
+        flow.hash(&mut hasher);
+        seq.hash(&mut hasher);
+        let hash = hasher.finish();
+        // an odd step so the positions differ
+        (hash, (hash >> 32) | 1)
+    }
+
+    /// SYNs noted and flows promoted since the last call, and the share of
+    /// the current generation's bits set, formatted for the periodic report.
+    pub fn take_report(&mut self) -> String {
+        let set: u64 = self.generations[self.current].iter().map(|w| w.count_ones() as u64).sum();
+        let report = format!("syn filter noted {} promoted {} fill {}%",
+            self.noted, self.promoted, set * 100 / self.bits);
+        self.noted = 0;
+        self.promoted = 0;
+        report
+    }
+}
+
+fn clear(generation: &mut [u64]) {
+    for w in generation.iter_mut() {
+        *w = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn_filter::*;
+
+    const S2NS: u64 = 1000*1000*1000;
+
+    fn flow(port: u16) -> Flow {
+        Flow::from_parts("10.22.0.1".parse().unwrap(), "128.138.97.6".parse().unwrap(), port, 443)
+    }
+
+    #[test]
+    fn test_syn_filter() {
+        let mut filter = SynFilter::new(1 << 16, 30*S2NS, MonotonicNs(0));
+        for port in 0..1000 {
+            filter.insert(&flow(port), 1, MonotonicNs(port as u64));
+        }
+        assert!((0..1000).all(|port| filter.promote(&flow(port), 1, MonotonicNs(S2NS))));
+        let false_positives = (1000..11000).filter(|&port| filter.promote(&flow(port), 1, MonotonicNs(S2NS))).count();
+        assert!(false_positives < 100, "false positives {}", false_positives);
+        assert_eq!(filter.take_report(), format!("syn filter noted 1000 promoted {} fill 4%", 1000 + false_positives));
+
+        // remembered into the next window, forgotten after it
+        assert!(filter.promote(&flow(1), 1, MonotonicNs(45*S2NS)));
+        filter.insert(&flow(20000), 1, MonotonicNs(45*S2NS));
+        assert!(!filter.promote(&flow(1), 1, MonotonicNs(76*S2NS)));
+        assert!(filter.promote(&flow(20000), 1, MonotonicNs(76*S2NS)));
+        assert!(!filter.promote(&flow(20000), 1, MonotonicNs(200*S2NS)));
+
+        // only at the sequence number following the SYN
+        filter.insert(&flow(30000), 1001, MonotonicNs(200*S2NS));
+        assert!(!filter.promote(&flow(30000), 1518, MonotonicNs(200*S2NS)));
+        assert!(filter.promote(&flow(30000), 1001, MonotonicNs(200*S2NS)));
+    }
+}
@@ -0,0 +1,298 @@
+//
+// Tenants
+//
+// One detector can serve the registrations of several logical stations
+// (tenants). Each registration names its tenant (StationToDetector tenant_id,
+// 0 or unset for the detector's own station) and with tenants configured
+// (SessionConfig.tenants, DETECTOR_TENANTS) its sessions are kept apart from
+// the other tenants':
+//
+// - Session keys of tenant N are prefixed "tN|", so two tenants registering
+//   the same client and phantom get a session each, extended, limited and
+//   removed independently. The default station's keys have no prefix. A
+//   packet is looked up under every configured tenant's prefix in turn, the
+//   default station's first, so each tenant adds a lookup per packet.
+// - Registrations for a tenant that isn't configured are refused.
+// - A tenant can be limited to a number of tracked sessions and to a number
+//   of registrations accepted per second (counted over one second windows).
+//   Registrations over either limit are refused, though one only refreshing
+//   sessions already tracked isn't held to the session limit. Stored
+//   registrations read while priming are only held to the session limit.
+// - The station can delete every session of a tenant with a
+//   StationToDetector carrying delete_tenant_sessions, on redis or the
+//   control address. Connections already forwarded for them drain as when a
+//   registration is removed. The default station's sessions can't be
+//   deleted this way.
+//
+// Sessions, accepted and refused registrations and deleted sessions are
+// reported per tenant once per report period.
+//
+// DETECTOR_TENANTS lists the tenants, comma separated, each an id optionally
+// followed by its limits: "7:max=10000:rate=50,9". Listing 0 limits the
+// default station.
+//
+// Without tenants configured the tenant ids of registrations are ignored and
+// every session is the default station's.
+
+use std::collections::BTreeMap;
+use std::mem;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use timekeeping::MonotonicNs;
+
+pub type TenantId = u32;
+
+/// The detector's own station.
+pub const DEFAULT_TENANT: TenantId = 0;
+
+const WINDOW_NS: u64 = 1000*1000*1000;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TenantLimits
+{
+    // Sessions tracked at once. None is unlimited.
+    pub max_sessions: Option<usize>,
+    // Registrations accepted per second. None is unlimited.
+    pub rate: Option<u32>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TenantConfig
+{
+    // Tenants registrations are accepted for (besides the default station)
+    // and their limits.
+    pub tenants: BTreeMap<TenantId, TenantLimits>,
+}
+
+impl FromStr for TenantConfig
+{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<TenantConfig, String> {
+        let mut config = TenantConfig::default();
+        for spec in s.split(',').map(|t| t.trim()).filter(|t| !t.is_empty()) {
+            let mut parts = spec.split(':');
+            let id: TenantId = parts.next().unwrap_or("").parse()
+                .map_err(|_| format!("bad tenant id in {:?}", spec))?;
+            let mut limits = TenantLimits::default();
+            for limit in parts {
+                let (name, value) = match limit.find('=') {
+                    Some(i) => (&limit[..i], &limit[i+1..]),
+                    None => return Err(format!("expected name=value, got {:?}", limit)),
+                };
+                match name {
+                    "max" => limits.max_sessions = Some(value.parse()
+                        .map_err(|_| format!("bad session limit {:?}", value))?),
+                    "rate" => limits.rate = Some(value.parse()
+                        .map_err(|_| format!("bad rate limit {:?}", value))?),
+                    _ => return Err(format!("unknown tenant limit {:?}", name)),
+                }
+            }
+            if config.tenants.insert(id, limits).is_some() {
+                return Err(format!("tenant {} listed twice", id))
+            }
+        }
+        Ok(config)
+    }
+}
+
+impl TenantConfig
+{
+    /// Whether registrations for `tenant` are accepted.
+    pub fn accepts(&self, tenant: TenantId) -> bool {
+        tenant == DEFAULT_TENANT || self.tenants.contains_key(&tenant)
+    }
+
+    /// Tenants other than the default station, in order.
+    pub fn ids(&self) -> impl Iterator<Item = TenantId> + '_ {
+        self.tenants.keys().cloned().filter(|t| *t != DEFAULT_TENANT)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Refusal
+{
+    UnknownTenant,
+    SessionLimit(usize),
+    RateLimit(u32),
+}
+
+impl Refusal
+{
+    pub fn reason(&self, tenant: TenantId) -> String {
+        match *self {
+            Refusal::UnknownTenant => format!("unknown tenant {}", tenant),
+            Refusal::SessionLimit(max) => format!("tenant {} at its limit of {} sessions", tenant, max),
+            Refusal::RateLimit(rate) => format!("tenant {} over its limit of {} registrations per second", tenant, rate),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TenantCounts
+{
+    // Since the last report.
+    pub accepted: u64,
+    pub over_session_limit: u64,
+    pub over_rate_limit: u64,
+    pub deleted: u64,
+}
+
+#[derive(Default)]
+struct TenantState
+{
+    counts: TenantCounts,
+    // Start of the current rate window, and registrations accepted in it.
+    window: MonotonicNs,
+    in_window: u32,
+}
+
+pub struct Tenants
+{
+    config: TenantConfig,
+    state: Mutex<BTreeMap<TenantId, TenantState>>,
+    // Registrations for tenants not configured, since the last report.
+    unknown: Mutex<u64>,
+}
+
+impl Tenants
+{
+    pub fn new(config: TenantConfig) -> Tenants {
+        Tenants {
+            config: config,
+            state: Mutex::new(BTreeMap::new()),
+            unknown: Mutex::new(0),
+        }
+    }
+
+    pub fn config(&self) -> &TenantConfig {
+        &self.config
+    }
+
+    /// Admits a registration for `tenant` at `now` as far as the tenant and
+    /// its rate go, or says why it is refused. Priming registrations aren't
+    /// held to the rate. The session limit is checked by admit_sessions, when
+    /// the registration is inserted.
+    pub fn admit(&self, tenant: TenantId, now: MonotonicNs, priming: bool) -> Result<(), Refusal> {
+        if !self.config.accepts(tenant) {
+            *self.unknown.lock().unwrap_or_else(|e| e.into_inner()) += 1;
+            return Err(Refusal::UnknownTenant)
+        }
+        let limits = self.config.tenants.get(&tenant).cloned().unwrap_or_default();
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let st = state.entry(tenant).or_default();
+        if let (Some(rate), false) = (limits.rate, priming) {
+            if now.saturating_since(st.window) >= WINDOW_NS {
+                st.window = now;
+                st.in_window = 0;
+            }
+            if st.in_window >= rate {
+                st.counts.over_rate_limit += 1;
+                return Err(Refusal::RateLimit(rate))
+            }
+            st.in_window += 1;
+        }
+        Ok(())
+    }
+
+    /// Admits a registration admit let through adding `new_sessions` to the
+    /// `sessions` its tenant has tracked, or refuses it for the session
+    /// limit. Only new sessions count, one refreshing sessions already
+    /// tracked is admitted however many there are. Called with the session
+    /// map locked, so concurrent registrations can't take a tenant past it.
+    pub fn admit_sessions(&self, tenant: TenantId, sessions: usize, new_sessions: usize) -> Result<(), Refusal> {
+        let limits = self.config.tenants.get(&tenant).cloned().unwrap_or_default();
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let st = state.entry(tenant).or_default();
+        if let Some(max) = limits.max_sessions {
+            if new_sessions > 0 && sessions + new_sessions > max {
+                st.counts.over_session_limit += 1;
+                return Err(Refusal::SessionLimit(max))
+            }
+        }
+        st.counts.accepted += 1;
+        Ok(())
+    }
+
+    /// Counts `n` sessions of `tenant` deleted by the station.
+    pub fn count_deleted(&self, tenant: TenantId, n: usize) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.entry(tenant).or_default().counts.deleted += n as u64;
+    }
+
+    /// Counts per tenant (the default station and every configured one)
+    /// since the last call, and the registrations refused for unknown
+    /// tenants.
+    pub fn take_counts(&self) -> (Vec<(TenantId, TenantCounts)>, u64) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let ids = Some(DEFAULT_TENANT).into_iter().chain(self.config.ids());
+        let counts = ids.map(|t| {
+            let counts = state.get_mut(&t).map(|st| mem::take(&mut st.counts)).unwrap_or_default();
+            (t, counts)
+        }).collect();
+        let unknown = mem::replace(&mut *self.unknown.lock().unwrap_or_else(|e| e.into_inner()), 0);
+        (counts, unknown)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tenants::*;
+
+    const S: u64 = 1000*1000*1000;
+
+    #[test]
+    fn test_tenant_config() {
+        let config: TenantConfig = "7:max=100:rate=5, 9,0:max=3".parse().unwrap();
+        assert_eq!(config.tenants[&7], TenantLimits { max_sessions: Some(100), rate: Some(5) });
+        assert_eq!(config.tenants[&9], TenantLimits::default());
+        assert_eq!(config.tenants[&0].max_sessions, Some(3));
+        assert_eq!(config.ids().collect::<Vec<_>>(), vec![7, 9]);
+        assert!(config.accepts(0) && config.accepts(9) && !config.accepts(8));
+        assert_eq!("".parse::<TenantConfig>(), Ok(TenantConfig::default()));
+
+        assert!("x".parse::<TenantConfig>().is_err());
+        assert!("7:max".parse::<TenantConfig>().is_err());
+        assert!("7:burst=3".parse::<TenantConfig>().is_err());
+        assert!("7,7".parse::<TenantConfig>().is_err());
+    }
+
+    #[test]
+    fn test_tenant_limits() {
+        let tenants = Tenants::new("7:max=10:rate=2,9".parse().unwrap());
+        assert_eq!(tenants.admit(8, MonotonicNs(S), false), Err(Refusal::UnknownTenant));
+        assert_eq!(tenants.admit(9, MonotonicNs(S), false), Ok(()));
+        assert_eq!(tenants.admit_sessions(9, 1000, 1), Ok(()));
+        assert_eq!(tenants.admit(0, MonotonicNs(S), false), Ok(()));
+        assert_eq!(tenants.admit_sessions(0, 1000, 1), Ok(()));
+
+        // two a second
+        assert_eq!(tenants.admit(7, MonotonicNs(S), false), Ok(()));
+        assert_eq!(tenants.admit(7, MonotonicNs(S + 1), false), Ok(()));
+        assert_eq!(tenants.admit(7, MonotonicNs(S + 2), false), Err(Refusal::RateLimit(2)));
+        // except while priming
+        assert_eq!(tenants.admit(7, MonotonicNs(S + 3), true), Ok(()));
+        assert_eq!(tenants.admit(7, MonotonicNs(2*S), false), Ok(()));
+        for sessions in 0..4 {
+            assert_eq!(tenants.admit_sessions(7, sessions, 1), Ok(()));
+        }
+        // ten at a time, though sessions already tracked can be refreshed
+        assert_eq!(tenants.admit_sessions(7, 10, 1), Err(Refusal::SessionLimit(10)));
+        assert_eq!(tenants.admit_sessions(7, 9, 2), Err(Refusal::SessionLimit(10)));
+        assert_eq!(tenants.admit_sessions(7, 10, 0), Ok(()));
+        assert_eq!(Refusal::SessionLimit(10).reason(7), "tenant 7 at its limit of 10 sessions");
+        tenants.count_deleted(7, 4);
+
+        let (counts, unknown) = tenants.take_counts();
+        assert_eq!(unknown, 1);
+        assert_eq!(counts, vec![
+            (0, TenantCounts { accepted: 1, ..TenantCounts::default() }),
+            (7, TenantCounts { accepted: 5, over_session_limit: 2, over_rate_limit: 1, deleted: 4 }),
+            (9, TenantCounts { accepted: 1, ..TenantCounts::default() }),
+        ]);
+        let (counts, unknown) = tenants.take_counts();
+        assert_eq!(unknown, 0);
+        assert!(counts.iter().all(|c| c.1 == TenantCounts::default()));
+    }
+}
@@ -0,0 +1,206 @@
+//
+// Timekeeping
+//
+// All timeouts and expiry times in the detector (tracked flows, phantom
+// sessions, stats periods) are nanosecond timestamps taken from a Clock. By
+// default this is a monotonic clock counting from the first time any clock in
+// the process is read, so every component in a process agrees on "now".
+//
+// A simulated clock only moves when told to. It is shared by cloning, so a
+// replay or test harness can hand the same clock to several trackers and step
+// them through time deterministically.
+//
+// Clock times are MonotonicNs and only mean something inside the process that
+// read them. Times exchanged with other hosts (station timestamps, absolute
+// expirations) are unix times, WallClockNs, which can jump when the system
+// clock is set. The two are never compared directly: a wall clock time is
+// turned into a duration from the current wall clock time (or converted with
+// to_monotonic) at the point it is received, and a clock time is sent as a
+// duration from now (or converted with to_wall_clock).
+//
+// Lifetimes and limits taken by public APIs are std::time::Duration, turned
+// into ns with duration_ns where they meet clock times.
+
+use std::cmp;
+use std::sync::{Arc, Once};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+static START_INIT: Once = Once::new();
+static mut START: Option<Instant> = None;
+
+/// A time on a Clock, in ns.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MonotonicNs(pub u64);
+
+impl MonotonicNs
+{
+    pub fn saturating_add(self, ns: u64) -> MonotonicNs {
+        MonotonicNs(self.0.saturating_add(ns))
+    }
+
+    pub fn saturating_sub(self, ns: u64) -> MonotonicNs {
+        MonotonicNs(self.0.saturating_sub(ns))
+    }
+
+    /// Nanoseconds from `earlier` to this time, 0 if `earlier` is later.
+    pub fn saturating_since(self, earlier: MonotonicNs) -> u64 {
+        self.0.saturating_sub(earlier.0)
+    }
+
+    /// The wall clock time this is, given both clocks read at the same moment.
+    pub fn to_wall_clock(self, now: MonotonicNs, wall_now: WallClockNs) -> WallClockNs {
+        if self >= now {
+            wall_now.saturating_add(self.saturating_since(now))
+        } else {
+            WallClockNs(wall_now.0.saturating_sub(now.saturating_since(self)))
+        }
+    }
+}
+
+/// A unix time, in ns.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WallClockNs(pub u64);
+
+impl WallClockNs
+{
+    /// The system clock. Only for comparing against timestamps from other
+    /// hosts; use a Clock for timeouts.
+    pub fn now() -> WallClockNs {
+        match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(d) => WallClockNs(d.as_secs() * 1_000_000_000 + d.subsec_nanos() as u64),
+            Err(_) => WallClockNs(0),
+        }
+    }
+
+    pub fn saturating_add(self, ns: u64) -> WallClockNs {
+        WallClockNs(self.0.saturating_add(ns))
+    }
+
+    /// Nanoseconds from `earlier` to this time, 0 if `earlier` is later.
+    pub fn saturating_since(self, earlier: WallClockNs) -> u64 {
+        self.0.saturating_sub(earlier.0)
+    }
+
+    /// Nanoseconds between the two times, in either order.
+    pub fn distance(self, other: WallClockNs) -> u64 {
+        cmp::max(self, other).saturating_since(cmp::min(self, other))
+    }
+
+    /// The clock time this is, given both clocks read at the same moment.
+    pub fn to_monotonic(self, wall_now: WallClockNs, now: MonotonicNs) -> MonotonicNs {
+        if self >= wall_now {
+            now.saturating_add(self.saturating_since(wall_now))
+        } else {
+            now.saturating_sub(wall_now.saturating_since(self))
+        }
+    }
+}
+
+/// `d` in ns, saturating at u64::MAX (some 584 years).
+pub fn duration_ns(d: Duration) -> u64 {
+    cmp::min(d.as_nanos(), u64::MAX as u128) as u64
+}
+
+/// Current time on the process-wide monotonic clock.
+pub fn now() -> MonotonicNs {
+    unsafe {
+        START_INIT.call_once(|| { START = Some(Instant::now()); });
+        let elapsed = START.expect("timekeeping start not set").elapsed();
+        MonotonicNs(elapsed.as_secs() * 1_000_000_000 + elapsed.subsec_nanos() as u64)
+    }
+}
+
+#[derive(Clone)]
+pub enum Clock
+{
+    // The process-wide monotonic clock (see now).
+    Monotonic,
+    // Current time in ns, only changed by set/advance.
+    Simulated(Arc<AtomicU64>),
+}
+
+impl Clock
+{
+    pub fn simulated(start_ns: u64) -> Clock {
+        Clock::Simulated(Arc::new(AtomicU64::new(start_ns)))
+    }
+
+    pub fn now(&self) -> MonotonicNs {
+        match self {
+            Clock::Monotonic => now(),
+            Clock::Simulated(t) => MonotonicNs(t.load(Ordering::Acquire)),
+        }
+    }
+
+    /// Moves a simulated clock forward. No effect on the monotonic clock.
+    pub fn advance(&self, ns: u64) {
+        if let Clock::Simulated(t) = self {
+            t.fetch_add(ns, Ordering::AcqRel);
+        }
+    }
+
+    /// Sets a simulated clock. No effect on the monotonic clock.
+    pub fn set(&self, ns: u64) {
+        if let Clock::Simulated(t) = self {
+            t.store(ns, Ordering::Release);
+        }
+    }
+}
+
+impl Default for Clock
+{
+    fn default() -> Clock {
+        Clock::Monotonic
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use timekeeping::*;
+    use std::{thread, time};
+
+    #[test]
+    fn test_monotonic_clock() {
+        let c = Clock::Monotonic;
+        let a = c.now();
+        thread::sleep(time::Duration::from_millis(10));
+        let b = c.now();
+        assert!(b.saturating_since(a) >= 10*1000*1000);
+
+        // set/advance only apply to simulated clocks
+        c.advance(1000*1000*1000*1000);
+        assert!(c.now() < b.saturating_add(1000*1000*1000*1000));
+    }
+
+    #[test]
+    fn test_simulated_clock() {
+        let c = Clock::simulated(100);
+        let shared = c.clone();
+        assert_eq!(c.now(), MonotonicNs(100));
+
+        shared.advance(50);
+        assert_eq!(c.now(), MonotonicNs(150));
+
+        c.set(10);
+        assert_eq!(shared.now(), MonotonicNs(10));
+    }
+
+    #[test]
+    fn test_clock_conversion() {
+        let now = MonotonicNs(1000);
+        let wall_now = WallClockNs(1_600_000_000_000);
+
+        assert_eq!(WallClockNs(1_600_000_000_500).to_monotonic(wall_now, now), MonotonicNs(1500));
+        assert_eq!(WallClockNs(1_599_999_999_900).to_monotonic(wall_now, now), MonotonicNs(900));
+        // before the monotonic clock started
+        assert_eq!(WallClockNs(1_000).to_monotonic(wall_now, now), MonotonicNs(0));
+
+        assert_eq!(MonotonicNs(1500).to_wall_clock(now, wall_now), WallClockNs(1_600_000_000_500));
+        assert_eq!(MonotonicNs(900).to_wall_clock(now, wall_now), WallClockNs(1_599_999_999_900));
+
+        assert_eq!(WallClockNs(10).distance(WallClockNs(4)), 6);
+        assert_eq!(WallClockNs(4).distance(WallClockNs(10)), 6);
+        assert_eq!(MonotonicNs(4).saturating_since(MonotonicNs(10)), 0);
+    }
+}
@@ -0,0 +1,310 @@
+//
+// TLS ClientHello Fingerprints
+//
+// For transport debugging and censor evasion research the detector can
+// fingerprint the TLS ClientHello each matched connection opens with
+// (DETECTOR_TLS_FINGERPRINTS). Only the first payload of connections to
+// registered phantoms is looked at, never unmatched traffic, and only a
+// ClientHello that fits in it (the common case, a single segment) is
+// fingerprinted. The latest fingerprint is kept in the session's SessionEntry
+// and sampled QA records carry the one of their first connection.
+//
+// Fingerprints follow JA4 (https://github.com/FoxIO-LLC/ja4), for example
+// "t13d1516h2_8daaf6152771_e5627efa2ab1":
+//
+//  - "t" for TLS over TCP, the TLS version (the highest in supported_versions
+//    if the client sends it, the ClientHello version otherwise), "d" if it
+//    names a server (SNI) or "i" if not, the number of cipher suites and of
+//    extensions (2 digits, at most 99) and the first and last characters of
+//    the first ALPN protocol ("00" without ALPN; its first and last hex
+//    digits if either isn't alphanumeric);
+//  - the first 12 hex digits of the SHA256 of the cipher suites, as 4 hex
+//    digits each, sorted and comma separated;
+//  - the same of the extensions, sorted, without SNI and ALPN, followed by
+//    "_" and the signature algorithms in the order the client sent them.
+//
+// GREASE values (RFC 8701) are left out throughout. Hashes of empty lists are
+// all zero.
+
+use std::fmt;
+
+use hex;
+use sha2::{Digest, Sha256};
+
+const TLS_TYPE_HANDSHAKE: u8 = 0x16;
+const HANDSHAKE_CLIENT_HELLO: u8 = 0x01;
+
+const EXT_SERVER_NAME: u16 = 0x0000;
+const EXT_SIGNATURE_ALGORITHMS: u16 = 0x000d;
+const EXT_ALPN: u16 = 0x0010;
+const EXT_SUPPORTED_VERSIONS: u16 = 0x002b;
+
+/// Length of a fingerprint, "t13d1516h2_8daaf6152771_e5627efa2ab1".
+pub const FINGERPRINT_LEN: usize = 36;
+
+/// JA4 style fingerprint of a ClientHello, see above.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TlsFingerprint([u8; FINGERPRINT_LEN]);
+
+impl TlsFingerprint
+{
+    pub fn as_str(&self) -> &str {
+        // only ever built from ASCII
+        ::std::str::from_utf8(&self.0).unwrap_or("")
+    }
+}
+
+impl fmt::Display for TlsFingerprint
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl fmt::Debug for TlsFingerprint
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "TlsFingerprint({})", self.as_str())
+    }
+}
+
+// Reads the fields of a ClientHello front to back.
+struct Reader<'a>
+{
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a>
+{
+    fn bytes(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.buf.len() < n {
+            return None
+        }
+        let (head, rest) = self.buf.split_at(n);
+        self.buf = rest;
+        Some(head)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.bytes(1).map(|b| b[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.bytes(2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u24(&mut self) -> Option<usize> {
+        self.bytes(3).map(|b| (b[0] as usize) << 16 | (b[1] as usize) << 8 | b[2] as usize)
+    }
+
+    // A field with a length prefix of `len_bytes` (1 or 2) bytes.
+    fn vec(&mut self, len_bytes: usize) -> Option<Reader<'a>> {
+        let len = match len_bytes {
+            1 => self.u8()? as usize,
+            _ => self.u16()? as usize,
+        };
+        self.bytes(len).map(|buf| Reader { buf: buf })
+    }
+
+    // The u16s of the rest of the buffer.
+    fn u16s(&mut self) -> Vec<u16> {
+        let mut values = Vec::with_capacity(self.buf.len() / 2);
+        while let Some(v) = self.u16() {
+            values.push(v);
+        }
+        values
+    }
+}
+
+fn is_grease(v: u16) -> bool {
+    v & 0x0f0f == 0x0a0a && v >> 8 == v & 0xff
+}
+
+fn version_label(version: u16) -> &'static str {
+    match version {
+        0x0304 => "13",
+        0x0303 => "12",
+        0x0302 => "11",
+        0x0301 => "10",
+        0x0300 => "s3",
+        _ => "00",
+    }
+}
+
+// First and last characters of the first ALPN protocol.
+fn alpn_label(protocol: &[u8]) -> [u8; 2] {
+    let (first, last) = match (protocol.first(), protocol.last()) {
+        (Some(&f), Some(&l)) => (f, l),
+        _ => return *b"00",
+    };
+    if first.is_ascii_alphanumeric() && last.is_ascii_alphanumeric() {
+        return [first, last]
+    }
+    let digits = b"0123456789abcdef";
+    [digits[(first >> 4) as usize], digits[(last & 0x0f) as usize]]
+}
+
+// First 12 hex digits of the SHA256 of `values` (4 hex digits each, comma
+// separated), followed by `_` and `suffix` if there is one.
+fn hash12(values: &[u16], suffix: &[u16]) -> String {
+    if values.is_empty() {
+        return "000000000000".to_string()
+    }
+    let join = |vs: &[u16]| vs.iter().map(|v| format!("{:04x}", v)).collect::<Vec<String>>().join(",");
+    let mut input = join(values);
+    if !suffix.is_empty() {
+        input.push('_');
+        input.push_str(&join(suffix));
+    }
+    let digest = Sha256::digest(input.as_bytes());
+    hex::encode(&digest[..6])
+}
+
+/// Fingerprint of the ClientHello `payload` starts with, None if it doesn't
+/// start with a complete one.
+pub fn fingerprint(payload: &[u8]) -> Option<TlsFingerprint> {
+    let mut record = Reader { buf: payload };
+    if record.u8()? != TLS_TYPE_HANDSHAKE {
+        return None
+    }
+    record.u16()?;
+    let mut handshake = record.vec(2)?;
+    if handshake.u8()? != HANDSHAKE_CLIENT_HELLO {
+        return None
+    }
+    let len = handshake.u24()?;
+    let mut hello = Reader { buf: handshake.bytes(len)? };
+
+    let mut version = hello.u16()?;
+    hello.bytes(32)?;
+    hello.vec(1)?;
+    let mut ciphers: Vec<u16> = hello.vec(2)?.u16s().into_iter().filter(|c| !is_grease(*c)).collect();
+    hello.vec(1)?;
+
+    let mut extensions = Vec::new();
+    let mut sni = false;
+    let mut alpn = *b"00";
+    let mut signature_algorithms = Vec::new();
+    // extensions are optional in TLS 1.2
+    if let Some(mut exts) = hello.vec(2) {
+        while !exts.buf.is_empty() {
+            let ext = exts.u16()?;
+            let mut data = exts.vec(2)?;
+            if is_grease(ext) {
+                continue
+            }
+            extensions.push(ext);
+            match ext {
+                EXT_SERVER_NAME => sni = true,
+                EXT_ALPN => {
+                    if let Some(first) = data.vec(2).and_then(|mut list| list.vec(1)) {
+                        alpn = alpn_label(first.buf);
+                    }
+                },
+                EXT_SIGNATURE_ALGORITHMS => {
+                    if let Some(mut algs) = data.vec(2) {
+                        signature_algorithms = algs.u16s().into_iter().filter(|a| !is_grease(*a)).collect();
+                    }
+                },
+                EXT_SUPPORTED_VERSIONS => {
+                    if let Some(mut versions) = data.vec(1) {
+                        if let Some(v) = versions.u16s().into_iter().filter(|v| !is_grease(*v)).max() {
+                            version = v;
+                        }
+                    }
+                },
+                _ => {},
+            }
+        }
+    }
+
+    let count = |n: usize| format!("{:02}", n.min(99));
+    let mut fp = String::with_capacity(FINGERPRINT_LEN);
+    fp.push('t');
+    fp.push_str(version_label(version));
+    fp.push(if sni { 'd' } else { 'i' });
+    fp.push_str(&count(ciphers.len()));
+    fp.push_str(&count(extensions.len()));
+    fp.push(alpn[0] as char);
+    fp.push(alpn[1] as char);
+    fp.push('_');
+    ciphers.sort();
+    fp.push_str(&hash12(&ciphers, &[]));
+    fp.push('_');
+    extensions.retain(|e| *e != EXT_SERVER_NAME && *e != EXT_ALPN);
+    extensions.sort();
+    fp.push_str(&hash12(&extensions, &signature_algorithms));
+
+    let mut bytes = [0; FINGERPRINT_LEN];
+    if fp.len() != FINGERPRINT_LEN {
+        return None
+    }
+    bytes.copy_from_slice(fp.as_bytes());
+    Some(TlsFingerprint(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use tls_fingerprint::*;
+
+    // A ClientHello with `ciphers` and `extensions` (type, data).
+    fn client_hello(ciphers: &[u16], extensions: &[(u16, Vec<u8>)]) -> Vec<u8> {
+        let mut hello = vec![0x03, 0x03];
+        hello.extend_from_slice(&[0; 32]);
+        hello.push(0);
+        hello.extend_from_slice(&((ciphers.len() * 2) as u16).to_be_bytes());
+        for c in ciphers {
+            hello.extend_from_slice(&c.to_be_bytes());
+        }
+        hello.extend_from_slice(&[1, 0]);
+        let mut exts = Vec::new();
+        for &(t, ref data) in extensions {
+            exts.extend_from_slice(&t.to_be_bytes());
+            exts.extend_from_slice(&(data.len() as u16).to_be_bytes());
+            exts.extend_from_slice(data);
+        }
+        hello.extend_from_slice(&(exts.len() as u16).to_be_bytes());
+        hello.extend(exts);
+
+        let mut handshake = vec![HANDSHAKE_CLIENT_HELLO, 0, (hello.len() >> 8) as u8, hello.len() as u8];
+        handshake.extend(hello);
+        let mut record = vec![TLS_TYPE_HANDSHAKE, 0x03, 0x01];
+        record.extend_from_slice(&(handshake.len() as u16).to_be_bytes());
+        record.extend(handshake);
+        record
+    }
+
+    #[test]
+    fn test_tls_fingerprint() {
+        let sni = (EXT_SERVER_NAME, vec![0, 6, 0, 0, 3, b'a', b'.', b'b']);
+        let alpn = (EXT_ALPN, vec![0, 6, 2, b'h', b'2', 2, b'h', b'3']);
+        let versions = (EXT_SUPPORTED_VERSIONS, vec![4, 0x3a, 0x3a, 0x03, 0x04]);
+        let sig_algs = (EXT_SIGNATURE_ALGORITHMS, vec![0, 4, 0x08, 0x04, 0x04, 0x03]);
+        let grease = (0x1a1a, vec![]);
+        let hello = client_hello(&[0x0a0a, 0x1302, 0x1301, 0xc02b],
+                                 &[grease.clone(), sni.clone(), alpn, versions, sig_algs.clone()]);
+
+        let fp = fingerprint(&hello).unwrap();
+        assert_eq!(fp.as_str().len(), FINGERPRINT_LEN);
+        assert!(fp.to_string().starts_with("t13d0304h2_"), "{}", fp);
+        // the hashes are over the sorted values, grease left out
+        assert_eq!(&fp.as_str()[11..23], &hash12(&[0x1301, 0x1302, 0xc02b], &[])[..]);
+        assert_eq!(&fp.as_str()[24..], &hash12(&[EXT_SIGNATURE_ALGORITHMS, EXT_SUPPORTED_VERSIONS], &[0x0804, 0x0403])[..]);
+        assert_eq!(hash12(&[0x1301], &[]), "0f2cb44170f4");
+        // the order the client sends them in doesn't matter
+        let reordered = client_hello(&[0xc02b, 0x1301, 0x1302], &[sig_algs.clone(), sni.clone(),
+            (EXT_ALPN, vec![0, 3, 2, b'h', b'2']), (EXT_SUPPORTED_VERSIONS, vec![2, 0x03, 0x04])]);
+        assert_eq!(fingerprint(&reordered), Some(fp));
+
+        // TLS 1.2 without SNI, an ALPN protocol that isn't alphanumeric, and
+        // nothing to hash
+        let hello = client_hello(&[], &[(EXT_ALPN, vec![0, 3, 2, 0xab, b'-'])]);
+        assert_eq!(fingerprint(&hello).unwrap().as_str(), "t12i0001ad_000000000000_000000000000");
+
+        // not a (complete) ClientHello
+        let full = client_hello(&[0x1301], &[sni]);
+        assert!(fingerprint(&full[..full.len() - 1]).is_none());
+        assert!(fingerprint(&[0x17, 0x03, 0x03, 0, 0]).is_none());
+        assert!(fingerprint(b"GET / HTTP/1.1\r\n").is_none());
+    }
+}
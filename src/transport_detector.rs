@@ -0,0 +1,224 @@
+//
+// Transport Detection
+//
+// Some transports can't be told apart by the registration alone: the prefix
+// transport has the client open its connection with one of a set of known
+// byte strings, and the detector should check that it does before trusting
+// the connection. A TransportDetector is shown the first payload each
+// connection of a tracked session sends (its first TCP segment with data, or
+// its first UDP datagram) along with the session, and returns a Verdict:
+//
+//  - Pass: not its transport, or it can't tell. The next detector is asked;
+//    if none has a verdict the connection is forwarded as before.
+//  - Confirm: the connection is the detector's transport. The session is
+//    marked confirmed (SessionEntry.confirmed).
+//  - Annotate: the connection is forwarded and the session labelled with a
+//    tag of the detector's choosing (SessionEntry.annotation), the latest one
+//    kept.
+//  - Reject: the connection claims the session but isn't its transport. It
+//    is not forwarded, this packet nor any later one, and is closed as usual
+//    on RST, FIN or going idle.
+//  - NeedMore: the payload is too short to tell. The packet is forwarded and
+//    the detectors are shown the connection's payload so far again with its
+//    next segment (in order, for TCP). A connection still short of a verdict
+//    after its first flight (FIRST_FLIGHT_SEGMENTS) is rejected, so splitting
+//    the first write into small segments doesn't get past a detector.
+//
+// Detectors are asked in the order they were added, until one has a verdict.
+// Each connection is inspected once it has a verdict, later packets aren't
+// looked at even if every detector passed, so detectors run only over the
+// first flight of a connection and add nothing to the per packet cost of
+// forwarding. Verdicts are counted per detector for the periodic report.
+//
+// PrefixDetector, the detector of the prefix transport, is enabled by listing
+// the known prefixes (hex) in DETECTOR_PREFIX_TAGS.
+
+use signalling::TransportType;
+use flow_tracker::Flow;
+use sessions::SessionEntry;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Verdict
+{
+    Pass,
+    Confirm,
+    Annotate(u32),
+    Reject,
+    NeedMore,
+}
+
+/// Payload segments a connection's first payload is held over while the
+/// detectors need more of it, see the notes above.
+pub const FIRST_FLIGHT_SEGMENTS: u8 = 8;
+
+/// First payload inspection for a transport, see the notes above.
+pub trait TransportDetector
+{
+    /// Name the detector's verdicts are reported under.
+    fn name(&self) -> &str;
+
+    /// Verdict on the connection `flow` of `session`, which first sent
+    /// `payload`.
+    fn inspect(&mut self, session: &SessionEntry, flow: &Flow, payload: &[u8]) -> Verdict;
+}
+
+// Verdicts of one detector since the last report.
+#[derive(Default)]
+struct VerdictCounts
+{
+    confirmed: u64,
+    annotated: u64,
+    rejected: u64,
+}
+
+pub struct TransportDetectors
+{
+    detectors: Vec<(Box<dyn TransportDetector>, VerdictCounts)>,
+    // Connections no detector had a verdict on.
+    passed: u64,
+}
+
+impl TransportDetectors
+{
+    pub fn new() -> TransportDetectors {
+        TransportDetectors {
+            detectors: Vec::new(),
+            passed: 0,
+        }
+    }
+
+    pub fn add(&mut self, detector: Box<dyn TransportDetector>) {
+        self.detectors.push((detector, VerdictCounts::default()));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.detectors.is_empty()
+    }
+
+    /// The first verdict a detector has on the connection `flow` of
+    /// `session`, which first sent `payload`. Pass if none has one.
+    pub fn inspect(&mut self, session: &SessionEntry, flow: &Flow, payload: &[u8]) -> Verdict {
+        for &mut (ref mut detector, ref mut counts) in self.detectors.iter_mut() {
+            let verdict = detector.inspect(session, flow, payload);
+            match verdict {
+                Verdict::Pass => continue,
+                Verdict::NeedMore => return verdict,
+                Verdict::Confirm => counts.confirmed += 1,
+                Verdict::Annotate(_) => counts.annotated += 1,
+                Verdict::Reject => counts.rejected += 1,
+            }
+            debug!("{} {:?} {} [{:016x}]", detector.name(), verdict, flow, session.session_id);
+            return verdict
+        }
+        self.passed += 1;
+        Verdict::Pass
+    }
+
+    /// Verdicts of each detector since the last call.
+    pub fn take_report(&mut self) -> String {
+        let mut report = format!("transport detectors passed {}", self.passed);
+        self.passed = 0;
+        for &mut (ref detector, ref mut counts) in self.detectors.iter_mut() {
+            report.push_str(&format!(" {} confirmed {} annotated {} rejected {}",
+                detector.name(), counts.confirmed, counts.annotated, counts.rejected));
+            *counts = VerdictCounts::default();
+        }
+        report
+    }
+}
+
+/// Detector of the prefix transport: connections of Prefix sessions must
+/// start with one of the known prefixes, and are rejected otherwise. Payloads
+/// too short to tell need more.
+pub struct PrefixDetector
+{
+    prefixes: Vec<Vec<u8>>,
+}
+
+impl PrefixDetector
+{
+    pub fn new(prefixes: Vec<Vec<u8>>) -> PrefixDetector {
+        PrefixDetector { prefixes: prefixes }
+    }
+}
+
+impl TransportDetector for PrefixDetector
+{
+    fn name(&self) -> &str {
+        "prefix"
+    }
+
+    fn inspect(&mut self, session: &SessionEntry, _flow: &Flow, payload: &[u8]) -> Verdict {
+        if session.details.transport != TransportType::Prefix {
+            return Verdict::Pass
+        }
+        if self.prefixes.iter().any(|p| payload.starts_with(p)) {
+            return Verdict::Confirm
+        }
+        match self.prefixes.iter().any(|p| p.starts_with(payload)) {
+            true => Verdict::NeedMore,
+            false => Verdict::Reject,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use transport_detector::*;
+    use sessions::SessionDetails;
+    use timekeeping::MonotonicNs;
+    use std::time::Duration;
+
+    fn session(transport: TransportType) -> SessionEntry {
+        let mut details = SessionDetails::new("192.168.0.1", "10.10.0.1", 443, Duration::from_nanos(1)).unwrap();
+        details.transport = transport;
+        SessionEntry {
+            session_id: 7,
+            active_connections: 1,
+            connections: 1,
+            ..SessionEntry::new(details, MonotonicNs(0))
+        }
+    }
+
+    // Annotates every connection with the length of its first payload.
+    struct LengthTagger;
+
+    impl TransportDetector for LengthTagger
+    {
+        fn name(&self) -> &str {
+            "length"
+        }
+
+        fn inspect(&mut self, _session: &SessionEntry, _flow: &Flow, payload: &[u8]) -> Verdict {
+            Verdict::Annotate(payload.len() as u32)
+        }
+    }
+
+    #[test]
+    fn test_transport_detectors() {
+        let flow = Flow::from_parts("192.168.0.1".parse().unwrap(), "10.10.0.1".parse().unwrap(), 5555, 443);
+        let prefix = session(TransportType::Prefix);
+        let mut detectors = TransportDetectors::new();
+        assert!(detectors.is_empty());
+        detectors.add(Box::new(PrefixDetector::new(vec![b"GET / ".to_vec(), vec![0x16, 0x03, 0x01]])));
+
+        assert_eq!(detectors.inspect(&prefix, &flow, b"GET / HTTP/1.1"), Verdict::Confirm);
+        assert_eq!(detectors.inspect(&prefix, &flow, &[0x16, 0x03, 0x01, 0x02]), Verdict::Confirm);
+        // a short first segment isn't enough to pass
+        assert_eq!(detectors.inspect(&prefix, &flow, b"G"), Verdict::NeedMore);
+        assert_eq!(detectors.inspect(&prefix, &flow, b"GE"), Verdict::NeedMore);
+        assert_eq!(detectors.inspect(&prefix, &flow, b"POST / HTTP/1.1"), Verdict::Reject);
+        // other transports aren't the prefix detector's to judge
+        assert_eq!(detectors.inspect(&session(TransportType::Min), &flow, b"POST"), Verdict::Pass);
+
+        // later detectors see what earlier ones passed
+        detectors.add(Box::new(LengthTagger));
+        assert_eq!(detectors.inspect(&session(TransportType::Min), &flow, b"POST"), Verdict::Annotate(4));
+        assert_eq!(detectors.inspect(&prefix, &flow, b"GET / "), Verdict::Confirm);
+
+        assert_eq!(detectors.take_report(),
+            "transport detectors passed 1 prefix confirmed 3 annotated 0 rejected 1 length confirmed 0 annotated 1 rejected 0");
+        assert_eq!(detectors.take_report(),
+            "transport detectors passed 0 prefix confirmed 0 annotated 0 rejected 0 length confirmed 0 annotated 0 rejected 0");
+    }
+}
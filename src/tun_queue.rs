@@ -0,0 +1,247 @@
+//
+// Non-blocking Tun Writes
+//
+// Forwarded packets are written into tun devices the application's proxies
+// read. Blocking writes would hold up the whole core, capture included, as
+// soon as a proxy stops reading, so the devices are written non-blocking, and
+// frames a device won't take (EAGAIN, ENOBUFS) wait in a bounded queue of
+// DETECTOR_TUN_QUEUE_FRAMES per device. The queue is drained, oldest first,
+// before each new frame is written and on every event loop tick; frames
+// arriving with the queue full are dropped.
+//
+// Each device reports, since the last report, the frames written, queued,
+// dropped for a full queue and failed otherwise, along with its queue depth
+// and its high watermark, the deepest it got. A watermark near the queue size
+// means the application isn't keeping up.
+
+use std::collections::VecDeque;
+use std::io;
+
+use libc;
+use tuntap::TunTap;
+
+use error::{DetectorError, DetectorResult};
+
+pub const DEFAULT_QUEUE_FRAMES: usize = 1024;
+
+/// Where a TunQueue writes its frames.
+pub trait FrameWriter
+{
+    /// Writes `frame` without blocking.
+    fn write_frame(&mut self, frame: &[u8]) -> io::Result<()>;
+}
+
+impl FrameWriter for TunTap
+{
+    fn write_frame(&mut self, frame: &[u8]) -> io::Result<()> {
+        let n = unsafe { libc::write(self.get_fd(), frame.as_ptr() as *const libc::c_void, frame.len()) };
+        match n {
+            n if n < 0 => Err(io::Error::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+}
+
+// Whether `e` means the device can't take a frame for now.
+fn is_full(e: &io::Error) -> bool {
+    match e.raw_os_error() {
+        Some(libc::EAGAIN) | Some(libc::ENOBUFS) => true,
+        _ => false,
+    }
+}
+
+/// Makes writes to `tun` non-blocking.
+pub fn set_nonblocking(tun: &TunTap) -> io::Result<()> {
+    let fd = tun.get_fd();
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+    if flags < 0 || unsafe { libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) } < 0 {
+        return Err(io::Error::last_os_error())
+    }
+    Ok(())
+}
+
+pub struct TunQueue<W: FrameWriter = TunTap>
+{
+    name: String,
+    // Frames are discarded without one.
+    writer: Option<W>,
+    queue: VecDeque<Vec<u8>>,
+    capacity: usize,
+
+    // Since the last report.
+    writes: u64,
+    queued: u64,
+    dropped: u64,
+    failed: u64,
+    watermark: usize,
+}
+
+impl TunQueue<TunTap>
+{
+    /// Queue writing into `tun`, made non-blocking, named `name` in reports.
+    pub fn open(tun: TunTap, name: &str, capacity: usize) -> DetectorResult<TunQueue> {
+        set_nonblocking(&tun).map_err(|e| DetectorError::Capture {
+            device: name.to_string(),
+            reason: e.to_string(),
+        })?;
+        Ok(TunQueue::with_writer(Some(tun), name, capacity))
+    }
+}
+
+impl<W: FrameWriter> TunQueue<W>
+{
+    /// Queue writing into `writer`, if any.
+    pub fn with_writer(writer: Option<W>, name: &str, capacity: usize) -> TunQueue<W> {
+        TunQueue {
+            name: name.to_string(),
+            writer: writer,
+            queue: VecDeque::new(),
+            capacity: capacity,
+            writes: 0,
+            queued: 0,
+            dropped: 0,
+            failed: 0,
+            watermark: 0,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Writes `frame`, or queues it behind the frames still waiting.
+    pub fn send(&mut self, frame: Vec<u8>) -> DetectorResult<()> {
+        if !self.queue.is_empty() {
+            self.flush();
+        }
+        if !self.queue.is_empty() {
+            self.enqueue(frame);
+            return Ok(())
+        }
+        let res = match self.writer {
+            Some(ref mut w) => w.write_frame(&frame),
+            None => Ok(()),
+        };
+        match res {
+            Ok(()) => self.writes += 1,
+            Err(ref e) if is_full(e) => self.enqueue(frame),
+            Err(e) => {
+                self.failed += 1;
+                return Err(DetectorError::Forward(format!("{}: {}", self.name, e)))
+            },
+        }
+        Ok(())
+    }
+
+    fn enqueue(&mut self, frame: Vec<u8>) {
+        if self.queue.len() >= self.capacity {
+            self.dropped += 1;
+            return
+        }
+        self.queue.push_back(frame);
+        self.queued += 1;
+        if self.queue.len() > self.watermark {
+            self.watermark = self.queue.len();
+        }
+    }
+
+    /// Writes the queued frames the device takes now.
+    pub fn flush(&mut self) {
+        let writer = match self.writer {
+            Some(ref mut w) => w,
+            None => {
+                self.queue.clear();
+                return
+            },
+        };
+        while let Some(frame) = self.queue.pop_front() {
+            match writer.write_frame(&frame) {
+                Ok(()) => self.writes += 1,
+                Err(ref e) if is_full(e) => {
+                    self.queue.push_front(frame);
+                    break;
+                },
+                Err(e) => {
+                    self.failed += 1;
+                    debug!("{}", DetectorError::Forward(format!("{}: {}", self.name, e)));
+                },
+            }
+        }
+    }
+
+    /// Frames waiting to be written.
+    pub fn depth(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Writes since the last call, and the queue's high watermark.
+    pub fn take_report(&mut self) -> String {
+        let report = format!("{} writes {} queued {} dropped {} failed {} depth {} watermark {}",
+            self.name, self.writes, self.queued, self.dropped, self.failed,
+            self.queue.len(), self.watermark);
+        self.writes = 0;
+        self.queued = 0;
+        self.dropped = 0;
+        self.failed = 0;
+        self.watermark = self.queue.len();
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tun_queue::*;
+
+    // Takes `room` frames, then is full until given more.
+    struct Device
+    {
+        room: usize,
+        written: Vec<u8>,
+        broken: bool,
+    }
+
+    impl FrameWriter for Device
+    {
+        fn write_frame(&mut self, frame: &[u8]) -> io::Result<()> {
+            if self.broken {
+                return Err(io::Error::from_raw_os_error(libc::EIO))
+            }
+            if self.room == 0 {
+                return Err(io::Error::from_raw_os_error(libc::EAGAIN))
+            }
+            self.room -= 1;
+            self.written.push(frame[0]);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_tun_queue() {
+        let device = Device { room: 2, written: Vec::new(), broken: false };
+        let mut q = TunQueue::with_writer(Some(device), "tun1", 3);
+        for i in 0..6 {
+            assert!(q.send(vec![i]).is_ok());
+        }
+        // two written, three queued, the last dropped
+        assert_eq!(q.depth(), 3);
+        assert_eq!(q.take_report(), "tun1 writes 2 queued 3 dropped 1 failed 0 depth 3 watermark 3");
+
+        // the queue goes first, in order
+        q.writer.as_mut().unwrap().room = 3;
+        assert!(q.send(vec![6]).is_ok());
+        assert_eq!(q.writer.as_ref().unwrap().written, vec![0, 1, 2, 3, 4]);
+        q.writer.as_mut().unwrap().room = 10;
+        q.flush();
+        assert_eq!(q.writer.as_ref().unwrap().written, vec![0, 1, 2, 3, 4, 6]);
+        assert_eq!(q.take_report(), "tun1 writes 4 queued 1 dropped 0 failed 0 depth 0 watermark 3");
+
+        q.writer.as_mut().unwrap().broken = true;
+        assert!(q.send(vec![7]).is_err());
+        assert_eq!(q.take_report(), "tun1 writes 0 queued 0 dropped 0 failed 1 depth 0 watermark 0");
+
+        // without a device everything is discarded
+        let mut closed: TunQueue<Device> = TunQueue::with_writer(None, "tun2", 3);
+        assert!(closed.send(vec![0]).is_ok());
+        assert_eq!(closed.take_report(), "tun2 writes 1 queued 0 dropped 0 failed 0 depth 0 watermark 0");
+    }
+}
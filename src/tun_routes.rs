@@ -0,0 +1,287 @@
+//
+// Tun Routing Policy
+//
+// Each core forwards matched traffic into its tun device, tun<core>. Stations
+// that hand v4 and v6, or different transports, to different proxies can
+// route it into other devices instead, by the attributes of the session a
+// packet belongs to. The routes are listed in the TOML file named by
+// DETECTOR_TUN_ROUTES:
+//
+//   [[route]]
+//   device = "tunv6-"
+//   ip_version = 6
+//
+//   [[route]]
+//   device = "obfs4-"
+//   transport = "obfs4"
+//   phantom_subnet = "192.0.2.0/24"
+//
+// A route applies to a packet if every attribute it names matches: the IP
+// version, the session's transport (the TransportType names, case
+// insensitive), and the subnet the phantom is in. The first route that applies
+// picks the device; packets no route applies to go into tun<core> as before.
+// Like the unmatched forward devices, a route's device is per core: "tunv6-"
+// is opened as tunv6-<core>. Routes may share a device, and may give it a
+// firewall mark ("mark = 0x20", see fwmark.rs).
+//
+// Only routes naming a transport need the session, which costs a session
+// lookup per forwarded packet. Later fragments of a packet carry no ports to
+// find it by, so they only follow routes that don't name a transport.
+//
+// Each device is written non-blocking with its own queue, and reports its
+// writes and drops (see tun_queue.rs).
+
+use std::fs;
+use std::net::IpAddr;
+
+use ipnetwork::IpNetwork;
+use protobuf::ProtobufEnum;
+use serde_derive::Deserialize;
+use toml;
+
+use error::DetectorResult;
+use signalling::TransportType;
+use tun_queue::TunQueue;
+
+// Layout of the routes file.
+#[derive(Deserialize)]
+struct RouteFile {
+    route: Vec<RouteEntry>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct RouteEntry {
+    pub device: String,
+    pub ip_version: Option<u8>,
+    pub transport: Option<String>,
+    pub phantom_subnet: Option<String>,
+    pub mark: Option<u32>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TunRoute
+{
+    // Device prefix, opened as <device><core>.
+    pub device: String,
+    pub ip_version: Option<u8>,
+    pub transport: Option<TransportType>,
+    pub phantom_subnet: Option<IpNetwork>,
+    // Firewall mark of the device's packets (see fwmark.rs).
+    pub mark: Option<u32>,
+}
+
+impl TunRoute
+{
+    /// Whether the route applies to a packet to `phantom` of a session with
+    /// `transport`, if known.
+    pub fn applies(&self, phantom: &IpAddr, transport: Option<TransportType>) -> bool {
+        if let Some(v) = self.ip_version {
+            if (v == 4) != phantom.is_ipv4() {
+                return false
+            }
+        }
+        if let Some(t) = self.transport {
+            if transport != Some(t) {
+                return false
+            }
+        }
+        match self.phantom_subnet {
+            Some(net) => net.contains(*phantom),
+            None => true,
+        }
+    }
+}
+
+/// The routes listed in `entries`, in order. Routes sharing a device can't
+/// give it different marks.
+pub fn parse_routes(entries: &[RouteEntry]) -> Result<Vec<TunRoute>, String> {
+    let mut routes: Vec<TunRoute> = Vec::new();
+    for entry in entries.iter() {
+        if entry.device.is_empty() {
+            return Err("route without a device".to_string())
+        }
+        let conflict = routes.iter().any(|r| r.device == entry.device
+            && r.mark.is_some() && entry.mark.is_some() && r.mark != entry.mark);
+        if conflict {
+            return Err(format!("conflicting marks for {}", entry.device))
+        }
+        match entry.ip_version {
+            None | Some(4) | Some(6) => {},
+            Some(v) => return Err(format!("bad ip_version {} for {}", v, entry.device)),
+        }
+        let transport = match entry.transport {
+            Some(ref name) => Some(*TransportType::values().iter()
+                .find(|t| format!("{:?}", t).eq_ignore_ascii_case(name))
+                .ok_or_else(|| format!("unknown transport {} for {}", name, entry.device))?),
+            None => None,
+        };
+        let phantom_subnet = match entry.phantom_subnet {
+            Some(ref s) => Some(s.parse::<IpNetwork>()
+                .map_err(|e| format!("bad phantom_subnet {} for {}: {}", s, entry.device, e))?),
+            None => None,
+        };
+        routes.push(TunRoute {
+            device: entry.device.clone(),
+            ip_version: entry.ip_version,
+            transport: transport,
+            phantom_subnet: phantom_subnet,
+            mark: entry.mark,
+        });
+    }
+    Ok(routes)
+}
+
+/// The routes in the file at `path`.
+pub fn load_routes(path: &str) -> Result<Vec<TunRoute>, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("can't read {}: {}", path, e))?;
+    let file: RouteFile = toml::from_str(&contents)
+        .map_err(|e| format!("can't parse {}: {}", path, e))?;
+    parse_routes(&file.route).map_err(|e| format!("{} in {}", e, path))
+}
+
+pub struct TunRouter
+{
+    // Each route's device, an index into devices.
+    routes: Vec<(TunRoute, usize)>,
+    devices: Vec<TunQueue>,
+}
+
+impl TunRouter
+{
+    /// Opens the devices of `routes` on core `lcore`, each queueing up to
+    /// `capacity` frames.
+    pub fn open(routes: Vec<TunRoute>, lcore: i32, capacity: usize) -> DetectorResult<TunRouter> {
+        let mut router = TunRouter::with_routes(routes, lcore, capacity);
+        for device in router.devices.iter_mut() {
+            let tun = ::open_tun(device.name())?;
+            *device = TunQueue::open(tun, device.name(), capacity)?;
+        }
+        Ok(router)
+    }
+
+    // Router for `routes` with none of its devices open. What would be
+    // written into them is discarded.
+    fn with_routes(routes: Vec<TunRoute>, lcore: i32, capacity: usize) -> TunRouter {
+        let mut devices: Vec<TunQueue> = Vec::new();
+        let mut indexed = Vec::new();
+        for route in routes.into_iter() {
+            let name = format!("{}{}", route.device, lcore);
+            let i = match devices.iter().position(|d| d.name() == name) {
+                Some(i) => i,
+                None => {
+                    devices.push(TunQueue::with_writer(None, &name, capacity));
+                    devices.len() - 1
+                },
+            };
+            indexed.push((route, i));
+        }
+        TunRouter {
+            routes: indexed,
+            devices: devices,
+        }
+    }
+
+    /// Each device's name and mark, by index. A device has the mark of the
+    /// first of its routes that gives one.
+    pub fn device_marks(&self) -> Vec<(String, Option<u32>)> {
+        self.devices.iter().enumerate().map(|(i, d)| {
+            let mark = self.routes.iter().filter(|r| r.1 == i).filter_map(|r| r.0.mark).next();
+            (d.name().to_string(), mark)
+        }).collect()
+    }
+
+    /// Whether a route needs the transport of a packet's session.
+    pub fn needs_transport(&self) -> bool {
+        self.routes.iter().any(|r| r.0.transport.is_some())
+    }
+
+    /// The device the first route applying to a packet to `phantom` picks,
+    /// None for tun<core>.
+    pub fn route(&self, phantom: &IpAddr, transport: Option<TransportType>) -> Option<usize> {
+        self.routes.iter().find(|r| r.0.applies(phantom, transport)).map(|r| r.1)
+    }
+
+    /// Writes `frame` into `device`, or into `default` (tun<core>) if None.
+    pub fn send(&mut self, device: Option<usize>, default: &mut TunQueue, frame: Vec<u8>) -> DetectorResult<()> {
+        match device {
+            Some(i) => self.devices[i].send(frame),
+            None => default.send(frame),
+        }
+    }
+
+    /// Writes the frames queued for each device that it takes now.
+    pub fn flush(&mut self) {
+        for device in self.devices.iter_mut() {
+            if device.depth() > 0 {
+                device.flush();
+            }
+        }
+    }
+
+    /// Writes into each device since the last call.
+    pub fn take_report(&mut self) -> String {
+        let devices: Vec<String> = self.devices.iter_mut().map(|d| d.take_report()).collect();
+        format!("tun routes {}", devices.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tun_routes::*;
+
+    fn entry(device: &str, ip_version: Option<u8>, transport: Option<&str>, subnet: Option<&str>) -> RouteEntry {
+        RouteEntry {
+            device: device.to_string(),
+            ip_version: ip_version,
+            transport: transport.map(|t| t.to_string()),
+            phantom_subnet: subnet.map(|s| s.to_string()),
+            mark: None,
+        }
+    }
+
+    #[test]
+    fn test_tun_routes() {
+        let routes = parse_routes(&[
+            entry("obfs4-", None, Some("obfs4"), Some("192.0.2.0/24")),
+            RouteEntry { mark: Some(0x20), ..entry("tunv6-", Some(6), None, None) },
+            entry("obfs4-", Some(6), Some("OBFS4"), None),
+        ]).unwrap();
+        assert_eq!(routes[0].transport, Some(TransportType::Obfs4));
+        assert_eq!(routes[2].transport, Some(TransportType::Obfs4));
+
+        assert!(parse_routes(&[entry("", None, None, None)]).is_err());
+        assert!(parse_routes(&[entry("x", Some(5), None, None)]).is_err());
+        assert!(parse_routes(&[entry("x", None, Some("quic"), None)]).is_err());
+        assert!(parse_routes(&[entry("x", None, None, Some("192.0.2.0/33"))]).is_err());
+        assert!(parse_routes(&[
+            RouteEntry { mark: Some(1), ..entry("x", Some(4), None, None) },
+            RouteEntry { mark: Some(2), ..entry("x", Some(6), None, None) },
+        ]).is_err());
+
+        let mut router = TunRouter::with_routes(routes, 3, 8);
+        assert!(router.needs_transport());
+        assert_eq!(router.device_marks(),
+            vec![("obfs4-3".to_string(), None), ("tunv6-3".to_string(), Some(0x20))]);
+        let v4_in: IpAddr = "192.0.2.7".parse().unwrap();
+        let v4_out: IpAddr = "198.51.100.7".parse().unwrap();
+        let v6: IpAddr = "2001:db8::7".parse().unwrap();
+        assert_eq!(router.route(&v4_in, Some(TransportType::Obfs4)), Some(0));
+        assert_eq!(router.route(&v4_in, Some(TransportType::Min)), None);
+        assert_eq!(router.route(&v4_in, None), None);
+        assert_eq!(router.route(&v4_out, Some(TransportType::Obfs4)), None);
+        // the first route that applies wins, devices are shared
+        assert_eq!(router.route(&v6, Some(TransportType::Obfs4)), Some(1));
+        assert_eq!(router.route(&v6, None), Some(1));
+
+        let mut default = TunQueue::with_writer(None, "tun3", 8);
+        let route = router.route(&v6, None);
+        assert!(router.send(route, &mut default, vec![0; 40]).is_ok());
+        assert!(router.send(None, &mut default, vec![0; 40]).is_ok());
+        assert_eq!(router.take_report(), "tun routes \
+            obfs4-3 writes 0 queued 0 dropped 0 failed 0 depth 0 watermark 0 \
+            tunv6-3 writes 1 queued 0 dropped 0 failed 0 depth 0 watermark 0");
+        assert_eq!(default.take_report(), "tun3 writes 1 queued 0 dropped 0 failed 0 depth 0 watermark 0");
+        assert!(!TunRouter::with_routes(Vec::new(), 0, 8).needs_transport());
+    }
+}
@@ -0,0 +1,672 @@
+//
+// Unmatched Phantom Connections
+//
+// Connections to a phantom address that don't belong to a registered session
+// normally get no answer at all from the detector, while a registered phantom
+// answers like a live host. A censor probing phantom space can tell the two
+// apart. With an unmatched action configured (DETECTOR_UNMATCHED_ACTION) every
+// packet of an unmatched connection to the configured phantom subnets gets the
+// same treatment:
+//
+//  - drop: nothing is sent back (the default, and the behavior without this
+//    module);
+//  - rst: the detector answers for the phantom with a TCP reset, as a host
+//    with no listener on that port would;
+//  - delayed-rst: the same reset, sent UnmatchedConfig.reset_delay_ns later,
+//    so every probe costs the prober that long;
+//  - tarpit: a SYN is answered with a SYN-ACK advertising a zero window and
+//    everything after it is ignored, so the prober's handshake completes and
+//    the connection then stalls until its own timeouts give up;
+//  - forward: the packets are sent into a second tun device per core
+//    (DETECTOR_UNMATCHED_TUN plus the core number) that the station routes to
+//    a decoy site, so unregistered phantoms look like ordinary live hosts.
+//
+// Different phantom subnets can get different actions (DETECTOR_UNMATCHED_RULES,
+// UnmatchedConfig.rules); the rule with the longest matching prefix applies,
+// and the rules override DETECTOR_UNMATCHED_ACTION for the subnets they cover.
+//
+// Connections a registered session turns away because it is at its connection
+// limit get the same action, so they aren't distinguishable either. Packets
+// of registered sessions are never handled here.
+//
+// Resets and SYN-ACKs are written by hand (pnet has no mutable packets here)
+// and sent over a raw socket, which needs CAP_NET_RAW. The tarpit keeps no
+// state: its initial sequence numbers are a keyed hash of the connection's
+// addresses and ports. Delayed resets wait in a bounded queue (max_delayed,
+// further ones are dropped and counted) and go out on the next unmatched
+// packet or periodic cleanup after they are due, so within about a second.
+// Traffic from the stations in detector_filter_list (liveness testing) is
+// never answered, so phantoms still look unused to the station's own liveness
+// tests.
+
+use std::collections::VecDeque;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::io;
+use std::mem;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use ipnetwork::IpNetwork;
+use libc;
+use pnet::packet::Packet;
+use pnet::packet::tcp::{TcpFlags, TcpPacket};
+use tuntap::TunTap;
+
+use error::{DetectorError, DetectorResult, error_chain};
+use timekeeping::MonotonicNs;
+use util::{IpPacket, checksum};
+
+const TTL: u8 = 64;
+// Window the tarpit's SYN-ACKs advertise.
+const TARPIT_WINDOW: u16 = 0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnmatchedAction
+{
+    Drop,
+    Reset,
+    DelayedReset,
+    Tarpit,
+    Forward,
+}
+
+impl FromStr for UnmatchedAction
+{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<UnmatchedAction, String> {
+        match s {
+            "drop" => Ok(UnmatchedAction::Drop),
+            "rst" => Ok(UnmatchedAction::Reset),
+            "delayed-rst" => Ok(UnmatchedAction::DelayedReset),
+            "tarpit" => Ok(UnmatchedAction::Tarpit),
+            "forward" => Ok(UnmatchedAction::Forward),
+            _ => Err(format!("unknown unmatched action {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for UnmatchedAction
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            UnmatchedAction::Drop => "drop",
+            UnmatchedAction::Reset => "rst",
+            UnmatchedAction::DelayedReset => "delayed-rst",
+            UnmatchedAction::Tarpit => "tarpit",
+            UnmatchedAction::Forward => "forward",
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct UnmatchedConfig
+{
+    pub action: UnmatchedAction,
+    // Phantom space the action applies to.
+    pub subnets: Vec<IpNetwork>,
+    // Actions of individual phantom subnets, overriding `action` where they
+    // overlap `subnets`.
+    pub rules: Vec<(IpNetwork, UnmatchedAction)>,
+    // Prefix of the decoy tun devices, for UnmatchedAction::Forward.
+    pub tun_prefix: String,
+    // How long (ns) UnmatchedAction::DelayedReset holds its resets.
+    pub reset_delay_ns: u64,
+    // Resets held at once, at most.
+    pub max_delayed: usize,
+}
+
+impl Default for UnmatchedConfig
+{
+    fn default() -> UnmatchedConfig {
+        UnmatchedConfig {
+            action: UnmatchedAction::Drop,
+            subnets: Vec::new(),
+            rules: Vec::new(),
+            tun_prefix: "decoy".to_string(),
+            reset_delay_ns: 2*1000*1000*1000,
+            max_delayed: 4096,
+        }
+    }
+}
+
+impl UnmatchedConfig
+{
+    /// Every subnet with its action, most specific first. A subnet listed
+    /// more than once keeps its first rule, and `subnets` come after `rules`.
+    pub fn all_rules(&self) -> Vec<(IpNetwork, UnmatchedAction)> {
+        let mut rules: Vec<(IpNetwork, UnmatchedAction)> = Vec::new();
+        let listed = self.rules.iter().cloned()
+            .chain(self.subnets.iter().map(|net| (*net, self.action)));
+        for rule in listed {
+            if !rules.iter().any(|r| r.0 == rule.0) {
+                rules.push(rule);
+            }
+        }
+        // stable, so earlier rules win among those of the same length
+        rules.sort_by(|a, b| b.0.prefix().cmp(&a.0.prefix()));
+        rules
+    }
+}
+
+// Raw IP sockets for sending resets, one per address family. The packets
+// include their IP header (IPPROTO_RAW).
+struct RawSockets
+{
+    v4: libc::c_int,
+    v6: libc::c_int,
+}
+
+impl RawSockets
+{
+    fn open() -> DetectorResult<RawSockets> {
+        let open = |family| {
+            let fd = unsafe {
+                libc::socket(family, libc::SOCK_RAW | libc::SOCK_CLOEXEC, libc::IPPROTO_RAW)
+            };
+            if fd < 0 {
+                return Err(DetectorError::Unmatched {
+                    context: "raw socket", source: io::Error::last_os_error() })
+            }
+            Ok(fd)
+        };
+        let v4 = open(libc::AF_INET)?;
+        match open(libc::AF_INET6) {
+            Ok(v6) => Ok(RawSockets { v4: v4, v6: v6 }),
+            Err(e) => {
+                unsafe { libc::close(v4); }
+                Err(e)
+            },
+        }
+    }
+
+    fn send(&self, dst: &IpAddr, pkt: &[u8]) -> DetectorResult<()> {
+        let sent = match dst {
+            IpAddr::V4(ip) => {
+                let mut addr: libc::sockaddr_in = unsafe { mem::zeroed() };
+                addr.sin_family = libc::AF_INET as libc::sa_family_t;
+                addr.sin_addr.s_addr = u32::from(*ip).to_be();
+                unsafe {
+                    libc::sendto(self.v4, pkt.as_ptr() as *const libc::c_void, pkt.len(), 0,
+                                 &addr as *const libc::sockaddr_in as *const libc::sockaddr,
+                                 mem::size_of::<libc::sockaddr_in>() as libc::socklen_t)
+                }
+            },
+            IpAddr::V6(ip) => {
+                let mut addr: libc::sockaddr_in6 = unsafe { mem::zeroed() };
+                addr.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+                addr.sin6_addr.s6_addr = ip.octets();
+                unsafe {
+                    libc::sendto(self.v6, pkt.as_ptr() as *const libc::c_void, pkt.len(), 0,
+                                 &addr as *const libc::sockaddr_in6 as *const libc::sockaddr,
+                                 mem::size_of::<libc::sockaddr_in6>() as libc::socklen_t)
+                }
+            },
+        };
+        if sent < 0 {
+            return Err(DetectorError::Unmatched {
+                context: "reset send", source: io::Error::last_os_error() })
+        }
+        Ok(())
+    }
+}
+
+impl Drop for RawSockets
+{
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.v4);
+            libc::close(self.v6);
+        }
+    }
+}
+
+// A reset waiting for its time.
+struct DelayedReset
+{
+    due: MonotonicNs,
+    dst: IpAddr,
+    pkt: Vec<u8>,
+}
+
+pub struct Unmatched
+{
+    // Most specific first, see UnmatchedConfig::all_rules.
+    rules: Vec<(IpNetwork, UnmatchedAction)>,
+    // Opened if a rule needs them.
+    sockets: Option<RawSockets>,
+    tun: Option<TunTap>,
+
+    reset_delay_ns: u64,
+    max_delayed: usize,
+    delayed: VecDeque<DelayedReset>,
+    // Keys the tarpit's sequence numbers.
+    tarpit_key: RandomState,
+
+    // Since the last report: packets handled and failed to send, delayed
+    // resets dropped for a full queue, and SYN-ACKs sent by the tarpit.
+    handled: u64,
+    failed: u64,
+    overflow: u64,
+    tarpitted: u64,
+}
+
+impl Unmatched
+{
+    /// Opens whatever the configured actions send through on core `lcore`.
+    pub fn open(config: UnmatchedConfig, lcore: i32) -> DetectorResult<Unmatched> {
+        let rules = config.all_rules();
+        let uses = |actions: &[UnmatchedAction]| rules.iter().any(|r| actions.contains(&r.1));
+        let sockets = match uses(&[UnmatchedAction::Reset, UnmatchedAction::DelayedReset, UnmatchedAction::Tarpit]) {
+            true => Some(RawSockets::open()?),
+            false => None,
+        };
+        let tun = match uses(&[UnmatchedAction::Forward]) {
+            true => Some(::open_tun(&format!("{}{}", config.tun_prefix, lcore))?),
+            false => None,
+        };
+        Ok(Unmatched::with_outputs(config, sockets, tun))
+    }
+
+    // Unmatched sending through `sockets` and `tun`, if given. What would be
+    // sent through a missing one is discarded.
+    fn with_outputs(config: UnmatchedConfig, sockets: Option<RawSockets>, tun: Option<TunTap>) -> Unmatched {
+        Unmatched {
+            rules: config.all_rules(),
+            sockets: sockets,
+            tun: tun,
+            reset_delay_ns: config.reset_delay_ns,
+            max_delayed: config.max_delayed,
+            delayed: VecDeque::new(),
+            tarpit_key: RandomState::new(),
+            handled: 0,
+            failed: 0,
+            overflow: 0,
+            tarpitted: 0,
+        }
+    }
+
+    /// The action for connections to `phantom`, None outside the configured
+    /// phantom space.
+    pub fn action_for(&self, phantom: &IpAddr) -> Option<UnmatchedAction> {
+        self.rules.iter().find(|r| r.0.contains(*phantom)).map(|r| r.1)
+    }
+
+    /// Whether `phantom` is in the phantom space the actions apply to.
+    pub fn covers(&self, phantom: &IpAddr) -> bool {
+        self.action_for(phantom).is_some()
+    }
+
+    /// Applies the action of its phantom to a packet of an unmatched
+    /// connection, received at `now`.
+    pub fn handle(&mut self, ip_pkt: &IpPacket, tcp_pkt: &TcpPacket, now: MonotonicNs) {
+        self.send_due(now);
+        let action = match self.action_for(&ip_pkt.addresses().1) {
+            Some(a) => a,
+            None => return,
+        };
+        let res = match action {
+            UnmatchedAction::Drop => Ok(()),
+            UnmatchedAction::Reset => match reset_reply(ip_pkt, tcp_pkt) {
+                Some((dst, pkt)) => self.send(&dst, &pkt),
+                // never answer a reset
+                None => return,
+            },
+            UnmatchedAction::DelayedReset => match reset_reply(ip_pkt, tcp_pkt) {
+                Some((dst, pkt)) => {
+                    self.delay(DelayedReset { due: now.saturating_add(self.reset_delay_ns), dst: dst, pkt: pkt });
+                    Ok(())
+                },
+                None => return,
+            },
+            UnmatchedAction::Tarpit => match tarpit_reply(ip_pkt, tcp_pkt, &self.tarpit_key) {
+                Some((dst, pkt)) => {
+                    self.tarpitted += 1;
+                    self.send(&dst, &pkt)
+                },
+                // the rest of the connection is swallowed
+                None => Ok(()),
+            },
+            UnmatchedAction::Forward => match self.tun {
+                Some(ref tun) => tun.send(ip_pkt.tun_frame()).map(|_| ())
+                    .map_err(|e| DetectorError::Forward(e.to_string())),
+                None => Ok(()),
+            },
+        };
+        self.handled += 1;
+        if let Err(e) = res {
+            self.failed += 1;
+            debug!("{}", error_chain(&e));
+        }
+    }
+
+    /// Sends the delayed resets due at `now`.
+    pub fn send_due(&mut self, now: MonotonicNs) {
+        while self.delayed.front().map_or(false, |r| r.due <= now) {
+            if let Some(r) = self.delayed.pop_front() {
+                if let Err(e) = self.send(&r.dst, &r.pkt) {
+                    self.failed += 1;
+                    debug!("{}", error_chain(&e));
+                }
+            }
+        }
+    }
+
+    /// Delayed resets waiting to be sent.
+    pub fn delayed(&self) -> usize {
+        self.delayed.len()
+    }
+
+    fn delay(&mut self, reset: DelayedReset) {
+        if self.delayed.len() >= self.max_delayed {
+            self.overflow += 1;
+            return
+        }
+        // every reset waits as long, so the queue stays in due order
+        self.delayed.push_back(reset);
+    }
+
+    fn send(&self, dst: &IpAddr, pkt: &[u8]) -> DetectorResult<()> {
+        match self.sockets {
+            Some(ref sockets) => sockets.send(dst, pkt),
+            None => Ok(()),
+        }
+    }
+
+    /// Report line for the period, resetting the counters.
+    pub fn take_report(&mut self) -> String {
+        let mut actions: Vec<String> = Vec::new();
+        for &(_, action) in &self.rules {
+            if !actions.contains(&action.to_string()) {
+                actions.push(action.to_string());
+            }
+        }
+        let mut line = format!("unmatched {} handled {} failed {}",
+            actions.join(","), self.handled, self.failed);
+        if self.rules.iter().any(|r| r.1 == UnmatchedAction::DelayedReset) {
+            line.push_str(&format!(" delayed {} overflow {}", self.delayed.len(), self.overflow));
+        }
+        if self.rules.iter().any(|r| r.1 == UnmatchedAction::Tarpit) {
+            line.push_str(&format!(" tarpitted {}", self.tarpitted));
+        }
+        self.handled = 0;
+        self.failed = 0;
+        self.overflow = 0;
+        self.tarpitted = 0;
+        line
+    }
+}
+
+// The reset a host with no listener would send in answer to `tcp_pkt`
+// (RFC 793, 3.4), from the phantom back to the client, with its destination.
+// None if `tcp_pkt` is itself a reset.
+fn reset_reply(ip_pkt: &IpPacket, tcp_pkt: &TcpPacket) -> Option<(IpAddr, Vec<u8>)> {
+    let flags = tcp_pkt.get_flags();
+    if (flags & TcpFlags::RST) != 0 {
+        return None
+    }
+    let (seq, ack, rst_flags) = if (flags & TcpFlags::ACK) != 0 {
+        (tcp_pkt.get_acknowledgement(), 0, TcpFlags::RST)
+    } else {
+        (0, tcp_pkt.get_sequence().wrapping_add(segment_len(tcp_pkt)), TcpFlags::RST | TcpFlags::ACK)
+    };
+    Some(reply(ip_pkt, tcp_pkt, seq, ack, rst_flags, 0))
+}
+
+// The tarpit's SYN-ACK in answer to the SYN `tcp_pkt`, with a zero window and
+// an initial sequence number keyed by `key`. None for any other segment.
+fn tarpit_reply(ip_pkt: &IpPacket, tcp_pkt: &TcpPacket, key: &RandomState) -> Option<(IpAddr, Vec<u8>)> {
+    if tcp_pkt.get_flags() & (TcpFlags::SYN | TcpFlags::ACK | TcpFlags::RST) != TcpFlags::SYN {
+        return None
+    }
+    let mut hasher = key.build_hasher();
+    (ip_pkt.addresses(), tcp_pkt.get_source(), tcp_pkt.get_destination()).hash(&mut hasher);
+    let isn = hasher.finish() as u32;
+    let ack = tcp_pkt.get_sequence().wrapping_add(segment_len(tcp_pkt));
+    Some(reply(ip_pkt, tcp_pkt, isn, ack, TcpFlags::SYN | TcpFlags::ACK, TARPIT_WINDOW))
+}
+
+// Sequence numbers `tcp_pkt` takes: its payload, SYN and FIN.
+fn segment_len(tcp_pkt: &TcpPacket) -> u32 {
+    let flags = tcp_pkt.get_flags();
+    let mut len = tcp_pkt.payload().len() as u32;
+    if (flags & TcpFlags::SYN) != 0 { len += 1; }
+    if (flags & TcpFlags::FIN) != 0 { len += 1; }
+    len
+}
+
+// A segment without options or payload in answer to `tcp_pkt`, from the
+// phantom back to the client, with its destination.
+fn reply(ip_pkt: &IpPacket, tcp_pkt: &TcpPacket, seq: u32, ack: u32, flags: u16, window: u16) -> (IpAddr, Vec<u8>) {
+    let mut tcp = Vec::with_capacity(20);
+    tcp.extend_from_slice(&be16(tcp_pkt.get_destination()));
+    tcp.extend_from_slice(&be16(tcp_pkt.get_source()));
+    tcp.extend_from_slice(&be32(seq));
+    tcp.extend_from_slice(&be32(ack));
+    tcp.push(5 << 4);
+    tcp.push(flags as u8);
+    tcp.extend_from_slice(&be16(window));
+    // checksum, urgent pointer
+    tcp.extend_from_slice(&[0; 4]);
+
+    let mut pkt = Vec::with_capacity(60);
+    let dst = match ip_pkt {
+        IpPacket::V4(ip) => {
+            let (src, dst) = (ip.get_destination().octets(), ip.get_source().octets());
+            let mut pseudo = Vec::with_capacity(12);
+            pseudo.extend_from_slice(&src);
+            pseudo.extend_from_slice(&dst);
+            pseudo.extend_from_slice(&[0, 6, 0, tcp.len() as u8]);
+            let sum = checksum(&[&pseudo, &tcp]);
+            tcp[16..18].copy_from_slice(&be16(sum));
+
+            pkt.extend_from_slice(&[0x45, 0]);
+            pkt.extend_from_slice(&be16(20 + tcp.len() as u16));
+            // identification, don't fragment
+            pkt.extend_from_slice(&[0, 0, 0x40, 0, TTL, 6, 0, 0]);
+            pkt.extend_from_slice(&src);
+            pkt.extend_from_slice(&dst);
+            let sum = checksum(&[&pkt]);
+            pkt[10..12].copy_from_slice(&be16(sum));
+            IpAddr::V4(ip.get_source())
+        },
+        IpPacket::V6(ip) => {
+            let (src, dst) = (ip.get_destination().octets(), ip.get_source().octets());
+            let mut pseudo = Vec::with_capacity(40);
+            pseudo.extend_from_slice(&src);
+            pseudo.extend_from_slice(&dst);
+            pseudo.extend_from_slice(&be32(tcp.len() as u32));
+            pseudo.extend_from_slice(&[0, 0, 0, 6]);
+            let sum = checksum(&[&pseudo, &tcp]);
+            tcp[16..18].copy_from_slice(&be16(sum));
+
+            pkt.extend_from_slice(&[0x60, 0, 0, 0]);
+            pkt.extend_from_slice(&be16(tcp.len() as u16));
+            pkt.extend_from_slice(&[6, TTL]);
+            pkt.extend_from_slice(&src);
+            pkt.extend_from_slice(&dst);
+            IpAddr::V6(ip.get_source())
+        },
+    };
+    pkt.extend_from_slice(&tcp);
+    (dst, pkt)
+}
+
+fn be16(n: u16) -> [u8; 2] {
+    [(n >> 8) as u8, n as u8]
+}
+
+fn be32(n: u32) -> [u8; 4] {
+    [(n >> 24) as u8, (n >> 16) as u8, (n >> 8) as u8, n as u8]
+}
+
+#[cfg(test)]
+mod tests {
+    use unmatched::*;
+    use pnet::packet::ipv4::Ipv4Packet;
+    use pnet::packet::ipv6::Ipv6Packet;
+
+    // A TCP segment from 10.0.0.1:40000 (or 2001:db8::1) to the phantom.
+    fn segment(v6: bool, flags: u8, seq: u32, ack: u32, payload: &[u8]) -> Vec<u8> {
+        let mut tcp = Vec::new();
+        tcp.extend_from_slice(&be16(40000));
+        tcp.extend_from_slice(&be16(443));
+        tcp.extend_from_slice(&be32(seq));
+        tcp.extend_from_slice(&be32(ack));
+        tcp.extend_from_slice(&[5 << 4, flags, 0xff, 0xff, 0, 0, 0, 0]);
+        tcp.extend_from_slice(payload);
+
+        let mut pkt = Vec::new();
+        if v6 {
+            pkt.extend_from_slice(&[0x60, 0, 0, 0]);
+            pkt.extend_from_slice(&be16(tcp.len() as u16));
+            pkt.extend_from_slice(&[6, 64]);
+            pkt.extend_from_slice(&"2001:db8::1".parse::<::std::net::Ipv6Addr>().unwrap().octets());
+            pkt.extend_from_slice(&"2001:48a8:687f:1::5".parse::<::std::net::Ipv6Addr>().unwrap().octets());
+        } else {
+            pkt.extend_from_slice(&[0x45, 0]);
+            pkt.extend_from_slice(&be16(20 + tcp.len() as u16));
+            pkt.extend_from_slice(&[0, 0, 0x40, 0, 64, 6, 0, 0, 10, 0, 0, 1, 192, 122, 190, 7]);
+        }
+        pkt.extend_from_slice(&tcp);
+        pkt
+    }
+
+    #[test]
+    fn test_unmatched_reset_v4() {
+        // SYN: answered with RST|ACK acknowledging it
+        let syn = segment(false, TcpFlags::SYN as u8, 1000, 0, &[]);
+        let ip = IpPacket::V4(Ipv4Packet::new(&syn).unwrap());
+        let (dst, rst) = reset_reply(&ip, &ip.tcp().unwrap()).unwrap();
+        assert_eq!(dst, "10.0.0.1".parse::<IpAddr>().unwrap());
+        assert_eq!(rst.len(), 40);
+        assert_eq!(&rst[12..20], &[192, 122, 190, 7, 10, 0, 0, 1]);
+        assert_eq!(&rst[20..32], &[0x01, 0xbb, 0x9c, 0x40, 0, 0, 0, 0, 0, 0, 0x03, 0xe9]);
+        assert_eq!(rst[33], (TcpFlags::RST | TcpFlags::ACK) as u8);
+        // checksums verify
+        assert_eq!(checksum(&[&rst[..20]]), 0);
+        let mut pseudo = rst[12..20].to_vec();
+        pseudo.extend_from_slice(&[0, 6, 0, 20]);
+        assert_eq!(checksum(&[&pseudo, &rst[20..]]), 0);
+
+        // data with ACK: RST at the acknowledged sequence number
+        let data = segment(false, (TcpFlags::ACK | TcpFlags::PSH) as u8, 1001, 77, b"hello");
+        let ip = IpPacket::V4(Ipv4Packet::new(&data).unwrap());
+        let (_, rst) = reset_reply(&ip, &ip.tcp().unwrap()).unwrap();
+        assert_eq!(&rst[24..32], &[0, 0, 0, 77, 0, 0, 0, 0]);
+        assert_eq!(rst[33], TcpFlags::RST as u8);
+
+        // resets are never answered
+        let reset = segment(false, TcpFlags::RST as u8, 1001, 0, &[]);
+        let ip = IpPacket::V4(Ipv4Packet::new(&reset).unwrap());
+        assert!(reset_reply(&ip, &ip.tcp().unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_unmatched_reset_v6() {
+        let syn = segment(true, (TcpFlags::SYN | TcpFlags::FIN) as u8, 0xffffffff, 0, b"x");
+        let ip = IpPacket::V6(Ipv6Packet::new(&syn).unwrap());
+        let (dst, rst) = reset_reply(&ip, &ip.tcp().unwrap()).unwrap();
+        assert_eq!(dst, "2001:db8::1".parse::<IpAddr>().unwrap());
+        assert_eq!(rst.len(), 60);
+        assert_eq!(&rst[4..8], &[0, 20, 6, TTL]);
+        assert_eq!(&rst[8..40], &syn[24..40].iter().chain(syn[8..24].iter()).cloned().collect::<Vec<u8>>()[..]);
+        // payload, SYN and FIN each take a sequence number, wrapping around
+        assert_eq!(&rst[48..52], &[0, 0, 0, 2]);
+        let mut pseudo = rst[8..40].to_vec();
+        pseudo.extend_from_slice(&[0, 0, 0, 20, 0, 0, 0, 6]);
+        assert_eq!(checksum(&[&pseudo, &rst[40..]]), 0);
+    }
+
+    #[test]
+    fn test_unmatched_config() {
+        assert_eq!("rst".parse::<UnmatchedAction>(), Ok(UnmatchedAction::Reset));
+        assert_eq!("forward".parse::<UnmatchedAction>(), Ok(UnmatchedAction::Forward));
+        assert!("accept".parse::<UnmatchedAction>().is_err());
+
+        let config = UnmatchedConfig {
+            subnets: vec!["192.122.190.0/24".parse().unwrap()],
+            ..UnmatchedConfig::default()
+        };
+        let mut unmatched = Unmatched::open(config, 0).unwrap();
+        assert!(unmatched.covers(&"192.122.190.7".parse().unwrap()));
+        assert!(!unmatched.covers(&"8.8.8.8".parse().unwrap()));
+
+        let syn = segment(false, TcpFlags::SYN as u8, 1000, 0, &[]);
+        let ip = IpPacket::V4(Ipv4Packet::new(&syn).unwrap());
+        unmatched.handle(&ip, &ip.tcp().unwrap(), MonotonicNs(0));
+        assert_eq!(unmatched.take_report(), "unmatched drop handled 1 failed 0");
+        assert_eq!(unmatched.take_report(), "unmatched drop handled 0 failed 0");
+    }
+
+    #[test]
+    fn test_unmatched_rules() {
+        assert_eq!("delayed-rst".parse::<UnmatchedAction>(), Ok(UnmatchedAction::DelayedReset));
+        assert_eq!("tarpit".parse::<UnmatchedAction>(), Ok(UnmatchedAction::Tarpit));
+
+        let config = UnmatchedConfig {
+            action: UnmatchedAction::Reset,
+            subnets: vec!["192.122.0.0/16".parse().unwrap()],
+            rules: vec![
+                ("192.122.190.0/24".parse().unwrap(), UnmatchedAction::Tarpit),
+                ("192.122.190.0/28".parse().unwrap(), UnmatchedAction::DelayedReset),
+                ("192.122.0.0/16".parse().unwrap(), UnmatchedAction::Drop),
+            ],
+            reset_delay_ns: 1000,
+            max_delayed: 1,
+            ..UnmatchedConfig::default()
+        };
+        let mut unmatched = Unmatched::with_outputs(config, None, None);
+        // the longest prefix wins, and a rule wins over the subnets
+        assert_eq!(unmatched.action_for(&"192.122.190.7".parse().unwrap()), Some(UnmatchedAction::DelayedReset));
+        assert_eq!(unmatched.action_for(&"192.122.190.77".parse().unwrap()), Some(UnmatchedAction::Tarpit));
+        assert_eq!(unmatched.action_for(&"192.122.1.1".parse().unwrap()), Some(UnmatchedAction::Drop));
+        assert_eq!(unmatched.action_for(&"8.8.8.8".parse().unwrap()), None);
+
+        // resets to 192.122.190.7 wait their delay, one at a time
+        let syn = segment(false, TcpFlags::SYN as u8, 1000, 0, &[]);
+        let ip = IpPacket::V4(Ipv4Packet::new(&syn).unwrap());
+        unmatched.handle(&ip, &ip.tcp().unwrap(), MonotonicNs(0));
+        unmatched.handle(&ip, &ip.tcp().unwrap(), MonotonicNs(10));
+        assert_eq!(unmatched.delayed(), 1);
+        unmatched.send_due(MonotonicNs(999));
+        assert_eq!(unmatched.delayed(), 1);
+        unmatched.send_due(MonotonicNs(1000));
+        assert_eq!(unmatched.delayed(), 0);
+        assert_eq!(unmatched.take_report(),
+            "unmatched delayed-rst,tarpit,drop handled 2 failed 0 delayed 0 overflow 1 tarpitted 0");
+    }
+
+    #[test]
+    fn test_unmatched_tarpit() {
+        let key = RandomState::new();
+        let syn = segment(false, TcpFlags::SYN as u8, 0xffffffff, 0, &[]);
+        let ip = IpPacket::V4(Ipv4Packet::new(&syn).unwrap());
+        let (dst, synack) = tarpit_reply(&ip, &ip.tcp().unwrap(), &key).unwrap();
+        assert_eq!(dst, "10.0.0.1".parse::<IpAddr>().unwrap());
+        assert_eq!(synack.len(), 40);
+        assert_eq!(&synack[12..20], &[192, 122, 190, 7, 10, 0, 0, 1]);
+        assert_eq!(&synack[20..24], &[0x01, 0xbb, 0x9c, 0x40]);
+        // acknowledges the SYN, with a zero window
+        assert_eq!(&synack[28..32], &[0, 0, 0, 0]);
+        assert_eq!(synack[33], (TcpFlags::SYN | TcpFlags::ACK) as u8);
+        assert_eq!(&synack[34..36], &[0, 0]);
+        assert_eq!(checksum(&[&synack[..20]]), 0);
+        let mut pseudo = synack[12..20].to_vec();
+        pseudo.extend_from_slice(&[0, 6, 0, 20]);
+        assert_eq!(checksum(&[&pseudo, &synack[20..]]), 0);
+
+        // a retransmitted SYN gets the same sequence number, without state
+        let (_, again) = tarpit_reply(&ip, &ip.tcp().unwrap(), &key).unwrap();
+        assert_eq!(&again[24..28], &synack[24..28]);
+
+        // and nothing else is answered
+        for flags in &[TcpFlags::ACK, TcpFlags::ACK | TcpFlags::PSH, TcpFlags::RST, TcpFlags::SYN | TcpFlags::ACK] {
+            let pkt = segment(false, *flags as u8, 1, 1, b"x");
+            let ip = IpPacket::V4(Ipv4Packet::new(&pkt).unwrap());
+            assert!(tarpit_reply(&ip, &ip.tcp().unwrap(), &key).is_none());
+        }
+    }
+}
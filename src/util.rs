@@ -4,15 +4,20 @@ extern crate hkdf;
 extern crate sha2;
 extern crate ipnetwork;
 
-use std::fs::File;
-use std::io::prelude::*;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, prelude::*};
 use std::io::BufReader;
 use std::error::Error;
+use std::net::IpAddr;
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::path::Path;
 
 use pnet::packet::Packet;
+use pnet::packet::ip::{IpNextHeaderProtocol, IpNextHeaderProtocols};
 use pnet::packet::tcp::{TcpOptionNumbers, TcpPacket};
 use pnet::packet::udp::UdpPacket;
-use pnet::packet::ipv4::Ipv4Packet;
+use pnet::packet::ipv4::{Ipv4Flags, Ipv4Packet};
 use pnet::packet::ipv6::Ipv6Packet;
 
 
@@ -22,23 +27,181 @@ pub enum IpPacket<'p> {
 }
 
 impl<'p> IpPacket<'p> {
+    /// The transport protocol and everything from its header on, past any v6
+    /// extension headers.
+    pub fn transport(&'p self) -> Result<(IpNextHeaderProtocol, &'p [u8]), V6ChainError> {
+        match self {
+            IpPacket::V4(v4) => Ok((v4.get_next_level_protocol(), v4.payload())),
+            IpPacket::V6(v6) => {
+                let payload = v6.payload();
+                let (next_header, offset) = walk_v6_chain(v6.get_next_header(), payload)?;
+                Ok((next_header, &payload[offset..]))
+            },
+        }
+    }
+
+    /// Source and destination addresses.
+    pub fn addresses(&self) -> (IpAddr, IpAddr) {
+        match self {
+            IpPacket::V4(v4) => (IpAddr::V4(v4.get_source()), IpAddr::V4(v4.get_destination())),
+            IpPacket::V6(v6) => (IpAddr::V6(v6.get_source()), IpAddr::V6(v6.get_destination())),
+        }
+    }
+
+    /// Where the packet is a fragment, which one.
+    pub fn fragment(&self) -> Option<Fragment> {
+        match self {
+            IpPacket::V4(v4) => {
+                let frag = Fragment {
+                    id: v4.get_identification() as u32,
+                    offset: v4.get_fragment_offset(),
+                    more: v4.get_flags() & Ipv4Flags::MoreFragments != 0,
+                };
+                if frag.offset != 0 || frag.more { Some(frag) } else { None }
+            },
+            IpPacket::V6(v6) => match walk_v6_headers(v6.get_next_header(), v6.payload()) {
+                Ok((_, _, frag)) => frag,
+                Err(_) => None,
+            },
+        }
+    }
+
     pub fn tcp(&'p self) -> Option<TcpPacket<'p>> {
-        let payload = match self {
-            IpPacket::V4(v4) => v4.payload(),
-            IpPacket::V6(v6) => v6.payload(),
-        };
-        TcpPacket::new(payload)
+        match self.transport() {
+            Ok((_, payload)) => TcpPacket::new(payload),
+            Err(_) => None,
+        }
     }
 
     pub fn udp(&'p self) -> Option<UdpPacket<'p>> {
-        let payload = match self {
-            IpPacket::V4(v4) => v4.payload(),
-            IpPacket::V6(v6) => v6.payload(),
+        match self.transport() {
+            Ok((_, payload)) => UdpPacket::new(payload),
+            Err(_) => None,
+        }
+    }
+
+    pub fn packet(&self) -> &[u8] {
+        match self {
+            IpPacket::V4(v4) => v4.packet(),
+            IpPacket::V6(v6) => v6.packet(),
+        }
+    }
+
+    /// The packet as a frame for a tun device.
+    pub fn tun_frame(&self) -> Vec<u8> {
+        let data = self.packet();
+        let mut tun_pkt = Vec::with_capacity(data.len()+4);
+        // These mystery bytes are a link-layer header; the kernel "receives"
+        // tun packets as if they were really physically "received". Since they
+        // weren't physically received, they do not have an Ethernet header. It
+        // looks like the tun setup has its own type of header, rather than just
+        // making up a fake Ethernet header.
+        let raw_hdr = match self {
+            IpPacket::V4(_p) => [0x00, 0x01, 0x08, 0x00],
+            IpPacket::V6(_p) => [0x00, 0x01, 0x86, 0xdd],
+        };
+        tun_pkt.extend_from_slice(&raw_hdr);
+        tun_pkt.extend_from_slice(data);
+        tun_pkt
+    }
+}
+
+// Most extension headers walked before giving up on a v6 packet.
+const V6_MAX_EXT_HEADERS: usize = 8;
+
+#[derive(Debug, PartialEq)]
+pub enum V6ChainError
+{
+    // A header runs past the end of the packet, or there are too many.
+    Unparseable,
+    // A fragment other than the first, which carries no transport header.
+    LaterFragment,
+}
+
+// The fragment header fields (v4 or v6) of a fragmented packet. The offset is
+// in 8 octet units; only the first fragment, at offset 0, has the transport
+// header.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fragment
+{
+    pub id: u32,
+    pub offset: u16,
+    pub more: bool,
+}
+
+// Walks the hop-by-hop, routing, fragment and destination options headers at
+// the start of a v6 payload. Returns the protocol that follows them and the
+// offset of its header in the payload.
+pub fn walk_v6_chain(first: IpNextHeaderProtocol, payload: &[u8])
+    -> Result<(IpNextHeaderProtocol, usize), V6ChainError>
+{
+    match walk_v6_headers(first, payload)? {
+        (_, _, Some(ref frag)) if frag.offset != 0 => Err(V6ChainError::LaterFragment),
+        (next_header, offset, _) => Ok((next_header, offset)),
+    }
+}
+
+// As walk_v6_chain, also returning the fragment header if there is one. The
+// walk stops after the fragment header of a later fragment, since what
+// follows it is the middle of the original payload.
+fn walk_v6_headers(first: IpNextHeaderProtocol, payload: &[u8])
+    -> Result<(IpNextHeaderProtocol, usize, Option<Fragment>), V6ChainError>
+{
+    let mut next_header = first;
+    let mut offset = 0;
+    let mut fragment = None;
+    for _ in 0..V6_MAX_EXT_HEADERS {
+        let len = match next_header {
+            IpNextHeaderProtocols::Hopopt |
+            IpNextHeaderProtocols::Ipv6Route |
+            IpNextHeaderProtocols::Ipv6Opts => {
+                // length in 8 octet units, not counting the first 8
+                match payload.get(offset + 1) {
+                    Some(l) => (*l as usize + 1) * 8,
+                    None => return Err(V6ChainError::Unparseable),
+                }
+            },
+            IpNextHeaderProtocols::Ipv6Frag => {
+                if payload.len() < offset + 8 {
+                    return Err(V6ChainError::Unparseable);
+                }
+                let frag = Fragment {
+                    id: deser_be_u32_slice(&payload[offset+4..offset+8]),
+                    offset: ((payload[offset+2] as u16) << 8 | payload[offset+3] as u16) >> 3,
+                    more: payload[offset+3] & 1 != 0,
+                };
+                fragment = Some(frag);
+                if frag.offset != 0 {
+                    return Ok((IpNextHeaderProtocol::new(payload[offset]), offset + 8, fragment));
+                }
+                8
+            },
+            _ => return Ok((next_header, offset, fragment)),
         };
-        UdpPacket::new(payload)
+        if payload.len() < offset + len {
+            return Err(V6ChainError::Unparseable);
+        }
+        next_header = IpNextHeaderProtocol::new(payload[offset]);
+        offset += len;
     }
+    Err(V6ChainError::Unparseable)
 }
 
+// Internet checksum (RFC 1071) over the concatenation of `parts`, each of an
+// even length but the last.
+pub fn checksum(parts: &[&[u8]]) -> u16 {
+    let mut sum: u32 = 0;
+    for part in parts.iter() {
+        for word in part.chunks(2) {
+            let hi = (word[0] as u32) << 8;
+            sum += if word.len() == 2 { hi | word[1] as u32 } else { hi };
+        }
+    }
+    while (sum >> 16) != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
 
 // Pass in a host-order IPv4 addr, get a String.
 #[inline]
@@ -111,6 +274,33 @@ pub fn tcp_seq_lt(a: u32, b: u32) -> bool
     }
 }
 
+/// A unix socket bound to `path` by `bind` that only our user can use,
+/// replacing whatever socket an earlier run left there. It is bound in a
+/// directory of its own (mode 0700) next to `path`, restricted, then moved
+/// into place, so it is never reachable with the mode the umask gave it.
+pub fn bind_private<T, F>(path: &Path, bind: F) -> io::Result<T>
+    where F: FnOnce(&Path) -> io::Result<T>
+{
+    let name = path.file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no file name"))?;
+    let mut staging_name = OsString::from(".");
+    staging_name.push(name);
+    staging_name.push(".bind");
+    let staging = path.with_file_name(staging_name);
+    // left by an earlier run that failed part way
+    let _ = fs::remove_dir_all(&staging);
+    fs::DirBuilder::new().mode(0o700).create(&staging)?;
+
+    let bound = staging.join("socket");
+    let res = bind(&bound).and_then(|socket| {
+        fs::set_permissions(&bound, fs::Permissions::from_mode(0o600))?;
+        fs::rename(&bound, path)?;
+        Ok(socket)
+    });
+    let _ = fs::remove_dir_all(&staging);
+    res
+}
+
 // Returns memory used by this process. Should be equivalent to the RES field of
 // top. Units are "kB", which I'm guessing is KiB.
 pub fn mem_used_kb() -> u64
@@ -253,4 +443,48 @@ mod tests {
     {
         assert!(mem_used_kb() > 0);
     }
+
+    #[test]
+    fn walk_v6_chain_finds_transport()
+    {
+        let tcp = [0u8; 20];
+        assert_eq!(walk_v6_chain(IpNextHeaderProtocols::Tcp, &tcp),
+                   Ok((IpNextHeaderProtocols::Tcp, 0)));
+
+        // hop-by-hop (8 bytes) -> destination options (16 bytes) -> first
+        // fragment -> tcp
+        let mut chain = vec![60, 0, 0, 0, 0, 0, 0, 0];
+        chain.extend_from_slice(&[44, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        chain.extend_from_slice(&[6, 0, 0, 1, 0, 0, 0, 1]);
+        chain.extend_from_slice(&tcp);
+        assert_eq!(walk_v6_chain(IpNextHeaderProtocols::Hopopt, &chain),
+                   Ok((IpNextHeaderProtocols::Tcp, 32)));
+
+        // later fragments have no transport header
+        let mut later = chain.clone();
+        later[26] = 0x05;
+        assert_eq!(walk_v6_chain(IpNextHeaderProtocols::Hopopt, &later),
+                   Err(V6ChainError::LaterFragment));
+
+        // truncated header
+        assert_eq!(walk_v6_chain(IpNextHeaderProtocols::Hopopt, &chain[..12]),
+                   Err(V6ChainError::Unparseable));
+        assert_eq!(walk_v6_chain(IpNextHeaderProtocols::Ipv6Route, &[]),
+                   Err(V6ChainError::Unparseable));
+
+        // fragment ids and offsets
+        chain[28..32].copy_from_slice(&[0x12, 0x34, 0x56, 0x78]);
+        later[28..32].copy_from_slice(&[0x12, 0x34, 0x56, 0x78]);
+        assert_eq!(walk_v6_headers(IpNextHeaderProtocols::Hopopt, &chain).unwrap().2,
+                   Some(Fragment { id: 0x12345678, offset: 0, more: true }));
+        assert_eq!(walk_v6_headers(IpNextHeaderProtocols::Hopopt, &later),
+                   Ok((IpNextHeaderProtocols::Tcp, 32,
+                       Some(Fragment { id: 0x12345678, offset: 0xa0, more: true }))));
+        assert_eq!(walk_v6_headers(IpNextHeaderProtocols::Tcp, &tcp), Ok((IpNextHeaderProtocols::Tcp, 0, None)));
+
+        // a loop of headers is cut off
+        let looped = vec![0u8; 8 * (V6_MAX_EXT_HEADERS + 1)];
+        assert_eq!(walk_v6_chain(IpNextHeaderProtocols::Hopopt, &looped),
+                   Err(V6ChainError::Unparseable));
+    }
 }
@@ -0,0 +1,204 @@
+//
+// VLAN Tags
+//
+// Span ports deliver traffic with one 802.1Q tag, or two (802.1ad QinQ: an
+// outer service tag and an inner customer tag). The tags sit between the
+// Ethernet header and the IP packet:
+//
+//   dst | src | TPID | TCI | [TPID | TCI] | ethertype | IP packet
+//
+// where the TPID is 0x8100 (802.1Q), 0x88a8 (802.1ad) or 0x9100 (pre-standard
+// QinQ) and the low 12 bits of the TCI are the VLAN ID. Up to MAX_TAGS tags are
+// skipped before IP parsing. With VlanConfig.expected_tags (DETECTOR_VLAN_TAGS)
+// set, packets carrying any other number of tags are dropped, so traffic from
+// a misconfigured mirror session doesn't get mixed in.
+//
+// Packets are counted per tag stack (the VLAN IDs, outermost first) for the
+// periodic report, for up to MAX_STACKS distinct stacks per period.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use pnet::packet::ethernet::EtherType;
+
+/// Most tags skipped before the IP header.
+pub const MAX_TAGS: usize = 2;
+
+// Distinct tag stacks counted per report period, the packets of others are
+// only counted in total.
+const MAX_STACKS: usize = 256;
+
+const TPIDS: [EtherType; 3] = [EtherType(0x8100), EtherType(0x88a8), EtherType(0x9100)];
+
+#[derive(Clone, Default)]
+pub struct VlanConfig
+{
+    // Number of tags every packet is expected to carry, others are dropped.
+    // None accepts 0 to MAX_TAGS.
+    pub expected_tags: Option<usize>,
+}
+
+/// The VLAN IDs of a packet's tags, outermost first.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TagStack
+{
+    len: usize,
+    ids: [u16; MAX_TAGS],
+}
+
+impl TagStack
+{
+    pub fn ids(&self) -> &[u16] {
+        &self.ids[..self.len]
+    }
+}
+
+impl fmt::Display for TagStack
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, id) in self.ids().iter().enumerate() {
+            if i > 0 {
+                write!(f, ".")?;
+            }
+            write!(f, "{}", id)?;
+        }
+        Ok(())
+    }
+}
+
+/// Skips the VLAN tags at the start of an Ethernet payload of type
+/// `ethertype`. Returns the ethertype following them, their IDs and the rest
+/// of the payload; None if there are more than MAX_TAGS or the payload ends
+/// inside them.
+pub fn strip_tags(ethertype: EtherType, payload: &[u8]) -> Option<(EtherType, TagStack, &[u8])> {
+    let mut stack = TagStack { len: 0, ids: [0; MAX_TAGS] };
+    let mut ethertype = ethertype;
+    let mut rest = payload;
+    while TPIDS.contains(&ethertype) {
+        if stack.len == MAX_TAGS || rest.len() < 4 {
+            return None
+        }
+        stack.ids[stack.len] = u16::from_be_bytes([rest[0], rest[1]]) & 0x0fff;
+        stack.len += 1;
+        ethertype = EtherType(u16::from_be_bytes([rest[2], rest[3]]));
+        rest = &rest[4..];
+    }
+    Some((ethertype, stack, rest))
+}
+
+pub struct Vlans
+{
+    config: VlanConfig,
+
+    tagged: HashMap<TagStack, u64>,
+    untagged: u64,
+    // Tagged packets of stacks past MAX_STACKS.
+    other: u64,
+    unexpected: u64,
+    malformed: u64,
+}
+
+impl Vlans
+{
+    pub fn new(config: VlanConfig) -> Vlans {
+        Vlans {
+            config: config,
+            tagged: HashMap::new(),
+            untagged: 0,
+            other: 0,
+            unexpected: 0,
+            malformed: 0,
+        }
+    }
+
+    /// The ethertype and payload past the tags of an Ethernet frame with
+    /// `ethertype` and `payload`, counting it under its tag stack. None,
+    /// counted, if its tags can't be parsed or it doesn't carry the expected
+    /// number.
+    pub fn untag<'p>(&mut self, ethertype: EtherType, payload: &'p [u8]) -> Option<(EtherType, &'p [u8])> {
+        let (ethertype, stack, rest) = match strip_tags(ethertype, payload) {
+            Some(stripped) => stripped,
+            None => {
+                self.malformed += 1;
+                return None
+            },
+        };
+        match self.config.expected_tags {
+            Some(n) if n != stack.len => {
+                self.unexpected += 1;
+                return None
+            },
+            _ => {},
+        }
+        if stack.len == 0 {
+            self.untagged += 1;
+        } else if let Some(n) = self.tagged.get_mut(&stack) {
+            *n += 1;
+        } else if self.tagged.len() < MAX_STACKS {
+            self.tagged.insert(stack, 1);
+        } else {
+            self.other += 1;
+        }
+        Some((ethertype, rest))
+    }
+
+    /// Packets per tag stack since the last call, and those dropped.
+    pub fn take_report(&mut self) -> String {
+        let mut tagged: Vec<(TagStack, u64)> = self.tagged.drain().collect();
+        tagged.sort_by(|a, b| a.0.ids().cmp(b.0.ids()));
+        let mut report = format!("vlan untagged {} unexpected {} malformed {} other {}",
+            self.untagged, self.unexpected, self.malformed, self.other);
+        for (stack, n) in tagged.iter() {
+            report.push_str(&format!(" {} {}", stack, n));
+        }
+        self.untagged = 0;
+        self.other = 0;
+        self.unexpected = 0;
+        self.malformed = 0;
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vlan::*;
+    use pnet::packet::ethernet::EtherTypes;
+
+    #[test]
+    fn test_vlan_untag() {
+        let ip = [0x45, 0, 0, 20];
+        // one 802.1Q tag, VLAN 100 with priority bits set
+        let mut one = vec![0xe0, 100, 0x08, 0x00];
+        one.extend_from_slice(&ip);
+        // 802.1ad service tag 10 around customer tag 0x123
+        let mut two = vec![0x00, 10, 0x81, 0x00, 0x01, 0x23, 0x86, 0xdd];
+        two.extend_from_slice(&ip);
+        let mut three = vec![0x00, 1, 0x88, 0xa8];
+        three.extend_from_slice(&two);
+
+        let (ethertype, stack, rest) = strip_tags(EtherType(0x8100), &one).unwrap();
+        assert_eq!((ethertype, stack.ids(), rest), (EtherTypes::Ipv4, &[100][..], &ip[..]));
+        let (ethertype, stack, rest) = strip_tags(EtherType(0x88a8), &two).unwrap();
+        assert_eq!((ethertype, stack.ids(), rest), (EtherTypes::Ipv6, &[10, 0x123][..], &ip[..]));
+        assert_eq!(stack.to_string(), "10.291");
+        assert_eq!(strip_tags(EtherTypes::Ipv4, &ip).unwrap().1.ids().len(), 0);
+        assert!(strip_tags(EtherType(0x88a8), &three).is_none());
+        assert!(strip_tags(EtherType(0x8100), &one[..3]).is_none());
+
+        let mut vlans = Vlans::new(VlanConfig::default());
+        assert_eq!(vlans.untag(EtherType(0x8100), &one), Some((EtherTypes::Ipv4, &ip[..])));
+        assert!(vlans.untag(EtherType(0x9100), &two).is_some());
+        assert!(vlans.untag(EtherType(0x8100), &one).is_some());
+        assert!(vlans.untag(EtherTypes::Ipv4, &ip).is_some());
+        assert!(vlans.untag(EtherType(0x88a8), &three).is_none());
+        assert_eq!(vlans.take_report(), "vlan untagged 1 unexpected 0 malformed 1 other 0 10.291 1 100 2");
+        assert_eq!(vlans.take_report(), "vlan untagged 0 unexpected 0 malformed 0 other 0");
+
+        // only double tagged packets expected
+        let mut vlans = Vlans::new(VlanConfig { expected_tags: Some(2) });
+        assert!(vlans.untag(EtherType(0x8100), &one).is_none());
+        assert!(vlans.untag(EtherTypes::Ipv4, &ip).is_none());
+        assert_eq!(vlans.untag(EtherType(0x88a8), &two), Some((EtherTypes::Ipv6, &ip[..])));
+        assert_eq!(vlans.take_report(), "vlan untagged 0 unexpected 2 malformed 0 other 0 10.291 1");
+    }
+}
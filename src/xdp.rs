@@ -0,0 +1,752 @@
+//
+// XDP Pre-filter
+//
+// Most of what crosses the tap is neither to nor from phantom space, and the
+// detector throws it away only after it has been copied to userspace and
+// parsed. With an XDP interface configured (DETECTOR_XDP_INTERFACE) the
+// detector attaches a small XDP program to it that drops that traffic in the
+// driver instead. A packet is passed on if
+//
+//  - its destination or source address is in one of the phantom subnets
+//    (PHANTOM_SUBNET_LOCATION, every generation, kept in an LPM trie map per
+//    address family);
+//  - it's TCP or UDP to one of DETECTOR_XDP_PASS_PORTS, whatever the
+//    addresses. Registrations that arrive on the tap (TLS to decoys on 443,
+//    DNS on 53) need their ports listed here, as does the station's own
+//    management traffic (SSH on 22). Those three are passed by default;
+//  - it isn't IPv4 or IPv6 over plain Ethernet (ARP, VLAN tagged frames, ...);
+//    those are left to the detector;
+//  - or it's one of the sampled packets (one in DETECTOR_XDP_SAMPLE of the
+//    rest), so the detector's view of the tap as a whole stays measurable.
+//
+// Everything else is dropped. The program is assembled here and loaded with
+// the bpf syscall, so neither clang nor libbpf is needed; attaching it uses a
+// BPF link (Linux 5.9 or later) and needs CAP_NET_ADMIN and CAP_BPF (or
+// CAP_SYS_ADMIN).
+//
+// - There is one filter per interface but a detector process per core. Every
+//   core tries to attach; the first one succeeds and owns the filter, the
+//   others find it attached and leave it alone. The link is released when
+//   the owner exits, so a detector that dies leaves the tap unfiltered rather
+//   than blackholed.
+// - The owner checks the phantom subnets file once a second and, when it
+//   changes, brings the maps in line: new subnets are added before old ones
+//   are removed, so phantom traffic is never dropped in between. Once the
+//   station pushes phantom subnets (config_push.rs) the file is no longer
+//   checked and each pushed set is applied the same way.
+// - The filter only sees what goes through the kernel's driver: PF_RING ZC
+//   and DPDK capture bypass it.
+// - The owner reports how many packets were passed for each reason, and
+//   dropped, per period.
+
+use std::fs;
+use std::io;
+use std::mem;
+use std::time::{Duration, Instant, SystemTime};
+
+use ipnetwork::IpNetwork;
+use libc;
+
+use error::{DetectorError, DetectorResult, error_chain};
+use phantom_subnets::PhantomSubnets;
+
+// How often the owner checks whether the phantom subnets file changed.
+const RELOAD_CHECK: Duration = Duration::from_secs(1);
+// Entries of each LPM trie.
+const MAX_SUBNETS: u32 = 65536;
+const MAX_PASS_PORTS: u32 = 64;
+// Passed unless DETECTOR_XDP_PASS_PORTS lists others: SSH, DNS and TLS.
+pub const DEFAULT_PASS_PORTS: [u16; 3] = [22, 53, 443];
+const VERIFIER_LOG_LEN: usize = 64 * 1024;
+
+// What the program counts, one slot each in the counters map.
+const COUNT_PHANTOM: i32 = 0;
+const COUNT_PORT: i32 = 1;
+const COUNT_SAMPLED: i32 = 2;
+const COUNT_DROPPED: i32 = 3;
+const COUNT_OTHER: i32 = 4;
+const COUNTERS: usize = 5;
+
+// linux/bpf.h
+const BPF_MAP_CREATE: libc::c_int = 0;
+const BPF_MAP_LOOKUP_ELEM: libc::c_int = 1;
+const BPF_MAP_UPDATE_ELEM: libc::c_int = 2;
+const BPF_MAP_DELETE_ELEM: libc::c_int = 3;
+const BPF_PROG_LOAD: libc::c_int = 5;
+const BPF_LINK_CREATE: libc::c_int = 28;
+const BPF_MAP_TYPE_HASH: u32 = 1;
+const BPF_MAP_TYPE_ARRAY: u32 = 2;
+const BPF_MAP_TYPE_LPM_TRIE: u32 = 11;
+const BPF_F_NO_PREALLOC: u32 = 1;
+const BPF_PROG_TYPE_XDP: u32 = 6;
+const BPF_XDP: u32 = 37;
+const BPF_PSEUDO_MAP_FD: u8 = 1;
+const BPF_FUNC_MAP_LOOKUP_ELEM: i32 = 1;
+const BPF_FUNC_GET_PRANDOM_U32: i32 = 7;
+const XDP_DROP: i32 = 1;
+const XDP_PASS: i32 = 2;
+// linux/if_link.h
+const XDP_FLAGS_SKB_MODE: u32 = 2;
+const XDP_FLAGS_DRV_MODE: u32 = 4;
+
+// Instruction encodings used by the program (class | size/op | source).
+const LDX_W: u8 = 0x61;
+const LDX_H: u8 = 0x69;
+const LDX_B: u8 = 0x71;
+const ST_W: u8 = 0x62;
+const STX_W: u8 = 0x63;
+const STX_H: u8 = 0x6b;
+const XADD_DW: u8 = 0xdb;
+const LD_IMM64: u8 = 0x18;
+const ADD64_K: u8 = 0x07;
+const ADD64_X: u8 = 0x0f;
+const AND64_K: u8 = 0x57;
+const LSH64_K: u8 = 0x67;
+const RSH64_K: u8 = 0x77;
+const MOV64_K: u8 = 0xb7;
+const MOV64_X: u8 = 0xbf;
+const JA: u8 = 0x05;
+const JEQ_K: u8 = 0x15;
+const JGT_X: u8 = 0x2d;
+const JNE_K: u8 = 0x55;
+const JLT_K: u8 = 0xa5;
+const CALL: u8 = 0x85;
+const EXIT: u8 = 0x95;
+
+const R0: u8 = 0;
+const R1: u8 = 1;
+const R2: u8 = 2;
+const R3: u8 = 3;
+const R4: u8 = 4;
+const R5: u8 = 5;
+const R6: u8 = 6;
+const R7: u8 = 7;
+const R8: u8 = 8;
+const R10: u8 = 10;
+
+// Packet layout, and EtherTypes as loaded from the packet on a little endian
+// host.
+const ETH_HLEN: i32 = 14;
+const ETH_P_IP: i32 = 0x0008;
+const ETH_P_IPV6: i32 = 0xdd86;
+const IPPROTO_TCP: i32 = 6;
+const IPPROTO_UDP: i32 = 17;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum XdpMode
+{
+    // In the driver, before any skb is allocated. Needs driver support.
+    Driver,
+    // After the skb is allocated, on any interface. Much slower, for testing.
+    Generic,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct XdpConfig
+{
+    pub interface: String,
+    pub mode: XdpMode,
+    // phantom_subnets.toml
+    pub subnets_path: String,
+    // Pass one in `sample` of the packets that would be dropped (0 = none).
+    pub sample: u32,
+    // TCP and UDP destination ports passed whatever the addresses.
+    pub pass_ports: Vec<u16>,
+}
+
+impl XdpConfig
+{
+    pub fn new(interface: &str, subnets_path: &str) -> XdpConfig {
+        XdpConfig {
+            interface: interface.to_string(),
+            mode: XdpMode::Driver,
+            subnets_path: subnets_path.to_string(),
+            sample: 0,
+            pass_ports: DEFAULT_PASS_PORTS.to_vec(),
+        }
+    }
+}
+
+fn xdp_error(context: &'static str, source: io::Error) -> DetectorError {
+    DetectorError::Xdp { context: context, source: source }
+}
+
+/// Whether attaching failed because a filter (another core's, usually) is
+/// already attached to the interface.
+pub fn already_attached(err: &DetectorError) -> bool {
+    match *err {
+        DetectorError::Xdp { context: "attach", ref source } => source.raw_os_error() == Some(libc::EBUSY),
+        _ => false,
+    }
+}
+
+// A bpf object (map, program or link), closed on drop.
+struct Fd(libc::c_int);
+
+impl Drop for Fd
+{
+    fn drop(&mut self) {
+        unsafe { libc::close(self.0); }
+    }
+}
+
+fn bpf<T>(cmd: libc::c_int, attr: &mut T) -> io::Result<libc::c_int> {
+    let rc = unsafe {
+        libc::syscall(libc::SYS_bpf, cmd, attr as *mut T, mem::size_of::<T>())
+    };
+    if rc < 0 {
+        return Err(io::Error::last_os_error())
+    }
+    Ok(rc as libc::c_int)
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct MapCreateAttr
+{
+    map_type: u32,
+    key_size: u32,
+    value_size: u32,
+    max_entries: u32,
+    map_flags: u32,
+    inner_map_fd: u32,
+    numa_node: u32,
+    map_name: [u8; 16],
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct MapElemAttr
+{
+    map_fd: u32,
+    pad: u32,
+    key: u64,
+    value: u64,
+    flags: u64,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct ProgLoadAttr
+{
+    prog_type: u32,
+    insn_cnt: u32,
+    insns: u64,
+    license: u64,
+    log_level: u32,
+    log_size: u32,
+    log_buf: u64,
+    kern_version: u32,
+    prog_flags: u32,
+    prog_name: [u8; 16],
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct LinkCreateAttr
+{
+    prog_fd: u32,
+    target_ifindex: u32,
+    attach_type: u32,
+    flags: u32,
+}
+
+fn bpf_name(name: &str) -> [u8; 16] {
+    let mut out = [0; 16];
+    for (o, b) in out.iter_mut().zip(name.bytes().take(15)) {
+        *o = b;
+    }
+    out
+}
+
+fn create_map(name: &str, map_type: u32, key_size: u32, value_size: u32, max_entries: u32, flags: u32)
+    -> DetectorResult<Fd>
+{
+    let mut attr = MapCreateAttr {
+        map_type: map_type,
+        key_size: key_size,
+        value_size: value_size,
+        max_entries: max_entries,
+        map_flags: flags,
+        map_name: bpf_name(name),
+        ..MapCreateAttr::default()
+    };
+    bpf(BPF_MAP_CREATE, &mut attr).map(Fd).map_err(|e| xdp_error("map create", e))
+}
+
+fn map_update(map: &Fd, key: &[u8], value: &[u8]) -> io::Result<()> {
+    let mut attr = MapElemAttr {
+        map_fd: map.0 as u32,
+        key: key.as_ptr() as u64,
+        value: value.as_ptr() as u64,
+        ..MapElemAttr::default()
+    };
+    bpf(BPF_MAP_UPDATE_ELEM, &mut attr).map(|_| ())
+}
+
+fn map_delete(map: &Fd, key: &[u8]) -> io::Result<()> {
+    let mut attr = MapElemAttr {
+        map_fd: map.0 as u32,
+        key: key.as_ptr() as u64,
+        ..MapElemAttr::default()
+    };
+    bpf(BPF_MAP_DELETE_ELEM, &mut attr).map(|_| ())
+}
+
+fn map_lookup_u64(map: &Fd, key: u32) -> io::Result<u64> {
+    let mut value: u64 = 0;
+    let mut attr = MapElemAttr {
+        map_fd: map.0 as u32,
+        key: &key as *const u32 as u64,
+        value: &mut value as *mut u64 as u64,
+        ..MapElemAttr::default()
+    };
+    bpf(BPF_MAP_LOOKUP_ELEM, &mut attr)?;
+    Ok(value)
+}
+
+/// LPM trie key of `net`: prefix length, then the network address.
+pub fn subnet_key(net: &IpNetwork) -> Vec<u8> {
+    let mut key = Vec::with_capacity(20);
+    key.extend_from_slice(&(net.prefix() as u32).to_ne_bytes());
+    match *net {
+        IpNetwork::V4(n) => key.extend_from_slice(&n.network().octets()),
+        IpNetwork::V6(n) => key.extend_from_slice(&n.network().octets()),
+    }
+    key
+}
+
+/// Subnets of `wanted` not in `current`, and subnets of `current` not in
+/// `wanted`.
+pub fn subnet_changes(current: &[IpNetwork], wanted: &[IpNetwork]) -> (Vec<IpNetwork>, Vec<IpNetwork>) {
+    let added = wanted.iter().filter(|n| !current.contains(n)).cloned().collect();
+    let removed = current.iter().filter(|n| !wanted.contains(n)).cloned().collect();
+    (added, removed)
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Insn
+{
+    pub code: u8,
+    // dst in the low nibble, src in the high one
+    pub regs: u8,
+    pub off: i16,
+    pub imm: i32,
+}
+
+// Builds a program, resolving jumps to labels once it's complete.
+struct Asm
+{
+    insns: Vec<Insn>,
+    labels: Vec<(&'static str, usize)>,
+    jumps: Vec<(usize, &'static str)>,
+}
+
+impl Asm
+{
+    fn new() -> Asm {
+        Asm { insns: Vec::new(), labels: Vec::new(), jumps: Vec::new() }
+    }
+
+    fn op(&mut self, code: u8, dst: u8, src: u8, off: i16, imm: i32) {
+        self.insns.push(Insn { code: code, regs: src << 4 | dst, off: off, imm: imm });
+    }
+
+    fn label(&mut self, name: &'static str) {
+        self.labels.push((name, self.insns.len()));
+    }
+
+    fn jump(&mut self, code: u8, dst: u8, src: u8, imm: i32, target: &'static str) {
+        self.jumps.push((self.insns.len(), target));
+        self.op(code, dst, src, 0, imm);
+    }
+
+    fn load_map(&mut self, dst: u8, map_fd: i32) {
+        self.op(LD_IMM64, dst, BPF_PSEUDO_MAP_FD, 0, map_fd);
+        self.op(0, 0, 0, 0, 0);
+    }
+
+    // r0 = the value for the key at r10 + `key`, or 0.
+    fn lookup(&mut self, map_fd: i32, key: i16) {
+        self.load_map(R1, map_fd);
+        self.op(MOV64_X, R2, R10, 0, 0);
+        self.op(ADD64_K, R2, 0, 0, key as i32);
+        self.op(CALL, 0, 0, 0, BPF_FUNC_MAP_LOOKUP_ELEM);
+    }
+
+    // Counts the packet in slot `counter` and returns `action`.
+    fn count_and_exit(&mut self, counters_fd: i32, counter: i32, action: i32) {
+        self.op(ST_W, R10, 0, -28, counter);
+        self.lookup(counters_fd, -28);
+        self.op(JEQ_K, R0, 0, 2, 0);
+        self.op(MOV64_K, R1, 0, 0, 1);
+        self.op(XADD_DW, R0, R1, 0, 0);
+        self.op(MOV64_K, R0, 0, 0, action);
+        self.op(EXIT, 0, 0, 0, 0);
+    }
+
+    fn finish(mut self) -> Vec<Insn> {
+        for &(at, target) in self.jumps.iter() {
+            let to = self.labels.iter().find(|l| l.0 == target)
+                .unwrap_or_else(|| panic!("xdp program jumps to undefined label {}", target)).1;
+            self.insns[at].off = (to as isize - at as isize - 1) as i16;
+        }
+        self.insns
+    }
+}
+
+// File descriptors of the program's maps.
+pub struct MapFds
+{
+    pub v4: i32,
+    pub v6: i32,
+    // None if no ports are passed.
+    pub ports: Option<i32>,
+    pub counters: i32,
+}
+
+// Random 31 bit numbers below this come up one time in `sample`.
+fn sample_threshold(sample: u32) -> i32 {
+    ::std::cmp::min((1u64 << 31) / sample as u64, i32::MAX as u64) as i32
+}
+
+/// The filter program for `maps`, passing one in `sample` of the packets it
+/// would drop (none if 0).
+pub fn program(maps: &MapFds, sample: u32) -> Vec<Insn> {
+    let mut a = Asm::new();
+    // r6 = ctx, r7 = data, r3 = data_end (the helpers clobber r1-r5)
+    a.op(MOV64_X, R6, R1, 0, 0);
+    a.op(LDX_W, R7, R6, 0, 0);
+    a.op(LDX_W, R3, R6, 4, 0);
+    a.op(MOV64_X, R4, R7, 0, 0);
+    a.op(ADD64_K, R4, 0, 0, ETH_HLEN);
+    a.jump(JGT_X, R4, R3, 0, "other");
+    a.op(LDX_H, R5, R7, 12, 0);
+    a.jump(JEQ_K, R5, 0, ETH_P_IP, "v4");
+    a.jump(JEQ_K, R5, 0, ETH_P_IPV6, "v6");
+    a.jump(JA, 0, 0, 0, "other");
+
+    // Key at r10-8: prefix length 32, then the address. Destination first.
+    a.label("v4");
+    a.op(MOV64_X, R4, R7, 0, 0);
+    a.op(ADD64_K, R4, 0, 0, ETH_HLEN + 20);
+    a.jump(JGT_X, R4, R3, 0, "other");
+    a.op(ST_W, R10, 0, -8, 32);
+    for &addr in [ETH_HLEN + 16, ETH_HLEN + 12].iter() {
+        a.op(LDX_W, R5, R7, addr as i16, 0);
+        a.op(STX_W, R10, R5, -4, 0);
+        a.lookup(maps.v4, -8);
+        a.jump(JNE_K, R0, 0, 0, "phantom");
+    }
+    // r8 = protocol, r4 = transport header
+    a.op(LDX_B, R8, R7, (ETH_HLEN + 9) as i16, 0);
+    a.op(LDX_B, R5, R7, ETH_HLEN as i16, 0);
+    a.op(AND64_K, R5, 0, 0, 0x0f);
+    a.op(LSH64_K, R5, 0, 0, 2);
+    a.op(MOV64_X, R4, R7, 0, 0);
+    a.op(ADD64_X, R4, R5, 0, 0);
+    a.op(ADD64_K, R4, 0, 0, ETH_HLEN);
+    a.jump(JA, 0, 0, 0, "transport");
+
+    // Key at r10-20: prefix length 128, then the address.
+    a.label("v6");
+    a.op(MOV64_X, R4, R7, 0, 0);
+    a.op(ADD64_K, R4, 0, 0, ETH_HLEN + 40);
+    a.jump(JGT_X, R4, R3, 0, "other");
+    a.op(ST_W, R10, 0, -20, 128);
+    for &addr in [ETH_HLEN + 24, ETH_HLEN + 8].iter() {
+        for i in 0..4 {
+            a.op(LDX_W, R5, R7, (addr + 4*i) as i16, 0);
+            a.op(STX_W, R10, R5, (-16 + 4*i) as i16, 0);
+        }
+        a.lookup(maps.v6, -20);
+        a.jump(JNE_K, R0, 0, 0, "phantom");
+    }
+    // Only a transport header right after the fixed one is looked at.
+    a.op(LDX_B, R8, R7, (ETH_HLEN + 6) as i16, 0);
+    a.op(MOV64_X, R4, R7, 0, 0);
+    a.op(ADD64_K, R4, 0, 0, ETH_HLEN + 40);
+
+    a.label("transport");
+    if let Some(ports) = maps.ports {
+        a.jump(JEQ_K, R8, 0, IPPROTO_TCP, "ports");
+        a.jump(JNE_K, R8, 0, IPPROTO_UDP, "miss");
+        a.label("ports");
+        a.op(LDX_W, R3, R6, 4, 0);
+        a.op(MOV64_X, R1, R4, 0, 0);
+        a.op(ADD64_K, R1, 0, 0, 4);
+        a.jump(JGT_X, R1, R3, 0, "miss");
+        a.op(LDX_H, R5, R4, 2, 0);
+        a.op(STX_H, R10, R5, -24, 0);
+        a.lookup(ports, -24);
+        a.jump(JNE_K, R0, 0, 0, "port");
+    }
+
+    a.label("miss");
+    if sample > 0 {
+        // A 31 bit random number, below the threshold one time in `sample`
+        a.op(CALL, 0, 0, 0, BPF_FUNC_GET_PRANDOM_U32);
+        a.op(RSH64_K, R0, 0, 0, 1);
+        a.jump(JLT_K, R0, 0, sample_threshold(sample), "sampled");
+    }
+    a.count_and_exit(maps.counters, COUNT_DROPPED, XDP_DROP);
+    if sample > 0 {
+        a.label("sampled");
+        a.count_and_exit(maps.counters, COUNT_SAMPLED, XDP_PASS);
+    }
+    a.label("phantom");
+    a.count_and_exit(maps.counters, COUNT_PHANTOM, XDP_PASS);
+    if maps.ports.is_some() {
+        a.label("port");
+        a.count_and_exit(maps.counters, COUNT_PORT, XDP_PASS);
+    }
+    a.label("other");
+    a.count_and_exit(maps.counters, COUNT_OTHER, XDP_PASS);
+    a.finish()
+}
+
+fn load_program(insns: &[Insn]) -> DetectorResult<Fd> {
+    let license = b"GPL\0";
+    let mut log = vec![0u8; VERIFIER_LOG_LEN];
+    let mut attr = ProgLoadAttr {
+        prog_type: BPF_PROG_TYPE_XDP,
+        insn_cnt: insns.len() as u32,
+        insns: insns.as_ptr() as u64,
+        license: license.as_ptr() as u64,
+        log_level: 1,
+        log_size: log.len() as u32,
+        log_buf: log.as_mut_ptr() as u64,
+        prog_name: bpf_name("conjure_filter"),
+        ..ProgLoadAttr::default()
+    };
+    bpf(BPF_PROG_LOAD, &mut attr).map(Fd).map_err(|e| {
+        // The verifier's reason is the last line of its log
+        let end = log.iter().position(|&b| b == 0).unwrap_or(log.len());
+        let text = String::from_utf8_lossy(&log[..end]);
+        let reason = text.lines().rev().find(|l| !l.is_empty()).unwrap_or("").to_string();
+        xdp_error("load", io::Error::new(e.kind(), format!("{}: {}", e, reason)))
+    })
+}
+
+pub struct XdpFilter
+{
+    config: XdpConfig,
+    v4: Fd,
+    v6: Fd,
+    counters: Fd,
+    // Kept for as long as the filter should stay attached.
+    _ports: Option<Fd>,
+    _prog: Fd,
+    _link: Fd,
+    subnets: Vec<IpNetwork>,
+    subnets_modified: Option<SystemTime>,
+    // Set once the station pushed subnets, the file is then ignored.
+    pushed: bool,
+    last_check: Instant,
+    last_counts: [u64; COUNTERS],
+}
+
+impl XdpFilter
+{
+    /// Loads the filter with the subnets in `config.subnets_path` and attaches
+    /// it to `config.interface`. See already_attached for the error when
+    /// another core was first.
+    pub fn attach(config: XdpConfig) -> DetectorResult<XdpFilter> {
+        let v4 = create_map("conjure_v4", BPF_MAP_TYPE_LPM_TRIE, 8, 1, MAX_SUBNETS, BPF_F_NO_PREALLOC)?;
+        let v6 = create_map("conjure_v6", BPF_MAP_TYPE_LPM_TRIE, 20, 1, MAX_SUBNETS, BPF_F_NO_PREALLOC)?;
+        let counters = create_map("conjure_counts", BPF_MAP_TYPE_ARRAY, 4, 8, COUNTERS as u32, 0)?;
+        let ports = match config.pass_ports.is_empty() {
+            true => None,
+            false => {
+                let map = create_map("conjure_ports", BPF_MAP_TYPE_HASH, 2, 1, MAX_PASS_PORTS, 0)?;
+                for port in config.pass_ports.iter() {
+                    map_update(&map, &port.to_be_bytes(), &[1]).map_err(|e| xdp_error("port update", e))?;
+                }
+                Some(map)
+            },
+        };
+
+        let (subnets, modified) = load_subnets(&config.subnets_path)?;
+        let mut current = Vec::new();
+        sync_subnets(&v4, &v6, &mut current, &subnets)?;
+
+        let fds = MapFds {
+            v4: v4.0,
+            v6: v6.0,
+            ports: ports.as_ref().map(|p| p.0),
+            counters: counters.0,
+        };
+        let prog = load_program(&program(&fds, config.sample))?;
+
+        let ifindex = {
+            let name = ::std::ffi::CString::new(config.interface.clone())
+                .map_err(|_| xdp_error("interface", io::Error::from(io::ErrorKind::InvalidInput)))?;
+            unsafe { libc::if_nametoindex(name.as_ptr()) }
+        };
+        if ifindex == 0 {
+            return Err(xdp_error("interface", io::Error::last_os_error()))
+        }
+        let mut attr = LinkCreateAttr {
+            prog_fd: prog.0 as u32,
+            target_ifindex: ifindex,
+            attach_type: BPF_XDP,
+            flags: match config.mode {
+                XdpMode::Driver => XDP_FLAGS_DRV_MODE,
+                XdpMode::Generic => XDP_FLAGS_SKB_MODE,
+            },
+        };
+        let link = bpf(BPF_LINK_CREATE, &mut attr).map(Fd).map_err(|e| xdp_error("attach", e))?;
+
+        Ok(XdpFilter {
+            config: config,
+            v4: v4,
+            v6: v6,
+            counters: counters,
+            _ports: ports,
+            _prog: prog,
+            _link: link,
+            subnets: current,
+            subnets_modified: modified,
+            pushed: false,
+            last_check: Instant::now(),
+            last_counts: [0; COUNTERS],
+        })
+    }
+
+    pub fn config(&self) -> &XdpConfig {
+        &self.config
+    }
+
+    /// Passes exactly the phantom subnets `subnets` from now on. Returns how
+    /// many were added and removed.
+    pub fn set_subnets(&mut self, subnets: &[IpNetwork]) -> DetectorResult<(usize, usize)> {
+        sync_subnets(&self.v4, &self.v6, &mut self.subnets, subnets)
+    }
+
+    /// Passes the phantom subnets the station pushed from now on, ignoring
+    /// the file.
+    pub fn set_pushed_subnets(&mut self, subnets: &[IpNetwork]) -> DetectorResult<(usize, usize)> {
+        self.pushed = true;
+        self.set_subnets(subnets)
+    }
+
+    /// Reloads the phantom subnets file if it changed since it was last
+    /// loaded, checking at most once a second.
+    pub fn refresh(&mut self, now: Instant) {
+        if self.pushed || now.duration_since(self.last_check) < RELOAD_CHECK {
+            return
+        }
+        self.last_check = now;
+        let modified = fs::metadata(&self.config.subnets_path).and_then(|m| m.modified()).ok();
+        if modified.is_none() || modified == self.subnets_modified {
+            return
+        }
+        match load_subnets(&self.config.subnets_path) {
+            Ok((subnets, modified)) => {
+                self.subnets_modified = modified;
+                match self.set_subnets(&subnets) {
+                    Ok((added, removed)) => info!("xdp filter phantom subnets reloaded, {} added {} removed", added, removed),
+                    Err(e) => error!("xdp filter phantom subnets not reloaded: {}", error_chain(&e)),
+                }
+            },
+            Err(e) => error!("xdp filter phantom subnets not reloaded: {}", error_chain(&e)),
+        }
+    }
+
+    /// Packets passed for each reason, and dropped, since the last call.
+    pub fn take_report(&mut self) -> String {
+        let mut delta = [0u64; COUNTERS];
+        for (i, (d, last)) in delta.iter_mut().zip(self.last_counts.iter_mut()).enumerate() {
+            let count = map_lookup_u64(&self.counters, i as u32).unwrap_or(*last);
+            *d = count.wrapping_sub(*last);
+            *last = count;
+        }
+        format!("xdp filter {} passed phantom {} port {} sampled {} other {} dropped {} subnets {}",
+            self.config.interface,
+            delta[COUNT_PHANTOM as usize], delta[COUNT_PORT as usize], delta[COUNT_SAMPLED as usize],
+            delta[COUNT_OTHER as usize], delta[COUNT_DROPPED as usize], self.subnets.len())
+    }
+}
+
+// Brings the LPM tries from `current` to `wanted`, adding before removing.
+// `current` is kept up to date even if an update fails part way.
+fn sync_subnets(v4: &Fd, v6: &Fd, current: &mut Vec<IpNetwork>, wanted: &[IpNetwork])
+    -> DetectorResult<(usize, usize)>
+{
+    let (added, removed) = subnet_changes(current, wanted);
+    for net in added.iter() {
+        let map = if net.is_ipv4() { v4 } else { v6 };
+        map_update(map, &subnet_key(net), &[1]).map_err(|e| xdp_error("subnet update", e))?;
+        current.push(*net);
+    }
+    for net in removed.iter() {
+        let map = if net.is_ipv4() { v4 } else { v6 };
+        if let Err(e) = map_delete(map, &subnet_key(net)) {
+            if e.raw_os_error() != Some(libc::ENOENT) {
+                return Err(xdp_error("subnet delete", e))
+            }
+        }
+        current.retain(|n| n != net);
+    }
+    Ok((added.len(), removed.len()))
+}
+
+// The subnets in the phantom subnets file, and when it was last modified.
+fn load_subnets(path: &str) -> DetectorResult<(Vec<IpNetwork>, Option<SystemTime>)> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+    let subnets = PhantomSubnets::load(path)
+        .map_err(|e| xdp_error("phantom subnets", io::Error::new(io::ErrorKind::InvalidData, e)))?;
+    Ok((subnets.nets(), modified))
+}
+
+#[cfg(test)]
+mod tests {
+    use xdp::*;
+
+    #[test]
+    fn test_xdp_subnet_keys() {
+        let v4: IpNetwork = "192.122.190.0/24".parse().unwrap();
+        assert_eq!(subnet_key(&v4), vec![24, 0, 0, 0, 192, 122, 190, 0]);
+        // host bits aren't part of the key
+        let v6: IpNetwork = "2001:48a8:687f:1::1/64".parse().unwrap();
+        let key = subnet_key(&v6);
+        assert_eq!(key.len(), 20);
+        assert_eq!(&key[..4], &[64, 0, 0, 0]);
+        assert_eq!(&key[4..12], &[0x20, 0x01, 0x48, 0xa8, 0x68, 0x7f, 0, 1]);
+        assert!(key[12..].iter().all(|&b| b == 0));
+
+        let a: IpNetwork = "10.0.0.0/8".parse().unwrap();
+        let b: IpNetwork = "10.1.0.0/16".parse().unwrap();
+        let c: IpNetwork = "2001::/32".parse().unwrap();
+        assert_eq!(subnet_changes(&[a, b], &[b, c]), (vec![c], vec![a]));
+        assert_eq!(subnet_changes(&[a], &[a]), (vec![], vec![]));
+    }
+
+    #[test]
+    fn test_xdp_program() {
+        let fds = MapFds { v4: 3, v6: 4, ports: None, counters: 5 };
+        let plain = program(&fds, 0);
+        assert_eq!(plain[0], Insn { code: MOV64_X, regs: R1 << 4 | R6, off: 0, imm: 0 });
+        assert_eq!(plain.last().unwrap().code, EXIT);
+        // every program path ends in one of the three (plain) or five exits
+        assert_eq!(plain.iter().filter(|i| i.code == EXIT).count(), 3);
+
+        let fds = MapFds { ports: Some(6), ..fds };
+        let full = program(&fds, 100);
+        assert_eq!(full.iter().filter(|i| i.code == EXIT).count(), 5);
+        assert!(full.iter().any(|i| i.code == JLT_K && i.imm == 21474836));
+        assert_eq!(sample_threshold(1), i32::MAX);
+        // maps are referenced by fd
+        for fd in 3..7 {
+            assert!(full.iter().any(|i| i.code == LD_IMM64 && i.regs == BPF_PSEUDO_MAP_FD << 4 | R1 && i.imm == fd));
+        }
+        // jumps land inside the program
+        for (pc, i) in full.iter().enumerate() {
+            if i.code & 0x07 == 0x05 && i.code != CALL && i.code != EXIT {
+                let to = pc as isize + 1 + i.off as isize;
+                assert!(to > pc as isize && (to as usize) < full.len(), "jump at {} to {}", pc, to);
+            }
+        }
+    }
+}
@@ -0,0 +1,385 @@
+//
+// End-to-end Tests
+//
+// The unit tests cover the detector's pieces; these run capture -> match ->
+// forward as a whole. Each test moves its thread into a new network namespace
+// holding a veth pair (veth0 <-> veth1), starts a detector core that captures
+// on veth1 (handing every frame to rust_process_packet, as detect.c does with
+// PF_RING) and forwards into tun0, publishes registrations to the core's
+// control listener, and then sends frames into veth0 and checks which packets
+// come out of tun0.
+//
+// Creating namespaces and devices needs CAP_SYS_ADMIN and CAP_NET_ADMIN, so
+// the tests are only built with the netns-tests feature (required-features in
+// Cargo.toml). Run them as root, or in a user namespace as an unprivileged
+// user:
+//
+//   sudo -E cargo test --features netns-tests --test netns
+//   unshare -rn cargo test --features netns-tests --test netns
+//
+// iproute2's `ip` must be on the PATH.
+
+extern crate libc;
+extern crate protobuf;
+extern crate rust_dark_decoy;
+
+use std::ffi::CString;
+use std::io;
+use std::mem;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use std::os::raw::c_void;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use protobuf::Message;
+use rust_dark_decoy::PerCoreGlobal;
+use rust_dark_decoy::control::{ControlConfig, seal_message};
+use rust_dark_decoy::process_packet::rust_process_packet;
+use rust_dark_decoy::sessions::SessionConfig;
+use rust_dark_decoy::signalling::StationToDetector;
+use rust_dark_decoy::util::checksum;
+
+const CONTROL_KEY: [u8; 32] = [7; 32];
+const CLIENT: &'static str = "192.0.2.10";
+const CLIENT_V6: &'static str = "2001:db8::10";
+
+// Runs `ip` with `args` in the calling thread's namespace.
+fn ip(args: &[&str]) {
+    let status = Command::new("ip").args(args).status()
+        .unwrap_or_else(|e| panic!("running ip {:?}: {}", args, e));
+    assert!(status.success(), "ip {:?}: {}", args, status);
+}
+
+// Moves this thread into a new network namespace with loopback and a veth
+// pair, both ends up.
+fn enter_netns() {
+    if unsafe { libc::unshare(libc::CLONE_NEWNET) } != 0 {
+        panic!("unshare(CLONE_NEWNET): {} (run as root, or under unshare -rn)",
+            io::Error::last_os_error());
+    }
+    ip(&["link", "set", "lo", "up"]);
+    ip(&["link", "add", "veth0", "type", "veth", "peer", "name", "veth1"]);
+    ip(&["link", "set", "veth0", "up"]);
+    ip(&["link", "set", "veth1", "up"]);
+}
+
+// An AF_PACKET socket seeing every frame on one interface: whole Ethernet
+// frames for SOCK_RAW, IP packets for SOCK_DGRAM (used on the tun device).
+struct PacketSocket
+{
+    fd: libc::c_int,
+}
+
+impl PacketSocket
+{
+    fn bind(ifname: &str, kind: libc::c_int) -> io::Result<PacketSocket> {
+        let proto = (libc::ETH_P_ALL as u16).to_be();
+        let fd = unsafe { libc::socket(libc::AF_PACKET, kind, proto as libc::c_int) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let sock = PacketSocket { fd: fd };
+
+        let name = CString::new(ifname).unwrap();
+        let index = unsafe { libc::if_nametoindex(name.as_ptr()) };
+        if index == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut addr: libc::sockaddr_ll = unsafe { mem::zeroed() };
+        addr.sll_family = libc::AF_PACKET as u16;
+        addr.sll_protocol = proto;
+        addr.sll_ifindex = index as libc::c_int;
+        let ret = unsafe {
+            libc::bind(fd, &addr as *const libc::sockaddr_ll as *const libc::sockaddr,
+                       mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t)
+        };
+        if ret != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let timeout = libc::timeval { tv_sec: 0, tv_usec: 100 * 1000 };
+        let ret = unsafe {
+            libc::setsockopt(fd, libc::SOL_SOCKET, libc::SO_RCVTIMEO,
+                             &timeout as *const libc::timeval as *const c_void,
+                             mem::size_of::<libc::timeval>() as libc::socklen_t)
+        };
+        if ret != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(sock)
+    }
+
+    fn send(&self, frame: &[u8]) {
+        let sent = unsafe { libc::send(self.fd, frame.as_ptr() as *const c_void, frame.len(), 0) };
+        assert_eq!(sent, frame.len() as isize, "send: {}", io::Error::last_os_error());
+    }
+
+    // The next frame, or None once nothing has arrived for 100ms.
+    fn recv(&self) -> Option<Vec<u8>> {
+        let mut buf = vec![0u8; 65536];
+        let n = unsafe { libc::recv(self.fd, buf.as_mut_ptr() as *mut c_void, buf.len(), 0) };
+        if n < 0 {
+            return None;
+        }
+        buf.truncate(n as usize);
+        Some(buf)
+    }
+}
+
+impl Drop for PacketSocket
+{
+    fn drop(&mut self) {
+        unsafe { libc::close(self.fd); }
+    }
+}
+
+// A detector core capturing on veth1 and forwarding into tun0, with frames
+// injected on veth0 and what it forwards read back from tun0.
+struct Station
+{
+    global: Box<PerCoreGlobal>,
+    control: SocketAddr,
+    inject: PacketSocket,
+    capture: PacketSocket,
+    forwarded: PacketSocket,
+}
+
+impl Station
+{
+    fn start() -> Station {
+        Station::with_config(SessionConfig::default())
+    }
+
+    // As start, with `config`'s session settings (its control listener
+    // replaced).
+    fn with_config(config: SessionConfig) -> Station {
+        enter_netns();
+        let control: SocketAddr = "127.0.0.1:41000".parse().unwrap();
+        let config = SessionConfig {
+            control: Some(ControlConfig {
+                addr: control,
+                key: CONTROL_KEY.to_vec(),
+                multicast_interface: Ipv4Addr::new(0, 0, 0, 0),
+            }),
+            ..config
+        };
+        let global = PerCoreGlobal::for_netns_test(0, config).expect("starting detector core");
+        global.flow_tracker.phantom_flows.spawn_control_listener();
+
+        Station {
+            global: Box::new(global),
+            control: control,
+            inject: PacketSocket::bind("veth0", libc::SOCK_RAW).unwrap(),
+            capture: PacketSocket::bind("veth1", libc::SOCK_RAW).unwrap(),
+            forwarded: PacketSocket::bind("tun0", libc::SOCK_DGRAM).unwrap(),
+        }
+    }
+
+    // Sends a registration of `phantom` for `client` to the control listener
+    // and waits for the core to have it.
+    fn register(&self, client: &str, phantom: &str) {
+        self.register_port(client, phantom, 0);
+    }
+
+    // As register, for `phantom` on `port` only (0 for the default ports).
+    fn register_port(&self, client: &str, phantom: &str, port: u16) {
+        let before = self.global.flow_tracker.phantom_flows.len();
+        let mut s2d = StationToDetector::new();
+        s2d.set_client_ip(client.to_string());
+        s2d.set_phantom_ip(phantom.to_string());
+        s2d.set_timeout_ns(60 * 1000 * 1000 * 1000);
+        s2d.set_phantom_port(port as u32);
+        let msg = seal_message(&CONTROL_KEY, &s2d.write_to_bytes().unwrap());
+        let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        sock.send_to(&msg, self.control).unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while self.global.flow_tracker.phantom_flows.len() == before {
+            assert!(Instant::now() < deadline, "registration of {} not ingested", phantom);
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    // Sends `frames` into veth0, runs everything captured on veth1 through
+    // the detector, and returns the packets that came out of tun0.
+    fn run(&mut self, frames: &[Vec<u8>]) -> Vec<Vec<u8>> {
+        for frame in frames.iter() {
+            self.inject.send(frame);
+        }
+        while let Some(mut frame) = self.capture.recv() {
+            let global: *mut PerCoreGlobal = &mut *self.global;
+            rust_process_packet(global, frame.as_mut_ptr() as *mut c_void, frame.len());
+        }
+        let mut forwarded = Vec::new();
+        while let Some(pkt) = self.forwarded.recv() {
+            forwarded.push(pkt);
+        }
+        forwarded
+    }
+}
+
+// An Ethernet frame holding a TCP SYN from `src`:40000 to `dst`:443. v6
+// packets start with a hop-by-hop options header.
+fn syn_frame(src: &str, dst: &str) -> Vec<u8> {
+    syn_frame_to(src, dst, 443)
+}
+
+// As syn_frame, to `dst`:`port`.
+fn syn_frame_to(src: &str, dst: &str, port: u16) -> Vec<u8> {
+    tcp_frame(src, 40000, dst, port, 0x02)
+}
+
+// An Ethernet frame holding a TCP segment from `src`:`sport` to `dst`:`port`
+// with `flags`, sequence number 1 and no payload.
+fn tcp_frame(src: &str, sport: u16, dst: &str, port: u16, flags: u8) -> Vec<u8> {
+    let mut tcp = Vec::new();
+    tcp.extend_from_slice(&[(sport >> 8) as u8, sport as u8, (port >> 8) as u8, port as u8]);
+    tcp.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 0]);
+    tcp.extend_from_slice(&[5 << 4, flags, 0xff, 0xff, 0, 0, 0, 0]);
+
+    let mut frame = vec![0xff; 6];
+    frame.extend_from_slice(&[0x02, 0, 0, 0, 0, 1]);
+    match (src.parse::<IpAddr>().unwrap(), dst.parse::<IpAddr>().unwrap()) {
+        (IpAddr::V4(s), IpAddr::V4(d)) => {
+            frame.extend_from_slice(&[0x08, 0x00]);
+            let mut hdr = vec![0x45, 0, 0, 40, 0, 1, 0x40, 0, 64, 6, 0, 0];
+            hdr.extend_from_slice(&s.octets());
+            hdr.extend_from_slice(&d.octets());
+            let sum = checksum(&[&hdr]);
+            hdr[10] = (sum >> 8) as u8;
+            hdr[11] = sum as u8;
+            frame.extend_from_slice(&hdr);
+        },
+        (IpAddr::V6(s), IpAddr::V6(d)) => {
+            frame.extend_from_slice(&[0x86, 0xdd]);
+            frame.extend_from_slice(&[0x60, 0, 0, 0, 0, 28, 0, 64]);
+            frame.extend_from_slice(&s.octets());
+            frame.extend_from_slice(&d.octets());
+            // hop-by-hop, next header TCP, padding
+            frame.extend_from_slice(&[6, 0, 1, 4, 0, 0, 0, 0]);
+        },
+        _ => panic!("mixed address families"),
+    }
+    frame.extend_from_slice(&tcp);
+    frame
+}
+
+// Destinations of the forwarded packets.
+fn destinations(pkts: &[Vec<u8>]) -> Vec<IpAddr> {
+    pkts.iter().filter_map(|p| match p.first().map(|b| b >> 4) {
+        Some(4) if p.len() >= 20 => {
+            let mut a = [0u8; 4];
+            a.copy_from_slice(&p[16..20]);
+            Some(IpAddr::V4(Ipv4Addr::from(a)))
+        },
+        Some(6) if p.len() >= 40 => {
+            let mut a = [0u8; 16];
+            a.copy_from_slice(&p[24..40]);
+            Some(IpAddr::V6(Ipv6Addr::from(a)))
+        },
+        _ => None,
+    }).collect()
+}
+
+#[test]
+fn test_registered_phantom_forwarded() {
+    let mut station = Station::start();
+    let phantom: IpAddr = "198.51.100.7".parse().unwrap();
+
+    // nothing is forwarded before the registration
+    let forwarded = station.run(&[syn_frame(CLIENT, "198.51.100.7")]);
+    assert!(!destinations(&forwarded).contains(&phantom), "{:?}", forwarded);
+
+    station.register(CLIENT, "198.51.100.7");
+    let forwarded = station.run(&[
+        syn_frame(CLIENT, "198.51.100.7"),
+        syn_frame(CLIENT, "198.51.100.8"),
+        syn_frame("192.0.2.11", "198.51.100.7"),
+    ]);
+    let dsts = destinations(&forwarded);
+    assert_eq!(dsts.iter().filter(|d| **d == phantom).count(), 1, "{:?}", dsts);
+    assert!(!dsts.contains(&"198.51.100.8".parse().unwrap()), "{:?}", dsts);
+
+    // the forwarded packet is the client's SYN, unchanged
+    let syn = syn_frame(CLIENT, "198.51.100.7");
+    let pkt = forwarded.iter().find(|p| p.len() >= 20 && p[16..20] == [198, 51, 100, 7]).unwrap();
+    assert_eq!(&pkt[..], &syn[14..]);
+}
+
+#[test]
+fn test_registered_v6_phantom_forwarded() {
+    let mut station = Station::start();
+    let phantom: IpAddr = "2001:db8:1::7".parse().unwrap();
+
+    station.register(CLIENT_V6, "2001:db8:1::7");
+    let forwarded = station.run(&[
+        syn_frame(CLIENT_V6, "2001:db8:1::7"),
+        syn_frame(CLIENT_V6, "2001:db8:1::8"),
+    ]);
+    let dsts = destinations(&forwarded);
+    assert_eq!(dsts.iter().filter(|d| **d == phantom).count(), 1, "{:?}", dsts);
+    assert!(!dsts.contains(&"2001:db8:1::8".parse().unwrap()), "{:?}", dsts);
+}
+
+#[test]
+fn test_registered_v6_phantom_port_forwarded() {
+    let mut station = Station::start();
+    let phantom: IpAddr = "2001:db8:1::9".parse().unwrap();
+
+    // a port outside the default phantom ports, named by the registration
+    station.register_port(CLIENT_V6, "2001:db8:1::9", 8443);
+    let forwarded = station.run(&[syn_frame_to(CLIENT_V6, "2001:db8:1::9", 8443)]);
+    let dsts = destinations(&forwarded);
+    assert_eq!(dsts.iter().filter(|d| **d == phantom).count(), 1, "{:?}", dsts);
+
+    // the phantom isn't registered on the default port
+    let forwarded = station.run(&[syn_frame(CLIENT_V6, "2001:db8:1::9")]);
+    assert!(!destinations(&forwarded).contains(&phantom), "{:?}", forwarded);
+}
+
+#[test]
+fn test_same_v6_phantom_ports_forwarded() {
+    let mut station = Station::start();
+    let phantom: IpAddr = "2001:db8:1::a".parse().unwrap();
+
+    // two clients given the same phantom on different ports
+    station.register_port(CLIENT_V6, "2001:db8:1::a", 443);
+    station.register_port("2001:db8::11", "2001:db8:1::a", 8443);
+    assert_eq!(station.global.flow_tracker.phantom_flows.len(), 2);
+
+    for &port in [443, 8443].iter() {
+        let forwarded = station.run(&[syn_frame_to(CLIENT_V6, "2001:db8:1::a", port)]);
+        let dsts = destinations(&forwarded);
+        assert_eq!(dsts.iter().filter(|d| **d == phantom).count(), 1, "port {} {:?}", port, dsts);
+    }
+
+    // neither registration covers another port
+    let forwarded = station.run(&[syn_frame_to(CLIENT_V6, "2001:db8:1::a", 80)]);
+    assert!(!destinations(&forwarded).contains(&phantom), "{:?}", forwarded);
+}
+
+#[test]
+fn test_connection_limit_not_forwarded() {
+    let config = SessionConfig { max_connections: Some(1), ..SessionConfig::default() };
+    let mut station = Station::with_config(config);
+    let phantom: IpAddr = "198.51.100.9".parse().unwrap();
+    station.register(CLIENT, "198.51.100.9");
+
+    // the first connection is forwarded, the second is over the limit
+    let forwarded = station.run(&[
+        tcp_frame(CLIENT, 40000, "198.51.100.9", 443, 0x02),
+        tcp_frame(CLIENT, 40001, "198.51.100.9", 443, 0x02),
+    ]);
+    assert_eq!(destinations(&forwarded).iter().filter(|d| **d == phantom).count(), 1);
+
+    // nor are the refused connection's later packets, while the open one's
+    // still are
+    let forwarded = station.run(&[tcp_frame(CLIENT, 40001, "198.51.100.9", 443, 0x10)]);
+    assert!(!destinations(&forwarded).contains(&phantom), "{:?}", forwarded);
+    let forwarded = station.run(&[tcp_frame(CLIENT, 40000, "198.51.100.9", 443, 0x10)]);
+    assert_eq!(destinations(&forwarded).iter().filter(|d| **d == phantom).count(), 1);
+    // as are those of a connection that was never refused, its SYN unseen
+    let forwarded = station.run(&[tcp_frame(CLIENT, 40002, "198.51.100.9", 443, 0x10)]);
+    assert_eq!(destinations(&forwarded).iter().filter(|d| **d == phantom).count(), 1);
+}